@@ -3,11 +3,12 @@
 
 use std::fmt;
 
+use rustc_hash::FxHashMap;
 use salsa::Durability;
 use triomphe::Arc;
 use vfs::FileId;
 
-use crate::{CrateGraph, ProcMacros, SourceDatabaseExt, SourceRoot, SourceRootId};
+use crate::{CrateData, CrateGraph, ProcMacros, SourceDatabaseExt, SourceRoot, SourceRootId};
 
 /// Encapsulate a bunch of raw `.set` calls on the database.
 #[derive(Default)]
@@ -15,7 +16,9 @@ pub struct Change {
     pub roots: Option<Vec<SourceRoot>>,
     pub files_changed: Vec<(FileId, Option<Arc<str>>)>,
     pub crate_graph: Option<CrateGraph>,
+    pub ad_hoc_crates: Option<Vec<CrateData>>,
     pub proc_macros: Option<ProcMacros>,
+    pub active_targets: Option<FxHashMap<FileId, String>>,
 }
 
 impl fmt::Debug for Change {
@@ -30,6 +33,12 @@ impl fmt::Debug for Change {
         if self.crate_graph.is_some() {
             d.field("crate_graph", &self.crate_graph);
         }
+        if let Some(ad_hoc_crates) = &self.ad_hoc_crates {
+            d.field("ad_hoc_crates", ad_hoc_crates);
+        }
+        if let Some(active_targets) = &self.active_targets {
+            d.field("active_targets", active_targets);
+        }
         d.finish()
     }
 }
@@ -47,14 +56,24 @@ impl Change {
         self.files_changed.push((file_id, new_text))
     }
 
+    /// Sets the crate graph of the loaded workspaces. The ad-hoc crates depend on the ids of its
+    /// crates, so they are cleared unless they are set by this change as well.
     pub fn set_crate_graph(&mut self, graph: CrateGraph) {
         self.crate_graph = Some(graph);
     }
 
+    pub fn set_ad_hoc_crates(&mut self, crates: Vec<CrateData>) {
+        self.ad_hoc_crates = Some(crates);
+    }
+
     pub fn set_proc_macros(&mut self, proc_macros: ProcMacros) {
         self.proc_macros = Some(proc_macros);
     }
 
+    pub fn set_active_targets(&mut self, active_targets: FxHashMap<FileId, String>) {
+        self.active_targets = Some(active_targets);
+    }
+
     pub fn apply(self, db: &mut dyn SourceDatabaseExt) {
         let _p = profile::span("RootDatabase::apply_change");
         if let Some(roots) = self.roots {
@@ -76,12 +95,25 @@ impl Change {
             let text = text.unwrap_or_else(|| Arc::from(""));
             db.set_file_text_with_durability(file_id, text, durability)
         }
-        if let Some(crate_graph) = self.crate_graph {
-            db.set_crate_graph_with_durability(Arc::new(crate_graph), Durability::HIGH);
+        let ad_hoc_crates = match self.crate_graph {
+            Some(crate_graph) => {
+                db.set_workspace_crate_graph_with_durability(
+                    Arc::new(crate_graph),
+                    Durability::HIGH,
+                );
+                Some(self.ad_hoc_crates.unwrap_or_default())
+            }
+            None => self.ad_hoc_crates,
+        };
+        if let Some(ad_hoc_crates) = ad_hoc_crates {
+            db.set_ad_hoc_crates_with_durability(Arc::from(ad_hoc_crates), Durability::LOW);
         }
         if let Some(proc_macros) = self.proc_macros {
             db.set_proc_macros_with_durability(Arc::new(proc_macros), Durability::HIGH);
         }
+        if let Some(active_targets) = self.active_targets {
+            db.set_active_targets_with_durability(Arc::new(active_targets), Durability::LOW);
+        }
     }
 }
 
@@ -9,13 +9,14 @@ use test_utils::{
 };
 use triomphe::Arc;
 use tt::token_id::{Leaf, Subtree, TokenTree};
-use vfs::{file_set::FileSet, VfsPath};
+use vfs::{file_set::FileSet, AbsPathBuf, VfsPath};
 
 use crate::{
     input::{CrateName, CrateOrigin, LangCrateOrigin},
     Change, CrateDisplayName, CrateGraph, CrateId, Dependency, Edition, Env, FileId, FilePosition,
-    FileRange, ProcMacro, ProcMacroExpander, ProcMacroExpansionError, ProcMacros, ReleaseChannel,
-    SourceDatabaseExt, SourceRoot, SourceRootId,
+    FileRange, ProcMacro, ProcMacroDiagnosticLevel, ProcMacroExpander, ProcMacroExpansion,
+    ProcMacroExpansionDiagnostic, ProcMacroExpansionError, ProcMacroSpanData, ProcMacros,
+    ReleaseChannel, SourceDatabaseExt, SourceRoot, SourceRootId,
 };
 
 pub const WORKSPACE: SourceRootId = SourceRootId(0);
@@ -334,7 +335,7 @@ impl ChangeFixture {
     }
 }
 
-fn default_test_proc_macros() -> [(String, ProcMacro); 5] {
+fn default_test_proc_macros() -> [(String, ProcMacro); 11] {
     [
         (
             r#"
@@ -406,6 +407,90 @@ pub fn shorten(input: TokenStream) -> TokenStream {
                 expander: sync::Arc::new(ShortenProcMacroExpander),
             },
         ),
+        (
+            r#"
+#[proc_macro]
+pub fn reject_literals(input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "reject_literals".into(),
+                kind: crate::ProcMacroKind::FuncLike,
+                expander: sync::Arc::new(RejectLiteralsProcMacroExpander),
+            },
+        ),
+        (
+            r#"
+#[proc_macro_attribute]
+pub fn reject_literals_attr(attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "reject_literals_attr".into(),
+                kind: crate::ProcMacroKind::Attr,
+                expander: sync::Arc::new(RejectLiteralsProcMacroExpander),
+            },
+        ),
+        (
+            r#"
+#[proc_macro]
+pub fn track_path(input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "track_path".into(),
+                kind: crate::ProcMacroKind::FuncLike,
+                expander: sync::Arc::new(TrackPathProcMacroExpander),
+            },
+        ),
+        (
+            r#"
+#[proc_macro]
+pub fn track_env(input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "track_env".into(),
+                kind: crate::ProcMacroKind::FuncLike,
+                expander: sync::Arc::new(TrackEnvProcMacroExpander),
+            },
+        ),
+        (
+            r#"
+#[proc_macro]
+pub fn server_crash(input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "server_crash".into(),
+                kind: crate::ProcMacroKind::FuncLike,
+                expander: sync::Arc::new(ServerCrashProcMacroExpander),
+            },
+        ),
+        (
+            r#"
+#[proc_macro]
+pub fn span_info(input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "span_info".into(),
+                kind: crate::ProcMacroKind::FuncLike,
+                expander: sync::Arc::new(SpanInfoProcMacroExpander),
+            },
+        ),
     ]
 }
 
@@ -524,8 +609,9 @@ impl ProcMacroExpander for IdentityProcMacroExpander {
         subtree: &Subtree,
         _: Option<&Subtree>,
         _: &Env,
-    ) -> Result<Subtree, ProcMacroExpansionError> {
-        Ok(subtree.clone())
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        Ok(subtree.clone().into())
     }
 }
 
@@ -538,9 +624,11 @@ impl ProcMacroExpander for AttributeInputReplaceProcMacroExpander {
         _: &Subtree,
         attrs: Option<&Subtree>,
         _: &Env,
-    ) -> Result<Subtree, ProcMacroExpansionError> {
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
         attrs
             .cloned()
+            .map(Into::into)
             .ok_or_else(|| ProcMacroExpansionError::Panic("Expected attribute input".into()))
     }
 }
@@ -553,7 +641,8 @@ impl ProcMacroExpander for MirrorProcMacroExpander {
         input: &Subtree,
         _: Option<&Subtree>,
         _: &Env,
-    ) -> Result<Subtree, ProcMacroExpansionError> {
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
         fn traverse(input: &Subtree) -> Subtree {
             let mut token_trees = vec![];
             for tt in input.token_trees.iter().rev() {
@@ -565,7 +654,7 @@ impl ProcMacroExpander for MirrorProcMacroExpander {
             }
             Subtree { delimiter: input.delimiter, token_trees }
         }
-        Ok(traverse(input))
+        Ok(traverse(input).into())
     }
 }
 
@@ -580,8 +669,9 @@ impl ProcMacroExpander for ShortenProcMacroExpander {
         input: &Subtree,
         _: Option<&Subtree>,
         _: &Env,
-    ) -> Result<Subtree, ProcMacroExpansionError> {
-        return Ok(traverse(input));
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        return Ok(traverse(input).into());
 
         fn traverse(input: &Subtree) -> Subtree {
             let token_trees = input
@@ -612,3 +702,172 @@ impl ProcMacroExpander for ShortenProcMacroExpander {
         }
     }
 }
+
+// Emits an error for every literal in the input. Expands to nothing as a function-like macro, and
+// to the item as an attribute, whose input is checked for literals as well.
+#[derive(Debug)]
+struct RejectLiteralsProcMacroExpander;
+impl ProcMacroExpander for RejectLiteralsProcMacroExpander {
+    fn expand(
+        &self,
+        input: &Subtree,
+        attrs: Option<&Subtree>,
+        _: &Env,
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        let mut expansion = match attrs {
+            Some(attrs) => {
+                let mut expansion = ProcMacroExpansion::from(input.clone());
+                traverse(attrs, &mut expansion.diagnostics);
+                expansion
+            }
+            None => ProcMacroExpansion::from(Subtree::empty()),
+        };
+        traverse(input, &mut expansion.diagnostics);
+        return Ok(expansion);
+
+        fn traverse(input: &Subtree, acc: &mut Vec<ProcMacroExpansionDiagnostic>) {
+            for tt in &input.token_trees {
+                match tt {
+                    TokenTree::Leaf(Leaf::Literal(lit)) => acc.push(ProcMacroExpansionDiagnostic {
+                        level: ProcMacroDiagnosticLevel::Error,
+                        message: format!("unexpected literal `{}`", lit.text),
+                        spans: vec![lit.span],
+                        children: vec![ProcMacroExpansionDiagnostic {
+                            level: ProcMacroDiagnosticLevel::Help,
+                            message: "remove the literal".to_owned(),
+                            spans: vec![lit.span],
+                            children: Vec::new(),
+                        }],
+                    }),
+                    TokenTree::Leaf(_) => (),
+                    TokenTree::Subtree(subtree) => traverse(subtree, acc),
+                }
+            }
+        }
+    }
+}
+
+// Expands to nothing, declaring a dependency on the file whose absolute path is the string literal
+// it is given.
+#[derive(Debug)]
+struct TrackPathProcMacroExpander;
+impl ProcMacroExpander for TrackPathProcMacroExpander {
+    fn expand(
+        &self,
+        input: &Subtree,
+        _: Option<&Subtree>,
+        _: &Env,
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        let Some(TokenTree::Leaf(Leaf::Literal(lit))) = input.token_trees.first() else {
+            return Err(ProcMacroExpansionError::Panic("Expected a string literal".into()));
+        };
+        let path = lit.text.trim_matches('"');
+        let mut expansion = ProcMacroExpansion::from(Subtree::empty());
+        expansion.tracked_paths.push(AbsPathBuf::assert(path.into()));
+        Ok(expansion)
+    }
+}
+
+// Reads the environment variable named by the string literal it is given and expands to a unit
+// struct named after its value, or to nothing if it is unset.
+#[derive(Debug)]
+struct TrackEnvProcMacroExpander;
+impl ProcMacroExpander for TrackEnvProcMacroExpander {
+    fn expand(
+        &self,
+        input: &Subtree,
+        _: Option<&Subtree>,
+        env: &Env,
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        let Some(TokenTree::Leaf(Leaf::Literal(lit))) = input.token_trees.first() else {
+            return Err(ProcMacroExpansionError::Panic("Expected a string literal".into()));
+        };
+        let var = lit.text.trim_matches('"');
+        let value = env.get(var);
+        let token_trees = match &value {
+            Some(value) => {
+                let ident = |text: &str| {
+                    TokenTree::Leaf(Leaf::Ident(tt::Ident {
+                        text: text.into(),
+                        span: tt::TokenId::UNSPECIFIED,
+                    }))
+                };
+                let semi = TokenTree::Leaf(Leaf::Punct(tt::Punct {
+                    char: ';',
+                    spacing: tt::Spacing::Alone,
+                    span: tt::TokenId::UNSPECIFIED,
+                }));
+                vec![ident("struct"), ident(value), semi]
+            }
+            None => Vec::new(),
+        };
+        let mut expansion = ProcMacroExpansion::from(Subtree {
+            delimiter: tt::token_id::Delimiter::UNSPECIFIED,
+            token_trees,
+        });
+        expansion.tracked_env_vars.push((var.to_owned(), value));
+        Ok(expansion)
+    }
+}
+
+// Fails as if the proc-macro server crashed while expanding it.
+#[derive(Debug)]
+struct ServerCrashProcMacroExpander;
+impl ProcMacroExpander for ServerCrashProcMacroExpander {
+    fn expand(
+        &self,
+        _: &Subtree,
+        _: Option<&Subtree>,
+        _: &Env,
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        Err(ProcMacroExpansionError::ServerCrashed(
+            "proc-macro server crashed while expanding `server_crash` and has been restarted"
+                .into(),
+        ))
+    }
+}
+
+// Expands to a string literal containing the location of the macro input and the source text of
+// its first token.
+#[derive(Debug)]
+struct SpanInfoProcMacroExpander;
+impl ProcMacroExpander for SpanInfoProcMacroExpander {
+    fn expand(
+        &self,
+        input: &Subtree,
+        _: Option<&Subtree>,
+        _: &Env,
+        spans: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        let Some(location) = &spans.location else {
+            let mut expansion = ProcMacroExpansion::from(Subtree::empty());
+            expansion.needs_location = true;
+            return Ok(expansion);
+        };
+        let first_token = match input.token_trees.first() {
+            Some(TokenTree::Leaf(leaf)) => Some(*leaf.span()),
+            Some(TokenTree::Subtree(subtree)) => Some(subtree.delimiter.open),
+            None => None,
+        };
+        let source_text = first_token
+            .and_then(|span| spans.spans.iter().find(|(id, _)| *id == span))
+            .map_or("", |&(_, range)| &spans.text[range]);
+        let text = format!(
+            "{}:{}:{} {source_text}",
+            location.file_path.as_deref().unwrap_or("<unknown>"),
+            location.line,
+            location.column,
+        );
+        let literal =
+            tt::Literal { text: format!("{text:?}").into(), span: tt::TokenId::UNSPECIFIED };
+        Ok(Subtree {
+            delimiter: tt::token_id::Delimiter::UNSPECIFIED,
+            token_trees: vec![TokenTree::Leaf(Leaf::Literal(literal))],
+        }
+        .into())
+    }
+}
@@ -11,9 +11,9 @@ use std::{fmt, mem, ops, panic::RefUnwindSafe, str::FromStr, sync};
 use cfg::CfgOptions;
 use la_arena::{Arena, Idx};
 use rustc_hash::{FxHashMap, FxHashSet};
-use syntax::SmolStr;
+use syntax::{SmolStr, TextRange, TextSize};
 use triomphe::Arc;
-use tt::token_id::Subtree;
+use tt::token_id::{Subtree, TokenId};
 use vfs::{file_set::FileSet, AbsPathBuf, AnchoredPath, FileId, VfsPath};
 
 // Map from crate id to the name of the crate and path of the proc-macro. If the value is `None`,
@@ -85,7 +85,7 @@ impl SourceRoot {
 ///
 /// `CrateGraph` is `!Serialize` by design, see
 /// <https://github.com/rust-lang/rust-analyzer/blob/master/docs/dev/architecture.md#serialization>
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct CrateGraph {
     arena: Arena<CrateData>,
 }
@@ -254,13 +254,86 @@ pub trait ProcMacroExpander: fmt::Debug + Send + Sync + RefUnwindSafe {
         subtree: &Subtree,
         attrs: Option<&Subtree>,
         env: &Env,
-    ) -> Result<Subtree, ProcMacroExpansionError>;
+        spans: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError>;
+}
+
+/// Source information about the input tokens of a proc-macro expansion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcMacroSpanData {
+    /// The source text of the macro input.
+    pub text: String,
+    /// The ranges of the input tokens in `text`.
+    pub spans: Vec<(TokenId, TextRange)>,
+    /// Where `text` is located. Only filled in when an expansion without it asked for it, see
+    /// [`ProcMacroExpansion::needs_location`].
+    pub location: Option<ProcMacroSpanLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcMacroSpanLocation {
+    /// The path of the file containing the macro input, `None` if the input is part of another
+    /// macro expansion.
+    pub file_path: Option<String>,
+    /// The offset of the input in its file.
+    pub offset: TextSize,
+    /// The 1-based line of the start of the input.
+    pub line: u32,
+    /// The 0-based column, in characters, of the start of the input.
+    pub column: u32,
+}
+
+/// The result of a successful proc-macro expansion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcMacroExpansion {
+    pub subtree: Subtree,
+    /// Diagnostics the macro emitted while expanding.
+    pub diagnostics: Vec<ProcMacroExpansionDiagnostic>,
+    /// Environment variables the macro read via `proc_macro::tracked_env::var`, with the value it
+    /// observed.
+    pub tracked_env_vars: Vec<(String, Option<String>)>,
+    /// Files the macro declared to depend on via `proc_macro::tracked_path::path`.
+    pub tracked_paths: Vec<AbsPathBuf>,
+    /// Whether the macro asked for the location of a span, which was not part of the
+    /// [`ProcMacroSpanData`]. Expanding again with the location filled in gives the correct result.
+    pub needs_location: bool,
+}
+
+impl From<Subtree> for ProcMacroExpansion {
+    fn from(subtree: Subtree) -> Self {
+        ProcMacroExpansion {
+            subtree,
+            diagnostics: Vec::new(),
+            tracked_env_vars: Vec::new(),
+            tracked_paths: Vec::new(),
+            needs_location: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcMacroExpansionDiagnostic {
+    pub level: ProcMacroDiagnosticLevel,
+    pub message: String,
+    /// The spans of the macro input the diagnostic points at.
+    pub spans: Vec<TokenId>,
+    pub children: Vec<ProcMacroExpansionDiagnostic>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProcMacroDiagnosticLevel {
+    Error,
+    Warning,
+    Note,
+    Help,
 }
 
 pub enum ProcMacroExpansionError {
     Panic(String),
     /// Things like "proc macro server was killed by OOM".
     System(String),
+    /// The proc macro server crashed while expanding the macro, and has been restarted.
+    ServerCrashed(String),
 }
 
 pub type ProcMacroLoadResult = Result<Vec<ProcMacro>, String>;
@@ -321,6 +394,9 @@ pub struct CrateData {
     // FIXME: These things should not be per crate! These are more per workspace crate graph level things
     pub target_layout: TargetLayoutLoadResult,
     pub channel: Option<ReleaseChannel>,
+    /// The target triple this crate is analyzed for, if the workspace is analyzed for additional
+    /// targets besides its primary one. `None` for the primary target.
+    pub target: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -400,10 +476,16 @@ impl CrateGraph {
             target_layout,
             is_proc_macro,
             channel,
+            target: None,
         };
         self.arena.alloc(data)
     }
 
+    /// Sets the target `krate` is analyzed for, see [`CrateData::target`].
+    pub fn set_target(&mut self, krate: CrateId, target: Option<String>) {
+        self.arena[krate].target = target;
+    }
+
     /// Remove the crate from crate graph. If any crates depend on this crate, the dependency would be replaced
     /// with the second input.
     pub fn remove_and_replace(
@@ -469,6 +551,16 @@ impl CrateGraph {
         self.arena.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns the data of `krate`, or `None` if it is not part of this graph.
+    pub fn get(&self, krate: CrateId) -> Option<&CrateData> {
+        let idx = u32::from(krate.into_raw()) as usize;
+        (idx < self.arena.len()).then(|| &self.arena[krate])
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = CrateId> + '_ {
         self.arena.iter().map(|(idx, _)| idx)
     }
@@ -600,6 +692,14 @@ impl CrateGraph {
             mem::take(proc_macros).into_iter().map(|(id, macros)| (id_map[&id], macros)).collect();
     }
 
+    /// Appends the ad-hoc crates, see [`SourceDatabase::ad_hoc_crates`]. Their dependencies refer
+    /// to crates that already are in the graph, so no ids have to be adjusted.
+    ///
+    /// [`SourceDatabase::ad_hoc_crates`]: crate::SourceDatabase::ad_hoc_crates
+    pub(crate) fn add_ad_hoc_crates(&mut self, crates: &[CrateData]) {
+        self.arena.alloc_many(crates.iter().cloned());
+    }
+
     fn find_path(
         &self,
         visited: &mut FxHashSet<CrateId>,
@@ -8,7 +8,7 @@ pub mod fixture;
 
 use std::panic;
 
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::{ast, Parse, SourceFile, TextRange, TextSize};
 use triomphe::Arc;
 
@@ -16,9 +16,11 @@ pub use crate::{
     change::Change,
     input::{
         CrateData, CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, Dependency,
-        Edition, Env, LangCrateOrigin, ProcMacro, ProcMacroExpander, ProcMacroExpansionError,
-        ProcMacroId, ProcMacroKind, ProcMacroLoadResult, ProcMacroPaths, ProcMacros,
-        ReleaseChannel, SourceRoot, SourceRootId, TargetLayoutLoadResult,
+        Edition, Env, LangCrateOrigin, ProcMacro, ProcMacroDiagnosticLevel, ProcMacroExpander,
+        ProcMacroExpansion, ProcMacroExpansionDiagnostic, ProcMacroExpansionError, ProcMacroId,
+        ProcMacroKind, ProcMacroLoadResult, ProcMacroPaths, ProcMacroSpanData,
+        ProcMacroSpanLocation, ProcMacros, ReleaseChannel, SourceRoot, SourceRootId,
+        TargetLayoutLoadResult,
     },
 };
 pub use salsa::{self, Cancelled};
@@ -61,6 +63,8 @@ pub trait FileLoader {
     fn file_text(&self, file_id: FileId) -> Arc<str>;
     fn resolve_path(&self, path: AnchoredPath<'_>) -> Option<FileId>;
     fn relevant_crates(&self, file_id: FileId) -> Arc<FxHashSet<CrateId>>;
+    /// Path of the file, `None` if the file isn't part of any source root.
+    fn file_path(&self, file_id: FileId) -> Option<VfsPath>;
 }
 
 /// Database which stores all significant input facts: source code and project
@@ -71,13 +75,55 @@ pub trait SourceDatabase: FileLoader + std::fmt::Debug {
     #[salsa::invoke(parse_query)]
     fn parse(&self, file_id: FileId) -> Parse<ast::SourceFile>;
 
-    /// The crate graph.
-    #[salsa::input]
+    /// The crate graph, made of the crates of the loaded workspaces followed by the ad-hoc crates.
+    #[salsa::invoke(crate_graph_query)]
     fn crate_graph(&self) -> Arc<CrateGraph>;
 
+    /// The crates of the loaded workspaces.
+    #[salsa::input]
+    fn workspace_crate_graph(&self) -> Arc<CrateGraph>;
+
+    /// Single-file crates for files that belong to no crate of the workspaces. They come and go
+    /// while the server is running, so unlike the workspace they have a low durability. Their ids
+    /// follow the ones of the workspace crates, and they only depend on workspace crates.
+    #[salsa::input]
+    fn ad_hoc_crates(&self) -> Arc<[CrateData]>;
+
+    /// The data of a single crate. For crates of the workspaces, this doesn't depend on the
+    /// ad-hoc crates, so prefer it over indexing [`SourceDatabase::crate_graph`] in queries.
+    #[salsa::invoke(crate_data_query)]
+    fn crate_data(&self, krate: CrateId) -> Arc<CrateData>;
+
     /// The crate graph.
     #[salsa::input]
     fn proc_macros(&self) -> Arc<ProcMacros>;
+
+    /// The target chosen for files that are part of crates analyzed for several targets, see
+    /// [`CrateData::target`]. Files not in here use the primary target.
+    #[salsa::input]
+    fn active_targets(&self) -> Arc<FxHashMap<FileId, String>>;
+}
+
+fn crate_graph_query(db: &dyn SourceDatabase) -> Arc<CrateGraph> {
+    let crate_graph = db.workspace_crate_graph();
+    let ad_hoc_crates = db.ad_hoc_crates();
+    if ad_hoc_crates.is_empty() {
+        return crate_graph;
+    }
+    let mut crate_graph = CrateGraph::clone(&crate_graph);
+    crate_graph.add_ad_hoc_crates(&ad_hoc_crates);
+    Arc::new(crate_graph)
+}
+
+fn crate_data_query(db: &dyn SourceDatabase, krate: CrateId) -> Arc<CrateData> {
+    let crate_graph = db.workspace_crate_graph();
+    match crate_graph.get(krate) {
+        Some(data) => Arc::new(data.clone()),
+        None => {
+            let idx = u32::from(krate.into_raw()) as usize - crate_graph.len();
+            Arc::new(db.ad_hoc_crates()[idx].clone())
+        }
+    }
 }
 
 fn parse_query(db: &dyn SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
@@ -134,4 +180,9 @@ impl<T: SourceDatabaseExt> FileLoader for FileLoaderDelegate<&'_ T> {
         let source_root = self.0.file_source_root(file_id);
         self.0.source_root_crates(source_root)
     }
+
+    fn file_path(&self, file_id: FileId) -> Option<VfsPath> {
+        let source_root = self.0.source_root(self.0.file_source_root(file_id));
+        source_root.path_for_file(&file_id).cloned()
+    }
 }
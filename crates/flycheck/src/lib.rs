@@ -17,10 +17,13 @@ use rustc_hash::FxHashMap;
 use serde::Deserialize;
 use stdx::process::streaming_output;
 
+mod test_runner;
+
 pub use cargo_metadata::diagnostic::{
     Applicability, Diagnostic, DiagnosticCode, DiagnosticLevel, DiagnosticSpan,
     DiagnosticSpanMacroExpansion,
 };
+pub use test_runner::{panic_location, CargoTestHandle, CargoTestMessage, TestState};
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum InvocationStrategy {
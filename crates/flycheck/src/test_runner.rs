@@ -0,0 +1,169 @@
+//! This module provides the functionality needed to run `cargo test` in a background
+//! thread and report the result of each test back, using libtest's JSON output.
+
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+use command_group::CommandGroup;
+use crossbeam_channel::Sender;
+use serde::Deserialize;
+use stdx::process::streaming_output;
+
+use crate::JodGroupChild;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum TestState {
+    Started,
+    Ok,
+    Ignored,
+    Failed {
+        #[serde(default)]
+        stdout: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CargoTestMessage {
+    Test {
+        name: String,
+        #[serde(flatten)]
+        state: TestState,
+    },
+    Suite,
+    /// A line of output which isn't a libtest event, like the output of cargo itself.
+    #[serde(skip)]
+    Custom {
+        text: String,
+    },
+    /// The cargo process exited.
+    #[serde(skip)]
+    Finished,
+}
+
+impl CargoTestMessage {
+    fn from_line(line: &str) -> CargoTestMessage {
+        let mut deserializer = serde_json::Deserializer::from_str(line);
+        deserializer.disable_recursion_limit();
+        CargoTestMessage::deserialize(&mut deserializer)
+            .unwrap_or_else(|_| CargoTestMessage::Custom { text: line.to_owned() })
+    }
+}
+
+/// A handle to a `cargo test` process.
+///
+/// The process is killed when this struct is dropped.
+pub struct CargoTestHandle {
+    // XXX: drop order is significant, the reader thread only exits once the process is dead
+    _child: JodGroupChild,
+    _thread: stdx::thread::JoinHandle,
+}
+
+impl CargoTestHandle {
+    /// Spawns `command`, which is expected to be a `cargo test` invocation with libtest's
+    /// `--format=json` output enabled, and sends its messages to `sender`. The last message is
+    /// always [`CargoTestMessage::Finished`].
+    pub fn spawn(
+        mut command: Command,
+        sender: Sender<CargoTestMessage>,
+    ) -> io::Result<CargoTestHandle> {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null());
+        let mut child = command.group_spawn().map(JodGroupChild)?;
+
+        let stdout = child.0.inner().stdout.take().unwrap();
+        let stderr = child.0.inner().stderr.take().unwrap();
+
+        let thread = stdx::thread::Builder::new(stdx::thread::ThreadIntent::Worker)
+            .name("CargoTestHandle".to_owned())
+            .spawn(move || {
+                // The receiving end is dropped when the test run gets aborted, so send errors are
+                // ignored here.
+                let output = streaming_output(
+                    stdout,
+                    stderr,
+                    &mut |line| _ = sender.send(CargoTestMessage::from_line(line)),
+                    &mut |line| _ = sender.send(CargoTestMessage::Custom { text: line.to_owned() }),
+                );
+                if let Err(e) = output {
+                    _ = sender.send(CargoTestMessage::Custom { text: e.to_string() });
+                }
+                _ = sender.send(CargoTestMessage::Finished);
+            })
+            .expect("failed to spawn thread");
+        Ok(CargoTestHandle { _child: child, _thread: thread })
+    }
+}
+
+/// Extracts the location of the panic from the captured output of a failed test, as a path
+/// relative to the workspace root, a line and a column.
+pub fn panic_location(stdout: &str) -> Option<(&str, u32, u32)> {
+    let (_, rest) = stdout.split_once("panicked at ")?;
+    if let Some(message) = rest.strip_prefix('\'') {
+        // `panicked at 'message', src/lib.rs:1:5`, where the message may span multiple lines.
+        return message.match_indices("', ").find_map(|(idx, sep)| {
+            let location = &message[idx + sep.len()..];
+            parse_location(location.lines().next()?)
+        });
+    }
+    // `panicked at src/lib.rs:1:5:` followed by the message on the next line.
+    let location = rest.lines().next()?;
+    parse_location(location.strip_suffix(':').unwrap_or(location))
+}
+
+fn parse_location(location: &str) -> Option<(&str, u32, u32)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let path = parts.next().filter(|it| !it.is_empty())?;
+    Some((path, line, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_libtest_messages() {
+        let messages = [
+            r#"{ "type": "suite", "event": "started", "test_count": 2 }"#,
+            r#"{ "type": "test", "event": "started", "name": "tests::passes" }"#,
+            r#"{ "type": "test", "name": "tests::passes", "event": "ok" }"#,
+            r#"{ "type": "test", "name": "tests::fails", "event": "failed", "stdout": "boom\n" }"#,
+            r#"{ "type": "test", "name": "tests::ignored", "event": "ignored" }"#,
+            "running 2 tests",
+        ]
+        .map(|line| format!("{:?}", CargoTestMessage::from_line(line)));
+        assert_eq!(
+            messages,
+            [
+                "Suite",
+                r#"Test { name: "tests::passes", state: Started }"#,
+                r#"Test { name: "tests::passes", state: Ok }"#,
+                r#"Test { name: "tests::fails", state: Failed { stdout: "boom\n" } }"#,
+                r#"Test { name: "tests::ignored", state: Ignored }"#,
+                r#"Custom { text: "running 2 tests" }"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_panic_locations() {
+        assert_eq!(
+            panic_location(
+                "thread 'tests::fails' panicked at src/lib.rs:12:9:\nassertion failed\n"
+            ),
+            Some(("src/lib.rs", 12, 9))
+        );
+        assert_eq!(
+            panic_location(
+                "thread 'tests::fails' panicked at 'assertion failed: `(left == right)`\n  \
+                 left: `1`,\n right: `2`', src/foo/bar.rs:3:5\nnote: run with `RUST_BACKTRACE=1`"
+            ),
+            Some(("src/foo/bar.rs", 3, 5))
+        );
+        assert_eq!(panic_location("no panic here"), None);
+    }
+}
@@ -103,8 +103,8 @@ impl Attrs {
         let krate = loc.container.krate;
         let item_tree = loc.id.item_tree(db);
         let enum_ = &item_tree[loc.id.value];
-        let crate_graph = db.crate_graph();
-        let cfg_options = &crate_graph[krate].cfg_options;
+        let crate_data = db.crate_data(krate);
+        let cfg_options = &crate_data.cfg_options;
 
         let mut idx = 0;
         for variant in enum_.variants.clone() {
@@ -126,7 +126,6 @@ impl Attrs {
         // FIXME: There should be some proper form of mapping between item tree field ids and hir field ids
         let mut res = ArenaMap::default();
 
-        let crate_graph = db.crate_graph();
         let (fields, item_tree, krate) = match v {
             VariantId::EnumVariantId(it) => {
                 let e = it.parent;
@@ -135,7 +134,8 @@ impl Attrs {
                 let item_tree = loc.id.item_tree(db);
                 let enum_ = &item_tree[loc.id.value];
 
-                let cfg_options = &crate_graph[krate].cfg_options;
+                let crate_data = db.crate_data(krate);
+                let cfg_options = &crate_data.cfg_options;
 
                 let Some(variant) = enum_
                     .variants
@@ -174,7 +174,8 @@ impl Attrs {
             Fields::Unit => return Arc::new(res),
         };
 
-        let cfg_options = &crate_graph[krate].cfg_options;
+        let crate_data = db.crate_data(krate);
+        let cfg_options = &crate_data.cfg_options;
 
         let mut idx = 0;
         for field in fields {
@@ -11,7 +11,7 @@ use std::ops::Index;
 use base_db::CrateId;
 use cfg::{CfgExpr, CfgOptions};
 use either::Either;
-use hir_expand::{name::Name, HirFileId, InFile};
+use hir_expand::{name::Name, proc_macro::EmittedDiagnostic, HirFileId, InFile};
 use la_arena::{Arena, ArenaMap};
 use profile::Count;
 use rustc_hash::FxHashMap;
@@ -24,6 +24,7 @@ use crate::{
     hir::{
         dummy_expr_id, Binding, BindingId, Expr, ExprId, Label, LabelId, Pat, PatId, RecordFieldPat,
     },
+    item_scope::UseTreeId,
     nameres::DefMap,
     path::{ModPath, Path},
     src::{HasChildSource, HasSource},
@@ -51,6 +52,8 @@ pub struct Body {
     pub body_expr: ExprId,
     /// Block expressions in this body that may contain inner items.
     block_scopes: Vec<BlockId>,
+    /// The imports the paths of the macro calls in this body have been resolved through.
+    pub used_imports: Vec<UseTreeId>,
     _c: Count<Self>,
 }
 
@@ -105,12 +108,36 @@ pub struct SyntheticSyntax;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum BodyDiagnostic {
-    InactiveCode { node: InFile<SyntaxNodePtr>, cfg: CfgExpr, opts: CfgOptions },
-    MacroError { node: InFile<AstPtr<ast::MacroCall>>, message: String },
-    UnresolvedProcMacro { node: InFile<AstPtr<ast::MacroCall>>, krate: CrateId },
-    UnresolvedMacroCall { node: InFile<AstPtr<ast::MacroCall>>, path: ModPath },
-    UnreachableLabel { node: InFile<AstPtr<ast::Lifetime>>, name: Name },
-    UndeclaredLabel { node: InFile<AstPtr<ast::Lifetime>>, name: Name },
+    InactiveCode {
+        node: InFile<SyntaxNodePtr>,
+        cfg: CfgExpr,
+        opts: CfgOptions,
+    },
+    MacroError {
+        node: InFile<AstPtr<ast::MacroCall>>,
+        message: String,
+    },
+    ProcMacroDiagnostic {
+        node: InFile<AstPtr<ast::MacroCall>>,
+        diagnostic: EmittedDiagnostic,
+    },
+    UnresolvedProcMacro {
+        node: InFile<AstPtr<ast::MacroCall>>,
+        krate: CrateId,
+        crash: Option<Box<str>>,
+    },
+    UnresolvedMacroCall {
+        node: InFile<AstPtr<ast::MacroCall>>,
+        path: ModPath,
+    },
+    UnreachableLabel {
+        node: InFile<AstPtr<ast::Lifetime>>,
+        name: Name,
+    },
+    UndeclaredLabel {
+        node: InFile<AstPtr<ast::Lifetime>>,
+        name: Name,
+    },
 }
 
 impl Body {
@@ -132,13 +159,13 @@ impl Body {
                         let item_tree = f.id.item_tree(db);
                         let func = &item_tree[f.id.value];
                         let krate = f.container.module(db).krate;
-                        let crate_graph = db.crate_graph();
+                        let crate_data = db.crate_data(krate);
                         (
                             param_list,
                             func.params.clone().map(move |param| {
                                 item_tree
                                     .attrs(db, krate, param.into())
-                                    .is_cfg_enabled(&crate_graph[krate].cfg_options)
+                                    .is_cfg_enabled(&crate_data.cfg_options)
                             }),
                         )
                     });
@@ -213,6 +240,7 @@ impl Body {
             _c: _,
             body_expr: _,
             block_scopes,
+            used_imports,
             exprs,
             labels,
             params,
@@ -221,6 +249,7 @@ impl Body {
             binding_owners,
         } = self;
         block_scopes.shrink_to_fit();
+        used_imports.shrink_to_fit();
         exprs.shrink_to_fit();
         labels.shrink_to_fit();
         params.shrink_to_fit();
@@ -293,6 +322,7 @@ impl Default for Body {
             labels: Default::default(),
             params: Default::default(),
             block_scopes: Default::default(),
+            used_imports: Default::default(),
             binding_owners: Default::default(),
             _c: Default::default(),
         }
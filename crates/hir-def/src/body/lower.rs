@@ -1,7 +1,7 @@
 //! Transforms `ast::Expr` into an equivalent `hir_def::expr::Expr`
 //! representation.
 
-use std::mem;
+use std::{cell::RefCell, mem};
 
 use base_db::CrateId;
 use either::Either;
@@ -67,6 +67,7 @@ pub(super) fn lower(
             params: Vec::new(),
             body_expr: dummy_expr_id(),
             block_scopes: Vec::new(),
+            used_imports: Vec::new(),
             _c: Count::new(),
         },
         expander,
@@ -681,6 +682,11 @@ impl ExprCollector<'_> {
         let (id, prev_owner) = self.initialize_binding_owner(syntax_ptr);
         let tmp = job(self);
         self.body.exprs[id] = mem::replace(&mut self.body.exprs[tmp], Expr::Missing);
+        // The source of the expression now belongs to `id`, not to the dangling `Missing` one.
+        if let Some(src) = self.source_map.expr_map_back.remove(tmp) {
+            self.source_map.expr_map.insert(src.clone(), id);
+            self.source_map.expr_map_back.insert(id, src);
+        }
         self.current_binding_owner = prev_owner;
         id
     }
@@ -909,18 +915,20 @@ impl ExprCollector<'_> {
 
         let macro_call_ptr = self.expander.to_source(AstPtr::new(&mcall));
         let module = self.expander.module.local_id;
+        let used_imports = RefCell::new(Vec::new());
         let res = self.expander.enter_expand(self.db, mcall, |path| {
-            self.def_map
-                .resolve_path(
-                    self.db,
-                    module,
-                    &path,
-                    crate::item_scope::BuiltinShadowMode::Other,
-                    Some(MacroSubNs::Bang),
-                )
-                .0
-                .take_macros()
+            let (def, imports) = self.def_map.resolve_path_with_imports(
+                self.db,
+                module,
+                &path,
+                crate::item_scope::BuiltinShadowMode::Other,
+                Some(MacroSubNs::Bang),
+            );
+            used_imports.borrow_mut().extend(imports);
+            def.take_macros()
         });
+        // Eagerly expanded macros resolve the macro calls of their input with the same resolver.
+        self.body.used_imports.extend(used_imports.into_inner());
 
         let res = match res {
             Ok(res) => res,
@@ -941,6 +949,14 @@ impl ExprCollector<'_> {
                     self.source_map.diagnostics.push(BodyDiagnostic::UnresolvedProcMacro {
                         node: InFile::new(outer_file, syntax_ptr),
                         krate: *krate,
+                        crash: None,
+                    });
+                }
+                Some(ExpandError::ProcMacroCrashed(krate, crash)) => {
+                    self.source_map.diagnostics.push(BodyDiagnostic::UnresolvedProcMacro {
+                        node: InFile::new(outer_file, syntax_ptr),
+                        krate: *krate,
+                        crash: Some((**crash).clone()),
                     });
                 }
                 Some(ExpandError::RecursionOverflowPoisoned) => {
@@ -961,7 +977,9 @@ impl ExprCollector<'_> {
             Some((mark, expansion)) => {
                 // Keep collecting even with expansion errors so we can provide completions and
                 // other services in incomplete macro expressions.
-                self.source_map.expansions.insert(macro_call_ptr, self.expander.current_file_id);
+                self.source_map
+                    .expansions
+                    .insert(macro_call_ptr.clone(), self.expander.current_file_id);
                 let prev_ast_id_map = mem::replace(
                     &mut self.ast_id_map,
                     self.db.ast_id_map(self.expander.current_file_id),
@@ -969,6 +987,15 @@ impl ExprCollector<'_> {
 
                 if record_diagnostics {
                     // FIXME: Report parse errors here
+                    if let Some(macro_file) = self.expander.current_file_id.macro_file() {
+                        let diagnostics = self.db.proc_macro_diagnostics(macro_file.macro_call_id);
+                        self.source_map.diagnostics.extend(diagnostics.iter().map(|it| {
+                            BodyDiagnostic::ProcMacroDiagnostic {
+                                node: macro_call_ptr.clone(),
+                                diagnostic: it.clone(),
+                            }
+                        }));
+                    }
                 }
 
                 let id = collector(self, Some(expansion.tree()));
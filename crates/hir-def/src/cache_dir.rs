@@ -0,0 +1,93 @@
+//! The on-disk layout shared by the caches that persist analysis results across restarts, like
+//! [`crate::item_tree::ItemTreeCache`].
+//!
+//! Every rust-analyzer version gets a directory of its own, as entries written by one version
+//! can't be read by another. An entry is stored in a file named after two hashes: one of a key
+//! identifying what the entry is for (like the path of a file), and one of the contents it has been
+//! computed from (like the text of that file).
+
+use std::{
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rustc_hash::FxHasher;
+
+/// The entries written by one rust-analyzer version, see the [module docs](self).
+#[derive(Debug)]
+pub struct CacheDir {
+    dir: PathBuf,
+}
+
+impl CacheDir {
+    /// Returns the directory of the entries written by `version` below `root`.
+    pub fn new(root: &Path, version: &str) -> CacheDir {
+        let version_dir: String = version
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-') { c } else { '_' })
+            .collect();
+        CacheDir { dir: root.join(version_dir) }
+    }
+
+    pub fn hash(it: &str) -> u64 {
+        let mut hasher = FxHasher::default();
+        it.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the entries, along with the key and content hashes they have been stored for.
+    pub fn entries(&self) -> impl Iterator<Item = (PathBuf, u64, u64)> {
+        fs::read_dir(&self.dir).into_iter().flatten().flatten().filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?.strip_suffix(".json")?;
+            let (key_hash, content_hash) = name.split_once('-')?;
+            let key_hash = u64::from_str_radix(key_hash, 16).ok()?;
+            let content_hash = u64::from_str_radix(content_hash, 16).ok()?;
+            Some((path, key_hash, content_hash))
+        })
+    }
+
+    /// Stores an entry. Other processes never see a partially written entry.
+    pub fn write(&self, key_hash: u64, content_hash: u64, contents: &[u8]) -> io::Result<()> {
+        static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{key_hash:016x}-{content_hash:016x}.json"));
+        // Write to a temporary file first, so that other processes never see partial entries.
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &path).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            e
+        })
+    }
+
+    /// Removes the entries `is_stale` returns `true` for, given their key and content hashes, and
+    /// the directories of all other rust-analyzer versions.
+    pub fn evict(&self, is_stale: impl Fn(u64, u64) -> bool) {
+        for (path, key_hash, content_hash) in self.entries() {
+            if is_stale(key_hash, content_hash) {
+                let _ = fs::remove_file(path);
+            }
+        }
+
+        if let Some(parent) = self.dir.parent() {
+            let other_versions = fs::read_dir(parent)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|it| it.path())
+                .filter(|it| *it != self.dir && it.is_dir());
+            for dir in other_versions {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+}
@@ -57,8 +57,8 @@ impl FunctionData {
             item_tree[func.visibility].clone()
         };
 
-        let crate_graph = db.crate_graph();
-        let cfg_options = &crate_graph[krate].cfg_options;
+        let crate_data = db.crate_data(krate);
+        let cfg_options = &crate_data.cfg_options;
         let enabled_params = func
             .params
             .clone()
@@ -493,6 +493,7 @@ pub struct ConstData {
     pub type_ref: Interned<TypeRef>,
     pub visibility: RawVisibility,
     pub rustc_allow_incoherent_impl: bool,
+    pub has_body: bool,
 }
 
 impl ConstData {
@@ -516,6 +517,7 @@ impl ConstData {
             type_ref: konst.type_ref.clone(),
             visibility,
             rustc_allow_incoherent_impl,
+            has_body: konst.has_body,
         })
     }
 }
@@ -751,6 +753,15 @@ impl<'a> AssocItemCollector<'a> {
                         self.module_id.local_id,
                         error_call_kind(),
                         krate,
+                        None,
+                    )
+                }
+                hir_expand::ExpandError::ProcMacroCrashed(krate, crash) => {
+                    DefDiagnostic::unresolved_proc_macro(
+                        self.module_id.local_id,
+                        error_call_kind(),
+                        krate,
+                        Some(*crash),
                     )
                 }
                 _ => DefDiagnostic::macro_error(
@@ -768,6 +779,16 @@ impl<'a> AssocItemCollector<'a> {
                 errors.into(),
             ));
         }
+        if let Some(macro_file) = self.expander.current_file_id().macro_file() {
+            let diagnostics = self.db.proc_macro_diagnostics(macro_file.macro_call_id);
+            self.diagnostics.extend(diagnostics.iter().map(|it| {
+                DefDiagnostic::proc_macro_diagnostic(
+                    self.module_id.local_id,
+                    error_call_kind(),
+                    it.clone(),
+                )
+            }));
+        }
 
         let tree_id = item_tree::TreeId::new(self.expander.current_file_id(), None);
         let item_tree = tree_id.item_tree(self.db);
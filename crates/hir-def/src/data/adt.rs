@@ -194,7 +194,7 @@ impl StructData {
         let krate = loc.container.krate;
         let item_tree = loc.id.item_tree(db);
         let repr = repr_from_value(db, krate, &item_tree, ModItem::from(loc.id.value).into());
-        let cfg_options = db.crate_graph()[loc.container.krate].cfg_options.clone();
+        let cfg_options = db.crate_data(loc.container.krate).cfg_options.clone();
 
         let attrs = item_tree.attrs(db, loc.container.krate, ModItem::from(loc.id.value).into());
 
@@ -250,7 +250,7 @@ impl StructData {
         let krate = loc.container.krate;
         let item_tree = loc.id.item_tree(db);
         let repr = repr_from_value(db, krate, &item_tree, ModItem::from(loc.id.value).into());
-        let cfg_options = db.crate_graph()[loc.container.krate].cfg_options.clone();
+        let cfg_options = db.crate_data(loc.container.krate).cfg_options.clone();
 
         let attrs = item_tree.attrs(db, loc.container.krate, ModItem::from(loc.id.value).into());
         let mut flags = StructFlags::NO_FLAGS;
@@ -297,7 +297,7 @@ impl EnumData {
         let loc = e.lookup(db);
         let krate = loc.container.krate;
         let item_tree = loc.id.item_tree(db);
-        let cfg_options = db.crate_graph()[krate].cfg_options.clone();
+        let cfg_options = db.crate_data(krate).cfg_options.clone();
         let repr = repr_from_value(db, krate, &item_tree, ModItem::from(loc.id.value).into());
         let rustc_has_incoherent_inherent_impls = item_tree
             .attrs(db, loc.container.krate, ModItem::from(loc.id.value).into())
@@ -388,7 +388,7 @@ fn lower_enum(
 
     let item_tree_variants = item_tree[loc.id.value].variants.clone();
 
-    let cfg_options = &db.crate_graph()[krate].cfg_options;
+    let cfg_options = &db.crate_data(krate).cfg_options;
     let variants = ast
         .value
         .variant_list()
@@ -524,7 +524,7 @@ fn lower_struct(
 
     match (&ast.value, fields) {
         (ast::StructKind::Tuple(fl), Fields::Tuple(fields)) => {
-            let cfg_options = &db.crate_graph()[krate].cfg_options;
+            let cfg_options = &db.crate_data(krate).cfg_options;
             for ((i, fd), item_tree_id) in fl.fields().enumerate().zip(fields.clone()) {
                 if !item_tree.attrs(db, krate, item_tree_id.into()).is_cfg_enabled(cfg_options) {
                     continue;
@@ -542,7 +542,7 @@ fn lower_struct(
             StructKind::Tuple
         }
         (ast::StructKind::Record(fl), Fields::Record(fields)) => {
-            let cfg_options = &db.crate_graph()[krate].cfg_options;
+            let cfg_options = &db.crate_data(krate).cfg_options;
             for (fd, item_tree_id) in fl.fields().zip(fields.clone()) {
                 if !item_tree.attrs(db, krate, item_tree_id.into()).is_cfg_enabled(cfg_options) {
                     continue;
@@ -40,7 +40,7 @@ impl Expander {
             module,
             recursion_depth: 0,
             recursion_limit,
-            cfg_options: db.crate_graph()[module.krate].cfg_options.clone(),
+            cfg_options: db.crate_data(module.krate).cfg_options.clone(),
             hygiene: Hygiene::new(db.upcast(), current_file_id),
             krate: module.krate,
         }
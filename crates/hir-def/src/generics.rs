@@ -12,6 +12,7 @@ use hir_expand::{
 use intern::Interned;
 use la_arena::{Arena, ArenaMap, Idx};
 use once_cell::unsync::Lazy;
+use serde::{Deserialize, Serialize};
 use stdx::impl_from;
 use syntax::ast::{self, HasGenericParams, HasName, HasTypeBounds};
 use triomphe::Arc;
@@ -30,7 +31,7 @@ use crate::{
 };
 
 /// Data about a generic type parameter (to a function, struct, impl, ...).
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub struct TypeParamData {
     pub name: Option<Name>,
     pub default: Option<Interned<TypeRef>>,
@@ -38,27 +39,27 @@ pub struct TypeParamData {
 }
 
 /// Data about a generic lifetime parameter (to a function, struct, impl, ...).
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub struct LifetimeParamData {
     pub name: Name,
 }
 
 /// Data about a generic const parameter (to a function, struct, impl, ...).
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub struct ConstParamData {
     pub name: Name,
     pub ty: Interned<TypeRef>,
     pub has_default: bool,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum TypeParamProvenance {
     TypeParamList,
     TraitSelf,
     ArgumentImplTrait,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum TypeOrConstParamData {
     TypeParamData(TypeParamData),
     ConstParamData(ConstParamData),
@@ -106,9 +107,11 @@ impl TypeOrConstParamData {
 impl_from!(TypeParamData, ConstParamData for TypeOrConstParamData);
 
 /// Data about the generic parameters of a function, struct, impl, etc.
-#[derive(Clone, PartialEq, Eq, Debug, Default, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Hash, Serialize, Deserialize)]
 pub struct GenericParams {
+    #[serde(with = "hir_expand::arena_serde::arena")]
     pub type_or_consts: Arena<TypeOrConstParamData>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     pub lifetimes: Arena<LifetimeParamData>,
     pub where_predicates: Vec<WherePredicate>,
 }
@@ -117,7 +120,7 @@ pub struct GenericParams {
 /// where clauses like `where T: Foo + Bar` are turned into multiple of these.
 /// It might still result in multiple actual predicates though, because of
 /// associated type bindings like `Iterator<Item = u32>`.
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum WherePredicate {
     TypeBound {
         target: WherePredicateTypeTarget,
@@ -134,11 +137,11 @@ pub enum WherePredicate {
     },
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum WherePredicateTypeTarget {
     TypeRef(Interned<TypeRef>),
     /// For desugared where predicates that can directly refer to a type param.
-    TypeOrConstParam(LocalTypeOrConstParamId),
+    TypeOrConstParam(#[serde(with = "hir_expand::arena_serde::idx")] LocalTypeOrConstParamId),
 }
 
 impl GenericParams {
@@ -419,6 +422,73 @@ impl GenericParams {
     }
 }
 
+/// Returns the bounds which the `where_predicates` of the generic parameters of `def` are lowered
+/// from, in the same order. The predicates of `impl Trait` arguments come last and are desugared
+/// from types, so they have no entry.
+pub fn where_predicate_sources(
+    db: &dyn DefDatabase,
+    def: GenericDefId,
+) -> InFile<Vec<ast::TypeBound>> {
+    fn any<N: HasGenericParams>(src: InFile<N>) -> InFile<ast::AnyHasGenericParams> {
+        src.map(ast::AnyHasGenericParams::new)
+    }
+    let (src, supertraits) = match def {
+        GenericDefId::FunctionId(it) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::AdtId(AdtId::StructId(it)) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::AdtId(AdtId::UnionId(it)) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::AdtId(AdtId::EnumId(it)) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::TraitId(it) => {
+            let src = it.lookup(db).source(db);
+            let supertraits = src.value.type_bound_list();
+            (any(src), supertraits)
+        }
+        GenericDefId::TraitAliasId(it) => {
+            let src = it.lookup(db).source(db);
+            let supertraits = src.value.type_bound_list();
+            (any(src), supertraits)
+        }
+        GenericDefId::TypeAliasId(it) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::ImplId(it) => (any(it.lookup(db).source(db)), None),
+        GenericDefId::EnumVariantId(_) | GenericDefId::ConstId(_) => {
+            return InFile::new(FileId(!0).into(), Vec::new())
+        }
+    };
+
+    // This follows `GenericParams::fill`, see there for the order of the predicates.
+    let mut bounds = Vec::new();
+    let mut push_bounds = |list: Option<ast::TypeBoundList>, target_is_lifetime: bool| {
+        for bound in list.iter().flat_map(|it| it.bounds()) {
+            // Lifetimes can only be bounded by lifetimes, other bounds of them aren't lowered.
+            if !target_is_lifetime || matches!(bound.kind(), ast::TypeBoundKind::Lifetime(_)) {
+                bounds.push(bound);
+            }
+        }
+    };
+    push_bounds(supertraits, false);
+    let item = &src.value;
+    if let Some(params) = item.generic_param_list() {
+        for param in params.type_or_const_params() {
+            if let ast::TypeOrConstParam::Type(it) = param {
+                push_bounds(it.type_bound_list(), false);
+            }
+        }
+        for param in params.lifetime_params() {
+            push_bounds(param.type_bound_list(), true);
+        }
+    }
+    for pred in item.where_clause().iter().flat_map(|it| it.predicates()) {
+        let target_is_lifetime = if pred.ty().is_some() {
+            false
+        } else if pred.lifetime().is_some() {
+            true
+        } else {
+            continue;
+        };
+        push_bounds(pred.type_bound_list(), target_is_lifetime);
+    }
+    src.with_value(bounds)
+}
+
 fn file_id_and_params_of(
     def: GenericDefId,
     db: &dyn DefDatabase,
@@ -10,6 +10,7 @@ use hir_expand::{
     AstId,
 };
 use intern::Interned;
+use serde::{Deserialize, Serialize};
 use syntax::ast::{self, HasName};
 
 use crate::{
@@ -19,7 +20,7 @@ use crate::{
     path::Path,
 };
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum Mutability {
     Shared,
     Mut,
@@ -85,7 +86,7 @@ impl Rawness {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct TraitRef {
     pub path: Path,
 }
@@ -108,7 +109,7 @@ impl TraitRef {
 /// Note: Most users of `TypeRef` that end up in the salsa database intern it using
 /// `Interned<TypeRef>` to save space. But notably, nested `TypeRef`s are not interned, since that
 /// does not seem to save any noticeable amount of memory.
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum TypeRef {
     Never,
     Placeholder,
@@ -128,7 +129,7 @@ pub enum TypeRef {
     Error,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct LifetimeRef {
     pub name: Name,
 }
@@ -147,7 +148,7 @@ impl LifetimeRef {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum TypeBound {
     Path(Path, TraitBoundModifier),
     ForLifetime(Box<[Name]>, Path),
@@ -157,7 +158,7 @@ pub enum TypeBound {
 
 /// A modifier on a bound, currently this is only used for `?Sized`, where the
 /// modifier is `Maybe`.
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum TraitBoundModifier {
     None,
     Maybe,
@@ -375,7 +376,7 @@ impl TypeBound {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ConstRef {
     Scalar(LiteralConstRef),
     Path(Name),
@@ -452,7 +453,7 @@ impl ConstRef {
 }
 
 /// A literal constant value
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LiteralConstRef {
     Int(i128),
     UInt(u128),
@@ -6,6 +6,7 @@ use std::collections::hash_map::Entry;
 use base_db::CrateId;
 use hir_expand::{attrs::AttrId, db::ExpandDatabase, name::Name, AstId, MacroCallId};
 use itertools::Itertools;
+use la_arena::Idx;
 use once_cell::sync::Lazy;
 use profile::Count;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -15,13 +16,35 @@ use syntax::ast;
 
 use crate::{
     db::DefDatabase, per_ns::PerNs, visibility::Visibility, AdtId, BuiltinType, ConstId,
-    ExternCrateId, HasModule, ImplId, LocalModuleId, MacroId, ModuleDefId, ModuleId, TraitId,
+    ExternCrateId, HasModule, ImplId, ImportId, LocalModuleId, MacroId, ModuleDefId, ModuleId,
+    TraitId,
 };
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum ImportType {
-    Glob,
+    /// A glob import, along with the named import the imported entry originally comes from.
+    Glob(Option<UseTreeId>),
+    /// A declaration or an `extern crate` item.
     Named,
+    /// A named import through the given `use` tree.
+    Import(UseTreeId),
+}
+
+impl ImportType {
+    /// Returns the named import the entries imported with this import type come from.
+    pub(crate) fn use_import(self) -> Option<UseTreeId> {
+        match self {
+            ImportType::Import(import) | ImportType::Glob(Some(import)) => Some(import),
+            ImportType::Glob(None) | ImportType::Named => None,
+        }
+    }
+}
+
+/// A `use` tree of an import, like `Bar` in `use foo::{Bar, baz::*};`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UseTreeId {
+    pub import: ImportId,
+    pub idx: Idx<ast::UseTree>,
 }
 
 #[derive(Debug, Default)]
@@ -42,14 +65,21 @@ pub struct ItemScope {
     macros: FxHashMap<Name, (MacroId, Visibility)>,
     unresolved: FxHashSet<Name>,
 
+    /// The named imports the entries of `types`, `values` and `macros` come from.
+    use_imports_types: FxHashMap<Name, UseTreeId>,
+    use_imports_values: FxHashMap<Name, UseTreeId>,
+    use_imports_macros: FxHashMap<Name, UseTreeId>,
+    /// The imports the import and macro paths of this scope have been resolved through.
+    used_imports: FxHashSet<UseTreeId>,
+
     /// The defs declared in this scope. Each def has a single scope where it is
     /// declared.
     declarations: Vec<ModuleDefId>,
 
     impls: Vec<ImplId>,
     unnamed_consts: Vec<ConstId>,
-    /// Traits imported via `use Trait as _;`.
-    unnamed_trait_imports: FxHashMap<TraitId, Visibility>,
+    /// Traits imported via `use Trait as _;`, along with the `use` tree importing them.
+    unnamed_trait_imports: FxHashMap<TraitId, (Visibility, Option<UseTreeId>)>,
     extern_crate_decls: Vec<ExternCrateId>,
     /// Macros visible in current module in legacy textual scope
     ///
@@ -156,6 +186,87 @@ impl ItemScope {
         self.types.get(name).copied()
     }
 
+    /// Returns the named imports `def`, the result of looking up `name` in this scope, comes from.
+    pub(crate) fn imports_of(
+        &self,
+        name: &Name,
+        def: PerNs,
+    ) -> impl Iterator<Item = UseTreeId> + '_ {
+        let types = def
+            .types
+            .filter(|&(it, _)| self.types.get(name).map(|&(def, _)| def) == Some(it))
+            .and_then(|_| self.use_imports_types.get(name));
+        let values = def
+            .values
+            .filter(|&(it, _)| self.values.get(name).map(|&(def, _)| def) == Some(it))
+            .and_then(|_| self.use_imports_values.get(name));
+        let macros = def
+            .macros
+            .filter(|&(it, _)| self.macros.get(name).map(|&(def, _)| def) == Some(it))
+            .and_then(|_| self.use_imports_macros.get(name));
+        types.into_iter().chain(values).chain(macros).copied().unique()
+    }
+
+    /// Splits `def`, an entry of [`ItemScope::resolutions`], by the named imports its namespaces
+    /// come from.
+    pub(crate) fn split_by_import(
+        &self,
+        name: Option<&Name>,
+        def: PerNs,
+    ) -> SmallVec<[(PerNs, Option<UseTreeId>); 1]> {
+        let Some(name) = name else {
+            let import = match def.take_types() {
+                Some(ModuleDefId::TraitId(tr)) => {
+                    self.unnamed_trait_imports.get(&tr).and_then(|&(_, import)| import)
+                }
+                _ => None,
+            };
+            return smallvec![(def, import)];
+        };
+        let namespaces = [
+            PerNs { types: def.types, ..PerNs::none() },
+            PerNs { values: def.values, ..PerNs::none() },
+            PerNs { macros: def.macros, ..PerNs::none() },
+        ];
+        let mut res: SmallVec<[(PerNs, Option<UseTreeId>); 1]> = SmallVec::new();
+        for def in namespaces.into_iter().filter(|it| !it.is_none()) {
+            let import = self.imports_of(name, def).next();
+            match res.iter_mut().find(|(_, it)| *it == import) {
+                Some((acc, _)) => *acc = acc.or(def),
+                None => res.push((def, import)),
+            }
+        }
+        res
+    }
+
+    /// Returns the named imports the entries of this scope come from, along with the items they
+    /// bring into scope. An import bringing items of several namespaces into scope is returned
+    /// once per namespace. Entries glob imported from other scopes are returned with the named
+    /// import of the scope they have been imported from.
+    pub fn use_imports(&self) -> impl Iterator<Item = (UseTreeId, ItemInNs)> + '_ {
+        let types = self
+            .use_imports_types
+            .iter()
+            .filter_map(|(name, &import)| Some((import, ItemInNs::Types(self.types.get(name)?.0))));
+        let values = self.use_imports_values.iter().filter_map(|(name, &import)| {
+            Some((import, ItemInNs::Values(self.values.get(name)?.0)))
+        });
+        let macros = self.use_imports_macros.iter().filter_map(|(name, &import)| {
+            Some((import, ItemInNs::Macros(self.macros.get(name)?.0)))
+        });
+        let unnamed_traits = self.unnamed_trait_imports.iter().filter_map(|(&tr, &(_, import))| {
+            Some((import?, ItemInNs::Types(ModuleDefId::TraitId(tr))))
+        });
+        types.chain(values).chain(macros).chain(unnamed_traits)
+    }
+
+    /// Returns the imports that paths of this scope have been resolved through while collecting the
+    /// `DefMap`, like the ones of import paths and macro calls. Paths in the signatures and bodies
+    /// of items are not included.
+    pub fn used_imports(&self) -> impl Iterator<Item = UseTreeId> + '_ {
+        self.used_imports.iter().copied()
+    }
+
     /// XXX: this is O(N) rather than O(1), try to not introduce new usages.
     pub(crate) fn name_of(&self, item: ItemInNs) -> Option<(&Name, Visibility)> {
         let (def, mut iter) = match item {
@@ -266,11 +377,20 @@ impl ItemScope {
     }
 
     pub(crate) fn unnamed_trait_vis(&self, tr: TraitId) -> Option<Visibility> {
-        self.unnamed_trait_imports.get(&tr).copied()
+        self.unnamed_trait_imports.get(&tr).map(|&(vis, _)| vis)
     }
 
-    pub(crate) fn push_unnamed_trait(&mut self, tr: TraitId, vis: Visibility) {
-        self.unnamed_trait_imports.insert(tr, vis);
+    pub(crate) fn push_unnamed_trait(
+        &mut self,
+        tr: TraitId,
+        vis: Visibility,
+        import: Option<UseTreeId>,
+    ) {
+        self.unnamed_trait_imports.insert(tr, (vis, import));
+    }
+
+    pub(crate) fn record_used_imports(&mut self, imports: impl IntoIterator<Item = UseTreeId>) {
+        self.used_imports.extend(imports);
     }
 
     pub(crate) fn push_res_with_import(
@@ -286,43 +406,85 @@ impl ItemScope {
             (
                 $changed:ident,
                 ( $this:ident / $def:ident ) . $field:ident,
+                $this_use_imports:ident,
                 $glob_imports:ident [ $lookup:ident ],
                 $def_import_type:ident
             ) => {{
                 if let Some(fld) = $def.$field {
                     let existing = $this.$field.entry($lookup.1.clone());
-                    match existing {
+                    let inserted = match existing {
                         Entry::Vacant(entry) => {
                             match $def_import_type {
-                                ImportType::Glob => {
+                                ImportType::Glob(_) => {
                                     $glob_imports.$field.insert($lookup.clone());
                                 }
-                                ImportType::Named => {
+                                ImportType::Named | ImportType::Import(_) => {
                                     $glob_imports.$field.remove(&$lookup);
                                 }
                             }
 
                             entry.insert(fld);
-                            $changed = true;
+                            true
                         }
                         Entry::Occupied(mut entry)
-                            if matches!($def_import_type, ImportType::Named) =>
+                            if !matches!($def_import_type, ImportType::Glob(_)) =>
                         {
                             if $glob_imports.$field.remove(&$lookup) {
                                 cov_mark::hit!(import_shadowed);
                                 entry.insert(fld);
-                                $changed = true;
+                                true
+                            } else {
+                                // An import of the def that is already declared under the same
+                                // name is still used through the paths naming it.
+                                if let ImportType::Import(import) = $def_import_type {
+                                    if entry.get().0 == fld.0 {
+                                        $this
+                                            .$this_use_imports
+                                            .entry($lookup.1.clone())
+                                            .or_insert(import);
+                                    }
+                                }
+                                false
+                            }
+                        }
+                        _ => false,
+                    };
+                    if inserted {
+                        match $def_import_type.use_import() {
+                            Some(import) => {
+                                $this.$this_use_imports.insert($lookup.1.clone(), import);
+                            }
+                            None => {
+                                $this.$this_use_imports.remove(&$lookup.1);
                             }
                         }
-                        _ => {}
+                        $changed = true;
                     }
                 }
             }};
         }
 
-        check_changed!(changed, (self / def).types, glob_imports[lookup], def_import_type);
-        check_changed!(changed, (self / def).values, glob_imports[lookup], def_import_type);
-        check_changed!(changed, (self / def).macros, glob_imports[lookup], def_import_type);
+        check_changed!(
+            changed,
+            (self / def).types,
+            use_imports_types,
+            glob_imports[lookup],
+            def_import_type
+        );
+        check_changed!(
+            changed,
+            (self / def).values,
+            use_imports_values,
+            glob_imports[lookup],
+            def_import_type
+        );
+        check_changed!(
+            changed,
+            (self / def).macros,
+            use_imports_macros,
+            glob_imports[lookup],
+            def_import_type
+        );
 
         if def.is_none() && self.unresolved.insert(lookup.1) {
             changed = true;
@@ -335,7 +497,7 @@ impl ItemScope {
         self.entries().map(|(name, res)| (Some(name.clone()), res)).chain(
             self.unnamed_trait_imports
                 .iter()
-                .map(|(tr, vis)| (None, PerNs::types(ModuleDefId::TraitId(*tr), *vis))),
+                .map(|(tr, &(vis, _))| (None, PerNs::types(ModuleDefId::TraitId(*tr), vis))),
         )
     }
 
@@ -345,7 +507,7 @@ impl ItemScope {
             .values_mut()
             .chain(self.values.values_mut())
             .map(|(_, v)| v)
-            .chain(self.unnamed_trait_imports.values_mut())
+            .chain(self.unnamed_trait_imports.values_mut().map(|(v, _)| v))
             .for_each(|vis| *vis = Visibility::Module(this_module));
 
         for (mac, vis) in self.macros.values_mut() {
@@ -395,6 +557,10 @@ impl ItemScope {
             values,
             macros,
             unresolved,
+            use_imports_types,
+            use_imports_values,
+            use_imports_macros,
+            used_imports,
             declarations,
             impls,
             unnamed_consts,
@@ -408,6 +574,10 @@ impl ItemScope {
         values.shrink_to_fit();
         macros.shrink_to_fit();
         unresolved.shrink_to_fit();
+        use_imports_types.shrink_to_fit();
+        use_imports_values.shrink_to_fit();
+        use_imports_macros.shrink_to_fit();
+        used_imports.shrink_to_fit();
         declarations.shrink_to_fit();
         impls.shrink_to_fit();
         unnamed_consts.shrink_to_fit();
@@ -30,12 +30,14 @@
 //! In general, any item in the `ItemTree` stores its `AstId`, which allows mapping it back to its
 //! surface syntax.
 
+mod cache;
 mod lower;
 mod pretty;
 #[cfg(test)]
 mod tests;
 
 use std::{
+    cmp::Ordering,
     fmt::{self, Debug},
     hash::{Hash, Hasher},
     marker::PhantomData,
@@ -56,11 +58,14 @@ use intern::Interned;
 use la_arena::{Arena, Idx, IdxRange, RawIdx};
 use profile::Count;
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::SmallVec;
 use stdx::never;
 use syntax::{ast, match_ast, SyntaxKind};
 use triomphe::Arc;
 
+pub use self::cache::ItemTreeCache;
+
 use crate::{
     attr::Attrs,
     db::DefDatabase,
@@ -71,7 +76,7 @@ use crate::{
     BlockId,
 };
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct RawVisibilityId(u32);
 
 impl RawVisibilityId {
@@ -94,19 +99,47 @@ impl fmt::Debug for RawVisibilityId {
 }
 
 /// The item tree of a source file.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ItemTree {
+    #[serde(skip)]
     _c: Count<Self>,
 
     top_level: SmallVec<[ModItem; 1]>,
+    // JSON only supports string keys, so the map is stored as a list of pairs.
+    #[serde(serialize_with = "serialize_attrs", deserialize_with = "deserialize_attrs")]
     attrs: FxHashMap<AttrOwner, RawAttrs>,
 
     data: Option<Box<ItemTreeData>>,
 }
 
+fn serialize_attrs<S: Serializer>(
+    attrs: &FxHashMap<AttrOwner, RawAttrs>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(attrs.iter())
+}
+
+fn deserialize_attrs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<FxHashMap<AttrOwner, RawAttrs>, D::Error> {
+    Vec::<(AttrOwner, RawAttrs)>::deserialize(deserializer).map(|it| it.into_iter().collect())
+}
+
 impl ItemTree {
     pub(crate) fn file_item_tree_query(db: &dyn DefDatabase, file_id: HirFileId) -> Arc<ItemTree> {
         let _p = profile::span("file_item_tree_query").detail(|| format!("{file_id:?}"));
+        let Some((file_id, cache)) = file_id.file_id().zip(db.item_tree_cache()) else {
+            return Arc::new(Self::lower_file(db, file_id));
+        };
+
+        let text = db.file_text(file_id);
+        if let Some(item_tree) = cache.get(file_id, &text) {
+            return Arc::new(item_tree);
+        }
+        Arc::new(Self::lower_file(db, file_id.into()))
+    }
+
+    fn lower_file(db: &dyn DefDatabase, file_id: HirFileId) -> ItemTree {
         let syntax = db.parse_or_expand(file_id);
         if never!(syntax.kind() == SyntaxKind::ERROR, "{:?} from {:?} {}", file_id, syntax, syntax)
         {
@@ -140,7 +173,7 @@ impl ItemTree {
             item_tree.attrs.insert(AttrOwner::TopLevel, attrs);
         }
         item_tree.shrink_to_fit();
-        Arc::new(item_tree)
+        item_tree
     }
 
     /// Returns an iterator over all items located at the top level of the `HirFileId` this
@@ -237,8 +270,9 @@ impl ItemTree {
     }
 }
 
-#[derive(Default, Debug, Eq, PartialEq)]
+#[derive(Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
 struct ItemVisibilities {
+    #[serde(with = "hir_expand::arena_serde::arena")]
     arena: Arena<RawVisibility>,
 }
 
@@ -260,42 +294,62 @@ static VIS_PUB: RawVisibility = RawVisibility::Public;
 static VIS_PRIV: RawVisibility = RawVisibility::Module(ModPath::from_kind(PathKind::Super(0)));
 static VIS_PUB_CRATE: RawVisibility = RawVisibility::Module(ModPath::from_kind(PathKind::Crate));
 
-#[derive(Default, Debug, Eq, PartialEq)]
+#[derive(Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
 struct ItemTreeData {
+    #[serde(with = "hir_expand::arena_serde::arena")]
     imports: Arena<Import>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     extern_crates: Arena<ExternCrate>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     extern_blocks: Arena<ExternBlock>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     functions: Arena<Function>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     params: Arena<Param>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     structs: Arena<Struct>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     fields: Arena<Field>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     unions: Arena<Union>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     enums: Arena<Enum>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     variants: Arena<Variant>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     consts: Arena<Const>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     statics: Arena<Static>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     traits: Arena<Trait>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     trait_aliases: Arena<TraitAlias>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     impls: Arena<Impl>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     type_aliases: Arena<TypeAlias>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     mods: Arena<Mod>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     macro_calls: Arena<MacroCall>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     macro_rules: Arena<MacroRules>,
+    #[serde(with = "hir_expand::arena_serde::arena")]
     macro_defs: Arena<MacroDef>,
 
     vis: ItemVisibilities,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum AttrOwner {
     /// Attributes on an item.
     ModItem(ModItem),
     /// Inner attributes of the source file.
     TopLevel,
 
-    Variant(Idx<Variant>),
-    Field(Idx<Field>),
-    Param(Idx<Param>),
+    Variant(#[serde(with = "hir_expand::arena_serde::idx")] Idx<Variant>),
+    Field(#[serde(with = "hir_expand::arena_serde::idx")] Idx<Field>),
+    Param(#[serde(with = "hir_expand::arena_serde::idx")] Idx<Param>),
 }
 
 macro_rules! from_attrs {
@@ -347,12 +401,36 @@ impl<N: ItemTreeNode> PartialEq for FileItemTreeId<N> {
 }
 impl<N: ItemTreeNode> Eq for FileItemTreeId<N> {}
 
+impl<N: ItemTreeNode> PartialOrd for FileItemTreeId<N> {
+    fn partial_cmp(&self, other: &FileItemTreeId<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<N: ItemTreeNode> Ord for FileItemTreeId<N> {
+    fn cmp(&self, other: &FileItemTreeId<N>) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
 impl<N: ItemTreeNode> Hash for FileItemTreeId<N> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.index.hash(state)
     }
 }
 
+impl<N: ItemTreeNode> Serialize for FileItemTreeId<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        hir_expand::arena_serde::idx::serialize(&self.index, serializer)
+    }
+}
+
+impl<'de, N: ItemTreeNode> Deserialize<'de> for FileItemTreeId<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let index = hir_expand::arena_serde::idx::deserialize(deserializer)?;
+        Ok(FileItemTreeId { index, _p: PhantomData })
+    }
+}
+
 impl<N: ItemTreeNode> fmt::Debug for FileItemTreeId<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.index.fmt(f)
@@ -382,6 +460,10 @@ impl TreeId {
         self.file
     }
 
+    pub(crate) fn block(self) -> Option<BlockId> {
+        self.block
+    }
+
     pub(crate) fn is_block(self) -> bool {
         self.block.is_some()
     }
@@ -435,7 +517,7 @@ impl<N: ItemTreeNode> Hash for ItemTreeId<N> {
 
 macro_rules! mod_items {
     ( $( $typ:ident in $fld:ident -> $ast:ty ),+ $(,)? ) => {
-        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
         pub enum ModItem {
             $(
                 $typ(FileItemTreeId<$typ>),
@@ -540,20 +622,21 @@ impl<N: ItemTreeNode> Index<FileItemTreeId<N>> for ItemTree {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Import {
     pub visibility: RawVisibilityId,
     pub ast_id: FileAstId<ast::Use>,
     pub use_tree: UseTree,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct UseTree {
+    #[serde(with = "hir_expand::arena_serde::idx")]
     pub index: Idx<ast::UseTree>,
     kind: UseTreeKind,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum UseTreeKind {
     /// ```
     /// use path::to::Item;
@@ -574,7 +657,7 @@ pub enum UseTreeKind {
     Prefixed { prefix: Option<Interned<ModPath>>, list: Box<[UseTree]> },
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ExternCrate {
     pub name: Name,
     pub alias: Option<ImportAlias>,
@@ -582,33 +665,34 @@ pub struct ExternCrate {
     pub ast_id: FileAstId<ast::ExternCrate>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ExternBlock {
     pub abi: Option<Interned<str>>,
     pub ast_id: FileAstId<ast::ExternBlock>,
     pub children: Box<[ModItem]>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Function {
     pub name: Name,
     pub visibility: RawVisibilityId,
     pub explicit_generic_params: Interned<GenericParams>,
     pub abi: Option<Interned<str>>,
+    #[serde(with = "hir_expand::arena_serde::idx_range")]
     pub params: IdxRange<Param>,
     pub ret_type: Interned<TypeRef>,
     pub ast_id: FileAstId<ast::Fn>,
     pub(crate) flags: FnFlags,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Param {
     Normal(Interned<TypeRef>),
     Varargs,
 }
 
 bitflags::bitflags! {
-    #[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
     pub(crate) struct FnFlags: u8 {
         const HAS_SELF_PARAM = 1 << 0;
         const HAS_BODY = 1 << 1;
@@ -620,7 +704,7 @@ bitflags::bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Struct {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -629,7 +713,7 @@ pub struct Struct {
     pub ast_id: FileAstId<ast::Struct>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Union {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -638,25 +722,28 @@ pub struct Union {
     pub ast_id: FileAstId<ast::Union>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Enum {
     pub name: Name,
     pub visibility: RawVisibilityId,
     pub generic_params: Interned<GenericParams>,
+    #[serde(with = "hir_expand::arena_serde::idx_range")]
     pub variants: IdxRange<Variant>,
     pub ast_id: FileAstId<ast::Enum>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Const {
     /// `None` for `const _: () = ();`
     pub name: Option<Name>,
     pub visibility: RawVisibilityId,
     pub type_ref: Interned<TypeRef>,
+    /// Whether the constant has a value, which is only optional for trait constants.
+    pub has_body: bool,
     pub ast_id: FileAstId<ast::Const>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Static {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -665,7 +752,7 @@ pub struct Static {
     pub ast_id: FileAstId<ast::Static>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Trait {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -676,7 +763,7 @@ pub struct Trait {
     pub ast_id: FileAstId<ast::Trait>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TraitAlias {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -684,7 +771,7 @@ pub struct TraitAlias {
     pub ast_id: FileAstId<ast::TraitAlias>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Impl {
     pub generic_params: Interned<GenericParams>,
     pub target_trait: Option<Interned<TraitRef>>,
@@ -694,7 +781,7 @@ pub struct Impl {
     pub ast_id: FileAstId<ast::Impl>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TypeAlias {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -705,7 +792,7 @@ pub struct TypeAlias {
     pub ast_id: FileAstId<ast::TypeAlias>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Mod {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -713,7 +800,7 @@ pub struct Mod {
     pub ast_id: FileAstId<ast::Module>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ModKind {
     /// `mod m { ... }`
     Inline { items: Box<[ModItem]> },
@@ -721,7 +808,7 @@ pub enum ModKind {
     Outline,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MacroCall {
     /// Path to the called macro.
     pub path: Interned<ModPath>,
@@ -729,7 +816,7 @@ pub struct MacroCall {
     pub expand_to: ExpandTo,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MacroRules {
     /// The name of the declared macro.
     pub name: Name,
@@ -737,7 +824,7 @@ pub struct MacroRules {
 }
 
 /// "Macros 2.0" macro definition.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MacroDef {
     pub name: Name,
     pub visibility: RawVisibilityId,
@@ -913,7 +1000,7 @@ impl ModItem {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AssocItem {
     Function(FileItemTreeId<Function>),
     TypeAlias(FileItemTreeId<TypeAlias>),
@@ -950,28 +1037,28 @@ impl AssocItem {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Variant {
     pub name: Name,
     pub fields: Fields,
     pub ast_id: FileAstId<ast::Variant>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Fields {
-    Record(IdxRange<Field>),
-    Tuple(IdxRange<Field>),
+    Record(#[serde(with = "hir_expand::arena_serde::idx_range")] IdxRange<Field>),
+    Tuple(#[serde(with = "hir_expand::arena_serde::idx_range")] IdxRange<Field>),
     Unit,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FieldAstId {
     Record(FileAstId<ast::RecordField>),
     Tuple(FileAstId<ast::TupleField>),
 }
 
 /// A single field of an enum variant or struct
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Field {
     pub name: Name,
     pub type_ref: Interned<TypeRef>,
@@ -0,0 +1,178 @@
+//! An on-disk cache for the `ItemTree`s of library files.
+//!
+//! Dependencies and the sysroot make up the bulk of a workspace, but they hardly ever change
+//! between two sessions. Persisting their `ItemTree`s lets a restarted server skip lowering (and
+//! in many cases parsing) those files.
+//!
+//! `ItemTree`s only refer to their own file, so unlike results made up of salsa-interned IDs (see
+//! [`crate::stable_id`]) they can be persisted as they are. An entry is keyed by the file's path,
+//! its text and the rust-analyzer version, and all of these are checked again when an entry is
+//! used. The entries are stored in a [`CacheDir`].
+//!
+//! `file_item_tree` is a salsa query, so it must neither do any I/O nor mutate the cache: the
+//! entries of all library files are read when the cache is created, and [`ItemTreeCache::persist`]
+//! writes the `ItemTree`s the database has computed since, and evicts the entries that have become
+//! stale.
+
+use std::{fmt, fs, path::Path, sync::Mutex};
+
+use base_db::{
+    salsa::debug::{DebugQueryTable, TableEntry},
+    FileId,
+};
+use hir_expand::HirFileId;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use triomphe::Arc;
+
+use crate::{
+    cache_dir::CacheDir,
+    db::{DefDatabase, FileItemTreeQuery},
+};
+
+use super::ItemTree;
+
+/// A directory of persisted `ItemTree`s, see the [module docs](self).
+pub struct ItemTreeCache {
+    dir: CacheDir,
+    version: String,
+    /// The paths of the library files whose `ItemTree`s get persisted.
+    paths: FxHashMap<FileId, String>,
+    /// The contents of the entries read from disk, by file and hash of the file's text.
+    entries: FxHashMap<(FileId, u64), Vec<u8>>,
+    /// The entries written to disk by [`Self::persist`] so far, by file and hash of the file's text.
+    written: Mutex<FxHashSet<(FileId, u64)>>,
+}
+
+impl PartialEq for ItemTreeCache {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for ItemTreeCache {}
+
+impl fmt::Debug for ItemTreeCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ItemTreeCache")
+            .field("dir", &self.dir)
+            .field("version", &self.version)
+            .field("files", &self.paths.len())
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    version: String,
+    path: String,
+    text_hash: u64,
+    item_tree: T,
+}
+
+impl ItemTreeCache {
+    /// Creates a cache for the given library files, whose entries are stored below `dir`, and reads
+    /// the entries stored for them so far. Entries written by a different `version` of
+    /// rust-analyzer are never used.
+    pub fn load(
+        dir: &Path,
+        version: &str,
+        files: impl IntoIterator<Item = (FileId, String)>,
+    ) -> ItemTreeCache {
+        let dir = CacheDir::new(dir, version);
+        let paths: FxHashMap<_, _> = files.into_iter().collect();
+
+        let files_by_path_hash: FxHashMap<_, _> =
+            paths.iter().map(|(&file_id, path)| (CacheDir::hash(path), file_id)).collect();
+        let mut entries = FxHashMap::default();
+        for (path, path_hash, text_hash) in dir.entries() {
+            let Some(&file_id) = files_by_path_hash.get(&path_hash) else { continue };
+            match fs::read(&path) {
+                Ok(contents) => {
+                    entries.insert((file_id, text_hash), contents);
+                }
+                Err(e) => tracing::debug!("failed to read {}: {e}", path.display()),
+            }
+        }
+
+        ItemTreeCache {
+            dir,
+            version: version.to_owned(),
+            paths,
+            entries,
+            written: Default::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, file_id: FileId, text: &str) -> Option<ItemTree> {
+        let path = self.paths.get(&file_id)?;
+        let text_hash = CacheDir::hash(text);
+        let contents = self.entries.get(&(file_id, text_hash))?;
+        let entry: Entry<ItemTree> = match serde_json::from_slice(contents) {
+            Ok(it) => it,
+            Err(e) => {
+                tracing::debug!("discarding invalid item tree cache entry for {path}: {e}");
+                return None;
+            }
+        };
+        if entry.version != self.version || entry.path != *path || entry.text_hash != text_hash {
+            return None;
+        }
+        Some(entry.item_tree)
+    }
+
+    /// Writes the `ItemTree`s of library files that `db` has computed and that are not on disk yet,
+    /// and removes the entries which won't be used anymore: the ones of files outside of the
+    /// workspace, the ones of older versions of the files' text, and the ones of other
+    /// rust-analyzer versions.
+    pub fn persist(&self, db: &dyn DefDatabase) {
+        let computed: Vec<TableEntry<HirFileId, Arc<ItemTree>>> =
+            FileItemTreeQuery.in_db(db).entries();
+        // The text hash of every library file whose `ItemTree` has been computed, by path hash.
+        let mut used = FxHashMap::default();
+        for file_id in computed.into_iter().filter_map(|it| it.key.file_id()) {
+            let Some(path) = self.paths.get(&file_id) else { continue };
+            let text_hash = CacheDir::hash(&db.file_text(file_id));
+            used.insert(CacheDir::hash(path), text_hash);
+            let key = (file_id, text_hash);
+            if self.entries.contains_key(&key) || self.written.lock().unwrap().contains(&key) {
+                continue;
+            }
+            // The memoized `ItemTree` may be outdated, querying it again brings it up to date.
+            let item_tree = db.file_item_tree(file_id.into());
+            let entry = Entry {
+                version: self.version.clone(),
+                path: path.clone(),
+                text_hash,
+                item_tree: &*item_tree,
+            };
+            let contents = match serde_json::to_vec(&entry) {
+                Ok(it) => it,
+                Err(e) => {
+                    tracing::debug!("failed to serialize item tree of {path}: {e}");
+                    continue;
+                }
+            };
+            match self.dir.write(CacheDir::hash(path), text_hash, &contents) {
+                Ok(()) => {
+                    self.written.lock().unwrap().insert(key);
+                }
+                Err(e) => {
+                    tracing::debug!("failed to persist item tree cache entry for {path}: {e}")
+                }
+            }
+        }
+        self.evict(&used);
+    }
+
+    /// Removes the stale entries, `used` maps the path hash of every library file whose `ItemTree`
+    /// has been computed to the hash of its current text.
+    fn evict(&self, used: &FxHashMap<u64, u64>) {
+        let known: FxHashSet<u64> = self.paths.values().map(|it| CacheDir::hash(it)).collect();
+        self.dir.evict(|path_hash, text_hash| match used.get(&path_hash) {
+            Some(&used_text_hash) => used_text_hash != text_hash,
+            None => !known.contains(&path_hash),
+        });
+    }
+}
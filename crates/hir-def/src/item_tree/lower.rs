@@ -406,8 +406,9 @@ impl<'a> Ctx<'a> {
         let name = konst.name().map(|it| it.as_name());
         let type_ref = self.lower_type_ref_opt(konst.ty());
         let visibility = self.lower_visibility(konst);
+        let has_body = konst.body().is_some();
         let ast_id = self.source_ast_id_map.ast_id(konst);
-        let res = Const { name, visibility, type_ref, ast_id };
+        let res = Const { name, visibility, type_ref, has_body, ast_id };
         id(self.data().consts.alloc(res))
     }
 
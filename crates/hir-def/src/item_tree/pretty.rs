@@ -328,7 +328,7 @@ impl Printer<'_> {
                 wln!(self, "}}");
             }
             ModItem::Const(it) => {
-                let Const { name, visibility, type_ref, ast_id: _ } = &self.tree[it];
+                let Const { name, visibility, type_ref, has_body, ast_id: _ } = &self.tree[it];
                 self.print_visibility(*visibility);
                 w!(self, "const ");
                 match name {
@@ -337,7 +337,11 @@ impl Printer<'_> {
                 }
                 w!(self, ": ");
                 self.print_type_ref(type_ref);
-                wln!(self, " = _;");
+                if *has_body {
+                    wln!(self, " = _;");
+                } else {
+                    wln!(self, ";");
+                }
             }
             ModItem::Static(it) => {
                 let Static { name, visibility, mutable, type_ref, ast_id: _ } = &self.tree[it];
@@ -1,6 +1,9 @@
 use base_db::fixture::WithFixture;
 use expect_test::{expect, Expect};
 
+use base_db::SourceDatabaseExt;
+use triomphe::Arc;
+
 use crate::{db::DefDatabase, test_db::TestDB};
 
 fn check(ra_fixture: &str, expect: Expect) {
@@ -358,3 +361,89 @@ trait Tr<'a, T: 'a>: Super where Self: for<'a> Tr<'a, T> {}
         "#]],
     )
 }
+
+#[test]
+fn persisted_item_trees() {
+    use base_db::salsa::Durability;
+
+    use super::ItemTreeCache;
+
+    let fixture = r#"
+//- /main.rs crate:main deps:lib
+struct Local;
+//- /lib.rs crate:lib new_source_root:library
+//! crate docs
+#![cfg_attr(feature = "x", deny(warnings))]
+
+pub use self::inner::{Item as Renamed, *};
+
+#[derive(Debug)]
+pub enum E<'a, T: Copy + 'a> where T: Default {
+    Unit,
+    Tuple(#[attr] &'a T, [u8; 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff]),
+    Record { f: fn(x: u8, ...) -> m!() },
+}
+
+pub(crate) mod inner {
+    pub struct Item<const N: usize = { 1 + 1 }>;
+    extern "C" {
+        static S: u8;
+    }
+}
+
+impl<T: ?Sized> Tr for Box<T> {
+    type Assoc<'b> = &'b dyn Iterator<Item = T> where T: 'b;
+    unsafe fn f(self, _: impl for<'x> Fn(&'x u8)) {}
+}
+
+macro_rules! m { () => { u8 } }
+"#;
+    let dir = std::env::temp_dir().join(format!(
+        "ra-item-tree-cache-{}-{:?}",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let version_dir = dir.join("0.0.0__test_");
+    let entries = || std::fs::read_dir(&version_dir).map_or(0, |it| it.count());
+
+    let (mut db, files) = TestDB::with_many_files(fixture);
+    let (local, lib) = (files[0], files[1]);
+    let cache = |files: Vec<_>| Arc::new(ItemTreeCache::load(&dir, "0.0.0 (test)", files));
+    let lib_cache = || cache(vec![(lib, "/lib.rs".to_owned())]);
+    db.set_item_tree_cache_with_durability(Some(lib_cache()), Durability::HIGH);
+    let lowered = db.file_item_tree(lib.into());
+    db.file_item_tree(local.into());
+    assert_eq!(entries(), 0, "item trees are only written when persisting the cache");
+    db.item_tree_cache().unwrap().persist(&db);
+    assert_eq!(entries(), 1, "only the library file should be persisted");
+
+    let (mut db, _) = TestDB::with_many_files(fixture);
+    let text = db.file_text(lib);
+    let loaded = lib_cache().get(lib, &text).expect("item tree was not persisted");
+    assert_eq!(loaded, *lowered);
+    assert_eq!(loaded.pretty_print(&db), lowered.pretty_print(&db));
+
+    // Entries are only used for the exact file they were created from.
+    assert!(lib_cache().get(lib, &format!("{text} ")).is_none());
+    assert!(lib_cache().get(local, &text).is_none());
+    assert!(cache(vec![(lib, "/other.rs".to_owned())]).get(lib, &text).is_none());
+    assert!(ItemTreeCache::load(&dir, "0.0.1", vec![(lib, "/lib.rs".to_owned())])
+        .get(lib, &text)
+        .is_none());
+
+    // Entries of older versions of a file get evicted.
+    db.set_file_text(lib, Arc::from(format!("{text} ")));
+    db.set_item_tree_cache_with_durability(Some(lib_cache()), Durability::HIGH);
+    db.file_item_tree(lib.into());
+    db.item_tree_cache().unwrap().persist(&db);
+    assert_eq!(entries(), 1);
+    assert!(lib_cache().get(lib, &text).is_none());
+    assert!(lib_cache().get(lib, &format!("{text} ")).is_some());
+
+    // So do entries of files which aren't part of the workspace anymore.
+    cache(Vec::new()).persist(&db);
+    assert_eq!(entries(), 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -3,6 +3,7 @@
 //! This attribute to tell the compiler about semi built-in std library
 //! features, such as Fn family of traits.
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 use syntax::SmolStr;
 use triomphe::Arc;
 
@@ -165,7 +166,7 @@ impl LangItems {
         if let Some(&target) = start_crate_target {
             return Some(target);
         }
-        db.crate_graph()[start_crate]
+        db.crate_data(start_crate)
             .dependencies
             .iter()
             .find_map(|dep| db.lang_item(dep.crate_id, item))
@@ -203,7 +204,7 @@ macro_rules! language_item_table {
     ) => {
 
         /// A representation of all the valid language items in Rust.
-        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum LangItem {
             $(
                 #[doc = concat!("The `", stringify!($name), "` lang item.")]
@@ -237,8 +238,8 @@ impl LangItem {
     }
 
     pub fn path(&self, db: &dyn DefDatabase, start_crate: CrateId) -> Option<Path> {
-        let t = db.lang_item(start_crate, *self)?;
-        Some(Path::LangItem(t))
+        db.lang_item(start_crate, *self)?;
+        Some(Path::LangItem(*self))
     }
 }
 
@@ -19,6 +19,7 @@ pub mod db;
 pub mod attr;
 pub mod path;
 pub mod builtin_type;
+pub mod cache_dir;
 pub mod per_ns;
 pub mod item_scope;
 
@@ -47,6 +48,8 @@ pub mod child_by_source;
 pub mod visibility;
 pub mod find_path;
 pub mod import_map;
+pub mod stable_id;
+pub mod used_imports;
 
 pub use rustc_abi as layout;
 use triomphe::Arc;
@@ -79,6 +82,7 @@ use hir_expand::{
 use item_tree::ExternBlock;
 use la_arena::Idx;
 use nameres::DefMap;
+use serde::{Deserialize, Serialize};
 use stdx::impl_from;
 use syntax::ast;
 
@@ -332,7 +336,7 @@ pub struct ExternBlockId(salsa::InternId);
 type ExternBlockLoc = ItemLoc<ExternBlock>;
 impl_intern!(ExternBlockId, ExternBlockLoc, intern_extern_block, lookup_intern_extern_block);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MacroExpander {
     Declarative,
     BuiltIn(BuiltinFnLikeExpander),
@@ -0,0 +1,45 @@
+//! Tests for `builtin_attr_macro.rs` from `hir_expand`.
+
+use expect_test::expect;
+
+use crate::macro_expansion_tests::check;
+
+#[test]
+fn test_global_allocator_expand() {
+    check(
+        r#"
+#[rustc_builtin_macro]
+pub macro global_allocator($item:item) {}
+
+struct System;
+
+#[global_allocator]
+static GLOBAL: System = System;
+"#,
+        expect![[r#"
+#[rustc_builtin_macro]
+pub macro global_allocator($item:item) {}
+
+struct System;
+
+#[global_allocator]
+static GLOBAL: System = System;
+
+static GLOBAL: System = System;
+const _: () = {
+    unsafe fn __rg_alloc(size: usize, align: usize) -> *mut u8 {
+        core::alloc::GlobalAlloc::alloc(&GLOBAL, core::alloc::Layout::from_size_align_unchecked(size, align))
+    }
+    unsafe fn __rg_dealloc(ptr:*mut u8, size: usize, align: usize) {
+        core::alloc::GlobalAlloc::dealloc(&GLOBAL, ptr, core::alloc::Layout::from_size_align_unchecked(size, align))
+    }
+    unsafe fn __rg_realloc(ptr:*mut u8, size: usize, align: usize, new_size: usize) -> *mut u8 {
+        core::alloc::GlobalAlloc::realloc(&GLOBAL, ptr, core::alloc::Layout::from_size_align_unchecked(size, align), new_size)
+    }
+    unsafe fn __rg_alloc_zeroed(size: usize, align: usize) -> *mut u8 {
+        core::alloc::GlobalAlloc::alloc_zeroed(&GLOBAL, core::alloc::Layout::from_size_align_unchecked(size, align))
+    }
+}
+;"#]],
+    );
+}
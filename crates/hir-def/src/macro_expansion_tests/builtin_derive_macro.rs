@@ -491,3 +491,102 @@ impl < > core::fmt::Debug for Command< > where {
 }"#]],
     );
 }
+
+#[test]
+fn test_const_param_ty_expand() {
+    check(
+        r#"
+//- minicore: derive, const_param_ty
+use core::marker::ConstParamTy;
+
+#[derive(ConstParamTy)]
+struct Foo<T, const N: usize>([T; N]);
+"#,
+        expect![[r#"
+use core::marker::ConstParamTy;
+
+#[derive(ConstParamTy)]
+struct Foo<T, const N: usize>([T; N]);
+
+impl <T: core::marker::ConstParamTy, const N: usize, > core::marker::ConstParamTy for Foo<T, N, > where {}"#]],
+    );
+}
+
+#[test]
+fn test_coerce_pointee_expand() {
+    check(
+        r#"
+//- minicore: coerce_pointee
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct MyPtr<'a, #[pointee] T: ?Sized, U, const N: usize>
+where
+    U: Clone,
+{
+    ptr: &'a T,
+    u: [U; N],
+}
+"#,
+        expect![[r#"
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct MyPtr<'a, #[pointee] T: ?Sized, U, const N: usize>
+where
+    U: Clone,
+{
+    ptr: &'a T,
+    u: [U; N],
+}
+
+impl <'a, T:?Sized+core::marker::Unsize<__S>, U, const N: usize, __S: ?Sized> core::ops::DispatchFromDyn<MyPtr<'a, __S, U, N, >> for MyPtr<'a, T, U, N, > where U: Clone, {}
+impl <'a, T:?Sized+core::marker::Unsize<__S>, U, const N: usize, __S: ?Sized> core::ops::CoerceUnsized<MyPtr<'a, __S, U, N, >> for MyPtr<'a, T, U, N, > where U: Clone, {}"#]],
+    );
+}
+
+#[test]
+fn test_coerce_pointee_expand_single_param() {
+    check(
+        r#"
+//- minicore: coerce_pointee
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct MyBox<T: ?Sized>(*const T);
+"#,
+        expect![[r#"
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct MyBox<T: ?Sized>(*const T);
+
+impl <T:?Sized+core::marker::Unsize<__S>, __S: ?Sized> core::ops::DispatchFromDyn<MyBox<__S, >> for MyBox<T, > {}
+impl <T:?Sized+core::marker::Unsize<__S>, __S: ?Sized> core::ops::CoerceUnsized<MyBox<__S, >> for MyBox<T, > {}"#]],
+    );
+}
+
+#[test]
+fn test_coerce_pointee_expand_ambiguous_pointee() {
+    check(
+        r#"
+//- minicore: coerce_pointee
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct Pair<T: ?Sized, U: ?Sized>(*const T, *const U);
+"#,
+        expect![[r#"
+use core::marker::CoercePointee;
+
+#[derive(CoercePointee)]
+#[repr(transparent)]
+struct Pair<T: ?Sized, U: ?Sized>(*const T, *const U);
+"#]],
+    );
+}
@@ -12,6 +12,7 @@
 mod mbe;
 mod builtin_fn_macro;
 mod builtin_derive_macro;
+mod builtin_attr_macro;
 mod proc_macros;
 
 use std::{iter, ops::Range, sync};
@@ -23,6 +24,7 @@ use hir_expand::{
     db::{DeclarativeMacroExpander, ExpandDatabase},
     AstId, InFile, MacroFile,
 };
+use rustc_hash::FxHashSet;
 use stdx::format_to;
 use syntax::{
     ast::{self, edit::IndentLevel},
@@ -224,6 +226,9 @@ pub fn identity_when_valid(_attr: TokenStream, item: TokenStream) -> TokenStream
             ModuleDefId::FunctionId(function_id) => {
                 Some(function_id.lookup(&db).source(&db).syntax().cloned())
             }
+            ModuleDefId::StaticId(static_id) => {
+                Some(static_id.lookup(&db).source(&db).syntax().cloned())
+            }
             _ => None,
         };
         if let Some(src) = src {
@@ -234,9 +239,11 @@ pub fn identity_when_valid(_attr: TokenStream, item: TokenStream) -> TokenStream
         }
     }
 
+    // A derive may expand to several impls, which are printed together with the first one.
+    let mut seen_derive_files = FxHashSet::default();
     for impl_id in def_map[local_id].scope.impls() {
         let src = impl_id.lookup(&db).source(&db);
-        if src.file_id.is_builtin_derive(&db) {
+        if src.file_id.is_builtin_derive(&db) && seen_derive_files.insert(src.file_id) {
             let pp = pretty_print_macro_expansion(src.value.syntax().clone(), None);
             format_to!(expanded_text, "\n{}", pp)
         }
@@ -342,11 +349,12 @@ impl base_db::ProcMacroExpander for IdentityWhenValidProcMacroExpander {
         subtree: &Subtree,
         _: Option<&Subtree>,
         _: &base_db::Env,
-    ) -> Result<Subtree, base_db::ProcMacroExpansionError> {
+        _: &base_db::ProcMacroSpanData,
+    ) -> Result<base_db::ProcMacroExpansion, base_db::ProcMacroExpansionError> {
         let (parse, _) =
             ::mbe::token_tree_to_syntax_node(subtree, ::mbe::TopEntryPoint::MacroItems);
         if parse.errors().is_empty() {
-            Ok(subtree.clone())
+            Ok(subtree.clone().into())
         } else {
             panic!("got invalid macro input: {:?}", parse.errors());
         }
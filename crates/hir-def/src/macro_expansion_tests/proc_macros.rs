@@ -127,3 +127,18 @@ macro_rules! id {
 "#]],
     );
 }
+
+#[test]
+fn span_information() {
+    check(
+        r#"
+//- proc_macros: span_info
+const _: &str = proc_macros::span_info!(
+    foo + bar
+);
+"#,
+        expect![[r#"
+const _: &str = "/main.rs:1:39 foo";
+"#]],
+    );
+}
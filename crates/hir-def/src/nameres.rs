@@ -71,7 +71,7 @@ use triomphe::Arc;
 
 use crate::{
     db::DefDatabase,
-    item_scope::{BuiltinShadowMode, ItemScope},
+    item_scope::{BuiltinShadowMode, ItemScope, UseTreeId},
     item_tree::{ItemTreeId, Mod, TreeId},
     nameres::{diagnostics::DefDiagnostic, path_resolution::ResolveMode},
     path::ModPath,
@@ -296,19 +296,16 @@ impl DefMap {
 
     pub(crate) fn crate_def_map_query(db: &dyn DefDatabase, krate: CrateId) -> Arc<DefMap> {
         let _p = profile::span("crate_def_map_query").detail(|| {
-            db.crate_graph()[krate].display_name.as_deref().unwrap_or_default().to_string()
+            db.crate_data(krate).display_name.as_deref().unwrap_or_default().to_string()
         });
 
-        let crate_graph = db.crate_graph();
+        let crate_data = db.crate_data(krate);
 
-        let edition = crate_graph[krate].edition;
-        let origin = ModuleOrigin::CrateRoot { definition: crate_graph[krate].root_file_id };
+        let edition = crate_data.edition;
+        let origin = ModuleOrigin::CrateRoot { definition: crate_data.root_file_id };
         let def_map = DefMap::empty(krate, edition, ModuleData::new(origin, Visibility::Public));
-        let def_map = collector::collect_defs(
-            db,
-            def_map,
-            TreeId::new(crate_graph[krate].root_file_id.into(), None),
-        );
+        let def_map =
+            collector::collect_defs(db, def_map, TreeId::new(crate_data.root_file_id.into(), None));
 
         Arc::new(def_map)
     }
@@ -485,6 +482,47 @@ impl DefMap {
         (res.resolved_def, res.segment_index)
     }
 
+    /// Like [`DefMap::resolve_path`], but returns the imports the path has been resolved through
+    /// along with the resolution.
+    pub(crate) fn resolve_path_with_imports(
+        &self,
+        db: &dyn DefDatabase,
+        original_module: LocalModuleId,
+        path: &ModPath,
+        shadow: BuiltinShadowMode,
+        expected_macro_subns: Option<MacroSubNs>,
+    ) -> (PerNs, Vec<UseTreeId>) {
+        let res = self.resolve_path_fp_with_macro(
+            db,
+            ResolveMode::Other,
+            original_module,
+            path,
+            shadow,
+            expected_macro_subns,
+        );
+        (res.resolved_def, res.imports)
+    }
+
+    /// Like [`DefMap::resolve_path_locally`], but returns the imports the path has been resolved
+    /// through along with the resolution.
+    pub(crate) fn resolve_path_locally_with_imports(
+        &self,
+        db: &dyn DefDatabase,
+        original_module: LocalModuleId,
+        path: &ModPath,
+        shadow: BuiltinShadowMode,
+    ) -> (PerNs, Vec<UseTreeId>) {
+        let res = self.resolve_path_fp_with_macro_single(
+            db,
+            ResolveMode::Other,
+            original_module,
+            path,
+            shadow,
+            None,
+        );
+        (res.resolved_def, res.imports)
+    }
+
     /// Ascends the `DefMap` hierarchy and calls `f` with every `DefMap` and containing module.
     ///
     /// If `f` returns `Some(val)`, iteration is stopped and `Some(val)` is returned. If `f` returns
@@ -11,7 +11,7 @@ use either::Either;
 use hir_expand::{
     ast_id_map::FileAstId,
     attrs::{Attr, AttrId},
-    builtin_attr_macro::find_builtin_attr,
+    builtin_attr_macro::{find_builtin_attr, keeps_test_functions},
     builtin_derive_macro::find_builtin_derive,
     builtin_fn_macro::find_builtin_macro,
     hygiene::Hygiene,
@@ -33,7 +33,7 @@ use crate::{
     attr_macro_as_call_id,
     db::DefDatabase,
     derive_macro_as_call_id,
-    item_scope::{ImportType, PerNsGlobImports},
+    item_scope::{ImportType, PerNsGlobImports, UseTreeId},
     item_tree::{
         self, ExternCrate, Fields, FileItemTreeId, ImportKind, ItemTree, ItemTreeId, ItemTreeNode,
         MacroCall, MacroDef, MacroRules, Mod, ModItem, ModKind, TreeId,
@@ -52,7 +52,7 @@ use crate::{
     tt,
     visibility::{RawVisibility, Visibility},
     AdtId, AstId, AstIdWithPath, ConstLoc, CrateRootModuleId, EnumLoc, EnumVariantId,
-    ExternBlockLoc, ExternCrateLoc, FunctionId, FunctionLoc, ImplLoc, ImportLoc, Intern,
+    ExternBlockLoc, ExternCrateLoc, FunctionId, FunctionLoc, ImplLoc, ImportId, ImportLoc, Intern,
     ItemContainerId, LocalModuleId, Macro2Id, Macro2Loc, MacroExpander, MacroId, MacroRulesId,
     MacroRulesLoc, ModuleDefId, ModuleId, ProcMacroId, ProcMacroLoc, StaticLoc, StructLoc,
     TraitAliasLoc, TraitLoc, TypeAliasLoc, UnionLoc, UnresolvedMacro,
@@ -63,11 +63,10 @@ static EXPANSION_DEPTH_LIMIT: Limit = Limit::new(128);
 static FIXED_POINT_LIMIT: Limit = Limit::new(8192);
 
 pub(super) fn collect_defs(db: &dyn DefDatabase, def_map: DefMap, tree_id: TreeId) -> DefMap {
-    let crate_graph = db.crate_graph();
+    let krate = db.crate_data(def_map.krate);
 
     let mut deps = FxHashMap::default();
     // populate external prelude and dependency list
-    let krate = &crate_graph[def_map.krate];
     for dep in &krate.dependencies {
         tracing::debug!("crate dep {:?} -> {:?}", dep.name, dep.crate_id);
 
@@ -111,6 +110,7 @@ pub(super) fn collect_defs(db: &dyn DefDatabase, def_map: DefMap, tree_id: TreeI
         proc_macros,
         from_glob_import: Default::default(),
         skip_attrs: Default::default(),
+        unresolved_cfg_accessible: Default::default(),
         is_proc_macro,
         hygienes: FxHashMap::default(),
     };
@@ -146,7 +146,7 @@ impl PartialResolvedImport {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum ImportSource {
-    Import { id: ItemTreeId<item_tree::Import>, use_tree: Idx<ast::UseTree> },
+    Import { id: ItemTreeId<item_tree::Import>, use_tree: Idx<ast::UseTree>, import: ImportId },
     ExternCrate(ItemTreeId<item_tree::ExternCrate>),
 }
 
@@ -167,6 +167,7 @@ impl Import {
         krate: CrateId,
         tree: &ItemTree,
         id: ItemTreeId<item_tree::Import>,
+        import: ImportId,
         mut cb: impl FnMut(Self),
     ) {
         let it = &tree[id.value];
@@ -181,7 +182,7 @@ impl Import {
                 kind,
                 is_prelude,
                 is_macro_use: false,
-                source: ImportSource::Import { id, use_tree: idx },
+                source: ImportSource::Import { id, use_tree: idx, import },
             });
         });
     }
@@ -205,6 +206,15 @@ impl Import {
             source: ImportSource::ExternCrate(id),
         }
     }
+
+    fn use_tree_id(&self) -> Option<UseTreeId> {
+        match self.source {
+            ImportSource::Import { use_tree, import, .. } => {
+                Some(UseTreeId { import, idx: use_tree })
+            }
+            ImportSource::ExternCrate(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -255,6 +265,9 @@ struct DefCollector<'a> {
     /// This also stores the attributes to skip when we resolve derive helpers and non-macro
     /// non-builtin attributes in general.
     skip_attrs: FxHashMap<InFile<ModItem>, AttrId>,
+    /// `#[cfg_accessible]` attributes whose path didn't resolve yet. Their items are dropped if it
+    /// still doesn't once name resolution reaches a fixed point.
+    unresolved_cfg_accessible: FxHashSet<(AstId<ast::Item>, AttrId)>,
     /// `Hygiene` cache, because `Hygiene` construction is expensive.
     ///
     /// Almost all paths should have been lowered to `ModPath` during `ItemTree` construction.
@@ -267,7 +280,7 @@ impl DefCollector<'_> {
     fn seed_with_top_level(&mut self) {
         let _p = profile::span("seed_with_top_level");
 
-        let file_id = self.db.crate_graph()[self.def_map.krate].root_file_id;
+        let file_id = self.db.crate_data(self.def_map.krate).root_file_id;
         let item_tree = self.db.file_item_tree(file_id.into());
         let attrs = item_tree.top_level_attrs(self.db, self.def_map.krate);
         let crate_data = Arc::get_mut(&mut self.def_map.data).unwrap();
@@ -479,6 +492,11 @@ impl DefCollector<'_> {
             self.unresolved_macros.iter().enumerate().find_map(|(idx, directive)| match &directive
                 .kind
             {
+                MacroDirectiveKind::Attr { ast_id, attr, .. }
+                    if self.unresolved_cfg_accessible.contains(&(ast_id.ast_id, attr.id)) =>
+                {
+                    None
+                }
                 MacroDirectiveKind::Attr { ast_id, mod_item, attr, tree } => {
                     self.def_map.diagnostics.push(DefDiagnostic::unresolved_macro_call(
                         directive.module_id,
@@ -515,7 +533,19 @@ impl DefCollector<'_> {
                 // Continue name resolution with the new data.
                 ReachedFixedPoint::No
             }
-            None => ReachedFixedPoint::Yes,
+            None => {
+                // Nothing else can make the paths of the remaining `#[cfg_accessible]` attributes
+                // resolve anymore, so their items are removed.
+                let unresolved_cfg_accessible = mem::take(&mut self.unresolved_cfg_accessible);
+                self.unresolved_macros.retain(|directive| {
+                    !matches!(
+                        &directive.kind,
+                        MacroDirectiveKind::Attr { ast_id, attr, .. }
+                            if unresolved_cfg_accessible.contains(&(ast_id.ast_id, attr.id))
+                    )
+                });
+                ReachedFixedPoint::Yes
+            }
         }
     }
 
@@ -767,7 +797,11 @@ impl DefCollector<'_> {
         res
     }
 
-    fn resolve_import(&self, module_id: LocalModuleId, import: &Import) -> PartialResolvedImport {
+    fn resolve_import(
+        &mut self,
+        module_id: LocalModuleId,
+        import: &Import,
+    ) -> PartialResolvedImport {
         let _p = profile::span("resolve_import")
             .detail(|| format!("{}", import.path.display(self.db.upcast())));
         tracing::debug!("resolving import: {:?} ({:?})", import, self.def_map.data.edition);
@@ -795,6 +829,11 @@ impl DefCollector<'_> {
                 None, // An import may resolve to any kind of macro.
             );
 
+            let this_import = import.use_tree_id();
+            self.def_map.modules[module_id]
+                .scope
+                .record_used_imports(res.imports.into_iter().filter(|&it| Some(it) != this_import));
+
             let def = res.resolved_def;
             if res.reached_fixedpoint == ReachedFixedPoint::No || def.is_none() {
                 return PartialResolvedImport::Unresolved;
@@ -826,6 +865,24 @@ impl DefCollector<'_> {
         }
     }
 
+    /// Records the imports the path of a resolved macro call has been resolved through.
+    fn record_macro_path_imports(
+        &mut self,
+        module_id: LocalModuleId,
+        path: &ModPath,
+        subns: MacroSubNs,
+    ) {
+        let res = self.def_map.resolve_path_fp_with_macro(
+            self.db,
+            ResolveMode::Other,
+            module_id,
+            path,
+            BuiltinShadowMode::Module,
+            Some(subns),
+        );
+        self.def_map.modules[module_id].scope.record_used_imports(res.imports);
+    }
+
     fn record_resolved_import(&mut self, directive: &ImportDirective) {
         let _p = profile::span("record_resolved_import");
 
@@ -874,7 +931,11 @@ impl DefCollector<'_> {
                     }
                 }
 
-                self.update(module_id, &[(name.cloned(), def)], vis, ImportType::Named);
+                let import_type = match import.use_tree_id() {
+                    Some(id) => ImportType::Import(id),
+                    None => ImportType::Named,
+                };
+                self.update(module_id, &[(name.cloned(), def)], vis, import_type);
             }
             ImportKind::Glob => {
                 tracing::debug!("glob import: {:?}", import);
@@ -901,7 +962,7 @@ impl DefCollector<'_> {
                                 .filter(|(_, res)| !res.is_none())
                                 .collect::<Vec<_>>();
 
-                            self.update(module_id, &items, vis, ImportType::Glob);
+                            self.update(module_id, &items, vis, ImportType::Glob(None));
                         } else {
                             // glob import from same crate => we do an initial
                             // import, and then need to propagate any further
@@ -931,9 +992,24 @@ impl DefCollector<'_> {
                                     )
                                 })
                                 .filter(|(_, res)| !res.is_none())
+                                .flat_map(|(n, res)| {
+                                    scope
+                                        .split_by_import(n.as_ref(), res)
+                                        .into_iter()
+                                        .map(move |(res, import)| (n.clone(), res, import))
+                                })
                                 .collect::<Vec<_>>();
 
-                            self.update(module_id, &items, vis, ImportType::Glob);
+                            // Entries coming from named imports are imported separately, so that
+                            // their usages are recorded as usages of these imports.
+                            let (items, imported): (Vec<_>, Vec<_>) =
+                                items.into_iter().partition(|(.., import)| import.is_none());
+                            let items =
+                                items.into_iter().map(|(n, res, _)| (n, res)).collect::<Vec<_>>();
+                            self.update(module_id, &items, vis, ImportType::Glob(None));
+                            for (n, res, import) in imported {
+                                self.update(module_id, &[(n, res)], vis, ImportType::Glob(import));
+                            }
                             // record the glob import in case we add further items
                             let glob = self.glob_imports.entry(m.local_id).or_default();
                             if !glob.iter().any(|(mid, _)| *mid == module_id) {
@@ -963,7 +1039,7 @@ impl DefCollector<'_> {
                                 (Some(name), res)
                             })
                             .collect::<Vec<_>>();
-                        self.update(module_id, &resolutions, vis, ImportType::Glob);
+                        self.update(module_id, &resolutions, vis, ImportType::Glob(None));
                     }
                     Some(d) => {
                         tracing::debug!("glob import {:?} from non-module/enum {:?}", import, d);
@@ -1045,7 +1121,11 @@ impl DefCollector<'_> {
 
                     if should_update {
                         changed = true;
-                        self.def_map.modules[module_id].scope.push_unnamed_trait(tr, vis);
+                        self.def_map.modules[module_id].scope.push_unnamed_trait(
+                            tr,
+                            vis,
+                            import_type.use_import(),
+                        );
                     }
                 }
             }
@@ -1072,7 +1152,7 @@ impl DefCollector<'_> {
                 glob_importing_module,
                 resolutions,
                 glob_import_vis,
-                ImportType::Glob,
+                ImportType::Glob(import_type.use_import()),
                 depth + 1,
             );
         }
@@ -1119,6 +1199,7 @@ impl DefCollector<'_> {
                         resolver_def_id,
                     );
                     if let Ok(Some(call_id)) = call_id {
+                        self.record_macro_path_imports(directive.module_id, &ast_id.path, subns);
                         push_resolved(directive, call_id);
 
                         res = ReachedFixedPoint::No;
@@ -1136,6 +1217,7 @@ impl DefCollector<'_> {
                     );
 
                     if let Ok((macro_id, def_id, call_id)) = id {
+                        self.record_macro_path_imports(directive.module_id, &ast_id.path, subns);
                         self.def_map.modules[directive.module_id].scope.set_derive_macro_invoc(
                             ast_id.ast_id,
                             call_id,
@@ -1201,6 +1283,7 @@ impl DefCollector<'_> {
                         Some(def) if def.is_attribute() => def,
                         _ => return true,
                     };
+                    self.record_macro_path_imports(directive.module_id, path, subns);
                     if matches!(
                         def,
                         MacroDefId { kind:MacroDefKind::BuiltInAttr(expander, _),.. }
@@ -1282,6 +1365,20 @@ impl DefCollector<'_> {
                         return recollect_without(self);
                     }
 
+                    if matches!(
+                        def,
+                        MacroDefId { kind: MacroDefKind::BuiltInAttr(expander, _), .. }
+                        if expander.is_cfg_accessible()
+                    ) {
+                        // `#[cfg_accessible(path)]` keeps the item only if the path resolves.
+                        // Until it does, the directive is retried like an unresolved macro.
+                        if self.is_cfg_accessible(directive.module_id, file_id, attr) {
+                            return recollect_without(self);
+                        }
+                        self.unresolved_cfg_accessible.insert((ast_id, attr.id));
+                        return true;
+                    }
+
                     // Not resolved to a derive helper or the derive attribute, so try to treat as a normal attribute.
                     let call_id =
                         attr_macro_as_call_id(self.db, file_ast_id, attr, self.def_map.krate, def);
@@ -1293,17 +1390,20 @@ impl DefCollector<'_> {
                             directive.module_id,
                             loc.kind,
                             loc.def.krate,
+                            None,
                         ));
                         return recollect_without(self);
                     }
 
-                    // Skip #[test]/#[bench] expansion, which would merely result in more memory usage
-                    // due to duplicating functions into macro expansions
+                    // Skip #[test]/#[bench] expansion where it results in the function itself,
+                    // which would merely result in more memory usage due to duplicating functions
+                    // into macro expansions
                     if matches!(
                         loc.def.kind,
                         MacroDefKind::BuiltInAttr(expander, _)
                         if expander.is_test() || expander.is_bench()
-                    ) {
+                    ) && keeps_test_functions(self.db.upcast(), self.def_map.krate)
+                    {
                         return recollect_without(self);
                     }
 
@@ -1317,6 +1417,7 @@ impl DefCollector<'_> {
                                 directive.module_id,
                                 loc.kind,
                                 loc.def.krate,
+                                None,
                             ));
 
                             return recollect_without(self);
@@ -1346,6 +1447,35 @@ impl DefCollector<'_> {
         res
     }
 
+    /// Whether the single path given to a `#[cfg_accessible]` attribute resolves to an item
+    /// visible from `module_id`.
+    fn is_cfg_accessible(&self, module_id: LocalModuleId, file_id: HirFileId, attr: &Attr) -> bool {
+        let hygiene = if file_id.is_macro() {
+            Hygiene::new(self.db.upcast(), file_id)
+        } else {
+            Hygiene::new_unhygienic()
+        };
+        let Some(mut paths) = attr.parse_path_comma_token_tree(self.db.upcast(), &hygiene) else {
+            return false;
+        };
+        let (Some(path), None) = (paths.next(), paths.next()) else { return false };
+        let res = self.def_map.resolve_path_fp_with_macro(
+            self.db,
+            ResolveMode::Other,
+            module_id,
+            &path,
+            BuiltinShadowMode::Module,
+            None,
+        );
+        res.segment_index.is_none()
+            && !res
+                .resolved_def
+                .filter_visibility(|vis| {
+                    vis.is_visible_from_def_map(self.db, &self.def_map, module_id)
+                })
+                .is_none()
+    }
+
     fn collect_macro_expansion(
         &mut self,
         module_id: LocalModuleId,
@@ -1370,7 +1500,21 @@ impl DefCollector<'_> {
                 // why is this reported here?
                 hir_expand::ExpandError::UnresolvedProcMacro(krate) => {
                     always!(krate == loc.def.krate);
-                    DefDiagnostic::unresolved_proc_macro(module_id, loc.kind.clone(), loc.def.krate)
+                    DefDiagnostic::unresolved_proc_macro(
+                        module_id,
+                        loc.kind.clone(),
+                        loc.def.krate,
+                        None,
+                    )
+                }
+                hir_expand::ExpandError::ProcMacroCrashed(krate, crash) => {
+                    always!(krate == loc.def.krate);
+                    DefDiagnostic::unresolved_proc_macro(
+                        module_id,
+                        loc.kind.clone(),
+                        loc.def.krate,
+                        Some(*crash),
+                    )
                 }
                 _ => DefDiagnostic::macro_error(module_id, loc.kind.clone(), err.to_string()),
             };
@@ -1382,6 +1526,13 @@ impl DefCollector<'_> {
             let diag = DefDiagnostic::macro_expansion_parse_error(module_id, loc.kind, &errors);
             self.def_map.diagnostics.push(diag);
         }
+        let diagnostics = self.db.proc_macro_diagnostics(macro_call_id);
+        if !diagnostics.is_empty() {
+            let loc: MacroCallLoc = self.db.lookup_intern_macro_call(macro_call_id);
+            self.def_map.diagnostics.extend(diagnostics.iter().map(|it| {
+                DefDiagnostic::proc_macro_diagnostic(module_id, loc.kind.clone(), it.clone())
+            }));
+        }
 
         // Then, fetch and process the item tree. This will reuse the expansion result from above.
         let item_tree = self.db.file_item_tree(file_id);
@@ -1474,7 +1625,7 @@ impl DefCollector<'_> {
         }
 
         for directive in &self.unresolved_imports {
-            if let ImportSource::Import { id: import, use_tree } = directive.import.source {
+            if let ImportSource::Import { id: import, use_tree, .. } = directive.import.source {
                 if matches!(
                     (directive.import.path.segments().first(), &directive.import.path.kind),
                     (Some(krate), PathKind::Plain | PathKind::Abs) if diagnosed_extern_crates.contains(krate)
@@ -1577,7 +1728,7 @@ impl ModCollector<'_, '_> {
             match item {
                 ModItem::Mod(m) => self.collect_module(m, &attrs),
                 ModItem::Import(import_id) => {
-                    let _import_id = ImportLoc {
+                    let id = ImportLoc {
                         container: module,
                         id: ItemTreeId::new(self.tree_id, import_id),
                     }
@@ -1587,6 +1738,7 @@ impl ModCollector<'_, '_> {
                         krate,
                         self.item_tree,
                         ItemTreeId::new(self.tree_id, import_id),
+                        id,
                         |import| {
                             self.def_collector.unresolved_imports.push(ImportDirective {
                                 module_id: self.module_id,
@@ -2214,6 +2366,11 @@ impl ModCollector<'_, '_> {
             // Legacy macros need to be expanded immediately, so that any macros they produce
             // are in scope.
             if let Some(val) = res {
+                self.def_collector.record_macro_path_imports(
+                    self.module_id,
+                    &mac.path,
+                    MacroSubNs::Bang,
+                );
                 self.def_collector.collect_macro_expansion(
                     self.module_id,
                     val,
@@ -2315,6 +2472,7 @@ mod tests {
             proc_macros: Ok(vec![]),
             from_glob_import: Default::default(),
             skip_attrs: Default::default(),
+            unresolved_cfg_accessible: Default::default(),
             is_proc_macro: false,
             hygienes: FxHashMap::default(),
         };
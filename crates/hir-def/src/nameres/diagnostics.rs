@@ -2,7 +2,7 @@
 
 use base_db::CrateId;
 use cfg::{CfgExpr, CfgOptions};
-use hir_expand::{attrs::AttrId, ErasedAstId, MacroCallKind};
+use hir_expand::{attrs::AttrId, proc_macro::EmittedDiagnostic, ErasedAstId, MacroCallKind};
 use la_arena::Idx;
 use syntax::{ast, SyntaxError};
 
@@ -15,29 +15,71 @@ use crate::{
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum DefDiagnosticKind {
-    UnresolvedModule { ast: AstId<ast::Module>, candidates: Box<[String]> },
+    UnresolvedModule {
+        ast: AstId<ast::Module>,
+        candidates: Box<[String]>,
+    },
 
-    UnresolvedExternCrate { ast: AstId<ast::ExternCrate> },
+    UnresolvedExternCrate {
+        ast: AstId<ast::ExternCrate>,
+    },
 
-    UnresolvedImport { id: ItemTreeId<item_tree::Import>, index: Idx<ast::UseTree> },
+    UnresolvedImport {
+        id: ItemTreeId<item_tree::Import>,
+        index: Idx<ast::UseTree>,
+    },
 
-    UnconfiguredCode { ast: ErasedAstId, cfg: CfgExpr, opts: CfgOptions },
+    UnconfiguredCode {
+        ast: ErasedAstId,
+        cfg: CfgExpr,
+        opts: CfgOptions,
+    },
 
-    UnresolvedProcMacro { ast: MacroCallKind, krate: CrateId },
+    /// `crash` is the message of the proc-macro server crash the expansion failed with, if any.
+    UnresolvedProcMacro {
+        ast: MacroCallKind,
+        krate: CrateId,
+        crash: Option<Box<str>>,
+    },
 
-    UnresolvedMacroCall { ast: MacroCallKind, path: ModPath },
+    UnresolvedMacroCall {
+        ast: MacroCallKind,
+        path: ModPath,
+    },
 
-    MacroError { ast: MacroCallKind, message: String },
+    MacroError {
+        ast: MacroCallKind,
+        message: String,
+    },
 
-    MacroExpansionParseError { ast: MacroCallKind, errors: Box<[SyntaxError]> },
+    MacroExpansionParseError {
+        ast: MacroCallKind,
+        errors: Box<[SyntaxError]>,
+    },
 
-    UnimplementedBuiltinMacro { ast: AstId<ast::Macro> },
+    ProcMacroDiagnostic {
+        ast: MacroCallKind,
+        diagnostic: EmittedDiagnostic,
+    },
 
-    InvalidDeriveTarget { ast: AstId<ast::Item>, id: usize },
+    UnimplementedBuiltinMacro {
+        ast: AstId<ast::Macro>,
+    },
 
-    MalformedDerive { ast: AstId<ast::Adt>, id: usize },
+    InvalidDeriveTarget {
+        ast: AstId<ast::Item>,
+        id: usize,
+    },
 
-    MacroDefError { ast: AstId<ast::Macro>, message: String },
+    MalformedDerive {
+        ast: AstId<ast::Adt>,
+        id: usize,
+    },
+
+    MacroDefError {
+        ast: AstId<ast::Macro>,
+        message: String,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -85,13 +127,28 @@ impl DefDiagnostic {
         Self { in_module: container, kind: DefDiagnosticKind::UnconfiguredCode { ast, cfg, opts } }
     }
 
+    pub(crate) fn proc_macro_diagnostic(
+        container: LocalModuleId,
+        ast: MacroCallKind,
+        diagnostic: EmittedDiagnostic,
+    ) -> Self {
+        Self {
+            in_module: container,
+            kind: DefDiagnosticKind::ProcMacroDiagnostic { ast, diagnostic },
+        }
+    }
+
     // FIXME: Whats the difference between this and unresolved_macro_call
     pub(crate) fn unresolved_proc_macro(
         container: LocalModuleId,
         ast: MacroCallKind,
         krate: CrateId,
+        crash: Option<Box<str>>,
     ) -> Self {
-        Self { in_module: container, kind: DefDiagnosticKind::UnresolvedProcMacro { ast, krate } }
+        Self {
+            in_module: container,
+            kind: DefDiagnosticKind::UnresolvedProcMacro { ast, krate, crash },
+        }
     }
 
     pub(crate) fn macro_error(
@@ -16,7 +16,7 @@ use triomphe::Arc;
 
 use crate::{
     db::DefDatabase,
-    item_scope::BUILTIN_SCOPE,
+    item_scope::{UseTreeId, BUILTIN_SCOPE},
     nameres::{sub_namespace_match, BlockInfo, BuiltinShadowMode, DefMap, MacroSubNs},
     path::{ModPath, PathKind},
     per_ns::PerNs,
@@ -42,6 +42,8 @@ pub(super) struct ResolvePathResult {
     pub(super) segment_index: Option<usize>,
     pub(super) reached_fixedpoint: ReachedFixedPoint,
     pub(super) krate: Option<CrateId>,
+    /// The named imports the segments of the path have been resolved through.
+    pub(super) imports: Vec<UseTreeId>,
 }
 
 impl ResolvePathResult {
@@ -55,7 +57,18 @@ impl ResolvePathResult {
         segment_index: Option<usize>,
         krate: Option<CrateId>,
     ) -> ResolvePathResult {
-        ResolvePathResult { resolved_def, segment_index, reached_fixedpoint, krate }
+        ResolvePathResult {
+            resolved_def,
+            segment_index,
+            reached_fixedpoint,
+            krate,
+            imports: Vec::new(),
+        }
+    }
+
+    fn with_imports(mut self, imports: Vec<UseTreeId>) -> ResolvePathResult {
+        self.imports = imports;
+        self
     }
 }
 
@@ -148,6 +161,10 @@ impl DefMap {
             );
 
             // Merge `new` into `result`.
+            if result.resolved_def.is_none() {
+                // The names of outer `DefMap`s are only used if the inner ones don't define them.
+                result.imports = new.imports;
+            }
             result.resolved_def = result.resolved_def.or(new.resolved_def);
             if result.reached_fixedpoint == ReachedFixedPoint::No {
                 result.reached_fixedpoint = new.reached_fixedpoint;
@@ -182,15 +199,23 @@ impl DefMap {
         shadow: BuiltinShadowMode,
         expected_macro_subns: Option<MacroSubNs>,
     ) -> ResolvePathResult {
-        let graph = db.crate_graph();
         let _cx = stdx::panic_context::enter(format!(
             "DefMap {:?} crate_name={:?} block={:?} path={}",
             self.krate,
-            graph[self.krate].display_name,
+            db.crate_data(self.krate).display_name,
             self.block,
             path.display(db.upcast())
         ));
 
+        let mut imports = Vec::new();
+        // Only the types namespace of a segment is used when it is followed by another one.
+        let used_namespaces = |def: PerNs, idx: usize| {
+            if idx + 1 < path.segments().len() {
+                PerNs { types: def.types, values: None, macros: None }
+            } else {
+                def
+            }
+        };
         let mut segments = path.segments().iter().enumerate();
         let mut curr_per_ns = match path.kind {
             PathKind::DollarCrate(krate) => {
@@ -235,13 +260,17 @@ impl DefMap {
                     if path.segments().len() == 1 { shadow } else { BuiltinShadowMode::Module };
 
                 tracing::debug!("resolving {:?} in module", segment);
-                self.resolve_name_in_module(
+                let def = self.resolve_name_in_module(
                     db,
                     original_module,
                     segment,
                     prefer_module,
                     expected_macro_subns,
-                )
+                );
+                imports.extend(
+                    self[original_module].scope.imports_of(segment, used_namespaces(def, 0)),
+                );
+                def
             }
             PathKind::Super(lvl) => {
                 let mut local_id = original_module;
@@ -342,7 +371,8 @@ impl DefMap {
                             ReachedFixedPoint::Yes,
                             s.map(|s| s + i),
                             Some(module.krate),
-                        );
+                        )
+                        .with_imports(imports);
                     }
 
                     let def_map;
@@ -354,7 +384,9 @@ impl DefMap {
                     };
 
                     // Since it is a qualified path here, it should not contains legacy macros
-                    module_data.scope.get(segment)
+                    let def = module_data.scope.get(segment);
+                    imports.extend(module_data.scope.imports_of(segment, used_namespaces(def, i)));
+                    def
                 }
                 ModuleDefId::AdtId(AdtId::EnumId(e)) => {
                     // enum variant
@@ -379,7 +411,8 @@ impl DefMap {
                                 ReachedFixedPoint::Yes,
                                 Some(i),
                                 Some(self.krate),
-                            );
+                            )
+                            .with_imports(imports);
                         }
                     }
                 }
@@ -397,7 +430,8 @@ impl DefMap {
                         ReachedFixedPoint::Yes,
                         Some(i),
                         Some(self.krate),
-                    );
+                    )
+                    .with_imports(imports);
                 }
             };
 
@@ -406,6 +440,7 @@ impl DefMap {
         }
 
         ResolvePathResult::with(curr_per_ns, ReachedFixedPoint::Yes, None, Some(self.krate))
+            .with_imports(imports)
     }
 
     fn resolve_name_in_module(
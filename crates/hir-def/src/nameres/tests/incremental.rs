@@ -1,4 +1,7 @@
-use base_db::SourceDatabaseExt;
+use base_db::{
+    CrateData, CrateGraph, CrateName, Dependency, FileId, FileLoader, FileSet, SourceDatabaseExt,
+    SourceRoot, VfsPath,
+};
 use triomphe::Arc;
 
 use crate::{db::DefDatabase, AdtId, ModuleDefId};
@@ -235,3 +238,110 @@ pub type Ty = ();
         assert_eq!(n_reparsed_files, 0);
     }
 }
+
+#[test]
+fn changing_a_tracked_file_invalidates_the_proc_macro_expansion() {
+    let (mut db, files) = TestDB::with_many_files(
+        r#"
+//- proc_macros: track_path
+//- /lib.rs crate:lib
+proc_macros::track_path!("/data.txt");
+//- /other.txt
+"#,
+    );
+    let (lib, other) = (files[0], files[1]);
+    let krate = *db.relevant_crates(lib).iter().next().unwrap();
+    let expansions = |db: &TestDB| {
+        let events = db.log_executed(|| {
+            db.crate_def_map(krate);
+        });
+        events.iter().filter(|it| it.contains("expand_proc_macro")).count()
+    };
+    assert_eq!(expansions(&db), 1);
+
+    db.set_file_text(other, Arc::from("changed"));
+    assert_eq!(expansions(&db), 0);
+
+    // The tracked file appearing in the VFS reruns the expansion.
+    let crate_graph = db.crate_graph();
+    let last_file = crate_graph.iter().map(|it| crate_graph[it].root_file_id).chain(files).max();
+    let data = FileId(last_file.unwrap().0 + 1);
+    let source_root_id = db.file_source_root(lib);
+    let source_root = db.source_root(source_root_id);
+    let mut file_set = FileSet::default();
+    for file in source_root.iter() {
+        file_set.insert(file, source_root.path_for_file(&file).unwrap().clone());
+    }
+    file_set.insert(data, VfsPath::new_virtual_path("/data.txt".to_owned()));
+    db.set_file_text(data, Arc::from("data"));
+    db.set_file_source_root(data, source_root_id);
+    db.set_source_root(source_root_id, Arc::new(SourceRoot::new_local(file_set)));
+    assert_eq!(expansions(&db), 1);
+
+    db.set_file_text(other, Arc::from("changed again"));
+    assert_eq!(expansions(&db), 0);
+
+    db.set_file_text(data, Arc::from("changed"));
+    assert_eq!(expansions(&db), 1);
+}
+
+#[test]
+fn changing_a_tracked_env_var_invalidates_the_proc_macro_expansion() {
+    let (mut db, file) = TestDB::with_single_file(
+        r#"
+//- proc_macros: track_env
+//- /lib.rs crate:lib env:STRUCT_NAME=Foo
+proc_macros::track_env!("STRUCT_NAME");
+"#,
+    );
+    let krate = *db.relevant_crates(file).iter().next().unwrap();
+    let def_map = db.crate_def_map(krate).dump(&db);
+    assert!(def_map.contains("Foo: t v"), "{def_map}");
+
+    let mut crate_graph = CrateGraph::clone(&db.workspace_crate_graph());
+    for (_, data) in crate_graph.iter_mut() {
+        data.env.set("STRUCT_NAME", "Bar".to_owned());
+    }
+    db.set_workspace_crate_graph(Arc::new(crate_graph));
+
+    let events = db.log_executed(|| {
+        db.crate_def_map(krate);
+    });
+    assert!(format!("{events:?}").contains("expand_proc_macro"), "{events:#?}");
+    let def_map = db.crate_def_map(krate).dump(&db);
+    assert!(def_map.contains("Bar: t v") && !def_map.contains("Foo"), "{def_map}");
+}
+
+#[test]
+fn adding_an_ad_hoc_crate_does_not_invalidate_the_workspace() {
+    let (mut db, files) = TestDB::with_many_files(
+        r#"
+//- /lib.rs crate:lib
+pub struct Foo;
+//- /script.rs
+use lib::Foo;
+"#,
+    );
+    let (lib_file, script) = (files[0], files[1]);
+    let lib = *db.relevant_crates(lib_file).iter().next().unwrap();
+    let events = db.log_executed(|| {
+        db.crate_def_map(lib);
+    });
+    assert!(format!("{events:?}").contains("crate_def_map"), "{events:#?}");
+
+    let mut ad_hoc = CrateData::clone(&db.crate_data(lib));
+    ad_hoc.root_file_id = script;
+    ad_hoc.display_name = None;
+    ad_hoc.dependencies = vec![Dependency::new(CrateName::new("lib").unwrap(), lib)];
+    db.set_ad_hoc_crates(Arc::from(vec![ad_hoc]));
+
+    let events = db.log_executed(|| {
+        db.crate_def_map(lib);
+    });
+    assert!(!format!("{events:?}").contains("crate_def_map"), "{events:#?}");
+
+    let ad_hoc = db.crate_graph().iter().last().unwrap();
+    assert_ne!(ad_hoc, lib);
+    let def_map = db.crate_def_map(ad_hoc).dump(&db);
+    assert!(def_map.contains("Foo: t v"), "{def_map}");
+}
@@ -1433,3 +1433,127 @@ pub mod prelude {
         "#]],
     );
 }
+
+#[test]
+fn test_functions_are_removed_from_libraries_without_cfg_test() {
+    check(
+        r#"
+//- /lib.rs crate:lib deps:core library
+use core::{bench, test};
+
+#[test]
+fn a_test() {}
+#[bench]
+fn a_bench(_: &mut ()) {}
+fn not_a_test() {}
+
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro test($item:item) {}
+#[rustc_builtin_macro]
+pub macro bench($item:item) {}
+"#,
+        expect![[r#"
+            crate
+            bench: m
+            not_a_test: v
+            test: m
+        "#]],
+    );
+}
+
+#[test]
+fn test_functions_are_kept_with_cfg_test() {
+    check(
+        r#"
+//- /lib.rs crate:lib deps:core cfg:test library
+use core::test;
+
+#[test]
+fn a_test() {}
+
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro test($item:item) {}
+"#,
+        expect![[r#"
+            crate
+            a_test: v
+            test: m
+        "#]],
+    );
+}
+
+#[test]
+fn test_functions_are_kept_in_local_crates() {
+    check(
+        r#"
+//- /lib.rs crate:lib deps:core
+use core::test;
+
+#[test]
+fn a_test() {}
+
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro test($item:item) {}
+"#,
+        expect![[r#"
+            crate
+            a_test: v
+            test: m
+        "#]],
+    );
+}
+
+#[test]
+fn cfg_accessible_keeps_items_with_accessible_paths() {
+    check(
+        r#"
+//- /lib.rs crate:lib deps:core
+use core::cfg_accessible;
+
+mod m {
+    pub fn public() {}
+    fn private() {}
+}
+
+#[cfg_accessible(m::public)]
+fn accessible() {}
+#[cfg_accessible(m::private)]
+fn private() {}
+#[cfg_accessible(m::missing)]
+fn missing() {}
+#[cfg_accessible(core::Item)]
+fn in_dependency() {}
+#[cfg_accessible(later::Item)]
+fn defined_by_macro() {}
+
+macro_rules! define_later {
+    () => { mod later { pub struct Item; } }
+}
+define_later!();
+
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro cfg_accessible($item:item) {}
+pub struct Item;
+"#,
+        expect![[r#"
+            crate
+            accessible: v
+            cfg_accessible: m
+            defined_by_macro: v
+            in_dependency: v
+            later: t
+            m: t
+
+            crate::later
+            Item: t v
+
+            crate::m
+            private: v
+            public: v
+        "#]],
+    );
+}
@@ -7,17 +7,18 @@ use std::{
 };
 
 use crate::{
-    lang_item::LangItemTarget,
+    lang_item::LangItem,
     lower::LowerCtx,
     type_ref::{ConstRef, LifetimeRef, TypeBound, TypeRef},
 };
 use hir_expand::name::Name;
 use intern::Interned;
+use serde::{Deserialize, Serialize};
 use syntax::ast;
 
 pub use hir_expand::mod_path::{path, ModPath, PathKind};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImportAlias {
     /// Unnamed alias, as in `use Foo as _;`
     Underscore,
@@ -34,7 +35,7 @@ impl Display for ImportAlias {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Path {
     /// A normal path
     Normal {
@@ -47,12 +48,13 @@ pub enum Path {
     },
     /// A link to a lang item. It is used in desugaring of things like `it?`. We can show these
     /// links via a normal path since they might be private and not accessible in the usage place.
-    LangItem(LangItemTarget),
+    /// The lang item is looked up in the crate of the resolver resolving the path.
+    LangItem(LangItem),
 }
 
 /// Generic arguments to a path segment (e.g. the `i32` in `Option<i32>`). This
 /// also includes bindings of associated types, like in `Iterator<Item = Foo>`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GenericArgs {
     pub args: Box<[GenericArg]>,
     /// This specifies whether the args contain a Self type as the first
@@ -69,7 +71,7 @@ pub struct GenericArgs {
 }
 
 /// An associated type binding like in `Iterator<Item = T>`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AssociatedTypeBinding {
     /// The name of the associated type.
     pub name: Name,
@@ -86,7 +88,7 @@ pub struct AssociatedTypeBinding {
 }
 
 /// A single generic argument.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GenericArg {
     Type(TypeRef),
     Lifetime(LifetimeRef),
@@ -15,7 +15,7 @@ use crate::{
     db::DefDatabase,
     generics::{GenericParams, TypeOrConstParamData},
     hir::{BindingId, ExprId, LabelId},
-    item_scope::{BuiltinShadowMode, BUILTIN_SCOPE},
+    item_scope::{BuiltinShadowMode, UseTreeId, BUILTIN_SCOPE},
     lang_item::LangItemTarget,
     nameres::{DefMap, MacroSubNs},
     path::{ModPath, Path, PathKind},
@@ -185,7 +185,7 @@ impl Resolver {
             Path::Normal { mod_path, .. } => mod_path,
             Path::LangItem(l) => {
                 return Some((
-                    match *l {
+                    match db.lang_item(self.krate(), *l)? {
                         LangItemTarget::Union(it) => TypeNs::AdtId(it.into()),
                         LangItemTarget::TypeAlias(it) => TypeNs::TypeAliasId(it),
                         LangItemTarget::Struct(it) => TypeNs::AdtId(it.into()),
@@ -272,7 +272,7 @@ impl Resolver {
         let path = match path {
             Path::Normal { mod_path, .. } => mod_path,
             Path::LangItem(l) => {
-                return Some(ResolveValueResult::ValueNs(match *l {
+                return Some(ResolveValueResult::ValueNs(match db.lang_item(self.krate(), *l)? {
                     LangItemTarget::Function(it) => ValueNs::FunctionId(it),
                     LangItemTarget::Static(it) => ValueNs::StaticId(it),
                     LangItemTarget::Struct(it) => ValueNs::StructId(it),
@@ -386,6 +386,28 @@ impl Resolver {
         }
     }
 
+    /// Returns the imports `path` is resolved through when looking it up in the items in scope.
+    /// Local bindings and generic parameters shadowing these items are not taken into account.
+    pub fn resolve_path_imports(&self, db: &dyn DefDatabase, path: &ModPath) -> Vec<UseTreeId> {
+        if path.kind == PathKind::Plain {
+            for scope in self.scopes() {
+                if let Scope::BlockScope(m) = scope {
+                    let (def, imports) = m.def_map.resolve_path_locally_with_imports(
+                        db,
+                        m.module_id,
+                        path,
+                        BuiltinShadowMode::Other,
+                    );
+                    if !def.is_none() {
+                        return imports;
+                    }
+                }
+            }
+        }
+        let ModuleItemMap { def_map, module_id } = &self.module_scope;
+        def_map.resolve_path_with_imports(db, *module_id, path, BuiltinShadowMode::Other, None).1
+    }
+
     pub fn resolve_path_as_macro(
         &self,
         db: &dyn DefDatabase,
@@ -543,6 +565,21 @@ impl Resolver {
             .flat_map(|params| params.where_predicates.iter())
     }
 
+    /// Like [`Self::where_predicates_in_scope`], along with the item declaring each predicate and
+    /// the index of the predicate in the item's generic parameters.
+    pub fn where_predicates_in_scope_with_origin(
+        &self,
+    ) -> impl Iterator<Item = (GenericDefId, usize, &crate::generics::WherePredicate)> {
+        self.scopes()
+            .filter_map(|scope| match scope {
+                Scope::GenericParams { def, params } => Some((*def, params)),
+                _ => None,
+            })
+            .flat_map(|(def, params)| {
+                params.where_predicates.iter().enumerate().map(move |(idx, it)| (def, idx, it))
+            })
+    }
+
     pub fn generic_def(&self) -> Option<GenericDefId> {
         self.scopes().find_map(|scope| match scope {
             Scope::GenericParams { def, .. } => Some(*def),
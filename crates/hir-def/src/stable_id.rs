@@ -0,0 +1,415 @@
+//! Representations of item IDs that stay valid across processes, building on
+//! [`hir_expand::stable_id`].
+//!
+//! Items are encoded by their (encoded) `ItemLoc`s, and the block expressions their modules and
+//! `ItemTree`s belong to by their `BlockLoc`s. Decoding interns these again, so as long as the
+//! files and crates involved haven't changed, the decoded IDs are the ones name resolution creates.
+
+use base_db::{FileId, ProcMacroKind};
+use hir_expand::{
+    proc_macro::ProcMacroExpander,
+    stable_id::{
+        ProcMacroKindDef, StableAstId, StableCrateId, StableFileId, StableIdDecoder,
+        StableIdEncoder, StableIdTables,
+    },
+    HirFileId,
+};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use syntax::ast;
+
+use crate::{
+    builtin_type::BuiltinType,
+    db::DefDatabase,
+    item_tree::{
+        Const, Enum, ExternBlock, FileItemTreeId, Function, Impl, ItemTreeId, ItemTreeNode,
+        MacroDef, MacroRules, Static, Struct, Trait, TraitAlias, TreeId, TypeAlias, Union,
+    },
+    AdtId, AssocItemLoc, BlockId, BlockLoc, CrateRootModuleId, EnumVariantId, Intern,
+    ItemContainerId, ItemLoc, LocalEnumVariantId, LocalModuleId, Lookup, Macro2Loc, MacroExpander,
+    MacroId, MacroRulesLoc, ModuleDefId, ModuleId, ProcMacroLoc,
+};
+
+/// The tables the IDs of a [`StableDefIdEncoder`] point into.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StableDefIdTables {
+    ids: StableIdTables,
+    /// Every block only refers to the blocks before it.
+    blocks: Vec<StableBlockLoc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum StableModuleDefId {
+    Module(StableModuleId),
+    Function(StableAssocItemLoc<Function>),
+    Struct(StableItemLoc<Struct>),
+    Union(StableItemLoc<Union>),
+    Enum(StableItemLoc<Enum>),
+    EnumVariant {
+        parent: StableItemLoc<Enum>,
+        #[serde(with = "hir_expand::arena_serde::idx")]
+        local_id: LocalEnumVariantId,
+    },
+    Const(StableAssocItemLoc<Const>),
+    Static(StableAssocItemLoc<Static>),
+    Trait(StableItemLoc<Trait>),
+    TraitAlias(StableItemLoc<TraitAlias>),
+    TypeAlias(StableAssocItemLoc<TypeAlias>),
+    BuiltinType(BuiltinType),
+    Macro2 {
+        loc: StableItemLoc<MacroDef>,
+        expander: MacroExpander,
+        allow_internal_unsafe: bool,
+    },
+    MacroRules {
+        loc: StableItemLoc<MacroRules>,
+        expander: MacroExpander,
+        allow_internal_unsafe: bool,
+        local_inner: bool,
+    },
+    ProcMacro {
+        krate: StableCrateId,
+        id: StableItemTreeId<Function>,
+        expander: ProcMacroExpander,
+        #[serde(with = "ProcMacroKindDef")]
+        kind: ProcMacroKind,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StableModuleId {
+    krate: StableCrateId,
+    /// An index into the blocks of the tables.
+    block: Option<u32>,
+    #[serde(with = "hir_expand::arena_serde::idx")]
+    local_id: LocalModuleId,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StableItemTreeId<N: ItemTreeNode> {
+    file_id: StableFileId,
+    /// An index into the blocks of the tables.
+    block: Option<u32>,
+    value: FileItemTreeId<N>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StableItemLoc<N: ItemTreeNode> {
+    container: StableModuleId,
+    id: StableItemTreeId<N>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StableAssocItemLoc<N: ItemTreeNode> {
+    container: StableItemContainerId,
+    id: StableItemTreeId<N>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum StableItemContainerId {
+    ExternBlock(StableItemLoc<ExternBlock>),
+    Module(StableModuleId),
+    Impl(StableItemLoc<Impl>),
+    Trait(StableItemLoc<Trait>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StableBlockLoc {
+    ast_id: StableAstId<ast::BlockExpr>,
+    module: StableModuleId,
+}
+
+/// Encodes IDs into [`StableDefIdTables`], see the [module docs](self).
+pub struct StableDefIdEncoder<'a> {
+    db: &'a dyn DefDatabase,
+    ids: StableIdEncoder<'a>,
+    blocks: Vec<StableBlockLoc>,
+    block_indices: FxHashMap<BlockId, u32>,
+}
+
+impl<'a> StableDefIdEncoder<'a> {
+    /// Creates an encoder for IDs of items in the files in `paths`.
+    pub fn new(db: &'a dyn DefDatabase, paths: &'a FxHashMap<FileId, String>) -> Self {
+        StableDefIdEncoder {
+            db,
+            ids: StableIdEncoder::new(db.upcast(), paths),
+            blocks: Vec::new(),
+            block_indices: FxHashMap::default(),
+        }
+    }
+
+    pub fn finish(self) -> StableDefIdTables {
+        StableDefIdTables { ids: self.ids.finish(), blocks: self.blocks }
+    }
+
+    pub fn file_id(&mut self, file_id: HirFileId) -> Option<StableFileId> {
+        self.ids.file_id(file_id)
+    }
+
+    /// Returns `None` if the item isn't in a file with a path, or its crate can't be identified, see
+    /// [`StableIdEncoder`].
+    pub fn module_def_id(&mut self, id: ModuleDefId) -> Option<StableModuleDefId> {
+        let db = self.db;
+        let id = match id {
+            ModuleDefId::ModuleId(it) => StableModuleDefId::Module(self.module_id(it)?),
+            ModuleDefId::FunctionId(it) => {
+                StableModuleDefId::Function(self.assoc_item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::AdtId(AdtId::StructId(it)) => {
+                StableModuleDefId::Struct(self.item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::AdtId(AdtId::UnionId(it)) => {
+                StableModuleDefId::Union(self.item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::AdtId(AdtId::EnumId(it)) => {
+                StableModuleDefId::Enum(self.item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::EnumVariantId(EnumVariantId { parent, local_id }) => {
+                StableModuleDefId::EnumVariant {
+                    parent: self.item_loc(parent.lookup(db))?,
+                    local_id,
+                }
+            }
+            ModuleDefId::ConstId(it) => {
+                StableModuleDefId::Const(self.assoc_item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::StaticId(it) => {
+                StableModuleDefId::Static(self.assoc_item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::TraitId(it) => StableModuleDefId::Trait(self.item_loc(it.lookup(db))?),
+            ModuleDefId::TraitAliasId(it) => {
+                StableModuleDefId::TraitAlias(self.item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::TypeAliasId(it) => {
+                StableModuleDefId::TypeAlias(self.assoc_item_loc(it.lookup(db))?)
+            }
+            ModuleDefId::BuiltinType(it) => StableModuleDefId::BuiltinType(it),
+            ModuleDefId::MacroId(MacroId::Macro2Id(it)) => {
+                let Macro2Loc { container, id, expander, allow_internal_unsafe } = it.lookup(db);
+                StableModuleDefId::Macro2 {
+                    loc: self.item_loc(ItemLoc { container, id })?,
+                    expander,
+                    allow_internal_unsafe,
+                }
+            }
+            ModuleDefId::MacroId(MacroId::MacroRulesId(it)) => {
+                let MacroRulesLoc { container, id, expander, allow_internal_unsafe, local_inner } =
+                    it.lookup(db);
+                StableModuleDefId::MacroRules {
+                    loc: self.item_loc(ItemLoc { container, id })?,
+                    expander,
+                    allow_internal_unsafe,
+                    local_inner,
+                }
+            }
+            ModuleDefId::MacroId(MacroId::ProcMacroId(it)) => {
+                let ProcMacroLoc { container, id, expander, kind } = it.lookup(db);
+                StableModuleDefId::ProcMacro {
+                    krate: self.ids.krate(container.krate())?,
+                    id: self.item_tree_id(id)?,
+                    expander,
+                    kind,
+                }
+            }
+        };
+        Some(id)
+    }
+
+    fn module_id(
+        &mut self,
+        ModuleId { krate, block, local_id }: ModuleId,
+    ) -> Option<StableModuleId> {
+        let block = match block {
+            Some(block) => Some(self.block_id(block)?),
+            None => None,
+        };
+        Some(StableModuleId { krate: self.ids.krate(krate)?, block, local_id })
+    }
+
+    fn block_id(&mut self, block: BlockId) -> Option<u32> {
+        if let Some(&it) = self.block_indices.get(&block) {
+            return Some(it);
+        }
+        let BlockLoc { ast_id, module } = block.lookup(self.db);
+        let loc =
+            StableBlockLoc { ast_id: self.ids.ast_id(ast_id)?, module: self.module_id(module)? };
+        let idx = self.blocks.len() as u32;
+        self.blocks.push(loc);
+        self.block_indices.insert(block, idx);
+        Some(idx)
+    }
+
+    fn item_tree_id<N: ItemTreeNode>(&mut self, id: ItemTreeId<N>) -> Option<StableItemTreeId<N>> {
+        let tree = id.tree_id();
+        let block = match tree.block() {
+            Some(block) => Some(self.block_id(block)?),
+            None => None,
+        };
+        Some(StableItemTreeId {
+            file_id: self.ids.file_id(tree.file_id())?,
+            block,
+            value: id.value,
+        })
+    }
+
+    fn item_loc<N: ItemTreeNode>(&mut self, loc: ItemLoc<N>) -> Option<StableItemLoc<N>> {
+        Some(StableItemLoc {
+            container: self.module_id(loc.container)?,
+            id: self.item_tree_id(loc.id)?,
+        })
+    }
+
+    fn assoc_item_loc<N: ItemTreeNode>(
+        &mut self,
+        loc: AssocItemLoc<N>,
+    ) -> Option<StableAssocItemLoc<N>> {
+        let container = match loc.container {
+            ItemContainerId::ExternBlockId(it) => {
+                StableItemContainerId::ExternBlock(self.item_loc(it.lookup(self.db))?)
+            }
+            ItemContainerId::ModuleId(it) => StableItemContainerId::Module(self.module_id(it)?),
+            ItemContainerId::ImplId(it) => {
+                StableItemContainerId::Impl(self.item_loc(it.lookup(self.db))?)
+            }
+            ItemContainerId::TraitId(it) => {
+                StableItemContainerId::Trait(self.item_loc(it.lookup(self.db))?)
+            }
+        };
+        Some(StableAssocItemLoc { container, id: self.item_tree_id(loc.id)? })
+    }
+}
+
+/// Decodes IDs encoded by a [`StableDefIdEncoder`], see the [module docs](self).
+pub struct StableDefIdDecoder<'a> {
+    db: &'a dyn DefDatabase,
+    ids: StableIdDecoder,
+    blocks: Vec<BlockId>,
+}
+
+impl<'a> StableDefIdDecoder<'a> {
+    /// Interns the macro calls and blocks of `tables`. Returns `None` if they refer to files or
+    /// crates that don't exist anymore, see [`StableIdDecoder::new`].
+    pub fn new(
+        db: &'a dyn DefDatabase,
+        tables: &StableDefIdTables,
+        file_ids: &FxHashMap<String, FileId>,
+    ) -> Option<Self> {
+        let ids = StableIdDecoder::new(db.upcast(), &tables.ids, file_ids)?;
+        let mut decoder = StableDefIdDecoder { db, ids, blocks: Vec::new() };
+        for StableBlockLoc { ast_id, module } in &tables.blocks {
+            let loc = BlockLoc {
+                ast_id: decoder.ids.ast_id(ast_id)?,
+                module: decoder.module_id(module)?,
+            };
+            decoder.blocks.push(loc.intern(db));
+        }
+        Some(decoder)
+    }
+
+    pub fn file_id(&self, file_id: StableFileId) -> Option<HirFileId> {
+        self.ids.file_id(file_id)
+    }
+
+    pub fn module_def_id(&self, id: &StableModuleDefId) -> Option<ModuleDefId> {
+        let db = self.db;
+        let id = match id {
+            StableModuleDefId::Module(it) => self.module_id(it)?.into(),
+            StableModuleDefId::Function(it) => self.assoc_item_loc(it)?.intern(db).into(),
+            StableModuleDefId::Struct(it) => self.item_loc(it)?.intern(db).into(),
+            StableModuleDefId::Union(it) => self.item_loc(it)?.intern(db).into(),
+            StableModuleDefId::Enum(it) => self.item_loc(it)?.intern(db).into(),
+            StableModuleDefId::EnumVariant { parent, local_id } => {
+                EnumVariantId { parent: self.item_loc(parent)?.intern(db), local_id: *local_id }
+                    .into()
+            }
+            StableModuleDefId::Const(it) => self.assoc_item_loc(it)?.intern(db).into(),
+            StableModuleDefId::Static(it) => self.assoc_item_loc(it)?.intern(db).into(),
+            StableModuleDefId::Trait(it) => self.item_loc(it)?.intern(db).into(),
+            StableModuleDefId::TraitAlias(it) => self.item_loc(it)?.intern(db).into(),
+            StableModuleDefId::TypeAlias(it) => self.assoc_item_loc(it)?.intern(db).into(),
+            StableModuleDefId::BuiltinType(it) => (*it).into(),
+            StableModuleDefId::Macro2 { loc, expander, allow_internal_unsafe } => {
+                let ItemLoc { container, id } = self.item_loc(loc)?;
+                Macro2Loc {
+                    container,
+                    id,
+                    expander: *expander,
+                    allow_internal_unsafe: *allow_internal_unsafe,
+                }
+                .intern(db)
+                .into()
+            }
+            StableModuleDefId::MacroRules { loc, expander, allow_internal_unsafe, local_inner } => {
+                let ItemLoc { container, id } = self.item_loc(loc)?;
+                MacroRulesLoc {
+                    container,
+                    id,
+                    expander: *expander,
+                    allow_internal_unsafe: *allow_internal_unsafe,
+                    local_inner: *local_inner,
+                }
+                .intern(db)
+                .into()
+            }
+            StableModuleDefId::ProcMacro { krate, id, expander, kind } => ProcMacroLoc {
+                container: CrateRootModuleId { krate: self.ids.krate(*krate)? },
+                id: self.item_tree_id(id)?,
+                expander: *expander,
+                kind: *kind,
+            }
+            .intern(db)
+            .into(),
+        };
+        Some(id)
+    }
+
+    fn module_id(
+        &self,
+        StableModuleId { krate, block, local_id }: &StableModuleId,
+    ) -> Option<ModuleId> {
+        let block = match block {
+            Some(idx) => Some(*self.blocks.get(*idx as usize)?),
+            None => None,
+        };
+        Some(ModuleId { krate: self.ids.krate(*krate)?, block, local_id: *local_id })
+    }
+
+    fn item_tree_id<N: ItemTreeNode>(&self, id: &StableItemTreeId<N>) -> Option<ItemTreeId<N>> {
+        let block = match id.block {
+            Some(idx) => Some(*self.blocks.get(idx as usize)?),
+            None => None,
+        };
+        let tree = TreeId::new(self.ids.file_id(id.file_id)?, block);
+        Some(ItemTreeId::new(tree, id.value))
+    }
+
+    fn item_loc<N: ItemTreeNode>(&self, loc: &StableItemLoc<N>) -> Option<ItemLoc<N>> {
+        Some(ItemLoc {
+            container: self.module_id(&loc.container)?,
+            id: self.item_tree_id(&loc.id)?,
+        })
+    }
+
+    fn assoc_item_loc<N: ItemTreeNode>(
+        &self,
+        loc: &StableAssocItemLoc<N>,
+    ) -> Option<AssocItemLoc<N>> {
+        let db = self.db;
+        let container = match &loc.container {
+            StableItemContainerId::ExternBlock(it) => {
+                ItemContainerId::ExternBlockId(self.item_loc(it)?.intern(db))
+            }
+            StableItemContainerId::Module(it) => ItemContainerId::ModuleId(self.module_id(it)?),
+            StableItemContainerId::Impl(it) => {
+                ItemContainerId::ImplId(self.item_loc(it)?.intern(db))
+            }
+            StableItemContainerId::Trait(it) => {
+                ItemContainerId::TraitId(self.item_loc(it)?.intern(db))
+            }
+        };
+        Some(AssocItemLoc { container, id: self.item_tree_id(&loc.id)? })
+    }
+}
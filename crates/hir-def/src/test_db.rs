@@ -5,7 +5,7 @@ use std::{fmt, panic, sync::Mutex};
 use base_db::{
     salsa::{self, Durability},
     AnchoredPath, CrateId, FileId, FileLoader, FileLoaderDelegate, FilePosition, SourceDatabase,
-    Upcast,
+    Upcast, VfsPath,
 };
 use hir_expand::{db::ExpandDatabase, InFile};
 use rustc_hash::FxHashSet;
@@ -35,6 +35,7 @@ impl Default for TestDB {
     fn default() -> Self {
         let mut this = Self { storage: Default::default(), events: Default::default() };
         this.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
+        this.set_item_tree_cache_with_durability(None, Durability::HIGH);
         this
     }
 }
@@ -78,6 +79,9 @@ impl FileLoader for TestDB {
     fn relevant_crates(&self, file_id: FileId) -> Arc<FxHashSet<CrateId>> {
         FileLoaderDelegate(self).relevant_crates(file_id)
     }
+    fn file_path(&self, file_id: FileId) -> Option<VfsPath> {
+        FileLoaderDelegate(self).file_path(file_id)
+    }
 }
 
 impl TestDB {
@@ -0,0 +1,380 @@
+//! Finds the imports used by the items of a module.
+//!
+//! Import and macro paths record the imports they have been resolved through while the `DefMap` is
+//! collected, see [`ItemScope::used_imports`]. The paths in the signatures and bodies of the items
+//! are resolved here.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use triomphe::Arc;
+
+use crate::{
+    body::scope::ScopeId,
+    data::adt::VariantData,
+    db::DefDatabase,
+    generics::{TypeOrConstParamData, WherePredicate, WherePredicateTypeTarget},
+    hir::{Expr, LiteralOrConst, Pat, Statement},
+    item_scope::{ItemScope, UseTreeId},
+    item_tree::{AssocItem, ItemTree},
+    path::{GenericArg, GenericArgs, Path},
+    resolver::{resolver_for_scope, HasResolver, ResolveValueResult, Resolver, TypeNs, ValueNs},
+    type_ref::{TypeBound, TypeRef},
+    AdtId, AssocItemId, ConstId, DefWithBodyId, EnumVariantId, FunctionId, GenericDefId, ImplId,
+    Lookup, ModuleDefId, ModuleId, StaticId, TraitId, TypeAliasId,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UsedImports {
+    /// The imports used by the paths of the module and of its descendants.
+    pub imports: FxHashSet<UseTreeId>,
+    /// The bodies of the items of the module, including the items of the block expressions in
+    /// them but not the items of its descendants. Their method calls may use trait imports too,
+    /// which is only known after type inference.
+    pub bodies: Vec<DefWithBodyId>,
+}
+
+pub(crate) fn used_imports_query(db: &dyn DefDatabase, module: ModuleId) -> Arc<UsedImports> {
+    let _p = profile::span("used_imports_query");
+    let def_map = module.def_map(db);
+    let mut collector = Collector { db, used: UsedImports::default() };
+    collector.collect_scope(&def_map[module.local_id].scope);
+    let mut used = collector.used;
+    for &child in def_map[module.local_id].children.values() {
+        used.imports.extend(db.used_imports(def_map.module_id(child)).imports.iter().copied());
+    }
+    used.imports.shrink_to_fit();
+    used.bodies.shrink_to_fit();
+    Arc::new(used)
+}
+
+struct Collector<'a> {
+    db: &'a dyn DefDatabase,
+    used: UsedImports,
+}
+
+impl Collector<'_> {
+    fn collect_scope(&mut self, scope: &ItemScope) {
+        self.used.imports.extend(scope.used_imports());
+        for def in scope.declarations() {
+            self.collect_def(def);
+        }
+        for impl_ in scope.impls() {
+            self.collect_impl(impl_);
+        }
+        for konst in scope.unnamed_consts() {
+            self.collect_const(konst);
+        }
+    }
+
+    fn collect_def(&mut self, def: ModuleDefId) {
+        match def {
+            ModuleDefId::FunctionId(it) => self.collect_function(it),
+            ModuleDefId::AdtId(adt) => {
+                let resolver = adt.resolver(self.db);
+                self.collect_generics(&resolver, adt.into());
+                match adt {
+                    AdtId::StructId(it) => {
+                        self.collect_fields(&resolver, &self.db.struct_data(it).variant_data)
+                    }
+                    AdtId::UnionId(it) => {
+                        self.collect_fields(&resolver, &self.db.union_data(it).variant_data)
+                    }
+                    AdtId::EnumId(it) => {
+                        let data = self.db.enum_data(it);
+                        for (local_id, variant) in data.variants.iter() {
+                            self.collect_fields(&resolver, &variant.variant_data);
+                            self.collect_body(EnumVariantId { parent: it, local_id }.into());
+                        }
+                    }
+                }
+            }
+            ModuleDefId::ConstId(it) => self.collect_const(it),
+            ModuleDefId::StaticId(it) => self.collect_static(it),
+            ModuleDefId::TraitId(it) => self.collect_trait(it),
+            ModuleDefId::TraitAliasId(it) => {
+                self.collect_generics(&it.resolver(self.db), it.into())
+            }
+            ModuleDefId::TypeAliasId(it) => self.collect_type_alias(it),
+            ModuleDefId::ModuleId(_)
+            | ModuleDefId::EnumVariantId(_)
+            | ModuleDefId::MacroId(_)
+            | ModuleDefId::BuiltinType(_) => {}
+        }
+    }
+
+    fn collect_assoc_item(&mut self, item: AssocItemId) {
+        match item {
+            AssocItemId::FunctionId(it) => self.collect_function(it),
+            AssocItemId::ConstId(it) => self.collect_const(it),
+            AssocItemId::TypeAliasId(it) => self.collect_type_alias(it),
+        }
+    }
+
+    fn collect_function(&mut self, func: FunctionId) {
+        let resolver = func.resolver(self.db);
+        self.collect_generics(&resolver, func.into());
+        let data = self.db.function_data(func);
+        for param in data.params.iter() {
+            self.collect_type_ref(&resolver, param);
+        }
+        self.collect_type_ref(&resolver, &data.ret_type);
+        self.collect_body(func.into());
+    }
+
+    fn collect_const(&mut self, konst: ConstId) {
+        let resolver = konst.resolver(self.db);
+        self.collect_type_ref(&resolver, &self.db.const_data(konst).type_ref);
+        self.collect_body(konst.into());
+    }
+
+    fn collect_static(&mut self, statik: StaticId) {
+        let resolver = statik.resolver(self.db);
+        self.collect_type_ref(&resolver, &self.db.static_data(statik).type_ref);
+        self.collect_body(statik.into());
+    }
+
+    fn collect_type_alias(&mut self, alias: TypeAliasId) {
+        let resolver = alias.resolver(self.db);
+        self.collect_generics(&resolver, alias.into());
+        let data = self.db.type_alias_data(alias);
+        if let Some(type_ref) = &data.type_ref {
+            self.collect_type_ref(&resolver, type_ref);
+        }
+        for bound in data.bounds.iter() {
+            self.collect_type_bound(&resolver, bound);
+        }
+    }
+
+    fn collect_trait(&mut self, trait_: TraitId) {
+        let resolver = trait_.resolver(self.db);
+        self.collect_generics(&resolver, trait_.into());
+        for &(_, item) in self.db.trait_data(trait_).items.iter() {
+            self.collect_assoc_item(item);
+        }
+        let loc = trait_.lookup(self.db);
+        let item_tree = loc.id.item_tree(self.db);
+        self.collect_assoc_macro_calls(&resolver, &item_tree, &item_tree[loc.id.value].items);
+    }
+
+    fn collect_impl(&mut self, impl_: ImplId) {
+        let resolver = impl_.resolver(self.db);
+        self.collect_generics(&resolver, impl_.into());
+        let data = self.db.impl_data(impl_);
+        self.collect_type_ref(&resolver, &data.self_ty);
+        if let Some(target_trait) = &data.target_trait {
+            self.collect_type_path(&resolver, &target_trait.path);
+        }
+        for &item in data.items.iter() {
+            self.collect_assoc_item(item);
+        }
+        let loc = impl_.lookup(self.db);
+        let item_tree = loc.id.item_tree(self.db);
+        self.collect_assoc_macro_calls(&resolver, &item_tree, &item_tree[loc.id.value].items);
+    }
+
+    fn collect_assoc_macro_calls(
+        &mut self,
+        resolver: &Resolver,
+        item_tree: &ItemTree,
+        items: &[AssocItem],
+    ) {
+        for item in items {
+            if let AssocItem::MacroCall(call) = item {
+                let imports = resolver.resolve_path_imports(self.db, &item_tree[*call].path);
+                self.used.imports.extend(imports);
+            }
+        }
+    }
+
+    fn collect_fields(&mut self, resolver: &Resolver, variant_data: &VariantData) {
+        for (_, field) in variant_data.fields().iter() {
+            self.collect_type_ref(resolver, &field.type_ref);
+        }
+    }
+
+    fn collect_generics(&mut self, resolver: &Resolver, def: GenericDefId) {
+        let params = self.db.generic_params(def);
+        for (_, param) in params.type_or_consts.iter() {
+            match param {
+                TypeOrConstParamData::TypeParamData(it) => {
+                    if let Some(default) = &it.default {
+                        self.collect_type_ref(resolver, default);
+                    }
+                }
+                TypeOrConstParamData::ConstParamData(it) => self.collect_type_ref(resolver, &it.ty),
+            }
+        }
+        for predicate in params.where_predicates.iter() {
+            match predicate {
+                WherePredicate::TypeBound { target, bound }
+                | WherePredicate::ForLifetime { target, bound, .. } => {
+                    if let WherePredicateTypeTarget::TypeRef(type_ref) = target {
+                        self.collect_type_ref(resolver, type_ref);
+                    }
+                    self.collect_type_bound(resolver, bound);
+                }
+                WherePredicate::Lifetime { .. } => {}
+            }
+        }
+    }
+
+    fn collect_body(&mut self, owner: DefWithBodyId) {
+        self.used.bodies.push(owner);
+        let body = self.db.body(owner);
+        self.used.imports.extend(body.used_imports.iter().copied());
+        for (_, def_map) in body.blocks(self.db) {
+            for (_, module) in def_map.modules() {
+                self.collect_scope(&module.scope);
+            }
+        }
+
+        let scopes = self.db.expr_scopes(owner);
+        let mut resolvers: FxHashMap<Option<ScopeId>, Resolver> = FxHashMap::default();
+        let root_resolver = resolver_for_scope(self.db, owner, None);
+        for &param in body.params.iter() {
+            body.walk_pats(param, &mut |pat| self.collect_pat(&root_resolver, &body[pat]));
+        }
+        for (expr_id, expr) in body.exprs.iter() {
+            let scope = scopes.scope_for(expr_id);
+            let resolver =
+                resolvers.entry(scope).or_insert_with(|| resolver_for_scope(self.db, owner, scope));
+            let mut pats = Vec::new();
+            match expr {
+                Expr::Path(path) => self.collect_value_path(resolver, path),
+                Expr::RecordLit { path: Some(path), .. } => self.collect_type_path(resolver, path),
+                Expr::MethodCall { generic_args: Some(generic_args), .. } => {
+                    self.collect_generic_args(resolver, generic_args)
+                }
+                Expr::Cast { type_ref, .. } => self.collect_type_ref(resolver, type_ref),
+                Expr::Closure { args, arg_types, ret_type, .. } => {
+                    for type_ref in arg_types.iter().chain(Some(ret_type)).flatten() {
+                        self.collect_type_ref(resolver, type_ref);
+                    }
+                    pats.extend(args.iter().copied());
+                }
+                Expr::Let { pat, .. } => pats.push(*pat),
+                Expr::Match { arms, .. } => pats.extend(arms.iter().map(|arm| arm.pat)),
+                Expr::Block { statements, .. }
+                | Expr::Async { statements, .. }
+                | Expr::Unsafe { statements, .. } => {
+                    for statement in statements.iter() {
+                        if let Statement::Let { pat, type_ref, .. } = statement {
+                            if let Some(type_ref) = type_ref {
+                                self.collect_type_ref(resolver, type_ref);
+                            }
+                            pats.push(*pat);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            for pat in pats {
+                body.walk_pats(pat, &mut |pat| self.collect_pat(resolver, &body[pat]));
+            }
+        }
+    }
+
+    fn collect_pat(&mut self, resolver: &Resolver, pat: &Pat) {
+        match pat {
+            Pat::Path(path) | Pat::TupleStruct { path: Some(path), .. } => {
+                self.collect_value_path(resolver, path)
+            }
+            Pat::Record { path: Some(path), .. } => self.collect_type_path(resolver, path),
+            Pat::Range { start, end } => {
+                for bound in [start, end].into_iter().flatten() {
+                    if let LiteralOrConst::Const(path) = &**bound {
+                        self.collect_value_path(resolver, path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_type_ref(&mut self, resolver: &Resolver, type_ref: &TypeRef) {
+        match type_ref {
+            TypeRef::Path(path) => self.collect_type_path(resolver, path),
+            TypeRef::Tuple(types) => {
+                types.iter().for_each(|it| self.collect_type_ref(resolver, it))
+            }
+            TypeRef::Fn(params, ..) => {
+                params.iter().for_each(|(_, it)| self.collect_type_ref(resolver, it))
+            }
+            TypeRef::RawPtr(inner, _)
+            | TypeRef::Reference(inner, ..)
+            | TypeRef::Array(inner, _)
+            | TypeRef::Slice(inner) => self.collect_type_ref(resolver, inner),
+            TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
+                bounds.iter().for_each(|it| self.collect_type_bound(resolver, it))
+            }
+            TypeRef::Never | TypeRef::Placeholder | TypeRef::Macro(_) | TypeRef::Error => {}
+        }
+    }
+
+    fn collect_type_bound(&mut self, resolver: &Resolver, bound: &TypeBound) {
+        match bound {
+            TypeBound::Path(path, _) | TypeBound::ForLifetime(_, path) => {
+                self.collect_type_path(resolver, path)
+            }
+            TypeBound::Lifetime(_) | TypeBound::Error => {}
+        }
+    }
+
+    fn collect_value_path(&mut self, resolver: &Resolver, path: &Path) {
+        let is_local = path.type_anchor().is_none()
+            && matches!(
+                resolver.resolve_path_in_value_ns(self.db, path),
+                Some(
+                    ResolveValueResult::ValueNs(
+                        ValueNs::LocalBinding(_) | ValueNs::GenericParam(_) | ValueNs::ImplSelf(_)
+                    ) | ResolveValueResult::Partial(
+                        TypeNs::GenericParam(_) | TypeNs::SelfType(_) | TypeNs::AdtSelfType(_),
+                        _
+                    )
+                )
+            );
+        self.collect_path(resolver, path, is_local);
+    }
+
+    fn collect_type_path(&mut self, resolver: &Resolver, path: &Path) {
+        let is_local = path.type_anchor().is_none()
+            && matches!(
+                resolver.resolve_path_in_type_ns(self.db, path),
+                Some((TypeNs::GenericParam(_) | TypeNs::SelfType(_) | TypeNs::AdtSelfType(_), _))
+            );
+        self.collect_path(resolver, path, is_local);
+    }
+
+    /// Records the imports `path` is resolved through, unless it resolves to a local binding, a
+    /// generic parameter or `Self`.
+    fn collect_path(&mut self, resolver: &Resolver, path: &Path, is_local: bool) {
+        if let Some(type_anchor) = path.type_anchor() {
+            self.collect_type_ref(resolver, type_anchor);
+        } else if let (Some(mod_path), false) = (path.mod_path(), is_local) {
+            self.used.imports.extend(resolver.resolve_path_imports(self.db, mod_path));
+        }
+        for segment in path.segments().iter() {
+            if let Some(generic_args) = segment.args_and_bindings {
+                self.collect_generic_args(resolver, generic_args);
+            }
+        }
+    }
+
+    fn collect_generic_args(&mut self, resolver: &Resolver, generic_args: &GenericArgs) {
+        for arg in generic_args.args.iter() {
+            if let GenericArg::Type(type_ref) = arg {
+                self.collect_type_ref(resolver, type_ref);
+            }
+        }
+        for binding in generic_args.bindings.iter() {
+            if let Some(args) = &binding.args {
+                self.collect_generic_args(resolver, args);
+            }
+            if let Some(type_ref) = &binding.type_ref {
+                self.collect_type_ref(resolver, type_ref);
+            }
+            for bound in binding.bounds.iter() {
+                self.collect_type_bound(resolver, bound);
+            }
+        }
+    }
+}
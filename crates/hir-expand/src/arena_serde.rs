@@ -0,0 +1,55 @@
+//! `serde` support for `la_arena` types, to be used with `#[serde(with = "...")]`.
+//!
+//! Indices are serialized as their raw `u32` value, so they only stay meaningful together with the
+//! arena they point into.
+
+use la_arena::{Arena, Idx, IdxRange, RawIdx};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub mod idx {
+    use super::*;
+
+    pub fn serialize<T, S: Serializer>(idx: &Idx<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        u32::from(idx.into_raw()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D: Deserializer<'de>>(deserializer: D) -> Result<Idx<T>, D::Error> {
+        u32::deserialize(deserializer).map(|raw| Idx::from_raw(RawIdx::from(raw)))
+    }
+}
+
+pub mod idx_range {
+    use super::*;
+
+    pub fn serialize<T, S: Serializer>(
+        range: &IdxRange<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (u32::from(range.start().into_raw()), u32::from(range.end().into_raw()))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<IdxRange<T>, D::Error> {
+        let (start, end) = <(u32, u32)>::deserialize(deserializer)?;
+        Ok(IdxRange::new(Idx::from_raw(RawIdx::from(start))..Idx::from_raw(RawIdx::from(end))))
+    }
+}
+
+pub mod arena {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        arena: &Arena<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(arena.values())
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arena<T>, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Arena::from_iter)
+    }
+}
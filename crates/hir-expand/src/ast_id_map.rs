@@ -15,6 +15,7 @@ use std::{
 use la_arena::{Arena, Idx};
 use profile::Count;
 use rustc_hash::FxHasher;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use syntax::{ast, AstNode, AstPtr, SyntaxNode, SyntaxNodePtr};
 
 /// `AstId` points to an AST node in a specific file.
@@ -48,6 +49,19 @@ impl<N: AstIdNode> fmt::Debug for FileAstId<N> {
     }
 }
 
+impl<N: AstIdNode> Serialize for FileAstId<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::arena_serde::idx::serialize(&self.raw, serializer)
+    }
+}
+
+impl<'de, N: AstIdNode> Deserialize<'de> for FileAstId<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = crate::arena_serde::idx::deserialize(deserializer)?;
+        Ok(FileAstId { raw, covariant: PhantomData })
+    }
+}
+
 impl<N: AstIdNode> FileAstId<N> {
     // Can't make this a From implementation because of coherence
     pub fn upcast<M: AstIdNode>(self) -> FileAstId<M>
@@ -6,6 +6,7 @@ use cfg::CfgExpr;
 use either::Either;
 use intern::Interned;
 use mbe::{syntax_node_to_token_tree, DelimiterKind, Punct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::{smallvec, SmallVec};
 use syntax::{ast, match_ast, AstNode, SmolStr, SyntaxNode};
 use triomphe::Arc;
@@ -36,6 +37,19 @@ impl ops::Deref for RawAttrs {
     }
 }
 
+impl Serialize for RawAttrs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawAttrs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = Vec::<Attr>::deserialize(deserializer)?;
+        Ok(Self { entries: if entries.is_empty() { None } else { Some(Arc::from(entries)) } })
+    }
+}
+
 impl RawAttrs {
     pub const EMPTY: Self = Self { entries: None };
 
@@ -99,7 +113,7 @@ impl RawAttrs {
             return self;
         }
 
-        let crate_graph = db.crate_graph();
+        let crate_data = db.crate_data(krate);
         let new_attrs = Arc::from(
             self.iter()
                 .flat_map(|attr| -> SmallVec<[_; 1]> {
@@ -131,7 +145,7 @@ impl RawAttrs {
                         },
                     );
 
-                    let cfg_options = &crate_graph[krate].cfg_options;
+                    let cfg_options = &crate_data.cfg_options;
                     let cfg = Subtree { delimiter: subtree.delimiter, token_trees: cfg.to_vec() };
                     let cfg = CfgExpr::parse(&cfg);
                     if cfg_options.check(&cfg) == Some(false) {
@@ -150,7 +164,7 @@ impl RawAttrs {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AttrId {
     id: u32,
 }
@@ -180,14 +194,14 @@ impl AttrId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Attr {
     pub id: AttrId,
     pub path: Interned<ModPath>,
     pub input: Option<Interned<AttrInput>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AttrInput {
     /// `#[attr = "string"]`
     Literal(SmolStr),
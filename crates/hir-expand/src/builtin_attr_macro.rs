@@ -1,10 +1,18 @@
 //! Builtin attributes.
 
-use crate::{db::ExpandDatabase, name, tt, ExpandResult, MacroCallId, MacroCallKind};
+use base_db::CrateId;
+use cfg::{CfgAtom, CfgExpr};
+use serde::{Deserialize, Serialize};
+use syntax::ast::{self, HasName};
+
+use crate::{
+    builtin_derive_macro::find_builtin_crate, db::ExpandDatabase, name, quote, tt, ExpandError,
+    ExpandResult, MacroCallId, MacroCallKind,
+};
 
 macro_rules! register_builtin {
     ( $(($name:ident, $variant:ident) => $expand:ident),* ) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum BuiltinAttrExpander {
             $($variant),*
         }
@@ -34,6 +42,9 @@ macro_rules! register_builtin {
 }
 
 impl BuiltinAttrExpander {
+    pub fn is_cfg_accessible(self) -> bool {
+        matches!(self, BuiltinAttrExpander::CfgAccessible)
+    }
     pub fn is_derive(self) -> bool {
         matches!(self, BuiltinAttrExpander::Derive | BuiltinAttrExpander::DeriveConst)
     }
@@ -46,14 +57,16 @@ impl BuiltinAttrExpander {
 }
 
 register_builtin! {
-    (bench, Bench) => dummy_attr_expand,
+    (bench, Bench) => bench_attr_expand,
+    // Whether the item is kept depends on name resolution, so this is decided in the def collector,
+    // which never expands the attribute. If the item is there, expanding to it is accurate.
     (cfg_accessible, CfgAccessible) => dummy_attr_expand,
     (cfg_eval, CfgEval) => dummy_attr_expand,
     (derive, Derive) => derive_attr_expand,
     // derive const is equivalent to derive for our proposes.
     (derive_const, DeriveConst) => derive_attr_expand,
-    (global_allocator, GlobalAllocator) => dummy_attr_expand,
-    (test, Test) => dummy_attr_expand,
+    (global_allocator, GlobalAllocator) => global_allocator_attr_expand,
+    (test, Test) => test_attr_expand,
     (test_case, TestCase) => dummy_attr_expand
 }
 
@@ -69,6 +82,130 @@ fn dummy_attr_expand(
     ExpandResult::ok(tt.clone())
 }
 
+/// Whether `#[test]` and `#[bench]` functions are kept in the given crate.
+///
+/// rustc drops them unless the crate is built by the test harness, i.e. with `cfg(test)`. Local
+/// crates are always analysed as if they were, even if a project model didn't enable the cfg (like
+/// our test fixtures), so that their tests can be navigated to and run.
+pub fn keeps_test_functions(db: &dyn ExpandDatabase, krate: CrateId) -> bool {
+    let krate = db.crate_data(krate);
+    krate.origin.is_local()
+        || krate.cfg_options.check(&CfgExpr::Atom(CfgAtom::Flag("test".into()))) == Some(true)
+}
+
+fn test_attr_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> ExpandResult<tt::Subtree> {
+    test_or_bench_expand(db, id, tt, "test", |fn_| match fn_.param_list() {
+        Some(params) if params.params().next().is_some() || params.self_param().is_some() => {
+            Some("functions used as tests can not have any arguments")
+        }
+        _ => None,
+    })
+}
+
+fn bench_attr_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> ExpandResult<tt::Subtree> {
+    test_or_bench_expand(db, id, tt, "bench", |fn_| {
+        let params = fn_.param_list();
+        let n_params = params.as_ref().map_or(0, |it| it.params().count());
+        let has_self = params.map_or(false, |it| it.self_param().is_some());
+        (n_params != 1 || has_self)
+            .then_some("functions used as benches must have exactly one argument")
+    })
+}
+
+/// Expands to the annotated function if [`keeps_test_functions`], and to nothing otherwise. The test
+/// descriptor rustc emits alongside it is omitted, as it refers to the `test` crate which isn't part
+/// of the crate graph.
+fn test_or_bench_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+    attr_name: &str,
+    check_signature: impl FnOnce(&ast::Fn) -> Option<&'static str>,
+) -> ExpandResult<tt::Subtree> {
+    let loc = db.lookup_intern_macro_call(id);
+    if !keeps_test_functions(db, loc.krate) {
+        return ExpandResult::ok(tt::Subtree::empty());
+    }
+    let err = match annotated_item(db, &loc.kind) {
+        Some(ast::Item::Fn(fn_)) => check_signature(&fn_).map(ExpandError::other),
+        _ => Some(ExpandError::other(format!(
+            "the `#[{attr_name}]` attribute may only be used on a non-associated function"
+        ))),
+    };
+    ExpandResult { value: tt.clone(), err }
+}
+
+/// Expands `#[global_allocator] static A: Alloc = ...;` to the static, followed by the allocator
+/// shims that forward to it through `GlobalAlloc`, which makes sure `Alloc` implements the trait.
+fn global_allocator_attr_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> ExpandResult<tt::Subtree> {
+    let loc = db.lookup_intern_macro_call(id);
+    let name = match annotated_item(db, &loc.kind) {
+        Some(ast::Item::Static(static_)) => static_.name(),
+        _ => None,
+    };
+    let Some(name) = name else {
+        return ExpandResult::new(tt.clone(), ExpandError::other("allocators must be statics"));
+    };
+    let name = tt::Ident { text: name.text().into(), span: tt::TokenId::unspecified() };
+    let krate = find_builtin_crate(db, id);
+    let call = |method: &str, before: tt::Subtree, after: tt::Subtree| {
+        let (krate, krate2, name) = (krate.clone(), krate.clone(), name.clone());
+        let layout = quote! { #krate2::alloc::Layout::from_size_align_unchecked(size, align) };
+        let method = tt::Ident { text: method.into(), span: tt::TokenId::unspecified() };
+        quote! { #krate::alloc::GlobalAlloc::#method(&#name, #before #layout #after) }
+    };
+    let alloc = call("alloc", quote! {}, quote! {});
+    let dealloc = call("dealloc", quote! { ptr, }, quote! {});
+    let realloc = call("realloc", quote! { ptr, }, quote! { , new_size });
+    let alloc_zeroed = call("alloc_zeroed", quote! {}, quote! {});
+    let ptr = || {
+        let star = tt::Punct {
+            char: '*',
+            spacing: ::tt::Spacing::Alone,
+            span: tt::TokenId::unspecified(),
+        };
+        quote! { #star mut u8 }
+    };
+    let (ptr1, ptr2, ptr3, ptr4, ptr5) = (ptr(), ptr(), ptr(), ptr(), ptr());
+    let item = tt.clone();
+    ExpandResult::ok(quote! {
+        #item
+        const _: () = {
+            unsafe fn __rg_alloc(size: usize, align: usize) -> #ptr1 {
+                #alloc
+            }
+            unsafe fn __rg_dealloc(ptr: #ptr2, size: usize, align: usize) {
+                #dealloc
+            }
+            unsafe fn __rg_realloc(ptr: #ptr3, size: usize, align: usize, new_size: usize) -> #ptr4 {
+                #realloc
+            }
+            unsafe fn __rg_alloc_zeroed(size: usize, align: usize) -> #ptr5 {
+                #alloc_zeroed
+            }
+        };
+    })
+}
+
+fn annotated_item(db: &dyn ExpandDatabase, kind: &MacroCallKind) -> Option<ast::Item> {
+    match kind {
+        MacroCallKind::Attr { ast_id, .. } => Some(ast_id.to_node(db)),
+        _ => None,
+    }
+}
+
 /// We generate a very specific expansion here, as we do not actually expand the `#[derive]` attribute
 /// itself in name res, but we do want to expand it to something for the IDE layer, so that the input
 /// derive attributes can be downmapped, and resolved as proper paths.
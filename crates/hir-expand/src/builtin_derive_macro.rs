@@ -5,6 +5,7 @@ use base_db::{CrateOrigin, LangCrateOrigin};
 use itertools::izip;
 use mbe::TokenMap;
 use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
 use stdx::never;
 use tracing::debug;
 
@@ -18,7 +19,7 @@ use crate::{db::ExpandDatabase, name, quote, ExpandError, ExpandResult, MacroCal
 
 macro_rules! register_builtin {
     ( $($trait:ident => $expand:ident),* ) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum BuiltinDeriveExpander {
             $($trait),*
         }
@@ -57,7 +58,9 @@ register_builtin! {
     Ord => ord_expand,
     PartialOrd => partial_ord_expand,
     Eq => eq_expand,
-    PartialEq => partial_eq_expand
+    PartialEq => partial_eq_expand,
+    ConstParamTy => const_param_ty_expand,
+    CoercePointee => coerce_pointee_expand
 }
 
 pub fn find_builtin_derive(ident: &name::Name) -> Option<BuiltinDeriveExpander> {
@@ -373,13 +376,12 @@ fn expand_simple_derive(
     ExpandResult::ok(expanded)
 }
 
-fn find_builtin_crate(db: &dyn ExpandDatabase, id: MacroCallId) -> tt::TokenTree {
+pub(crate) fn find_builtin_crate(db: &dyn ExpandDatabase, id: MacroCallId) -> tt::TokenTree {
     // FIXME: make hygiene works for builtin derive macro
     // such that $crate can be used here.
-    let cg = db.crate_graph();
     let krate = db.lookup_intern_macro_call(id).krate;
 
-    let tt = if matches!(cg[krate].origin, CrateOrigin::Lang(LangCrateOrigin::Core)) {
+    let tt = if matches!(db.crate_data(krate).origin, CrateOrigin::Lang(LangCrateOrigin::Core)) {
         cov_mark::hit!(test_copy_expand_in_core);
         quote! { crate }
     } else {
@@ -842,3 +844,139 @@ fn partial_ord_expand(
         }
     })
 }
+
+fn const_param_ty_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &ast::Adt,
+    tm: &TokenMap,
+) -> ExpandResult<tt::Subtree> {
+    let krate = find_builtin_crate(db, id);
+    expand_simple_derive(tt, tm, quote! { #krate::marker::ConstParamTy }, |_| quote! {})
+}
+
+/// Given a smart pointer like
+///
+/// ```ignore (only-for-syntax-highlight)
+/// #[derive(CoercePointee)]
+/// #[repr(transparent)]
+/// struct MyPtr<'a, #[pointee] T: ?Sized, U> where U: Clone { ... }
+/// ```
+///
+/// creates the impls that make it coercible to (and dispatchable on) unsized pointees:
+///
+/// ```ignore (only-for-syntax-highlight)
+/// impl<'a, T: ?Sized + Unsize<__S>, U, __S: ?Sized> DispatchFromDyn<MyPtr<'a, __S, U>>
+///     for MyPtr<'a, T, U> where U: Clone {}
+/// impl<'a, T: ?Sized + Unsize<__S>, U, __S: ?Sized> CoerceUnsized<MyPtr<'a, __S, U>>
+///     for MyPtr<'a, T, U> where U: Clone {}
+/// ```
+///
+/// The pointee is the type parameter marked `#[pointee]`, or the only type parameter if there's
+/// just one.
+fn coerce_pointee_expand(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    tt: &ast::Adt,
+    tm: &TokenMap,
+) -> ExpandResult<tt::Subtree> {
+    let ast::Adt::Struct(strukt) = tt else {
+        return ExpandResult::new(
+            tt::Subtree::empty(),
+            ExpandError::other("`CoercePointee` can only be derived on `struct`s"),
+        );
+    };
+    let name = match name_to_token(tm, strukt.name()) {
+        Ok(it) => it,
+        Err(e) => return ExpandResult::new(tt::Subtree::empty(), e),
+    };
+    let generic_params: Vec<_> =
+        strukt.generic_param_list().into_iter().flat_map(|it| it.generic_params()).collect();
+    let type_params: Vec<_> = generic_params
+        .iter()
+        .filter_map(|it| match it {
+            ast::GenericParam::TypeParam(it) => Some(it),
+            _ => None,
+        })
+        .collect();
+    let mut marked = type_params
+        .iter()
+        .filter(|it| it.attrs().any(|it| it.simple_name() == Some("pointee".into())));
+    let pointee = match (marked.next(), marked.next()) {
+        (Some(it), None) => it,
+        (None, None) if type_params.len() == 1 => &type_params[0],
+        _ => {
+            return ExpandResult::new(
+                tt::Subtree::empty(),
+                ExpandError::other(
+                    "exactly one generic type parameter must be marked as `#[pointee]`",
+                ),
+            )
+        }
+    };
+
+    let krate = find_builtin_crate(db, id);
+    let target = tt::Ident { text: "__S".into(), span: tt::TokenId::unspecified() };
+    let mut params = Vec::new();
+    let mut self_args = Vec::new();
+    let mut target_args = Vec::new();
+    let to_tt = |node: &dyn AstNode| mbe::syntax_node_to_token_tree(node.syntax()).0;
+    for param in &generic_params {
+        let (param, self_arg, target_arg) = match param {
+            ast::GenericParam::LifetimeParam(it) => {
+                let lifetime = it.lifetime().map_or_else(tt::Subtree::empty, |it| to_tt(&it));
+                (to_tt(it), lifetime.clone(), lifetime)
+            }
+            ast::GenericParam::TypeParam(it) => {
+                let name = it.name().map_or_else(tt::Subtree::empty, |it| to_tt(&it));
+                let bounds = it.type_bound_list().map(|it| to_tt(&it));
+                let (param_name, target_arg) = (name.clone(), name.clone());
+                if it == *pointee {
+                    let (krate, unsize_target, target) =
+                        (krate.clone(), target.clone(), target.clone());
+                    let unsize = quote! { #krate::marker::Unsize<#unsize_target> };
+                    let param = match bounds {
+                        Some(bounds) => quote! { #param_name : #bounds + #unsize },
+                        None => quote! { #param_name : #unsize },
+                    };
+                    (param, name, quote! { #target })
+                } else {
+                    let param = match bounds {
+                        Some(bounds) => quote! { #param_name : #bounds },
+                        None => param_name,
+                    };
+                    (param, name, target_arg)
+                }
+            }
+            ast::GenericParam::ConstParam(it) => {
+                let name = it.name().map_or_else(tt::Subtree::empty, |it| to_tt(&it));
+                let ty = it.ty().map_or_else(tt::Subtree::empty, |it| to_tt(&it));
+                let param_name = name.clone();
+                (quote! { const #param_name : #ty }, name.clone(), name)
+            }
+        };
+        params.push(quote! { #param , });
+        self_args.push(quote! { #self_arg , });
+        target_args.push(quote! { #target_arg , });
+    }
+    params.push(quote! { #target : ?Sized });
+    let where_clause = strukt
+        .where_clause()
+        .map(|it| mbe::syntax_node_to_token_tree(it.syntax()).0)
+        .unwrap_or_else(tt::Subtree::empty);
+
+    let impl_for = |trait_name: &str| {
+        let trait_name = tt::Ident { text: trait_name.into(), span: tt::TokenId::unspecified() };
+        let (krate, name, name_) = (krate.clone(), name.clone(), name.clone());
+        let (params, self_args, target_args) =
+            (params.clone(), self_args.clone(), target_args.clone());
+        let where_clause = where_clause.clone();
+        quote! {
+            impl < ##params > #krate::ops::#trait_name<#name < ##target_args > >
+                for #name_ < ##self_args > #where_clause {}
+        }
+    };
+    let dispatch_from_dyn = impl_for("DispatchFromDyn");
+    let coerce_unsized = impl_for("CoerceUnsized");
+    ExpandResult::ok(quote! { #dispatch_from_dyn #coerce_unsized })
+}
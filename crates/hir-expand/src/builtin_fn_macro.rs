@@ -8,6 +8,7 @@ use cfg::CfgExpr;
 use either::Either;
 use mbe::{parse_exprs_with_sep, parse_to_token_tree, TokenMap};
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 use syntax::{
     ast::{self, AstToken},
     SmolStr,
@@ -20,12 +21,12 @@ use crate::{
 
 macro_rules! register_builtin {
     ( LAZY: $(($name:ident, $kind: ident) => $expand:ident),* , EAGER: $(($e_name:ident, $e_kind: ident) => $e_expand:ident),*  ) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum BuiltinFnLikeExpander {
             $($kind),*
         }
 
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum EagerExpander {
             $($e_kind),*
         }
@@ -438,7 +439,7 @@ fn cfg_expand(
 ) -> ExpandResult<tt::Subtree> {
     let loc = db.lookup_intern_macro_call(id);
     let expr = CfgExpr::parse(tt);
-    let enabled = db.crate_graph()[loc.krate].cfg_options.check(&expr) != Some(false);
+    let enabled = db.crate_data(loc.krate).cfg_options.check(&expr) != Some(false);
     let expanded = if enabled { quote!(true) } else { quote!(false) };
     ExpandResult::ok(expanded)
 }
@@ -450,7 +451,7 @@ fn panic_expand(
 ) -> ExpandResult<tt::Subtree> {
     let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
     // Expand to a macro call `$crate::panic::panic_{edition}`
-    let mut call = if db.crate_graph()[loc.krate].edition >= Edition::Edition2021 {
+    let mut call = if db.crate_data(loc.krate).edition >= Edition::Edition2021 {
         quote!(#DOLLAR_CRATE::panic::panic_2021!)
     } else {
         quote!(#DOLLAR_CRATE::panic::panic_2015!)
@@ -468,7 +469,7 @@ fn unreachable_expand(
 ) -> ExpandResult<tt::Subtree> {
     let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
     // Expand to a macro call `$crate::panic::unreachable_{edition}`
-    let mut call = if db.crate_graph()[loc.krate].edition >= Edition::Edition2021 {
+    let mut call = if db.crate_data(loc.krate).edition >= Edition::Edition2021 {
         quote!(#DOLLAR_CRATE::panic::unreachable_2021!)
     } else {
         quote!(#DOLLAR_CRATE::panic::unreachable_2015!)
@@ -752,7 +753,7 @@ fn include_str_expand(
 
 fn get_env_inner(db: &dyn ExpandDatabase, arg_id: MacroCallId, key: &str) -> Option<String> {
     let krate = db.lookup_intern_macro_call(arg_id).krate;
-    db.crate_graph()[krate].env.get(key)
+    db.crate_data(krate).env.get(key)
 }
 
 fn env_expand(
@@ -1,22 +1,25 @@
 //! Defines database & queries for macro expansion.
 
-use base_db::{salsa, CrateId, Edition, SourceDatabase};
+use base_db::{
+    salsa, AnchoredPath, CrateId, Edition, ProcMacroExpansion, ProcMacroExpansionDiagnostic,
+    ProcMacroSpanData, ProcMacroSpanLocation, SourceDatabase,
+};
 use either::Either;
 use limit::Limit;
 use mbe::syntax_node_to_token_tree;
 use rustc_hash::FxHashSet;
 use syntax::{
     ast::{self, HasAttrs, HasDocComments},
-    AstNode, GreenNode, Parse, SyntaxError, SyntaxNode, SyntaxToken, T,
+    AstNode, GreenNode, Parse, SyntaxError, SyntaxNode, SyntaxToken, TextRange, TextSize, T,
 };
 use triomphe::Arc;
 
 use crate::{
     ast_id_map::AstIdMap, builtin_attr_macro::pseudo_derive_attr_expansion,
-    builtin_fn_macro::EagerExpander, fixup, hygiene::HygieneFrame, tt, AstId, BuiltinAttrExpander,
-    BuiltinDeriveExpander, BuiltinFnLikeExpander, EagerCallInfo, ExpandError, ExpandResult,
-    ExpandTo, HirFileId, HirFileIdRepr, MacroCallId, MacroCallKind, MacroCallLoc, MacroDefId,
-    MacroDefKind, MacroFile, ProcMacroExpander,
+    builtin_fn_macro::EagerExpander, fixup, hygiene::HygieneFrame, proc_macro::EmittedDiagnostic,
+    tt, AstId, BuiltinAttrExpander, BuiltinDeriveExpander, BuiltinFnLikeExpander, EagerCallInfo,
+    ExpandError, ExpandResult, ExpandTo, HirFileId, HirFileIdRepr, MacroCallId, MacroCallKind,
+    MacroCallLoc, MacroDefId, MacroDefKind, MacroFile, ProcMacroExpander,
 };
 
 /// Total limit on the number of tokens produced by any macro invocation.
@@ -155,12 +158,20 @@ pub trait ExpandDatabase: SourceDatabase {
     /// proc macros, since they are not deterministic in general, and
     /// non-determinism breaks salsa in a very, very, very bad way.
     /// @edwin0cheng heroically debugged this once! See #4315 for details
-    fn expand_proc_macro(&self, call: MacroCallId) -> ExpandResult<Arc<tt::Subtree>>;
+    ///
+    /// Also returns the diagnostics the proc macro emitted while expanding.
+    fn expand_proc_macro(
+        &self,
+        call: MacroCallId,
+    ) -> ExpandResult<(Arc<tt::Subtree>, Arc<[ProcMacroExpansionDiagnostic]>)>;
     /// Firewall query that returns the errors from the `parse_macro_expansion` query.
     fn parse_macro_expansion_error(
         &self,
         macro_call: MacroCallId,
     ) -> ExpandResult<Box<[SyntaxError]>>;
+    /// Firewall query that returns the diagnostics a proc macro emitted while expanding
+    /// `macro_call`. Empty for all other kinds of macros.
+    fn proc_macro_diagnostics(&self, macro_call: MacroCallId) -> Arc<[EmittedDiagnostic]>;
 
     fn hygiene_frame(&self, file_id: HirFileId) -> Arc<HygieneFrame>;
 }
@@ -247,7 +258,16 @@ pub fn expand_speculative(
     let mut speculative_expansion = match loc.def.kind {
         MacroDefKind::ProcMacro(expander, ..) => {
             tt.delimiter = tt::Delimiter::unspecified();
-            expander.expand(db, loc.def.krate, loc.krate, &tt, attr_arg.as_ref())
+            expander
+                .expand(
+                    db,
+                    loc.def.krate,
+                    loc.krate,
+                    &tt,
+                    attr_arg.as_ref(),
+                    &ProcMacroSpanData::default(),
+                )
+                .map(|it| it.subtree)
         }
         MacroDefKind::BuiltInAttr(BuiltinAttrExpander::Derive, _) => {
             pseudo_derive_attr_expansion(&tt, attr_arg.as_ref()?)
@@ -332,6 +352,26 @@ fn parse_macro_expansion_error(
         .map(|it| it.0.errors().to_vec().into_boxed_slice())
 }
 
+fn proc_macro_diagnostics(
+    db: &dyn ExpandDatabase,
+    macro_call_id: MacroCallId,
+) -> Arc<[EmittedDiagnostic]> {
+    let loc = db.lookup_intern_macro_call(macro_call_id);
+    if !matches!(loc.def.kind, MacroDefKind::ProcMacro(..)) {
+        return Arc::from(Vec::new());
+    }
+    let diagnostics = db.expand_proc_macro(macro_call_id).value.1;
+    match db.macro_arg(macro_call_id) {
+        // Mapping the spans depends on the position of the macro call, so do it here rather than
+        // in `expand_proc_macro`, to not have to reexpand when the call merely moves.
+        Some(macro_arg) if !diagnostics.is_empty() => {
+            let ranges = ProcMacroSpanRanges::new(db, &loc, &macro_arg.0, &macro_arg.1);
+            diagnostics.iter().map(|it| ranges.map_diagnostic(it)).collect::<Vec<_>>().into()
+        }
+        _ => Arc::from(Vec::new()),
+    }
+}
+
 fn macro_arg(
     db: &dyn ExpandDatabase,
     id: MacroCallId,
@@ -440,7 +480,7 @@ fn decl_macro_expander(
     def_crate: CrateId,
     id: AstId<ast::Macro>,
 ) -> Arc<DeclarativeMacroExpander> {
-    let is_2021 = db.crate_graph()[def_crate].edition >= Edition::Edition2021;
+    let is_2021 = db.crate_data(def_crate).edition >= Edition::Edition2021;
     let (mac, def_site_token_map) = match id.to_node(db) {
         ast::Macro::MacroRules(macro_rules) => match macro_rules.token_tree() {
             Some(arg) => {
@@ -496,7 +536,7 @@ fn macro_expand(db: &dyn ExpandDatabase, id: MacroCallId) -> ExpandResult<Arc<tt
     }
 
     let (ExpandResult { value: mut tt, mut err }, tmap) = match loc.def.kind {
-        MacroDefKind::ProcMacro(..) => return db.expand_proc_macro(id),
+        MacroDefKind::ProcMacro(..) => return db.expand_proc_macro(id).map(|(tt, _)| tt),
         MacroDefKind::BuiltInDerive(expander, ..) => {
             let arg = db.macro_arg_text(id).unwrap();
 
@@ -560,14 +600,20 @@ fn macro_expand(db: &dyn ExpandDatabase, id: MacroCallId) -> ExpandResult<Arc<tt
     ExpandResult { value: Arc::new(tt), err }
 }
 
-fn expand_proc_macro(db: &dyn ExpandDatabase, id: MacroCallId) -> ExpandResult<Arc<tt::Subtree>> {
+fn expand_proc_macro(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+) -> ExpandResult<(Arc<tt::Subtree>, Arc<[ProcMacroExpansionDiagnostic]>)> {
     let loc = db.lookup_intern_macro_call(id);
     let Some(macro_arg) = db.macro_arg(id) else {
         return ExpandResult {
-            value: Arc::new(tt::Subtree {
-                delimiter: tt::Delimiter::UNSPECIFIED,
-                token_trees: Vec::new(),
-            }),
+            value: (
+                Arc::new(tt::Subtree {
+                    delimiter: tt::Delimiter::UNSPECIFIED,
+                    token_trees: Vec::new(),
+                }),
+                Arc::from(Vec::new()),
+            ),
             err: Some(ExpandError::other("invalid token tree")),
         };
     };
@@ -587,17 +633,145 @@ fn expand_proc_macro(db: &dyn ExpandDatabase, id: MacroCallId) -> ExpandResult<A
         _ => None,
     };
 
-    let ExpandResult { value: mut tt, err } =
-        expander.expand(db, loc.def.krate, loc.krate, arg_tt, attr_arg.as_ref());
+    let expand = |spans: &ProcMacroSpanData| {
+        expander.expand(db, loc.def.krate, loc.krate, arg_tt, attr_arg.as_ref(), spans)
+    };
+    let mut spans = proc_macro_span_data(db, id, &loc, arg_tt, arg_tm);
+    let mut res = expand(&spans);
+    // Only depend on the location of the macro call if the macro asked for it, as otherwise merely
+    // moving the call around would require expanding it again.
+    if res.value.needs_location {
+        spans.location = proc_macro_span_location(db, &loc);
+        res = expand(&spans);
+    }
+    let ExpandResult {
+        value:
+            ProcMacroExpansion { subtree: mut tt, diagnostics, tracked_env_vars, tracked_paths, .. },
+        err,
+    } = res;
+
+    // The macro sees the environment of the calling crate. Depend on the variables it read, so
+    // that changing their value re-expands the macro.
+    let krate_data = db.crate_data(loc.krate);
+    for (var, _) in &tracked_env_vars {
+        krate_data.env.get(var);
+    }
+
+    // Depend on the files the macro declared to read, so that changing them invalidates the
+    // expansion. Files that are not part of the VFS yet are loaded into it by the client, which
+    // changes the source root and so reruns this query.
+    let root_file = krate_data.root_file_id;
+    for path in tracked_paths.iter().filter_map(|it| it.as_os_str().to_str()) {
+        if let Some(file_id) = db.resolve_path(AnchoredPath { anchor: root_file, path }) {
+            db.file_text(file_id);
+        }
+    }
+
+    let diagnostics = Arc::from(diagnostics);
 
     // Set a hard limit for the expanded tt
     if let Err(value) = check_tt_count(&tt) {
-        return value;
+        return value.map(|tt| (tt, diagnostics));
     }
 
     fixup::reverse_fixups(&mut tt, arg_tm, undo_info);
 
-    ExpandResult { value: Arc::new(tt), err }
+    ExpandResult { value: (Arc::new(tt), diagnostics), err }
+}
+
+/// Collects the source text of the input of a proc macro call and the ranges of its tokens.
+fn proc_macro_span_data(
+    db: &dyn ExpandDatabase,
+    id: MacroCallId,
+    loc: &MacroCallLoc,
+    arg_tt: &tt::Subtree,
+    arg_tm: &mbe::TokenMap,
+) -> ProcMacroSpanData {
+    let Some(arg) = db.macro_arg_text(id) else { return ProcMacroSpanData::default() };
+    let arg = SyntaxNode::new_root(arg);
+    let mut spans: Vec<_> = arg_tm.entries().collect();
+    if let MacroCallKind::Attr { attr_args, invoc_attr_index, .. } = &loc.kind {
+        // FIXME: handle `cfg_attr`
+        let attr_input = ast::Item::cast(arg.clone())
+            .and_then(|it| it.doc_comments_and_attrs().nth(invoc_attr_index.ast_index()))
+            .and_then(Either::left)
+            .and_then(|it| it.token_tree());
+        if let Some(attr_input) = attr_input {
+            let shift = mbe::Shift::new(arg_tt);
+            let start = attr_input.syntax().text_range().start();
+            spans.extend(attr_args.1.entries().map(|(id, range)| (shift.shift(id), range + start)));
+        }
+    }
+    ProcMacroSpanData { text: arg.text().to_string(), spans, location: None }
+}
+
+fn proc_macro_span_location(
+    db: &dyn ExpandDatabase,
+    loc: &MacroCallLoc,
+) -> Option<ProcMacroSpanLocation> {
+    let arg = loc.kind.arg(db)?;
+    let offset = arg.text_range().start();
+    let before = arg.ancestors().last()?.text().slice(TextRange::up_to(offset)).to_string();
+    let (line, column) = match before.rfind('\n') {
+        Some(idx) => (before.matches('\n').count() + 1, before[idx + 1..].chars().count()),
+        None => (1, before.chars().count()),
+    };
+    let file_path =
+        loc.kind.file_id().file_id().and_then(|file_id| Some(db.file_path(file_id)?.to_string()));
+    Some(ProcMacroSpanLocation { file_path, offset, line: line as u32, column: column as u32 })
+}
+
+/// Maps the spans of a proc macro's input back to ranges in the file containing the macro call.
+struct ProcMacroSpanRanges<'a> {
+    arg_tm: &'a mbe::TokenMap,
+    arg_start: Option<TextSize>,
+    /// The shift applied to the attribute input, its token map and the start of its token tree.
+    attr_input: Option<(mbe::Shift, &'a mbe::TokenMap, TextSize)>,
+}
+
+impl<'a> ProcMacroSpanRanges<'a> {
+    fn new(
+        db: &dyn ExpandDatabase,
+        loc: &'a MacroCallLoc,
+        arg_tt: &tt::Subtree,
+        arg_tm: &'a mbe::TokenMap,
+    ) -> Self {
+        let arg_start = loc.kind.arg(db).map(|it| it.text_range().start());
+        let attr_input = match &loc.kind {
+            MacroCallKind::Attr { ast_id, attr_args, invoc_attr_index } => {
+                // FIXME: handle `cfg_attr`
+                ast_id
+                    .to_node(db)
+                    .doc_comments_and_attrs()
+                    .nth(invoc_attr_index.ast_index())
+                    .and_then(Either::left)
+                    .and_then(|it| it.token_tree())
+                    .map(|it| {
+                        (mbe::Shift::new(arg_tt), &attr_args.1, it.syntax().text_range().start())
+                    })
+            }
+            _ => None,
+        };
+        ProcMacroSpanRanges { arg_tm, arg_start, attr_input }
+    }
+
+    fn range(&self, span: tt::TokenId) -> Option<TextRange> {
+        if let Some((shift, attr_tm, start)) = self.attr_input {
+            if let Some(span) = shift.unshift(span) {
+                return attr_tm.range_by_token_id(span).map(|it| it + start);
+            }
+        }
+        Some(self.arg_tm.range_by_token_id(span)? + self.arg_start?)
+    }
+
+    fn map_diagnostic(&self, diagnostic: &ProcMacroExpansionDiagnostic) -> EmittedDiagnostic {
+        EmittedDiagnostic {
+            level: diagnostic.level,
+            message: diagnostic.message.clone(),
+            ranges: diagnostic.spans.iter().filter_map(|&it| self.range(it)).collect(),
+            children: diagnostic.children.iter().map(|it| self.map_diagnostic(it)).collect(),
+        }
+    }
 }
 
 fn hygiene_frame(db: &dyn ExpandDatabase, file_id: HirFileId) -> Arc<HygieneFrame> {
@@ -7,6 +7,7 @@
 #![warn(rust_2018_idioms, unused_lifetimes, semicolon_in_expressions_from_macros)]
 
 pub mod db;
+pub mod arena_serde;
 pub mod ast_id_map;
 pub mod name;
 pub mod hygiene;
@@ -18,6 +19,7 @@ pub mod quote;
 pub mod eager;
 pub mod mod_path;
 pub mod attrs;
+pub mod stable_id;
 mod fixup;
 
 use mbe::TokenMap;
@@ -34,6 +36,7 @@ use base_db::{
     CrateId, FileId, FileRange, ProcMacroKind,
 };
 use either::Either;
+use serde::{Deserialize, Serialize};
 use syntax::{
     algo::{self, skip_trivia_token},
     ast::{self, AstNode, HasDocComments},
@@ -56,6 +59,8 @@ pub type ExpandResult<T> = ValueResult<T, ExpandError>;
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum ExpandError {
     UnresolvedProcMacro(CrateId),
+    /// The proc-macro server crashed while expanding a macro of the given crate.
+    ProcMacroCrashed(CrateId, Box<Box<str>>),
     Mbe(mbe::ExpandError),
     RecursionOverflowPoisoned,
     Other(Box<Box<str>>),
@@ -77,6 +82,7 @@ impl fmt::Display for ExpandError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ExpandError::UnresolvedProcMacro(_) => f.write_str("unresolved proc-macro"),
+            ExpandError::ProcMacroCrashed(_, it) => f.write_str(it),
             ExpandError::Mbe(it) => it.fmt(f),
             ExpandError::RecursionOverflowPoisoned => {
                 f.write_str("overflow expanding the original macro")
@@ -103,7 +109,7 @@ impl fmt::Display for ExpandError {
 /// The two variants are encoded in a single u32 which are differentiated by the MSB.
 /// If the MSB is 0, the value represents a `FileId`, otherwise the remaining 31 bits represent a
 /// `MacroCallId`.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct HirFileId(u32);
 
 impl fmt::Debug for HirFileId {
@@ -827,7 +833,7 @@ impl ErasedAstId {
 /// * `InFile<SyntaxNode>` -- syntax node in a file
 /// * `InFile<ast::FnDef>` -- ast node in a file
 /// * `InFile<TextSize>` -- offset in a file
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub struct InFile<T> {
     pub file_id: HirFileId,
     pub value: T,
@@ -1121,7 +1127,7 @@ impl<N: AstNode> InFile<N> {
 ///
 /// `ExpandTo` captures this bit of information about a particular macro call
 /// site.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ExpandTo {
     Statements,
     Items,
@@ -12,10 +12,11 @@ use crate::{
 };
 use base_db::CrateId;
 use either::Either;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use syntax::{ast, AstNode};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ModPath {
     pub kind: PathKind,
     segments: SmallVec<[Name; 1]>,
@@ -30,7 +31,7 @@ impl<'a> UnescapedModPath<'a> {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum PathKind {
     Plain,
     /// `self::` is `Super(0)`
@@ -39,7 +40,7 @@ pub enum PathKind {
     /// Absolute path (::foo)
     Abs,
     /// `$crate` from macro expansion
-    DollarCrate(CrateId),
+    DollarCrate(#[serde(with = "crate::arena_serde::idx")] CrateId),
 }
 
 impl ModPath {
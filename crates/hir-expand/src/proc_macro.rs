@@ -1,7 +1,12 @@
 //! Proc Macro Expander stub
 
-use base_db::{CrateId, ProcMacroExpansionError, ProcMacroId, ProcMacroKind};
+use base_db::{
+    CrateId, ProcMacroDiagnosticLevel, ProcMacroExpansion, ProcMacroExpansionError, ProcMacroId,
+    ProcMacroKind, ProcMacroSpanData,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use stdx::never;
+use syntax::TextRange;
 
 use crate::{db::ExpandDatabase, tt, ExpandError, ExpandResult};
 
@@ -12,6 +17,32 @@ pub struct ProcMacroExpander {
 
 const DUMMY_ID: u32 = !0;
 
+/// Serialized as the index of the proc-macro in its crate's dylib, so it is only meaningful
+/// together with the list of proc-macros the dylib has been loaded with.
+impl Serialize for ProcMacroExpander {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.proc_macro_id.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProcMacroExpander {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u32::deserialize(deserializer).map(|id| Self { proc_macro_id: ProcMacroId(id) })
+    }
+}
+
+/// A diagnostic a proc-macro emitted while expanding, with its spans mapped back to the macro
+/// input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedDiagnostic {
+    pub level: ProcMacroDiagnosticLevel,
+    pub message: String,
+    /// The ranges the diagnostic points at, in the file containing the macro call. Spans that do
+    /// not come from the macro input are dropped.
+    pub ranges: Vec<TextRange>,
+    pub children: Vec<EmittedDiagnostic>,
+}
+
 impl ProcMacroExpander {
     pub fn new(proc_macro_id: ProcMacroId) -> Self {
         assert_ne!(proc_macro_id.0, DUMMY_ID);
@@ -33,11 +64,13 @@ impl ProcMacroExpander {
         calling_crate: CrateId,
         tt: &tt::Subtree,
         attr_arg: Option<&tt::Subtree>,
-    ) -> ExpandResult<tt::Subtree> {
+        spans: &ProcMacroSpanData,
+    ) -> ExpandResult<ProcMacroExpansion> {
         match self.proc_macro_id {
-            ProcMacroId(DUMMY_ID) => {
-                ExpandResult::new(tt::Subtree::empty(), ExpandError::UnresolvedProcMacro(def_crate))
-            }
+            ProcMacroId(DUMMY_ID) => ExpandResult::new(
+                tt::Subtree::empty().into(),
+                ExpandError::UnresolvedProcMacro(def_crate),
+            ),
             ProcMacroId(id) => {
                 let proc_macros = db.proc_macros();
                 let proc_macros = match proc_macros.get(&def_crate) {
@@ -45,7 +78,7 @@ impl ProcMacroExpander {
                     Some(Err(_)) | None => {
                         never!("Non-dummy expander even though there are no proc macros");
                         return ExpandResult::new(
-                            tt::Subtree::empty(),
+                            tt::Subtree::empty().into(),
                             ExpandError::other("Internal error"),
                         );
                     }
@@ -59,27 +92,38 @@ impl ProcMacroExpander {
                             id
                         );
                         return ExpandResult::new(
-                            tt::Subtree::empty(),
+                            tt::Subtree::empty().into(),
                             ExpandError::other("Internal error"),
                         );
                     }
                 };
 
-                let krate_graph = db.crate_graph();
                 // Proc macros have access to the environment variables of the invoking crate.
-                let env = &krate_graph[calling_crate].env;
-                match proc_macro.expander.expand(tt, attr_arg, env) {
+                let env = &db.crate_data(calling_crate).env;
+                match proc_macro.expander.expand(tt, attr_arg, env, spans) {
                     Ok(t) => ExpandResult::ok(t),
                     Err(err) => match err {
                         // Don't discard the item in case something unexpected happened while expanding attributes
                         ProcMacroExpansionError::System(text)
                             if proc_macro.kind == ProcMacroKind::Attr =>
                         {
-                            ExpandResult { value: tt.clone(), err: Some(ExpandError::other(text)) }
+                            ExpandResult {
+                                value: tt.clone().into(),
+                                err: Some(ExpandError::other(text)),
+                            }
+                        }
+                        ProcMacroExpansionError::ServerCrashed(text) => {
+                            let err =
+                                ExpandError::ProcMacroCrashed(def_crate, Box::new(text.into()));
+                            let value = match proc_macro.kind {
+                                ProcMacroKind::Attr => tt.clone(),
+                                _ => tt::Subtree::empty(),
+                            };
+                            ExpandResult::new(value.into(), err)
                         }
                         ProcMacroExpansionError::System(text)
                         | ProcMacroExpansionError::Panic(text) => {
-                            ExpandResult::new(tt::Subtree::empty(), ExpandError::other(text))
+                            ExpandResult::new(tt::Subtree::empty().into(), ExpandError::other(text))
                         }
                     },
                 }
@@ -106,6 +106,17 @@ macro_rules! __quote {
     ( < ) => {$crate::__quote!(@PUNCT '<')};
     ( > ) => {$crate::__quote!(@PUNCT '>')};
     ( ! ) => {$crate::__quote!(@PUNCT '!')};
+    ( = ) => {$crate::__quote!(@PUNCT '=')};
+    ( + ) => {$crate::__quote!(@PUNCT '+')};
+    ( ? ) => {$crate::__quote!(@PUNCT '?')};
+    ( _ ) => {
+        vec![ {
+            crate::tt::Leaf::Ident(crate::tt::Ident {
+                text: "_".into(),
+                span: crate::tt::TokenId::unspecified(),
+            }).into()
+        }]
+    };
 
     ( $first:tt $($tail:tt)+ ) => {
         {
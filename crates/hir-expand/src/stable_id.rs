@@ -0,0 +1,396 @@
+//! Representations of files, crates and macro calls that stay valid across processes.
+//!
+//! `FileId`s, `CrateId`s and interned IDs like `MacroCallId` are only meaningful within the process
+//! that created them. To persist data that refers to them, a [`StableIdEncoder`] replaces them by
+//! indices into [`StableIdTables`], which identify files by their path, crates by the path of their
+//! root file, and macro calls by their encoded `MacroCallLoc`. A [`StableIdDecoder`] maps these
+//! back, interning the macro calls again, so the decoded IDs are the ones the database would have
+//! created itself.
+//!
+//! The tables don't say anything about the *contents* of the files and crates, users have to make
+//! sure those haven't changed since the IDs were encoded.
+
+use base_db::{CrateId, FileId, ProcMacroKind};
+use mbe::TokenMap;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use syntax::ast;
+use triomphe::Arc;
+
+use crate::{
+    ast_id_map::{AstIdNode, FileAstId},
+    attrs::AttrId,
+    builtin_attr_macro::BuiltinAttrExpander,
+    builtin_derive_macro::BuiltinDeriveExpander,
+    builtin_fn_macro::{BuiltinFnLikeExpander, EagerExpander},
+    db::ExpandDatabase,
+    proc_macro::ProcMacroExpander,
+    tt, AstId, EagerCallInfo, ExpandTo, HirFileId, HirFileIdRepr, InFile, MacroCallId,
+    MacroCallKind, MacroCallLoc, MacroDefId, MacroDefKind,
+};
+
+/// The files, crates and macro calls the IDs of a [`StableIdEncoder`] point into.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StableIdTables {
+    files: Vec<String>,
+    /// The root file of every crate, as an index into `files`.
+    crates: Vec<u32>,
+    /// Every macro call only refers to the macro calls before it.
+    macro_calls: Vec<StableMacroCallLoc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StableFileId {
+    File(u32),
+    MacroFile(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StableCrateId(u32);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StableAstId<N: AstIdNode> {
+    file_id: StableFileId,
+    value: FileAstId<N>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StableMacroCallLoc {
+    def: StableMacroDefId,
+    krate: StableCrateId,
+    eager: Option<StableEagerCallInfo>,
+    kind: StableMacroCallKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StableMacroDefId {
+    krate: StableCrateId,
+    kind: StableMacroDefKind,
+    local_inner: bool,
+    allow_internal_unsafe: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum StableMacroDefKind {
+    Declarative(StableAstId<ast::Macro>),
+    BuiltIn(BuiltinFnLikeExpander, StableAstId<ast::Macro>),
+    BuiltInAttr(BuiltinAttrExpander, StableAstId<ast::Macro>),
+    BuiltInDerive(BuiltinDeriveExpander, StableAstId<ast::Macro>),
+    BuiltInEager(EagerExpander, StableAstId<ast::Macro>),
+    ProcMacro(
+        ProcMacroExpander,
+        #[serde(with = "ProcMacroKindDef")] ProcMacroKind,
+        StableAstId<ast::Fn>,
+    ),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StableEagerCallInfo {
+    arg: (tt::Subtree, TokenMap),
+    /// An index into the macro calls of the tables.
+    arg_id: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum StableMacroCallKind {
+    FnLike {
+        ast_id: StableAstId<ast::MacroCall>,
+        expand_to: ExpandTo,
+    },
+    Derive {
+        ast_id: StableAstId<ast::Adt>,
+        derive_attr_index: AttrId,
+        derive_index: u32,
+    },
+    Attr {
+        ast_id: StableAstId<ast::Item>,
+        attr_args: (tt::Subtree, TokenMap),
+        invoc_attr_index: AttrId,
+    },
+}
+
+/// `serde` support for [`ProcMacroKind`], to be used with `#[serde(with = "ProcMacroKindDef")]`.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ProcMacroKind")]
+pub enum ProcMacroKindDef {
+    CustomDerive,
+    FuncLike,
+    Attr,
+}
+
+/// Encodes IDs into [`StableIdTables`], see the [module docs](self).
+pub struct StableIdEncoder<'a> {
+    db: &'a dyn ExpandDatabase,
+    paths: &'a FxHashMap<FileId, String>,
+    /// The number of crates in the crate graph with a given root file.
+    crates_by_root: FxHashMap<FileId, usize>,
+    tables: StableIdTables,
+    files: FxHashMap<FileId, u32>,
+    crates: FxHashMap<CrateId, StableCrateId>,
+    macro_calls: FxHashMap<MacroCallId, u32>,
+}
+
+impl<'a> StableIdEncoder<'a> {
+    /// Creates an encoder for IDs referring to the files in `paths`.
+    pub fn new(db: &'a dyn ExpandDatabase, paths: &'a FxHashMap<FileId, String>) -> Self {
+        let crate_graph = db.crate_graph();
+        let mut crates_by_root = FxHashMap::default();
+        for krate in crate_graph.iter() {
+            *crates_by_root.entry(crate_graph[krate].root_file_id).or_default() += 1;
+        }
+        StableIdEncoder {
+            db,
+            paths,
+            crates_by_root,
+            tables: StableIdTables::default(),
+            files: FxHashMap::default(),
+            crates: FxHashMap::default(),
+            macro_calls: FxHashMap::default(),
+        }
+    }
+
+    pub fn finish(self) -> StableIdTables {
+        self.tables
+    }
+
+    /// Returns `None` if the file, or a file the macro call producing it depends on, has no path.
+    pub fn file_id(&mut self, file_id: HirFileId) -> Option<StableFileId> {
+        match file_id.repr() {
+            HirFileIdRepr::FileId(file_id) => self.real_file_id(file_id).map(StableFileId::File),
+            HirFileIdRepr::MacroFile(macro_file) => {
+                self.macro_call_id(macro_file.macro_call_id).map(StableFileId::MacroFile)
+            }
+        }
+    }
+
+    /// Returns `None` if the crate's root file has no path, or is the root of several crates.
+    pub fn krate(&mut self, krate: CrateId) -> Option<StableCrateId> {
+        if let Some(&it) = self.crates.get(&krate) {
+            return Some(it);
+        }
+        let root_file_id = self.db.crate_graph()[krate].root_file_id;
+        if self.crates_by_root.get(&root_file_id) != Some(&1) {
+            return None;
+        }
+        let root = self.real_file_id(root_file_id)?;
+        let id = StableCrateId(self.tables.crates.len() as u32);
+        self.tables.crates.push(root);
+        self.crates.insert(krate, id);
+        Some(id)
+    }
+
+    pub fn ast_id<N: AstIdNode>(&mut self, ast_id: AstId<N>) -> Option<StableAstId<N>> {
+        Some(StableAstId { file_id: self.file_id(ast_id.file_id)?, value: ast_id.value })
+    }
+
+    fn real_file_id(&mut self, file_id: FileId) -> Option<u32> {
+        if let Some(&it) = self.files.get(&file_id) {
+            return Some(it);
+        }
+        let path = self.paths.get(&file_id)?;
+        let idx = self.tables.files.len() as u32;
+        self.tables.files.push(path.clone());
+        self.files.insert(file_id, idx);
+        Some(idx)
+    }
+
+    fn macro_call_id(&mut self, id: MacroCallId) -> Option<u32> {
+        if let Some(&it) = self.macro_calls.get(&id) {
+            return Some(it);
+        }
+        let MacroCallLoc { def, krate, eager, kind } = self.db.lookup_intern_macro_call(id);
+        let def = self.macro_def_id(def)?;
+        let krate = self.krate(krate)?;
+        let eager = match eager.map(|it| *it) {
+            // Expansion errors may refer to crates and aren't worth persisting.
+            Some(EagerCallInfo { error: Some(_), .. }) => return None,
+            Some(EagerCallInfo { arg, arg_id, error: None }) => Some(StableEagerCallInfo {
+                arg: (*arg).clone(),
+                arg_id: match arg_id {
+                    Some(arg_id) => Some(self.macro_call_id(arg_id)?),
+                    None => None,
+                },
+            }),
+            None => None,
+        };
+        let kind = match kind {
+            MacroCallKind::FnLike { ast_id, expand_to } => {
+                StableMacroCallKind::FnLike { ast_id: self.ast_id(ast_id)?, expand_to }
+            }
+            MacroCallKind::Derive { ast_id, derive_attr_index, derive_index } => {
+                StableMacroCallKind::Derive {
+                    ast_id: self.ast_id(ast_id)?,
+                    derive_attr_index,
+                    derive_index,
+                }
+            }
+            MacroCallKind::Attr { ast_id, attr_args, invoc_attr_index } => {
+                StableMacroCallKind::Attr {
+                    ast_id: self.ast_id(ast_id)?,
+                    attr_args: (*attr_args).clone(),
+                    invoc_attr_index,
+                }
+            }
+        };
+
+        let idx = self.tables.macro_calls.len() as u32;
+        self.tables.macro_calls.push(StableMacroCallLoc { def, krate, eager, kind });
+        self.macro_calls.insert(id, idx);
+        Some(idx)
+    }
+
+    fn macro_def_id(&mut self, def: MacroDefId) -> Option<StableMacroDefId> {
+        let MacroDefId { krate, kind, local_inner, allow_internal_unsafe } = def;
+        let kind = match kind {
+            MacroDefKind::Declarative(ast_id) => {
+                StableMacroDefKind::Declarative(self.ast_id(ast_id)?)
+            }
+            MacroDefKind::BuiltIn(expander, ast_id) => {
+                StableMacroDefKind::BuiltIn(expander, self.ast_id(ast_id)?)
+            }
+            MacroDefKind::BuiltInAttr(expander, ast_id) => {
+                StableMacroDefKind::BuiltInAttr(expander, self.ast_id(ast_id)?)
+            }
+            MacroDefKind::BuiltInDerive(expander, ast_id) => {
+                StableMacroDefKind::BuiltInDerive(expander, self.ast_id(ast_id)?)
+            }
+            MacroDefKind::BuiltInEager(expander, ast_id) => {
+                StableMacroDefKind::BuiltInEager(expander, self.ast_id(ast_id)?)
+            }
+            MacroDefKind::ProcMacro(expander, kind, ast_id) => {
+                StableMacroDefKind::ProcMacro(expander, kind, self.ast_id(ast_id)?)
+            }
+        };
+        Some(StableMacroDefId {
+            krate: self.krate(krate)?,
+            kind,
+            local_inner,
+            allow_internal_unsafe,
+        })
+    }
+}
+
+/// Decodes IDs encoded by a [`StableIdEncoder`], see the [module docs](self).
+pub struct StableIdDecoder {
+    files: Vec<FileId>,
+    crates: Vec<CrateId>,
+    macro_calls: Vec<MacroCallId>,
+}
+
+impl StableIdDecoder {
+    /// Interns the macro calls of `tables`. Returns `None` if one of the files of the tables isn't
+    /// in `file_ids` anymore, or one of its crates isn't in the crate graph.
+    pub fn new(
+        db: &dyn ExpandDatabase,
+        tables: &StableIdTables,
+        file_ids: &FxHashMap<String, FileId>,
+    ) -> Option<Self> {
+        let files =
+            tables.files.iter().map(|path| file_ids.get(path).copied()).collect::<Option<_>>()?;
+
+        let crate_graph = db.crate_graph();
+        // The crate of every root file, or `None` if it is the root of several crates.
+        let mut crates_by_root = FxHashMap::default();
+        for krate in crate_graph.iter() {
+            crates_by_root
+                .entry(crate_graph[krate].root_file_id)
+                .and_modify(|it| *it = None)
+                .or_insert(Some(krate));
+        }
+
+        let mut decoder = StableIdDecoder { files, crates: Vec::new(), macro_calls: Vec::new() };
+        decoder.crates = tables
+            .crates
+            .iter()
+            .map(|&root| *crates_by_root.get(decoder.files.get(root as usize)?)?)
+            .collect::<Option<_>>()?;
+        for loc in &tables.macro_calls {
+            let loc = decoder.macro_call_loc(loc)?;
+            decoder.macro_calls.push(db.intern_macro_call(loc));
+        }
+        Some(decoder)
+    }
+
+    pub fn file_id(&self, file_id: StableFileId) -> Option<HirFileId> {
+        match file_id {
+            StableFileId::File(idx) => self.files.get(idx as usize).map(|&it| it.into()),
+            StableFileId::MacroFile(idx) => {
+                self.macro_calls.get(idx as usize).map(|it| it.as_file())
+            }
+        }
+    }
+
+    pub fn krate(&self, krate: StableCrateId) -> Option<CrateId> {
+        self.crates.get(krate.0 as usize).copied()
+    }
+
+    pub fn ast_id<N: AstIdNode>(&self, ast_id: &StableAstId<N>) -> Option<AstId<N>> {
+        Some(InFile::new(self.file_id(ast_id.file_id)?, ast_id.value))
+    }
+
+    fn macro_call_loc(&self, loc: &StableMacroCallLoc) -> Option<MacroCallLoc> {
+        let StableMacroCallLoc { def, krate, eager, kind } = loc;
+        let eager = match eager {
+            Some(StableEagerCallInfo { arg, arg_id }) => Some(Box::new(EagerCallInfo {
+                arg: Arc::new(arg.clone()),
+                arg_id: match arg_id {
+                    Some(idx) => Some(*self.macro_calls.get(*idx as usize)?),
+                    None => None,
+                },
+                error: None,
+            })),
+            None => None,
+        };
+        let kind = match kind {
+            StableMacroCallKind::FnLike { ast_id, expand_to } => {
+                MacroCallKind::FnLike { ast_id: self.ast_id(ast_id)?, expand_to: *expand_to }
+            }
+            StableMacroCallKind::Derive { ast_id, derive_attr_index, derive_index } => {
+                MacroCallKind::Derive {
+                    ast_id: self.ast_id(ast_id)?,
+                    derive_attr_index: *derive_attr_index,
+                    derive_index: *derive_index,
+                }
+            }
+            StableMacroCallKind::Attr { ast_id, attr_args, invoc_attr_index } => {
+                MacroCallKind::Attr {
+                    ast_id: self.ast_id(ast_id)?,
+                    attr_args: Arc::new(attr_args.clone()),
+                    invoc_attr_index: *invoc_attr_index,
+                }
+            }
+        };
+        Some(MacroCallLoc { def: self.macro_def_id(def)?, krate: self.krate(*krate)?, eager, kind })
+    }
+
+    fn macro_def_id(&self, def: &StableMacroDefId) -> Option<MacroDefId> {
+        let StableMacroDefId { krate, kind, local_inner, allow_internal_unsafe } = def;
+        let kind = match kind {
+            StableMacroDefKind::Declarative(ast_id) => {
+                MacroDefKind::Declarative(self.ast_id(ast_id)?)
+            }
+            StableMacroDefKind::BuiltIn(expander, ast_id) => {
+                MacroDefKind::BuiltIn(*expander, self.ast_id(ast_id)?)
+            }
+            StableMacroDefKind::BuiltInAttr(expander, ast_id) => {
+                MacroDefKind::BuiltInAttr(*expander, self.ast_id(ast_id)?)
+            }
+            StableMacroDefKind::BuiltInDerive(expander, ast_id) => {
+                MacroDefKind::BuiltInDerive(*expander, self.ast_id(ast_id)?)
+            }
+            StableMacroDefKind::BuiltInEager(expander, ast_id) => {
+                MacroDefKind::BuiltInEager(*expander, self.ast_id(ast_id)?)
+            }
+            StableMacroDefKind::ProcMacro(expander, kind, ast_id) => {
+                MacroDefKind::ProcMacro(*expander, *kind, self.ast_id(ast_id)?)
+            }
+        };
+        Some(MacroDefId {
+            krate: self.krate(*krate)?,
+            kind,
+            local_inner: *local_inner,
+            allow_internal_unsafe: *allow_internal_unsafe,
+        })
+    }
+}
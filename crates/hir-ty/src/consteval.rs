@@ -3,20 +3,23 @@
 use base_db::CrateId;
 use chalk_ir::{BoundVar, DebruijnIndex, GenericArgData};
 use hir_def::{
-    hir::Expr,
+    body::Body,
+    hir::{Expr, ExprId},
     path::Path,
-    resolver::{Resolver, ValueNs},
+    resolver::{resolver_for_expr, Resolver, ValueNs},
     type_ref::LiteralConstRef,
-    ConstBlockLoc, EnumVariantId, GeneralConstId, StaticId,
+    AssocItemId, ConstBlockLoc, DefWithBodyId, EnumVariantId, FunctionId, GeneralConstId,
+    ItemContainerId, Lookup, StaticId,
 };
-use la_arena::{Idx, RawIdx};
+use la_arena::{ArenaMap, Idx, RawIdx};
 use stdx::never;
 use triomphe::Arc;
 
 use crate::{
     db::HirDatabase, infer::InferenceContext, lower::ParamLoweringMode,
-    mir::monomorphize_mir_body_bad, to_placeholder_idx, utils::Generics, Const, ConstData,
-    ConstScalar, ConstValue, GenericArg, Interner, MemoryMap, Substitution, Ty, TyBuilder,
+    mir::monomorphize_mir_body_bad, to_placeholder_idx, utils::Generics, CallableDefId, Const,
+    ConstData, ConstScalar, ConstValue, GenericArg, InferenceResult, Interner, MemoryMap,
+    Substitution, Ty, TyBuilder, TyKind,
 };
 
 use super::mir::{interpret_mir, lower_to_mir, pad16, MirEvalError, MirLowerError};
@@ -273,6 +276,113 @@ pub(crate) fn const_eval_discriminant_variant(
     Ok(c)
 }
 
+/// Evaluates `expr`, an expression of the body of `owner`, as if it was the body of a constant.
+/// Expressions that aren't constant, like ones that use locals or call functions which aren't
+/// `const fn`, fail to evaluate.
+pub(crate) fn const_eval_expr_query(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+    expr: ExprId,
+) -> Result<Const, ConstEvalError> {
+    if db.const_exprs(owner).get(expr) != Some(&true) {
+        return Err(MirLowerError::NotSupported("non constant expression".to_owned()).into());
+    }
+    let body = db.body(owner);
+    let infer = db.infer(owner);
+    let mir_body = lower_to_mir(db, owner, &body, &infer, expr)?;
+    let c = interpret_mir(db, Arc::new(mir_body), false).0?;
+    Ok(c)
+}
+
+/// Returns whether each expression of the body of `owner` is a constant expression, that is one
+/// which only calls `const fn`s and doesn't refer to statics or generic parameters.
+pub(crate) fn const_exprs_query(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+) -> Arc<ArenaMap<ExprId, bool>> {
+    let body = db.body(owner);
+    let infer = db.infer(owner);
+    let mut result = ArenaMap::default();
+    for (expr, _) in body.exprs.iter() {
+        is_const_expr(db, owner, &body, &infer, expr, &mut result);
+    }
+    Arc::new(result)
+}
+
+fn is_const_expr(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+    body: &Body,
+    infer: &InferenceResult,
+    expr: ExprId,
+    result: &mut ArenaMap<ExprId, bool>,
+) -> bool {
+    if let Some(&is_const) = result.get(expr) {
+        return is_const;
+    }
+    let is_const_fn = |func: FunctionId| {
+        if db.function_data(func).has_const_kw() {
+            return true;
+        }
+        // Intrinsics aren't declared `const`, but the evaluator knows the ones it supports.
+        match func.lookup(db.upcast()).container {
+            ItemContainerId::ExternBlockId(block) => {
+                let id = block.lookup(db.upcast()).id;
+                id.item_tree(db.upcast())[id.value].abi.as_deref() == Some("rust-intrinsic")
+            }
+            _ => false,
+        }
+    };
+    let is_const = match &body[expr] {
+        Expr::Closure { .. } | Expr::Async { .. } | Expr::Await { .. } | Expr::Yield { .. } => {
+            false
+        }
+        // Overloaded operators and method calls.
+        Expr::BinaryOp { .. }
+        | Expr::UnaryOp { .. }
+        | Expr::Index { .. }
+        | Expr::MethodCall { .. } => {
+            infer.method_resolution(expr).map_or(true, |(func, _)| is_const_fn(func))
+        }
+        Expr::Call { callee, .. } => match infer[*callee].kind(Interner) {
+            TyKind::FnDef(def, _) => match db.lookup_intern_callable_def((*def).into()) {
+                CallableDefId::FunctionId(func) => is_const_fn(func),
+                CallableDefId::StructId(_) | CallableDefId::EnumVariantId(_) => true,
+            },
+            _ => false,
+        },
+        Expr::Path(path) => match infer.assoc_resolutions_for_expr(expr) {
+            Some((AssocItemId::ConstId(_) | AssocItemId::FunctionId(_), _)) => true,
+            Some((AssocItemId::TypeAliasId(_), _)) => false,
+            None => {
+                let resolver = resolver_for_expr(db.upcast(), owner, expr);
+                match resolver.resolve_path_in_value_ns_fully(db.upcast(), path) {
+                    // Locals which aren't declared within the evaluated expression fail to lower.
+                    Some(
+                        ValueNs::LocalBinding(_)
+                        | ValueNs::FunctionId(_)
+                        | ValueNs::ConstId(_)
+                        | ValueNs::StructId(_)
+                        | ValueNs::EnumVariantId(_)
+                        | ValueNs::ImplSelf(_),
+                    ) => true,
+                    Some(ValueNs::StaticId(_) | ValueNs::GenericParam(_)) => false,
+                    // Variants of `Self` and of type aliases.
+                    None => infer.variant_resolution_for_expr(expr).is_some(),
+                }
+            }
+        },
+        _ => true,
+    };
+    let mut children_are_const = true;
+    body[expr].walk_child_exprs(|child| {
+        children_are_const &= is_const_expr(db, owner, body, infer, child, result);
+    });
+    let is_const = is_const && children_are_const;
+    result.insert(expr, is_const);
+    is_const
+}
+
 // FIXME: Ideally constants in const eval should have separate body (issue #7434), and this function should
 // get an `InferenceResult` instead of an `InferenceContext`. And we should remove `ctx.clone().resolve_all()` here
 // and make this function private. See the fixme comment on `InferenceContext::resolve_all`.
@@ -88,6 +88,12 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::cycle(crate::consteval::const_eval_discriminant_recover)]
     fn const_eval_discriminant(&self, def: EnumVariantId) -> Result<i128, ConstEvalError>;
 
+    #[salsa::invoke(crate::consteval::const_eval_expr_query)]
+    fn const_eval_expr(&self, owner: DefWithBodyId, expr: ExprId) -> Result<Const, ConstEvalError>;
+
+    #[salsa::invoke(crate::consteval::const_exprs_query)]
+    fn const_exprs(&self, owner: DefWithBodyId) -> Arc<ArenaMap<ExprId, bool>>;
+
     #[salsa::invoke(crate::lower::impl_trait_query)]
     fn impl_trait(&self, def: ImplId) -> Option<Binders<TraitRef>>;
 
@@ -1,5 +1,6 @@
 //! Type inference-based diagnostics.
 mod expr;
+mod impl_check;
 mod match_check;
 mod unsafe_check;
 mod decl_check;
@@ -9,6 +10,7 @@ pub use crate::diagnostics::{
     expr::{
         record_literal_missing_fields, record_pattern_missing_fields, BodyValidationDiagnostic,
     },
+    impl_check::{trait_impl_signature_mismatch, SignatureMismatch},
     unsafe_check::{missing_unsafe, unsafe_expressions, UnsafeExpr},
 };
 
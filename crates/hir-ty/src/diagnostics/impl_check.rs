@@ -0,0 +1,93 @@
+//! Checks that the methods of a trait impl match the signatures declared in the trait.
+
+use std::mem;
+
+use hir_def::{FunctionId, ItemContainerId, Lookup};
+
+use crate::{
+    db::HirDatabase, infer::normalize, utils::generics, CallableDefId, Canonical,
+    CanonicalVarKinds, Interner, Substitution, Ty, TyBuilder, TyExt,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureMismatch {
+    /// Exactly one of the methods takes `self`.
+    SelfParam { trait_has_self: bool },
+    /// The number of parameters, including `self`, differs.
+    ParamCount { expected: usize, found: usize },
+    /// The type of a parameter or, if `param` is `None`, of the return value differs.
+    ParamType { param: Option<usize>, expected: Ty, found: Ty },
+}
+
+/// Compares the signature of `impl_fn` with the one of `trait_fn`, the trait method it implements.
+///
+/// Types are compared in the environment of `impl_fn` after substituting the trait's generic
+/// parameters with the ones of the impl.
+pub fn trait_impl_signature_mismatch(
+    db: &dyn HirDatabase,
+    impl_fn: FunctionId,
+    trait_fn: FunctionId,
+) -> Option<SignatureMismatch> {
+    let impl_data = db.function_data(impl_fn);
+    let trait_data = db.function_data(trait_fn);
+    if impl_data.has_self_param() != trait_data.has_self_param() {
+        return Some(SignatureMismatch::SelfParam { trait_has_self: trait_data.has_self_param() });
+    }
+    if impl_data.params.len() != trait_data.params.len() {
+        return Some(SignatureMismatch::ParamCount {
+            expected: trait_data.params.len(),
+            found: impl_data.params.len(),
+        });
+    }
+
+    let ItemContainerId::ImplId(impl_id) = impl_fn.lookup(db.upcast()).container else {
+        return None;
+    };
+    let impl_generics = generics(db.upcast(), impl_fn.into());
+    let trait_generics = generics(db.upcast(), trait_fn.into());
+    // The parameters of generic methods can only be matched up if they are of the same kinds.
+    let same_kinds = impl_generics.len_self() == trait_generics.len_self()
+        && impl_generics
+            .iter_self()
+            .zip(trait_generics.iter_self())
+            .all(|((_, a), (_, b))| mem::discriminant(a) == mem::discriminant(b));
+    if !same_kinds {
+        return None;
+    }
+    let impl_subst = TyBuilder::placeholder_subst(db, impl_fn);
+    // The own parameters of a method come before the ones of its container.
+    let (own_params, impl_params) =
+        impl_subst.as_slice(Interner).split_at(impl_generics.len_self());
+    let trait_ref = db.impl_trait(impl_id)?.substitute(Interner, impl_params);
+    let trait_subst = Substitution::from_iter(
+        Interner,
+        own_params.iter().chain(trait_ref.substitution.iter(Interner)).cloned(),
+    );
+
+    let impl_sig = db
+        .callable_item_signature(CallableDefId::FunctionId(impl_fn))
+        .substitute(Interner, &impl_subst);
+    let trait_sig = db
+        .callable_item_signature(CallableDefId::FunctionId(trait_fn))
+        .substitute(Interner, &trait_subst);
+    let env = db.trait_environment(impl_fn.into());
+    let params =
+        impl_sig.params().iter().zip(trait_sig.params()).enumerate().map(|(i, it)| (Some(i), it));
+    let ret = std::iter::once((None, (impl_sig.ret(), trait_sig.ret())));
+    params.chain(ret).find_map(|(param, (found, expected))| {
+        let found = normalize(db, env.clone(), found.clone());
+        let expected = normalize(db, env.clone(), expected.clone());
+        if found.contains_unknown() || expected.contains_unknown() {
+            return None;
+        }
+        let tys = Canonical {
+            value: (found.clone(), expected.clone()),
+            binders: CanonicalVarKinds::empty(Interner),
+        };
+        (!crate::could_unify(db, env.clone(), &tys)).then_some(SignatureMismatch::ParamType {
+            param,
+            expected,
+            found,
+        })
+    })
+}
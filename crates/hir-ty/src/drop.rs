@@ -0,0 +1,109 @@
+//! Whether dropping a value of a type runs any code.
+
+use base_db::CrateId;
+use hir_def::{data::adt::StructFlags, lang_item::LangItem, AdtId, FunctionId, VariantId};
+use hir_expand::name;
+use rustc_hash::FxHashSet;
+use triomphe::Arc;
+
+use crate::{db::HirDatabase, Interner, Substitution, TraitEnvironment, Ty, TyKind};
+
+/// Returns `true` if dropping a value of type `ty` calls a `Drop` impl, either of the type itself
+/// or of something that it owns. Types whose drop glue isn't known, like generic parameters and
+/// trait objects, are assumed to have some.
+pub fn has_drop_glue(db: &dyn HirDatabase, ty: Ty, env: Arc<TraitEnvironment>) -> bool {
+    let Some(drop_fn) = drop_fn(db, env.krate) else {
+        // Without a `Drop` trait nothing can implement it.
+        return false;
+    };
+    DropGlue { db, env, drop_fn, visited: FxHashSet::default() }.has_drop_glue(ty)
+}
+
+fn drop_fn(db: &dyn HirDatabase, krate: CrateId) -> Option<FunctionId> {
+    let drop_trait = db.lang_item(krate, LangItem::Drop)?.as_trait()?;
+    db.trait_data(drop_trait).method_by_name(&name![drop])
+}
+
+struct DropGlue<'a> {
+    db: &'a dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    drop_fn: FunctionId,
+    /// Types that are already being checked, so that recursive types terminate.
+    visited: FxHashSet<Ty>,
+}
+
+impl DropGlue<'_> {
+    fn has_drop_glue(&mut self, ty: Ty) -> bool {
+        if !self.visited.insert(ty.clone()) {
+            return false;
+        }
+        match ty.kind(Interner) {
+            TyKind::Adt(id, subst) => self.adt_has_drop_glue(id.0, &ty, subst),
+            TyKind::Tuple(_, subst) => subst
+                .iter(Interner)
+                .filter_map(|it| it.ty(Interner))
+                .any(|it| self.has_drop_glue(it.clone())),
+            TyKind::Array(ty, _) | TyKind::Slice(ty) => self.has_drop_glue(ty.clone()),
+            TyKind::Closure(closure, subst) => {
+                let (owner, _) = self.db.lookup_intern_closure((*closure).into());
+                let infer = self.db.infer(owner);
+                let (captures, _) = infer.closure_info(closure);
+                // By reference captures have reference types, so they are skipped here.
+                captures.iter().any(|it| self.has_drop_glue(it.ty(subst)))
+            }
+            TyKind::Scalar(_)
+            | TyKind::Str
+            | TyKind::Never
+            | TyKind::Ref(..)
+            | TyKind::Raw(..)
+            | TyKind::FnDef(..)
+            | TyKind::Function(_)
+            | TyKind::Foreign(_)
+            | TyKind::Error => false,
+            TyKind::Dyn(_)
+            | TyKind::Placeholder(_)
+            | TyKind::BoundVar(_)
+            | TyKind::InferenceVar(..)
+            | TyKind::Alias(_)
+            | TyKind::AssociatedType(..)
+            | TyKind::OpaqueType(..)
+            | TyKind::Generator(..)
+            | TyKind::GeneratorWitness(..) => true,
+        }
+    }
+
+    fn adt_has_drop_glue(&mut self, adt: AdtId, ty: &Ty, subst: &Substitution) -> bool {
+        let (impl_drop, _) = self.db.lookup_impl_method(
+            self.env.clone(),
+            self.drop_fn,
+            Substitution::from1(Interner, ty.clone()),
+        );
+        if impl_drop != self.drop_fn {
+            return true;
+        }
+        let variants: Vec<VariantId> = match adt {
+            AdtId::StructId(it) => {
+                if self.db.struct_data(it).flags.contains(StructFlags::IS_MANUALLY_DROP) {
+                    return false;
+                }
+                vec![it.into()]
+            }
+            AdtId::EnumId(it) => {
+                let data = self.db.enum_data(it);
+                data.variants
+                    .iter()
+                    .map(|(local_id, _)| hir_def::EnumVariantId { parent: it, local_id }.into())
+                    .collect()
+            }
+            // Fields of unions are never dropped.
+            AdtId::UnionId(_) => return false,
+        };
+        variants.into_iter().any(|variant| {
+            let field_types = self.db.field_types(variant);
+            let has_drop_glue = field_types
+                .iter()
+                .any(|(_, it)| self.has_drop_glue(it.clone().substitute(Interner, subst)));
+            has_drop_glue
+        })
+    }
+}
@@ -31,8 +31,8 @@ use hir_def::{
     path::{ModPath, Path},
     resolver::{HasResolver, ResolveValueResult, Resolver, TypeNs, ValueNs},
     type_ref::TypeRef,
-    AdtId, AssocItemId, DefWithBodyId, EnumVariantId, FieldId, FunctionId, ItemContainerId, Lookup,
-    TraitId, TypeAliasId, VariantId,
+    AdtId, AssocItemId, DefWithBodyId, EnumVariantId, FieldId, FunctionId, GenericDefId,
+    ItemContainerId, Lookup, TraitId, TypeAliasId, VariantId,
 };
 use hir_expand::name::{name, Name};
 use la_arena::{ArenaMap, Entry};
@@ -234,6 +234,14 @@ pub enum InferenceDiagnostic {
         expr: ExprId,
         expected: Ty,
     },
+    UnsatisfiedTraitBound {
+        call_expr: ExprId,
+        /// The item whose generic predicates contain the bound.
+        callee: GenericDefId,
+        /// The index of the bound in `generic_predicates(callee)`.
+        predicate: usize,
+        trait_ref: TraitRef,
+    },
 }
 
 /// A mismatch between an expected and an inferred type.
@@ -674,6 +682,22 @@ impl<'a> InferenceContext<'a> {
             }
             true
         });
+        for cause in table.take_unfulfilled_obligations() {
+            let trait_ref = table.resolve_completely(cause.trait_ref);
+            // FIXME: Remove this when we are on par with rustc in terms of inference
+            if trait_ref.substitution.type_parameters(Interner).any(|it| it.contains_unknown()) {
+                continue;
+            }
+            let diagnostic = InferenceDiagnostic::UnsatisfiedTraitBound {
+                call_expr: cause.call_expr,
+                callee: cause.callee,
+                predicate: cause.predicate,
+                trait_ref,
+            };
+            if !diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+        }
         for (_, subst) in method_resolutions.values_mut() {
             *subst = table.resolve_completely(subst.clone());
         }
@@ -42,12 +42,12 @@ use crate::{
     traits::FnTrait,
     utils::{generics, Generics},
     Adjust, Adjustment, AdtId, AutoBorrow, Binders, CallableDefId, FnPointer, FnSig, FnSubst,
-    Interner, Rawness, Scalar, Substitution, TraitRef, Ty, TyBuilder, TyExt, TyKind,
+    Interner, Rawness, Scalar, Substitution, TraitRef, Ty, TyBuilder, TyExt, TyKind, WhereClause,
 };
 
 use super::{
-    coerce::auto_deref_adjust_steps, find_breakable, BreakableContext, Diverges, Expectation,
-    InferenceContext, InferenceDiagnostic, TypeMismatch,
+    coerce::auto_deref_adjust_steps, find_breakable, unify::ObligationCause, BreakableContext,
+    Diverges, Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch,
 };
 
 impl InferenceContext<'_> {
@@ -370,7 +370,7 @@ impl InferenceContext<'_> {
                     }
                 };
                 let indices_to_skip = self.check_legacy_const_generics(derefed_callee, args);
-                self.register_obligations_for_call(&callee_ty);
+                self.register_obligations_for_call(tgt_expr, &callee_ty);
 
                 let expected_inputs = self.expected_inputs_for_expected_output(
                     expected,
@@ -1223,7 +1223,7 @@ impl InferenceContext<'_> {
         self.write_method_resolution(tgt_expr, func, subst.clone());
 
         let method_ty = self.db.value_ty(func.into()).substitute(Interner, &subst);
-        self.register_obligations_for_call(&method_ty);
+        self.register_obligations_for_call(tgt_expr, &method_ty);
 
         self.infer_expr_coerce(rhs, &Expectation::has_type(rhs_ty.clone()));
 
@@ -1560,7 +1560,7 @@ impl InferenceContext<'_> {
             }
         };
         let method_ty = method_ty.substitute(Interner, &substs);
-        self.register_obligations_for_call(&method_ty);
+        self.register_obligations_for_call(tgt_expr, &method_ty);
         let (formal_receiver_ty, param_tys, ret_ty, is_varargs) =
             match method_ty.callable_sig(self.db) {
                 Some(sig) => {
@@ -1757,18 +1757,32 @@ impl InferenceContext<'_> {
         Substitution::from_iter(Interner, substs)
     }
 
-    fn register_obligations_for_call(&mut self, callable_ty: &Ty) {
+    fn register_obligations_for_call(&mut self, call_expr: ExprId, callable_ty: &Ty) {
         let callable_ty = self.resolve_ty_shallow(callable_ty);
         if let TyKind::FnDef(fn_def, parameters) = callable_ty.kind(Interner) {
             let def: CallableDefId = from_chalk(self.db, *fn_def);
             let generic_predicates = self.db.generic_predicates(def.into());
-            for predicate in generic_predicates.iter() {
+            let sized_trait = self.resolve_lang_trait(LangItem::Sized).map(to_chalk_trait_id);
+            for (idx, predicate) in generic_predicates.iter().enumerate() {
                 let (predicate, binders) = predicate
                     .clone()
                     .substitute(Interner, parameters)
                     .into_value_and_skipped_binders();
                 always!(binders.len(Interner) == 0); // quantified where clauses not yet handled
-                self.push_obligation(predicate.cast(Interner));
+                match predicate {
+                    // Implicit `Sized` bounds have no origin to point at.
+                    WhereClause::Implemented(trait_ref)
+                        if Some(trait_ref.trait_id) != sized_trait =>
+                    {
+                        self.table.register_obligation_with_cause(ObligationCause {
+                            call_expr,
+                            callee: def.into(),
+                            predicate: idx,
+                            trait_ref,
+                        });
+                    }
+                    predicate => self.push_obligation(predicate.cast(Interner)),
+                }
             }
             // add obligation for trait implementation, if this is a trait method
             match def {
@@ -9,6 +9,7 @@ use chalk_ir::{
 use chalk_solve::infer::ParameterEnaVariableExt;
 use either::Either;
 use ena::unify::UnifyKey;
+use hir_def::{hir::ExprId, GenericDefId};
 use hir_expand::name;
 use stdx::never;
 use triomphe::Arc;
@@ -19,7 +20,7 @@ use crate::{
     to_chalk_trait_id, traits::FnTrait, AliasEq, AliasTy, BoundVar, Canonical, Const, ConstValue,
     DebruijnIndex, GenericArg, GenericArgData, Goal, Guidance, InEnvironment, InferenceVar,
     Interner, Lifetime, ParamKind, ProjectionTy, ProjectionTyExt, Scalar, Solution, Substitution,
-    TraitEnvironment, Ty, TyBuilder, TyExt, TyKind, VariableKind,
+    TraitEnvironment, TraitRef, Ty, TyBuilder, TyExt, TyKind, VariableKind,
 };
 
 impl InferenceContext<'_> {
@@ -142,18 +143,33 @@ bitflags::bitflags! {
 
 type ChalkInferenceTable = chalk_solve::infer::InferenceTable<Interner>;
 
+/// Where an obligation comes from: the `predicate`-th of the `generic_predicates` of `callee`,
+/// which `call_expr` has to fulfill. Obligations with a cause that can't be fulfilled are reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ObligationCause {
+    pub(crate) call_expr: ExprId,
+    pub(crate) callee: GenericDefId,
+    pub(crate) predicate: usize,
+    pub(crate) trait_ref: TraitRef,
+}
+
+type PendingObligation = (Canonicalized<InEnvironment<Goal>>, Option<ObligationCause>);
+
 #[derive(Clone)]
 pub(crate) struct InferenceTable<'a> {
     pub(crate) db: &'a dyn HirDatabase,
     pub(crate) trait_env: Arc<TraitEnvironment>,
     var_unification_table: ChalkInferenceTable,
     type_variable_table: Vec<TypeVariableFlags>,
-    pending_obligations: Vec<Canonicalized<InEnvironment<Goal>>>,
+    pending_obligations: Vec<PendingObligation>,
+    /// The causes of the obligations that the solver proved to have no solution.
+    unfulfilled_obligations: Vec<ObligationCause>,
 }
 
 pub(crate) struct InferenceTableSnapshot {
     var_table_snapshot: chalk_solve::infer::InferenceSnapshot<Interner>,
-    pending_obligations: Vec<Canonicalized<InEnvironment<Goal>>>,
+    pending_obligations: Vec<PendingObligation>,
+    unfulfilled_obligations_len: usize,
     type_variable_table_snapshot: Vec<TypeVariableFlags>,
 }
 
@@ -165,6 +181,7 @@ impl<'a> InferenceTable<'a> {
             var_unification_table: ChalkInferenceTable::new(),
             type_variable_table: Vec::new(),
             pending_obligations: Vec::new(),
+            unfulfilled_obligations: Vec::new(),
         }
     }
 
@@ -468,6 +485,7 @@ impl<'a> InferenceTable<'a> {
         InferenceTableSnapshot {
             var_table_snapshot,
             pending_obligations,
+            unfulfilled_obligations_len: self.unfulfilled_obligations.len(),
             type_variable_table_snapshot,
         }
     }
@@ -476,6 +494,7 @@ impl<'a> InferenceTable<'a> {
         self.var_unification_table.rollback_to(snapshot.var_table_snapshot);
         self.type_variable_table = snapshot.type_variable_table_snapshot;
         self.pending_obligations = snapshot.pending_obligations;
+        self.unfulfilled_obligations.truncate(snapshot.unfulfilled_obligations_len);
     }
 
     pub(crate) fn run_in_snapshot<T>(&mut self, f: impl FnOnce(&mut InferenceTable<'_>) -> T) -> T {
@@ -498,18 +517,35 @@ impl<'a> InferenceTable<'a> {
 
     pub(crate) fn register_obligation(&mut self, goal: Goal) {
         let in_env = InEnvironment::new(&self.trait_env.env, goal);
-        self.register_obligation_in_env(in_env)
+        self.register_obligation_in_env(in_env, None)
+    }
+
+    /// Registers the obligation that `cause.trait_ref` holds, to be reported by
+    /// [`Self::take_unfulfilled_obligations`] if it doesn't.
+    pub(crate) fn register_obligation_with_cause(&mut self, cause: ObligationCause) {
+        let goal = cause.trait_ref.clone().cast(Interner);
+        let in_env = InEnvironment::new(&self.trait_env.env, goal);
+        self.register_obligation_in_env(in_env, Some(cause))
     }
 
-    fn register_obligation_in_env(&mut self, goal: InEnvironment<Goal>) {
+    fn register_obligation_in_env(
+        &mut self,
+        goal: InEnvironment<Goal>,
+        cause: Option<ObligationCause>,
+    ) {
         let canonicalized = self.canonicalize(goal);
-        if !self.try_resolve_obligation(&canonicalized) {
-            self.pending_obligations.push(canonicalized);
+        if !self.try_resolve_obligation(&canonicalized, cause.as_ref()) {
+            self.pending_obligations.push((canonicalized, cause));
         }
     }
 
     pub(crate) fn register_infer_ok<T>(&mut self, infer_ok: InferOk<T>) {
-        infer_ok.goals.into_iter().for_each(|goal| self.register_obligation_in_env(goal));
+        infer_ok.goals.into_iter().for_each(|goal| self.register_obligation_in_env(goal, None));
+    }
+
+    /// Returns the causes of the obligations registered so far that can't be fulfilled.
+    pub(crate) fn take_unfulfilled_obligations(&mut self) -> Vec<ObligationCause> {
+        mem::take(&mut self.unfulfilled_obligations)
     }
 
     pub(crate) fn resolve_obligations_as_possible(&mut self) {
@@ -519,9 +555,9 @@ impl<'a> InferenceTable<'a> {
         while changed {
             changed = false;
             mem::swap(&mut self.pending_obligations, &mut obligations);
-            for canonicalized in obligations.drain(..) {
+            for (canonicalized, cause) in obligations.drain(..) {
                 if !self.check_changed(&canonicalized) {
-                    self.pending_obligations.push(canonicalized);
+                    self.pending_obligations.push((canonicalized, cause));
                     continue;
                 }
                 changed = true;
@@ -530,7 +566,7 @@ impl<'a> InferenceTable<'a> {
                     canonicalized.value.value,
                     Interner,
                 );
-                self.register_obligation_in_env(uncanonical);
+                self.register_obligation_in_env(uncanonical, cause);
             }
         }
     }
@@ -626,6 +662,7 @@ impl<'a> InferenceTable<'a> {
     fn try_resolve_obligation(
         &mut self,
         canonicalized: &Canonicalized<InEnvironment<Goal>>,
+        cause: Option<&ObligationCause>,
     ) -> bool {
         let solution = self.db.trait_solve(
             self.trait_env.krate,
@@ -654,7 +691,9 @@ impl<'a> InferenceTable<'a> {
                 false
             }
             None => {
-                // FIXME obligation cannot be fulfilled => diagnostic
+                if let Some(cause) = cause {
+                    self.unfulfilled_obligations.push(cause.clone());
+                }
                 true
             }
         }
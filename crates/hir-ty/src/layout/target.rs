@@ -10,8 +10,8 @@ pub fn target_data_layout_query(
     db: &dyn HirDatabase,
     krate: CrateId,
 ) -> Option<Arc<TargetDataLayout>> {
-    let crate_graph = db.crate_graph();
-    let target_layout = crate_graph[krate].target_layout.as_ref().ok()?;
+    let crate_data = db.crate_data(krate);
+    let target_layout = crate_data.target_layout.as_ref().ok()?;
     let res = TargetDataLayout::parse_from_llvm_datalayout_string(&target_layout);
     if let Err(_e) = &res {
         // FIXME: Print the error here once it implements debug/display
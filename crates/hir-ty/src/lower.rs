@@ -1559,6 +1559,28 @@ pub(crate) fn generic_predicates_query(
     predicates.into()
 }
 
+/// Returns where the `idx`-th predicate of `generic_predicates(def)` comes from: the item whose
+/// generic parameters declare it, and the index of the where predicate it is lowered from in them.
+/// Implicit `Sized` predicates have no origin.
+pub fn generic_predicate_origin(
+    db: &dyn HirDatabase,
+    def: GenericDefId,
+    idx: usize,
+) -> Option<(GenericDefId, usize)> {
+    // This has to lower the predicates the same way as `generic_predicates_query`, as a where
+    // predicate might be lowered to any number of predicates.
+    let resolver = def.resolver(db.upcast());
+    let ctx = TyLoweringContext::new(db, &resolver, def.into())
+        .with_type_param_mode(ParamLoweringMode::Variable);
+    let origin = resolver
+        .where_predicates_in_scope_with_origin()
+        .flat_map(|(owner, pred_idx, pred)| {
+            ctx.lower_where_predicate(pred, false).map(move |_| (owner, pred_idx))
+        })
+        .nth(idx);
+    origin
+}
+
 /// Generate implicit `: Sized` predicates for all generics that has no `?Sized` bound.
 /// Exception is Self of a trait def.
 fn implicitly_sized_clauses<'a>(
@@ -2048,7 +2070,7 @@ pub(crate) fn const_or_path_to_chalk(
             .unwrap_or_else(|| unknown_const(expected_ty))
         }
         &ConstRef::Complex(it) => {
-            let crate_data = &db.crate_graph()[owner.module(db.upcast()).krate()];
+            let crate_data = db.crate_data(owner.module(db.upcast()).krate());
             if crate_data.env.get("__ra_is_test_fixture").is_none() && crate_data.origin.is_local()
             {
                 // FIXME: current `InTypeConstId` is very unstable, so we only use it in non local crate
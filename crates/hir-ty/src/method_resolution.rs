@@ -6,6 +6,7 @@ use std::ops::ControlFlow;
 
 use base_db::{CrateId, Edition};
 use chalk_ir::{cast::Cast, Mutability, TyKind, UniverseIndex, WhereClause};
+use either::Either;
 use hir_def::{
     data::{adt::StructFlags, ImplData},
     item_scope::ItemScope,
@@ -136,12 +137,13 @@ pub(crate) const ALL_FLOAT_FPS: [TyFingerprint; 2] = [
 pub struct TraitImpls {
     // If the `Option<TyFingerprint>` is `None`, the impl may apply to any self type.
     map: FxHashMap<TraitId, FxHashMap<Option<TyFingerprint>, Vec<ImplId>>>,
+    orphan_impls: Vec<ImplId>,
 }
 
 impl TraitImpls {
     pub(crate) fn trait_impls_in_crate_query(db: &dyn HirDatabase, krate: CrateId) -> Arc<Self> {
         let _p = profile::span("trait_impls_in_crate_query").detail(|| format!("{krate:?}"));
-        let mut impls = Self { map: FxHashMap::default() };
+        let mut impls = Self { map: FxHashMap::default(), orphan_impls: Vec::default() };
 
         let crate_def_map = db.crate_def_map(krate);
         impls.collect_def_map(db, &crate_def_map);
@@ -152,7 +154,7 @@ impl TraitImpls {
 
     pub(crate) fn trait_impls_in_block_query(db: &dyn HirDatabase, block: BlockId) -> Arc<Self> {
         let _p = profile::span("trait_impls_in_block_query");
-        let mut impls = Self { map: FxHashMap::default() };
+        let mut impls = Self { map: FxHashMap::default(), orphan_impls: Vec::default() };
 
         let block_def_map = db.block_def_map(block);
         impls.collect_def_map(db, &block_def_map);
@@ -177,6 +179,7 @@ impl TraitImpls {
     }
 
     fn shrink_to_fit(&mut self) {
+        self.orphan_impls.shrink_to_fit();
         self.map.shrink_to_fit();
         self.map.values_mut().for_each(|map| {
             map.shrink_to_fit();
@@ -190,16 +193,16 @@ impl TraitImpls {
                 // Reservation impls should be ignored during trait resolution, so we never need
                 // them during type analysis. See rust-lang/rust#64631 for details.
                 //
-                // FIXME: Reservation impls should be considered during coherence checks. If we are
-                // (ever) to implement coherence checks, this filtering should be done by the trait
-                // solver.
+                // FIXME: Reservation impls should be considered by the overlap check in
+                // `overlapping_trait_impls`, this filtering should be done by the trait solver.
                 if db.attrs(impl_id.into()).by_key("rustc_reservation_impl").exists() {
                     continue;
                 }
-                let target_trait = match db.impl_trait(impl_id) {
-                    Some(tr) => tr.skip_binders().hir_trait_id(),
-                    None => continue,
-                };
+                let Some(target_trait) = db.impl_trait(impl_id) else { continue };
+                if is_trait_impl_orphan(db, def_map.krate(), target_trait.skip_binders()) {
+                    self.orphan_impls.push(impl_id);
+                }
+                let target_trait = target_trait.skip_binders().hir_trait_id();
                 let self_ty = db.impl_self_ty(impl_id);
                 let self_ty_fp = TyFingerprint::for_trait_impl(self_ty.skip_binders());
                 self.map
@@ -256,6 +259,12 @@ impl TraitImpls {
     pub fn all_impls(&self) -> impl Iterator<Item = ImplId> + '_ {
         self.map.values().flat_map(|map| map.values().flat_map(|v| v.iter().copied()))
     }
+
+    /// Impls that violate the orphan rules, i.e. that implement a foreign trait without involving
+    /// a local type.
+    pub fn orphan_impls(&self) -> &[ImplId] {
+        &self.orphan_impls
+    }
 }
 
 /// Inherent impls defined in some crate.
@@ -854,6 +863,134 @@ fn is_inherent_impl_coherent(
     }
 }
 
+/// Checks whether `trait_ref`, the trait implemented by an impl in `krate`, violates the orphan
+/// rules (see RFC 2451).
+///
+/// Impls with uncovered type parameters before the first local type are not considered orphans
+/// here, as they are a different error (E0210).
+fn is_trait_impl_orphan(db: &dyn HirDatabase, krate: CrateId, trait_ref: &TraitRef) -> bool {
+    if trait_ref.hir_trait_id().lookup(db.upcast()).container.krate() == krate {
+        return false;
+    }
+    for ty in trait_ref.substitution.type_parameters(Interner) {
+        match ty_locality(db, krate, &ty) {
+            TyLocality::Local | TyLocality::Uncovered => return false,
+            TyLocality::Foreign => {}
+        }
+    }
+    true
+}
+
+enum TyLocality {
+    /// The type is defined in the current crate, possibly behind fundamental types like `&`.
+    Local,
+    /// The type is a type parameter not covered by a non-fundamental type, or unknown.
+    Uncovered,
+    Foreign,
+}
+
+fn ty_locality(db: &dyn HirDatabase, krate: CrateId, ty: &Ty) -> TyLocality {
+    let is_local = |module: ModuleId| match module.krate() == krate {
+        true => TyLocality::Local,
+        false => TyLocality::Foreign,
+    };
+    match ty.kind(Interner) {
+        TyKind::Adt(AdtId(adt), subst) => {
+            if adt.module(db.upcast()).krate() == krate {
+                return TyLocality::Local;
+            }
+            let is_fundamental = match adt {
+                hir_def::AdtId::StructId(id) => {
+                    db.struct_data(*id).flags.contains(StructFlags::IS_FUNDAMENTAL)
+                }
+                _ => false,
+            };
+            match subst.type_parameters(Interner).next() {
+                Some(ty) if is_fundamental => ty_locality(db, krate, &ty),
+                _ => TyLocality::Foreign,
+            }
+        }
+        TyKind::Ref(_, _, ty) => ty_locality(db, krate, ty),
+        TyKind::Dyn(_) => match ty.dyn_trait() {
+            Some(trait_) => is_local(trait_.lookup(db.upcast()).container),
+            None => TyLocality::Foreign,
+        },
+        &TyKind::Foreign(id) => {
+            is_local(from_foreign_def_id(id).lookup(db.upcast()).container.module(db.upcast()))
+        }
+        TyKind::BoundVar(_) | TyKind::Placeholder(_) | TyKind::Error => TyLocality::Uncovered,
+        _ => TyLocality::Foreign,
+    }
+}
+
+/// Returns the impls of the same trait that overlap with the trait impl `impl_`, i.e. that could
+/// apply to the same types.
+///
+/// Only impls visible from the crate of `impl_` are considered: the ones of its dependencies, its
+/// own ones and the ones of the block it is defined in. Negative impls, impls of `#[marker]`
+/// traits and impls using specialization are never considered to overlap.
+pub fn overlapping_trait_impls(db: &dyn HirDatabase, impl_: ImplId) -> Vec<ImplId> {
+    let _p = profile::span("overlapping_trait_impls");
+    let Some(trait_ref) = db.impl_trait(impl_) else { return Vec::new() };
+    let trait_ = trait_ref.skip_binders().hir_trait_id();
+    let may_overlap = |impl_: ImplId| {
+        let data = db.impl_data(impl_);
+        let is_specializing = data.items.iter().any(|&it| match it {
+            AssocItemId::FunctionId(it) => db.function_data(it).has_default_kw(),
+            _ => false,
+        });
+        let has_unknown = db.impl_trait(impl_).map_or(true, |it| {
+            it.skip_binders().substitution.type_parameters(Interner).any(|ty| ty.contains_unknown())
+        });
+        !data.is_negative && !is_specializing && !has_unknown
+    };
+    if db.attrs(trait_.into()).by_key("marker").exists() || !may_overlap(impl_) {
+        return Vec::new();
+    }
+
+    let module = impl_.lookup(db.upcast()).container;
+    let krate = module.krate();
+    let block_impls = module.containing_block().map(|it| db.trait_impls_in_block(it));
+    let all_impls = db.trait_impls_in_deps(krate);
+    let self_ty_fp =
+        TyFingerprint::for_trait_impl(&trait_ref.skip_binders().self_type_parameter(Interner));
+    let candidates =
+        all_impls.iter().chain(block_impls.as_ref()).flat_map(|impls| match self_ty_fp {
+            Some(fp) => Either::Left(impls.for_trait_and_self_ty(trait_, fp)),
+            None => Either::Right(impls.for_trait(trait_)),
+        });
+
+    let env =
+        TraitEnvironment { block: module.containing_block(), ..TraitEnvironment::empty(krate) };
+    let mut table = InferenceTable::new(db, Arc::new(env));
+    candidates
+        .filter(|&other| other != impl_ && may_overlap(other))
+        .filter(|&other| {
+            table.run_in_snapshot(|table| {
+                let instantiate = |table: &mut InferenceTable<'_>, impl_: ImplId| {
+                    let subst = TyBuilder::subst_for_def(db, impl_, None)
+                        .fill_with_inference_vars(table)
+                        .build();
+                    let trait_ref = db.impl_trait(impl_)?.substitute(Interner, &subst);
+                    let wcs = crate::chalk_db::convert_where_clauses(db, impl_.into(), &subst);
+                    Some((trait_ref, wcs))
+                };
+                let (Some((trait_ref, wcs)), Some((other_trait_ref, other_wcs))) =
+                    (instantiate(table, impl_), instantiate(table, other))
+                else {
+                    return false;
+                };
+                if !table.unify(&trait_ref, &other_trait_ref) {
+                    return false;
+                }
+                // The impls overlap unless the where clauses of either of them can't hold.
+                let wcs = wcs.into_iter().chain(other_wcs).map(|it| it.cast(Interner));
+                table.try_obligation(crate::Goal::all(Interner, wcs)).is_some()
+            })
+        })
+        .collect()
+}
+
 pub fn iterate_path_candidates(
     ty: &Canonical<Ty>,
     db: &dyn HirDatabase,
@@ -1101,7 +1238,7 @@ fn iterate_trait_method_candidates(
         if data.skip_array_during_method_dispatch && self_is_array {
             // FIXME: this should really be using the edition of the method name's span, in case it
             // comes from a macro
-            if db.crate_graph()[env.krate].edition < Edition::Edition2021 {
+            if db.crate_data(env.krate).edition < Edition::Edition2021 {
                 continue;
             }
         }
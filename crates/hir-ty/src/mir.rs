@@ -18,12 +18,18 @@ use la_arena::{Arena, ArenaMap, Idx, RawIdx};
 mod eval;
 mod lower;
 mod borrowck;
+mod drops;
 mod pretty;
 mod monomorphization;
 
-pub use borrowck::{borrowck_query, BorrowckResult, MutabilityReason};
+pub use borrowck::{
+    borrowck_query, BorrowConflict, BorrowConflictKind, BorrowckResult, MovedValueUse,
+    MutabilityReason,
+};
+pub use drops::{implicit_drops, ImplicitDrop};
 pub use eval::{
-    interpret_mir, pad16, render_const_using_debug_impl, Evaluator, MirEvalError, VTableMap,
+    interpret_mir, interpret_mir_in_sandbox, pad16, render_const_using_debug_impl, Evaluator,
+    MirEvalError, MirEvalFrame, MirEvalLocal, MirEvalObserver, MirEvalSandbox, VTableMap,
 };
 pub use lower::{
     lower_to_mir, mir_body_for_closure_query, mir_body_query, mir_body_recover, MirLowerError,
@@ -933,7 +939,9 @@ pub enum Rvalue {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum StatementKind {
     Assign(Place, Rvalue),
-    //FakeRead(Box<(FakeReadCause, Place)>),
+    /// A read of a place which doesn't produce any value, e.g. the scrutinee of `let _ = x;`. It
+    /// does nothing at runtime, but is needed for analysis like `unused_variables`.
+    FakeRead(Place),
     //SetDiscriminant {
     //    place: Box<Place>,
     //    variant_index: VariantIdx,
@@ -1030,7 +1038,7 @@ impl MirBody {
                             }
                         }
                     }
-                    StatementKind::Deinit(p) => f(p),
+                    StatementKind::FakeRead(p) | StatementKind::Deinit(p) => f(p),
                     StatementKind::StorageLive(_)
                     | StatementKind::StorageDead(_)
                     | StatementKind::Nop => (),
@@ -1094,7 +1102,7 @@ impl MirBody {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum MirSpan {
     ExprId(ExprId),
     PatId(PatId),
@@ -1,12 +1,18 @@
-//! MIR borrow checker, which is used in diagnostics like `unused_mut`
+//! MIR borrow checker, which is used in diagnostics like `unused_mut` and `unused_variables`, and
+//! to report uses of moved values and conflicting borrows.
 
-// Currently it is an ad-hoc implementation, only useful for mutability analysis. Feel free to remove all of these
-// if needed for implementing a proper borrow checker.
+// The mutability analysis is ad-hoc. The move and borrow checks are conservative: they only track
+// whole locals and approximate lifetimes with liveness, and prefer missing an error over reporting
+// a false one.
+
+mod loans;
+mod moves;
 
 use std::iter;
 
 use hir_def::{DefWithBodyId, HasModule};
 use la_arena::ArenaMap;
+use rustc_hash::FxHashSet;
 use stdx::never;
 use triomphe::Arc;
 
@@ -22,8 +28,12 @@ use super::{
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Stores spans which implies that the local should be mutable.
 pub enum MutabilityReason {
-    Mut { spans: Vec<MirSpan> },
+    Mut {
+        spans: Vec<MirSpan>,
+    },
     Not,
+    /// The local is never read nor mutated after its initialization.
+    Unused,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -32,11 +42,44 @@ pub struct MovedOutOfRef {
     pub span: MirSpan,
 }
 
+/// A use of a local that might have been moved out before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovedValueUse {
+    pub local: LocalId,
+    pub span: MirSpan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowConflictKind {
+    /// A mutable borrow of a place that is already borrowed as mutable.
+    SecondMutBorrow,
+    /// A mutable borrow of a place that is already borrowed as shared.
+    MutBorrowOfShared,
+    /// A shared borrow of a place that is already borrowed as mutable.
+    SharedBorrowOfMut,
+    /// A read of a place that is borrowed as mutable.
+    UseOfMutBorrowed,
+    /// A move out of a place that is borrowed.
+    MoveOfBorrowed,
+    /// An assignment to a place that is borrowed.
+    AssignToBorrowed,
+}
+
+/// An access to (a part of) `local` that conflicts with a borrow of it that is still in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowConflict {
+    pub local: LocalId,
+    pub kind: BorrowConflictKind,
+    pub span: MirSpan,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BorrowckResult {
     pub mir_body: Arc<MirBody>,
     pub mutability_of_locals: ArenaMap<LocalId, MutabilityReason>,
     pub moved_out_of_ref: Vec<MovedOutOfRef>,
+    pub moved_value_uses: Vec<MovedValueUse>,
+    pub borrow_conflicts: Vec<BorrowConflict>,
 }
 
 fn all_mir_bodies(
@@ -77,9 +120,12 @@ pub fn borrowck_query(
     let r = all_mir_bodies(db, def)
         .map(|body| {
             let body = body?;
+            let is_copy = is_copy_map(db, &body);
             Ok(BorrowckResult {
                 mutability_of_locals: mutability_of_locals(db, &body),
                 moved_out_of_ref: moved_out_of_ref(db, &body),
+                moved_value_uses: moves::moved_value_uses(&body, &is_copy),
+                borrow_conflicts: loans::borrow_conflicts(db, &body, &is_copy),
                 mir_body: body,
             })
         })
@@ -87,6 +133,97 @@ pub fn borrowck_query(
     Ok(r.into())
 }
 
+/// Returns whether the type of each local is `Copy`. Types with errors count as `Copy`, so that
+/// they are never moved.
+fn is_copy_map(db: &dyn HirDatabase, body: &MirBody) -> ArenaMap<LocalId, bool> {
+    body.locals
+        .iter()
+        .map(|(id, local)| {
+            let is_copy = local.ty.data(Interner).flags.intersects(TypeFlags::HAS_ERROR)
+                || local.ty.clone().is_copy(db, body.owner);
+            (id, is_copy)
+        })
+        .collect()
+}
+
+/// The state of a forward dataflow analysis at some point of a body, see [`forward_dataflow`].
+pub(super) trait DataflowState: Clone {
+    /// Merges `other` into `self`, and returns whether anything changed.
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+impl DataflowState for FxHashSet<LocalId> {
+    fn join(&mut self, other: &Self) -> bool {
+        let len = self.len();
+        self.extend(other.iter().copied());
+        self.len() != len
+    }
+}
+
+/// Runs a forward dataflow analysis until it reaches a fixed point, and returns the state at the
+/// start of each reachable block. `entry` is the state at the start of the body, and `apply_block`
+/// turns the state at the start of a block into the state at its end.
+pub(super) fn forward_dataflow<S: DataflowState>(
+    body: &MirBody,
+    entry: S,
+    mut apply_block: impl FnMut(BasicBlockId, &mut S),
+) -> ArenaMap<BasicBlockId, S> {
+    let mut result: ArenaMap<BasicBlockId, S> = ArenaMap::default();
+    result.insert(body.start_block, entry);
+    let mut worklist = vec![body.start_block];
+    while let Some(block_id) = worklist.pop() {
+        let mut state = result[block_id].clone();
+        apply_block(block_id, &mut state);
+        let Some(terminator) = &body.basic_blocks[block_id].terminator else {
+            continue;
+        };
+        for target in successors(&terminator.kind) {
+            let changed = match result.get_mut(target) {
+                Some(entry) => entry.join(&state),
+                None => {
+                    result.insert(target, state.clone());
+                    true
+                }
+            };
+            if changed {
+                worklist.push(target);
+            }
+        }
+    }
+    result
+}
+
+pub(super) fn successors(terminator: &TerminatorKind) -> Vec<BasicBlockId> {
+    match terminator {
+        TerminatorKind::Goto { target } => vec![*target],
+        TerminatorKind::SwitchInt { targets, .. } => targets.all_targets().to_vec(),
+        TerminatorKind::Call { target, cleanup, .. } => {
+            target.iter().chain(cleanup).copied().collect()
+        }
+        TerminatorKind::Drop { target, unwind, .. }
+        | TerminatorKind::DropAndReplace { target, unwind, .. } => {
+            iter::once(target).chain(unwind).copied().collect()
+        }
+        TerminatorKind::Assert { target, cleanup, .. } => {
+            iter::once(target).chain(cleanup).copied().collect()
+        }
+        TerminatorKind::Yield { resume, drop, .. } => {
+            iter::once(resume).chain(drop).copied().collect()
+        }
+        TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+            vec![*real_target, *imaginary_target]
+        }
+        TerminatorKind::FalseUnwind { real_target, unwind } => {
+            iter::once(real_target).chain(unwind).copied().collect()
+        }
+        TerminatorKind::Resume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable
+        | TerminatorKind::GeneratorDrop => vec![],
+    }
+}
+
 fn moved_out_of_ref(db: &dyn HirDatabase, body: &MirBody) -> Vec<MovedOutOfRef> {
     let mut result = vec![];
     let mut for_operand = |op: &Operand, span: MirSpan| match op {
@@ -148,7 +285,8 @@ fn moved_out_of_ref(db: &dyn HirDatabase, body: &MirBody) -> Vec<MovedOutOfRef>
                         }
                     }
                 },
-                StatementKind::Deinit(_)
+                StatementKind::FakeRead(_)
+                | StatementKind::Deinit(_)
                 | StatementKind::StorageLive(_)
                 | StatementKind::StorageDead(_)
                 | StatementKind::Nop => (),
@@ -267,7 +405,10 @@ fn ever_initialized_map(
                         is_ever_initialized = false;
                     }
                 }
-                StatementKind::Deinit(_) | StatementKind::Nop | StatementKind::StorageLive(_) => (),
+                StatementKind::FakeRead(_)
+                | StatementKind::Deinit(_)
+                | StatementKind::Nop
+                | StatementKind::StorageLive(_) => (),
             }
         }
         let Some(terminator) = &block.terminator else {
@@ -328,11 +469,43 @@ fn mutability_of_locals(
     body: &MirBody,
 ) -> ArenaMap<LocalId, MutabilityReason> {
     let mut result: ArenaMap<LocalId, MutabilityReason> =
-        body.locals.iter().map(|it| (it.0, MutabilityReason::Not)).collect();
-    let mut push_mut_span = |local, span| match &mut result[local] {
-        MutabilityReason::Mut { spans } => spans.push(span),
-        it @ MutabilityReason::Not => *it = MutabilityReason::Mut { spans: vec![span] },
-    };
+        body.locals.iter().map(|it| (it.0, MutabilityReason::Unused)).collect();
+    fn push_mut_span(
+        local: LocalId,
+        span: MirSpan,
+        result: &mut ArenaMap<LocalId, MutabilityReason>,
+    ) {
+        match &mut result[local] {
+            MutabilityReason::Mut { spans } => spans.push(span),
+            it @ (MutabilityReason::Not | MutabilityReason::Unused) => {
+                *it = MutabilityReason::Mut { spans: vec![span] }
+            }
+        }
+    }
+    fn record_usage(local: LocalId, result: &mut ArenaMap<LocalId, MutabilityReason>) {
+        if let it @ MutabilityReason::Unused = &mut result[local] {
+            *it = MutabilityReason::Not;
+        }
+    }
+    fn record_usage_for_place(place: &Place, result: &mut ArenaMap<LocalId, MutabilityReason>) {
+        record_usage(place.local, result);
+        record_usage_for_projection(place, result);
+    }
+    fn record_usage_for_projection(
+        place: &Place,
+        result: &mut ArenaMap<LocalId, MutabilityReason>,
+    ) {
+        for proj in place.projection.iter() {
+            if let ProjectionElem::Index(index) = proj {
+                record_usage(*index, result);
+            }
+        }
+    }
+    fn record_usage_for_operand(op: &Operand, result: &mut ArenaMap<LocalId, MutabilityReason>) {
+        if let Operand::Copy(p) | Operand::Move(p) = op {
+            record_usage_for_place(p, result);
+        }
+    }
     let ever_init_maps = ever_initialized_map(db, body);
     for (block_id, mut ever_init_map) in ever_init_maps.into_iter() {
         let block = &body.basic_blocks[block_id];
@@ -342,23 +515,46 @@ fn mutability_of_locals(
                     match place_case(db, body, place) {
                         ProjectionCase::Direct => {
                             if ever_init_map.get(place.local).copied().unwrap_or_default() {
-                                push_mut_span(place.local, statement.span);
+                                push_mut_span(place.local, statement.span, &mut result);
                             } else {
                                 ever_init_map.insert(place.local, true);
                             }
                         }
                         ProjectionCase::DirectPart => {
                             // Partial initialization is not supported, so it is definitely `mut`
-                            push_mut_span(place.local, statement.span);
+                            push_mut_span(place.local, statement.span, &mut result);
                         }
-                        ProjectionCase::Indirect => (),
+                        ProjectionCase::Indirect => record_usage(place.local, &mut result),
+                    }
+                    record_usage_for_projection(place, &mut result);
+                    match value {
+                        Rvalue::CopyForDeref(p)
+                        | Rvalue::Discriminant(p)
+                        | Rvalue::Len(p)
+                        | Rvalue::Ref(_, p) => record_usage_for_place(p, &mut result),
+                        Rvalue::Use(o)
+                        | Rvalue::Repeat(o, _)
+                        | Rvalue::Cast(_, o, _)
+                        | Rvalue::ShallowInitBox(o, _)
+                        | Rvalue::UnaryOp(_, o) => record_usage_for_operand(o, &mut result),
+                        Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                            record_usage_for_operand(o1, &mut result);
+                            record_usage_for_operand(o2, &mut result);
+                        }
+                        Rvalue::Aggregate(_, ops) => {
+                            for op in ops.iter() {
+                                record_usage_for_operand(op, &mut result);
+                            }
+                        }
+                        Rvalue::ShallowInitBoxWithAlloc(_) => (),
                     }
                     if let Rvalue::Ref(BorrowKind::Mut { .. }, p) = value {
                         if place_case(db, body, p) != ProjectionCase::Indirect {
-                            push_mut_span(p.local, statement.span);
+                            push_mut_span(p.local, statement.span, &mut result);
                         }
                     }
                 }
+                StatementKind::FakeRead(p) => record_usage_for_place(p, &mut result),
                 StatementKind::StorageDead(p) => {
                     ever_init_map.insert(*p, false);
                 }
@@ -378,19 +574,28 @@ fn mutability_of_locals(
             | TerminatorKind::FalseEdge { .. }
             | TerminatorKind::FalseUnwind { .. }
             | TerminatorKind::GeneratorDrop
-            | TerminatorKind::SwitchInt { .. }
-            | TerminatorKind::Drop { .. }
-            | TerminatorKind::DropAndReplace { .. }
-            | TerminatorKind::Assert { .. }
-            | TerminatorKind::Yield { .. } => (),
-            TerminatorKind::Call { destination, .. } => {
+            | TerminatorKind::Drop { .. } => (),
+            TerminatorKind::SwitchInt { discr, .. } => record_usage_for_operand(discr, &mut result),
+            TerminatorKind::DropAndReplace { value, .. } => {
+                record_usage_for_operand(value, &mut result)
+            }
+            TerminatorKind::Assert { cond, .. } => record_usage_for_operand(cond, &mut result),
+            TerminatorKind::Yield { value, .. } => record_usage_for_operand(value, &mut result),
+            TerminatorKind::Call { func, args, destination, .. } => {
+                record_usage_for_operand(func, &mut result);
+                for arg in args.iter() {
+                    record_usage_for_operand(arg, &mut result);
+                }
                 if destination.projection.len() == 0 {
                     if ever_init_map.get(destination.local).copied().unwrap_or_default() {
-                        push_mut_span(destination.local, MirSpan::Unknown);
+                        push_mut_span(destination.local, MirSpan::Unknown, &mut result);
                     } else {
                         ever_init_map.insert(destination.local, true);
                     }
+                } else if place_case(db, body, destination) == ProjectionCase::Indirect {
+                    record_usage(destination.local, &mut result);
                 }
+                record_usage_for_projection(destination, &mut result);
             }
         }
     }
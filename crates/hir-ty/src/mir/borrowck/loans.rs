@@ -0,0 +1,690 @@
+//! Finds accesses to places that conflict with borrows of them which are still in use.
+//!
+//! A borrow is in use as long as a local that holds the resulting reference, or something derived
+//! from it, is live, that is it might be used later (a much simplified version of NLL). Locals are
+//! assumed to hold the borrows of everything they are computed from, and function calls are assumed
+//! to return the borrows of their arguments if the returned type can contain references.
+//!
+//! Mutable borrows into temporaries are two-phase borrows: they only conflict with other borrows
+//! once the temporary is used, which allows things like `v.push(v.len())`.
+
+use la_arena::ArenaMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use hir_def::{
+    hir::type_ref::{LifetimeRef, TypeBound, TypeRef},
+    path::{GenericArg, Path},
+};
+use hir_expand::name::{name, Name};
+
+use crate::{
+    db::HirDatabase,
+    mir::{
+        BasicBlockId, BorrowKind, LocalId, MirBody, MirSpan, Operand, Place, ProjectionElem,
+        Rvalue, StatementKind, TerminatorKind,
+    },
+    utils::generics,
+    CallableDefId, Interner, Ty, TyKind,
+};
+
+use super::{
+    forward_dataflow, place_case, successors, BorrowConflict, BorrowConflictKind, DataflowState,
+    ProjectionCase,
+};
+
+pub(super) fn borrow_conflicts(
+    db: &dyn HirDatabase,
+    body: &MirBody,
+    is_copy: &ArenaMap<LocalId, bool>,
+) -> Vec<BorrowConflict> {
+    let mut binding_locals: FxHashSet<LocalId> = body.binding_locals.values().copied().collect();
+    binding_locals.extend(body.param_locals.iter().copied());
+    let mut cx = LoansCtx {
+        db,
+        body,
+        is_copy,
+        binding_locals,
+        loans: vec![],
+        loan_of_statement: FxHashMap::default(),
+        result: vec![],
+    };
+    cx.collect_loans();
+    if cx.loans.is_empty() {
+        return vec![];
+    }
+    let live_after = live_after_map(body);
+    let states = cx.loan_state_map(&live_after);
+    for (block_id, state) in states.iter() {
+        let mut state = state.clone();
+        cx.apply_block(&live_after, block_id, &mut state, true);
+    }
+    cx.result
+}
+
+type LoanId = usize;
+
+#[derive(Debug)]
+struct Loan {
+    place: Place,
+    mutable: bool,
+    /// Mutable borrows into temporaries are reserved until the temporary is first used.
+    two_phase: bool,
+    span: MirSpan,
+}
+
+#[derive(Debug, Default, Clone)]
+struct LoanState {
+    /// The loans that a local might hold.
+    holds: FxHashMap<LocalId, FxHashSet<LoanId>>,
+    /// Two-phase loans that might not be activated yet.
+    reserved: FxHashSet<LoanId>,
+}
+
+impl DataflowState for LoanState {
+    fn join(&mut self, other: &LoanState) -> bool {
+        let mut changed = false;
+        for (local, loans) in &other.holds {
+            let entry = self.holds.entry(*local).or_default();
+            let len = entry.len();
+            entry.extend(loans.iter().copied());
+            changed |= entry.len() != len;
+        }
+        let len = self.reserved.len();
+        self.reserved.extend(other.reserved.iter().copied());
+        changed | (self.reserved.len() != len)
+    }
+}
+
+impl LoanState {
+    fn loans_of(&self, local: LocalId) -> impl Iterator<Item = LoanId> + '_ {
+        self.holds.get(&local).into_iter().flatten().copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Move,
+    SharedBorrow,
+    MutBorrow,
+    Assign,
+}
+
+struct LoansCtx<'a> {
+    db: &'a dyn HirDatabase,
+    body: &'a MirBody,
+    is_copy: &'a ArenaMap<LocalId, bool>,
+    binding_locals: FxHashSet<LocalId>,
+    loans: Vec<Loan>,
+    loan_of_statement: FxHashMap<(BasicBlockId, usize), LoanId>,
+    result: Vec<BorrowConflict>,
+}
+
+/// Where to look for the loans that are in use at an access.
+#[derive(Clone, Copy)]
+struct InUse<'a> {
+    /// The locals that are live after the access.
+    live: &'a FxHashSet<LocalId>,
+    /// The local that is overwritten by the access, so its current loans end.
+    dest: Option<LocalId>,
+    /// The locals which are used together with the accessed place, like other call arguments.
+    other_operands: &'a [LocalId],
+}
+
+type LiveAfterMap = ArenaMap<BasicBlockId, Vec<FxHashSet<LocalId>>>;
+
+impl LoansCtx<'_> {
+    fn collect_loans(&mut self) {
+        for (block_id, block) in self.body.basic_blocks.iter() {
+            for (idx, statement) in block.statements.iter().enumerate() {
+                let StatementKind::Assign(dest, Rvalue::Ref(kind, place)) = &statement.kind else {
+                    continue;
+                };
+                // Reborrows through references don't borrow any local, they keep the borrows that
+                // the reference holds instead.
+                if place_case(self.db, self.body, place) == ProjectionCase::Indirect {
+                    continue;
+                }
+                let mutable = match kind {
+                    BorrowKind::Shared => false,
+                    BorrowKind::Mut { .. } | BorrowKind::Unique => true,
+                    BorrowKind::Shallow => continue,
+                };
+                let two_phase = mutable
+                    && dest.projection.is_empty()
+                    && !self.binding_locals.contains(&dest.local);
+                self.loan_of_statement.insert((block_id, idx), self.loans.len());
+                self.loans.push(Loan {
+                    place: place.clone(),
+                    mutable,
+                    two_phase,
+                    span: statement.span,
+                });
+            }
+        }
+    }
+
+    /// Returns the loans that each local might hold at the start of each reachable block.
+    fn loan_state_map(&mut self, live_after: &LiveAfterMap) -> ArenaMap<BasicBlockId, LoanState> {
+        let body = self.body;
+        forward_dataflow(body, LoanState::default(), |block_id, state| {
+            self.apply_block(live_after, block_id, state, false)
+        })
+    }
+
+    fn apply_block(
+        &mut self,
+        live_after: &LiveAfterMap,
+        block_id: BasicBlockId,
+        state: &mut LoanState,
+        report: bool,
+    ) {
+        let body = self.body;
+        let block = &body.basic_blocks[block_id];
+        for (idx, statement) in block.statements.iter().enumerate() {
+            let span = statement.span;
+            match &statement.kind {
+                StatementKind::Assign(dest, value) => {
+                    let dest_local = dest.projection.is_empty().then_some(dest.local);
+                    let in_use = InUse {
+                        live: &live_after[block_id][idx],
+                        dest: dest_local,
+                        other_operands: &[],
+                    };
+                    let mut accesses = vec![];
+                    let mut incoming = FxHashSet::default();
+                    match value {
+                        Rvalue::Use(it)
+                        | Rvalue::Repeat(it, _)
+                        | Rvalue::Cast(_, it, _)
+                        | Rvalue::ShallowInitBox(it, _)
+                        | Rvalue::UnaryOp(_, it) => self.operand(it, &mut accesses),
+                        // Builtin operators only read their operands, this is also how constant
+                        // patterns are compared.
+                        Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                            for it in [lhs, rhs] {
+                                if let Operand::Copy(it) | Operand::Move(it) = it {
+                                    accesses.push((it, Access::Read));
+                                }
+                            }
+                        }
+                        Rvalue::Aggregate(_, it) => {
+                            it.iter().for_each(|it| self.operand(it, &mut accesses))
+                        }
+                        Rvalue::Ref(kind, place) => {
+                            match self.loan_of_statement.get(&(block_id, idx)) {
+                                Some(&loan) => {
+                                    let access = match kind {
+                                        _ if self.loans[loan].two_phase => None,
+                                        BorrowKind::Shared => Some(Access::SharedBorrow),
+                                        _ => Some(Access::MutBorrow),
+                                    };
+                                    if let Some(access) = access {
+                                        accesses.push((place, access));
+                                    }
+                                    incoming.insert(loan);
+                                    if self.loans[loan].two_phase {
+                                        state.reserved.insert(loan);
+                                    }
+                                }
+                                None => accesses.push((place, Access::Read)),
+                            }
+                            incoming.extend(state.loans_of(place.local));
+                        }
+                        Rvalue::Len(it) | Rvalue::Discriminant(it) | Rvalue::CopyForDeref(it) => {
+                            accesses.push((it, Access::Read))
+                        }
+                        Rvalue::ShallowInitBoxWithAlloc(_) => (),
+                    }
+                    if place_case(self.db, body, dest) != ProjectionCase::Indirect {
+                        accesses.push((dest, Access::Assign));
+                    }
+                    if report {
+                        for &(place, access) in &accesses {
+                            self.check_access(state, in_use, place, access, span, None);
+                        }
+                    }
+                    for &(place, access) in &accesses {
+                        if access == Access::Assign {
+                            continue;
+                        }
+                        self.activate(state, place.local, in_use, report);
+                        if access != Access::SharedBorrow && access != Access::MutBorrow {
+                            incoming.extend(state.loans_of(place.local));
+                        }
+                        if access == Access::Move {
+                            state.holds.remove(&place.local);
+                        }
+                    }
+                    match dest_local {
+                        Some(local) => {
+                            state.holds.insert(local, incoming);
+                        }
+                        None if place_case(self.db, body, dest) != ProjectionCase::Indirect => {
+                            state.holds.entry(dest.local).or_default().extend(incoming)
+                        }
+                        None => (),
+                    }
+                }
+                StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                    state.holds.remove(local);
+                }
+                StatementKind::Deinit(_) | StatementKind::FakeRead(_) | StatementKind::Nop => (),
+            }
+        }
+        let Some(terminator) = &block.terminator else {
+            return;
+        };
+        let span = terminator.span;
+        let live = &live_after[block_id][block.statements.len()];
+        match &terminator.kind {
+            TerminatorKind::Call { func, args, destination, .. } => {
+                let dest_local = destination.projection.is_empty().then_some(destination.local);
+                let mut accesses = vec![];
+                self.operand(func, &mut accesses);
+                args.iter().for_each(|it| self.operand(it, &mut accesses));
+                let arg_locals: Vec<LocalId> = accesses.iter().map(|(it, _)| it.local).collect();
+                let in_use = InUse { live, dest: dest_local, other_operands: &arg_locals };
+                if report {
+                    for &(place, access) in &accesses {
+                        self.check_access(state, in_use, place, access, span, None);
+                    }
+                }
+                let mut incoming = FxHashSet::default();
+                for &(place, access) in &accesses {
+                    self.activate(state, place.local, in_use, report);
+                    incoming.extend(state.loans_of(place.local));
+                    if access == Access::Move {
+                        state.holds.remove(&place.local);
+                    }
+                }
+                let ty = &body.locals[destination.local].ty;
+                if let Some(local) = dest_local {
+                    if may_contain_references(ty, returns_borrows_of_args(self.db, func)) {
+                        state.holds.insert(local, incoming);
+                    } else {
+                        state.holds.remove(&local);
+                    }
+                }
+            }
+            TerminatorKind::SwitchInt { discr, .. } => {
+                let mut accesses = vec![];
+                self.operand(discr, &mut accesses);
+                if report {
+                    for &(place, access) in &accesses {
+                        let in_use = InUse { live, dest: None, other_operands: &[] };
+                        self.check_access(state, in_use, place, access, span, None);
+                    }
+                }
+            }
+            TerminatorKind::Drop { .. }
+            | TerminatorKind::DropAndReplace { .. }
+            | TerminatorKind::Assert { .. }
+            | TerminatorKind::Yield { .. }
+            | TerminatorKind::Goto { .. }
+            | TerminatorKind::Resume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => (),
+        }
+    }
+
+    fn operand<'b>(&self, operand: &'b Operand, accesses: &mut Vec<(&'b Place, Access)>) {
+        let (Operand::Copy(place) | Operand::Move(place)) = operand else {
+            return;
+        };
+        // Lowering doesn't distinguish copies from moves, so the type decides.
+        let access = if place.projection.is_empty() && !self.is_copy[place.local] {
+            Access::Move
+        } else {
+            Access::Read
+        };
+        accesses.push((place, access));
+    }
+
+    /// Activates the reserved two-phase loans held by `local`, which is about to be used.
+    fn activate(&mut self, state: &mut LoanState, local: LocalId, in_use: InUse<'_>, report: bool) {
+        let activated: Vec<LoanId> =
+            state.loans_of(local).filter(|it| state.reserved.contains(it)).collect();
+        for loan in activated {
+            state.reserved.remove(&loan);
+            if report {
+                let place = self.loans[loan].place.clone();
+                let span = self.loans[loan].span;
+                self.check_access(state, in_use, &place, Access::MutBorrow, span, Some(loan));
+            }
+        }
+    }
+
+    /// Reports the first loan in use that conflicts with `access` to `place`, ignoring `excluded`.
+    fn check_access(
+        &mut self,
+        state: &LoanState,
+        in_use: InUse<'_>,
+        place: &Place,
+        access: Access,
+        span: MirSpan,
+        excluded: Option<LoanId>,
+    ) {
+        let holders =
+            in_use.live.iter().filter(|&&it| Some(it) != in_use.dest).chain(in_use.other_operands);
+        let mut conflict = None;
+        for loan_id in holders.flat_map(|&it| state.loans_of(it)) {
+            if Some(loan_id) == excluded || state.reserved.contains(&loan_id) {
+                continue;
+            }
+            let loan = &self.loans[loan_id];
+            if !places_overlap(&loan.place, place) {
+                continue;
+            }
+            conflict = match (access, loan.mutable) {
+                (Access::Read, true) => Some(BorrowConflictKind::UseOfMutBorrowed),
+                (Access::SharedBorrow, true) => Some(BorrowConflictKind::SharedBorrowOfMut),
+                (Access::MutBorrow, true) => Some(BorrowConflictKind::SecondMutBorrow),
+                (Access::MutBorrow, false) => Some(BorrowConflictKind::MutBorrowOfShared),
+                (Access::Move, _) => Some(BorrowConflictKind::MoveOfBorrowed),
+                (Access::Assign, _) => Some(BorrowConflictKind::AssignToBorrowed),
+                (Access::Read | Access::SharedBorrow, false) => None,
+            };
+            if conflict.is_some() {
+                break;
+            }
+        }
+        if let Some(kind) = conflict {
+            let it = BorrowConflict { local: place.local, kind, span };
+            if !self.result.contains(&it) {
+                self.result.push(it);
+            }
+        }
+    }
+}
+
+/// Places overlap unless they diverge at different fields of the same value.
+fn places_overlap(a: &Place, b: &Place) -> bool {
+    if a.local != b.local {
+        return false;
+    }
+    for (a, b) in a.projection.iter().zip(b.projection.iter()) {
+        match (a, b) {
+            (ProjectionElem::Field(a), ProjectionElem::Field(b)) if a != b => return false,
+            (ProjectionElem::TupleOrClosureField(a), ProjectionElem::TupleOrClosureField(b))
+                if a != b =>
+            {
+                return false
+            }
+            (ProjectionElem::Field(_) | ProjectionElem::TupleOrClosureField(_), _)
+            | (ProjectionElem::Deref, ProjectionElem::Deref)
+            | (ProjectionElem::OpaqueCast(_), ProjectionElem::OpaqueCast(_)) => (),
+            _ => return true,
+        }
+    }
+    true
+}
+
+/// Returns whether values of `ty` might hold borrows of locals.
+///
+/// The lifetimes of trait objects and opaque types aren't lowered, so these are assumed to hold
+/// borrows only if `linked`, that is if they are returned by a function whose signature ties a
+/// lifetime of its output to its arguments.
+fn may_contain_references(ty: &Ty, linked: bool) -> bool {
+    match ty.kind(Interner) {
+        TyKind::Scalar(_)
+        | TyKind::Str
+        | TyKind::Never
+        | TyKind::FnDef(..)
+        | TyKind::Function(_)
+        | TyKind::Foreign(_) => false,
+        // Generic parameters are chosen by the caller of the body, so they outlive its locals.
+        TyKind::Placeholder(_) => false,
+        TyKind::Tuple(_, subst) => subst
+            .iter(Interner)
+            .filter_map(|it| it.ty(Interner))
+            .any(|it| may_contain_references(it, linked)),
+        TyKind::Array(ty, _) | TyKind::Slice(ty) => may_contain_references(ty, linked),
+        // Lifetimes aren't inferred, so any lifetime argument might be a borrow.
+        TyKind::Adt(_, subst) => subst.iter(Interner).any(|it| match it.ty(Interner) {
+            Some(ty) => may_contain_references(ty, linked),
+            None => it.lifetime(Interner).is_some(),
+        }),
+        TyKind::Dyn(_)
+        | TyKind::OpaqueType(..)
+        | TyKind::Alias(_)
+        | TyKind::AssociatedType(..)
+        | TyKind::Error => linked,
+        TyKind::Ref(..)
+        | TyKind::Raw(..)
+        | TyKind::Closure(..)
+        | TyKind::Generator(..)
+        | TyKind::GeneratorWitness(..)
+        | TyKind::BoundVar(_)
+        | TyKind::InferenceVar(..) => true,
+    }
+}
+
+/// Returns whether the signature of the called function `func` ties the lifetime of its output to
+/// one of its arguments, either through a lifetime or through a generic parameter which both
+/// mention.
+fn returns_borrows_of_args(db: &dyn HirDatabase, func: &Operand) -> bool {
+    let Operand::Constant(c) = func else {
+        return true;
+    };
+    let TyKind::FnDef(def, _) = c.data(Interner).ty.kind(Interner) else {
+        return true;
+    };
+    let CallableDefId::FunctionId(func) = db.lookup_intern_callable_def((*def).into()) else {
+        // Constructors return their arguments.
+        return true;
+    };
+    let data = db.function_data(func);
+    let generics = generics(db.upcast(), func.into());
+    let type_params: FxHashSet<Name> = generics
+        .iter()
+        .filter_map(|(_, it)| it.name().cloned())
+        .chain(std::iter::once(name![Self]))
+        .collect();
+    let mut inputs = SignatureMentions::default();
+    for param in data.params.iter() {
+        inputs.collect(param, &type_params);
+    }
+    let mut output = SignatureMentions::default();
+    output.collect(&data.ret_type, &type_params);
+
+    let fn_lifetimes: FxHashSet<&Name> =
+        generics.params.lifetimes.iter().map(|(_, it)| &it.name).collect();
+    let lifetime_linked = output.lifetimes.iter().any(|lifetime| match lifetime {
+        // Elided lifetimes of the output come from one of the arguments.
+        None => !inputs.lifetimes.is_empty(),
+        Some(name) if *name == name!['static] => false,
+        // Lifetimes of the impl might be the ones of `self`.
+        Some(name) => inputs.lifetimes.contains(lifetime) || !fn_lifetimes.contains(name),
+    });
+    lifetime_linked || !output.type_params.is_disjoint(&inputs.type_params)
+}
+
+/// The lifetimes and generic parameters written in a part of a function signature.
+#[derive(Default)]
+struct SignatureMentions {
+    /// `None` stands for elided lifetimes.
+    lifetimes: FxHashSet<Option<Name>>,
+    type_params: FxHashSet<Name>,
+}
+
+impl SignatureMentions {
+    fn collect(&mut self, type_ref: &TypeRef, type_params: &FxHashSet<Name>) {
+        type_ref.walk(&mut |it| match it {
+            TypeRef::Reference(_, lifetime, _) => self.lifetime(lifetime.as_ref()),
+            TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
+                for bound in bounds {
+                    match bound.as_ref() {
+                        TypeBound::Lifetime(it) => self.lifetime(Some(it)),
+                        TypeBound::Path(it, _) | TypeBound::ForLifetime(_, it) => {
+                            self.path(it, type_params)
+                        }
+                        TypeBound::Error => (),
+                    }
+                }
+            }
+            TypeRef::Path(it) => self.path(it, type_params),
+            _ => (),
+        });
+    }
+
+    fn lifetime(&mut self, lifetime: Option<&LifetimeRef>) {
+        let name = lifetime.map(|it| &it.name).filter(|it| it.to_smol_str() != "'_");
+        self.lifetimes.insert(name.cloned());
+    }
+
+    fn path(&mut self, path: &Path, type_params: &FxHashSet<Name>) {
+        let segments = path.segments();
+        for segment in segments.iter() {
+            for arg in segment.args_and_bindings.iter().flat_map(|it| it.args.iter()) {
+                if let GenericArg::Lifetime(it) = arg {
+                    self.lifetime(Some(it));
+                }
+            }
+        }
+        if let Some(first) = segments.first() {
+            if type_params.contains(first.name) {
+                self.type_params.insert(first.name.clone());
+            }
+        }
+    }
+}
+
+/// Returns the locals that are live after each statement and the terminator of each block.
+///
+/// Locals are only used by reading them or by creating references to them. Drops are not counted
+/// as uses, so locals whose destructors use borrows don't keep them alive.
+fn live_after_map(body: &MirBody) -> ArenaMap<BasicBlockId, Vec<FxHashSet<LocalId>>> {
+    let mut live_in: ArenaMap<BasicBlockId, FxHashSet<LocalId>> =
+        body.basic_blocks.iter().map(|(id, _)| (id, FxHashSet::default())).collect();
+    let mut predecessors: ArenaMap<BasicBlockId, Vec<BasicBlockId>> =
+        body.basic_blocks.iter().map(|(id, _)| (id, vec![])).collect();
+    for (id, block) in body.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            for target in successors(&terminator.kind) {
+                predecessors[target].push(id);
+            }
+        }
+    }
+    let live_out = |live_in: &ArenaMap<BasicBlockId, FxHashSet<LocalId>>, id: BasicBlockId| {
+        let mut result = FxHashSet::default();
+        if let Some(terminator) = &body.basic_blocks[id].terminator {
+            for target in successors(&terminator.kind) {
+                result.extend(live_in[target].iter().copied());
+            }
+        }
+        result
+    };
+    let mut worklist: Vec<BasicBlockId> = body.basic_blocks.iter().map(|(id, _)| id).collect();
+    while let Some(id) = worklist.pop() {
+        let mut live = live_out(&live_in, id);
+        transfer_block_backwards(body, id, &mut live, &mut |_, _| ());
+        if live != live_in[id] {
+            live_in[id] = live;
+            worklist.extend(predecessors[id].iter().copied());
+        }
+    }
+    let mut result = ArenaMap::default();
+    for (id, block) in body.basic_blocks.iter() {
+        let mut live = live_out(&live_in, id);
+        let mut per_statement = vec![FxHashSet::default(); block.statements.len() + 1];
+        transfer_block_backwards(body, id, &mut live, &mut |idx, live| {
+            per_statement[idx] = live.clone();
+        });
+        result.insert(id, per_statement);
+    }
+    result
+}
+
+/// Applies the statements of a block to `live` in reverse order, calling `after` with the locals
+/// that are live after each of them.
+fn transfer_block_backwards(
+    body: &MirBody,
+    id: BasicBlockId,
+    live: &mut FxHashSet<LocalId>,
+    after: &mut dyn FnMut(usize, &FxHashSet<LocalId>),
+) {
+    fn use_place(place: &Place, live: &mut FxHashSet<LocalId>) {
+        live.insert(place.local);
+        for proj in place.projection.iter() {
+            if let ProjectionElem::Index(index) = proj {
+                live.insert(*index);
+            }
+        }
+    }
+    fn use_operand(operand: &Operand, live: &mut FxHashSet<LocalId>) {
+        if let Operand::Copy(place) | Operand::Move(place) = operand {
+            use_place(place, live);
+        }
+    }
+    fn assign(place: &Place, live: &mut FxHashSet<LocalId>) {
+        if place.projection.is_empty() {
+            live.remove(&place.local);
+        } else if place.projection.iter().any(|it| matches!(it, ProjectionElem::Deref)) {
+            use_place(place, live);
+        } else {
+            for proj in place.projection.iter() {
+                if let ProjectionElem::Index(index) = proj {
+                    live.insert(*index);
+                }
+            }
+        }
+    }
+    let block = &body.basic_blocks[id];
+    after(block.statements.len(), live);
+    if let Some(terminator) = &block.terminator {
+        match &terminator.kind {
+            TerminatorKind::Call { func, args, destination, .. } => {
+                assign(destination, live);
+                use_operand(func, live);
+                args.iter().for_each(|it| use_operand(it, live));
+            }
+            TerminatorKind::SwitchInt { discr, .. } => use_operand(discr, live),
+            TerminatorKind::Assert { cond, .. } => use_operand(cond, live),
+            TerminatorKind::Yield { value, .. } => use_operand(value, live),
+            TerminatorKind::DropAndReplace { value, .. } => use_operand(value, live),
+            TerminatorKind::Drop { .. }
+            | TerminatorKind::Goto { .. }
+            | TerminatorKind::Resume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => (),
+        }
+    }
+    for (idx, statement) in block.statements.iter().enumerate().rev() {
+        after(idx, live);
+        match &statement.kind {
+            StatementKind::Assign(place, value) => {
+                assign(place, live);
+                match value {
+                    Rvalue::Use(it)
+                    | Rvalue::Repeat(it, _)
+                    | Rvalue::Cast(_, it, _)
+                    | Rvalue::ShallowInitBox(it, _)
+                    | Rvalue::UnaryOp(_, it) => use_operand(it, live),
+                    Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                        use_operand(lhs, live);
+                        use_operand(rhs, live);
+                    }
+                    Rvalue::Aggregate(_, it) => it.iter().for_each(|it| use_operand(it, live)),
+                    Rvalue::Ref(_, it)
+                    | Rvalue::Len(it)
+                    | Rvalue::Discriminant(it)
+                    | Rvalue::CopyForDeref(it) => use_place(it, live),
+                    Rvalue::ShallowInitBoxWithAlloc(_) => (),
+                }
+            }
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                live.remove(local);
+            }
+            StatementKind::Deinit(_) | StatementKind::FakeRead(_) | StatementKind::Nop => (),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Finds uses of locals that might have been moved out on some path that reaches the use.
+//!
+//! Only moves of whole locals are tracked, so partially moved values are never reported.
+
+use la_arena::ArenaMap;
+use rustc_hash::FxHashSet;
+
+use crate::mir::{
+    BasicBlockId, LocalId, MirBody, MirSpan, Operand, Place, ProjectionElem, Rvalue, StatementKind,
+    TerminatorKind,
+};
+
+use super::{forward_dataflow, MovedValueUse};
+
+pub(super) fn moved_value_uses(
+    body: &MirBody,
+    is_copy: &ArenaMap<LocalId, bool>,
+) -> Vec<MovedValueUse> {
+    let cx = MovesCtx { body, is_copy };
+    let maybe_moved = cx.maybe_moved_map();
+    let mut result = vec![];
+    for (block_id, state) in maybe_moved.iter() {
+        let mut state = state.clone();
+        let mut report = |local, span| {
+            let it = MovedValueUse { local, span };
+            if !result.contains(&it) {
+                result.push(it);
+            }
+        };
+        cx.apply_block(block_id, &mut state, &mut report);
+    }
+    result
+}
+
+struct MovesCtx<'a> {
+    body: &'a MirBody,
+    is_copy: &'a ArenaMap<LocalId, bool>,
+}
+
+impl MovesCtx<'_> {
+    /// Returns the locals that might be moved out at the start of each reachable block.
+    fn maybe_moved_map(&self) -> ArenaMap<BasicBlockId, FxHashSet<LocalId>> {
+        forward_dataflow(self.body, FxHashSet::default(), |block_id, state| {
+            self.apply_block(block_id, state, &mut |_, _| ())
+        })
+    }
+
+    fn apply_block(
+        &self,
+        block_id: BasicBlockId,
+        state: &mut FxHashSet<LocalId>,
+        report: &mut dyn FnMut(LocalId, MirSpan),
+    ) {
+        let block = &self.body.basic_blocks[block_id];
+        for statement in &block.statements {
+            let span = statement.span;
+            match &statement.kind {
+                StatementKind::Assign(place, value) => {
+                    match value {
+                        Rvalue::Use(it)
+                        | Rvalue::Repeat(it, _)
+                        | Rvalue::Cast(_, it, _)
+                        | Rvalue::ShallowInitBox(it, _)
+                        | Rvalue::UnaryOp(_, it) => self.operand(it, span, state, report),
+                        // Builtin operators only read their operands, this is also how constant
+                        // patterns are compared.
+                        Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                            for it in [lhs, rhs] {
+                                if let Operand::Copy(it) | Operand::Move(it) = it {
+                                    self.use_place(it, span, state, report);
+                                }
+                            }
+                        }
+                        Rvalue::Aggregate(_, it) => {
+                            it.iter().for_each(|it| self.operand(it, span, state, report))
+                        }
+                        Rvalue::Ref(_, it)
+                        | Rvalue::Len(it)
+                        | Rvalue::Discriminant(it)
+                        | Rvalue::CopyForDeref(it) => self.use_place(it, span, state, report),
+                        Rvalue::ShallowInitBoxWithAlloc(_) => (),
+                    }
+                    self.assign(place, span, state, report);
+                }
+                StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                    state.remove(local);
+                }
+                StatementKind::Deinit(_) | StatementKind::FakeRead(_) | StatementKind::Nop => (),
+            }
+        }
+        let Some(terminator) = &block.terminator else {
+            return;
+        };
+        let span = terminator.span;
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr, .. } => self.operand(discr, span, state, report),
+            TerminatorKind::Call { func, args, destination, .. } => {
+                self.operand(func, span, state, report);
+                args.iter().for_each(|it| self.operand(it, span, state, report));
+                self.assign(destination, span, state, report);
+            }
+            TerminatorKind::Assert { cond, .. } => self.operand(cond, span, state, report),
+            TerminatorKind::Yield { value, .. } => self.operand(value, span, state, report),
+            // Drops of moved out locals are no-ops.
+            TerminatorKind::Drop { .. }
+            | TerminatorKind::DropAndReplace { .. }
+            | TerminatorKind::Goto { .. }
+            | TerminatorKind::Resume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => (),
+        }
+    }
+
+    fn operand(
+        &self,
+        operand: &Operand,
+        span: MirSpan,
+        state: &mut FxHashSet<LocalId>,
+        report: &mut dyn FnMut(LocalId, MirSpan),
+    ) {
+        let (Operand::Copy(place) | Operand::Move(place)) = operand else {
+            return;
+        };
+        self.use_place(place, span, state, report);
+        // Lowering doesn't distinguish copies from moves, so the type decides.
+        if place.projection.is_empty() && !self.is_copy[place.local] {
+            state.insert(place.local);
+        }
+    }
+
+    fn use_place(
+        &self,
+        place: &Place,
+        span: MirSpan,
+        state: &FxHashSet<LocalId>,
+        report: &mut dyn FnMut(LocalId, MirSpan),
+    ) {
+        if state.contains(&place.local) {
+            report(place.local, span);
+        }
+        for proj in place.projection.iter() {
+            if let ProjectionElem::Index(index) = proj {
+                if state.contains(index) {
+                    report(*index, span);
+                }
+            }
+        }
+    }
+
+    fn assign(
+        &self,
+        place: &Place,
+        span: MirSpan,
+        state: &mut FxHashSet<LocalId>,
+        report: &mut dyn FnMut(LocalId, MirSpan),
+    ) {
+        if place.projection.is_empty() {
+            state.remove(&place.local);
+        } else {
+            // Assigning to a part of a moved out local is an error as well.
+            self.use_place(place, span, state, report);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Finds the points where locals are dropped without the code saying so, which is at the end of
+//! their scopes, on `break`, `continue` and `return`, and before they are overwritten.
+
+use la_arena::ArenaMap;
+use rustc_hash::FxHashSet;
+
+use super::{
+    borrowck::forward_dataflow, BasicBlockId, LocalId, MirBody, MirSpan, Operand, Rvalue,
+    StatementKind, TerminatorKind,
+};
+
+/// A `Drop` terminator of a local that might still hold a value at that point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImplicitDrop {
+    pub local: LocalId,
+    /// The expression that ends the scope of the local, or the assignment that overwrites it.
+    pub span: MirSpan,
+}
+
+/// Returns the drops of whole locals in `body` that aren't no-ops, that is the local is not moved
+/// out or dead on every path that reaches the drop.
+pub fn implicit_drops(body: &MirBody) -> Vec<ImplicitDrop> {
+    let maybe_init = maybe_initialized_map(body);
+    let mut result = vec![];
+    for (block_id, state) in maybe_init.iter() {
+        let mut state = state.clone();
+        let block = &body.basic_blocks[block_id];
+        for statement in &block.statements {
+            apply_statement(&statement.kind, &mut state);
+        }
+        let Some(terminator) = &block.terminator else {
+            continue;
+        };
+        if let TerminatorKind::Drop { place, .. } = &terminator.kind {
+            if place.projection.is_empty() && state.contains(&place.local) {
+                result.push(ImplicitDrop { local: place.local, span: terminator.span });
+            }
+        }
+    }
+    result
+}
+
+/// Returns the locals that might be initialized at the start of each reachable block.
+fn maybe_initialized_map(body: &MirBody) -> ArenaMap<BasicBlockId, FxHashSet<LocalId>> {
+    let params = body.param_locals.iter().copied().collect();
+    forward_dataflow(body, params, |block_id, state| {
+        let block = &body.basic_blocks[block_id];
+        for statement in &block.statements {
+            apply_statement(&statement.kind, state);
+        }
+        if let Some(terminator) = &block.terminator {
+            apply_terminator(&terminator.kind, state);
+        }
+    })
+}
+
+fn apply_statement(statement: &StatementKind, state: &mut FxHashSet<LocalId>) {
+    match statement {
+        StatementKind::Assign(place, value) => {
+            match value {
+                Rvalue::Use(it)
+                | Rvalue::Repeat(it, _)
+                | Rvalue::Cast(_, it, _)
+                | Rvalue::ShallowInitBox(it, _)
+                | Rvalue::UnaryOp(_, it) => move_operand(it, state),
+                Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                    move_operand(lhs, state);
+                    move_operand(rhs, state);
+                }
+                Rvalue::Aggregate(_, it) => it.iter().for_each(|it| move_operand(it, state)),
+                Rvalue::Ref(..)
+                | Rvalue::Len(_)
+                | Rvalue::Discriminant(_)
+                | Rvalue::CopyForDeref(_)
+                | Rvalue::ShallowInitBoxWithAlloc(_) => (),
+            }
+            if place.projection.is_empty() {
+                state.insert(place.local);
+            }
+        }
+        StatementKind::Deinit(place) => {
+            if place.projection.is_empty() {
+                state.remove(&place.local);
+            }
+        }
+        StatementKind::StorageDead(local) => {
+            state.remove(local);
+        }
+        StatementKind::FakeRead(_) | StatementKind::StorageLive(_) | StatementKind::Nop => (),
+    }
+}
+
+fn apply_terminator(terminator: &TerminatorKind, state: &mut FxHashSet<LocalId>) {
+    match terminator {
+        TerminatorKind::SwitchInt { discr, .. } => move_operand(discr, state),
+        TerminatorKind::Call { func, args, destination, .. } => {
+            move_operand(func, state);
+            args.iter().for_each(|it| move_operand(it, state));
+            if destination.projection.is_empty() {
+                state.insert(destination.local);
+            }
+        }
+        TerminatorKind::Drop { place, .. } => {
+            if place.projection.is_empty() {
+                state.remove(&place.local);
+            }
+        }
+        TerminatorKind::Goto { .. }
+        | TerminatorKind::Resume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable
+        | TerminatorKind::DropAndReplace { .. }
+        | TerminatorKind::Assert { .. }
+        | TerminatorKind::Yield { .. }
+        | TerminatorKind::GeneratorDrop
+        | TerminatorKind::FalseEdge { .. }
+        | TerminatorKind::FalseUnwind { .. } => (),
+    }
+}
+
+/// Lowering doesn't distinguish copies from moves, but locals of `Copy` types are never dropped, so
+/// every use of a whole local can be treated as a move.
+fn move_operand(operand: &Operand, state: &mut FxHashSet<LocalId>) {
+    if let Operand::Copy(place) | Operand::Move(place) = operand {
+        if place.projection.is_empty() {
+            state.remove(&place.local);
+        }
+    }
+}
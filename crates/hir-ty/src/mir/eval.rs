@@ -42,9 +42,18 @@ use super::{
     MirSpan, Operand, Place, ProjectionElem, Rvalue, StatementKind, TerminatorKind, UnOp,
 };
 
+mod observer;
+mod sandbox;
 mod shim;
 #[cfg(test)]
 mod tests;
+mod thread;
+
+use sandbox::Sandbox;
+use thread::Threads;
+
+pub use observer::{MirEvalFrame, MirEvalLocal, MirEvalObserver};
+pub use sandbox::MirEvalSandbox;
 
 macro_rules! from_bytes {
     ($ty:tt, $value:expr) => {
@@ -88,39 +97,14 @@ impl VTableMap {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-struct TlsData {
-    keys: Vec<u128>,
-}
-
-impl TlsData {
-    fn create_key(&mut self) -> usize {
-        self.keys.push(0);
-        self.keys.len() - 1
-    }
-
-    fn get_key(&mut self, key: usize) -> Result<u128> {
-        let r = self.keys.get(key).ok_or_else(|| {
-            MirEvalError::UndefinedBehavior(format!("Getting invalid tls key {key}"))
-        })?;
-        Ok(*r)
-    }
-
-    fn set_key(&mut self, key: usize, value: u128) -> Result<()> {
-        let r = self.keys.get_mut(key).ok_or_else(|| {
-            MirEvalError::UndefinedBehavior(format!("Setting invalid tls key {key}"))
-        })?;
-        *r = value;
-        Ok(())
-    }
-}
-
 struct StackFrame {
     body: Arc<MirBody>,
     locals: Locals,
     destination: Option<BasicBlockId>,
     prev_stack_ptr: usize,
     span: (MirSpan, DefWithBodyId),
+    /// The closure this frame executes, if any. `body.owner` is the parent of the closure then.
+    closure: Option<ClosureId>,
 }
 
 pub struct Evaluator<'a> {
@@ -136,7 +120,10 @@ pub struct Evaluator<'a> {
     /// store the type as an interned id in place of function and vtable pointers, and we recover back the type at the
     /// time of use.
     vtable_map: VTableMap,
-    thread_local_storage: TlsData,
+    threads: Threads,
+    /// The file system, environment and clock visible to the program.
+    sandbox: Sandbox,
+    observer: Option<&'a mut (dyn MirEvalObserver + 'static)>,
     random_state: oorandom::Rand64,
     stdout: Vec<u8>,
     stderr: Vec<u8>,
@@ -313,6 +300,8 @@ pub enum MirEvalError {
     InvalidVTableId(usize),
     CoerceUnsizedError(Ty),
     LangItemNotFound(LangItem),
+    /// Every thread of the program is blocked, and none of them can be woken up.
+    Deadlock,
 }
 
 impl MirEvalError {
@@ -399,6 +388,7 @@ impl MirEvalError {
             | MirEvalError::TargetDataLayoutNotAvailable
             | MirEvalError::CoerceUnsizedError(_)
             | MirEvalError::LangItemNotFound(_)
+            | MirEvalError::Deadlock
             | MirEvalError::InvalidVTableId(_) => writeln!(f, "{:?}", err)?,
         }
         Ok(())
@@ -424,6 +414,7 @@ impl std::fmt::Debug for MirEvalError {
             Self::TypeIsUnsized(ty, it) => write!(f, "{ty:?} is unsized. {it} should be sized."),
             Self::ExecutionLimitExceeded => write!(f, "execution limit exceeded"),
             Self::StackOverflow => write!(f, "stack overflow"),
+            Self::Deadlock => write!(f, "deadlock, every thread is blocked"),
             Self::MirLowerError(arg0, arg1) => {
                 f.debug_tuple("MirLowerError").field(arg0).field(arg1).finish()
             }
@@ -484,9 +475,30 @@ pub fn interpret_mir(
     // a zero size, hoping that they are all outside of our current body. Even without a fix for #7434, we can
     // (and probably should) do better here, for example by excluding bindings outside of the target expression.
     assert_placeholder_ty_is_unused: bool,
+) -> (Result<Const>, String, String) {
+    let evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused);
+    run_evaluator(evaluator, body)
+}
+
+/// Like [`interpret_mir`], but the program can use the files and environment variables of `sandbox`,
+/// and `observer` is notified of every step of the evaluation.
+pub fn interpret_mir_in_sandbox<'a>(
+    db: &'a dyn HirDatabase,
+    body: Arc<MirBody>,
+    sandbox: MirEvalSandbox,
+    observer: Option<&'a mut (dyn MirEvalObserver + 'static)>,
+) -> (Result<Const>, String, String) {
+    let mut evaluator = Evaluator::new(db, body.owner, false);
+    evaluator.sandbox = Sandbox::new(sandbox);
+    evaluator.observer = observer;
+    run_evaluator(evaluator, body)
+}
+
+fn run_evaluator(
+    mut evaluator: Evaluator<'_>,
+    body: Arc<MirBody>,
 ) -> (Result<Const>, String, String) {
     let ty = body.locals[return_slot()].ty.clone();
-    let mut evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused);
     let it: Result<Const> = (|| {
         if evaluator.ptr_size() != std::mem::size_of::<usize>() {
             not_supported!("targets with different pointer size from host");
@@ -520,7 +532,9 @@ impl Evaluator<'_> {
             heap: vec![0],
             code_stack: vec![],
             vtable_map: VTableMap::default(),
-            thread_local_storage: TlsData::default(),
+            threads: Threads::default(),
+            sandbox: Sandbox::default(),
+            observer: None,
             static_locations: HashMap::default(),
             db,
             random_state: oorandom::Rand64::new(0),
@@ -732,6 +746,20 @@ impl Evaluator<'_> {
         &mut self,
         body: Arc<MirBody>,
         args: impl Iterator<Item = IntervalOrOwned>,
+    ) -> Result<Vec<u8>> {
+        let is_outermost = self.threads.enter();
+        let result = self.interpret_mir_in_thread(body, args, is_outermost);
+        self.threads.exit();
+        result
+    }
+
+    /// Runs `body` to completion. In the outermost call this also schedules the other threads of the
+    /// program, whenever the running one blocks or finishes.
+    fn interpret_mir_in_thread(
+        &mut self,
+        body: Arc<MirBody>,
+        args: impl Iterator<Item = IntervalOrOwned>,
+        is_outermost: bool,
     ) -> Result<Vec<u8>> {
         if let Some(it) = self.stack_depth_limit.checked_sub(1) {
             self.stack_depth_limit = it;
@@ -743,15 +771,31 @@ impl Evaluator<'_> {
         self.fill_locals_for_body(&body, &mut locals, args)?;
         let prev_code_stack = mem::take(&mut self.code_stack);
         let span = (MirSpan::Unknown, body.owner);
-        self.code_stack.push(StackFrame { body, locals, destination: None, prev_stack_ptr, span });
+        self.code_stack.push(StackFrame {
+            body,
+            locals,
+            destination: None,
+            prev_stack_ptr,
+            span,
+            closure: None,
+        });
         'stack: loop {
             let Some(mut my_stack_frame) = self.code_stack.pop() else {
                 not_supported!("missing stack frame");
             };
+            let (closure, call_span) = (my_stack_frame.closure, my_stack_frame.span.0);
             let e = (|| {
                 let mut locals = &mut my_stack_frame.locals;
                 let body = &*my_stack_frame.body;
                 loop {
+                    if is_outermost {
+                        self.threads.step();
+                        if self.threads.switch_requested() {
+                            self.code_stack.push(my_stack_frame);
+                            self.switch_thread(&mut current_block_idx)?;
+                            return Ok(None);
+                        }
+                    }
                     let current_block = &body.basic_blocks[current_block_idx];
                     if let Some(it) = self.execution_limit.checked_sub(1) {
                         self.execution_limit = it;
@@ -759,6 +803,7 @@ impl Evaluator<'_> {
                         return Err(MirEvalError::ExecutionLimitExceeded);
                     }
                     for statement in &current_block.statements {
+                        self.notify_observer(locals, closure, call_span, statement.span);
                         match &statement.kind {
                             StatementKind::Assign(l, r) => {
                                 let addr = self.place_addr(l, &locals)?;
@@ -767,7 +812,8 @@ impl Evaluator<'_> {
                                 locals.drop_flags.add_place(l.clone());
                             }
                             StatementKind::Deinit(_) => not_supported!("de-init statement"),
-                            StatementKind::StorageLive(_)
+                            StatementKind::FakeRead(_)
+                            | StatementKind::StorageLive(_)
                             | StatementKind::StorageDead(_)
                             | StatementKind::Nop => (),
                         }
@@ -775,6 +821,7 @@ impl Evaluator<'_> {
                     let Some(terminator) = current_block.terminator.as_ref() else {
                         not_supported!("block without terminator");
                     };
+                    self.notify_observer(locals, closure, call_span, terminator.span);
                     match &terminator.kind {
                         TerminatorKind::Goto { target } => {
                             current_block_idx = *target;
@@ -827,6 +874,11 @@ impl Evaluator<'_> {
                                     target.ok_or(MirEvalError::UndefinedBehavior(
                                         "Diverging function returned".to_owned(),
                                     ))?;
+                                if self.threads.switch_requested() {
+                                    self.code_stack.push(my_stack_frame);
+                                    self.switch_thread(&mut current_block_idx)?;
+                                    return Ok(None);
+                                }
                             }
                         }
                         TerminatorKind::SwitchInt { discr, targets } => {
@@ -860,7 +912,9 @@ impl Evaluator<'_> {
                     let my_code_stack = mem::replace(&mut self.code_stack, prev_code_stack);
                     let mut error_stack = vec![];
                     for frame in my_code_stack.into_iter().rev() {
-                        if let DefWithBodyId::FunctionId(f) = frame.body.owner {
+                        if let Some(closure) = frame.closure {
+                            error_stack.push((Either::Right(closure), frame.span.0, frame.span.1));
+                        } else if let DefWithBodyId::FunctionId(f) = frame.body.owner {
                             error_stack.push((Either::Left(f), frame.span.0, frame.span.1));
                         }
                     }
@@ -868,6 +922,10 @@ impl Evaluator<'_> {
                 }
             };
             match my_stack_frame.destination {
+                None if is_outermost && !self.threads.is_main_thread_running() => {
+                    // The start routine of a spawned thread returned.
+                    self.finish_current_thread(&mut current_block_idx)?;
+                }
                 None => {
                     self.code_stack = prev_code_stack;
                     self.stack_depth_limit += 1;
@@ -1917,9 +1975,15 @@ impl Evaluator<'_> {
             TyKind::FnDef(def, generic_args) => {
                 self.exec_fn_def(*def, generic_args, destination, args, &locals, target_bb, span)
             }
-            TyKind::Closure(id, subst) => {
-                self.exec_closure(*id, bytes.slice(0..0), subst, destination, args, locals, span)
-            }
+            TyKind::Closure(id, subst) => self.exec_closure(
+                *id,
+                bytes.slice(0..0),
+                subst,
+                destination,
+                args,
+                target_bb,
+                (span, locals.body.owner),
+            ),
             _ => Err(MirEvalError::TypeError("function pointer to non function")),
         }
     }
@@ -1931,8 +1995,8 @@ impl Evaluator<'_> {
         generic_args: &Substitution,
         destination: Interval,
         args: &[IntervalAndTy],
-        locals: &Locals,
-        span: MirSpan,
+        target_bb: Option<BasicBlockId>,
+        span: (MirSpan, DefWithBodyId),
     ) -> Result<Option<StackFrame>> {
         let mir_body = self
             .db
@@ -1951,12 +2015,32 @@ impl Evaluator<'_> {
         let arg_bytes = iter::once(Ok(closure_data))
             .chain(args.iter().map(|it| Ok(it.get(&self)?.to_owned())))
             .collect::<Result<Vec<_>>>()?;
+        if let Some(target_bb) = target_bb {
+            // Like functions, closures get a frame in the code stack, so that a thread can be switched
+            // while it's inside of one.
+            let (mut locals, prev_stack_ptr) =
+                self.create_locals_for_body(mir_body.clone(), Some(destination))?;
+            self.fill_locals_for_body(
+                &mir_body,
+                &mut locals,
+                arg_bytes.into_iter().map(IntervalOrOwned::Owned),
+            )?;
+            let span = (span.0, locals.body.owner);
+            return Ok(Some(StackFrame {
+                body: mir_body,
+                locals,
+                destination: Some(target_bb),
+                prev_stack_ptr,
+                span,
+                closure: Some(closure),
+            }));
+        }
         let bytes = self
             .interpret_mir(mir_body, arg_bytes.into_iter().map(IntervalOrOwned::Owned))
             .map_err(|e| {
                 MirEvalError::InFunction(
                     Box::new(e),
-                    vec![(Either::Right(closure), span, locals.body.owner)],
+                    vec![(Either::Right(closure), span.0, span.1)],
                 )
             })?;
         destination.write_from_bytes(self, &bytes)?;
@@ -2127,6 +2211,7 @@ impl Evaluator<'_> {
                 destination: Some(target_bb),
                 prev_stack_ptr,
                 span,
+                closure: None,
             })
         } else {
             let result = self.interpret_mir(mir_body, arg_bytes).map_err(|e| {
@@ -2193,8 +2278,8 @@ impl Evaluator<'_> {
                     &Substitution::from_iter(Interner, ClosureSubst(subst).parent_subst()),
                     destination,
                     &args[1..],
-                    locals,
-                    span,
+                    target_bb,
+                    (span, locals.body.owner),
                 );
             }
             _ => {
@@ -2234,8 +2319,15 @@ impl Evaluator<'_> {
     }
 
     fn eval_static(&mut self, st: StaticId, locals: &Locals) -> Result<Address> {
-        if let Some(o) = self.static_locations.get(&st) {
-            return Ok(*o);
+        // Each thread gets its own copy of `#[thread_local]` statics.
+        let is_thread_local = self.db.attrs(st.into()).by_key("thread_local").exists();
+        let location = if is_thread_local {
+            self.threads.thread_local_static(st)
+        } else {
+            self.static_locations.get(&st).copied()
+        };
+        if let Some(o) = location {
+            return Ok(o);
         };
         let static_data = self.db.static_data(st);
         let result = if !static_data.is_extern {
@@ -2256,7 +2348,11 @@ impl Evaluator<'_> {
         };
         let addr = self.heap_allocate(self.ptr_size(), self.ptr_size())?;
         self.write_memory(addr, &result.addr.to_bytes())?;
-        self.static_locations.insert(st, addr);
+        if is_thread_local {
+            self.threads.set_thread_local_static(st, addr);
+        } else {
+            self.static_locations.insert(st, addr);
+        }
         Ok(addr)
     }
 
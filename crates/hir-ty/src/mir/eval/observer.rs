@@ -0,0 +1,136 @@
+//! Lets a debugger watch an evaluation step by step, and inspect its call stack and locals.
+
+use base_db::FileRange;
+use either::Either;
+use hir_def::DefWithBodyId;
+use hir_expand::{name::Name, InFile};
+use syntax::SyntaxNodePtr;
+
+use crate::{
+    consteval::intern_const_scalar,
+    display::HirDisplay,
+    mir::{LocalId, MirSpan},
+    ClosureId, ConstScalar, Ty,
+};
+
+use super::{Evaluator, Locals, MirEvalError, Result};
+
+/// Watches an evaluation. It can pause it by simply not returning from [`MirEvalObserver::step`].
+pub trait MirEvalObserver {
+    /// Called before each statement and terminator of the running thread is executed. `stack` is
+    /// its call stack, the innermost frame is the last one.
+    fn step(&mut self, stack: &[MirEvalFrame<'_, '_>]);
+}
+
+/// A frame of the call stack of a paused evaluation.
+pub struct MirEvalFrame<'a, 'db> {
+    evaluator: &'a Evaluator<'db>,
+    locals: &'a Locals,
+    closure: Option<ClosureId>,
+    span: MirSpan,
+}
+
+/// A named local of a [`MirEvalFrame`], rendered for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirEvalLocal {
+    pub name: Name,
+    pub ty: String,
+    /// The value of the local, or `None` if it can't be read, for example because it's not
+    /// initialized yet.
+    pub value: Option<String>,
+}
+
+impl MirEvalFrame<'_, '_> {
+    /// The body that this frame executes, the parent of the closure for closures.
+    pub fn owner(&self) -> DefWithBodyId {
+        self.locals.body.owner
+    }
+
+    pub fn closure(&self) -> Option<ClosureId> {
+        self.closure
+    }
+
+    /// The span that is executing in this frame, which is the call for every frame but the
+    /// innermost one.
+    pub fn span(&self) -> MirSpan {
+        self.span
+    }
+
+    /// The range of [`Self::span`] in the original file, spans inside of macro expansions are
+    /// mapped up to their macro calls.
+    pub fn source_range(&self) -> Option<FileRange> {
+        let db = self.evaluator.db;
+        let source_map = db.body_with_source_map(self.owner()).1;
+        let ptr: InFile<SyntaxNodePtr> = match self.span {
+            MirSpan::ExprId(e) => source_map.expr_syntax(e).ok()?.map(|it| it.into()),
+            MirSpan::PatId(p) => source_map.pat_syntax(p).ok()?.map(|it| match it {
+                Either::Left(e) => e.into(),
+                Either::Right(e) => e.into(),
+            }),
+            MirSpan::Unknown => return None,
+        };
+        let root = db.parse_or_expand(ptr.file_id);
+        let node = ptr.value.to_node(&root);
+        Some(InFile::new(ptr.file_id, &node).original_file_range(db.upcast()))
+    }
+
+    pub fn locals(&self) -> Vec<MirEvalLocal> {
+        let db = self.evaluator.db;
+        let body = db.body(self.owner());
+        self.locals
+            .body
+            .binding_locals
+            .iter()
+            .map(|(binding, &local)| {
+                let ty = &self.locals.body.locals[local].ty;
+                MirEvalLocal {
+                    name: body.bindings[binding].name.clone(),
+                    ty: ty.display(db).to_string(),
+                    value: self.render_local(local, ty).ok(),
+                }
+            })
+            .collect()
+    }
+
+    fn render_local(&self, local: LocalId, ty: &Ty) -> Result<String> {
+        let Some(interval) = self.locals.ptr.get(local) else {
+            return Err(MirEvalError::NotSupported("local without a memory location".to_owned()));
+        };
+        let bytes = interval.get(self.evaluator)?.to_vec();
+        let mut memory_map = self.evaluator.create_memory_map(&bytes, ty, self.locals)?;
+        memory_map.vtable = self.evaluator.vtable_map.clone();
+        let value = intern_const_scalar(ConstScalar::Bytes(bytes, memory_map), ty.clone());
+        Ok(value.display(self.evaluator.db).to_string())
+    }
+}
+
+impl Evaluator<'_> {
+    /// Tells the observer, if there is one, that the statement or terminator at `span` of the
+    /// current frame is about to run. `call_span` is where the current frame was called from.
+    pub(super) fn notify_observer(
+        &mut self,
+        locals: &Locals,
+        closure: Option<ClosureId>,
+        call_span: MirSpan,
+        span: MirSpan,
+    ) {
+        let Some(observer) = self.observer.take() else {
+            return;
+        };
+        let call_spans = self.code_stack.iter().skip(1).map(|it| it.span.0).chain([call_span]);
+        let stack = self
+            .code_stack
+            .iter()
+            .zip(call_spans)
+            .map(|(frame, span)| MirEvalFrame {
+                evaluator: self,
+                locals: &frame.locals,
+                closure: frame.closure,
+                span,
+            })
+            .chain([MirEvalFrame { evaluator: self, locals, closure, span }])
+            .collect::<Vec<_>>();
+        observer.step(&stack);
+        self.observer = Some(observer);
+    }
+}
@@ -0,0 +1,286 @@
+//! The outside world as seen by the interpreted program: an in-memory file system, environment
+//! variables and a clock. The program can't escape it, except for reading the files of a directory
+//! that the user explicitly allowed.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use rustc_hash::FxHashMap;
+
+pub(super) const ENOENT: i32 = 2;
+pub(super) const EBADF: i32 = 9;
+const EACCES: i32 = 13;
+const EEXIST: i32 = 17;
+const EISDIR: i32 = 21;
+pub(super) const EINVAL: i32 = 22;
+pub(super) const ENOTTY: i32 = 25;
+const EFBIG: i32 = 27;
+pub(super) const ENOSYS: i32 = 38;
+
+const O_ACCMODE: i32 = 0o3;
+const O_WRONLY: i32 = 0o1;
+const O_RDWR: i32 = 0o2;
+const O_CREAT: i32 = 0o100;
+const O_EXCL: i32 = 0o200;
+const O_TRUNC: i32 = 0o1000;
+const O_APPEND: i32 = 0o2000;
+
+/// Seconds since the unix epoch reported by `CLOCK_REALTIME` when the evaluation starts.
+const REALTIME_START: u64 = 1_577_836_800;
+/// Nanoseconds that pass between two reads of the clock. Time never stands still, so measured
+/// durations are non zero, but it's still deterministic.
+const CLOCK_TICK: u64 = 1_000;
+/// Maximum size of a file of the in-memory file system. Writes past it fail with `EFBIG`, so a
+/// program can't make the evaluator allocate arbitrary amounts of memory.
+const MAX_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Configures the sandbox of an evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct MirEvalSandbox {
+    /// Files of the in-memory file system, keyed by their absolute path.
+    pub files: FxHashMap<PathBuf, Vec<u8>>,
+    /// Environment variables, returned by `getenv`.
+    pub env: FxHashMap<String, String>,
+    /// When set, files that are not in `files` are read from the real file system, as long as they
+    /// are inside of this directory. This is also the working directory of the program. Opening
+    /// such a file for writing copies it to the in-memory file system, so that the real one is
+    /// never modified.
+    pub read_only_root: Option<PathBuf>,
+}
+
+/// What a file descriptor refers to.
+#[derive(Debug)]
+enum FileData {
+    /// A file of the in-memory file system.
+    Memory(PathBuf),
+    /// A snapshot of a file of the real file system.
+    Real(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct OpenFile {
+    data: FileData,
+    offset: usize,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FileKind {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Sandbox {
+    config: MirEvalSandbox,
+    open_files: FxHashMap<i32, OpenFile>,
+    /// Nanoseconds passed since the start of the evaluation.
+    clock: u64,
+}
+
+impl Sandbox {
+    pub(super) fn new(config: MirEvalSandbox) -> Sandbox {
+        Sandbox { config, open_files: FxHashMap::default(), clock: 0 }
+    }
+
+    pub(super) fn env(&self, name: &str) -> Option<&str> {
+        self.config.env.get(name).map(|it| it.as_str())
+    }
+
+    pub(super) fn current_dir(&self) -> PathBuf {
+        self.config.read_only_root.clone().unwrap_or_else(|| PathBuf::from("/"))
+    }
+
+    /// Returns `(seconds, nanoseconds)` of the given clock, and advances the time.
+    pub(super) fn clock_gettime(&mut self, clock_id: i32) -> (u64, u64) {
+        const CLOCK_REALTIME: i32 = 0;
+        self.clock += CLOCK_TICK;
+        let secs = self.clock / 1_000_000_000;
+        let nanos = self.clock % 1_000_000_000;
+        match clock_id {
+            CLOCK_REALTIME => (REALTIME_START + secs, nanos),
+            _ => (secs, nanos),
+        }
+    }
+
+    pub(super) fn open(&mut self, path: &str, flags: i32) -> Result<i32, i32> {
+        let path = self.absolute_path(path);
+        let readable = flags & O_ACCMODE != O_WRONLY;
+        let writable = flags & O_ACCMODE == O_WRONLY || flags & O_ACCMODE == O_RDWR;
+        let data = if self.config.files.contains_key(&path) {
+            if flags & O_CREAT != 0 && flags & O_EXCL != 0 {
+                return Err(EEXIST);
+            }
+            if flags & O_TRUNC != 0 && writable {
+                self.config.files.insert(path.clone(), vec![]);
+            }
+            FileData::Memory(path)
+        } else if let Some(kind) = self.real_file_kind(&path) {
+            if flags & O_CREAT != 0 && flags & O_EXCL != 0 {
+                return Err(EEXIST);
+            }
+            if kind == FileKind::Directory {
+                return Err(EISDIR);
+            }
+            if !writable {
+                FileData::Real(fs::read(&path).map_err(|_| EACCES)?)
+            } else {
+                // Copy on write, later opens of the file see the copy.
+                let contents = match flags & O_TRUNC {
+                    0 => fs::read(&path).map_err(|_| EACCES)?,
+                    _ => vec![],
+                };
+                self.config.files.insert(path.clone(), contents);
+                FileData::Memory(path)
+            }
+        } else if self.memory_file_kind(&path) == Some(FileKind::Directory) {
+            return Err(EISDIR);
+        } else if flags & O_CREAT != 0 {
+            self.config.files.insert(path.clone(), vec![]);
+            FileData::Memory(path)
+        } else {
+            return Err(ENOENT);
+        };
+        // 0, 1 and 2 are the standard streams
+        let fd = (3..).find(|it| !self.open_files.contains_key(it)).unwrap_or_default();
+        let append = flags & O_APPEND != 0;
+        self.open_files.insert(fd, OpenFile { data, offset: 0, readable, writable, append });
+        Ok(fd)
+    }
+
+    pub(super) fn close(&mut self, fd: i32) -> Result<(), i32> {
+        match self.open_files.remove(&fd) {
+            Some(_) => Ok(()),
+            None => Err(EBADF),
+        }
+    }
+
+    pub(super) fn read(&mut self, fd: i32, count: usize) -> Result<Vec<u8>, i32> {
+        let file = self.open_files.get_mut(&fd).ok_or(EBADF)?;
+        if !file.readable {
+            return Err(EBADF);
+        }
+        let contents = match &file.data {
+            FileData::Memory(path) => self.config.files.get(path).ok_or(EBADF)?,
+            FileData::Real(contents) => contents,
+        };
+        let start = file.offset.min(contents.len());
+        let end = start.saturating_add(count).min(contents.len());
+        file.offset = end;
+        Ok(contents[start..end].to_vec())
+    }
+
+    pub(super) fn write(&mut self, fd: i32, bytes: &[u8]) -> Result<(), i32> {
+        let file = self.open_files.get_mut(&fd).ok_or(EBADF)?;
+        if !file.writable {
+            return Err(EBADF);
+        }
+        // Files of the real file system are copied to memory when opened for writing.
+        let FileData::Memory(path) = &file.data else {
+            return Err(EBADF);
+        };
+        let contents = self.config.files.get_mut(path).ok_or(EBADF)?;
+        if file.append {
+            file.offset = contents.len();
+        }
+        let end = file
+            .offset
+            .checked_add(bytes.len())
+            .filter(|&end| end <= MAX_FILE_SIZE)
+            .ok_or(EFBIG)?;
+        if contents.len() < end {
+            contents.resize(end, 0);
+        }
+        contents[file.offset..end].copy_from_slice(bytes);
+        file.offset = end;
+        Ok(())
+    }
+
+    /// Implements `lseek`, `whence` is one of `SEEK_SET`, `SEEK_CUR` and `SEEK_END`.
+    pub(super) fn seek(&mut self, fd: i32, offset: i64, whence: i32) -> Result<u64, i32> {
+        let len = self.file_len(fd)?;
+        let file = self.open_files.get_mut(&fd).ok_or(EBADF)?;
+        let base = match whence {
+            0 => 0,
+            1 => file.offset as i64,
+            2 => len as i64,
+            _ => return Err(EINVAL),
+        };
+        let offset = base.checked_add(offset).ok_or(EINVAL)?;
+        let offset = u64::try_from(offset).map_err(|_| EINVAL)?;
+        file.offset = offset as usize;
+        Ok(offset)
+    }
+
+    /// Returns the kind and the size of the file at `path`.
+    pub(super) fn stat(&self, path: &str) -> Result<(FileKind, u64), i32> {
+        let path = self.absolute_path(path);
+        if let Some(contents) = self.config.files.get(&path) {
+            return Ok((FileKind::File, contents.len() as u64));
+        }
+        match self.real_file_kind(&path) {
+            Some(FileKind::File) => {
+                let len = fs::metadata(&path).map_err(|_| EACCES)?.len();
+                Ok((FileKind::File, len))
+            }
+            Some(FileKind::Directory) => Ok((FileKind::Directory, 0)),
+            None => match self.memory_file_kind(&path) {
+                Some(kind) => Ok((kind, 0)),
+                None => Err(ENOENT),
+            },
+        }
+    }
+
+    /// Returns the kind and the size of the file open as `fd`.
+    pub(super) fn fstat(&self, fd: i32) -> Result<(FileKind, u64), i32> {
+        Ok((FileKind::File, self.file_len(fd)? as u64))
+    }
+
+    fn file_len(&self, fd: i32) -> Result<usize, i32> {
+        let file = self.open_files.get(&fd).ok_or(EBADF)?;
+        Ok(match &file.data {
+            FileData::Memory(path) => self.config.files.get(path).map_or(0, |it| it.len()),
+            FileData::Real(contents) => contents.len(),
+        })
+    }
+
+    /// Makes `path` absolute and removes `.` and `..` components from it, without looking at the
+    /// file system.
+    fn absolute_path(&self, path: &str) -> PathBuf {
+        let mut result = PathBuf::from("/");
+        for component in self.current_dir().join(path).components() {
+            match component {
+                Component::Prefix(_) | Component::RootDir | Component::CurDir => (),
+                Component::ParentDir => {
+                    result.pop();
+                }
+                Component::Normal(it) => result.push(it),
+            }
+        }
+        result
+    }
+
+    /// Directories of the in-memory file system are implied by the paths of its files.
+    fn memory_file_kind(&self, path: &Path) -> Option<FileKind> {
+        if path == Path::new("/") {
+            return Some(FileKind::Directory);
+        }
+        self.config.files.keys().any(|it| it.starts_with(path)).then_some(FileKind::Directory)
+    }
+
+    /// Returns the kind of `path` in the real file system, if it can be read by the program.
+    fn real_file_kind(&self, path: &Path) -> Option<FileKind> {
+        let root = fs::canonicalize(self.config.read_only_root.as_ref()?).ok()?;
+        // Canonicalize, so that symlinks can't escape the root either.
+        let path = fs::canonicalize(path).ok()?;
+        if !path.starts_with(root) {
+            return None;
+        }
+        let metadata = fs::metadata(path).ok()?;
+        Some(if metadata.is_dir() { FileKind::Directory } else { FileKind::File })
+    }
+}
@@ -248,6 +248,19 @@ impl Evaluator<'_> {
         _span: MirSpan,
     ) -> Result<()> {
         match id {
+            24 => {
+                // SYS_sched_yield
+                self.threads.yield_now();
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])
+            }
+            202 => {
+                // SYS_futex
+                self.exec_futex(args, destination)
+            }
+            332 => {
+                // SYS_statx, std falls back to `stat64` when it's not available
+                self.write_errno_result(sandbox::ENOSYS, destination)
+            }
             318 => {
                 // SYS_getrandom
                 let [buf, len, _flags] = args else {
@@ -267,6 +280,116 @@ impl Evaluator<'_> {
         }
     }
 
+    /// Sets `errno` and returns -1, which is how most of libc functions report errors.
+    fn write_errno_result(&mut self, errno: i32, destination: Interval) -> Result<()> {
+        self.set_errno(errno)?;
+        destination.write_from_bytes(self, &(-1i64).to_le_bytes()[0..destination.size])
+    }
+
+    fn read_c_str(&self, addr: Address) -> Result<String> {
+        let mut bytes = vec![];
+        loop {
+            match self.read_memory(addr.offset(bytes.len()), 1)?[0] {
+                0 => break,
+                it => bytes.push(it),
+            }
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| MirEvalError::NotSupported("non utf8 c string".to_owned()))
+    }
+
+    fn allocate_c_str(&mut self, value: &str) -> Result<Address> {
+        let addr = self.heap_allocate(value.len() + 1, 1)?;
+        self.write_memory(addr, value.as_bytes())?;
+        self.write_memory(addr.offset(value.len()), &[0])?;
+        Ok(addr)
+    }
+
+    /// Fills the `struct stat` (or `struct stat64`) behind `buf`, using the x86_64 linux layout.
+    fn write_stat(
+        &mut self,
+        buf: &IntervalAndTy,
+        kind: sandbox::FileKind,
+        size: u64,
+        locals: &Locals,
+    ) -> Result<()> {
+        const S_IFDIR: u32 = 0o040000;
+        const S_IFREG: u32 = 0o100000;
+        let Some((stat_ty, ..)) = buf.ty.as_reference_or_ptr() else {
+            return Err(MirEvalError::TypeError("libc::stat arg1 is not a pointer"));
+        };
+        let stat_size = self.size_of_sized(stat_ty, locals, "libc::stat buf arg")?;
+        if stat_size < 144 {
+            not_supported!("struct stat with size {stat_size}");
+        }
+        let mode = match kind {
+            sandbox::FileKind::File => S_IFREG | 0o444,
+            sandbox::FileKind::Directory => S_IFDIR | 0o555,
+        };
+        let addr = Address::from_bytes(buf.get(self)?)?;
+        self.write_memory(addr, &vec![0; stat_size])?;
+        // st_nlink
+        self.write_memory(addr.offset(16), &1u64.to_le_bytes())?;
+        // st_mode
+        self.write_memory(addr.offset(24), &mode.to_le_bytes())?;
+        // st_size
+        self.write_memory(addr.offset(48), &size.to_le_bytes())?;
+        // st_blksize
+        self.write_memory(addr.offset(56), &4096u64.to_le_bytes())?;
+        // st_blocks
+        self.write_memory(addr.offset(64), &((size + 511) / 512).to_le_bytes())?;
+        Ok(())
+    }
+
+    fn exec_futex(&mut self, args: &[IntervalAndTy], destination: Interval) -> Result<()> {
+        const FUTEX_WAIT: i32 = 0;
+        const FUTEX_WAKE: i32 = 1;
+        const FUTEX_WAIT_BITSET: i32 = 9;
+        const FUTEX_WAKE_BITSET: i32 = 10;
+        const FUTEX_PRIVATE_FLAG: i32 = 128;
+        const FUTEX_CLOCK_REALTIME: i32 = 256;
+        let [addr, op, val, rest @ ..] = args else {
+            return Err(MirEvalError::TypeError("SYS_futex args are not provided"));
+        };
+        let addr = Address::from_bytes(addr.get(self)?)?;
+        let op = from_bytes!(i32, &pad16(op.get(self)?, true)[0..4]);
+        let val = from_bytes!(u32, &pad16(val.get(self)?, false)[0..4]);
+        // The timeout and the bitset are only passed by the operations that need them.
+        let bitset = match op & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME) {
+            FUTEX_WAIT_BITSET | FUTEX_WAKE_BITSET => {
+                let Some(bitset) = rest.get(2) else {
+                    return Err(MirEvalError::TypeError("SYS_futex bitset is not provided"));
+                };
+                from_bytes!(u32, &pad16(bitset.get(self)?, false)[0..4])
+            }
+            _ => u32::MAX,
+        };
+        match op & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME) {
+            FUTEX_WAIT | FUTEX_WAIT_BITSET => {
+                let has_timeout = match rest.get(0) {
+                    Some(timeout) => from_bytes!(usize, timeout.get(self)?) != 0,
+                    None => false,
+                };
+                if from_bytes!(u32, self.read_memory(addr, 4)?) != val {
+                    self.set_errno(thread::EAGAIN)?;
+                    return destination
+                        .write_from_bytes(self, &(-1i64).to_le_bytes()[0..destination.size]);
+                }
+                self.block_current_thread(thread::BlockedOn::Futex {
+                    addr,
+                    bitset,
+                    result: destination,
+                    has_timeout,
+                })
+            }
+            FUTEX_WAKE | FUTEX_WAKE_BITSET => {
+                let woken = self.wake_futex(addr, bitset, val as usize)?;
+                destination.write_from_bytes(self, &woken.to_le_bytes()[0..destination.size])
+            }
+            _ => not_supported!("futex operation {op}"),
+        }
+    }
+
     fn exec_extern_c(
         &mut self,
         as_str: &str,
@@ -309,13 +432,145 @@ impl Evaluator<'_> {
                     2 => {
                         self.write_to_stderr(interval)?;
                     }
-                    _ => not_supported!("write to arbitrary file descriptor"),
+                    _ => {
+                        let bytes = interval.get(self)?.to_vec();
+                        if let Err(errno) = self.sandbox.write(fd as i32, &bytes) {
+                            return self.write_errno_result(errno, destination);
+                        }
+                    }
                 }
                 destination.write_from_interval(self, len.interval)?;
                 Ok(())
             }
+            "open" | "open64" => {
+                let [path, flags, ..] = args else {
+                    return Err(MirEvalError::TypeError("libc::open args are not provided"));
+                };
+                let path = self.read_c_str(Address::from_bytes(path.get(self)?)?)?;
+                let flags = from_bytes!(i32, &pad16(flags.get(self)?, true)[0..4]);
+                match self.sandbox.open(&path, flags) {
+                    Ok(fd) => {
+                        destination.write_from_bytes(self, &fd.to_le_bytes()[0..destination.size])
+                    }
+                    Err(errno) => self.write_errno_result(errno, destination),
+                }
+            }
+            "read" => {
+                let [fd, ptr, len] = args else {
+                    return Err(MirEvalError::TypeError("libc::read args are not provided"));
+                };
+                let fd = from_bytes!(i32, &pad16(fd.get(self)?, true)[0..4]);
+                let addr = Address::from_bytes(ptr.get(self)?)?;
+                let len = from_bytes!(usize, len.get(self)?);
+                // stdin is always empty
+                let bytes = if fd == 0 { Ok(vec![]) } else { self.sandbox.read(fd, len) };
+                match bytes {
+                    Ok(bytes) => {
+                        self.write_memory(addr, &bytes)?;
+                        destination
+                            .write_from_bytes(self, &bytes.len().to_le_bytes()[0..destination.size])
+                    }
+                    Err(errno) => self.write_errno_result(errno, destination),
+                }
+            }
+            "close" => {
+                let [fd] = args else {
+                    return Err(MirEvalError::TypeError("libc::close args are not provided"));
+                };
+                let fd = from_bytes!(i32, &pad16(fd.get(self)?, true)[0..4]);
+                match self.sandbox.close(fd) {
+                    Ok(()) => {
+                        destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])
+                    }
+                    Err(errno) => self.write_errno_result(errno, destination),
+                }
+            }
+            "lseek" | "lseek64" => {
+                let [fd, offset, whence] = args else {
+                    return Err(MirEvalError::TypeError("libc::lseek args are not provided"));
+                };
+                let fd = from_bytes!(i32, &pad16(fd.get(self)?, true)[0..4]);
+                let offset = from_bytes!(i64, offset.get(self)?);
+                let whence = from_bytes!(i32, &pad16(whence.get(self)?, true)[0..4]);
+                match self.sandbox.seek(fd, offset, whence) {
+                    Ok(offset) => destination
+                        .write_from_bytes(self, &offset.to_le_bytes()[0..destination.size]),
+                    Err(errno) => self.write_errno_result(errno, destination),
+                }
+            }
+            "stat" | "stat64" | "lstat" | "lstat64" | "fstat" | "fstat64" => {
+                let [file, buf] = args else {
+                    return Err(MirEvalError::TypeError("libc::stat args are not provided"));
+                };
+                let stat = if as_str.starts_with('f') {
+                    let fd = from_bytes!(i32, &pad16(file.get(self)?, true)[0..4]);
+                    self.sandbox.fstat(fd)
+                } else {
+                    let path = self.read_c_str(Address::from_bytes(file.get(self)?)?)?;
+                    self.sandbox.stat(&path)
+                };
+                match stat {
+                    Ok((kind, size)) => {
+                        self.write_stat(buf, kind, size, locals)?;
+                        destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])
+                    }
+                    Err(errno) => self.write_errno_result(errno, destination),
+                }
+            }
+            "getenv" => {
+                let [name] = args else {
+                    return Err(MirEvalError::TypeError("libc::getenv args are not provided"));
+                };
+                let name = self.read_c_str(Address::from_bytes(name.get(self)?)?)?;
+                let Some(value) = self.sandbox.env(&name).map(|it| it.to_owned()) else {
+                    return destination.write_from_bytes(self, &vec![0; destination.size]);
+                };
+                let addr = self.allocate_c_str(&value)?;
+                destination.write_from_bytes(self, &addr.to_bytes())
+            }
+            "getcwd" => {
+                let [buf, size] = args else {
+                    return Err(MirEvalError::TypeError("libc::getcwd args are not provided"));
+                };
+                let addr = Address::from_bytes(buf.get(self)?)?;
+                let size = from_bytes!(usize, size.get(self)?);
+                let mut cwd =
+                    self.sandbox.current_dir().to_string_lossy().into_owned().into_bytes();
+                cwd.push(0);
+                if cwd.len() > size {
+                    // ERANGE
+                    self.set_errno(34)?;
+                    return destination.write_from_bytes(self, &vec![0; destination.size]);
+                }
+                self.write_memory(addr, &cwd)?;
+                destination.write_from_interval(self, buf.interval)
+            }
+            "clock_gettime" => {
+                let [clock_id, tp] = args else {
+                    return Err(MirEvalError::TypeError(
+                        "libc::clock_gettime args are not provided",
+                    ));
+                };
+                let clock_id = from_bytes!(i32, &pad16(clock_id.get(self)?, true)[0..4]);
+                let addr = Address::from_bytes(tp.get(self)?)?;
+                let (secs, nanos) = self.sandbox.clock_gettime(clock_id);
+                // struct timespec { tv_sec: time_t, tv_nsec: c_long }
+                let ptr_size = self.ptr_size();
+                self.write_memory(addr, &secs.to_le_bytes()[0..ptr_size])?;
+                self.write_memory(addr.offset(ptr_size), &nanos.to_le_bytes()[0..ptr_size])?;
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])
+            }
+            "isatty" => {
+                // None of the standard streams is a terminal.
+                self.set_errno(sandbox::ENOTTY)?;
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])
+            }
+            "dlsym" => {
+                // No symbol can be looked up dynamically, so std falls back to the raw syscalls.
+                destination.write_from_bytes(self, &vec![0; destination.size])
+            }
             "pthread_key_create" => {
-                let key = self.thread_local_storage.create_key();
+                let key = self.threads.create_tls_key();
                 let Some(arg0) = args.get(0) else {
                     return Err(MirEvalError::TypeError("pthread_key_create arg0 is not provided"));
                 };
@@ -343,7 +598,7 @@ impl Evaluator<'_> {
                     ));
                 };
                 let key = from_bytes!(usize, &pad16(arg0.get(self)?, false)[0..8]);
-                let value = self.thread_local_storage.get_key(key)?;
+                let value = self.threads.get_tls_key(key)?;
                 destination.write_from_bytes(self, &value.to_le_bytes()[0..destination.size])?;
                 Ok(())
             }
@@ -360,7 +615,7 @@ impl Evaluator<'_> {
                     ));
                 };
                 let value = from_bytes!(u128, pad16(arg1.get(self)?, false));
-                self.thread_local_storage.set_key(key, value)?;
+                self.threads.set_tls_key(key, value)?;
                 // return 0 as success
                 destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
                 Ok(())
@@ -371,11 +626,66 @@ impl Evaluator<'_> {
                 destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
                 Ok(())
             }
+            "pthread_create" => {
+                let [native, _attr, start_routine, arg] = args else {
+                    return Err(MirEvalError::TypeError("pthread_create args are not provided"));
+                };
+                let Some((native_ty, ..)) = native.ty.as_reference_or_ptr() else {
+                    return Err(MirEvalError::TypeError("pthread_create arg0 is not a pointer"));
+                };
+                let native = Interval::new(
+                    Address::from_bytes(native.get(self)?)?,
+                    self.size_of_sized(native_ty, locals, "pthread_create thread arg")?,
+                );
+                let id = self.spawn_thread(start_routine.interval, arg, locals, span)?;
+                native.write_from_bytes(self, &id.to_le_bytes()[0..native.size])?;
+                // return 0 as success
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
+                Ok(())
+            }
+            "pthread_join" => {
+                let [thread, retval] = args else {
+                    return Err(MirEvalError::TypeError("pthread_join args are not provided"));
+                };
+                let thread = from_bytes!(usize, &pad16(thread.get(self)?, false)[0..8]);
+                let retval = Address::from_bytes(retval.get(self)?)?;
+                self.join_thread(thread, destination, retval)
+            }
+            "pthread_detach" => {
+                let Some(thread) = args.get(0) else {
+                    return Err(MirEvalError::TypeError("pthread_detach arg0 is not provided"));
+                };
+                let thread = from_bytes!(usize, &pad16(thread.get(self)?, false)[0..8]);
+                self.threads.detach(thread)?;
+                // return 0 as success
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
+                Ok(())
+            }
+            "pthread_self" => {
+                let id = self.threads.current();
+                destination.write_from_bytes(self, &id.to_le_bytes()[0..destination.size])?;
+                Ok(())
+            }
+            "pthread_attr_init" | "pthread_attr_destroy" | "pthread_attr_setstacksize" => {
+                // we don't have a stack size limit per thread, so we ignore these
+                // return 0 as success
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
+                Ok(())
+            }
+            "sched_yield" => {
+                self.threads.yield_now();
+                // return 0 as success
+                destination.write_from_bytes(self, &0u64.to_le_bytes()[0..destination.size])?;
+                Ok(())
+            }
+            "__errno_location" => {
+                let addr = self.errno_location()?;
+                destination.write_from_bytes(self, &addr.to_bytes())?;
+                Ok(())
+            }
             "syscall" => {
                 let Some((id, rest)) = args.split_first() else {
-                    return Err(MirEvalError::TypeError(
-                        "syscall arg1 is not provided",
-                    ));
+                    return Err(MirEvalError::TypeError("syscall arg1 is not provided"));
                 };
                 let id = from_bytes!(i64, id.get(self)?);
                 self.exec_syscall(id, rest, destination, locals, span)
@@ -998,7 +1308,11 @@ impl Evaluator<'_> {
         _span: MirSpan,
     ) -> Result<()> {
         // We are a single threaded runtime with no UB checking and no optimization, so
-        // we can implement these as normal functions.
+        // we can implement these as normal functions. Threads are only switched between
+        // statements, so every atomic operation is also sequentially consistent.
+        if name.starts_with("fence_") || name.starts_with("singlethreadfence_") {
+            return Ok(());
+        }
         let Some(ty) = generic_args.as_slice(Interner).get(0).and_then(|it| it.ty(Interner)) else {
             return Err(MirEvalError::TypeError("atomic intrinsic generic arg is not provided"));
         };
@@ -1063,6 +1377,16 @@ impl Evaluator<'_> {
             let ans = !(lhs & rhs);
             return arg0_interval.write_from_bytes(self, &ans.to_le_bytes()[0..destination.size]);
         }
+        let is_max = name.starts_with("max_") || name.starts_with("umax_");
+        if is_max || name.starts_with("min_") || name.starts_with("umin_") {
+            destination.write_from_interval(self, arg0_interval)?;
+            let is_signed = !name.starts_with('u');
+            // Unsigned values are zero extended, so comparing them as `i128` is fine.
+            let lhs = i128::from_le_bytes(pad16(arg0_interval.get(self)?, is_signed));
+            let rhs = i128::from_le_bytes(pad16(arg1.get(self)?, is_signed));
+            let ans = if is_max { lhs.max(rhs) } else { lhs.min(rhs) };
+            return arg0_interval.write_from_bytes(self, &ans.to_le_bytes()[0..destination.size]);
+        }
         let Some(arg2) = args.get(2) else {
             return Err(MirEvalError::TypeError("atomic intrinsic arg2 is not provided"));
         };
@@ -1,12 +1,20 @@
-use base_db::{fixture::WithFixture, FileId};
+use base_db::{fixture::WithFixture, FileId, SourceDatabaseExt};
 use hir_def::db::DefDatabase;
 use syntax::{TextRange, TextSize};
 
 use crate::{db::HirDatabase, test_db::TestDB, Interner, Substitution};
 
-use super::{interpret_mir, MirEvalError};
-
-fn eval_main(db: &TestDB, file_id: FileId) -> Result<(String, String), MirEvalError> {
+use super::{
+    interpret_mir_in_sandbox, MirEvalError, MirEvalFrame, MirEvalLocal, MirEvalObserver,
+    MirEvalSandbox,
+};
+
+fn eval_main(
+    db: &TestDB,
+    file_id: FileId,
+    sandbox: MirEvalSandbox,
+    observer: Option<&mut (dyn MirEvalObserver + 'static)>,
+) -> Result<(String, String), MirEvalError> {
     let module_id = db.module_for_file(file_id);
     let def_map = module_id.def_map(db);
     let scope = &def_map[module_id.local_id].scope;
@@ -30,7 +38,7 @@ fn eval_main(db: &TestDB, file_id: FileId) -> Result<(String, String), MirEvalEr
             db.trait_environment(func_id.into()),
         )
         .map_err(|e| MirEvalError::MirLowerError(func_id.into(), e))?;
-    let (result, stdout, stderr) = interpret_mir(db, body, false);
+    let (result, stdout, stderr) = interpret_mir_in_sandbox(db, body, sandbox, observer);
     result?;
     Ok((stdout, stderr))
 }
@@ -40,9 +48,18 @@ fn check_pass(ra_fixture: &str) {
 }
 
 fn check_pass_and_stdio(ra_fixture: &str, expected_stdout: &str, expected_stderr: &str) {
+    check_pass_in_sandbox(ra_fixture, MirEvalSandbox::default(), expected_stdout, expected_stderr);
+}
+
+fn check_pass_in_sandbox(
+    ra_fixture: &str,
+    sandbox: MirEvalSandbox,
+    expected_stdout: &str,
+    expected_stderr: &str,
+) {
     let (db, file_ids) = TestDB::with_many_files(ra_fixture);
     let file_id = *file_ids.last().unwrap();
-    let x = eval_main(&db, file_id);
+    let x = eval_main(&db, file_id, sandbox, None);
     match x {
         Err(e) => {
             let mut err = String::new();
@@ -182,6 +199,47 @@ fn main() {
     );
 }
 
+#[test]
+fn drop_on_reassignment() {
+    check_pass(
+        r#"
+//- minicore: drop, add, cell, builtin_impls
+
+use core::cell::Cell;
+
+struct X<'a>(&'a Cell<i32>);
+impl<'a> Drop for X<'a> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1)
+    }
+}
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+fn main() {
+    let s = Cell::new(0);
+    let mut x = X(&s);
+    x = X(&s);
+    if s.get() != 1 {
+        should_not_reach();
+    }
+    let y = x;
+    x = X(&s);
+    if s.get() != 1 {
+        should_not_reach();
+    }
+    drop(y);
+    drop(x);
+    if s.get() != 3 {
+        should_not_reach();
+    }
+}
+    "#,
+    );
+}
+
 #[test]
 fn drop_in_place() {
     check_pass(
@@ -702,3 +760,551 @@ fn main() {
 "#,
     );
 }
+
+#[test]
+fn posix_threads() {
+    check_pass(
+        r#"
+//- minicore: option, add
+
+type pthread_t = usize;
+type c_void = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn pthread_create(
+        native: *mut pthread_t,
+        attr: *const c_void,
+        f: extern "C" fn(*mut c_void) -> *mut c_void,
+        value: *mut c_void,
+    ) -> c_int;
+    pub fn pthread_join(native: pthread_t, value: *mut *mut c_void) -> c_int;
+}
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+extern "C" fn add_one(arg: *mut c_void) -> *mut c_void {
+    let counter = arg as *mut i32;
+    *counter += 1;
+    arg
+}
+
+fn main() {
+    let mut counter = 0;
+    let arg = &mut counter as *mut i32 as *mut c_void;
+    let mut t1: pthread_t = 0;
+    let mut t2: pthread_t = 0;
+    pthread_create(&mut t1, 0 as *const c_void, add_one, arg);
+    pthread_create(&mut t2, 0 as *const c_void, add_one, arg);
+    if t1 == t2 {
+        should_not_reach();
+    }
+    let mut result = 0 as *mut c_void;
+    if pthread_join(t1, &mut result) != 0 {
+        should_not_reach();
+    }
+    if result != arg {
+        should_not_reach();
+    }
+    pthread_join(t2, 0 as *mut *mut c_void);
+    if counter != 2 {
+        should_not_reach();
+    }
+}
+        "#,
+    );
+}
+
+#[test]
+fn futex_mutex() {
+    check_pass(
+        r#"
+//- minicore: option, add
+
+type pthread_t = usize;
+type c_void = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn pthread_create(
+        native: *mut pthread_t,
+        attr: *const c_void,
+        f: extern "C" fn(*mut c_void) -> *mut c_void,
+        value: *mut c_void,
+    ) -> c_int;
+    pub fn pthread_join(native: pthread_t, value: *mut *mut c_void) -> c_int;
+    pub fn sched_yield() -> c_int;
+    pub unsafe extern "C" fn syscall(num: i64, ...) -> i64;
+}
+
+extern "rust-intrinsic" {
+    pub fn atomic_cxchg_seqcst_seqcst<T: Copy>(dst: *mut T, old: T, src: T) -> (T, bool);
+    pub fn atomic_xchg_seqcst<T: Copy>(dst: *mut T, src: T) -> T;
+    pub fn atomic_umax_seqcst<T: Copy>(dst: *mut T, src: T) -> T;
+    pub fn atomic_fence_seqcst();
+}
+
+const SYS_futex: i64 = 202;
+const FUTEX_WAIT_PRIVATE: i32 = 128;
+const FUTEX_WAKE_PRIVATE: i32 = 129;
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+struct Shared {
+    // 0: unlocked, 1: locked, 2: locked with waiters
+    lock: u32,
+    counter: i32,
+    max: u32,
+}
+
+fn lock(s: *mut Shared) {
+    if atomic_cxchg_seqcst_seqcst(&mut (*s).lock as *mut u32, 0, 1).1 {
+        return;
+    }
+    while atomic_xchg_seqcst(&mut (*s).lock as *mut u32, 2) != 0 {
+        syscall(SYS_futex, &mut (*s).lock as *mut u32, FUTEX_WAIT_PRIVATE, 2u32, 0usize);
+    }
+}
+
+fn unlock(s: *mut Shared) {
+    if atomic_xchg_seqcst(&mut (*s).lock as *mut u32, 0) == 2 {
+        syscall(SYS_futex, &mut (*s).lock as *mut u32, FUTEX_WAKE_PRIVATE, 1u32);
+    }
+}
+
+extern "C" fn worker(arg: *mut c_void) -> *mut c_void {
+    let s = arg as *mut Shared;
+    let mut i = 0;
+    while i < 3 {
+        lock(s);
+        let old = (*s).counter;
+        // Let the other thread run while holding the lock.
+        sched_yield();
+        (*s).counter = old;
+        (*s).counter += 1;
+        atomic_umax_seqcst(&mut (*s).max as *mut u32, (*s).counter as u32);
+        unlock(s);
+        i += 1;
+    }
+    atomic_fence_seqcst();
+    arg
+}
+
+fn main() {
+    let mut shared = Shared { lock: 0, counter: 0, max: 0 };
+    let arg = &mut shared as *mut Shared as *mut c_void;
+    let mut t1: pthread_t = 0;
+    let mut t2: pthread_t = 0;
+    pthread_create(&mut t1, 0 as *const c_void, worker, arg);
+    pthread_create(&mut t2, 0 as *const c_void, worker, arg);
+    pthread_join(t1, 0 as *mut *mut c_void);
+    pthread_join(t2, 0 as *mut *mut c_void);
+    if shared.counter != 6 {
+        should_not_reach();
+    }
+    if shared.max != 6 {
+        should_not_reach();
+    }
+    if shared.lock != 0 {
+        should_not_reach();
+    }
+}
+        "#,
+    );
+}
+
+#[test]
+fn spinning_threads_are_preempted() {
+    check_pass(
+        r#"
+//- minicore: option, add
+
+type pthread_t = usize;
+type c_void = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn pthread_create(
+        native: *mut pthread_t,
+        attr: *const c_void,
+        f: extern "C" fn(*mut c_void) -> *mut c_void,
+        value: *mut c_void,
+    ) -> c_int;
+    pub fn pthread_join(native: pthread_t, value: *mut *mut c_void) -> c_int;
+}
+
+extern "rust-intrinsic" {
+    pub fn atomic_load_seqcst<T: Copy>(src: *const T) -> T;
+    pub fn atomic_store_seqcst<T: Copy>(dst: *mut T, val: T);
+}
+
+extern "C" fn set_flag(arg: *mut c_void) -> *mut c_void {
+    atomic_store_seqcst(arg as *mut u32, 1);
+    arg
+}
+
+fn main() {
+    let mut flag = 0u32;
+    let arg = &mut flag as *mut u32 as *mut c_void;
+    let mut t: pthread_t = 0;
+    pthread_create(&mut t, 0 as *const c_void, set_flag, arg);
+    // Never blocks, so this only ends once the other thread got to run.
+    while atomic_load_seqcst(&flag as *const u32) == 0 {}
+    pthread_join(t, 0 as *mut *mut c_void);
+}
+        "#,
+    );
+}
+
+#[test]
+fn thread_local_storage() {
+    check_pass(
+        r#"
+//- minicore: option
+
+type pthread_t = usize;
+type pthread_key_t = u32;
+type c_void = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn pthread_create(
+        native: *mut pthread_t,
+        attr: *const c_void,
+        f: extern "C" fn(*mut c_void) -> *mut c_void,
+        value: *mut c_void,
+    ) -> c_int;
+    pub fn pthread_join(native: pthread_t, value: *mut *mut c_void) -> c_int;
+    pub fn pthread_key_create(
+        key: *mut pthread_key_t,
+        dtor: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+    pub fn pthread_getspecific(key: pthread_key_t) -> *mut c_void;
+    pub fn pthread_setspecific(key: pthread_key_t, value: *const c_void) -> c_int;
+}
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+#[thread_local]
+static mut LOCAL: i32 = 5;
+
+static mut KEY: pthread_key_t = 0;
+
+extern "C" fn thread(arg: *mut c_void) -> *mut c_void {
+    if LOCAL != 5 {
+        should_not_reach();
+    }
+    LOCAL = 7;
+    if pthread_getspecific(KEY) != 0 as *mut c_void {
+        should_not_reach();
+    }
+    pthread_setspecific(KEY, arg);
+    arg
+}
+
+fn main() {
+    pthread_key_create(&mut KEY, None);
+    let mut x = 0;
+    let p = &mut x as *mut i32 as *mut c_void;
+    pthread_setspecific(KEY, p);
+    LOCAL = 6;
+    let mut t: pthread_t = 0;
+    pthread_create(&mut t, 0 as *const c_void, thread, p);
+    pthread_join(t, 0 as *mut *mut c_void);
+    if LOCAL != 6 {
+        should_not_reach();
+    }
+    if pthread_getspecific(KEY) != p {
+        should_not_reach();
+    }
+}
+        "#,
+    );
+}
+
+#[test]
+fn sandboxed_files_env_and_time() {
+    let mut sandbox = MirEvalSandbox::default();
+    sandbox.files.insert("/data/input.txt".into(), b"hello".to_vec());
+    sandbox.env.insert("GREETING".to_owned(), "hi".to_owned());
+    check_pass_in_sandbox(
+        r#"
+//- minicore: option, add, slice, index, coerce_unsized
+
+type c_char = u8;
+type c_int = i32;
+
+#[repr(C)]
+struct stat {
+    st_dev: u64,
+    st_ino: u64,
+    st_nlink: u64,
+    st_mode: u32,
+    st_uid: u32,
+    st_gid: u32,
+    __pad0: c_int,
+    st_rdev: u64,
+    st_size: i64,
+    st_blksize: i64,
+    st_blocks: i64,
+    st_times: [i64; 6],
+    __unused: [i64; 3],
+}
+
+#[repr(C)]
+struct timespec {
+    tv_sec: i64,
+    tv_nsec: i64,
+}
+
+extern "C" {
+    pub fn open(path: *const c_char, oflag: c_int, ...) -> c_int;
+    pub fn read(fd: c_int, buf: *mut u8, count: usize) -> isize;
+    pub fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+    pub fn close(fd: c_int) -> c_int;
+    pub fn stat(path: *const c_char, buf: *mut stat) -> c_int;
+    pub fn getenv(s: *const c_char) -> *mut c_char;
+    pub fn clock_gettime(clk_id: c_int, tp: *mut timespec) -> c_int;
+}
+
+const O_WRONLY: c_int = 1;
+const O_CREAT: c_int = 0o100;
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+fn main() {
+    let fd = open(b"/data/input.txt\0" as *const u8, 0);
+    if fd < 0 {
+        should_not_reach();
+    }
+    let mut buf = [0u8; 8];
+    if read(fd, &mut buf[0], 8) != 5 || buf[4] != b'o' {
+        should_not_reach();
+    }
+    if read(fd, &mut buf[0], 8) != 0 {
+        should_not_reach();
+    }
+    close(fd);
+    if open(b"missing.txt\0" as *const u8, 0) != -1 {
+        should_not_reach();
+    }
+
+    let fd = open(b"/data/out.txt\0" as *const u8, O_WRONLY | O_CREAT, 0o644);
+    if write(fd, b"abc" as *const u8, 3) != 3 {
+        should_not_reach();
+    }
+    close(fd);
+    let mut st = stat {
+        st_dev: 0, st_ino: 0, st_nlink: 0, st_mode: 0, st_uid: 0, st_gid: 0, __pad0: 0,
+        st_rdev: 0, st_size: 0, st_blksize: 0, st_blocks: 0, st_times: [0; 6], __unused: [0; 3],
+    };
+    if stat(b"/data/../data/out.txt\0" as *const u8, &mut st) != 0 || st.st_size != 3 {
+        should_not_reach();
+    }
+    if stat(b"/data\0" as *const u8, &mut st) != 0 || st.st_mode & 0o170000 != 0o040000 {
+        should_not_reach();
+    }
+
+    let value = getenv(b"GREETING\0" as *const u8);
+    if *value != b'h' {
+        should_not_reach();
+    }
+    if getenv(b"HOME\0" as *const u8) != 0 as *mut c_char {
+        should_not_reach();
+    }
+
+    let mut t1 = timespec { tv_sec: 0, tv_nsec: 0 };
+    let mut t2 = timespec { tv_sec: 0, tv_nsec: 0 };
+    clock_gettime(1, &mut t1);
+    clock_gettime(1, &mut t2);
+    if t2.tv_nsec <= t1.tv_nsec {
+        should_not_reach();
+    }
+}
+        "#,
+        sandbox,
+        "",
+        "",
+    );
+}
+
+#[test]
+fn sandboxed_read_only_workspace() {
+    let mut sandbox = MirEvalSandbox::default();
+    sandbox.read_only_root = Some(env!("CARGO_MANIFEST_DIR").into());
+    check_pass_in_sandbox(
+        r#"
+//- minicore: slice, index, coerce_unsized
+type c_char = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn open(path: *const c_char, oflag: c_int, ...) -> c_int;
+    pub fn read(fd: c_int, buf: *mut u8, count: usize) -> isize;
+    pub fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+}
+
+const O_RDWR: c_int = 2;
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+fn main() {
+    // Relative paths are resolved against the root.
+    let fd = open(b"Cargo.toml\0" as *const u8, 0);
+    let mut buf = [0u8; 9];
+    if read(fd, &mut buf[0], 9) != 9 || buf[0] != b'[' {
+        should_not_reach();
+    }
+    if write(fd, b"x" as *const u8, 1) != -1 {
+        should_not_reach();
+    }
+    // Writes go to an in-memory copy of the file.
+    let fd = open(b"Cargo.toml\0" as *const u8, O_RDWR);
+    if write(fd, b"x" as *const u8, 1) != 1 {
+        should_not_reach();
+    }
+    let fd = open(b"Cargo.toml\0" as *const u8, 0);
+    if read(fd, &mut buf[0], 9) != 9 || buf[0] != b'x' || buf[1] != b'p' {
+        should_not_reach();
+    }
+    // Files outside of the root are not visible.
+    if open(b"../../Cargo.toml\0" as *const u8, 0) != -1 {
+        should_not_reach();
+    }
+}
+        "#,
+        sandbox,
+        "",
+        "",
+    );
+    let manifest = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+    assert!(manifest.unwrap().starts_with("[package]"));
+}
+
+#[test]
+fn sandboxed_seek_and_write_limits() {
+    let mut sandbox = MirEvalSandbox::default();
+    sandbox.files.insert("/data.txt".into(), b"hello".to_vec());
+    check_pass_in_sandbox(
+        r#"
+//- minicore: slice, index, coerce_unsized
+type c_char = u8;
+type c_int = i32;
+
+extern "C" {
+    pub fn open(path: *const c_char, oflag: c_int, ...) -> c_int;
+    pub fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+    pub fn lseek(fd: c_int, offset: i64, whence: c_int) -> i64;
+    pub fn __errno_location() -> *mut c_int;
+}
+
+const O_RDWR: c_int = 2;
+const SEEK_SET: c_int = 0;
+const SEEK_END: c_int = 2;
+const EINVAL: c_int = 22;
+const EFBIG: c_int = 27;
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+fn main() {
+    let fd = open(b"/data.txt\0" as *const u8, O_RDWR);
+    // The end of the file plus the offset doesn't fit in an `i64`.
+    if lseek(fd, 9223372036854775807, SEEK_END) != -1 || *__errno_location() != EINVAL {
+        should_not_reach();
+    }
+    if lseek(fd, 0, SEEK_END) != 5 {
+        should_not_reach();
+    }
+    // Writing far past the end would grow the file beyond the size limit.
+    if lseek(fd, 9223372036854775807, SEEK_SET) != 9223372036854775807 {
+        should_not_reach();
+    }
+    if write(fd, b"x" as *const u8, 1) != -1 || *__errno_location() != EFBIG {
+        should_not_reach();
+    }
+    if lseek(fd, 1 << 40, SEEK_SET) != 1 << 40 {
+        should_not_reach();
+    }
+    if write(fd, b"x" as *const u8, 1) != -1 || *__errno_location() != EFBIG {
+        should_not_reach();
+    }
+    // The failed writes left the file alone.
+    if lseek(fd, 0, SEEK_END) != 5 {
+        should_not_reach();
+    }
+}
+        "#,
+        sandbox,
+        "",
+        "",
+    );
+}
+
+#[derive(Default)]
+struct StepRecorder {
+    /// The depth of the stack, the source text and the locals of the innermost frame, for each step.
+    steps: Vec<(usize, String, Vec<MirEvalLocal>)>,
+    file_text: String,
+}
+
+impl MirEvalObserver for StepRecorder {
+    fn step(&mut self, stack: &[MirEvalFrame<'_, '_>]) {
+        let frame = stack.last().unwrap();
+        let text =
+            frame.source_range().map(|it| self.file_text[it.range].to_owned()).unwrap_or_default();
+        self.steps.push((stack.len(), text, frame.locals()));
+    }
+}
+
+#[test]
+fn observer_sees_stack_and_locals() {
+    let fixture = r#"
+fn add(a: i32, b: i32) -> i32 {
+    let c = a;
+    c
+}
+
+fn main() {
+    let x = 2;
+    let y = add(x, 3);
+}
+"#;
+    let (db, file_id) = TestDB::with_single_file(fixture);
+    let mut recorder =
+        StepRecorder { file_text: db.file_text(file_id).to_string(), ..Default::default() };
+    eval_main(&db, file_id, MirEvalSandbox::default(), Some(&mut recorder)).unwrap();
+    let render = |locals: &[MirEvalLocal]| {
+        locals
+            .iter()
+            .map(|it| {
+                format!(
+                    "{}: {} = {}",
+                    it.name.display(&db),
+                    it.ty,
+                    it.value.as_deref().unwrap_or("?")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let (_, text, locals) =
+        recorder.steps.iter().rev().find(|it| it.0 == 2 && it.1 == "c").unwrap();
+    assert_eq!(text, "c");
+    assert_eq!(render(locals), "a: i32 = 2, b: i32 = 3, c: i32 = 2");
+    assert!(recorder.steps.iter().all(|it| it.0 <= 2));
+    let (depth, _, locals) = recorder.steps.last().unwrap();
+    assert_eq!(*depth, 1);
+    assert_eq!(render(locals), "x: i32 = 2, y: i32 = 2");
+}
@@ -0,0 +1,378 @@
+//! Deterministic emulation of the threads of the interpreted program.
+//!
+//! Every thread runs on the thread of the evaluator, in a green thread fashion. The running thread
+//! keeps running until it blocks (on a futex or on joining another thread), yields, finishes or has
+//! used up its time slice of [`TIME_SLICE`] basic blocks, and then the next runnable thread in the
+//! order of creation takes over. Each thread has its own code stack, its own values for the pthread
+//! TLS keys and `#[thread_local]` statics, and its own `errno`.
+//!
+//! Threads are only switched between two basic blocks of the outermost evaluation, as the frames of
+//! nested evaluations (like the ones of closures called by shims) live on the native stack. A thread
+//! which spins without blocking inside a nested evaluation thus never lets the others run.
+
+use std::mem;
+
+use hir_def::StaticId;
+use rustc_hash::FxHashMap;
+
+use crate::mir::{BasicBlockId, MirSpan};
+
+use super::{
+    Address, Evaluator, Interval, IntervalAndTy, Locals, MirEvalError, Result, StackFrame,
+};
+
+pub(super) type ThreadId = usize;
+
+/// `EAGAIN`, returned by `FUTEX_WAIT` when the futex doesn't have the expected value.
+pub(super) const EAGAIN: i32 = 11;
+/// `EDEADLK`, returned by `pthread_join` when a thread tries to join itself.
+pub(super) const EDEADLK: i32 = 35;
+const ETIMEDOUT: i32 = 110;
+
+/// The number of basic blocks a thread may run before the next runnable thread takes over, so that
+/// threads spinning on an atomic make progress. Scheduling stays deterministic, as it only depends
+/// on the executed code.
+const TIME_SLICE: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum BlockedOn {
+    /// `FUTEX_WAIT`, `result` is where the return value of the syscall should be written on wake up.
+    Futex { addr: Address, bitset: u32, result: Interval, has_timeout: bool },
+    /// `pthread_join`, `retval` is the (possibly null) location for the return value of the thread.
+    Join { thread: ThreadId, result: Interval, retval: Address },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ThreadState {
+    Runnable,
+    Blocked(BlockedOn),
+    Finished,
+}
+
+struct Thread {
+    state: ThreadState,
+    /// Code stack and the block to continue from, for threads that are not running at the moment.
+    suspended: Option<(Vec<StackFrame>, BasicBlockId)>,
+    /// Where the start routine writes its return value. `None` for the main thread.
+    return_value: Option<Interval>,
+    tls_values: Vec<u128>,
+    thread_local_statics: FxHashMap<StaticId, Address>,
+    errno: Option<Address>,
+}
+
+impl Thread {
+    fn new(state: ThreadState, return_value: Option<Interval>) -> Thread {
+        Thread {
+            state,
+            suspended: None,
+            return_value,
+            tls_values: vec![],
+            thread_local_statics: FxHashMap::default(),
+            errno: None,
+        }
+    }
+}
+
+pub(super) struct Threads {
+    /// The first thread is the one that started the evaluation.
+    threads: Vec<Thread>,
+    current: ThreadId,
+    tls_key_count: usize,
+    /// Set by shims that blocked the current thread or yielded, the switch happens right after the
+    /// shim returns.
+    switch_requested: bool,
+    /// Count of nested `interpret_mir` calls. Threads can only be switched in the outermost one, as
+    /// the frames of the nested ones live on the native stack.
+    depth: usize,
+    /// The basic blocks the current thread has executed since it was resumed.
+    steps: usize,
+}
+
+impl Default for Threads {
+    fn default() -> Self {
+        Threads {
+            threads: vec![Thread::new(ThreadState::Runnable, None)],
+            current: 0,
+            tls_key_count: 0,
+            switch_requested: false,
+            depth: 0,
+            steps: 0,
+        }
+    }
+}
+
+impl Threads {
+    pub(super) fn current(&self) -> ThreadId {
+        self.current
+    }
+
+    pub(super) fn is_main_thread_running(&self) -> bool {
+        self.current == 0
+    }
+
+    pub(super) fn switch_requested(&self) -> bool {
+        self.switch_requested
+    }
+
+    pub(super) fn enter(&mut self) -> bool {
+        self.depth += 1;
+        self.depth == 1
+    }
+
+    pub(super) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(super) fn create_tls_key(&mut self) -> usize {
+        self.tls_key_count += 1;
+        self.tls_key_count - 1
+    }
+
+    pub(super) fn get_tls_key(&self, key: usize) -> Result<u128> {
+        if key >= self.tls_key_count {
+            return Err(MirEvalError::UndefinedBehavior(format!("Getting invalid tls key {key}")));
+        }
+        Ok(self.threads[self.current].tls_values.get(key).copied().unwrap_or_default())
+    }
+
+    pub(super) fn set_tls_key(&mut self, key: usize, value: u128) -> Result<()> {
+        if key >= self.tls_key_count {
+            return Err(MirEvalError::UndefinedBehavior(format!("Setting invalid tls key {key}")));
+        }
+        let values = &mut self.threads[self.current].tls_values;
+        if values.len() <= key {
+            values.resize(key + 1, 0);
+        }
+        values[key] = value;
+        Ok(())
+    }
+
+    pub(super) fn thread_local_static(&self, st: StaticId) -> Option<Address> {
+        self.threads[self.current].thread_local_statics.get(&st).copied()
+    }
+
+    pub(super) fn set_thread_local_static(&mut self, st: StaticId, addr: Address) {
+        self.threads[self.current].thread_local_statics.insert(st, addr);
+    }
+
+    /// Threads are never joined implicitly, so detaching only needs to check the id.
+    pub(super) fn detach(&self, thread: ThreadId) -> Result<()> {
+        match self.threads.get(thread) {
+            Some(_) => Ok(()),
+            None => {
+                Err(MirEvalError::UndefinedBehavior(format!("Detaching invalid thread {thread}")))
+            }
+        }
+    }
+
+    /// Counts a basic block of the outermost evaluation, and requests a switch once the current
+    /// thread has used up its time slice.
+    pub(super) fn step(&mut self) {
+        if self.depth == 1 {
+            self.steps += 1;
+            if self.steps >= TIME_SLICE {
+                self.switch_requested = true;
+            }
+        }
+    }
+
+    /// Makes the current thread give up its turn, without blocking it.
+    pub(super) fn yield_now(&mut self) {
+        // Switching in a nested evaluation is not possible, but yielding is just a hint anyway.
+        if self.depth == 1 {
+            self.switch_requested = true;
+        }
+    }
+}
+
+impl Evaluator<'_> {
+    /// Creates a new runnable thread that calls the function pointer `start_routine` with `arg`.
+    pub(super) fn spawn_thread(
+        &mut self,
+        start_routine: Interval,
+        arg: &IntervalAndTy,
+        locals: &Locals,
+        span: MirSpan,
+    ) -> Result<ThreadId> {
+        let id = self.threads.threads.len();
+        let ptr_size = self.ptr_size();
+        let return_value = Interval::new(self.heap_allocate(ptr_size, ptr_size)?, ptr_size);
+        // The target block is only a placeholder, the root frame of a thread has no caller to return to.
+        let frame = self.exec_fn_pointer(
+            start_routine,
+            return_value,
+            &[arg.clone()],
+            locals,
+            Some(locals.body.start_block),
+            span,
+        )?;
+        let thread = match frame {
+            Some(frame) => {
+                let frame = StackFrame { destination: None, ..frame };
+                let block = frame.body.start_block;
+                let mut thread = Thread::new(ThreadState::Runnable, Some(return_value));
+                thread.suspended = Some((vec![frame], block));
+                thread
+            }
+            // The start routine was handled by a shim, so the thread is already done.
+            None => Thread::new(ThreadState::Finished, Some(return_value)),
+        };
+        self.threads.threads.push(thread);
+        Ok(id)
+    }
+
+    /// Blocks the current thread until another thread wakes it up. The switch happens when the
+    /// calling shim returns.
+    pub(super) fn block_current_thread(&mut self, on: BlockedOn) -> Result<()> {
+        if self.threads.depth != 1 {
+            return Err(MirEvalError::NotSupported(
+                "blocking a thread inside a nested evaluation".to_owned(),
+            ));
+        }
+        self.threads.threads[self.threads.current].state = ThreadState::Blocked(on);
+        self.threads.switch_requested = true;
+        Ok(())
+    }
+
+    /// Implements `pthread_join`. Writes the result to `result` if the thread is already finished,
+    /// and blocks the current thread otherwise.
+    pub(super) fn join_thread(
+        &mut self,
+        thread: ThreadId,
+        result: Interval,
+        retval: Address,
+    ) -> Result<()> {
+        if thread == self.threads.current {
+            return result.write_from_bytes(self, &EDEADLK.to_le_bytes()[0..result.size]);
+        }
+        match self.threads.threads.get(thread).map(|it| it.state) {
+            Some(ThreadState::Finished) => self.write_join_result(thread, result, retval),
+            Some(_) => self.block_current_thread(BlockedOn::Join { thread, result, retval }),
+            None => {
+                Err(MirEvalError::UndefinedBehavior(format!("Joining invalid thread {thread}")))
+            }
+        }
+    }
+
+    fn write_join_result(
+        &mut self,
+        thread: ThreadId,
+        result: Interval,
+        retval: Address,
+    ) -> Result<()> {
+        if retval != Address::Invalid(0) {
+            if let Some(return_value) = self.threads.threads[thread].return_value {
+                Interval::new(retval, return_value.size).write_from_interval(self, return_value)?;
+            }
+        }
+        result.write_from_bytes(self, &0u64.to_le_bytes()[0..result.size])
+    }
+
+    /// Implements `FUTEX_WAKE`, and returns the number of woken threads.
+    pub(super) fn wake_futex(&mut self, addr: Address, bitset: u32, count: usize) -> Result<usize> {
+        let mut woken = 0;
+        for id in 0..self.threads.threads.len() {
+            if woken == count {
+                break;
+            }
+            let ThreadState::Blocked(BlockedOn::Futex {
+                addr: waiting_on,
+                bitset: waiting_bitset,
+                result,
+                ..
+            }) = self.threads.threads[id].state
+            else {
+                continue;
+            };
+            if waiting_on != addr || waiting_bitset & bitset == 0 {
+                continue;
+            }
+            result.write_from_bytes(self, &0u64.to_le_bytes()[0..result.size])?;
+            self.threads.threads[id].state = ThreadState::Runnable;
+            woken += 1;
+        }
+        Ok(woken)
+    }
+
+    /// Returns the address of the `errno` of the current thread.
+    pub(super) fn errno_location(&mut self) -> Result<Address> {
+        if let Some(it) = self.threads.threads[self.threads.current].errno {
+            return Ok(it);
+        }
+        let addr = self.heap_allocate(4, 4)?;
+        self.threads.threads[self.threads.current].errno = Some(addr);
+        Ok(addr)
+    }
+
+    pub(super) fn set_errno(&mut self, errno: i32) -> Result<()> {
+        let addr = self.errno_location()?;
+        self.write_memory(addr, &errno.to_le_bytes())
+    }
+
+    /// Suspends the current thread, which will continue from `current_block`, and loads the next
+    /// runnable thread into the code stack.
+    pub(super) fn switch_thread(&mut self, current_block: &mut BasicBlockId) -> Result<()> {
+        self.threads.switch_requested = false;
+        let frames = mem::take(&mut self.code_stack);
+        self.threads.threads[self.threads.current].suspended = Some((frames, *current_block));
+        self.resume_next_thread(current_block)
+    }
+
+    /// Marks the current thread as finished, wakes up the threads joining it, and loads the next
+    /// runnable thread into the code stack.
+    pub(super) fn finish_current_thread(&mut self, current_block: &mut BasicBlockId) -> Result<()> {
+        let finished = self.threads.current;
+        self.threads.threads[finished].state = ThreadState::Finished;
+        for id in 0..self.threads.threads.len() {
+            if let ThreadState::Blocked(BlockedOn::Join { thread, result, retval }) =
+                self.threads.threads[id].state
+            {
+                if thread == finished {
+                    self.write_join_result(finished, result, retval)?;
+                    self.threads.threads[id].state = ThreadState::Runnable;
+                }
+            }
+        }
+        self.resume_next_thread(current_block)
+    }
+
+    fn resume_next_thread(&mut self, current_block: &mut BasicBlockId) -> Result<()> {
+        let count = self.threads.threads.len();
+        // Round robin, starting from the thread after the current one and ending with the current one.
+        let next = (1..=count).map(|i| (self.threads.current + i) % count).find(|&id| {
+            matches!(self.threads.threads[id].state, ThreadState::Runnable)
+                && self.threads.threads[id].suspended.is_some()
+        });
+        let next = match next {
+            Some(it) => it,
+            // Time only passes when every thread is waiting, so a timed wait can expire now.
+            None => self.expire_timed_wait()?,
+        };
+        let Some((frames, block)) = self.threads.threads[next].suspended.take() else {
+            return Err(MirEvalError::NotSupported("resuming a running thread".to_owned()));
+        };
+        self.threads.current = next;
+        self.threads.steps = 0;
+        self.code_stack = frames;
+        *current_block = block;
+        Ok(())
+    }
+
+    fn expire_timed_wait(&mut self) -> Result<ThreadId> {
+        let Some(id) = self.threads.threads.iter().position(|it| {
+            matches!(it.state, ThreadState::Blocked(BlockedOn::Futex { has_timeout: true, .. }))
+        }) else {
+            return Err(MirEvalError::Deadlock);
+        };
+        if let ThreadState::Blocked(BlockedOn::Futex { result, .. }) =
+            self.threads.threads[id].state
+        {
+            result.write_from_bytes(self, &(-1i64).to_le_bytes()[0..result.size])?;
+        }
+        self.threads.threads[id].state = ThreadState::Runnable;
+        let current = mem::replace(&mut self.threads.current, id);
+        self.set_errno(ETIMEDOUT)?;
+        self.threads.current = current;
+        Ok(id)
+    }
+}
@@ -261,7 +261,8 @@ impl Filler<'_> {
                         | Rvalue::Discriminant(_)
                         | Rvalue::CopyForDeref(_) => (),
                     },
-                    StatementKind::Deinit(_)
+                    StatementKind::FakeRead(_)
+                    | StatementKind::Deinit(_)
                     | StatementKind::StorageLive(_)
                     | StatementKind::StorageDead(_)
                     | StatementKind::Nop => (),
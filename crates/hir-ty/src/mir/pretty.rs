@@ -228,6 +228,11 @@ impl<'a> MirPrettyCtx<'a> {
                         StatementKind::StorageLive(p) => {
                             wln!(this, "StorageLive({})", this.local_name(*p).display(self.db));
                         }
+                        StatementKind::FakeRead(p) => {
+                            w!(this, "FakeRead(");
+                            this.place(p);
+                            wln!(this, ");");
+                        }
                         StatementKind::Deinit(p) => {
                             w!(this, "Deinit(");
                             this.place(p);
@@ -112,7 +112,9 @@ impl Iterator for SuperTraits<'_> {
     }
 }
 
-fn direct_super_traits(db: &dyn DefDatabase, trait_: TraitId, cb: impl FnMut(TraitId)) {
+/// Calls `cb` for each trait that is a direct super trait of `trait_`, that is a trait bound
+/// on `Self` in the trait declaration or its `where` clauses.
+pub fn direct_super_traits(db: &dyn DefDatabase, trait_: TraitId, cb: impl FnMut(TraitId)) {
     let resolver = trait_.resolver(db);
     let generic_params = db.generic_params(trait_.into());
     let trait_self = generic_params.find_trait_self_param();
@@ -3,6 +3,8 @@
 //!
 //! This probably isn't the best way to do this -- ideally, diagnostics should
 //! be expressed in terms of hir types themselves.
+pub use base_db::ProcMacroDiagnosticLevel;
+pub use hir_expand::proc_macro::EmittedDiagnostic;
 pub use hir_ty::diagnostics::{CaseType, IncoherentImpl, IncorrectCase};
 
 use base_db::CrateId;
@@ -12,7 +14,9 @@ use hir_def::path::ModPath;
 use hir_expand::{name::Name, HirFileId, InFile};
 use syntax::{ast, AstPtr, SyntaxError, SyntaxNodePtr, TextRange};
 
-use crate::{AssocItem, Field, Local, MacroKind, Type};
+use crate::{
+    AssocItem, Field, Function, GenericDef, Impl, Local, MacroKind, Trait, TraitRef, Type,
+};
 
 macro_rules! diagnostics {
     ($($diag:ident,)*) => {
@@ -32,7 +36,9 @@ macro_rules! diagnostics {
 }
 
 diagnostics![
+    AssignToBorrowed,
     BreakOutsideOfLoop,
+    ConflictingBorrow,
     ExpectedFunction,
     InactiveCode,
     IncorrectCase,
@@ -51,7 +57,13 @@ diagnostics![
     NoSuchField,
     PrivateAssocItem,
     PrivateField,
+    ProcMacroDiagnostic,
     ReplaceFilterMapNextWithFindMap,
+    TraitImplIncorrectSignature,
+    TraitImplMissingAssocItems,
+    TraitImplOrphan,
+    TraitImplOverlap,
+    TraitImplRedundantAssocItems,
     TypedHole,
     TypeMismatch,
     UndeclaredLabel,
@@ -64,7 +76,10 @@ diagnostics![
     UnresolvedMethodCall,
     UnresolvedModule,
     UnresolvedProcMacro,
+    UnsatisfiedTraitBound,
     UnusedMut,
+    UnusedVariable,
+    UseOfMovedValue,
 ];
 
 #[derive(Debug)]
@@ -132,6 +147,8 @@ pub struct UnresolvedProcMacro {
     pub kind: MacroKind,
     /// The crate id of the proc-macro this macro belongs to, or `None` if the proc-macro can't be found.
     pub krate: CrateId,
+    /// The message of the proc-macro server crash the expansion failed with, if any.
+    pub crash: Option<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -148,6 +165,14 @@ pub struct MacroExpansionParseError {
     pub errors: Box<[SyntaxError]>,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProcMacroDiagnostic {
+    pub node: InFile<SyntaxNodePtr>,
+    pub precise_location: Option<TextRange>,
+    /// The diagnostic as emitted by the macro, its ranges are in `node.file_id`.
+    pub diagnostic: EmittedDiagnostic,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct MacroDefError {
     pub node: InFile<AstPtr<ast::Macro>>,
@@ -256,13 +281,110 @@ pub struct NeedMut {
     pub span: InFile<SyntaxNodePtr>,
 }
 
+#[derive(Debug)]
+pub struct UnsatisfiedTraitBound {
+    pub call_expr: InFile<AstPtr<ast::Expr>>,
+    pub trait_ref: TraitRef,
+    /// The item whose generics require the bound.
+    pub callee: GenericDef,
+    /// The bound as written in the callee's generics, if it could be found.
+    pub bound: Option<InFile<AstPtr<ast::TypeBound>>>,
+}
+
 #[derive(Debug)]
 pub struct UnusedMut {
     pub local: Local,
 }
 
+#[derive(Debug)]
+pub struct UnusedVariable {
+    pub local: Local,
+}
+
 #[derive(Debug)]
 pub struct MovedOutOfRef {
     pub ty: Type,
     pub span: InFile<SyntaxNodePtr>,
 }
+
+#[derive(Debug)]
+pub struct UseOfMovedValue {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+/// The kinds of [`ConflictingBorrow`]s, assignments to borrowed places are [`AssignToBorrowed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowConflictKind {
+    /// A mutable borrow of a place that is already borrowed as mutable.
+    SecondMutBorrow,
+    /// A mutable borrow of a place that is already borrowed as shared.
+    MutBorrowOfShared,
+    /// A shared borrow of a place that is already borrowed as mutable.
+    SharedBorrowOfMut,
+    /// A read of a place that is borrowed as mutable.
+    UseOfMutBorrowed,
+    /// A move out of a place that is borrowed.
+    MoveOfBorrowed,
+}
+
+#[derive(Debug)]
+pub struct ConflictingBorrow {
+    pub local: Local,
+    pub kind: BorrowConflictKind,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct AssignToBorrowed {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct TraitImplMissingAssocItems {
+    pub file_id: HirFileId,
+    pub impl_: AstPtr<ast::Impl>,
+    pub missing: Vec<(Name, AssocItem)>,
+}
+
+#[derive(Debug)]
+pub struct TraitImplOrphan {
+    pub file_id: HirFileId,
+    pub impl_: AstPtr<ast::Impl>,
+}
+
+#[derive(Debug)]
+pub struct TraitImplOverlap {
+    pub file_id: HirFileId,
+    pub impl_: AstPtr<ast::Impl>,
+    pub trait_: Trait,
+    pub self_ty: Type,
+    /// The impl that was defined first.
+    pub other: Impl,
+}
+
+#[derive(Debug)]
+pub struct TraitImplRedundantAssocItems {
+    pub file_id: HirFileId,
+    pub trait_: Trait,
+    pub impl_: AstPtr<ast::Impl>,
+    pub assoc_item: (Name, AssocItem),
+}
+
+#[derive(Debug)]
+pub struct TraitImplIncorrectSignature {
+    pub impl_fn: Function,
+    pub trait_fn: Function,
+    pub mismatch: SignatureMismatch,
+}
+
+#[derive(Debug)]
+pub enum SignatureMismatch {
+    /// Exactly one of the methods takes `self`.
+    SelfParam { trait_has_self: bool },
+    /// The number of parameters, including `self`, differs.
+    ParamCount { expected: usize, found: usize },
+    /// The type of a parameter or, if `param` is `None`, of the return value differs.
+    ParamType { param: Option<usize>, expected: Type, found: Type },
+}
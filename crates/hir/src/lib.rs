@@ -36,13 +36,14 @@ mod display;
 use std::{iter, ops::ControlFlow};
 
 use arrayvec::ArrayVec;
-use base_db::{CrateDisplayName, CrateId, CrateOrigin, Edition, FileId, ProcMacroKind};
+use base_db::{CrateDisplayName, CrateId, CrateOrigin, Edition, FileId, FileRange, ProcMacroKind};
 use either::Either;
 use hir_def::{
     body::{BodyDiagnostic, SyntheticSyntax},
     data::adt::VariantData,
     generics::{LifetimeParamData, TypeOrConstParamData, TypeParamProvenance},
     hir::{BindingAnnotation, BindingId, ExprOrPatId, LabelId, Pat},
+    item_scope::UseTreeId,
     item_tree::ItemTreeNode,
     lang_item::LangItemTarget,
     layout::{self, ReprOptions, TargetDataLayout},
@@ -51,20 +52,22 @@ use hir_def::{
     per_ns::PerNs,
     resolver::{HasResolver, Resolver},
     src::HasSource as _,
+    visibility::RawVisibility,
     AssocItemId, AssocItemLoc, AttrDefId, ConstId, ConstParamId, DefWithBodyId, EnumId,
     EnumVariantId, FunctionId, GenericDefId, HasModule, ImplId, InTypeConstId, ItemContainerId,
     LifetimeParamId, LocalEnumVariantId, LocalFieldId, Lookup, MacroExpander, MacroId, ModuleId,
     StaticId, StructId, TraitAliasId, TraitId, TypeAliasId, TypeOrConstParamId, TypeParamId,
     UnionId,
 };
-use hir_expand::{name::name, MacroCallKind};
+use hir_expand::{ast_id_map::FileAstId, name::name, MacroCallKind};
 use hir_ty::{
     all_super_traits, autoderef,
     consteval::{try_const_usize, unknown_const_as_generic, ConstEvalError, ConstExt},
     diagnostics::BodyValidationDiagnostic,
+    direct_super_traits,
     layout::{Layout as TyLayout, RustcEnumVariantIdx, TagEncoding},
     method_resolution::{self, TyFingerprint},
-    mir::{self, interpret_mir},
+    mir::{self, interpret_mir_in_sandbox},
     primitive::UintTy,
     traits::FnTrait,
     AliasTy, CallableDefId, CallableSig, Canonical, CanonicalVarKinds, Cast, ClosureId,
@@ -75,7 +78,7 @@ use hir_ty::{
 use itertools::Itertools;
 use nameres::diagnostics::DefDiagnosticKind;
 use once_cell::unsync::Lazy;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use stdx::{impl_from, never};
 use syntax::{
     ast::{self, HasAttrs as _, HasDocComments, HasName},
@@ -88,14 +91,17 @@ use crate::db::{DefDatabase, HirDatabase};
 pub use crate::{
     attrs::{HasAttrs, Namespace},
     diagnostics::{
-        AnyDiagnostic, BreakOutsideOfLoop, CaseType, ExpectedFunction, InactiveCode,
-        IncoherentImpl, IncorrectCase, InvalidDeriveTarget, MacroDefError, MacroError,
-        MacroExpansionParseError, MalformedDerive, MismatchedArgCount, MissingFields,
-        MissingMatchArms, MissingUnsafe, MovedOutOfRef, NeedMut, NoSuchField, PrivateAssocItem,
-        PrivateField, ReplaceFilterMapNextWithFindMap, TypeMismatch, TypedHole, UndeclaredLabel,
+        AnyDiagnostic, AssignToBorrowed, BorrowConflictKind, BreakOutsideOfLoop, CaseType,
+        ConflictingBorrow, EmittedDiagnostic, ExpectedFunction, InactiveCode, IncoherentImpl,
+        IncorrectCase, InvalidDeriveTarget, MacroDefError, MacroError, MacroExpansionParseError,
+        MalformedDerive, MismatchedArgCount, MissingFields, MissingMatchArms, MissingUnsafe,
+        MovedOutOfRef, NeedMut, NoSuchField, PrivateAssocItem, PrivateField, ProcMacroDiagnostic,
+        ProcMacroDiagnosticLevel, ReplaceFilterMapNextWithFindMap, SignatureMismatch,
+        TraitImplIncorrectSignature, TraitImplMissingAssocItems, TraitImplOrphan, TraitImplOverlap,
+        TraitImplRedundantAssocItems, TypeMismatch, TypedHole, UndeclaredLabel,
         UnimplementedBuiltinMacro, UnreachableLabel, UnresolvedExternCrate, UnresolvedField,
         UnresolvedImport, UnresolvedMacroCall, UnresolvedMethodCall, UnresolvedModule,
-        UnresolvedProcMacro, UnusedMut,
+        UnresolvedProcMacro, UnsatisfiedTraitBound, UnusedMut, UnusedVariable, UseOfMovedValue,
     },
     has_source::HasSource,
     semantics::{PathResolution, Semantics, SemanticsScope, TypeInfo, VisibleTraits},
@@ -114,9 +120,11 @@ pub use {
     cfg::{CfgAtom, CfgExpr, CfgOptions},
     hir_def::{
         attr::{builtin::AttributeTemplate, Attrs, AttrsWithOwner, Documentation},
+        cache_dir::CacheDir,
         data::adt::StructKind,
         find_path::PrefixKind,
         import_map,
+        item_tree::ItemTreeCache,
         lang_item::LangItem,
         nameres::{DefMap, ModuleSource},
         path::{ModPath, PathKind},
@@ -134,7 +142,7 @@ pub use {
     hir_ty::{
         display::{ClosureStyle, HirDisplay, HirDisplayError, HirWrite},
         layout::LayoutError,
-        mir::MirEvalError,
+        mir::{MirEvalError, MirEvalLocal, MirEvalSandbox},
         PointerCast, Safety,
     },
 };
@@ -611,6 +619,7 @@ impl Module {
         self.legacy_macros(db).into_iter().for_each(|m| emit_macro_def_diagnostics(db, acc, m));
 
         let inherent_impls = db.inherent_impls_in_crate(self.id.krate());
+        let trait_impls = db.trait_impls_in_crate(self.id.krate());
 
         for impl_def in self.impl_defs(db) {
             let loc = impl_def.id.lookup(db.upcast());
@@ -633,6 +642,16 @@ impl Module {
                 acc.push(IncoherentImpl { impl_: ast_id_map.get(node.ast_id()), file_id }.into())
             }
 
+            if trait_impls.orphan_impls().contains(&impl_def.id) {
+                let ast_id_map = db.ast_id_map(file_id);
+
+                acc.push(TraitImplOrphan { impl_: ast_id_map.get(node.ast_id()), file_id }.into())
+            }
+
+            if let Some(trait_) = impl_def.trait_(db).filter(|_| !impl_def.is_negative(db)) {
+                emit_trait_impl_diagnostics(db, acc, impl_def, trait_, file_id, node.ast_id());
+            }
+
             for item in impl_def.items(db) {
                 let def: DefWithBody = match item {
                     AssocItem::Function(it) => it.into(),
@@ -666,6 +685,53 @@ impl Module {
         def_map[self.id.local_id].scope.impls().map(Impl::from).collect()
     }
 
+    /// Returns the private named imports of this module, including `use Trait as _;` imports, that
+    /// are neither used by this module nor by its descendants.
+    pub fn unused_imports(self, db: &dyn HirDatabase) -> Vec<InFile<ast::UseTree>> {
+        let _p = profile::span("Module::unused_imports");
+        let def_map = self.id.def_map(db.upcast());
+        // The imports of block modules are used by the items of the enclosing module.
+        let module = self.nearest_non_block_module(db).id;
+        let used = db.used_imports(module);
+
+        let mut imports: FxHashMap<UseTreeId, Vec<_>> = FxHashMap::default();
+        for (import, item) in def_map[self.id.local_id].scope.use_imports() {
+            imports.entry(import).or_default().push(item);
+        }
+        let mut res = Vec::new();
+        for (import, items) in imports {
+            if used.imports.contains(&import) {
+                continue;
+            }
+            let loc = import.import.lookup(db.upcast());
+            if loc.container != self.id {
+                // The entry is glob imported from another module.
+                continue;
+            }
+            let item_tree = loc.id.item_tree(db.upcast());
+            let use_item = &item_tree[loc.id.value];
+            if item_tree[use_item.visibility] != RawVisibility::private() {
+                continue;
+            }
+            let traits: Vec<_> = items
+                .iter()
+                .filter_map(|item| match item {
+                    hir_def::item_scope::ItemInNs::Types(ModuleDefId::TraitId(it)) => Some(*it),
+                    _ => None,
+                })
+                .collect();
+            if !traits.is_empty() && is_trait_import_used(db, module, import, &traits) {
+                continue;
+            }
+            let file_id = loc.id.file_id();
+            res.push(InFile::new(
+                file_id,
+                use_item.use_tree_to_ast(db.upcast(), file_id, import.idx),
+            ));
+        }
+        res
+    }
+
     /// Finds a path that can be used to refer to the given item from within
     /// this module, if possible.
     pub fn find_use_path(
@@ -713,6 +779,50 @@ fn emit_macro_def_diagnostics(db: &dyn HirDatabase, acc: &mut Vec<AnyDiagnostic>
     }
 }
 
+/// Returns whether the items of `traits`, imported by `import` in `module`, are used by the method
+/// calls and associated item paths of the module, or of its descendants glob importing them.
+fn is_trait_import_used(
+    db: &dyn HirDatabase,
+    module: ModuleId,
+    import: UseTreeId,
+    traits: &[TraitId],
+) -> bool {
+    let def_map = module.def_map(db.upcast());
+    let mut modules = vec![module.local_id];
+    let mut bodies = Vec::new();
+    while let Some(module) = modules.pop() {
+        bodies.extend(db.used_imports(def_map.module_id(module)).bodies.iter().copied());
+        modules.extend(
+            def_map[module]
+                .children
+                .values()
+                .copied()
+                .filter(|&child| def_map[child].scope.use_imports().any(|(it, _)| it == import)),
+        );
+    }
+    let is_trait_item = |item: AssocItemId| {
+        let container = match item {
+            AssocItemId::FunctionId(it) => it.lookup(db.upcast()).container,
+            AssocItemId::ConstId(it) => it.lookup(db.upcast()).container,
+            AssocItemId::TypeAliasId(it) => it.lookup(db.upcast()).container,
+        };
+        matches!(container, ItemContainerId::TraitId(it) if traits.contains(&it))
+    };
+    bodies.into_iter().any(|owner| {
+        let body = db.body(owner);
+        let infer = db.infer(owner);
+        body.exprs.iter().any(|(expr, _)| {
+            infer
+                .method_resolution(expr)
+                .map(|(func, _)| func.into())
+                .or_else(|| infer.assoc_resolutions_for_expr(expr).map(|(item, _)| item))
+                .map_or(false, is_trait_item)
+        }) || body.pats.iter().any(|(pat, _)| {
+            infer.assoc_resolutions_for_pat(pat).map_or(false, |(item, _)| is_trait_item(item))
+        })
+    })
+}
+
 fn emit_def_diagnostic(db: &dyn HirDatabase, acc: &mut Vec<AnyDiagnostic>, diag: &DefDiagnostic) {
     emit_def_diagnostic_(db, acc, &diag.kind)
 }
@@ -762,11 +872,18 @@ fn emit_def_diagnostic_(
                 .into(),
             );
         }
-        DefDiagnosticKind::UnresolvedProcMacro { ast, krate } => {
+        DefDiagnosticKind::UnresolvedProcMacro { ast, krate, crash } => {
             let (node, precise_location, macro_name, kind) = precise_macro_call_location(ast, db);
             acc.push(
-                UnresolvedProcMacro { node, precise_location, macro_name, kind, krate: *krate }
-                    .into(),
+                UnresolvedProcMacro {
+                    node,
+                    precise_location,
+                    macro_name,
+                    kind,
+                    krate: *krate,
+                    crash: crash.as_deref().map(ToOwned::to_owned),
+                }
+                .into(),
             );
         }
         DefDiagnosticKind::UnresolvedMacroCall { ast, path } => {
@@ -791,6 +908,13 @@ fn emit_def_diagnostic_(
                 MacroExpansionParseError { node, precise_location, errors: errors.clone() }.into(),
             );
         }
+        DefDiagnosticKind::ProcMacroDiagnostic { ast, diagnostic } => {
+            let (node, precise_location, _, _) = precise_macro_call_location(ast, db);
+            acc.push(
+                ProcMacroDiagnostic { node, precise_location, diagnostic: diagnostic.clone() }
+                    .into(),
+            );
+        }
         DefDiagnosticKind::UnimplementedBuiltinMacro { ast } => {
             let node = ast.to_node(db.upcast());
             // Must have a name, otherwise we wouldn't emit it.
@@ -846,6 +970,123 @@ fn emit_def_diagnostic_(
     }
 }
 
+fn emit_trait_impl_diagnostics(
+    db: &dyn HirDatabase,
+    acc: &mut Vec<AnyDiagnostic>,
+    impl_def: Impl,
+    trait_: Trait,
+    file_id: HirFileId,
+    ast_id: FileAstId<ast::Impl>,
+) {
+    let trait_items = trait_.items(db);
+    let impl_items = impl_def.items(db);
+    // Functions and constants share a namespace, so they can't have the same name.
+    let counterpart = |items: &[AssocItem], item: AssocItem| {
+        let name = item.name(db)?;
+        items.iter().copied().find(|&it| {
+            matches!(
+                (it, item),
+                (AssocItem::TypeAlias(_), AssocItem::TypeAlias(_))
+                    | (
+                        AssocItem::Function(_) | AssocItem::Const(_),
+                        AssocItem::Function(_) | AssocItem::Const(_)
+                    )
+            ) && it.name(db).as_ref() == Some(&name)
+        })
+    };
+
+    let loc = impl_def.id.lookup(db.upcast());
+    for other in hir_ty::method_resolution::overlapping_trait_impls(db, impl_def.id) {
+        // Conflicts between impls of the same crate are only reported at the later one.
+        let other_loc = other.lookup(db.upcast());
+        if loc.container.krate() == other_loc.container.krate() {
+            let is_later = match loc.id.tree_id() == other_loc.id.tree_id() {
+                true => loc.id.value > other_loc.id.value,
+                false => impl_def.id > other,
+            };
+            if !is_later {
+                continue;
+            }
+        }
+        let impl_ = db.ast_id_map(file_id).get(ast_id);
+        acc.push(
+            TraitImplOverlap {
+                file_id,
+                impl_,
+                trait_,
+                self_ty: impl_def.self_ty(db),
+                other: other.into(),
+            }
+            .into(),
+        );
+    }
+
+    // Items of the impl that come from macros which failed to expand are unknown.
+    if db.impl_data_with_diagnostics(impl_def.id).1.is_empty() {
+        let missing: Vec<_> = trait_items
+            .iter()
+            .copied()
+            .filter(|&it| match it {
+                AssocItem::Function(it) => !db.function_data(it.id).has_body(),
+                AssocItem::Const(it) => !db.const_data(it.id).has_body,
+                AssocItem::TypeAlias(it) => db.type_alias_data(it.id).type_ref.is_none(),
+            })
+            .filter(|&it| counterpart(&impl_items, it).is_none())
+            .filter_map(|it| Some((it.name(db)?, it)))
+            .collect();
+        if !missing.is_empty() {
+            let impl_ = db.ast_id_map(file_id).get(ast_id);
+            acc.push(TraitImplMissingAssocItems { file_id, impl_, missing }.into());
+        }
+    }
+
+    for &item in &impl_items {
+        let Some(name) = item.name(db) else { continue };
+        let trait_item = match counterpart(&trait_items, item) {
+            Some(it) => it,
+            None => {
+                let impl_ = db.ast_id_map(file_id).get(ast_id);
+                acc.push(
+                    TraitImplRedundantAssocItems {
+                        file_id,
+                        trait_,
+                        impl_,
+                        assoc_item: (name, item),
+                    }
+                    .into(),
+                );
+                continue;
+            }
+        };
+        let (AssocItem::Function(impl_fn), AssocItem::Function(trait_fn)) = (item, trait_item)
+        else {
+            continue;
+        };
+        let Some(mismatch) =
+            hir_ty::diagnostics::trait_impl_signature_mismatch(db, impl_fn.id, trait_fn.id)
+        else {
+            continue;
+        };
+        let env = db.trait_environment(impl_fn.id.into());
+        let mismatch = match mismatch {
+            hir_ty::diagnostics::SignatureMismatch::SelfParam { trait_has_self } => {
+                SignatureMismatch::SelfParam { trait_has_self }
+            }
+            hir_ty::diagnostics::SignatureMismatch::ParamCount { expected, found } => {
+                SignatureMismatch::ParamCount { expected, found }
+            }
+            hir_ty::diagnostics::SignatureMismatch::ParamType { param, expected, found } => {
+                SignatureMismatch::ParamType {
+                    param,
+                    expected: Type { env: env.clone(), ty: expected },
+                    found: Type { env, ty: found },
+                }
+            }
+        };
+        acc.push(TraitImplIncorrectSignature { impl_fn, trait_fn, mismatch }.into());
+    }
+}
+
 fn precise_macro_call_location(
     ast: &MacroCallKind,
     db: &dyn HirDatabase,
@@ -920,6 +1161,18 @@ fn precise_macro_call_location(
     }
 }
 
+/// Finds the bound that the `idx`-th generic predicate of `def` was lowered from.
+fn trait_bound_source(
+    db: &dyn HirDatabase,
+    def: GenericDefId,
+    idx: usize,
+) -> Option<InFile<AstPtr<ast::TypeBound>>> {
+    let (owner, predicate) = hir_ty::generic_predicate_origin(db, def, idx)?;
+    let sources = hir_def::generics::where_predicate_sources(db.upcast(), owner);
+    let bound = sources.value.get(predicate)?;
+    Some(InFile::new(sources.file_id, AstPtr::new(bound)))
+}
+
 impl HasVisibility for Module {
     fn visibility(&self, db: &dyn HirDatabase) -> Visibility {
         let def_map = self.id.def_map(db.upcast());
@@ -1443,6 +1696,48 @@ impl DefWithBody {
         }
     }
 
+    /// Returns the points where locals of this body, and of the closures in it, are dropped
+    /// without the code saying so. Only locals whose types run a `Drop` impl are included.
+    pub fn implicit_drops(self, db: &dyn HirDatabase) -> Vec<ImplicitDrop> {
+        let def = self.id();
+        let Ok(borrowck_results) = db.borrowck(def) else {
+            return vec![];
+        };
+        let (body, source_map) = db.body_with_source_map(def);
+        let env = db.trait_environment_for_body(def);
+        let mut result = vec![];
+        for borrowck_result in borrowck_results.iter() {
+            let mir_body = &borrowck_result.mir_body;
+            let bindings: FxHashMap<_, _> =
+                mir_body.binding_locals.iter().map(|(binding, &local)| (local, binding)).collect();
+            for drop in mir::implicit_drops(mir_body) {
+                let Some(&binding_id) = bindings.get(&drop.local) else {
+                    continue;
+                };
+                let mir::MirSpan::ExprId(expr) = drop.span else {
+                    continue;
+                };
+                // A `while` drops the bindings of its condition when it doesn't match, but then
+                // they were never bound.
+                if matches!(body[expr], hir_def::hir::Expr::While { .. }) {
+                    continue;
+                }
+                let Ok(expr) = source_map.expr_syntax(expr) else {
+                    continue;
+                };
+                let ty = mir_body.locals[drop.local].ty.clone();
+                if !hir_ty::has_drop_glue(db, ty, env.clone()) {
+                    continue;
+                }
+                let drop = ImplicitDrop { local: Local { parent: def, binding_id }, expr };
+                if !result.contains(&drop) {
+                    result.push(drop);
+                }
+            }
+        }
+        result
+    }
+
     pub fn diagnostics(self, db: &dyn HirDatabase, acc: &mut Vec<AnyDiagnostic>) {
         let krate = self.module(db).id.krate();
 
@@ -1468,13 +1763,22 @@ impl DefWithBody {
                     }
                     .into(),
                 ),
-                BodyDiagnostic::UnresolvedProcMacro { node, krate } => acc.push(
+                BodyDiagnostic::ProcMacroDiagnostic { node, diagnostic } => acc.push(
+                    ProcMacroDiagnostic {
+                        node: node.clone().map(|it| it.into()),
+                        precise_location: None,
+                        diagnostic: diagnostic.clone(),
+                    }
+                    .into(),
+                ),
+                BodyDiagnostic::UnresolvedProcMacro { node, krate, crash } => acc.push(
                     UnresolvedProcMacro {
                         node: node.clone().map(|it| it.into()),
                         precise_location: None,
                         macro_name: None,
                         kind: MacroKind::ProcMacro,
                         krate: *krate,
+                        crash: crash.as_deref().map(ToOwned::to_owned),
                     }
                     .into(),
                 ),
@@ -1594,6 +1898,24 @@ impl DefWithBody {
                         .into(),
                     )
                 }
+                hir_ty::InferenceDiagnostic::UnsatisfiedTraitBound {
+                    call_expr,
+                    callee,
+                    predicate,
+                    trait_ref,
+                } => {
+                    let call_expr = expr_syntax(*call_expr);
+                    let env = db.trait_environment_for_body(self.into());
+                    acc.push(
+                        UnsatisfiedTraitBound {
+                            call_expr,
+                            trait_ref: TraitRef { env, trait_ref: trait_ref.clone() },
+                            callee: (*callee).into(),
+                            bound: trait_bound_source(db, *callee, *predicate),
+                        }
+                        .into(),
+                    )
+                }
             }
         }
         for (pat_or_expr, mismatch) in infer.type_mismatches() {
@@ -1654,13 +1976,77 @@ impl DefWithBody {
                             .into(),
                     )
                 }
+                let local_of = |local_id| {
+                    let binding_id = mir_body
+                        .binding_locals
+                        .iter()
+                        .find_map(|(binding_id, &it)| (it == local_id).then_some(binding_id))?;
+                    // Skip synthetic bindings
+                    if body[binding_id]
+                        .definitions
+                        .iter()
+                        .any(|&pat| source_map.pat_syntax(pat).is_err())
+                    {
+                        return None;
+                    }
+                    Some(Local { parent: self.into(), binding_id })
+                };
+                let span_syntax = |span: mir::MirSpan| -> Option<InFile<SyntaxNodePtr>> {
+                    match span {
+                        mir::MirSpan::ExprId(e) => {
+                            source_map.expr_syntax(e).ok().map(|s| s.map(|it| it.into()))
+                        }
+                        mir::MirSpan::PatId(p) => source_map.pat_syntax(p).ok().map(|s| {
+                            s.map(|it| match it {
+                                Either::Left(e) => e.into(),
+                                Either::Right(e) => e.into(),
+                            })
+                        }),
+                        mir::MirSpan::Unknown => None,
+                    }
+                };
+                for it in &borrowck_result.moved_value_uses {
+                    let (Some(local), Some(span)) = (local_of(it.local), span_syntax(it.span))
+                    else {
+                        continue;
+                    };
+                    acc.push(UseOfMovedValue { local, span }.into());
+                }
+                for it in &borrowck_result.borrow_conflicts {
+                    let (Some(local), Some(span)) = (local_of(it.local), span_syntax(it.span))
+                    else {
+                        continue;
+                    };
+                    let kind = match it.kind {
+                        mir::BorrowConflictKind::SecondMutBorrow => {
+                            BorrowConflictKind::SecondMutBorrow
+                        }
+                        mir::BorrowConflictKind::MutBorrowOfShared => {
+                            BorrowConflictKind::MutBorrowOfShared
+                        }
+                        mir::BorrowConflictKind::SharedBorrowOfMut => {
+                            BorrowConflictKind::SharedBorrowOfMut
+                        }
+                        mir::BorrowConflictKind::UseOfMutBorrowed => {
+                            BorrowConflictKind::UseOfMutBorrowed
+                        }
+                        mir::BorrowConflictKind::MoveOfBorrowed => {
+                            BorrowConflictKind::MoveOfBorrowed
+                        }
+                        mir::BorrowConflictKind::AssignToBorrowed => {
+                            acc.push(AssignToBorrowed { local, span }.into());
+                            continue;
+                        }
+                    };
+                    acc.push(ConflictingBorrow { local, kind, span }.into());
+                }
                 let mol = &borrowck_result.mutability_of_locals;
                 for (binding_id, binding_data) in hir_body.bindings.iter() {
                     if binding_data.problems.is_some() {
                         // We should report specific diagnostics for these problems, not `need-mut` and `unused-mut`.
                         continue;
                     }
-                    let Some(&local) = mir_body.binding_locals.get(binding_id) else {
+                    let Some(&local_id) = mir_body.binding_locals.get(binding_id) else {
                         continue;
                     };
                     if body[binding_id]
@@ -1671,10 +2057,22 @@ impl DefWithBody {
                         // Skip synthetic bindings
                         continue;
                     }
-                    let need_mut = &mol[local];
                     let local = Local { parent: self.into(), binding_id };
+                    let mut need_mut = &mol[local_id];
+                    if *need_mut == mir::MutabilityReason::Unused {
+                        let should_ignore = matches!(body[binding_id].name.as_str(), Some(it) if it.starts_with('_') || it == "self");
+                        let is_from_macro = body[binding_id].definitions.iter().any(|&pat| {
+                            source_map.pat_syntax(pat).map_or(true, |it| it.file_id.is_macro())
+                        });
+                        if !should_ignore && !is_from_macro {
+                            acc.push(UnusedVariable { local }.into());
+                        }
+                        // Like rustc, we report `unused_mut` for unused mutable variables as well.
+                        need_mut = &mir::MutabilityReason::Not;
+                    }
                     match (need_mut, local.is_mut(db)) {
-                        (mir::MutabilityReason::Mut { .. }, true)
+                        (mir::MutabilityReason::Unused, _)
+                        | (mir::MutabilityReason::Mut { .. }, true)
                         | (mir::MutabilityReason::Not, false) => (),
                         (mir::MutabilityReason::Mut { spans }, false) => {
                             for span in spans {
@@ -1970,9 +2368,13 @@ impl Function {
         def_map.fn_as_proc_macro(self.id).map(|id| Macro { id: id.into() })
     }
 
+    /// Runs the function with the MIR interpreter. The function can only see the files and the
+    /// environment of `sandbox`, and `observer` is notified of each step it takes.
     pub fn eval(
         self,
         db: &dyn HirDatabase,
+        sandbox: MirEvalSandbox,
+        observer: Option<Box<dyn EvalObserver>>,
         span_formatter: impl Fn(FileId, TextRange) -> String,
     ) -> String {
         let body = match db.monomorphized_mir_body(
@@ -1987,7 +2389,13 @@ impl Function {
                 return r;
             }
         };
-        let (result, stdout, stderr) = interpret_mir(db, body, false);
+        let mut observer = observer.map(MirObserver);
+        let (result, stdout, stderr) = interpret_mir_in_sandbox(
+            db,
+            body,
+            sandbox,
+            observer.as_mut().map(|it| it as &mut dyn mir::MirEvalObserver),
+        );
         let mut text = match result {
             Ok(_) => "pass".to_string(),
             Err(e) => {
@@ -2008,6 +2416,59 @@ impl Function {
     }
 }
 
+/// Watches an evaluation started by [`Function::eval`]. It can pause the evaluation by simply not
+/// returning from [`EvalObserver::step`].
+pub trait EvalObserver {
+    /// Called before each statement and terminator of the running thread is executed. `stack` is
+    /// its call stack, the innermost frame is the last one.
+    fn step(&mut self, stack: &[EvalFrame<'_, '_>]);
+}
+
+struct MirObserver(Box<dyn EvalObserver>);
+
+impl mir::MirEvalObserver for MirObserver {
+    fn step(&mut self, stack: &[mir::MirEvalFrame<'_, '_>]) {
+        let stack: Vec<_> = stack.iter().map(|frame| EvalFrame { frame }).collect();
+        self.0.step(&stack);
+    }
+}
+
+/// A frame of the call stack of an evaluation, see [`EvalObserver`].
+pub struct EvalFrame<'a, 'db> {
+    frame: &'a mir::MirEvalFrame<'a, 'db>,
+}
+
+impl EvalFrame<'_, '_> {
+    /// The body that this frame executes, the parent of the closure for closures.
+    pub fn owner(&self) -> DefWithBody {
+        self.frame.owner().into()
+    }
+
+    pub fn is_closure(&self) -> bool {
+        self.frame.closure().is_some()
+    }
+
+    /// Whether the frame is at the end of a block, where the locals of the block go out of scope.
+    /// The source range of these steps is the whole block.
+    pub fn is_at_block_end(&self, db: &dyn HirDatabase) -> bool {
+        let mir::MirSpan::ExprId(expr) = self.frame.span() else { return false };
+        matches!(
+            db.body(self.frame.owner())[expr],
+            hir_def::hir::Expr::Block { .. } | hir_def::hir::Expr::Unsafe { .. }
+        )
+    }
+
+    /// The range of the code that is executing in this frame, which is the call for every frame
+    /// but the innermost one. Code inside of macro expansions is mapped up to the macro call.
+    pub fn source_range(&self) -> Option<FileRange> {
+        self.frame.source_range()
+    }
+
+    pub fn locals(&self) -> Vec<MirEvalLocal> {
+        self.frame.locals()
+    }
+}
+
 // Note: logically, this belongs to `hir_ty`, but we are not using it there yet.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Access {
@@ -2157,22 +2618,8 @@ impl Const {
 
     pub fn render_eval(self, db: &dyn HirDatabase) -> Result<String, ConstEvalError> {
         let c = db.const_eval(self.id.into(), Substitution::empty(Interner))?;
-        let data = &c.data(Interner);
-        if let TyKind::Scalar(s) = data.ty.kind(Interner) {
-            if matches!(s, Scalar::Int(_) | Scalar::Uint(_)) {
-                if let hir_ty::ConstValue::Concrete(c) = &data.value {
-                    if let hir_ty::ConstScalar::Bytes(b, _) = &c.interned {
-                        let value = u128::from_le_bytes(mir::pad16(b, false));
-                        let value_signed =
-                            i128::from_le_bytes(mir::pad16(b, matches!(s, Scalar::Int(_))));
-                        if value >= 10 {
-                            return Ok(format!("{} ({:#X})", value_signed, value));
-                        } else {
-                            return Ok(format!("{}", value_signed));
-                        }
-                    }
-                }
-            }
+        if let Some(s) = render_const_int(&c) {
+            return Ok(s);
         }
         if let Ok(s) = mir::render_const_using_debug_impl(db, self.id, &c) {
             return Ok(s);
@@ -2182,6 +2629,27 @@ impl Const {
     }
 }
 
+/// Renders integer constants in decimal, followed by their hex value if it has more than one digit.
+fn render_const_int(c: &hir_ty::Const) -> Option<String> {
+    let data = &c.data(Interner);
+    let TyKind::Scalar(s @ (Scalar::Int(_) | Scalar::Uint(_))) = data.ty.kind(Interner) else {
+        return None;
+    };
+    let hir_ty::ConstValue::Concrete(c) = &data.value else {
+        return None;
+    };
+    let hir_ty::ConstScalar::Bytes(b, _) = &c.interned else {
+        return None;
+    };
+    let value = u128::from_le_bytes(mir::pad16(b, false));
+    let value_signed = i128::from_le_bytes(mir::pad16(b, matches!(s, Scalar::Int(_))));
+    if value >= 10 {
+        Some(format!("{} ({:#X})", value_signed, value))
+    } else {
+        Some(format!("{}", value_signed))
+    }
+}
+
 impl HasVisibility for Const {
     fn visibility(&self, db: &dyn HirDatabase) -> Visibility {
         db.const_visibility(self.id)
@@ -2245,6 +2713,17 @@ impl Trait {
         db.trait_data(self.id).items.iter().map(|(_name, it)| (*it).into()).collect()
     }
 
+    /// Returns the traits which are direct supertraits of this trait.
+    pub fn direct_supertraits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        let mut res = Vec::new();
+        direct_super_traits(db.upcast(), self.id, |it| {
+            if !res.contains(&Trait::from(it)) {
+                res.push(it.into());
+            }
+        });
+        res
+    }
+
     pub fn items_with_supertraits(self, db: &dyn HirDatabase) -> Vec<AssocItem> {
         let traits = all_super_traits(db.upcast(), self.into());
         traits.iter().flat_map(|tr| Trait::from(*tr).items(db)).collect()
@@ -2739,6 +3218,15 @@ impl GenericDef {
     }
 }
 
+/// A local that is dropped without the code saying so, see [`DefWithBody::implicit_drops`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplicitDrop {
+    pub local: Local,
+    /// The expression that drops the local: a block at whose end it goes out of scope, a `break`,
+    /// `continue` or `return` that leaves its scope, or an assignment that overwrites it.
+    pub expr: InFile<AstPtr<ast::Expr>>,
+}
+
 /// A single local definition.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Local {
@@ -4420,6 +4908,38 @@ impl Callable {
     pub fn return_type(&self) -> Type {
         self.ty.derived(self.sig.ret().clone())
     }
+    /// Returns the arguments of the type parameters of the called function itself, as they were
+    /// inferred at the call site. Parameters of the impl or trait of the function are not included.
+    ///
+    /// Returns `None` if the callee isn't a function, or if it has parameters that can't be given
+    /// in a turbofish, like const and `impl Trait` parameters.
+    pub fn inferred_type_args(&self, db: &dyn HirDatabase) -> Option<Vec<Type>> {
+        let Callee::Def(CallableDefId::FunctionId(func)) = self.callee else {
+            return None;
+        };
+        let params = db.generic_params(func.into());
+        let all_explicit_types = params.type_or_consts.iter().all(|(_, it)| {
+            matches!(
+                it,
+                TypeOrConstParamData::TypeParamData(it)
+                    if it.provenance == TypeParamProvenance::TypeParamList
+            )
+        });
+        if !all_explicit_types {
+            return None;
+        }
+        let TyKind::FnDef(_, substs) = self.ty.ty.kind(Interner) else {
+            return None;
+        };
+        // The function's own parameters come before the ones of its parent.
+        let args = substs
+            .iter(Interner)
+            .take(params.type_or_consts.len())
+            .filter_map(|it| it.ty(Interner))
+            .map(|ty| self.ty.derived(ty.clone()))
+            .collect();
+        Some(args)
+    }
 }
 
 fn closure_source(db: &dyn HirDatabase, closure: ClosureId) -> Option<ast::ClosureExpr> {
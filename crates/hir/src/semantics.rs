@@ -20,6 +20,7 @@ use hir_expand::{
     name::{known, AsName},
     ExpansionInfo, MacroCallId,
 };
+use hir_ty::display::HirDisplay;
 use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::{smallvec, SmallVec};
@@ -351,6 +352,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.type_of_pat(pat)
     }
 
+    /// Evaluates `expr` at compile time, if it is a constant expression, and renders its value.
+    pub fn render_const_eval(&self, expr: &ast::Expr) -> Option<String> {
+        self.imp.render_const_eval(expr)
+    }
+
     /// It also includes the changes that binding mode makes in the type. For example in
     /// `let ref x @ Some(_) = None` the result of `type_of_pat` is `Option<T>` but the result
     /// of this function is `&mut Option<T>`
@@ -1146,6 +1152,11 @@ impl<'db> SemanticsImpl<'db> {
             .map(|(ty, coerced)| TypeInfo { original: ty, adjusted: coerced })
     }
 
+    fn render_const_eval(&self, expr: &ast::Expr) -> Option<String> {
+        let c = self.analyze(expr.syntax())?.const_eval_expr(self.db, expr)?;
+        Some(crate::render_const_int(&c).unwrap_or_else(|| c.display(self.db).to_string()))
+    }
+
     fn type_of_binding_in_pat(&self, pat: &ast::IdentPat) -> Option<Type> {
         self.analyze(pat.syntax())?.type_of_binding_in_pat(self.db, pat)
     }
@@ -1687,6 +1698,17 @@ impl SemanticsScope<'_> {
         resolve_hir_path(self.db, &self.resolver, &path)
     }
 
+    /// Resolve a type as-if it was written at the given scope. Like [`Self::speculative_resolve`],
+    /// this doesn't take hygiene into account. Parts of the type that don't resolve, like `_`, are
+    /// unknown types.
+    pub fn speculative_resolve_type(&self, ty: &ast::Type) -> Type {
+        let ctx = LowerCtx::with_file_id(self.db.upcast(), self.file_id);
+        let ty =
+            hir_ty::TyLoweringContext::new(self.db, &self.resolver, self.resolver.module().into())
+                .lower_ty(&crate::TypeRef::from_ast(&ctx, ty.clone()));
+        Type::new_with_resolver(self.db, &self.resolver, ty)
+    }
+
     /// Iterates over associated types that may be specified after the given path (using
     /// `Ty::Assoc` syntax).
     pub fn assoc_type_shorthand_candidates<R>(
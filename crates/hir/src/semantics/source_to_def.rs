@@ -118,7 +118,7 @@ pub(super) struct SourceToDefCtx<'a, 'b> {
 impl SourceToDefCtx<'_, '_> {
     pub(super) fn file_to_def(&self, file: FileId) -> SmallVec<[ModuleId; 1]> {
         let _p = profile::span("SourceBinder::to_module_def");
-        let mut mods = SmallVec::new();
+        let mut mods: SmallVec<[ModuleId; 1]> = SmallVec::new();
         for &crate_id in self.db.relevant_crates(file).iter() {
             // FIXME: inner items
             let crate_def_map = self.db.crate_def_map(crate_id);
@@ -128,6 +128,13 @@ impl SourceToDefCtx<'_, '_> {
                     .map(|local_id| crate_def_map.module_id(local_id)),
             )
         }
+        if mods.len() > 1 {
+            // Modules of the file's active target come first, as the first one is used when a
+            // single module is asked for.
+            let active_target = self.db.active_targets().get(&file).cloned();
+            let crate_graph = self.db.crate_graph();
+            mods.sort_by_key(|module| crate_graph[module.krate()].target != active_target);
+        }
         mods
     }
 
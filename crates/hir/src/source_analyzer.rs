@@ -192,6 +192,16 @@ impl SourceAnalyzer {
         Some((mk_ty(ty), coerced.map(mk_ty)))
     }
 
+    pub(crate) fn const_eval_expr(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<hir_ty::Const> {
+        let expr_id = self.expr_id(db, expr)?;
+        let (owner, ..) = self.def.as_ref()?;
+        db.const_eval_expr(*owner, expr_id).ok()
+    }
+
     pub(crate) fn type_of_pat(
         &self,
         db: &dyn HirDatabase,
@@ -1,13 +1,19 @@
 //! File symbol extraction.
 
-use base_db::FileRange;
+use std::iter;
+
+use base_db::{FileId, FileRange};
 use hir_def::{
-    src::HasSource, AdtId, AssocItemId, DefWithBodyId, HasModule, ImplId, Lookup, MacroId,
-    ModuleDefId, ModuleId, TraitId,
+    src::HasSource,
+    stable_id::{StableDefIdDecoder, StableDefIdEncoder, StableDefIdTables, StableModuleDefId},
+    AdtId, AssocItemId, DefWithBodyId, HasModule, ImplId, Lookup, MacroId, ModuleDefId, ModuleId,
+    TraitId,
 };
-use hir_expand::{HirFileId, InFile};
+use hir_expand::{stable_id::StableFileId, HirFileId, InFile};
 use hir_ty::db::HirDatabase;
-use syntax::{ast::HasName, AstNode, SmolStr, SyntaxNode, SyntaxNodePtr};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use syntax::{ast::HasName, AstNode, SmolStr, SyntaxKind, SyntaxNode, TextRange, TextSize};
 
 use crate::{Module, ModuleDef, Semantics};
 
@@ -28,9 +34,9 @@ pub struct DeclarationLocation {
     /// The file id for both the `ptr` and `name_ptr`.
     pub hir_file_id: HirFileId,
     /// This points to the whole syntax node of the declaration.
-    pub ptr: SyntaxNodePtr,
+    pub ptr: DeclarationPtr,
     /// This points to the [`syntax::ast::Name`] identifier of the declaration.
-    pub name_ptr: SyntaxNodePtr,
+    pub name_ptr: DeclarationPtr,
 }
 
 impl DeclarationLocation {
@@ -58,16 +64,141 @@ impl DeclarationLocation {
     }
 }
 
+/// A "pointer" to a syntax node, like [`syntax::SyntaxNodePtr`], which unlike the latter can be
+/// restored from a persisted kind and range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeclarationPtr {
+    kind: SyntaxKind,
+    range: TextRange,
+}
+
+impl DeclarationPtr {
+    pub fn new(node: &SyntaxNode) -> Self {
+        DeclarationPtr { kind: node.kind(), range: node.text_range() }
+    }
+
+    pub fn to_node(&self, root: &SyntaxNode) -> SyntaxNode {
+        assert!(root.parent().is_none());
+        iter::successors(Some(root.clone()), |node| {
+            node.child_or_token_at_range(self.range).and_then(|it| it.into_node())
+        })
+        .find(|it| it.text_range() == self.range && it.kind() == self.kind)
+        .unwrap_or_else(|| panic!("can't resolve local ptr to SyntaxNode: {self:?}"))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        self.range
+    }
+}
+
 fn resolve_node(
     db: &dyn HirDatabase,
     file_id: HirFileId,
-    ptr: &SyntaxNodePtr,
+    ptr: &DeclarationPtr,
 ) -> InFile<SyntaxNode> {
     let root = db.parse_or_expand(file_id);
     let node = ptr.to_node(&root);
     InFile::new(file_id, node)
 }
 
+/// [`FileSymbol`]s in a form that can be persisted and used by another process, see
+/// [`hir_def::stable_id`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistedSymbols {
+    tables: StableDefIdTables,
+    symbols: Vec<PersistedSymbol>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedSymbol {
+    name: String,
+    def: StableModuleDefId,
+    file_id: StableFileId,
+    ptr: PersistedPtr,
+    name_ptr: PersistedPtr,
+    container_name: Option<String>,
+    is_alias: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedPtr {
+    kind: u16,
+    start: u32,
+    end: u32,
+}
+
+impl PersistedPtr {
+    fn new(ptr: DeclarationPtr) -> Self {
+        let DeclarationPtr { kind, range } = ptr;
+        PersistedPtr { kind: kind.into(), start: range.start().into(), end: range.end().into() }
+    }
+
+    fn to_ptr(&self) -> Option<DeclarationPtr> {
+        if self.kind > SyntaxKind::__LAST as u16 || self.start > self.end {
+            return None;
+        }
+        let range = TextRange::new(TextSize::from(self.start), TextSize::from(self.end));
+        Some(DeclarationPtr { kind: self.kind.into(), range })
+    }
+}
+
+impl PersistedSymbols {
+    /// Returns `None` if one of the symbols can't be encoded, because it refers to a file that is
+    /// not in `paths` or to a crate that can't be identified, see [`StableDefIdEncoder`].
+    pub fn new(
+        db: &dyn HirDatabase,
+        symbols: &[FileSymbol],
+        paths: &FxHashMap<FileId, String>,
+    ) -> Option<PersistedSymbols> {
+        let mut encoder = StableDefIdEncoder::new(db.upcast(), paths);
+        let symbols = symbols
+            .iter()
+            .map(|FileSymbol { name, def, loc, container_name, is_alias }| {
+                Some(PersistedSymbol {
+                    name: name.to_string(),
+                    def: encoder.module_def_id((*def).into())?,
+                    file_id: encoder.file_id(loc.hir_file_id)?,
+                    ptr: PersistedPtr::new(loc.ptr),
+                    name_ptr: PersistedPtr::new(loc.name_ptr),
+                    container_name: container_name.as_ref().map(ToString::to_string),
+                    is_alias: *is_alias,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(PersistedSymbols { tables: encoder.finish(), symbols })
+    }
+
+    /// Returns `None` if the symbols refer to files or crates that don't exist anymore, see
+    /// [`StableDefIdDecoder`].
+    pub fn to_symbols(
+        &self,
+        db: &dyn HirDatabase,
+        file_ids: &FxHashMap<String, FileId>,
+    ) -> Option<Vec<FileSymbol>> {
+        let decoder = StableDefIdDecoder::new(db.upcast(), &self.tables, file_ids)?;
+        self.symbols
+            .iter()
+            .map(|symbol| {
+                Some(FileSymbol {
+                    name: symbol.name.as_str().into(),
+                    def: decoder.module_def_id(&symbol.def)?.into(),
+                    loc: DeclarationLocation {
+                        hir_file_id: decoder.file_id(symbol.file_id)?,
+                        ptr: symbol.ptr.to_ptr()?,
+                        name_ptr: symbol.name_ptr.to_ptr()?,
+                    },
+                    container_name: symbol.container_name.as_deref().map(Into::into),
+                    is_alias: symbol.is_alias,
+                })
+            })
+            .collect()
+    }
+}
+
 /// Represents an outstanding module that the symbol collector must collect symbols from.
 struct SymbolCollectorWork {
     module_id: ModuleId,
@@ -257,8 +388,8 @@ impl<'a> SymbolCollector<'a> {
         let def = ModuleDef::from(id.into());
         let dec_loc = DeclarationLocation {
             hir_file_id: source.file_id,
-            ptr: SyntaxNodePtr::new(source.value.syntax()),
-            name_ptr: SyntaxNodePtr::new(name_node.syntax()),
+            ptr: DeclarationPtr::new(source.value.syntax()),
+            name_ptr: DeclarationPtr::new(name_node.syntax()),
         };
 
         if let Some(attrs) = def.attrs(self.db) {
@@ -290,8 +421,8 @@ impl<'a> SymbolCollector<'a> {
         let Some(name_node) = module.name() else { return };
         let dec_loc = DeclarationLocation {
             hir_file_id: declaration.file_id,
-            ptr: SyntaxNodePtr::new(module.syntax()),
-            name_ptr: SyntaxNodePtr::new(name_node.syntax()),
+            ptr: DeclarationPtr::new(module.syntax()),
+            name_ptr: DeclarationPtr::new(name_node.syntax()),
         };
 
         let def = ModuleDef::Module(module_id.into());
@@ -1,9 +1,10 @@
 use hir::HasSource;
+use ide_db::traits::{add_trait_assoc_items_to_impl, filter_assoc_items, DefaultMethods};
 use syntax::ast::{self, make, AstNode};
 
 use crate::{
     assist_context::{AssistContext, Assists},
-    utils::{add_trait_assoc_items_to_impl, filter_assoc_items, gen_trait_fn_body, DefaultMethods},
+    utils::gen_trait_fn_body,
     AssistId, AssistKind,
 };
 
@@ -1,5 +1,10 @@
 use hir::{InFile, ModuleDef};
-use ide_db::{helpers::mod_path_to_ast, imports::import_assets::NameToImport, items_locator};
+use ide_db::{
+    helpers::mod_path_to_ast,
+    imports::import_assets::NameToImport,
+    items_locator,
+    traits::{add_trait_assoc_items_to_impl, filter_assoc_items, DefaultMethods},
+};
 use itertools::Itertools;
 use syntax::{
     ast::{self, AstNode, HasName},
@@ -8,10 +13,7 @@ use syntax::{
 
 use crate::{
     assist_context::{AssistContext, Assists, SourceChangeBuilder},
-    utils::{
-        add_trait_assoc_items_to_impl, filter_assoc_items, gen_trait_fn_body,
-        generate_trait_impl_text, render_snippet, Cursor, DefaultMethods,
-    },
+    utils::{gen_trait_fn_body, generate_trait_impl_text, render_snippet, Cursor},
     AssistId, AssistKind,
 };
 
@@ -88,38 +90,36 @@ pub(crate) fn replace_derive_with_manual_impl(
             .zip(Some(trait_))
     });
 
+    let derive = DeriveAttr { attr, derives: current_derives, tree: args, trait_path: path };
     let mut no_traits_found = true;
     for (replace_trait_path, trait_) in found_traits.inspect(|_| no_traits_found = false) {
-        add_assist(
-            acc,
-            ctx,
-            &attr,
-            &current_derives,
-            &args,
-            &path,
-            &replace_trait_path,
-            Some(trait_),
-            &adt,
-        )?;
+        add_assist(acc, ctx, &derive, &replace_trait_path, Some(trait_), &adt)?;
     }
     if no_traits_found {
-        add_assist(acc, ctx, &attr, &current_derives, &args, &path, &path, None, &adt)?;
+        add_assist(acc, ctx, &derive, &derive.trait_path, None, &adt)?;
     }
     Some(())
 }
 
+/// The `#[derive]` attribute the assist was invoked on.
+struct DeriveAttr {
+    attr: ast::Attr,
+    /// The paths of all derives of the attribute.
+    derives: Vec<ast::Path>,
+    tree: ast::TokenTree,
+    /// The path of the derive to replace.
+    trait_path: ast::Path,
+}
+
 fn add_assist(
     acc: &mut Assists,
     ctx: &AssistContext<'_>,
-    attr: &ast::Attr,
-    old_derives: &[ast::Path],
-    old_tree: &ast::TokenTree,
-    old_trait_path: &ast::Path,
+    derive: &DeriveAttr,
     replace_trait_path: &ast::Path,
     trait_: Option<hir::Trait>,
     adt: &ast::Adt,
 ) -> Option<()> {
-    let target = attr.syntax().text_range();
+    let target = derive.attr.syntax().text_range();
     let annotated_name = adt.name()?;
     let label = format!("Convert to manual `impl {replace_trait_path} for {annotated_name}`");
 
@@ -131,7 +131,7 @@ fn add_assist(
             let insert_pos = adt.syntax().text_range().end();
             let impl_def_with_items =
                 impl_def_from_trait(&ctx.sema, adt, &annotated_name, trait_, replace_trait_path);
-            update_attribute(builder, old_derives, old_tree, old_trait_path, attr);
+            update_attribute(builder, derive);
             let trait_path = replace_trait_path.to_string();
             match (ctx.config.snippet_cap, impl_def_with_items) {
                 (None, _) => {
@@ -210,13 +210,9 @@ fn impl_def_from_trait(
     Some((impl_def, first_assoc_item))
 }
 
-fn update_attribute(
-    builder: &mut SourceChangeBuilder,
-    old_derives: &[ast::Path],
-    old_tree: &ast::TokenTree,
-    old_trait_path: &ast::Path,
-    attr: &ast::Attr,
-) {
+fn update_attribute(builder: &mut SourceChangeBuilder, derive: &DeriveAttr) {
+    let DeriveAttr { attr, derives: old_derives, tree: old_tree, trait_path: old_trait_path } =
+        derive;
     let new_derives = old_derives
         .iter()
         .filter(|t| t.to_string() != old_trait_path.to_string())
@@ -87,6 +87,9 @@ impl RootDatabase {
             // SourceDatabase
             base_db::ParseQuery
             base_db::CrateGraphQuery
+            base_db::WorkspaceCrateGraphQuery
+            base_db::AdHocCratesQuery
+            base_db::CrateDataQuery
             base_db::ProcMacrosQuery
 
             // SourceDatabaseExt
@@ -192,6 +195,8 @@ impl RootDatabase {
             crate::symbol_index::LibrarySymbolsQuery
             crate::symbol_index::LocalRootsQuery
             crate::symbol_index::LibraryRootsQuery
+            crate::symbol_index::SymbolIndexCacheQuery
+            crate::symbol_index::CrateContentHashQuery
 
             // LineIndexDatabase
             crate::LineIndexQuery
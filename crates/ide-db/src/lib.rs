@@ -46,12 +46,18 @@ use std::{fmt, mem::ManuallyDrop};
 
 use base_db::{
     salsa::{self, Durability},
-    AnchoredPath, CrateId, FileId, FileLoader, FileLoaderDelegate, SourceDatabase, Upcast,
+    AnchoredPath, CrateId, FileId, FileLoader, FileLoaderDelegate, SourceDatabase, Upcast, VfsPath,
+};
+use hir::{
+    db::{DefDatabase, ExpandDatabase, HirDatabase},
+    ItemTreeCache,
 };
-use hir::db::{DefDatabase, ExpandDatabase, HirDatabase};
 use triomphe::Arc;
 
-use crate::{line_index::LineIndex, symbol_index::SymbolsDatabase};
+use crate::{
+    line_index::LineIndex,
+    symbol_index::{SymbolIndexCache, SymbolsDatabase},
+};
 pub use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
 pub use ::line_index;
@@ -121,6 +127,9 @@ impl FileLoader for RootDatabase {
     fn relevant_crates(&self, file_id: FileId) -> Arc<FxHashSet<CrateId>> {
         FileLoaderDelegate(self).relevant_crates(file_id)
     }
+    fn file_path(&self, file_id: FileId) -> Option<VfsPath> {
+        FileLoaderDelegate(self).file_path(file_id)
+    }
 }
 
 impl salsa::Database for RootDatabase {}
@@ -134,11 +143,15 @@ impl Default for RootDatabase {
 impl RootDatabase {
     pub fn new(lru_capacity: Option<usize>) -> RootDatabase {
         let mut db = RootDatabase { storage: ManuallyDrop::new(salsa::Storage::default()) };
-        db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
+        db.set_workspace_crate_graph_with_durability(Default::default(), Durability::HIGH);
+        db.set_ad_hoc_crates_with_durability(Arc::from(Vec::new()), Durability::LOW);
         db.set_proc_macros_with_durability(Default::default(), Durability::HIGH);
+        db.set_active_targets_with_durability(Default::default(), Durability::LOW);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_library_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_expand_proc_attr_macros_with_durability(false, Durability::HIGH);
+        db.set_item_tree_cache_with_durability(None, Durability::HIGH);
+        db.set_symbol_index_cache_with_durability(None, Durability::HIGH);
         db.update_parse_query_lru_capacity(lru_capacity);
         db
     }
@@ -147,6 +160,34 @@ impl RootDatabase {
         self.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
     }
 
+    /// Persists the `ItemTree`s of library files in the given cache, and reuses the ones it
+    /// already contains.
+    pub fn set_item_tree_cache(&mut self, cache: Option<ItemTreeCache>) {
+        self.set_item_tree_cache_with_durability(cache.map(Arc::new), Durability::HIGH);
+    }
+
+    /// Writes the `ItemTree`s computed so far to the cache set by [`Self::set_item_tree_cache`],
+    /// see [`ItemTreeCache::persist`].
+    pub fn persist_item_tree_cache(&self) {
+        if let Some(cache) = self.item_tree_cache() {
+            cache.persist(self);
+        }
+    }
+
+    /// Persists the symbol indices of library source roots in the given cache, and reuses the ones
+    /// it already contains.
+    pub fn set_symbol_index_cache(&mut self, cache: Option<SymbolIndexCache>) {
+        self.set_symbol_index_cache_with_durability(cache.map(Arc::new), Durability::HIGH);
+    }
+
+    /// Writes the library symbol indices computed so far to the cache set by
+    /// [`Self::set_symbol_index_cache`], see [`SymbolIndexCache::persist`].
+    pub fn persist_symbol_index_cache(&self) {
+        if let Some(cache) = self.symbol_index_cache() {
+            cache.persist(self);
+        }
+    }
+
     pub fn update_parse_query_lru_capacity(&mut self, lru_capacity: Option<usize>) {
         let lru_capacity = lru_capacity.unwrap_or(base_db::DEFAULT_PARSE_LRU_CAP);
         base_db::ParseQuery.in_db_mut(self).set_lru_capacity(lru_capacity);
@@ -188,6 +229,9 @@ impl RootDatabase {
             // SourceDatabase
             // base_db::ParseQuery
             // base_db::CrateGraphQuery
+            // base_db::WorkspaceCrateGraphQuery
+            // base_db::AdHocCratesQuery
+            // base_db::CrateDataQuery
             // base_db::ProcMacrosQuery
 
             // SourceDatabaseExt
@@ -294,6 +338,8 @@ impl RootDatabase {
             symbol_index::LibrarySymbolsQuery
             // symbol_index::LocalRootsQuery
             // symbol_index::LibraryRootsQuery
+            // symbol_index::SymbolIndexCacheQuery
+            symbol_index::CrateContentHashQuery
 
             // LineIndexDatabase
             crate::LineIndexQuery
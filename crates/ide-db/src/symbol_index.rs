@@ -20,6 +20,8 @@
 //! file in the current workspace, and run a query against the union of all
 //! those FSTs.
 
+mod cache;
+
 use std::{
     cmp::Ordering,
     fmt,
@@ -29,7 +31,7 @@ use std::{
 
 use base_db::{
     salsa::{self, ParallelDatabase},
-    SourceDatabaseExt, SourceRootId, Upcast,
+    CrateId, SourceDatabaseExt, SourceRootId, Upcast,
 };
 use fst::{self, Streamer};
 use hir::{
@@ -38,11 +40,13 @@ use hir::{
     Crate, Module,
 };
 use rayon::prelude::*;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashSet, FxHasher};
 use triomphe::Arc;
 
 use crate::RootDatabase;
 
+pub use self::cache::SymbolIndexCache;
+
 #[derive(Debug)]
 pub struct Query {
     query: String,
@@ -111,11 +115,25 @@ pub trait SymbolsDatabase: HirDatabase + SourceDatabaseExt + Upcast<dyn HirDatab
     /// Files in libraries are assumed to never change.
     #[salsa::input]
     fn library_roots(&self) -> Arc<FxHashSet<SourceRootId>>;
+
+    /// The on-disk cache `library_symbols` reuses symbol indices from, if any.
+    #[salsa::input]
+    fn symbol_index_cache(&self) -> Option<Arc<SymbolIndexCache>>;
+
+    /// A hash of everything the symbols of a crate are computed from: the crate's data, the files
+    /// of its source root and the hashes of its dependencies.
+    fn crate_content_hash(&self, krate: CrateId) -> u64;
 }
 
 fn library_symbols(db: &dyn SymbolsDatabase, source_root_id: SourceRootId) -> Arc<SymbolIndex> {
     let _p = profile::span("library_symbols");
 
+    if let Some(cache) = db.symbol_index_cache() {
+        if let Some(symbols) = cache.get(db, source_root_id) {
+            return Arc::new(SymbolIndex::new(symbols));
+        }
+    }
+
     let mut symbol_collector = SymbolCollector::new(db.upcast());
 
     db.source_root_crates(source_root_id)
@@ -131,6 +149,45 @@ fn library_symbols(db: &dyn SymbolsDatabase, source_root_id: SourceRootId) -> Ar
     Arc::new(SymbolIndex::new(symbols))
 }
 
+fn crate_content_hash(db: &dyn SymbolsDatabase, krate: CrateId) -> u64 {
+    let crate_graph = db.crate_graph();
+    let data = &crate_graph[krate];
+    let mut hasher = FxHasher::default();
+    data.edition.hash(&mut hasher);
+    // `CfgOptions` aren't `Hash`, but their `Debug` output is sorted.
+    format!("{:?}", data.cfg_options).hash(&mut hasher);
+    data.origin.hash(&mut hasher);
+    let mut env: Vec<_> = data.env.iter().collect();
+    env.sort_unstable();
+    env.hash(&mut hasher);
+    data.is_proc_macro.hash(&mut hasher);
+    for dep in &data.dependencies {
+        (&dep.name, dep.is_prelude()).hash(&mut hasher);
+        db.crate_content_hash(dep.crate_id).hash(&mut hasher);
+    }
+    match db.proc_macros().get(&krate) {
+        Some(Ok(proc_macros)) => {
+            for proc_macro in proc_macros {
+                format!("{} {:?}", proc_macro.name, proc_macro.kind).hash(&mut hasher);
+            }
+        }
+        Some(Err(_)) => 1.hash(&mut hasher),
+        None => 0.hash(&mut hasher),
+    }
+
+    let source_root = db.source_root(db.file_source_root(data.root_file_id));
+    let mut files: Vec<_> = source_root
+        .iter()
+        .filter_map(|file_id| Some((source_root.path_for_file(&file_id)?.to_string(), file_id)))
+        .collect();
+    files.sort_unstable();
+    for (path, file_id) in files {
+        path.hash(&mut hasher);
+        SourceDatabaseExt::file_text(db, file_id).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
 fn module_symbols(db: &dyn SymbolsDatabase, module: Module) -> Arc<SymbolIndex> {
     let _p = profile::span("module_symbols");
 
@@ -461,4 +518,105 @@ struct Duplicate;
 
         expect_file!["./test_data/test_doc_alias.txt"].assert_debug_eq(&symbols);
     }
+
+    #[test]
+    fn persisted_library_symbols() {
+        use base_db::{
+            salsa::{
+                debug::{DebugQueryTable, TableEntry},
+                Durability,
+            },
+            SourceDatabase,
+        };
+        use hir::db::CrateDefMapQueryQuery;
+
+        let fixture = r#"
+//- minicore: derive, clone
+//- /main.rs crate:main deps:lib
+struct Local;
+//- /lib.rs crate:lib new_source_root:library
+#[derive(Clone)]
+pub struct Derived;
+
+macro_rules! define_struct {
+    () => {
+        pub struct StructFromMacro;
+    };
+}
+define_struct!();
+
+pub mod module {
+    pub trait Trait {
+        fn method(&self);
+    }
+}
+
+pub fn f() {
+    struct StructInFn;
+}
+
+const _: () = {
+    struct StructInUnnamedConst;
+};
+"#;
+        let dir = std::env::temp_dir().join(format!(
+            "ra-symbol-index-cache-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let setup = || {
+            let (mut db, fixture_files) = RootDatabase::with_many_files(fixture);
+            let lib = db.file_source_root(fixture_files[1]);
+            let crate_graph = db.crate_graph();
+            let source_roots: FxHashSet<_> = crate_graph
+                .iter()
+                .map(|krate| db.file_source_root(crate_graph[krate].root_file_id))
+                .collect();
+            let mut files = Vec::new();
+            for &source_root_id in &source_roots {
+                let source_root = db.source_root(source_root_id);
+                for file_id in source_root.iter() {
+                    let path = source_root.path_for_file(&file_id).unwrap();
+                    files.push((file_id, path.to_string()));
+                }
+            }
+            let library_roots: FxHashSet<_> = source_roots
+                .into_iter()
+                .filter(|&source_root_id| db.source_root(source_root_id).is_library)
+                .collect();
+            db.set_library_roots_with_durability(Arc::new(library_roots), Durability::HIGH);
+            let cache = SymbolIndexCache::load(&dir, "0.0.0 (test)", files);
+            db.set_symbol_index_cache_with_durability(Some(Arc::new(cache)), Durability::HIGH);
+            (db, lib)
+        };
+
+        let (db, lib) = setup();
+        let collected = db.library_symbols(lib);
+        assert!(collected.symbols.iter().any(|it| it.name == "StructFromMacro"));
+        assert!(collected.symbols.iter().any(|it| it.name == "StructInUnnamedConst"));
+        assert!(collected.symbols.iter().any(|it| it.name == "clone"));
+        db.symbol_index_cache().unwrap().persist(&db);
+
+        let (mut db, lib) = setup();
+        let loaded = db.library_symbols(lib);
+        let def_maps: Vec<TableEntry<_, _>> = CrateDefMapQueryQuery.in_db(&db).entries();
+        assert!(def_maps.is_empty(), "persisted symbols should not need any `DefMap`s");
+        // IDs are only comparable within a database, so collect the symbols again in this one.
+        db.set_symbol_index_cache_with_durability(None, Durability::HIGH);
+        assert_eq!(loaded.symbols, db.library_symbols(lib).symbols);
+
+        // Entries are only used for the exact crates they were created from.
+        let (mut db, lib) = setup();
+        let file_id = db.source_root(lib).iter().next().unwrap();
+        db.set_file_text(
+            file_id,
+            Arc::from(format!("{}\npub struct Added;", db.file_text(file_id))),
+        );
+        let symbols = db.library_symbols(lib);
+        assert!(symbols.symbols.iter().any(|it| it.name == "Added"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,218 @@
+//! An on-disk cache for the symbol indices of library source roots.
+//!
+//! Collecting the symbols of a library requires the `DefMap`s of its crates, so a restarted server
+//! that only wants to search the workspace's symbols would otherwise have to resolve the names of
+//! all dependencies first. The symbols are persisted as [`PersistedSymbols`], whose IDs are only
+//! valid as long as the crates they come from are unchanged: an entry is keyed by the paths of the
+//! root files of the source root's crates, and by a hash of these crates, their dependencies and
+//! all of their files (see [`SymbolsDatabase::crate_content_hash`]).
+//!
+//! Like the `ItemTreeCache`, the entries are read when the cache is created, so that
+//! `library_symbols` can use them without doing any I/O, and written outside of any query by
+//! [`SymbolIndexCache::persist`].
+
+use std::{
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::Mutex,
+};
+
+use base_db::{
+    salsa::debug::{DebugQueryTable, TableEntry},
+    CrateId, FileId, SourceRootId,
+};
+use hir::{
+    symbols::{FileSymbol, PersistedSymbols},
+    CacheDir,
+};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use serde::{Deserialize, Serialize};
+use triomphe::Arc;
+
+use super::{LibrarySymbolsQuery, SymbolIndex, SymbolsDatabase};
+
+/// A directory of persisted symbol indices, see the [module docs](self).
+pub struct SymbolIndexCache {
+    dir: CacheDir,
+    version: String,
+    /// The paths of all files of the workspace.
+    paths: FxHashMap<FileId, String>,
+    file_ids: FxHashMap<String, FileId>,
+    /// The contents of the entries read from disk, by hash of their key and content hash.
+    entries: FxHashMap<(u64, u64), Vec<u8>>,
+    /// The entries written to disk by [`Self::persist`] so far.
+    written: Mutex<FxHashSet<(u64, u64)>>,
+}
+
+impl PartialEq for SymbolIndexCache {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for SymbolIndexCache {}
+
+impl fmt::Debug for SymbolIndexCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SymbolIndexCache")
+            .field("dir", &self.dir)
+            .field("version", &self.version)
+            .field("files", &self.paths.len())
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    version: String,
+    key: String,
+    content_hash: u64,
+    symbols: T,
+}
+
+impl SymbolIndexCache {
+    /// Creates a cache for a workspace with the given files, whose entries are stored below `dir`,
+    /// and reads all entries stored so far. Entries written by a different `version` of
+    /// rust-analyzer are never used.
+    pub fn load(
+        dir: &Path,
+        version: &str,
+        files: impl IntoIterator<Item = (FileId, String)>,
+    ) -> SymbolIndexCache {
+        let dir = CacheDir::new(dir, version);
+        let paths: FxHashMap<_, _> = files.into_iter().collect();
+        let file_ids = paths.iter().map(|(&file_id, path)| (path.clone(), file_id)).collect();
+
+        let mut entries = FxHashMap::default();
+        for (path, key_hash, content_hash) in dir.entries() {
+            match fs::read(&path) {
+                Ok(contents) => {
+                    entries.insert((key_hash, content_hash), contents);
+                }
+                Err(e) => tracing::debug!("failed to read {}: {e}", path.display()),
+            }
+        }
+
+        SymbolIndexCache {
+            dir,
+            version: version.to_owned(),
+            paths,
+            file_ids,
+            entries,
+            written: Default::default(),
+        }
+    }
+
+    pub(super) fn get(
+        &self,
+        db: &dyn SymbolsDatabase,
+        source_root_id: SourceRootId,
+    ) -> Option<Vec<FileSymbol>> {
+        let (key, crates) = self.key(db, source_root_id)?;
+        let content_hash = content_hash(db, &crates);
+        let contents = self.entries.get(&(CacheDir::hash(&key), content_hash))?;
+        let entry: Entry<PersistedSymbols> = match serde_json::from_slice(contents) {
+            Ok(it) => it,
+            Err(e) => {
+                tracing::debug!("discarding invalid symbol index cache entry for {key}: {e}");
+                return None;
+            }
+        };
+        if entry.version != self.version || entry.key != key || entry.content_hash != content_hash {
+            return None;
+        }
+        entry.symbols.to_symbols(db.upcast(), &self.file_ids)
+    }
+
+    /// Writes the symbol indices of library source roots that `db` has computed and that are not
+    /// on disk yet, and removes the entries which won't be used anymore: the ones of source roots
+    /// outside of the workspace, the ones of older versions of their crates, and the ones of other
+    /// rust-analyzer versions.
+    pub fn persist(&self, db: &dyn SymbolsDatabase) {
+        let computed: Vec<TableEntry<SourceRootId, Arc<SymbolIndex>>> =
+            LibrarySymbolsQuery.in_db(db).entries();
+        // The content hash of every source root whose symbols have been computed, by key hash.
+        let mut used = FxHashMap::default();
+        for source_root_id in computed.into_iter().map(|it| it.key) {
+            let Some((key, crates)) = self.key(db, source_root_id) else { continue };
+            let key_hash = CacheDir::hash(&key);
+            let content_hash = content_hash(db, &crates);
+            used.insert(key_hash, content_hash);
+            let entry_key = (key_hash, content_hash);
+            if self.entries.contains_key(&entry_key)
+                || self.written.lock().unwrap().contains(&entry_key)
+            {
+                continue;
+            }
+            // The memoized index may be outdated, querying it again brings it up to date.
+            let index = db.library_symbols(source_root_id);
+            let Some(symbols) = PersistedSymbols::new(db.upcast(), &index.symbols, &self.paths)
+            else {
+                tracing::debug!("can't persist the symbols of {key}");
+                continue;
+            };
+            let entry = Entry { version: self.version.clone(), key, content_hash, symbols };
+            let contents = match serde_json::to_vec(&entry) {
+                Ok(it) => it,
+                Err(e) => {
+                    tracing::debug!("failed to serialize the symbols of {}: {e}", entry.key);
+                    continue;
+                }
+            };
+            match self.dir.write(key_hash, content_hash, &contents) {
+                Ok(()) => {
+                    self.written.lock().unwrap().insert(entry_key);
+                }
+                Err(e) => tracing::debug!(
+                    "failed to persist symbol index cache entry for {}: {e}",
+                    entry.key
+                ),
+            }
+        }
+
+        let known: FxHashSet<u64> = db
+            .library_roots()
+            .iter()
+            .filter_map(|&source_root_id| self.key(db, source_root_id))
+            .map(|(key, _)| CacheDir::hash(&key))
+            .collect();
+        self.dir.evict(|key_hash, content_hash| match used.get(&key_hash) {
+            Some(&used_content_hash) => used_content_hash != content_hash,
+            None => !known.contains(&key_hash),
+        });
+    }
+
+    /// Returns the key of the entry of a source root, which are the paths of the root files of its
+    /// crates, along with these crates in the same order.
+    fn key(
+        &self,
+        db: &dyn SymbolsDatabase,
+        source_root_id: SourceRootId,
+    ) -> Option<(String, Vec<CrateId>)> {
+        let crate_graph = db.crate_graph();
+        let mut roots = db
+            .source_root_crates(source_root_id)
+            .iter()
+            .map(|&krate| {
+                let path = self.paths.get(&crate_graph[krate].root_file_id)?;
+                Some((path.as_str(), krate))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if roots.is_empty() {
+            return None;
+        }
+        roots.sort_unstable();
+        let key = roots.iter().map(|&(path, _)| path).collect::<Vec<_>>().join("\n");
+        Some((key, roots.into_iter().map(|(_, krate)| krate).collect()))
+    }
+}
+
+fn content_hash(db: &dyn SymbolsDatabase, crates: &[CrateId]) -> u64 {
+    let mut hasher = FxHasher::default();
+    for &krate in crates {
+        db.crate_content_hash(krate).hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,228 @@
+use hir::BorrowConflictKind;
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: conflicting-borrow
+//
+// This diagnostic is triggered when a variable is borrowed, used or moved out while a conflicting
+// borrow of it is still in use.
+pub(crate) fn conflicting_borrow(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::ConflictingBorrow,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    let name = name.display(ctx.sema.db);
+    let (code, message) = match d.kind {
+        BorrowConflictKind::SecondMutBorrow => {
+            ("E0499", format!("cannot borrow `{name}` as mutable more than once at a time"))
+        }
+        BorrowConflictKind::MutBorrowOfShared => (
+            "E0502",
+            format!("cannot borrow `{name}` as mutable because it is also borrowed as immutable"),
+        ),
+        BorrowConflictKind::SharedBorrowOfMut => (
+            "E0502",
+            format!("cannot borrow `{name}` as immutable because it is also borrowed as mutable"),
+        ),
+        BorrowConflictKind::UseOfMutBorrowed => {
+            ("E0503", format!("cannot use `{name}` because it was mutably borrowed"))
+        }
+        BorrowConflictKind::MoveOfBorrowed => {
+            ("E0505", format!("cannot move out of `{name}` because it is borrowed"))
+        }
+    };
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError(code),
+        message,
+        d.span.clone(),
+    )
+    .experimental() // lifetimes are approximated by liveness
+}
+
+// Diagnostic: assign-to-borrowed
+//
+// This diagnostic is triggered when a variable is assigned to while a borrow of it is still in use.
+pub(crate) fn assign_to_borrowed(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::AssignToBorrowed,
+) -> Diagnostic {
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0506"),
+        format!(
+            "cannot assign to `{}` because it is borrowed",
+            d.local.name(ctx.sema.db).display(ctx.sema.db)
+        ),
+        d.span.clone(),
+    )
+    .experimental() // lifetimes are approximated by liveness
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn conflicting_borrows() {
+        check_diagnostics(
+            r#"
+struct V;
+impl V {
+    fn get(&self) -> &i32 { loop {} }
+    fn get_mut(&mut self) -> &mut i32 { loop {} }
+    fn push(&mut self, _: i32) {}
+}
+fn use_ref<T>(_: T) {}
+fn main() {
+    let mut v = V;
+    let first = v.get();
+    v.push(1);
+  //^ error: cannot borrow `v` as mutable because it is also borrowed as immutable
+    use_ref(first);
+
+    let a = &mut v;
+    let b = &mut v;
+      //^ 💡 warn: unused variable `b`
+          //^^^^^^ error: cannot borrow `v` as mutable more than once at a time
+    use_ref(a);
+
+    let a = v.get_mut();
+    let b = &v;
+      //^ 💡 warn: unused variable `b`
+          //^^ error: cannot borrow `v` as immutable because it is also borrowed as mutable
+    use_ref(a);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_and_assign_while_borrowed() {
+        check_diagnostics(
+            r#"
+struct S;
+fn consume<T>(_: T) {}
+fn main() {
+    let mut x = 5;
+    let r = &x;
+    x = 6;
+  //^^^^^ error: cannot assign to `x` because it is borrowed
+    consume(r);
+
+    let s = S;
+    let r = &s;
+    consume(s);
+  //^^^^^^^^^^ error: cannot move out of `s` because it is borrowed
+    consume(r);
+
+    let mut y = 5;
+    let m = &mut y;
+    let z = y + 1;
+      //^ 💡 warn: unused variable `z`
+          //^^^^^ error: cannot use `y` because it was mutably borrowed
+    consume(m);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_error_after_borrow_ends() {
+        check_diagnostics(
+            r#"
+struct V;
+impl V {
+    fn len(&self) -> i32 { 0 }
+    fn get(&self) -> &i32 { loop {} }
+    fn push(&mut self, _: i32) {}
+}
+fn use_ref<T>(_: T) {}
+fn main() {
+    let mut v = V;
+    v.push(v.len());
+    let first = v.get();
+    use_ref(first);
+    v.push(1);
+    let a = &mut v;
+    use_ref(a);
+    let b = &mut v;
+    use_ref(b);
+    let mut x = 5;
+    let r = &x;
+    use_ref(r);
+    x = 6;
+    let mut t = (V, V);
+    let r0 = &t.0;
+    let m1 = &mut t.1;
+    use_ref((r0, m1));
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_error_for_results_without_borrows() {
+        check_diagnostics(
+            r#"
+//- minicore: clone, index, fn, deref
+use core::ops::{Deref, Index};
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn push(&mut self, _: T) {}
+}
+impl<T> Index<usize> for Vec<T> {
+    type Output = T;
+    fn index(&self, _: usize) -> &T { loop {} }
+}
+fn generic_push<T: Clone>(mut v: Vec<T>) -> Vec<T> {
+    let x = v[0].clone();
+    v.push(x);
+    v
+}
+
+#[lang = "owned_box"]
+struct Box<T: ?Sized>(*mut T);
+impl<T: ?Sized> Deref for Box<T> {
+    type Target = T;
+    fn deref(&self) -> &T { loop {} }
+}
+struct String;
+impl String {
+    fn push(&mut self, _: char) {}
+}
+fn make(_: &mut String) -> Box<dyn Fn() -> usize> { loop {} }
+fn main() {
+    let mut s = String;
+    let h: Box<dyn Fn() -> usize> = make(&mut s);
+    s.push('a');
+    h();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn results_borrowing_from_arguments() {
+        check_diagnostics(
+            r#"
+//- minicore: fn
+#[lang = "owned_box"]
+struct Box<T: ?Sized>(*mut T);
+struct String;
+impl String {
+    fn push(&mut self, _: char) {}
+}
+fn make<'a>(_: &'a mut String) -> Box<dyn Fn() -> usize + 'a> { loop {} }
+fn use_ref<T>(_: T) {}
+fn main() {
+    let mut s = String;
+    let h = make(&mut s);
+    s.push('a');
+  //^ error: cannot borrow `s` as mutable more than once at a time
+    use_ref(h);
+}
+"#,
+        );
+    }
+}
@@ -167,6 +167,7 @@ fn main() {
             r#"
 struct A { a: &'static str }
 fn f(a: A) { let A { a: hello } = a; }
+                      //^^^^^ 💡 warn: unused variable `hello`
 "#,
         );
         check_diagnostics(
@@ -181,12 +182,14 @@ fn f(a: A) { let A { 0: 0 } = a; }
 struct A { a: &'static str }
 fn f(a: A) {
     let A { a$0: a } = a;
+    let _ = a;
 }
 "#,
             r#"
 struct A { a: &'static str }
 fn f(a: A) {
     let A { a } = a;
+    let _ = a;
 }
 "#,
         );
@@ -196,12 +199,14 @@ fn f(a: A) {
 struct A { a: &'static str, b: &'static str }
 fn f(a: A) {
     let A { a$0: a, b } = a;
+    let _ = (a, b);
 }
 "#,
             r#"
 struct A { a: &'static str, b: &'static str }
 fn f(a: A) {
     let A { a, b } = a;
+    let _ = (a, b);
 }
 "#,
         );
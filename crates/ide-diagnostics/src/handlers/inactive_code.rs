@@ -39,6 +39,14 @@ pub(crate) fn inactive_code(
 
 #[cfg(test)]
 mod tests {
+    use ide_db::{
+        assists::AssistResolveStrategy,
+        base_db::{
+            fixture::WithFixture, Change, CrateGraph, FileId, SourceDatabase, SourceDatabaseExt,
+        },
+        FxHashMap, RootDatabase,
+    };
+
     use crate::{tests::check_diagnostics_with_config, DiagnosticsConfig};
 
     pub(crate) fn check(ra_fixture: &str) {
@@ -170,4 +178,61 @@ union FooBar {
 "#,
         );
     }
+
+    #[test]
+    fn active_target_selects_cfg_branch() {
+        let (mut db, file_id) = RootDatabase::with_single_file(
+            r#"
+#[cfg(windows)] fn windows() {}
+#[cfg(not(windows))] fn unix() {}
+"#,
+        );
+        // Analyze the crate for an extra target as well, which enables different cfgs.
+        let mut crate_graph = CrateGraph::clone(&db.workspace_crate_graph());
+        let data = crate_graph[crate_graph.iter().next().unwrap()].clone();
+        let mut cfg_options = data.cfg_options.clone();
+        cfg_options.insert_atom("windows".into());
+        let windows = crate_graph.add_crate_root(
+            data.root_file_id,
+            data.edition,
+            data.display_name,
+            data.version,
+            cfg_options,
+            data.potential_cfg_options,
+            data.env,
+            data.is_proc_macro,
+            data.origin,
+            data.target_layout,
+            data.channel,
+        );
+        crate_graph.set_target(windows, Some("x86_64-pc-windows-msvc".to_owned()));
+        let mut change = Change::new();
+        change.set_crate_graph(crate_graph);
+        db.apply_change(change);
+
+        let inactive = |db: &RootDatabase| {
+            let config = DiagnosticsConfig::test_sample();
+            let text = db.file_text(file_id);
+            crate::diagnostics(db, &config, &AssistResolveStrategy::All, file_id)
+                .into_iter()
+                .map(|d| text[d.range].to_owned())
+                .collect::<Vec<_>>()
+        };
+        let set_active_target = |db: &mut RootDatabase, file_id: FileId, target: Option<&str>| {
+            let mut change = Change::new();
+            change.set_active_targets(FxHashMap::from_iter(
+                target.map(|it| (file_id, it.to_owned())),
+            ));
+            db.apply_change(change);
+        };
+
+        // Files are analyzed for the primary target by default.
+        assert_eq!(inactive(&db), ["#[cfg(windows)] fn windows() {}"]);
+
+        set_active_target(&mut db, file_id, Some("x86_64-pc-windows-msvc"));
+        assert_eq!(inactive(&db), ["#[cfg(not(windows))] fn unix() {}"]);
+
+        set_active_target(&mut db, file_id, None);
+        assert_eq!(inactive(&db), ["#[cfg(windows)] fn windows() {}"]);
+    }
 }
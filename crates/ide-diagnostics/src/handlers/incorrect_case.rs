@@ -176,9 +176,12 @@ fn NonSnakeCaseName() {}
         check_diagnostics(
             r#"
 fn foo(SomeParam: u8) {}
+     //^^^^^^^^^ 💡 warn: unused variable `SomeParam`
     // ^^^^^^^^^ 💡 warn: Parameter `SomeParam` should have snake_case name, e.g. `some_param`
 
 fn foo2(ok_param: &str, CAPS_PARAM: u8) {}
+      //^^^^^^^^ 💡 warn: unused variable `ok_param`
+                      //^^^^^^^^^^ 💡 warn: unused variable `CAPS_PARAM`
                      // ^^^^^^^^^^ 💡 warn: Parameter `CAPS_PARAM` should have snake_case name, e.g. `caps_param`
 "#,
         );
@@ -190,8 +193,10 @@ fn foo2(ok_param: &str, CAPS_PARAM: u8) {}
             r#"
 fn foo() {
     let SOME_VALUE = 10;
+      //^^^^^^^^^^ 💡 warn: unused variable `SOME_VALUE`
      // ^^^^^^^^^^ 💡 warn: Variable `SOME_VALUE` should have snake_case name, e.g. `some_value`
     let AnotherValue = 20;
+      //^^^^^^^^^^^^ 💡 warn: unused variable `AnotherValue`
      // ^^^^^^^^^^^^ 💡 warn: Variable `AnotherValue` should have snake_case name, e.g. `another_value`
 }
 "#,
@@ -293,6 +298,7 @@ impl someStruct {
     fn SomeFunc(&self) {
     // ^^^^^^^^ 💡 warn: Function `SomeFunc` should have snake_case name, e.g. `some_func`
         let WHY_VAR_IS_CAPS = 10;
+          //^^^^^^^^^^^^^^^ 💡 warn: unused variable `WHY_VAR_IS_CAPS`
          // ^^^^^^^^^^^^^^^ 💡 warn: Variable `WHY_VAR_IS_CAPS` should have snake_case name, e.g. `why_var_is_caps`
     }
 }
@@ -309,7 +315,9 @@ enum Option { Some, None }
 fn main() {
     match Option::None {
         None => (),
+      //^^^^ 💡 warn: unused variable `None`
         Some => (),
+      //^^^^ 💡 warn: unused variable `Some`
     }
 }
 "#,
@@ -325,8 +333,11 @@ enum Option { Some, None }
 fn main() {
     match Option::None {
         SOME_VAR @ None => (),
+      //^^^^^^^^ 💡 warn: unused variable `SOME_VAR`
      // ^^^^^^^^ 💡 warn: Variable `SOME_VAR` should have snake_case name, e.g. `some_var`
+                 //^^^^ 💡 warn: unused variable `None`
         Some => (),
+      //^^^^ 💡 warn: unused variable `Some`
     }
 }
 "#,
@@ -350,6 +361,7 @@ enum E {
 
 mod F {
     fn CheckItWorksWithCrateAttr(BAD_NAME_HI: u8) {}
+                               //^^^^^^^^^^^ 💡 warn: unused variable `BAD_NAME_HI`
 }
     "#,
         );
@@ -395,7 +407,12 @@ fn qualify() {
 
     #[test] // Issue #8809.
     fn parenthesized_parameter() {
-        check_diagnostics(r#"fn f((O): _) {}"#)
+        check_diagnostics(
+            r#"
+fn f((O): _) {}
+    //^ 💡 warn: unused variable `O`
+"#,
+        )
     }
 
     #[test]
@@ -473,6 +490,7 @@ mod CheckBadStyle {
 mod F {
     #![allow(non_snake_case)]
     fn CheckItWorksWithModAttr(BAD_NAME_HI: u8) {}
+                             //^^^^^^^^^^^ 💡 warn: unused variable `BAD_NAME_HI`
 }
 
 #[allow(non_snake_case, non_camel_case_types)]
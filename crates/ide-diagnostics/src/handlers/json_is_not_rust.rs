@@ -189,6 +189,7 @@ mod tests {
         check_fix(
             r#"
             //- /lib.rs crate:lib deps:serde
+            #[allow(unused_imports)]
             use serde::Serialize;
 
             fn some_garbage() {
@@ -209,6 +210,7 @@ mod tests {
             }
             "#,
             r#"
+            #[allow(unused_imports)]
             use serde::Serialize;
 
             fn some_garbage() {
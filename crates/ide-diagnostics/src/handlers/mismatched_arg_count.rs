@@ -73,6 +73,7 @@ fn f() { zero(); }
         check_diagnostics(
             r#"
 fn one(arg: u8) {}
+     //^^^ 💡 warn: unused variable `arg`
 fn f() { one(); }
           //^^ error: expected 1 argument, found 0
 "#,
@@ -81,6 +82,7 @@ fn f() { one(); }
         check_diagnostics(
             r#"
 fn one(arg: u8) {}
+     //^^^ 💡 warn: unused variable `arg`
 fn f() { one(1); }
 "#,
         );
@@ -118,6 +120,7 @@ fn f() {
             r#"
 struct S;
 impl S { fn method(&self, arg: u8) {} }
+                        //^^^ 💡 warn: unused variable `arg`
 
             fn f() {
                 S.method();
@@ -129,6 +132,7 @@ impl S { fn method(&self, arg: u8) {} }
             r#"
 struct S;
 impl S { fn method(&self, arg: u8) {} }
+                        //^^^ 💡 warn: unused variable `arg`
 
 fn f() {
     S::method(&S, 0);
@@ -277,6 +281,7 @@ struct S;
 impl S {
     fn method(#[cfg(NEVER)] self) {}
     fn method2(#[cfg(NEVER)] self, arg: u8) {}
+                                 //^^^ 💡 warn: unused variable `arg`
     fn method3(self, #[cfg(NEVER)] arg: u8) {}
 }
 
@@ -307,7 +312,9 @@ fn main() {
 #[rustc_legacy_const_generics(1, 3)]
 fn mixed<const N1: &'static str, const N2: bool>(
     a: u8,
+  //^ 💡 warn: unused variable `a`
     b: i8,
+  //^ 💡 warn: unused variable `b`
 ) {}
 
 fn f() {
@@ -318,7 +325,9 @@ fn f() {
 #[rustc_legacy_const_generics(1, 3)]
 fn b<const N1: u8, const N2: u8>(
     a: u8,
+  //^ 💡 warn: unused variable `a`
     b: u8,
+  //^ 💡 warn: unused variable `b`
 ) {}
 
 fn g() {
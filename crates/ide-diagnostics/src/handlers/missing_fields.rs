@@ -290,6 +290,7 @@ fn x(a: S) {
 struct S { s: u32 }
 fn x(a: S) {
     let S { ref s } = a;
+              //^ 💡 warn: unused variable `s`
 }
 ",
         )
@@ -627,6 +628,7 @@ struct TestStruct { one: i32, two: i64 }
 fn test_fn() {
     let one = 1;
     let s = TestStruct{ one, two: 2 };
+      //^ 💡 warn: unused variable `s`
 }
         "#,
         );
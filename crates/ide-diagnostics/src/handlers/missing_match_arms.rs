@@ -565,7 +565,9 @@ fn main() {
     // `Never` is deliberately not defined so that it's an uninferred type.
     match Option::<Never>::None {
         None => (),
+      //^^^^ 💡 warn: unused variable `None`
         Some(never) => match never {},
+           //^^^^^ 💡 warn: unused variable `never`
     }
     match Option::<Never>::None {
         //^^^^^^^^^^^^^^^^^^^^^ error: missing match arm: `None` not covered
@@ -717,6 +719,7 @@ fn main() {
 struct S { a: char}
 fn main(v: S) {
     match v { S{ a }      => {} }
+               //^ 💡 warn: unused variable `a`
     match v { S{ a: _x }  => {} }
     match v { S{ a: 'a' } => {} }
     match v { S{..}       => {} }
@@ -898,6 +901,7 @@ fn foo() {
     match &E::A {
         E::A => {}
         x => {}
+      //^ 💡 warn: unused variable `x`
     }
 }",
         );
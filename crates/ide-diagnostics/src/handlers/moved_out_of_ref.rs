@@ -29,6 +29,7 @@ fn main() {
     let a = &X;
     let b = *a;
       //^ error: cannot move `X` out of reference
+      //^ 💡 warn: unused variable `b`
 }
 "#,
         );
@@ -45,7 +46,9 @@ fn main() {
     let a = &Y(X, 5);
     let b = a.0;
       //^ error: cannot move `X` out of reference
+      //^ 💡 warn: unused variable `b`
     let y = a.1;
+      //^ 💡 warn: unused variable `y`
 }
 "#,
         );
@@ -61,6 +64,7 @@ fn main() {
     static S: X = X;
     let s = S;
       //^ error: cannot move `X` out of reference
+      //^ 💡 warn: unused variable `s`
 }
 "#,
         );
@@ -166,6 +170,7 @@ enum X {
 fn main() {
     let x = &X::Bar;
     let c = || match *x {
+      //^ 💡 warn: unused variable `c`
         X::Foo(t) => t,
         _ => 5,
     };
@@ -324,6 +324,7 @@ fn main() {
     let x_own = 2;
     let ref mut x_ref = x_own;
       //^^^^^^^^^^^^^ 💡 error: cannot mutate immutable variable `x_own`
+              //^^^^^ 💡 warn: unused variable `x_ref`
 }
 "#,
         );
@@ -332,6 +333,7 @@ fn main() {
 struct Foo;
 impl Foo {
     fn method(&mut self, x: i32) {}
+                       //^ 💡 warn: unused variable `x`
 }
 fn main() {
     let x = Foo;
@@ -389,6 +391,7 @@ fn main() {
     match (2, 3) {
         (x, mut y) => {
           //^^^^^ 💡 warn: variable does not need to be mutable
+              //^ 💡 warn: unused variable `y`
             x = 7;
           //^^^^^ 💡 error: cannot mutate immutable variable `x`
         }
@@ -410,6 +413,7 @@ fn main() {
     return;
     let mut x = 2;
       //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `x`
     &mut x;
 }
 "#,
@@ -420,6 +424,7 @@ fn main() {
     loop {}
     let mut x = 2;
       //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `x`
     &mut x;
 }
 "#,
@@ -441,6 +446,7 @@ fn main(b: bool) {
     }
     let mut x = 2;
       //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `x`
     &mut x;
 }
 "#,
@@ -455,6 +461,7 @@ fn main(b: bool) {
     }
     let mut x = 2;
       //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `x`
     &mut x;
 }
 "#,
@@ -534,6 +541,7 @@ fn main() {
         let z = (1, 2);
         match z {
             (k @ 5, ref mut t) if { continue; } => {
+           //^ 💡 warn: unused variable `k`
                   //^^^^^^^^^ 💡 error: cannot mutate immutable variable `z`
                 *t = 5;
             }
@@ -578,10 +586,13 @@ fn main() {
     let a;
     loop {
         let c @ (
+          //^ 💡 warn: unused variable `c`
             mut b,
           //^^^^^ 💡 warn: variable does not need to be mutable
+              //^ 💡 warn: unused variable `b`
             mut d
           //^^^^^ 💡 warn: variable does not need to be mutable
+              //^ 💡 warn: unused variable `d`
         );
         a = 1;
       //^^^^^ 💡 error: cannot mutate immutable variable `a`
@@ -600,6 +611,7 @@ fn main() {
             r#"
 fn f(mut x: i32) {
    //^^^^^ 💡 warn: variable does not need to be mutable
+       //^ 💡 warn: unused variable `x`
 }
 "#,
         );
@@ -614,7 +626,9 @@ fn f(x: i32) {
         check_diagnostics(
             r#"
 fn f((x, y): (i32, i32)) {
+       //^ 💡 warn: unused variable `y`
     let t = [0; 2];
+      //^ 💡 warn: unused variable `t`
    x = 5;
  //^^^^^ 💡 error: cannot mutate immutable variable `x`
 }
@@ -643,6 +657,7 @@ fn f() {
 fn f(x: [(i32, u8); 10]) {
     for (a, mut b) in x {
           //^^^^^ 💡 warn: variable does not need to be mutable
+              //^ 💡 warn: unused variable `b`
         a = 2;
       //^^^^^ 💡 error: cannot mutate immutable variable `a`
     }
@@ -660,8 +675,10 @@ fn f(x: [(i32, u8); 10]) {
     let mut it = x.into_iter();
     while let Some((a, mut b)) = it.next() {
                      //^^^^^ 💡 warn: variable does not need to be mutable
+                         //^ 💡 warn: unused variable `b`
         while let Some((c, mut d)) = it.next() {
                          //^^^^^ 💡 warn: variable does not need to be mutable
+                             //^ 💡 warn: unused variable `d`
             a = 2;
           //^^^^^ 💡 error: cannot mutate immutable variable `a`
             c = 2;
@@ -703,11 +720,13 @@ struct Foo;
 impl Index<usize> for Foo {
     type Output = (i32, u8);
     fn index(&self, index: usize) -> &(i32, u8) {
+                  //^^^^^ 💡 warn: unused variable `index`
         &(5, 2)
     }
 }
 impl IndexMut<usize> for Foo {
     fn index_mut(&mut self, index: usize) -> &mut (i32, u8) {
+                          //^^^^^ 💡 warn: unused variable `index`
         &mut (5, 2)
     }
 }
@@ -715,26 +734,34 @@ fn f() {
     let mut x = Foo;
       //^^^^^ 💡 warn: variable does not need to be mutable
     let y = &x[2];
+      //^ 💡 warn: unused variable `y`
     let x = Foo;
     let y = &mut x[2];
+      //^ 💡 warn: unused variable `y`
                //^💡 error: cannot mutate immutable variable `x`
     let mut x = &mut Foo;
       //^^^^^ 💡 warn: variable does not need to be mutable
     let y: &mut (i32, u8) = &mut x[2];
+      //^ 💡 warn: unused variable `y`
     let x = Foo;
     let ref mut y = x[7];
+              //^ 💡 warn: unused variable `y`
                   //^ 💡 error: cannot mutate immutable variable `x`
     let (ref mut y, _) = x[3];
+               //^ 💡 warn: unused variable `y`
                        //^ 💡 error: cannot mutate immutable variable `x`
     match x[10] {
         //^ 💡 error: cannot mutate immutable variable `x`
         (ref y, _) => (),
+           //^ 💡 warn: unused variable `y`
         (_, ref mut y) => (),
+                  //^ 💡 warn: unused variable `y`
     }
     let mut x = Foo;
     let mut i = 5;
       //^^^^^ 💡 warn: variable does not need to be mutable
     let y = &mut x[i];
+      //^ 💡 warn: unused variable `y`
 }
 "#,
         );
@@ -763,21 +790,29 @@ fn f() {
     let mut x = Foo;
       //^^^^^ 💡 warn: variable does not need to be mutable
     let y = &*x;
+      //^ 💡 warn: unused variable `y`
     let x = Foo;
     let y = &mut *x;
+      //^ 💡 warn: unused variable `y`
                //^^ 💡 error: cannot mutate immutable variable `x`
     let x = Foo;
+      //^ 💡 warn: unused variable `x`
     let x = Foo;
     let y: &mut (i32, u8) = &mut x;
+      //^ 💡 warn: unused variable `y`
                           //^^^^^^ 💡 error: cannot mutate immutable variable `x`
     let ref mut y = *x;
+              //^ 💡 warn: unused variable `y`
                   //^^ 💡 error: cannot mutate immutable variable `x`
     let (ref mut y, _) = *x;
+               //^ 💡 warn: unused variable `y`
                        //^^ 💡 error: cannot mutate immutable variable `x`
     match *x {
         //^^ 💡 error: cannot mutate immutable variable `x`
         (ref y, _) => (),
+           //^ 💡 warn: unused variable `y`
         (_, ref mut y) => (),
+                  //^ 💡 warn: unused variable `y`
     }
 }
 "#,
@@ -861,6 +896,7 @@ pub struct TreeLeaf {
 pub fn test() {
     let mut tree = Tree::Leaf(
       //^^^^^^^^ 💡 warn: variable does not need to be mutable
+          //^^^^ 💡 warn: unused variable `tree`
         TreeLeaf {
             depth: 0,
             data: 0
@@ -915,8 +951,10 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
             let _ = closure1();
                   //^^^^^^^^ 💡 error: cannot mutate immutable variable `closure1`
             let closure2 = || { x = x; };
+              //^^^^^^^^ 💡 warn: unused variable `closure2`
                               //^ 💡 error: cannot mutate immutable variable `x`
             let closure3 = || {
+              //^^^^^^^^ 💡 warn: unused variable `closure3`
                 let x = 2;
                 x = 5;
               //^^^^^ 💡 error: cannot mutate immutable variable `x`
@@ -924,6 +962,7 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
             };
             let x = X;
             let closure4 = || { x.mutate(); };
+              //^^^^^^^^ 💡 warn: unused variable `closure4`
                               //^ 💡 error: cannot mutate immutable variable `x`
         }
                     "#,
@@ -934,13 +973,16 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
         fn f() {
             let mut x = 5;
               //^^^^^ 💡 warn: variable does not need to be mutable
+                  //^ 💡 warn: unused variable `x`
             let mut y = 2;
             y = 7;
             let closure = || {
+              //^^^^^^^ 💡 warn: unused variable `closure`
                 let mut z = 8;
                 z = 3;
                 let mut k = z;
                   //^^^^^ 💡 warn: variable does not need to be mutable
+                      //^ 💡 warn: unused variable `k`
             };
         }
                     "#,
@@ -950,6 +992,7 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
 //- minicore: copy, fn
 fn f() {
     let closure = || {
+      //^^^^^^^ 💡 warn: unused variable `closure`
         || {
             || {
                 let x = 2;
@@ -969,10 +1012,13 @@ fn f() {
     let mut x = X;
       //^^^^^ 💡 warn: variable does not need to be mutable
     let c1 = || x;
+      //^^ 💡 warn: unused variable `c1`
     let mut x = X;
     let c2 = || { x = X; x };
+      //^^ 💡 warn: unused variable `c2`
     let mut x = X;
     let c2 = move || { x = X; };
+      //^^ 💡 warn: unused variable `c2`
 }
             "#,
         );
@@ -1020,6 +1066,7 @@ fn x(t: &[u8]) {
     match t {
         &[a, mut b] | &[a, _, mut b] => {
            //^^^^^ 💡 warn: variable does not need to be mutable
+               //^ 💡 warn: unused variable `b`
 
             a = 2;
           //^^^^^ 💡 error: cannot mutate immutable variable `a`
@@ -1039,6 +1086,7 @@ fn x(t: &[u8]) {
 //- minicore: coerce_unsized, deref_mut, slice
 use core::ops::{Deref, DerefMut};
 use core::{marker::Unsize, ops::CoerceUnsized};
+  //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warn: unused import
 
 #[lang = "owned_box"]
 pub struct Box<T: ?Sized> {
@@ -1078,6 +1126,7 @@ fn f() {
   //^^^^^^^ 💡 error: cannot mutate immutable variable `x`
     let x = Box::new(5);
     let closure = || *x = 2;
+      //^^^^^^^ 💡 warn: unused variable `closure`
                     //^ 💡 error: cannot mutate immutable variable `x`
 }
 "#,
@@ -1129,6 +1178,7 @@ macro_rules! mac {
 fn main2() {
     let mut x = mac![];
       //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `x`
 }
         "#,
         );
@@ -74,6 +74,7 @@ fn main(s: module::Struct) {
 fn main() {
     let strukt = {
         use crate as ForceParentBlockDefMap;
+          //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warn: unused import
         {
             pub struct Struct {
                 field: (),
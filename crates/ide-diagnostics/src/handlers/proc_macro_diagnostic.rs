@@ -0,0 +1,96 @@
+use hir::{EmittedDiagnostic, ProcMacroDiagnosticLevel};
+use ide_db::base_db::FileRange;
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext, Severity};
+
+// Diagnostic: proc-macro-diagnostic
+//
+// This diagnostic is shown for diagnostics that procedural macros emit while expanding, for
+// example via `proc_macro::Diagnostic::emit`.
+pub(crate) fn proc_macro_diagnostic(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::ProcMacroDiagnostic,
+) -> Diagnostic {
+    let severity = match d.diagnostic.level {
+        ProcMacroDiagnosticLevel::Error => Severity::Error,
+        ProcMacroDiagnosticLevel::Warning => Severity::Warning,
+        ProcMacroDiagnosticLevel::Note | ProcMacroDiagnosticLevel::Help => Severity::WeakWarning,
+    };
+    let file_id = d.node.file_id.original_file(ctx.sema.db);
+    let range_of = |range| ctx.resolve_precise_location(&d.node, Some(range));
+
+    // Point at the spans the macro reported if they come from its input, at the macro call
+    // otherwise.
+    let mut ranges = d.diagnostic.ranges.iter().copied();
+    let range = ctx.resolve_precise_location(&d.node, ranges.next().or(d.precise_location));
+
+    let mut message = d.diagnostic.message.clone();
+    let mut related = Vec::new();
+    related.extend(ranges.map(|it| (range_of(it), d.diagnostic.message.clone())));
+    for child in &d.diagnostic.children {
+        let child_message = format!("{}: {}", level_str(child), child.message);
+        if child.ranges.is_empty() {
+            message.push('\n');
+            message.push_str(&child_message);
+        } else {
+            related.extend(child.ranges.iter().map(|&it| (range_of(it), child_message.clone())));
+        }
+    }
+
+    related.into_iter().fold(
+        Diagnostic::new(DiagnosticCode::Ra("proc-macro-diagnostic", severity), message, range),
+        |diag, (range, message)| diag.with_related(FileRange { file_id, range }, message),
+    )
+}
+
+fn level_str(diagnostic: &EmittedDiagnostic) -> &'static str {
+    match diagnostic.level {
+        ProcMacroDiagnosticLevel::Error => "error",
+        ProcMacroDiagnosticLevel::Warning => "warning",
+        ProcMacroDiagnosticLevel::Note => "note",
+        ProcMacroDiagnosticLevel::Help => "help",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_diagnostics_with_proc_attr_macros};
+
+    #[test]
+    fn fn_like_macro() {
+        check_diagnostics(
+            r#"
+//- proc_macros: reject_literals
+proc_macros::reject_literals!(a, "b", 3);
+                               //^^^ error: unexpected literal `"b"`
+                                    //^ error: unexpected literal `3`
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_in_body() {
+        check_diagnostics(
+            r#"
+//- proc_macros: reject_literals
+fn f() {
+    proc_macros::reject_literals!(1);
+                                //^ error: unexpected literal `1`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn attribute_input() {
+        check_diagnostics_with_proc_attr_macros(
+            r#"
+//- proc_macros: reject_literals_attr
+#[proc_macros::reject_literals_attr(a = "b")]
+                                      //^^^ error: unexpected literal `"b"`
+fn f() -> u32 { 1 }
+              //^ error: unexpected literal `1`
+"#,
+        );
+    }
+}
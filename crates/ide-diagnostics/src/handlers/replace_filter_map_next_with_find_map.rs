@@ -65,6 +65,7 @@ mod tests {
         let mut config = DiagnosticsConfig::test_sample();
         config.disabled.insert("inactive-code".to_string());
         config.disabled.insert("E0599".to_string());
+        config.disabled.insert("unused_variables".to_string());
         check_diagnostics_with_config(config, ra_fixture)
     }
 
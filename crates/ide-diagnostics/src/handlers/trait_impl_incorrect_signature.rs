@@ -0,0 +1,267 @@
+use hir::{AsAssocItem, HasSource, HirDisplay, InFile, SignatureMismatch};
+use ide_db::{assists::Assist, source_change::SourceChange, traits::add_trait_assoc_items_to_impl};
+use syntax::{
+    ast::{self, HasName},
+    AstNode, SyntaxKind, SyntaxNode, SyntaxNodePtr, TextRange, TextSize,
+};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-impl-incorrect-signature
+//
+// This diagnostic is triggered when the signature of a method in a trait impl does not match the
+// declaration in the trait.
+pub(crate) fn trait_impl_incorrect_signature(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplIncorrectSignature,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let name = d.impl_fn.name(db);
+    let name = name.display(db);
+    let trait_name = d.trait_fn.as_assoc_item(db).and_then(|it| it.containing_trait(db));
+    let (code, message) = match &d.mismatch {
+        SignatureMismatch::SelfParam { trait_has_self: true } => (
+            "E0186",
+            format!("method `{name}` has a `self` declaration in the trait, but not in the impl"),
+        ),
+        SignatureMismatch::SelfParam { trait_has_self: false } => (
+            "E0185",
+            format!("method `{name}` has a `self` declaration in the impl, but not in the trait"),
+        ),
+        SignatureMismatch::ParamCount { expected, found } => (
+            "E0050",
+            format!(
+                "method `{name}` has {found} parameter{} but the declaration in trait `{}::{name}` has {expected}",
+                if *found == 1 { "" } else { "s" },
+                trait_name.map_or_else(String::new, |it| it.name(db).display(db).to_string()),
+            ),
+        ),
+        SignatureMismatch::ParamType { expected, found, .. } => (
+            "E0053",
+            format!(
+                "method `{name}` has an incompatible type for trait: expected `{}`, found `{}`",
+                expected.display(db),
+                found.display(db)
+            ),
+        ),
+    };
+
+    let source = d.impl_fn.source(db);
+    let node = source.as_ref().map(|src| src.as_ref().map(|it| mismatch_node(it, &d.mismatch)));
+    let mut diag = match node {
+        Some(node) => Diagnostic::new_with_syntax_node_ptr(
+            ctx,
+            DiagnosticCode::RustcHardError(code),
+            message,
+            node.map(|it| SyntaxNodePtr::new(&it)),
+        ),
+        None => {
+            Diagnostic::new(DiagnosticCode::RustcHardError(code), message, TextRange::default())
+        }
+    };
+    if let Some(source) = source {
+        let range = diag.range;
+        diag = diag.with_fixes(fixes(ctx, d, source, range));
+    }
+    diag
+}
+
+/// Returns the part of the impl method's signature that differs from the trait's.
+fn mismatch_node(fn_: &ast::Fn, mismatch: &SignatureMismatch) -> SyntaxNode {
+    let param_list = fn_.param_list();
+    let node = match mismatch {
+        SignatureMismatch::SelfParam { .. } | SignatureMismatch::ParamCount { .. } => {
+            param_list.map(|it| it.syntax().clone())
+        }
+        SignatureMismatch::ParamType { param: Some(idx), .. } => {
+            param_list.and_then(|it| match (it.self_param(), idx) {
+                (Some(self_param), 0) => Some(self_param.syntax().clone()),
+                (Some(_), idx) => it.params().nth(idx - 1).map(|it| it.syntax().clone()),
+                (None, &idx) => it.params().nth(idx).map(|it| it.syntax().clone()),
+            })
+        }
+        SignatureMismatch::ParamType { param: None, .. } => {
+            fn_.ret_type().and_then(|it| it.ty()).map(|it| it.syntax().clone())
+        }
+    };
+    node.or_else(|| fn_.name().map(|it| it.syntax().clone()))
+        .unwrap_or_else(|| fn_.syntax().clone())
+}
+
+fn fixes(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplIncorrectSignature,
+    impl_fn: InFile<ast::Fn>,
+    range: TextRange,
+) -> Option<Vec<Assist>> {
+    let db = ctx.sema.db;
+    let file_id = impl_fn.file_id.file_id()?;
+    let impl_fn = impl_fn.value;
+    let impl_ = impl_fn.syntax().ancestors().find_map(ast::Impl::cast)?;
+    let trait_ = d.trait_fn.as_assoc_item(db)?.containing_trait(db)?;
+    let trait_fn = ctx.sema.source(d.trait_fn)?.map(ast::AssocItem::Fn);
+
+    // Generate the method like the "Implement missing members" assist would, but into a copy of
+    // the impl, and take over its signature.
+    let target_scope = ctx.sema.scope(impl_.syntax())?;
+    let ast::AssocItem::Fn(new_fn) = add_trait_assoc_items_to_impl(
+        &ctx.sema,
+        &[trait_fn],
+        trait_,
+        &impl_.clone_for_update(),
+        target_scope,
+    ) else {
+        return None;
+    };
+    let new_signature = {
+        let range = signature_range(&new_fn)?;
+        let offset = new_fn.syntax().text_range().start();
+        new_fn.syntax().text().slice(range - offset).to_string()
+    };
+
+    let edit =
+        TextEdit::replace(signature_range(&impl_fn)?, format!("{} ", new_signature.trim_end()));
+    Some(vec![fix(
+        "fix_trait_impl_signature",
+        "Use the signature declared in the trait",
+        SourceChange::from_text_edit(file_id, edit),
+        range,
+    )])
+}
+
+/// The range from the first keyword of `fn_` up to its body.
+fn signature_range(fn_: &ast::Fn) -> Option<TextRange> {
+    let start = fn_
+        .syntax()
+        .children_with_tokens()
+        .find(|it| {
+            !matches!(
+                it.kind(),
+                SyntaxKind::WHITESPACE
+                    | SyntaxKind::COMMENT
+                    | SyntaxKind::ATTR
+                    | SyntaxKind::VISIBILITY
+            )
+        })?
+        .text_range()
+        .start();
+    let end: TextSize = fn_.body()?.syntax().text_range().start();
+    (start <= end).then(|| TextRange::new(start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn self_param_mismatch() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn with_self(&self);
+    fn without_self();
+}
+struct S;
+impl Trait for S {
+    fn with_self() {}
+              //^^ 💡 error: method `with_self` has a `self` declaration in the trait, but not in the impl
+    fn without_self(&self) {}
+                 //^^^^^^^ 💡 error: method `without_self` has a `self` declaration in the impl, but not in the trait
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn param_count_mismatch() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn f(&self, a: u8);
+}
+struct S;
+impl Trait for S {
+    fn f(&self, a: u8, b: u8) {}
+      //^^^^^^^^^^^^^^^^^^^^^ 💡 error: method `f` has 3 parameters but the declaration in trait `Trait::f` has 2
+              //^ 💡 warn: unused variable `a`
+                     //^ 💡 warn: unused variable `b`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn param_type_mismatch() {
+        check_diagnostics(
+            r#"
+trait Trait<T> {
+    type Out;
+    fn f(&self, a: T) -> Self::Out;
+    fn g(&mut self);
+}
+struct S;
+impl Trait<u32> for S {
+    type Out = i32;
+    fn f(&self, a: i32) -> i32 { 0 }
+              //^^^^^^ 💡 error: method `f` has an incompatible type for trait: expected `u32`, found `i32`
+              //^ 💡 warn: unused variable `a`
+    fn g(&self) {}
+       //^^^^^ 💡 error: method `g` has an incompatible type for trait: expected `&mut S`, found `&S`
+}
+struct U;
+impl Trait<u8> for U {
+    type Out = bool;
+    fn f(&self, a: u8) -> <Self as Trait<u8>>::Out { true }
+              //^ 💡 warn: unused variable `a`
+    fn g(&mut self) {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generic_methods() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn f<T>(&self, t: T) -> T;
+}
+struct S;
+impl Trait for S {
+    fn f<U>(&self, u: U) -> U { u }
+}
+struct R;
+impl Trait for R {
+    fn f<U>(&self, u: U) -> u8 { 0 }
+                 //^ 💡 warn: unused variable `u`
+                          //^^ 💡 error: method `f` has an incompatible type for trait: expected `U`, found `u8`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_signature() {
+        check_fix(
+            r#"
+trait Trait<T> {
+    fn f(&self, a: T) -> Option<T>;
+}
+struct S;
+impl Trait<u32> for S {
+    fn f(&self, a: i32$0) -> Option<i32> { None }
+}
+"#,
+            r#"
+trait Trait<T> {
+    fn f(&self, a: T) -> Option<T>;
+}
+struct S;
+impl Trait<u32> for S {
+    fn f(&self, a: u32) -> Option<u32> { None }
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use hir::{AssocItem, InFile};
+use ide_db::{
+    assists::Assist,
+    source_change::SourceChangeBuilder,
+    traits::{add_trait_assoc_items_to_impl, filter_assoc_items, DefaultMethods},
+};
+use itertools::Itertools;
+use syntax::{ast, AstNode, TextRange};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-impl-missing-assoc-item
+//
+// This diagnostic is triggered when a trait impl does not implement all required items of the
+// trait.
+pub(crate) fn trait_impl_missing_assoc_item(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplMissingAssocItems,
+) -> Diagnostic {
+    let missing = d.missing.iter().format_with(", ", |(name, item), f| {
+        f(&match *item {
+            AssocItem::Function(_) => "fn ",
+            AssocItem::Const(_) => "const ",
+            AssocItem::TypeAlias(_) => "type ",
+        })?;
+        f(&format_args!("`{}`", name.display(ctx.sema.db)))
+    });
+    let impl_ = d.impl_.to_node(&ctx.sema.parse_or_expand(d.file_id));
+    // Only highlight the header of the impl, like rustc does.
+    let header = match (impl_.impl_token(), impl_.self_ty()) {
+        (Some(impl_token), Some(self_ty)) if !d.file_id.is_macro() => {
+            TextRange::new(impl_token.text_range().start(), self_ty.syntax().text_range().end())
+        }
+        _ => {
+            ctx.sema.diagnostics_display_range(InFile::new(d.file_id, d.impl_.clone().into())).range
+        }
+    };
+    Diagnostic::new(
+        DiagnosticCode::RustcHardError("E0046"),
+        format!("not all trait items implemented, missing: {missing}"),
+        header,
+    )
+    .with_main_node(InFile::new(d.file_id, impl_.syntax().clone()))
+    .with_fixes(fixes(ctx, d, &impl_, header))
+}
+
+fn fixes(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplMissingAssocItems,
+    impl_: &ast::Impl,
+    range: TextRange,
+) -> Option<Vec<Assist>> {
+    let file_id = d.file_id.file_id()?;
+    let trait_ = ctx.sema.to_def(impl_)?.trait_(ctx.sema.db)?;
+    let items: Vec<_> = d.missing.iter().map(|&(_, it)| it).collect();
+    let missing = filter_assoc_items(&ctx.sema, &items, DefaultMethods::No);
+    if missing.is_empty() {
+        return None;
+    }
+    let target_scope = ctx.sema.scope(impl_.syntax())?;
+    let mut builder = SourceChangeBuilder::new(file_id);
+    let impl_mut = builder.make_mut(impl_.clone());
+    add_trait_assoc_items_to_impl(&ctx.sema, &missing, trait_, &impl_mut, target_scope);
+    Some(vec![fix(
+        "add_impl_missing_members",
+        "Implement missing members",
+        builder.finish(),
+        range,
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn missing_items() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    const C: u8;
+    const D: u8 = 0;
+    type T;
+    type U = ();
+    fn f();
+    fn g() {}
+}
+
+impl Trait for () {
+    const C: u8 = 0;
+    type T = ();
+    fn f() {}
+}
+
+struct S;
+  impl Trait for S {
+//^^^^^^^^^^^^^^^^ 💡 error: not all trait items implemented, missing: const `C`, type `T`, fn `f`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_missing_items_for_negative_or_unresolved_impls() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn f();
+}
+struct S;
+impl !Trait for S {}
+impl Unknown for S {}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_missing_items() {
+        check_fix(
+            r#"
+trait Trait<T> {
+    type X;
+    fn foo(&self) -> T;
+    fn bar(&self) {}
+}
+
+impl Trait<u32> for ()$0 {
+}
+"#,
+            r#"
+trait Trait<T> {
+    type X;
+    fn foo(&self) -> T;
+    fn bar(&self) {}
+}
+
+impl Trait<u32> for () {
+    type X;
+
+    fn foo(&self) -> u32 {
+        todo!()
+    }
+}
+"#,
+        );
+    }
+}
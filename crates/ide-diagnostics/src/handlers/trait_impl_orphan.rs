@@ -0,0 +1,96 @@
+use hir::InFile;
+use syntax::{ast, AstNode, TextRange};
+
+use crate::{adjusted_display_range, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-impl-orphan
+//
+// Only traits defined in the current crate can be implemented for arbitrary types.
+pub(crate) fn trait_impl_orphan(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplOrphan,
+) -> Diagnostic {
+    let range = adjusted_display_range::<ast::Impl>(
+        ctx,
+        InFile::new(d.file_id, d.impl_.clone().into()),
+        &|impl_| {
+            Some(TextRange::new(
+                impl_.impl_token()?.text_range().start(),
+                impl_.self_ty()?.syntax().text_range().end(),
+            ))
+        },
+    );
+    Diagnostic::new(
+        DiagnosticCode::RustcHardError("E0117"),
+        "only traits defined in the current crate can be implemented for arbitrary types",
+        range,
+    )
+    .with_main_node(InFile::new(
+        d.file_id,
+        d.impl_.to_node(&ctx.sema.parse_or_expand(d.file_id)).syntax().clone(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn foreign_trait_for_foreign_type() {
+        check_diagnostics(
+            r#"
+//- /foo.rs crate:foo
+pub trait Foo<T> {}
+pub struct Bar;
+//- /main.rs crate:main deps:foo
+use foo::{Bar, Foo};
+struct Local;
+  impl Foo<u8> for Bar {}
+//^^^^^^^^^^^^^^^^^^^^ error: only traits defined in the current crate can be implemented for arbitrary types
+  impl Foo<Bar> for &i32 {}
+//^^^^^^^^^^^^^^^^^^^^^^ error: only traits defined in the current crate can be implemented for arbitrary types
+impl Foo<Local> for Bar {}
+impl Foo<u8> for Local {}
+impl Foo<u8> for &Local {}
+impl Foo<u8> for dyn LocalTrait {}
+trait LocalTrait {}
+impl LocalTrait for Bar {}
+"#,
+        );
+    }
+
+    #[test]
+    fn fundamental_types() {
+        check_diagnostics(
+            r#"
+//- /foo.rs crate:foo
+pub trait Foo {}
+#[fundamental]
+pub struct Box<T>(T);
+pub struct Vec<T>(T);
+//- /main.rs crate:main deps:foo
+use foo::{Box, Foo, Vec};
+struct Local;
+impl Foo for Box<Local> {}
+  impl Foo for Vec<Local> {}
+//^^^^^^^^^^^^^^^^^^^^^^^ error: only traits defined in the current crate can be implemented for arbitrary types
+"#,
+        );
+    }
+
+    #[test]
+    fn uncovered_type_params_are_not_orphans() {
+        check_diagnostics(
+            r#"
+//- /foo.rs crate:foo
+pub trait Foo<T> {}
+pub trait Bar<T> {}
+//- /main.rs crate:main deps:foo
+use foo::{Bar, Foo};
+struct Local;
+impl<T> Foo<Local> for T {}
+impl<T> Bar<T> for Local {}
+"#,
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use hir::{HasSource, HirDisplay, InFile};
+use ide_db::base_db::FileRange;
+use syntax::{ast, AstNode, SyntaxNodePtr, TextRange};
+
+use crate::{adjusted_display_range, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-impl-overlap
+//
+// This diagnostic is triggered when two implementations of a trait could apply to the same type.
+pub(crate) fn trait_impl_overlap(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplOverlap,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let range = adjusted_display_range::<ast::Impl>(
+        ctx,
+        InFile::new(d.file_id, d.impl_.clone().into()),
+        &impl_header,
+    );
+    let mut diag = Diagnostic::new(
+        DiagnosticCode::RustcHardError("E0119"),
+        format!(
+            "conflicting implementations of trait `{}` for type `{}`",
+            d.trait_.name(db).display(db),
+            d.self_ty.display(db)
+        ),
+        range,
+    )
+    .with_main_node(InFile::new(
+        d.file_id,
+        d.impl_.to_node(&ctx.sema.parse_or_expand(d.file_id)).syntax().clone(),
+    ));
+    if let Some(other) = d.other.source(db) {
+        let range = match other.file_id.file_id() {
+            Some(file_id) => {
+                impl_header(other.value.clone()).map(|range| FileRange { file_id, range })
+            }
+            None => None,
+        };
+        let range = range.unwrap_or_else(|| {
+            ctx.sema.diagnostics_display_range(other.map(|it| SyntaxNodePtr::new(it.syntax())))
+        });
+        diag = diag.with_related(range, "first implementation here");
+    }
+    diag
+}
+
+fn impl_header(impl_: ast::Impl) -> Option<TextRange> {
+    Some(TextRange::new(
+        impl_.impl_token()?.text_range().start(),
+        impl_.self_ty()?.syntax().text_range().end(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn conflicting_impls() {
+        check_diagnostics(
+            r#"
+trait Foo<T> {}
+struct S;
+impl Foo<u8> for S {}
+  impl Foo<u8> for S {}
+//^^^^^^^^^^^^^^^^^^ error: conflicting implementations of trait `Foo` for type `S`
+impl Foo<u16> for S {}
+"#,
+        );
+    }
+
+    #[test]
+    fn blanket_impls() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+trait Foo {}
+trait Bar {}
+struct S;
+struct T;
+impl<U: Copy> Foo for U {}
+  impl Foo for S {}
+//^^^^^^^^^^^^^^ error: conflicting implementations of trait `Foo` for type `S`
+impl Foo for T {}
+impl Copy for S {}
+
+impl<U: Copy> Bar for U {}
+impl<U> Bar for &mut U {}
+"#,
+        );
+    }
+
+    #[test]
+    fn impls_in_dependencies() {
+        check_diagnostics(
+            r#"
+//- /foo.rs crate:foo
+pub trait Foo {}
+impl<T> Foo for T {}
+//- /main.rs crate:main deps:foo
+use foo::Foo;
+struct S;
+  impl Foo for S {}
+//^^^^^^^^^^^^^^ error: conflicting implementations of trait `Foo` for type `S`
+"#,
+        );
+    }
+
+    #[test]
+    fn no_overlap_for_negative_or_marker_impls() {
+        check_diagnostics(
+            r#"
+#[marker]
+trait Marker {}
+trait Foo {}
+struct S;
+impl<T> Marker for T {}
+impl Marker for S {}
+impl Foo for S {}
+impl !Foo for S {}
+"#,
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use hir::{AssocItem, HasSource, InFile};
+use syntax::{ast::HasName, AstNode, SyntaxNodePtr};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-impl-redundant-assoc-item
+//
+// This diagnostic is triggered when a trait impl contains an item that is not a member of the
+// trait.
+pub(crate) fn trait_impl_redundant_assoc_item(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::TraitImplRedundantAssocItems,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let (name, item) = &d.assoc_item;
+    let (code, kind, name_ptr) = match *item {
+        AssocItem::Function(it) => ("E0407", "method", it.source(db).map(name_or_item)),
+        AssocItem::Const(it) => ("E0438", "const", it.source(db).map(name_or_item)),
+        AssocItem::TypeAlias(it) => ("E0437", "type", it.source(db).map(name_or_item)),
+    };
+    // Point at the item's name, or at the whole impl if the item can't be found.
+    let node = name_ptr.unwrap_or_else(|| InFile::new(d.file_id, d.impl_.clone().into()));
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError(code),
+        format!(
+            "{kind} `{}` is not a member of trait `{}`",
+            name.display(db),
+            d.trait_.name(db).display(db)
+        ),
+        node,
+    )
+}
+
+fn name_or_item<N: HasName>(item: InFile<N>) -> InFile<SyntaxNodePtr> {
+    item.map(|it| match it.name() {
+        Some(name) => SyntaxNodePtr::new(name.syntax()),
+        None => SyntaxNodePtr::new(it.syntax()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn redundant_items() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn f();
+}
+struct S;
+impl Trait for S {
+    fn f() {}
+    fn g() {}
+     //^ error: method `g` is not a member of trait `Trait`
+    const C: u8 = 0;
+        //^ error: const `C` is not a member of trait `Trait`
+    type T = ();
+       //^ error: type `T` is not a member of trait `Trait`
+}
+"#,
+        );
+    }
+}
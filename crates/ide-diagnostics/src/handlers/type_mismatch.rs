@@ -206,6 +206,7 @@ fn main() {
        //^^^ 💡 error: expected &i32, found i32
 }
 fn test(arg: &i32) {}
+      //^^^ 💡 warn: unused variable `arg`
 "#,
         );
     }
@@ -217,13 +218,13 @@ fn test(arg: &i32) {}
 fn main() {
     test(123$0);
 }
-fn test(arg: &i32) {}
+fn test(_arg: &i32) {}
             "#,
             r#"
 fn main() {
     test(&123);
 }
-fn test(arg: &i32) {}
+fn test(_arg: &i32) {}
             "#,
         );
     }
@@ -235,13 +236,13 @@ fn test(arg: &i32) {}
 fn main() {
     test($0123);
 }
-fn test(arg: &mut i32) {}
+fn test(_arg: &mut i32) {}
             "#,
             r#"
 fn main() {
     test(&mut 123);
 }
-fn test(arg: &mut i32) {}
+fn test(_arg: &mut i32) {}
             "#,
         );
     }
@@ -254,13 +255,13 @@ fn test(arg: &mut i32) {}
 fn main() {
     test($0[1, 2, 3]);
 }
-fn test(arg: &[i32]) {}
+fn test(_arg: &[i32]) {}
             "#,
             r#"
 fn main() {
     test(&[1, 2, 3]);
 }
-fn test(arg: &[i32]) {}
+fn test(_arg: &[i32]) {}
             "#,
         );
     }
@@ -274,24 +275,26 @@ struct Foo;
 struct Bar;
 impl core::ops::Deref for Foo {
     type Target = Bar;
+    fn deref(&self) -> &Bar { &Bar }
 }
 
 fn main() {
     test($0Foo);
 }
-fn test(arg: &Bar) {}
+fn test(_arg: &Bar) {}
             "#,
             r#"
 struct Foo;
 struct Bar;
 impl core::ops::Deref for Foo {
     type Target = Bar;
+    fn deref(&self) -> &Bar { &Bar }
 }
 
 fn main() {
     test(&Foo);
 }
-fn test(arg: &Bar) {}
+fn test(_arg: &Bar) {}
             "#,
         );
     }
@@ -305,7 +308,7 @@ fn main() {
 }
 struct Test;
 impl Test {
-    fn call_by_ref(&self, arg: &i32) {}
+    fn call_by_ref(&self, _arg: &i32) {}
 }
             "#,
             r#"
@@ -314,7 +317,7 @@ fn main() {
 }
 struct Test;
 impl Test {
-    fn call_by_ref(&self, arg: &i32) {}
+    fn call_by_ref(&self, _arg: &i32) {}
 }
             "#,
         );
@@ -412,6 +415,7 @@ fn div(x: i32, y: i32) -> Option<i32> {
                 loop {}
             }
             fn run(t: Rate<5>) {
+                 //^ 💡 warn: unused variable `t`
             }
             fn main() {
                 run(f()) // FIXME: remove this error
@@ -427,6 +431,7 @@ fn div(x: i32, y: i32) -> Option<i32> {
             r#"
             pub struct Rate<T, const NOM: u32, const DENOM: u32>(T);
             fn run(t: Rate<u32, 1, 1>) {
+                 //^ 💡 warn: unused variable `t`
             }
             fn main() {
                 run(Rate::<_, _, _>(5));
@@ -651,6 +656,7 @@ fn h() {
 struct X<T>(T);
 
 fn foo(x: X<Unknown>) {}
+     //^ 💡 warn: unused variable `x`
 fn test1() {
     // Unknown might be `i32`, so we should not emit type mismatch here.
     foo(X(42));
@@ -44,6 +44,7 @@ extern crate core;
 extern crate self as foo;
 struct Foo;
 use foo::Foo as Bar;
+  //^^^^^^^^^^^^^^^ 💡 warn: unused import
 "#,
         );
     }
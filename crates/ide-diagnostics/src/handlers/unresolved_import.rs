@@ -30,6 +30,7 @@ mod tests {
         check_diagnostics(
             r#"
 use does_exist;
+  //^^^^^^^^^^ 💡 warn: unused import
 use does_not_exist;
   //^^^^^^^^^^^^^^ error: unresolved import
 
@@ -44,6 +45,7 @@ mod does_exist {}
         check_diagnostics(
             r#"
 use does_exist::{Exists, DoesntExist};
+               //^^^^^^ 💡 warn: unused import
                        //^^^^^^^^^^^ error: unresolved import
 
 use {does_not_exist::*, does_exist};
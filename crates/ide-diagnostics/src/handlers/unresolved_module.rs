@@ -165,6 +165,7 @@ mod baz {}
                                 ,
                             },
                         ),
+                        related: [],
                     },
                 ]
             "#]],
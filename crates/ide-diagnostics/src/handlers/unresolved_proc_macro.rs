@@ -6,7 +6,8 @@ use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext, Severity};
 //
 // This diagnostic is shown when a procedural macro can not be found. This usually means that
 // procedural macro support is simply disabled (and hence is only a weak hint instead of an error),
-// but can also indicate project setup problems.
+// but can also indicate project setup problems. It is also shown when the proc-macro server
+// crashed while expanding the macro.
 //
 // If you are seeing a lot of "proc macro not expanded" warnings, you can add this option to the
 // `rust-analyzer.diagnostics.disabled` list to prevent them from showing. Alternatively you can
@@ -29,9 +30,13 @@ pub(crate) fn unresolved_proc_macro(
         Some(name) => format!("proc macro `{name}` not expanded"),
         None => "proc macro not expanded".to_string(),
     };
-    let severity = if config_enabled { Severity::Error } else { Severity::WeakWarning };
+    // A crash means the macro was expanded after all.
+    let severity =
+        if config_enabled || d.crash.is_some() { Severity::Error } else { Severity::WeakWarning };
     let def_map = ctx.sema.db.crate_def_map(d.krate);
-    let message = if config_enabled {
+    let message = if let Some(crash) = &d.crash {
+        crash
+    } else if config_enabled {
         def_map.proc_macro_loading_error().unwrap_or("proc macro not found in the built dylib")
     } else {
         match d.kind {
@@ -43,3 +48,23 @@ pub(crate) fn unresolved_proc_macro(
 
     Diagnostic::new(DiagnosticCode::Ra("unresolved-proc-macro", severity), message, display_range)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn server_crash() {
+        check_diagnostics(
+            r#"
+//- proc_macros: server_crash
+proc_macros::server_crash!();
+           //^^^^^^^^^^^^ error: proc macro `server_crash` not expanded: proc-macro server crashed while expanding `server_crash` and has been restarted
+fn f() {
+    proc_macros::server_crash!();
+               //^^^^^^^^^^^^ error: proc macro `server_crash` not expanded: proc-macro server crashed while expanding `server_crash` and has been restarted
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use hir::{GenericDef, HirDisplay};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unsatisfied-trait-bound
+//
+// This diagnostic is triggered when a call requires a trait bound that does not hold.
+pub(crate) fn unsatisfied_trait_bound(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnsatisfiedTraitBound,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let mut diag = Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0277"),
+        format!("the trait bound `{}` is not satisfied", d.trait_ref.display(db)),
+        d.call_expr.clone().map(|it| it.into()),
+    )
+    .experimental();
+    if let Some(bound) = &d.bound {
+        let callee = match d.callee {
+            GenericDef::Function(it) => Some(it.name(db)),
+            GenericDef::Adt(it) => Some(it.name(db)),
+            GenericDef::Variant(it) => Some(it.parent_enum(db).name(db)),
+            _ => None,
+        };
+        let message = match callee {
+            Some(name) => format!("required by this bound in `{}`", name.display(db)),
+            None => "required by this bound".to_owned(),
+        };
+        let range = ctx.sema.diagnostics_display_range(bound.clone().map(|it| it.into()));
+        diag = diag.with_related(range, message);
+    }
+    diag
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::{
+        assists::AssistResolveStrategy,
+        base_db::{fixture::WithFixture, SourceDatabaseExt},
+        RootDatabase,
+    };
+
+    use crate::{
+        tests::{check_diagnostics, check_expect},
+        DiagnosticsConfig,
+    };
+
+    /// Checks the bounds that the diagnostics of `ra_fixture` point at.
+    fn check_bounds(ra_fixture: &str, expect: Expect) {
+        let (db, file_id) = RootDatabase::with_single_file(ra_fixture);
+        let text = db.file_text(file_id);
+        let diagnostics = crate::diagnostics(
+            &db,
+            &DiagnosticsConfig::test_sample(),
+            &AssistResolveStrategy::All,
+            file_id,
+        );
+        let actual: String = diagnostics
+            .iter()
+            .flat_map(|it| &it.related)
+            .map(|(range, message)| {
+                let start = usize::from(range.range.start());
+                let column = start - text[..start].rfind('\n').map_or(0, |it| it + 1);
+                let line = text[start - column..].lines().next().unwrap_or_default();
+                let marker = " ".repeat(column) + &"^".repeat(range.range.len().into());
+                format!("{line}\n{marker} {message}\n")
+            })
+            .collect();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn unsatisfied_where_clause() {
+        check_diagnostics(
+            r#"
+trait Trait {}
+struct A;
+struct B;
+impl Trait for A {}
+
+fn foo<T: Trait>(_: T) {}
+fn bar<T>(_: T) where T: Trait {}
+struct S;
+impl S {
+    fn method<T>(&self, _: T) where T: Trait {}
+}
+struct W<T: Trait>(T);
+
+fn main() {
+    foo(A);
+    foo(B);
+  //^^^^^^ error: the trait bound `B: Trait` is not satisfied
+    bar(B);
+  //^^^^^^ error: the trait bound `B: Trait` is not satisfied
+    S.method(B);
+  //^^^^^^^^^^^ error: the trait bound `B: Trait` is not satisfied
+    W(B);
+  //^^^^ error: the trait bound `B: Trait` is not satisfied
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn bounds_on_generic_params() {
+        check_diagnostics(
+            r#"
+//- minicore: clone
+trait Trait {}
+fn needs_trait<T: Trait>(_: T) {}
+fn needs_clone<T: Clone>(_: T) {}
+
+fn generic<T: Trait, U>(t: T, u: U) {
+    needs_trait(t);
+    needs_clone(&u);
+    needs_trait(u);
+  //^^^^^^^^^^^^^^ error: the trait bound `U: Trait` is not satisfied
+}
+
+fn unknown() {
+    needs_trait(missing());
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        check_diagnostics(
+            r#"
+//- minicore: fn, iterator, option, clone, copy, deref, sized
+trait Trait {}
+impl<T: Trait> Trait for &T {}
+impl Trait for i32 {}
+
+fn call<F: FnOnce() -> i32>(f: F) -> i32 { f() }
+fn call_mut<F>(mut f: F) where F: FnMut(i32) { f(1) }
+fn needs_trait<T: Trait + ?Sized>(_: &T) {}
+fn sum<I: Iterator<Item = i32>>(_: I) {}
+fn assoc<I>(_: I) where I: Iterator, I::Item: Trait {}
+
+struct Wrapper<T>(T);
+impl<T> Iterator for Wrapper<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> { None }
+}
+
+fn main() {
+    let x = 5;
+    call(|| x + 1);
+    call_mut(|_| ());
+    needs_trait(&&1);
+    sum(Wrapper(1));
+    assoc(Wrapper(&1));
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn points_at_bound() {
+        check_expect(
+            r#"
+trait Trait {}
+fn foo<T>(_: T) where T: Trait {}
+fn main() {
+    foo(());
+}
+"#,
+            expect![[r#"
+                [
+                    Diagnostic {
+                        code: RustcHardError(
+                            "E0277",
+                        ),
+                        message: "the trait bound `(): Trait` is not satisfied",
+                        range: 65..72,
+                        severity: Error,
+                        unused: false,
+                        experimental: true,
+                        fixes: None,
+                        main_node: Some(
+                            InFile {
+                                file_id: FileId(
+                                    FileId(
+                                        0,
+                                    ),
+                                ),
+                                value: CALL_EXPR@65..72
+                                  PATH_EXPR@65..68
+                                    PATH@65..68
+                                      PATH_SEGMENT@65..68
+                                        NAME_REF@65..68
+                                          IDENT@65..68 "foo"
+                                  ARG_LIST@68..72
+                                    L_PAREN@68..69 "("
+                                    TUPLE_EXPR@69..71
+                                      L_PAREN@69..70 "("
+                                      R_PAREN@70..71 ")"
+                                    R_PAREN@71..72 ")"
+                                ,
+                            },
+                        ),
+                        related: [
+                            (
+                                FileRange {
+                                    file_id: FileId(
+                                        0,
+                                    ),
+                                    range: 40..45,
+                                },
+                                "required by this bound in `foo`",
+                            ),
+                        ],
+                    },
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn points_at_the_failed_one_of_similar_bounds() {
+        check_bounds(
+            r#"
+trait Trait<T> {}
+impl Trait<u32> for () {}
+
+fn foo<T>(_: T) where T: Trait<u32>, T: Trait<i64> {}
+struct S<T>(T);
+impl<T: Trait<u32>> S<T> {
+    fn method<U>(&self, _: U) where U: Trait<u32> {}
+}
+fn main() {
+    foo(());
+    S(()).method(0i64);
+}
+"#,
+            expect![[r#"
+                fn foo<T>(_: T) where T: Trait<u32>, T: Trait<i64> {}
+                                                        ^^^^^^^^^^ required by this bound in `foo`
+                    fn method<U>(&self, _: U) where U: Trait<u32> {}
+                                                       ^^^^^^^^^^ required by this bound in `method`
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Detects imports which are not used in the module that contains them.
+
+use hir::{InFile, Module};
+use ide_db::{base_db::FileId, source_change::SourceChangeBuilder, FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, edit_in_place::Removable, HasVisibility},
+    AstNode, SyntaxNode, TextRange,
+};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-imports
+//
+// This diagnostic is triggered when an import is not used by the module it is declared in or by
+// any of its submodules.
+pub(crate) fn unused_imports(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext<'_>,
+    file_id: FileId,
+) {
+    let mut unused = FxHashMap::default();
+    for use_item in ctx.sema.parse(file_id).syntax().descendants().filter_map(ast::Use::cast) {
+        unused_imports_in_use_item(acc, ctx, file_id, &mut unused, use_item);
+    }
+}
+
+fn unused_imports_in_use_item(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext<'_>,
+    file_id: FileId,
+    unused: &mut FxHashMap<Module, FxHashSet<TextRange>>,
+    use_item: ast::Use,
+) {
+    if use_item.visibility().is_some() {
+        // Re-exports are used by definition.
+        return;
+    }
+    let Some(use_tree) = use_item.use_tree() else { return };
+    let Some(module) = ctx.sema.scope(use_item.syntax()).map(|it| it.module()) else { return };
+    // The ranges of the unused use trees of the module in this file.
+    let unused_ranges = unused.entry(module).or_insert_with(|| {
+        module
+            .unused_imports(ctx.sema.db)
+            .into_iter()
+            .filter(|it| it.file_id == file_id.into())
+            .map(|it| it.value.syntax().text_range())
+            .collect()
+    });
+
+    let leaves: Vec<_> = leaf_use_trees(use_tree).collect();
+    let unused: Vec<_> = leaves
+        .iter()
+        .filter(|leaf| unused_ranges.contains(&leaf.syntax().text_range()))
+        .cloned()
+        .collect();
+    if unused.is_empty() {
+        return;
+    }
+    let fully_unused = leaves.len() == unused.len();
+
+    let mut diagnostic_for = |range: TextRange, main_node: &SyntaxNode, to_remove: Vec<_>| {
+        let fixes = {
+            let mut builder = SourceChangeBuilder::new(file_id);
+            for it in to_remove {
+                match it {
+                    RemovalTarget::Use(it) => builder.make_mut(it).remove(),
+                    RemovalTarget::UseTree(it) => builder.make_mut(it).remove(),
+                }
+            }
+            vec![fix("remove_unused_imports", "Remove unused import", builder.finish(), range)]
+        };
+        acc.push(
+            Diagnostic::new(DiagnosticCode::RustcLint("unused_imports"), "unused import", range)
+                .with_main_node(InFile::new(file_id.into(), main_node.clone()))
+                .with_unused(true)
+                .experimental() // Imports used only from doc links and derive expansions might be missed.
+                .with_fixes(Some(fixes)),
+        );
+    };
+    if fully_unused {
+        diagnostic_for(
+            use_item.use_tree().unwrap().syntax().text_range(),
+            use_item.syntax(),
+            vec![RemovalTarget::Use(use_item.clone())],
+        );
+        return;
+    }
+    // Only remove whole subtrees which become empty, so that we don't leave empty `{}` behind.
+    let targets: Vec<_> = unused.iter().map(|leaf| removal_target(leaf, &unused)).collect();
+    for (leaf, target) in unused.iter().zip(targets.iter()) {
+        diagnostic_for(
+            leaf.syntax().text_range(),
+            leaf.syntax(),
+            vec![RemovalTarget::UseTree(target.clone())],
+        );
+    }
+}
+
+enum RemovalTarget {
+    Use(ast::Use),
+    UseTree(ast::UseTree),
+}
+
+fn leaf_use_trees(use_tree: ast::UseTree) -> impl Iterator<Item = ast::UseTree> {
+    use_tree
+        .syntax()
+        .descendants()
+        .filter_map(ast::UseTree::cast)
+        .filter(|it| it.use_tree_list().is_none())
+}
+
+/// Returns the outermost use tree containing `leaf` whose leaves are all unused.
+fn removal_target(leaf: &ast::UseTree, unused: &[ast::UseTree]) -> ast::UseTree {
+    let mut target = leaf.clone();
+    while let Some(parent) =
+        target.syntax().parent().and_then(ast::UseTreeList::cast).map(|list| list.parent_use_tree())
+    {
+        if parent.syntax().parent().and_then(ast::UseTreeList::cast).is_none() {
+            // The root use tree is handled by removing the whole `use` item.
+            break;
+        }
+        if !leaf_use_trees(parent.clone()).all(|it| unused.contains(&it)) {
+            break;
+        }
+        target = parent;
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unused_import() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+use foo::Bar;
+  //^^^^^^^^ 💡 warn: unused import
+use foo::{Baz, Bar as Qux};
+             //^^^^^^^^^^ 💡 warn: unused import
+fn f(_: Baz) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_diagnostic_for_used_imports() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+    pub fn baz() {}
+    pub mod qux {
+        pub struct Quux;
+    }
+    #[macro_export]
+    macro_rules! m { () => {} }
+}
+use foo::{Bar, baz, qux};
+use qux::Quux;
+use crate::m;
+pub use foo::Bar as ReexportedBar;
+fn f(_: Bar, _: Quux) {
+    baz();
+    m!();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn qualified_usage_does_not_use_import() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+}
+use foo::Bar;
+  //^^^^^^^^ 💡 warn: unused import
+fn f(_: foo::Bar) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn import_of_declared_name() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+}
+mod baz {}
+use foo;
+use baz;
+  //^^^ 💡 warn: unused import
+fn f(_: foo::Bar) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn used_from_child_module() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+use foo::{Bar, Baz};
+mod tests {
+    use super::*;
+    fn f(_: Bar) {}
+}
+mod tests2 {
+    use super::Baz;
+    fn f(_: Baz) {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_imports() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub trait Tr {
+        fn method(&self) {}
+    }
+    impl Tr for () {}
+    pub trait Unused {
+        fn unused_method(&self) {}
+    }
+}
+use foo::Tr as _;
+use foo::Unused as _;
+  //^^^^^^^^^^^^^^^^ 💡 warn: unused import
+fn f() {
+    ().method();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn used_from_macro_expansion() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+use foo::{Bar, Baz};
+             //^^^ 💡 warn: unused import
+macro_rules! m {
+    ($t:ty) => { fn f(_: $t) {} };
+}
+m!(Bar);
+"#,
+        );
+    }
+
+    #[test]
+    fn respect_lint_attributes() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Bar;
+}
+#[allow(unused_imports)]
+use foo::Bar;
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_remove_use_item() {
+        check_fix(
+            r#"
+mod foo {
+    pub struct Bar;
+}
+use foo::Bar$0;
+fn f() {}
+"#,
+            r#"
+mod foo {
+    pub struct Bar;
+}
+fn f() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_remove_use_tree() {
+        check_fix(
+            r#"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod qux {
+        pub struct Quux;
+        pub struct Quuz;
+    }
+}
+use foo::{Bar, qux::{Quux, Quuz$0}, Baz};
+fn f(_: Bar, _: Baz) {}
+"#,
+            r#"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod qux {
+        pub struct Quux;
+        pub struct Quuz;
+    }
+}
+use foo::{Bar, Baz};
+fn f(_: Bar, _: Baz) {}
+"#,
+        );
+    }
+}
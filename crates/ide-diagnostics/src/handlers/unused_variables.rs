@@ -0,0 +1,223 @@
+//! Detects local variables which are never read.
+
+use hir::InFile;
+use ide_db::source_change::SourceChange;
+use syntax::{ast, AstNode, SyntaxNodePtr};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-variables
+//
+// This diagnostic is triggered when a local variable is never read.
+pub(crate) fn unused_variables(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnusedVariable,
+) -> Diagnostic {
+    let source = d.local.primary_source(ctx.sema.db);
+    let ast = match source.name() {
+        Some(name) => name.map(|it| SyntaxNodePtr::new(it.syntax())),
+        None => source.syntax_ptr(),
+    };
+    let fixes = (|| {
+        let file_id = ast.file_id.file_id()?;
+        let mut edit_builder = TextEdit::builder();
+        for source in d.local.sources(ctx.sema.db) {
+            let Some(InFile { value: name, .. }) = source.name() else { continue };
+            let is_shorthand_field = source
+                .as_ident_pat()
+                .and_then(|pat| pat.syntax().parent())
+                .and_then(ast::RecordPatField::cast)
+                .map_or(false, |field| field.name_ref().is_none());
+            let insert =
+                if is_shorthand_field { format!("{}: _", name.text()) } else { "_".to_owned() };
+            edit_builder.insert(name.syntax().text_range().start(), insert);
+        }
+        let edit = edit_builder.finish();
+        Some(vec![fix(
+            "unused_variable",
+            "Insert `_` prefix to fix unused variable",
+            SourceChange::from_text_edit(file_id, edit),
+            ast.value.text_range(),
+        )])
+    })();
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcLint("unused_variables"),
+        format!("unused variable `{}`", d.local.name(ctx.sema.db).display(ctx.sema.db)),
+        ast,
+    )
+    .with_unused(true)
+    .experimental() // MIR lowering is not complete, so some usages might be missed.
+    .with_fixes(fixes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unused_variables_simple() {
+        check_diagnostics(
+            r#"
+//- minicore: fn
+struct Foo { f1: i32, f2: i64 }
+
+fn f(kkk: i32) {}
+   //^^^ 💡 warn: unused variable `kkk`
+fn main() {
+    let a = 2;
+      //^ 💡 warn: unused variable `a`
+    let b = 5;
+    let mut c = f(b);
+      //^^^^^ 💡 warn: variable does not need to be mutable
+          //^ 💡 warn: unused variable `c`
+    let (d, e) = (3, 5);
+       //^ 💡 warn: unused variable `d`
+    let _ = e;
+    let f1 = 2;
+    let f2 = 5;
+    let f = Foo { f1, f2 };
+    match f {
+        Foo { f1, f2 } => {
+                //^^ 💡 warn: unused variable `f2`
+            let _ = f1;
+        }
+    }
+    let g = false;
+    if g {}
+    let h: fn() -> i32 = || 2;
+    let i = h();
+      //^ 💡 warn: unused variable `i`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unused_self() {
+        check_diagnostics(
+            r#"
+struct S {
+}
+impl S {
+    fn owned_self(self, u: i32) {}
+                      //^ 💡 warn: unused variable `u`
+    fn ref_self(&self, u: i32) {}
+                     //^ 💡 warn: unused variable `u`
+    fn ref_mut_self(&mut self, u: i32) {}
+                             //^ 💡 warn: unused variable `u`
+    fn owned_mut_self(mut self) {}
+                    //^^^^^^^^ 💡 warn: variable does not need to be mutable
+
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn allow_unused_variables_for_identifiers_starting_with_underline() {
+        check_diagnostics(
+            r#"
+fn main() {
+    let _x = 2;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn respect_lint_attributes_for_unused_variables() {
+        check_diagnostics(
+            r#"
+fn main() {
+    #[allow(unused_variables)]
+    let x = 2;
+}
+
+#[deny(unused)]
+fn main2() {
+    let x = 2;
+      //^ 💡 error: unused variable `x`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn usages_through_places_and_closures() {
+        check_diagnostics(
+            r#"
+//- minicore: fn
+fn main() {
+    let a = [1, 2, 3];
+    let i = 1;
+    let _ = a[i];
+    let b = 2;
+    let c = || b;
+    c();
+    let d = 5;
+    d;
+    let e = Some(2);
+    match e {
+        _ => (),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_unused_variable() {
+        check_fix(
+            r#"
+fn main() {
+    let x$0 = 2;
+}
+"#,
+            r#"
+fn main() {
+    let _x = 2;
+}
+"#,
+        );
+        check_fix(
+            r#"
+fn main() {
+    let (x$0, y) = (2, 5);
+    let _ = y;
+}
+"#,
+            r#"
+fn main() {
+    let (_x, y) = (2, 5);
+    let _ = y;
+}
+"#,
+        );
+        check_fix(
+            r#"
+struct Foo { f1: i32, f2: i64 }
+fn main() {
+    let f = Foo { f1: 0, f2: 0 };
+    match f {
+        Foo { f1$0, f2 } => {
+            let _ = f2;
+        }
+    }
+}
+"#,
+            r#"
+struct Foo { f1: i32, f2: i64 }
+fn main() {
+    let f = Foo { f1: 0, f2: 0 };
+    match f {
+        Foo { f1: _f1, f2 } => {
+            let _ = f2;
+        }
+    }
+}
+"#,
+        );
+    }
+}
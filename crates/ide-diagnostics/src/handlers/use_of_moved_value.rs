@@ -0,0 +1,108 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: use-of-moved-value
+//
+// This diagnostic is triggered when a variable is used after its value might have been moved out.
+pub(crate) fn use_of_moved_value(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UseOfMovedValue,
+) -> Diagnostic {
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0382"),
+        format!("use of moved value: `{}`", d.local.name(ctx.sema.db).display(ctx.sema.db)),
+        d.span.clone(),
+    )
+    .experimental() // only moves of whole variables are tracked
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn use_after_move() {
+        check_diagnostics(
+            r#"
+struct S;
+fn consume(_: S) {}
+fn borrow(_: &S) {}
+fn main() {
+    let a = S;
+    consume(a);
+    borrow(&a);
+         //^^ error: use of moved value: `a`
+    let b = S;
+    let c = b;
+      //^ 💡 warn: unused variable `c`
+    consume(b);
+  //^^^^^^^^^^ error: use of moved value: `b`
+    let d = S;
+    if true {
+        consume(d);
+    }
+    consume(d);
+  //^^^^^^^^^^ error: use of moved value: `d`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn moved_in_loop() {
+        check_diagnostics(
+            r#"
+struct S;
+fn consume(_: S) {}
+fn main() {
+    let a = S;
+    loop {
+        consume(a);
+      //^^^^^^^^^^ error: use of moved value: `a`
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, option
+struct C;
+impl Clone for C {
+    fn clone(&self) -> Self { C }
+}
+impl Copy for C {}
+struct S;
+fn consume<T>(_: T) {}
+fn main() {
+    let c = C;
+    consume(c);
+    consume(c);
+    let mut a = S;
+    consume(a);
+    a = S;
+    consume(a);
+    let b = S;
+    if true {
+        consume(b);
+    } else {
+        let r = &b;
+          //^ 💡 warn: unused variable `r`
+    }
+    let s = Some(S);
+    match s {
+        Some(x) if false => consume(x),
+        Some(y) => consume(y),
+        None => {}
+    }
+    let t = (S, S);
+    consume(t.0);
+    consume(t.1);
+}
+"#,
+        );
+    }
+}
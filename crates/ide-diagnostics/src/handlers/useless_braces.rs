@@ -64,6 +64,7 @@ mod tests {
             r#"
 use a;
 use a::{c, d::e};
+  //^^^^^^^^^^^^ 💡 warn: unused import
 
 mod a {
     pub mod c {}
@@ -76,6 +77,7 @@ mod a {
         check_diagnostics(
             r#"
 use a;
+#[allow(unused_imports)]
 use a::{
     c,
     // d::e
@@ -92,6 +94,7 @@ mod a {
         check_diagnostics(
             r#"
 use a::{self};
+  //^^^^^^^^^ 💡 warn: unused import
 
 mod a {
 }
@@ -100,6 +103,7 @@ mod a {
         check_diagnostics(
             r#"
 use a::{self as cool_name};
+  //^^^^^^^^^^^^^^^^^^^^^^ 💡 warn: unused import
 
 mod a {
 }
@@ -118,41 +122,41 @@ use a::{b::self};
         check_fix(
             r#"
 mod b {}
-use {$0b};
+pub use {$0b};
 "#,
             r#"
 mod b {}
-use b;
+pub use b;
 "#,
         );
         check_fix(
             r#"
 mod b {}
-use {b$0};
+pub use {b$0};
 "#,
             r#"
 mod b {}
-use b;
+pub use b;
 "#,
         );
         check_fix(
             r#"
 mod a { pub mod c {} }
-use a::{c$0};
+pub use a::{c$0};
 "#,
             r#"
 mod a { pub mod c {} }
-use a::c;
+pub use a::c;
 "#,
         );
         check_fix(
             r#"
 mod a { pub mod c {} pub mod d { pub mod e {} } }
-use a::{c, d::{e$0}};
+pub use a::{c, d::{e$0}};
 "#,
             r#"
 mod a { pub mod c {} pub mod d { pub mod e {} } }
-use a::{c, d::e};
+pub use a::{c, d::e};
 "#,
         );
     }
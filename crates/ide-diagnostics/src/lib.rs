@@ -26,6 +26,7 @@
 #![warn(rust_2018_idioms, unused_lifetimes, semicolon_in_expressions_from_macros)]
 
 mod handlers {
+    pub(crate) mod borrow_errors;
     pub(crate) mod break_outside_of_loop;
     pub(crate) mod expected_function;
     pub(crate) mod inactive_code;
@@ -43,7 +44,13 @@ mod handlers {
     pub(crate) mod no_such_field;
     pub(crate) mod private_assoc_item;
     pub(crate) mod private_field;
+    pub(crate) mod proc_macro_diagnostic;
     pub(crate) mod replace_filter_map_next_with_find_map;
+    pub(crate) mod trait_impl_incorrect_signature;
+    pub(crate) mod trait_impl_missing_assoc_item;
+    pub(crate) mod trait_impl_orphan;
+    pub(crate) mod trait_impl_overlap;
+    pub(crate) mod trait_impl_redundant_assoc_item;
     pub(crate) mod typed_hole;
     pub(crate) mod type_mismatch;
     pub(crate) mod unimplemented_builtin_macro;
@@ -54,6 +61,9 @@ mod handlers {
     pub(crate) mod unresolved_macro_call;
     pub(crate) mod unresolved_module;
     pub(crate) mod unresolved_proc_macro;
+    pub(crate) mod unsatisfied_trait_bound;
+    pub(crate) mod unused_variables;
+    pub(crate) mod use_of_moved_value;
     pub(crate) mod undeclared_label;
     pub(crate) mod unreachable_label;
 
@@ -62,6 +72,7 @@ mod handlers {
     pub(crate) mod useless_braces;
     pub(crate) mod unlinked_file;
     pub(crate) mod json_is_not_rust;
+    pub(crate) mod unused_imports;
 }
 
 #[cfg(test)]
@@ -136,6 +147,8 @@ pub struct Diagnostic {
     pub fixes: Option<Vec<Assist>>,
     // The node that will be affected by `#[allow]` and similar attributes.
     pub main_node: Option<InFile<SyntaxNode>>,
+    /// Other locations relevant to the diagnostic, with a message explaining each.
+    pub related: Vec<(FileRange, String)>,
 }
 
 impl Diagnostic {
@@ -158,6 +171,7 @@ impl Diagnostic {
             experimental: false,
             fixes: None,
             main_node: None,
+            related: Vec::new(),
         }
     }
 
@@ -191,6 +205,11 @@ impl Diagnostic {
         self.unused = unused;
         self
     }
+
+    fn with_related(mut self, range: FileRange, message: impl Into<String>) -> Diagnostic {
+        self.related.push((range, message.into()));
+        self
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -311,6 +330,8 @@ pub fn diagnostics(
     let ctx = DiagnosticsContext { config, sema, resolve };
     if module.is_none() {
         handlers::unlinked_file::unlinked_file(&ctx, &mut res, file_id);
+    } else {
+        handlers::unused_imports::unused_imports(&mut res, &ctx, file_id);
     }
 
     let mut diags = Vec::new();
@@ -321,6 +342,8 @@ pub fn diagnostics(
     for diag in diags {
         #[rustfmt::skip]
         let d = match diag {
+            AnyDiagnostic::AssignToBorrowed(d) => handlers::borrow_errors::assign_to_borrowed(&ctx, &d),
+            AnyDiagnostic::ConflictingBorrow(d) => handlers::borrow_errors::conflicting_borrow(&ctx, &d),
             AnyDiagnostic::ExpectedFunction(d) => handlers::expected_function::expected_function(&ctx, &d),
             AnyDiagnostic::InactiveCode(d) => match handlers::inactive_code::inactive_code(&ctx, &d) {
                 Some(it) => it,
@@ -354,7 +377,13 @@ pub fn diagnostics(
             AnyDiagnostic::NoSuchField(d) => handlers::no_such_field::no_such_field(&ctx, &d),
             AnyDiagnostic::PrivateAssocItem(d) => handlers::private_assoc_item::private_assoc_item(&ctx, &d),
             AnyDiagnostic::PrivateField(d) => handlers::private_field::private_field(&ctx, &d),
+            AnyDiagnostic::ProcMacroDiagnostic(d) => handlers::proc_macro_diagnostic::proc_macro_diagnostic(&ctx, &d),
             AnyDiagnostic::ReplaceFilterMapNextWithFindMap(d) => handlers::replace_filter_map_next_with_find_map::replace_filter_map_next_with_find_map(&ctx, &d),
+            AnyDiagnostic::TraitImplIncorrectSignature(d) => handlers::trait_impl_incorrect_signature::trait_impl_incorrect_signature(&ctx, &d),
+            AnyDiagnostic::TraitImplMissingAssocItems(d) => handlers::trait_impl_missing_assoc_item::trait_impl_missing_assoc_item(&ctx, &d),
+            AnyDiagnostic::TraitImplOrphan(d) => handlers::trait_impl_orphan::trait_impl_orphan(&ctx, &d),
+            AnyDiagnostic::TraitImplOverlap(d) => handlers::trait_impl_overlap::trait_impl_overlap(&ctx, &d),
+            AnyDiagnostic::TraitImplRedundantAssocItems(d) => handlers::trait_impl_redundant_assoc_item::trait_impl_redundant_assoc_item(&ctx, &d),
             AnyDiagnostic::TypedHole(d) => handlers::typed_hole::typed_hole(&ctx, &d),
             AnyDiagnostic::TypeMismatch(d) => handlers::type_mismatch::type_mismatch(&ctx, &d),
             AnyDiagnostic::UndeclaredLabel(d) => handlers::undeclared_label::undeclared_label(&ctx, &d),
@@ -367,7 +396,10 @@ pub fn diagnostics(
             AnyDiagnostic::UnresolvedMethodCall(d) => handlers::unresolved_method::unresolved_method(&ctx, &d),
             AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
             AnyDiagnostic::UnresolvedProcMacro(d) => handlers::unresolved_proc_macro::unresolved_proc_macro(&ctx, &d, config.proc_macros_enabled, config.proc_attr_macros_enabled),
+            AnyDiagnostic::UnsatisfiedTraitBound(d) => handlers::unsatisfied_trait_bound::unsatisfied_trait_bound(&ctx, &d),
             AnyDiagnostic::UnusedMut(d) => handlers::mutability_errors::unused_mut(&ctx, &d),
+            AnyDiagnostic::UnusedVariable(d) => handlers::unused_variables::unused_variables(&ctx, &d),
+            AnyDiagnostic::UseOfMovedValue(d) => handlers::use_of_moved_value::use_of_moved_value(&ctx, &d),
             AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
         };
         res.push(d)
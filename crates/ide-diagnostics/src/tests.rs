@@ -4,7 +4,7 @@ mod sourcegen;
 use expect_test::Expect;
 use ide_db::{
     assists::AssistResolveStrategy,
-    base_db::{fixture::WithFixture, SourceDatabaseExt},
+    base_db::{fixture::WithFixture, FileId, SourceDatabaseExt},
     RootDatabase,
 };
 use stdx::trim_indent;
@@ -96,11 +96,26 @@ pub(crate) fn check_diagnostics(ra_fixture: &str) {
     check_diagnostics_with_config(config, ra_fixture)
 }
 
+/// Like [`check_diagnostics`], but with attribute proc-macros being expanded.
+#[track_caller]
+pub(crate) fn check_diagnostics_with_proc_attr_macros(ra_fixture: &str) {
+    let mut config = DiagnosticsConfig::test_sample();
+    config.disabled.insert("inactive-code".to_string());
+    let (mut db, files) = RootDatabase::with_many_files(ra_fixture);
+    db.enable_proc_attr_macros();
+    check_diagnostics_in_files(&db, config, files)
+}
+
 #[track_caller]
 pub(crate) fn check_diagnostics_with_config(config: DiagnosticsConfig, ra_fixture: &str) {
     let (db, files) = RootDatabase::with_many_files(ra_fixture);
+    check_diagnostics_in_files(&db, config, files)
+}
+
+#[track_caller]
+fn check_diagnostics_in_files(db: &RootDatabase, config: DiagnosticsConfig, files: Vec<FileId>) {
     for file_id in files {
-        let diagnostics = super::diagnostics(&db, &config, &AssistResolveStrategy::All, file_id);
+        let diagnostics = super::diagnostics(db, &config, &AssistResolveStrategy::All, file_id);
 
         let expected = extract_annotations(&db.file_text(file_id));
         let mut actual = diagnostics
@@ -167,6 +182,10 @@ fn minicore_smoke_test() {
         let mut config = DiagnosticsConfig::test_sample();
         // This should be ignored since we conditionaly remove code which creates single item use with braces
         config.disabled.insert("unused_braces".to_string());
+        // Minicore has many stub functions, which don't use their parameters, and imports used
+        // by flags which might be disabled.
+        config.disabled.insert("unused_variables".to_string());
+        config.disabled.insert("unused_imports".to_string());
         check_diagnostics_with_config(config, &source);
     }
 
@@ -36,12 +36,32 @@
 // Supported constraints:
 //
 // |===
-// | Constraint    | Restricts placeholder
+// | Constraint     | Restricts placeholder
 //
-// | kind(literal) | Is a literal (e.g. `42` or `"forty two"`)
-// | not(a)        | Negates the constraint `a`
+// | kind(literal)  | Is a literal (e.g. `42` or `"forty two"`)
+// | kind(expr)     | Is an expression
+// | kind(path)     | Is a path (e.g. `foo::Bar`)
+// | kind(pat)      | Is a pattern
+// | kind(type)     | Is a type
+// | kind(item)     | Is an item
+// | type(T)        | Is an expression or pattern of type `T`, where `_` stands for any type (e.g. `type(Option<_>)`)
+// | impls(Trait)   | Is an expression or pattern whose type implements `Trait` (e.g. `impls(Into<String>)`)
+// | not(a)         | Negates the constraint `a`
 // |===
 //
+// Types and traits in constraints are resolved in the same context as paths in the search pattern.
+// For example, `${x:type(Option<_>)}.unwrap() ==>> $x.expect("no value")` only rewrites `unwrap`
+// calls on options.
+//
+// A search pattern can also be a sequence of statements, like `let $a = $b.lock(); $c`, which
+// matches consecutive statements of a block. An expression at the end of such a pattern also
+// matches an expression statement, whose semicolon is then kept.
+//
+// Variadic placeholders, written as `$..<name>`, match any number of arguments or statements. For
+// example `foo($a, $..rest) ==>> bar($..rest)` drops the first argument, while
+// `let $a = lock(); $..rest ==>> with_lock(|$a| { $..rest })` moves the rest of a block into a
+// closure. There can be at most one of them in each argument or statement list.
+//
 // Available via the command `rust-analyzer.ssr`.
 //
 // ```rust
@@ -76,13 +96,19 @@ mod parsing;
 mod fragments;
 mod replacing;
 mod resolving;
+mod rule_file;
 mod search;
 #[macro_use]
 mod errors;
 #[cfg(test)]
 mod tests;
 
-pub use crate::{errors::SsrError, from_comment::ssr_from_comment, matching::Match};
+pub use crate::{
+    errors::SsrError,
+    from_comment::ssr_from_comment,
+    matching::Match,
+    rule_file::{parse_rule_file, NamedSsrRule},
+};
 
 use crate::{errors::bail, matching::MatchFailureReason};
 use hir::Semantics;
@@ -93,7 +119,7 @@ use syntax::{ast, AstNode, SyntaxNode, TextRange};
 use text_edit::TextEdit;
 
 // A structured search replace rule. Create by calling `parse` on a str.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SsrRule {
     /// A structured pattern that we're searching for.
     pattern: parsing::RawPattern,
@@ -343,7 +369,17 @@ impl SsrMatches {
 
 impl Match {
     pub fn matched_text(&self) -> String {
-        self.matched_node.text().to_string()
+        match (&self.last_matched_node, self.matched_node.parent()) {
+            (Some(last), Some(parent)) => {
+                let range = self.matched_node.text_range().cover(last.text_range());
+                parent.text().slice(range - parent.text_range().start()).to_string()
+            }
+            _ => self.matched_node.text().to_string(),
+        }
+    }
+
+    pub fn file_range(&self) -> FileRange {
+        self.range
     }
 }
 
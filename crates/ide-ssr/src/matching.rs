@@ -3,7 +3,7 @@
 
 use crate::{
     parsing::{Constraint, NodeKind, Placeholder, Var},
-    resolving::{ResolvedPattern, ResolvedRule, UfcsCallInfo},
+    resolving::{ResolvedConstraint, ResolvedPattern, ResolvedRule, UfcsCallInfo},
     SsrMatches,
 };
 use hir::Semantics;
@@ -11,7 +11,8 @@ use ide_db::{base_db::FileRange, FxHashMap};
 use std::{cell::Cell, iter::Peekable};
 use syntax::{
     ast::{self, AstNode, AstToken},
-    SmolStr, SyntaxElement, SyntaxElementChildren, SyntaxKind, SyntaxNode, SyntaxToken,
+    SmolStr, SyntaxElement, SyntaxElementChildren, SyntaxKind, SyntaxNode, SyntaxToken, TextRange,
+    TextSize,
 };
 
 // Creates a match error. If we're currently attempting to match some code that we thought we were
@@ -47,6 +48,9 @@ macro_rules! fail_match {
 pub struct Match {
     pub(crate) range: FileRange,
     pub(crate) matched_node: SyntaxNode,
+    /// For matches of a sequence of statements, the last node that was matched. `matched_node` is
+    /// the first statement then.
+    pub(crate) last_matched_node: Option<SyntaxNode>,
     pub(crate) placeholder_values: FxHashMap<Var, PlaceholderMatch>,
     pub(crate) ignored_comments: Vec<ast::Comment>,
     pub(crate) rule_index: usize,
@@ -96,6 +100,21 @@ pub(crate) fn get_match(
     })
 }
 
+/// Checks if the statements of the statement list `code`, starting at its `start`th statement,
+/// match a search pattern that is a sequence of statements.
+pub(crate) fn get_sequence_match(
+    debug_active: bool,
+    rule: &ResolvedRule,
+    code: &SyntaxNode,
+    start: usize,
+    restrict_range: &Option<FileRange>,
+    sema: &Semantics<'_, ide_db::RootDatabase>,
+) -> Result<Match, MatchFailed> {
+    record_match_fails_reasons_scope(debug_active, || {
+        Matcher::try_match_sequence(rule, code, start, restrict_range, sema)
+    })
+}
+
 /// Checks if our search pattern matches a particular node of the AST.
 struct Matcher<'db, 'sema> {
     sema: &'sema Semantics<'db, ide_db::RootDatabase>,
@@ -129,6 +148,7 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         let mut the_match = Match {
             range: sema.original_range(code),
             matched_node: code.clone(),
+            last_matched_node: None,
             placeholder_values: FxHashMap::default(),
             ignored_comments: Vec::new(),
             rule_index: rule.index,
@@ -149,6 +169,71 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         Ok(the_match)
     }
 
+    fn try_match_sequence(
+        rule: &ResolvedRule,
+        code: &SyntaxNode,
+        start: usize,
+        restrict_range: &Option<FileRange>,
+        sema: &'sema Semantics<'db, ide_db::RootDatabase>,
+    ) -> Result<Match, MatchFailed> {
+        let match_state = Matcher { sema, restrict_range: *restrict_range, rule };
+        let pattern: Vec<_> = rule.pattern.node.children().collect();
+        let code_elements: Vec<_> = code.children().skip(start).collect();
+        let len = match_state.attempt_match_list(
+            &mut Phase::First,
+            &pattern,
+            &code_elements,
+            code,
+            false,
+        )?;
+        let (Some(first), Some(mut last)) =
+            (code_elements.first().cloned(), code_elements.get(len.wrapping_sub(1)).cloned())
+        else {
+            fail_match!("Pattern matched no statements");
+        };
+        // A trailing expression of the pattern matches an expression statement, but the semicolon
+        // of the statement isn't part of the match, so that it's kept.
+        if pattern.last().map_or(false, |it| {
+            ast::Expr::can_cast(it.kind()) && match_state.get_placeholder_for_node(it).is_none()
+        }) {
+            if let Some(expr) = ast::ExprStmt::cast(last.clone()).and_then(|it| it.expr()) {
+                last = expr.syntax().clone();
+            }
+        }
+        let first_range = sema.original_range(&first);
+        let last_range = sema.original_range(&last);
+        if first_range.file_id != last_range.file_id {
+            fail_match!("Statements come from different files");
+        }
+        let range = FileRange {
+            file_id: first_range.file_id,
+            range: first_range.range.cover(last_range.range),
+        };
+        match_state.validate_range(&range)?;
+        let mut the_match = Match {
+            range,
+            matched_node: first,
+            last_matched_node: Some(last),
+            placeholder_values: FxHashMap::default(),
+            ignored_comments: Vec::new(),
+            rule_index: rule.index,
+            depth: 0,
+            rendered_template_paths: FxHashMap::default(),
+        };
+        match_state.attempt_match_list(
+            &mut Phase::Second(&mut the_match),
+            &pattern,
+            &code_elements,
+            code,
+            false,
+        )?;
+        the_match.depth = sema.ancestors_with_macros(the_match.matched_node.clone()).count();
+        if let Some(template) = &rule.template {
+            the_match.render_template_paths(template, sema)?;
+        }
+        Ok(the_match)
+    }
+
     /// Checks that `range` is within the permitted range if any. This is applicable when we're
     /// processing a macro expansion and we want to fail the match if we're working with a node that
     /// didn't originate from the token tree of the macro call.
@@ -172,7 +257,10 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         // Handle placeholders.
         if let Some(placeholder) = self.get_placeholder_for_node(pattern) {
             for constraint in &placeholder.constraints {
-                self.check_constraint(constraint, code)?;
+                // Constraints that need types are only checked once the code matched otherwise.
+                if matches!(phase, Phase::Second(_)) || !constraint.is_semantic() {
+                    self.check_constraint(constraint, code)?;
+                }
             }
             if let Phase::Second(matches_out) = phase {
                 let original_range = self.sema.original_range(code);
@@ -212,6 +300,12 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
             }
             SyntaxKind::TOKEN_TREE => self.attempt_match_token_tree(phase, pattern, code),
             SyntaxKind::PATH => self.attempt_match_path(phase, pattern, code),
+            SyntaxKind::ARG_LIST | SyntaxKind::STMT_LIST if self.has_variadic(pattern) => {
+                let pattern_elements: Vec<_> = pattern.children().collect();
+                let code_elements: Vec<_> = code.children().collect();
+                self.attempt_match_list(phase, &pattern_elements, &code_elements, code, true)?;
+                Ok(())
+            }
             _ => self.attempt_match_node_children(phase, pattern, code),
         }
     }
@@ -258,6 +352,112 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         }
     }
 
+    /// Matches the elements of a list, such as arguments or statements. One of the elements of the
+    /// pattern may be a variadic placeholder, which matches any number of elements: all remaining
+    /// ones if it's the last element of the pattern, otherwise as few as possible. Unless
+    /// `match_all` is set, the code may have more elements after the ones that matched. Returns how
+    /// many elements of the code were matched.
+    fn attempt_match_list(
+        &self,
+        phase: &mut Phase<'_>,
+        pattern: &[SyntaxNode],
+        code: &[SyntaxNode],
+        code_list: &SyntaxNode,
+        match_all: bool,
+    ) -> Result<usize, MatchFailed> {
+        let variadic =
+            pattern.iter().enumerate().find_map(|(idx, p)| Some((idx, self.get_variadic(p)?)));
+        let Some((variadic_idx, placeholder)) = variadic else {
+            if code.len() < pattern.len() || (match_all && code.len() > pattern.len()) {
+                fail_match!("Pattern has {} elements, code has {}", pattern.len(), code.len());
+            }
+            self.attempt_match_list_elements(phase, pattern, code)?;
+            let span = if match_all { Some(code_list.text_range()) } else { cover(code) };
+            phase.record_list_comments(code_list, span, None);
+            return Ok(pattern.len());
+        };
+        let (prefix, suffix) = (&pattern[..variadic_idx], &pattern[variadic_idx + 1..]);
+        let Some(max_len) = code.len().checked_sub(prefix.len() + suffix.len()) else {
+            fail_match!(
+                "Pattern has at least {} elements, code has {}",
+                prefix.len() + suffix.len(),
+                code.len()
+            );
+        };
+        let (prefix_code, rest) = code.split_at(prefix.len());
+        self.attempt_match_list_elements(phase, prefix, prefix_code)?;
+        let variadic_len = if match_all || suffix.is_empty() {
+            max_len
+        } else {
+            (0..=max_len)
+                .find(|&len| {
+                    self.attempt_match_list_elements(&mut Phase::First, suffix, &rest[len..])
+                        .is_ok()
+                })
+                .ok_or_else(|| {
+                    match_error!("No elements match the pattern after {}", placeholder.ident)
+                })?
+        };
+        let (variadic_code, suffix_code) = rest.split_at(variadic_len);
+        self.attempt_match_list_elements(phase, suffix, suffix_code)?;
+        let matched = prefix.len() + variadic_len + suffix.len();
+        if let Phase::Second(match_out) = phase {
+            let range = match (variadic_code.first(), variadic_code.last()) {
+                (Some(first), Some(last)) => {
+                    let first = self.sema.original_range(first);
+                    let last = self.sema.original_range(last);
+                    FileRange { file_id: first.file_id, range: first.range.cover(last.range) }
+                }
+                // An empty match is placed where its elements would have been.
+                _ => {
+                    let (file_id, offset) = match (suffix_code.first(), prefix_code.last()) {
+                        (Some(next), _) => {
+                            let next = self.sema.original_range(next);
+                            (next.file_id, next.range.start())
+                        }
+                        (None, Some(previous)) => {
+                            let previous = self.sema.original_range(previous);
+                            (previous.file_id, previous.range.end())
+                        }
+                        // Right after the opening parenthesis or brace.
+                        (None, None) => {
+                            let list = self.sema.original_range(code_list);
+                            (list.file_id, list.range.start() + TextSize::of('('))
+                        }
+                    };
+                    FileRange { file_id, range: TextRange::empty(offset) }
+                }
+            };
+            self.validate_range(&range)?;
+            match_out
+                .placeholder_values
+                .insert(placeholder.ident.clone(), PlaceholderMatch::from_range(range));
+        }
+        let span = if match_all { Some(code_list.text_range()) } else { cover(&code[..matched]) };
+        phase.record_list_comments(code_list, span, cover(variadic_code));
+        Ok(matched)
+    }
+
+    fn attempt_match_list_elements(
+        &self,
+        phase: &mut Phase<'_>,
+        pattern: &[SyntaxNode],
+        code: &[SyntaxNode],
+    ) -> Result<(), MatchFailed> {
+        for (p, c) in pattern.iter().zip(code) {
+            // An expression that ends a pattern's statements also matches an expression statement,
+            // so that `a(); b()` matches `a(); b();`.
+            if ast::Expr::can_cast(p.kind()) && self.get_placeholder_for_node(p).is_none() {
+                if let Some(expr) = ast::ExprStmt::cast(c.clone()).and_then(|it| it.expr()) {
+                    self.attempt_match_node(phase, p, expr.syntax())?;
+                    continue;
+                }
+            }
+            self.attempt_match_node(phase, p, c)?;
+        }
+        Ok(())
+    }
+
     fn attempt_match_token(
         &self,
         phase: &mut Phase<'_>,
@@ -315,6 +515,7 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         constraint: &Constraint,
         code: &SyntaxNode,
     ) -> Result<(), MatchFailed> {
+        use hir::HirDisplay;
         match constraint {
             Constraint::Kind(kind) => {
                 kind.matches(code)?;
@@ -324,10 +525,57 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
                     fail_match!("Constraint {:?} failed for '{}'", constraint, code.text());
                 }
             }
+            Constraint::Type(ty) => {
+                let Some(ResolvedConstraint::Type(expected)) =
+                    self.rule.resolved_constraints.get(ty.syntax())
+                else {
+                    fail_match!("Type `{}` of constraint wasn't resolved", ty);
+                };
+                let actual = self.type_of(code)?;
+                if !actual.could_unify_with(self.sema.db, expected) {
+                    fail_match!(
+                        "Code '{}' has type `{}`, not `{}`",
+                        code.text(),
+                        actual.display(self.sema.db),
+                        expected.display(self.sema.db)
+                    );
+                }
+            }
+            Constraint::Impls(path) => {
+                let Some(ResolvedConstraint::Impls { trait_, args }) =
+                    self.rule.resolved_constraints.get(path.syntax())
+                else {
+                    fail_match!("Trait `{}` of constraint wasn't resolved", path);
+                };
+                let actual = self.type_of(code)?;
+                if !actual.impls_trait(self.sema.db, *trait_, args) {
+                    fail_match!(
+                        "Type `{}` of code '{}' doesn't implement `{}`",
+                        actual.display(self.sema.db),
+                        code.text(),
+                        path
+                    );
+                }
+            }
         }
         Ok(())
     }
 
+    /// Returns the type of an expression or a pattern, before any adjustments.
+    fn type_of(&self, code: &SyntaxNode) -> Result<hir::Type, MatchFailed> {
+        let ty = if let Some(expr) = ast::Expr::cast(code.clone()) {
+            self.sema.type_of_expr(&expr)
+        } else if let Some(pat) = ast::Pat::cast(code.clone()) {
+            self.sema.type_of_pat(&pat)
+        } else {
+            None
+        };
+        match ty {
+            Some(ty) if !ty.original.is_unknown() => Ok(ty.original),
+            _ => fail_match!("Couldn't determine the type of '{}'", code.text()),
+        }
+    }
+
     /// Paths are matched based on whether they refer to the same thing, even if they're written
     /// differently.
     fn attempt_match_path(
@@ -569,14 +817,12 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         } else {
             self.attempt_match_opt(phase, pattern_args.next(), code.receiver())?;
         }
-        let mut code_args =
-            code.arg_list().ok_or_else(|| match_error!("Code method call has no args"))?.args();
-        loop {
-            match (pattern_args.next(), code_args.next()) {
-                (None, None) => return Ok(()),
-                (p, c) => self.attempt_match_opt(phase, p, c)?,
-            }
-        }
+        let code_arg_list =
+            code.arg_list().ok_or_else(|| match_error!("Code method call has no args"))?;
+        let pattern_args: Vec<_> = pattern_args.map(|it| it.syntax().clone()).collect();
+        let code_args: Vec<_> = code_arg_list.args().map(|it| it.syntax().clone()).collect();
+        self.attempt_match_list(phase, &pattern_args, &code_args, code_arg_list.syntax(), true)?;
+        Ok(())
     }
 
     fn attempt_match_ufcs_to_ufcs(
@@ -627,6 +873,20 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         res
     }
 
+    fn has_variadic(&self, pattern: &SyntaxNode) -> bool {
+        pattern.children().any(|it| self.get_variadic(&it).is_some())
+    }
+
+    /// Returns the variadic placeholder that the list element `node` consists of, if any. Within
+    /// statement lists, it may be followed by a semicolon.
+    fn get_variadic(&self, node: &SyntaxNode) -> Option<&Placeholder> {
+        let node = match ast::ExprStmt::cast(node.clone()).and_then(|it| it.expr()) {
+            Some(expr) => expr.syntax().clone(),
+            None => node.clone(),
+        };
+        self.get_placeholder_for_node(&node).filter(|it| it.variadic)
+    }
+
     fn get_placeholder_for_node(&self, node: &SyntaxNode) -> Option<&Placeholder> {
         self.get_placeholder(&SyntaxElement::Node(node.clone()))
     }
@@ -673,6 +933,27 @@ impl Phase<'_> {
         }
     }
 
+    /// Records the comments between the elements of `code_list` within `span`, except for those
+    /// that a variadic placeholder matched, which are kept along with the rest of its code.
+    fn record_list_comments(
+        &mut self,
+        code_list: &SyntaxNode,
+        span: Option<TextRange>,
+        variadic_span: Option<TextRange>,
+    ) {
+        let Some(span) = span else {
+            return;
+        };
+        for token in code_list.children_with_tokens().filter_map(|it| it.into_token()) {
+            let range = token.text_range();
+            if span.contains_range(range)
+                && !variadic_span.map_or(false, |it| it.contains_range(range))
+            {
+                self.record_ignored_comments(&token);
+            }
+        }
+    }
+
     fn record_ignored_comments(&mut self, token: &SyntaxToken) {
         if token.kind() == SyntaxKind::COMMENT {
             if let Phase::Second(match_out) = self {
@@ -684,6 +965,11 @@ impl Phase<'_> {
     }
 }
 
+/// Returns the range from the start of the first node to the end of the last one.
+fn cover(nodes: &[SyntaxNode]) -> Option<TextRange> {
+    Some(nodes.first()?.text_range().cover(nodes.last()?.text_range()))
+}
+
 fn is_closing_token(kind: SyntaxKind) -> bool {
     kind == SyntaxKind::R_PAREN || kind == SyntaxKind::R_CURLY || kind == SyntaxKind::R_BRACK
 }
@@ -723,11 +1009,27 @@ impl PlaceholderMatch {
 
 impl NodeKind {
     fn matches(&self, node: &SyntaxNode) -> Result<(), MatchFailed> {
+        // A placeholder that is a generic argument matches the whole argument, not just its type.
+        let node = match ast::TypeArg::cast(node.clone()).and_then(|it| it.ty()) {
+            Some(ty) => ty.syntax().clone(),
+            None => node.clone(),
+        };
         let ok = match self {
             Self::Literal => {
                 cov_mark::hit!(literal_constraint);
                 ast::Literal::can_cast(node.kind())
             }
+            Self::Expr => ast::Expr::can_cast(node.kind()),
+            Self::Path => matches!(
+                node.kind(),
+                SyntaxKind::PATH
+                    | SyntaxKind::PATH_EXPR
+                    | SyntaxKind::PATH_TYPE
+                    | SyntaxKind::PATH_PAT
+            ),
+            Self::Pat => ast::Pat::can_cast(node.kind()),
+            Self::Type => ast::Type::can_cast(node.kind()),
+            Self::Item => ast::Item::can_cast(node.kind()),
         };
         if !ok {
             fail_match!("Code '{}' isn't of kind {:?}", node.text(), self);
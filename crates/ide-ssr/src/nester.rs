@@ -31,6 +31,9 @@ pub(crate) fn nest_and_remove_collisions(
 #[derive(Default)]
 struct MatchCollector {
     matches_by_node: FxHashMap<SyntaxNode, Match>,
+    /// The keys of matches of statement sequences. These span several sibling nodes, so they can
+    /// overlap with other matches without either matched node being an ancestor of the other.
+    sequence_nodes: Vec<SyntaxNode>,
 }
 
 impl MatchCollector {
@@ -49,7 +52,31 @@ impl MatchCollector {
                 return;
             }
         }
-        self.matches_by_node.insert(matched_node, m);
+        let overlaps = |existing: &Match| {
+            existing.range.file_id == m.range.file_id
+                && existing.range.range.intersect(m.range.range).map_or(false, |it| !it.is_empty())
+        };
+        let overlapping = if m.last_matched_node.is_some() {
+            self.matches_by_node.iter().find(|(_, existing)| overlaps(existing)).map(|(it, _)| it)
+        } else {
+            self.sequence_nodes.iter().find(|it| overlaps(&self.matches_by_node[*it]))
+        };
+        if let Some(node) = overlapping.cloned() {
+            let existing = self.matches_by_node.get_mut(&node).unwrap();
+            // Matches that only partially overlap an existing one are dropped.
+            if existing.range.range.contains_range(m.range.range) {
+                try_add_sub_match(m, existing, sema);
+            }
+            return;
+        }
+        self.insert(m);
+    }
+
+    fn insert(&mut self, m: Match) {
+        if m.last_matched_node.is_some() {
+            self.sequence_nodes.push(m.matched_node.clone());
+        }
+        self.matches_by_node.insert(m.matched_node.clone(), m);
     }
 }
 
@@ -71,7 +98,7 @@ fn try_add_sub_match(
             // exceptional.
             let mut collector = MatchCollector::default();
             for m in std::mem::take(&mut p.inner_matches.matches) {
-                collector.matches_by_node.insert(m.matched_node.clone(), m);
+                collector.insert(m);
             }
             collector.add_match(m, sema);
             p.inner_matches = collector.into();
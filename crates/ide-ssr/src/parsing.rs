@@ -6,19 +6,19 @@
 //! e.g. expressions, type references etc.
 use ide_db::{FxHashMap, FxHashSet};
 use std::{fmt::Display, str::FromStr};
-use syntax::{SmolStr, SyntaxKind, SyntaxNode, T};
+use syntax::{ast, AstNode, SmolStr, SyntaxKind, SyntaxNode, T};
 
 use crate::errors::bail;
 use crate::{fragments, SsrError, SsrPattern, SsrRule};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct ParsedRule {
     pub(crate) placeholders_by_stand_in: FxHashMap<SmolStr, Placeholder>,
     pub(crate) pattern: SyntaxNode,
     pub(crate) template: Option<SyntaxNode>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct RawPattern {
     tokens: Vec<PatternElement>,
 }
@@ -37,6 +37,9 @@ pub(crate) struct Placeholder {
     /// A unique name used in place of this placeholder when we parse the pattern as Rust code.
     stand_in_name: String,
     pub(crate) constraints: Vec<Constraint>,
+    /// Whether this is a `$..name` placeholder, which matches any number of arguments or
+    /// statements.
+    pub(crate) variadic: bool,
 }
 
 /// Represents a `$var` in an SSR query.
@@ -47,11 +50,32 @@ pub(crate) struct Var(pub(crate) String);
 pub(crate) enum Constraint {
     Kind(NodeKind),
     Not(Box<Constraint>),
+    /// The type of the expression or pattern unifies with this type.
+    Type(ast::Type),
+    /// The type of the expression or pattern implements this trait.
+    Impls(ast::Path),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum NodeKind {
     Literal,
+    Expr,
+    Path,
+    Pat,
+    Type,
+    Item,
+}
+
+impl Constraint {
+    /// Whether checking the constraint needs type information, which is more expensive than
+    /// looking at the syntax.
+    pub(crate) fn is_semantic(&self) -> bool {
+        match self {
+            Constraint::Kind(_) => false,
+            Constraint::Not(sub) => sub.is_semantic(),
+            Constraint::Type(_) | Constraint::Impls(_) => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -71,6 +95,7 @@ impl ParsedRule {
         let mut builder = RuleBuilder {
             placeholders_by_stand_in: pattern.placeholders_by_stand_in(),
             rules: Vec::new(),
+            variadic_error: None,
         };
 
         let raw_template_stmt = raw_template.map(fragments::stmt);
@@ -83,6 +108,13 @@ impl ParsedRule {
         builder.try_add(fragments::item(&raw_pattern), raw_template.map(fragments::item));
         builder.try_add(fragments::pat(&raw_pattern), raw_template.map(fragments::pat));
         builder.try_add(fragments::stmt(&raw_pattern), raw_template_stmt);
+        // Sequences of statements are only matched as such if there's more than one, single
+        // statements are already covered above.
+        if let Ok(pattern) = fragments::stmts(&raw_pattern) {
+            if pattern.children().count() > 1 {
+                builder.try_add(Ok(pattern), raw_template.map(fragments::stmts));
+            }
+        }
         builder.build()
     }
 }
@@ -90,6 +122,8 @@ impl ParsedRule {
 struct RuleBuilder {
     placeholders_by_stand_in: FxHashMap<SmolStr, Placeholder>,
     rules: Vec<ParsedRule>,
+    /// Why a parse of the pattern was rejected because of how it used variadic placeholders.
+    variadic_error: Option<SsrError>,
 }
 
 impl RuleBuilder {
@@ -98,6 +132,12 @@ impl RuleBuilder {
         pattern: Result<SyntaxNode, ()>,
         template: Option<Result<SyntaxNode, ()>>,
     ) {
+        if let Ok(pattern) = &pattern {
+            if let Err(e) = validate_variadics(pattern, &self.placeholders_by_stand_in) {
+                self.variadic_error = Some(e);
+                return;
+            }
+        }
         match (pattern, template) {
             (Ok(pattern), Some(Ok(template))) => self.rules.push(ParsedRule {
                 placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
@@ -115,6 +155,9 @@ impl RuleBuilder {
 
     fn build(mut self) -> Result<Vec<ParsedRule>, SsrError> {
         if self.rules.is_empty() {
+            if let Some(e) = self.variadic_error {
+                return Err(e);
+            }
             bail!("Not a valid Rust expression, type, item, path or pattern");
         }
         // If any rules contain paths, then we reject any rules that don't contain paths. Allowing a
@@ -136,6 +179,49 @@ impl RuleBuilder {
     }
 }
 
+/// Checks that variadic placeholders are whole arguments or statements, and that each list of
+/// arguments or statements contains at most one of them. Within macro calls they match a sequence of
+/// tokens, like any other placeholder.
+fn validate_variadics(
+    pattern: &SyntaxNode,
+    placeholders_by_stand_in: &FxHashMap<SmolStr, Placeholder>,
+) -> Result<(), SsrError> {
+    let mut lists = FxHashSet::default();
+    for token in pattern.descendants_with_tokens().filter_map(|it| it.into_token()) {
+        let Some(placeholder) = placeholders_by_stand_in.get(token.text()) else {
+            continue;
+        };
+        if !placeholder.variadic
+            || token.parent().map(|it| it.kind()) == Some(SyntaxKind::TOKEN_TREE)
+        {
+            continue;
+        }
+        // The largest node that consists of nothing but the placeholder, or the statement
+        // containing it.
+        let Some(mut element) =
+            token.parent_ancestors().take_while(|it| it.text_range() == token.text_range()).last()
+        else {
+            continue;
+        };
+        if let Some(stmt) = element.parent().and_then(ast::ExprStmt::cast) {
+            element = stmt.syntax().clone();
+        }
+        let Some(list) = element
+            .parent()
+            .filter(|it| matches!(it.kind(), SyntaxKind::ARG_LIST | SyntaxKind::STMT_LIST))
+        else {
+            bail!(
+                "Variadic placeholder `{}` must be an argument or a statement",
+                placeholder.ident
+            );
+        };
+        if !lists.insert(list) {
+            bail!("Only one variadic placeholder is allowed per argument or statement list");
+        }
+    }
+    Ok(())
+}
+
 /// Returns whether there are any paths in `node`.
 fn contains_path(node: &SyntaxNode) -> bool {
     node.kind() == SyntaxKind::PATH
@@ -269,11 +355,23 @@ fn tokenize(source: &str) -> Result<Vec<Token>, SsrError> {
 fn parse_placeholder(tokens: &mut std::vec::IntoIter<Token>) -> Result<Placeholder, SsrError> {
     let mut name = None;
     let mut constraints = Vec::new();
+    let mut variadic = false;
     if let Some(token) = tokens.next() {
         match token.kind {
             SyntaxKind::IDENT => {
                 name = Some(token.text);
             }
+            // `..` is lexed as two dots.
+            T![.] => {
+                if tokens.next().map(|it| it.kind) != Some(T![.]) {
+                    bail!("Variadic placeholders should be written as $..name");
+                }
+                match tokens.next() {
+                    Some(token) if token.kind == SyntaxKind::IDENT => name = Some(token.text),
+                    _ => bail!("Variadic placeholders should be written as $..name"),
+                }
+                variadic = true;
+            }
             T!['{'] => {
                 let token =
                     tokens.next().ok_or_else(|| SsrError::new("Unexpected end of placeholder"))?;
@@ -299,7 +397,9 @@ fn parse_placeholder(tokens: &mut std::vec::IntoIter<Token>) -> Result<Placehold
         }
     }
     let name = name.ok_or_else(|| SsrError::new("Placeholder ($) with no name"))?;
-    Ok(Placeholder::new(name, constraints))
+    let mut placeholder = Placeholder::new(name, constraints);
+    placeholder.variadic = variadic;
+    Ok(placeholder)
 }
 
 fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint, SsrError> {
@@ -314,7 +414,8 @@ fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint
             let t = tokens.next().ok_or_else(|| {
                 SsrError::new("Unexpected end of constraint while looking for kind")
             })?;
-            if t.kind != SyntaxKind::IDENT {
+            // `type` is a keyword, so it isn't lexed as an ident.
+            if t.kind != SyntaxKind::IDENT && t.kind != T![type] {
                 bail!("Expected ident, found {:?} while parsing kind constraint", t.kind);
             }
             expect_token(tokens, ")")?;
@@ -326,10 +427,48 @@ fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint
             expect_token(tokens, ")")?;
             Ok(Constraint::Not(Box::new(sub)))
         }
+        "type" => {
+            expect_token(tokens, "(")?;
+            let ty = parse_constraint_type(tokens)?;
+            Ok(Constraint::Type(ty))
+        }
+        "impls" => {
+            expect_token(tokens, "(")?;
+            let ty = parse_constraint_type(tokens)?;
+            match ty {
+                ast::Type::PathType(it) => match it.path() {
+                    Some(path) => Ok(Constraint::Impls(path)),
+                    None => bail!("Expected a trait, found `{}`", it),
+                },
+                _ => bail!("Expected a trait, found `{}`", ty),
+            }
+        }
         x => bail!("Unsupported constraint type '{}'", x),
     }
 }
 
+/// Parses the tokens up to the closing parenthesis of a constraint as a type, consuming the
+/// parenthesis.
+fn parse_constraint_type(tokens: &mut std::vec::IntoIter<Token>) -> Result<ast::Type, SsrError> {
+    let mut text = String::new();
+    let mut depth = 0;
+    loop {
+        let t = tokens
+            .next()
+            .ok_or_else(|| SsrError::new("Unexpected end of constraint while looking for type"))?;
+        match t.kind {
+            T!['('] => depth += 1,
+            T![')'] if depth == 0 => break,
+            T![')'] => depth -= 1,
+            T![$] => bail!("Placeholders are not supported in types of constraints"),
+            _ => {}
+        }
+        text.push_str(&t.text);
+    }
+    let ty = fragments::ty(&text).map_err(|()| SsrError::new(format!("Invalid type `{text}`")))?;
+    Ok(ast::Type::cast(ty).expect("fragments::ty returns a type"))
+}
+
 fn expect_token(tokens: &mut std::vec::IntoIter<Token>, expected: &str) -> Result<(), SsrError> {
     if let Some(t) = tokens.next() {
         if t.text == expected {
@@ -344,6 +483,11 @@ impl NodeKind {
     fn from(name: &SmolStr) -> Result<NodeKind, SsrError> {
         Ok(match name.as_str() {
             "literal" => NodeKind::Literal,
+            "expr" => NodeKind::Expr,
+            "path" => NodeKind::Path,
+            "pat" => NodeKind::Pat,
+            "type" => NodeKind::Type,
+            "item" => NodeKind::Item,
             _ => bail!("Unknown node kind '{}'", name),
         })
     }
@@ -355,6 +499,7 @@ impl Placeholder {
             stand_in_name: format!("__placeholder_{name}"),
             constraints,
             ident: Var(name.to_string()),
+            variadic: false,
         }
     }
 }
@@ -4,7 +4,7 @@ use ide_db::{FxHashMap, FxHashSet};
 use itertools::Itertools;
 use syntax::{
     ast::{self, AstNode, AstToken},
-    SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize,
+    SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, T,
 };
 use text_edit::TextEdit;
 
@@ -77,7 +77,7 @@ fn render_replace(
         placeholder_tokens_requiring_parenthesis: FxHashSet::default(),
         placeholder_tokens_by_range: FxHashMap::default(),
     };
-    renderer.render_node(&template.node);
+    renderer.render_template(&template.node);
     renderer.maybe_rerender_with_extra_parenthesis(&template.node);
     for comment in &match_info.ignored_comments {
         renderer.out.push_str(&comment.syntax().to_string());
@@ -86,9 +86,45 @@ fn render_replace(
 }
 
 impl ReplacementRenderer<'_> {
+    fn render_template(&mut self, template: &SyntaxNode) {
+        if template.kind() == SyntaxKind::STMT_LIST {
+            // Statements replace other statements, the braces around them stay where they are.
+            for node_or_token in template.children_with_tokens() {
+                if !matches!(node_or_token.kind(), T!['{'] | T!['}']) {
+                    self.render_node_or_token(&node_or_token);
+                }
+            }
+        } else {
+            self.render_node(template);
+        }
+    }
+
     fn render_node_children(&mut self, node: &SyntaxNode) {
+        if node.kind() == SyntaxKind::ARG_LIST {
+            self.render_arg_list(node);
+            return;
+        }
+        for node_or_token in node.children_with_tokens() {
+            self.render_node_or_token(&node_or_token);
+        }
+    }
+
+    /// Arguments that render to nothing, which variadic placeholders that matched no arguments do,
+    /// are rendered without their comma. Otherwise `foo($..args, 1)` would become `foo(, 1)`.
+    fn render_arg_list(&mut self, node: &SyntaxNode) {
+        let mut last_arg_empty = false;
         for node_or_token in node.children_with_tokens() {
+            match node_or_token.kind() {
+                T![,] | SyntaxKind::WHITESPACE if last_arg_empty => continue,
+                T![')'] if last_arg_empty => {
+                    let len = self.out.trim_end().trim_end_matches(',').trim_end().len();
+                    self.out.truncate(len);
+                }
+                _ => {}
+            }
+            let len = self.out.len();
             self.render_node_or_token(&node_or_token);
+            last_arg_empty = node_or_token.as_node().is_some() && self.out.len() == len;
         }
     }
 
@@ -165,13 +201,17 @@ impl ReplacementRenderer<'_> {
                 if needs_parenthesis {
                     self.out.push('(');
                 }
-                self.placeholder_tokens_by_range.insert(
-                    TextRange::new(
-                        TextSize::of(&self.out),
-                        TextSize::of(&self.out) + TextSize::of(&matched_text),
-                    ),
-                    token.clone(),
-                );
+                // Variadic placeholders expand to several arguments or statements, which is not
+                // a single node and can't be parenthesized.
+                if !placeholder.variadic {
+                    self.placeholder_tokens_by_range.insert(
+                        TextRange::new(
+                            TextSize::of(&self.out),
+                            TextSize::of(&self.out) + TextSize::of(&matched_text),
+                        ),
+                        token.clone(),
+                    );
+                }
                 self.out.push_str(&matched_text);
                 if needs_parenthesis {
                     self.out.push(')');
@@ -194,21 +234,28 @@ impl ReplacementRenderer<'_> {
     // we rerender the template and wrap the problematic placeholders with parenthesis.
     fn maybe_rerender_with_extra_parenthesis(&mut self, template: &SyntaxNode) {
         if let Some(node) = parse_as_kind(&self.out, template.kind()) {
-            self.remove_node_ranges(node);
+            // Statements are parsed within braces, which shift the ranges of their nodes.
+            let offset = match template.kind() {
+                SyntaxKind::STMT_LIST => TextSize::of('{'),
+                _ => TextSize::from(0),
+            };
+            self.remove_node_ranges(node, offset);
             if self.placeholder_tokens_by_range.is_empty() {
                 return;
             }
             self.placeholder_tokens_requiring_parenthesis =
                 self.placeholder_tokens_by_range.values().cloned().collect();
             self.out.clear();
-            self.render_node(template);
+            self.render_template(template);
         }
     }
 
-    fn remove_node_ranges(&mut self, node: SyntaxNode) {
-        self.placeholder_tokens_by_range.remove(&node.text_range());
+    fn remove_node_ranges(&mut self, node: SyntaxNode, offset: TextSize) {
+        if let Some(range) = node.text_range().checked_sub(offset) {
+            self.placeholder_tokens_by_range.remove(&range);
+        }
         for child in node.children() {
-            self.remove_node_ranges(child);
+            self.remove_node_ranges(child, offset);
         }
     }
 }
@@ -248,5 +295,10 @@ fn parse_as_kind(code: &str, kind: SyntaxKind) -> Option<SyntaxNode> {
             return Some(item);
         }
     }
+    if kind == SyntaxKind::STMT_LIST {
+        if let Ok(stmts) = fragments::stmts(code) {
+            return Some(stmts);
+        }
+    }
     None
 }
@@ -5,7 +5,10 @@ use ide_db::{base_db::FilePosition, FxHashMap};
 use parsing::Placeholder;
 use syntax::{ast, SmolStr, SyntaxKind, SyntaxNode, SyntaxToken};
 
-use crate::{errors::error, parsing, SsrError};
+use crate::{
+    errors::{bail, error},
+    parsing, SsrError,
+};
 
 pub(crate) struct ResolutionScope<'db> {
     scope: hir::SemanticsScope<'db>,
@@ -16,6 +19,13 @@ pub(crate) struct ResolvedRule {
     pub(crate) pattern: ResolvedPattern,
     pub(crate) template: Option<ResolvedPattern>,
     pub(crate) index: usize,
+    // The `type` and `impls` constraints of the placeholders, keyed by their type or trait.
+    pub(crate) resolved_constraints: FxHashMap<SyntaxNode, ResolvedConstraint>,
+}
+
+pub(crate) enum ResolvedConstraint {
+    Type(hir::Type),
+    Impls { trait_: hir::Trait, args: Vec<hir::Type> },
 }
 
 pub(crate) struct ResolvedPattern {
@@ -45,6 +55,12 @@ impl ResolvedRule {
         resolution_scope: &ResolutionScope<'_>,
         index: usize,
     ) -> Result<ResolvedRule, SsrError> {
+        let mut resolved_constraints = FxHashMap::default();
+        for placeholder in rule.placeholders_by_stand_in.values() {
+            for constraint in &placeholder.constraints {
+                resolution_scope.resolve_constraint(constraint, &mut resolved_constraints)?;
+            }
+        }
         let resolver =
             Resolver { resolution_scope, placeholders_by_stand_in: rule.placeholders_by_stand_in };
         let resolved_template = match rule.template {
@@ -55,6 +71,7 @@ impl ResolvedRule {
             pattern: resolver.resolve_pattern_tree(rule.pattern)?,
             template: resolved_template,
             index,
+            resolved_constraints,
         })
     }
 
@@ -247,6 +264,53 @@ impl<'db> ResolutionScope<'db> {
         }
     }
 
+    fn resolve_constraint(
+        &self,
+        constraint: &parsing::Constraint,
+        resolved: &mut FxHashMap<SyntaxNode, ResolvedConstraint>,
+    ) -> Result<(), SsrError> {
+        use syntax::ast::AstNode;
+        match constraint {
+            parsing::Constraint::Kind(_) => {}
+            parsing::Constraint::Not(sub) => self.resolve_constraint(sub, resolved)?,
+            parsing::Constraint::Type(ty) => {
+                let resolved_ty = self.resolve_type(ty)?;
+                resolved.insert(ty.syntax().clone(), ResolvedConstraint::Type(resolved_ty));
+            }
+            parsing::Constraint::Impls(path) => {
+                let trait_ = match self.scope.speculative_resolve(path) {
+                    Some(hir::PathResolution::Def(hir::ModuleDef::Trait(it))) => it,
+                    _ => bail!("Failed to resolve trait `{}`", path),
+                };
+                let mut args = Vec::new();
+                let generic_args = path.segment().and_then(|it| it.generic_arg_list());
+                for arg in generic_args.iter().flat_map(|it| it.generic_args()) {
+                    match arg {
+                        ast::GenericArg::TypeArg(arg) => match arg.ty() {
+                            Some(ty) => args.push(self.resolve_type(&ty)?),
+                            None => bail!("Invalid type argument `{}`", arg),
+                        },
+                        _ => bail!("Only type arguments are supported in `impls`, found `{}`", arg),
+                    }
+                }
+                resolved.insert(path.syntax().clone(), ResolvedConstraint::Impls { trait_, args });
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves a type of a constraint. Every path in it must resolve, only `_` is left unknown.
+    fn resolve_type(&self, ty: &ast::Type) -> Result<hir::Type, SsrError> {
+        use syntax::ast::AstNode;
+        for path_type in ty.syntax().descendants().filter_map(ast::PathType::cast) {
+            let path = path_type.path().ok_or_else(|| error!("Invalid type `{}`", path_type))?;
+            if self.scope.speculative_resolve(&path).is_none() {
+                bail!("Failed to resolve type `{}`", path_type);
+            }
+        }
+        Ok(self.scope.speculative_resolve_type(ty))
+    }
+
     fn qualifier_type(&self, path: &SyntaxNode) -> Option<hir::Type> {
         use syntax::ast::AstNode;
         if let Some(path) = ast::Path::cast(path.clone()) {
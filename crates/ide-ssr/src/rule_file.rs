@@ -0,0 +1,66 @@
+//! Parsing of files with named SSR rules, which let a project keep its rules next to its code.
+//!
+//! Each rule is written as `<name>: <rule>`. Rules can be continued on the following lines, as long
+//! as those are indented. Lines that start with `#` are comments.
+//!
+//! ```text
+//! # Prefer `expect`, so that panics explain themselves.
+//! option-unwrap: ${x:type(Option<_>)}.unwrap() ==>> $x.expect("no value")
+//! swap-args:
+//!     foo($a, $b)
+//!     ==>> bar($b, $a)
+//! ```
+
+use ide_db::FxHashSet;
+
+use crate::{errors::bail, SsrError, SsrRule};
+
+/// A rule of a rule file.
+#[derive(Debug)]
+pub struct NamedSsrRule {
+    pub name: String,
+    pub rule: SsrRule,
+}
+
+/// Parses the contents of a rule file, errors mention the line of the rule that failed to parse.
+pub fn parse_rule_file(text: &str) -> Result<Vec<NamedSsrRule>, SsrError> {
+    // The line where each rule starts, its name and its text.
+    let mut raw_rules: Vec<(usize, &str, String)> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            match raw_rules.last_mut() {
+                Some((_, _, rule)) => {
+                    rule.push(' ');
+                    rule.push_str(line.trim());
+                }
+                None => bail!("line {line_number}: Indented line doesn't continue a rule"),
+            }
+            continue;
+        }
+        let Some((name, rule)) = line.split_once(':') else {
+            bail!("line {line_number}: Expected `<name>: <rule>`");
+        };
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+            bail!("line {line_number}: Invalid rule name `{name}`");
+        }
+        raw_rules.push((line_number, name, rule.trim().to_owned()));
+    }
+
+    let mut names = FxHashSet::default();
+    let mut rules = Vec::new();
+    for (line_number, name, rule) in raw_rules {
+        if !names.insert(name) {
+            bail!("line {line_number}: Rule `{name}` is defined more than once");
+        }
+        let rule = rule
+            .parse::<SsrRule>()
+            .map_err(|e| SsrError::new(format!("line {line_number}: {}", e.0)))?;
+        rules.push(NamedSsrRule { name: name.to_owned(), rule });
+    }
+    Ok(rules)
+}
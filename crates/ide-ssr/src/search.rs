@@ -211,7 +211,11 @@ impl MatchFinder<'_> {
         restrict_range: &Option<FileRange>,
         matches_out: &mut Vec<Match>,
     ) {
-        if !self.within_range_restrictions(code) {
+        if rule.pattern.node.kind() == SyntaxKind::STMT_LIST {
+            self.try_add_sequence_matches(rule, code, restrict_range, matches_out);
+            return;
+        }
+        if !self.within_range_restrictions(&self.sema.original_range(code)) {
             cov_mark::hit!(replace_nonpath_within_selection);
             return;
         }
@@ -220,14 +224,36 @@ impl MatchFinder<'_> {
         }
     }
 
-    /// Returns whether `code` is within one of our range restrictions if we have any. No range
-    /// restrictions is considered unrestricted and always returns true.
-    fn within_range_restrictions(&self, code: &SyntaxNode) -> bool {
+    /// Sequences of statements can start at any statement of a statement list, so we try each of
+    /// them.
+    fn try_add_sequence_matches(
+        &self,
+        rule: &ResolvedRule,
+        code: &SyntaxNode,
+        restrict_range: &Option<FileRange>,
+        matches_out: &mut Vec<Match>,
+    ) {
+        if code.kind() != SyntaxKind::STMT_LIST {
+            return;
+        }
+        for start in 0..code.children().count() {
+            if let Ok(m) =
+                matching::get_sequence_match(false, rule, code, start, restrict_range, &self.sema)
+            {
+                if self.within_range_restrictions(&m.range) {
+                    matches_out.push(m);
+                }
+            }
+        }
+    }
+
+    /// Returns whether `node_range` is within one of our range restrictions if we have any. No
+    /// range restrictions is considered unrestricted and always returns true.
+    fn within_range_restrictions(&self, node_range: &FileRange) -> bool {
         if self.restrict_ranges.is_empty() {
             // There is no range restriction.
             return true;
         }
-        let node_range = self.sema.original_range(code);
         for range in &self.restrict_ranges {
             if range.file_id == node_range.file_id && range.range.contains_range(node_range.range) {
                 return true;
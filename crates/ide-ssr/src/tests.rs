@@ -62,6 +62,48 @@ fn parser_undefined_placeholder_in_replacement() {
     );
 }
 
+#[test]
+fn parse_rule_file() {
+    let rules = crate::parse_rule_file(
+        r#"
+# Comments and blank lines are skipped.
+
+swap-args: foo($a, $b) ==>> foo($b, $a)
+multi_line:
+    bar($a)
+    ==>> baz($a)
+"#,
+    )
+    .unwrap();
+    let names: Vec<_> = rules.iter().map(|it| it.name.as_str()).collect();
+    assert_eq!(names, ["swap-args", "multi_line"]);
+    let (db, position, selections) =
+        single_file("fn foo() {} fn bar() {} fn baz() {} fn f() {foo(1, 2); bar(3);}");
+    let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+    for rule in rules {
+        match_finder.add_rule(rule.rule).unwrap();
+    }
+    let mut actual = db.file_text(position.file_id).to_string();
+    match_finder.edits()[&position.file_id].apply(&mut actual);
+    assert_eq!(actual, "fn foo() {} fn bar() {} fn baz() {} fn f() {foo(2, 1); baz(3);}");
+}
+
+#[test]
+fn parse_rule_file_errors() {
+    let error = |text: &str| crate::parse_rule_file(text).unwrap_err().to_string();
+    assert_eq!(error("foo($a)"), "Parse error: line 1: Expected `<name>: <rule>`");
+    assert_eq!(
+        error("  foo($a) ==>> bar($a)"),
+        "Parse error: line 1: Indented line doesn't continue a rule"
+    );
+    assert_eq!(error("a b: foo($a) ==>> bar($a)"), "Parse error: line 1: Invalid rule name `a b`");
+    assert_eq!(
+        error("a: foo($a) ==>> bar($a)\n\na: bar($a) ==>> foo($a)"),
+        "Parse error: line 3: Rule `a` is defined more than once"
+    );
+    assert_eq!(error("# comment\na: foo($a)"), "Parse error: line 2: Cannot find delimiter `==>>`");
+}
+
 /// `code` may optionally contain a cursor marker `$0`. If it doesn't, then the position will be
 /// the start of the file. If there's a second cursor marker, then we'll return a single range.
 pub(crate) fn single_file(code: &str) -> (ide_db::RootDatabase, FilePosition, Vec<FileRange>) {
@@ -536,6 +578,116 @@ fn literal_constraint() {
     assert_matches("Some(${a:not(kind(literal))})", code, &["Some(x1)", "Some(40 + 2)"]);
 }
 
+#[test]
+fn kind_constraints() {
+    let code = r#"
+        fn foo<T>(t: T) {}
+        fn f1() {
+            let x = 1;
+            foo(x);
+            foo(1 + 2);
+        }
+        "#;
+    assert_matches("foo(${a:kind(path)})", code, &["foo(x)"]);
+    assert_matches("foo(${a:kind(expr)})", code, &["foo(x)", "foo(1 + 2)"]);
+    assert_matches("foo(${a:not(kind(path))})", code, &["foo(1 + 2)"]);
+    assert_matches("Vec<${a:kind(type)}>", "struct Vec<T>(T); fn f(v: Vec<i32>) {}", &["Vec<i32>"]);
+}
+
+#[test]
+fn type_constraint() {
+    let code = r#"
+        enum Option<T> { Some(T), None }
+        use Option::Some;
+        struct Wrapper<T>(T);
+        impl<T> Option<T> { fn unwrap(self) -> T { loop {} } }
+        impl<T> Wrapper<T> { fn unwrap(self) -> T { self.0 } }
+        fn f1() {
+            let a = Some(1);
+            let b = Wrapper(1);
+            let c = Wrapper(true);
+            a.unwrap();
+            b.unwrap();
+            c.unwrap();
+        }
+        "#;
+    assert_matches("${x:type(Option<_>)}.unwrap()", code, &["a.unwrap()"]);
+    assert_matches("${x:type(Wrapper<i32>)}.unwrap()", code, &["b.unwrap()"]);
+    assert_matches("${x:not(type(Option<_>))}.unwrap()", code, &["b.unwrap()", "c.unwrap()"]);
+    assert_match_failure_reason(
+        "${x:type(Option<_>)}.unwrap()",
+        code,
+        "b.unwrap()",
+        "Code 'b' has type `Wrapper<i32>`, not `Option<{unknown}>`",
+    );
+}
+
+#[test]
+fn type_constraint_replace() {
+    assert_ssr_transform(
+        r#"${x:type(Option<_>)}.unwrap() ==>> $x.expect("no value")"#,
+        r#"
+        enum Option<T> { Some(T), None }
+        struct Wrapper<T>(T);
+        impl<T> Option<T> { fn unwrap(self) -> T { loop {} } }
+        impl<T> Wrapper<T> { fn unwrap(self) -> T { self.0 } }
+        fn f1(a: Option<i32>, b: Wrapper<i32>) {
+            a.unwrap();
+            b.unwrap();
+        }
+        "#,
+        expect![[r#"
+            enum Option<T> { Some(T), None }
+            struct Wrapper<T>(T);
+            impl<T> Option<T> { fn unwrap(self) -> T { loop {} } }
+            impl<T> Wrapper<T> { fn unwrap(self) -> T { self.0 } }
+            fn f1(a: Option<i32>, b: Wrapper<i32>) {
+                a.expect("no value");
+                b.unwrap();
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn impls_constraint() {
+    let code = r#"
+        trait Duplicate { fn duplicate(&self) -> Self; }
+        trait Convert<T> {}
+        struct A;
+        struct B;
+        impl Duplicate for A { fn duplicate(&self) -> Self { A } }
+        impl Convert<B> for A {}
+        fn foo<T>(t: T) {}
+        fn f1() {
+            foo(A);
+            foo(B);
+        }
+        "#;
+    assert_matches("foo(${e:impls(Duplicate)})", code, &["foo(A)"]);
+    assert_matches("foo(${e:not(impls(Duplicate))})", code, &["foo(B)"]);
+    assert_matches("foo(${e:impls(Convert<B>)})", code, &["foo(A)"]);
+    assert_no_match("foo(${e:impls(Convert<A>)})", code);
+}
+
+#[test]
+fn unresolved_constraint_types() {
+    let (db, position, selections) = single_file("struct A; fn f() {}");
+    let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+    let mut error = |pattern: &str| {
+        match_finder.add_search_pattern(pattern.parse().unwrap()).unwrap_err().to_string()
+    };
+    assert_eq!(
+        error("f(${a:type(Missing<_>)})"),
+        "Parse error: Failed to resolve type `Missing<_>`"
+    );
+    assert_eq!(error("f(${a:impls(A)})"), "Parse error: Failed to resolve trait `A`");
+    assert_eq!(
+        parse_error_text("f(${a:impls(&A)}) ==>> f($a)"),
+        "Parse error: Expected a trait, found `&A`"
+    );
+}
+
 #[test]
 fn match_reordered_struct_instantiation() {
     assert_matches(
@@ -1395,3 +1547,176 @@ fn replace_autoref_mut() {
         "#]],
     );
 }
+
+#[test]
+fn match_statement_sequence() {
+    let code = r#"
+        fn foo() {}
+        fn bar() {}
+        fn f() {
+            foo();
+            bar();
+            foo();
+            let x = 1;
+            bar()
+        }
+        "#;
+    assert_matches("foo(); bar()", code, &["foo();\n    bar()"]);
+    assert_matches("foo(); $a", code, &["foo();\n    bar();", "foo();\n    let x = 1;"]);
+    // Sequences that overlap with an earlier one are dropped.
+    assert_matches("$a; $b", code, &["foo();\n    bar();", "foo();\n    let x = 1;"]);
+    assert_no_match("foo(); foo()", code);
+}
+
+#[test]
+fn replace_statement_sequence() {
+    assert_ssr_transform(
+        "let $a = foo($b); bar($c) ==>> baz($b, $c)",
+        r#"
+        fn foo(x: i32) -> i32 { x }
+        fn bar(x: i32) {}
+        fn baz(x: i32, y: i32) {}
+        fn f() {
+            let x = foo(1);
+            bar(x);
+            let y = foo(2);
+            bar(y)
+        }
+        "#,
+        expect![[r#"
+            fn foo(x: i32) -> i32 { x }
+            fn bar(x: i32) {}
+            fn baz(x: i32, y: i32) {}
+            fn f() {
+                baz(1, x);
+                baz(2, y)
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn replace_statement_tail() {
+    assert_ssr_transform(
+        "let $a = lock(); $..rest ==>> with_lock(|$a| { $..rest })",
+        r#"
+        fn lock() -> i32 { 0 }
+        fn with_lock(f: impl FnOnce(i32) -> i32) -> i32 { f(0) }
+        fn f() -> i32 {
+            println!("start");
+            let g = lock();
+            let h = g + 1;
+            // Double it.
+            h * 2
+        }
+        "#,
+        expect![[r#"
+            fn lock() -> i32 { 0 }
+            fn with_lock(f: impl FnOnce(i32) -> i32) -> i32 { f(0) }
+            fn f() -> i32 {
+                println!("start");
+                with_lock(|g| { let h = g + 1;
+                // Double it.
+                h * 2 })
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn variadic_arguments() {
+    let code = r#"
+        fn foo(a: i32, b: i32, c: i32) {}
+        fn f() {
+            foo(1);
+            foo(1, 2);
+            foo(1, 2, 3);
+        }
+        "#;
+    assert_matches("foo($..args)", code, &["foo(1)", "foo(1, 2)", "foo(1, 2, 3)"]);
+    assert_matches("foo($a, $..rest, 3)", code, &["foo(1, 2, 3)"]);
+    assert_matches("foo($..rest, $a)", code, &["foo(1)", "foo(1, 2)", "foo(1, 2, 3)"]);
+    assert_ssr_transform(
+        "foo($a, $..rest) ==>> foo($a, $..rest, 0)",
+        code,
+        expect![[r#"
+            fn foo(a: i32, b: i32, c: i32) {}
+            fn f() {
+                foo(1, 0);
+                foo(1, 2, 0);
+                foo(1, 2, 3, 0);
+            }
+        "#]],
+    );
+    assert_ssr_transform(
+        "foo($a, $..rest) ==>> foo($..rest, $a)",
+        code,
+        expect![[r#"
+            fn foo(a: i32, b: i32, c: i32) {}
+            fn f() {
+                foo(1);
+                foo(2, 1);
+                foo(2, 3, 1);
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn variadic_method_call_arguments() {
+    assert_ssr_transform(
+        "Foo::foo($s, $..args) ==>> bar($s, $..args)",
+        r#"
+        struct Foo {}
+        impl Foo {
+            fn foo(&self, a: i32, b: i32) {}
+        }
+        fn bar(f: &Foo, a: i32, b: i32) {}
+        fn f(x: Foo) {
+            x.foo(1, 2);
+        }
+        "#,
+        expect![[r#"
+            struct Foo {}
+            impl Foo {
+                fn foo(&self, a: i32, b: i32) {}
+            }
+            fn bar(f: &Foo, a: i32, b: i32) {}
+            fn f(x: Foo) {
+                bar(&x, 1, 2);
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn variadic_statements_in_block() {
+    assert_matches(
+        "if $c { foo(); $..rest }",
+        r#"
+        fn foo() {}
+        fn f(c: bool) {
+            if c { foo(); }
+            if c { foo(); foo(); 1; }
+            if c { 1; foo(); }
+        }
+        "#,
+        &["if c { foo(); }", "if c { foo(); foo(); 1; }"],
+    );
+}
+
+#[test]
+fn variadic_errors() {
+    assert_eq!(
+        parse_error_text("$..a + 1 ==>> 1"),
+        "Parse error: Variadic placeholder `$a` must be an argument or a statement"
+    );
+    assert_eq!(
+        parse_error_text("foo($..a, $..b) ==>> foo()"),
+        "Parse error: Only one variadic placeholder is allowed per argument or statement list"
+    );
+    assert_eq!(
+        parse_error_text("foo($..) ==>> foo()"),
+        "Parse error: Variadic placeholders should be written as $..name"
+    );
+}
@@ -175,6 +175,24 @@ fn hover_simple(
             let attr = token.parent_ancestors().find_map(ast::Attr::cast)?;
             render::try_for_lint(&attr, token)
         })
+        // try operators and `const` blocks of constant expressions
+        .or_else(|| {
+            descended().find_map(|token| {
+                let parent = token.parent()?;
+                let expr = syntax::match_ast! {
+                    match parent {
+                        ast::BinExpr(it) => (it.op_token()? == *token).then(|| it.into())?,
+                        ast::PrefixExpr(it) => (it.op_token()? == *token).then(|| it.into())?,
+                        ast::BlockExpr(it) => match it.modifier()? {
+                            ast::BlockModifier::Const(kw) if kw == *token => it.into(),
+                            _ => return None,
+                        },
+                        _ => return None,
+                    }
+                };
+                render::const_expr(sema, config, expr)
+            })
+        })
         // try definitions
         .or_else(|| {
             descended()
@@ -36,11 +36,45 @@ pub(super) fn type_info_of(
     _config: &HoverConfig,
     expr_or_pat: &Either<ast::Expr, ast::Pat>,
 ) -> Option<HoverResult> {
-    let ty_info = match expr_or_pat {
-        Either::Left(expr) => sema.type_of_expr(expr)?,
-        Either::Right(pat) => sema.type_of_pat(pat)?,
+    let (ty_info, value) = match expr_or_pat {
+        Either::Left(expr) => (sema.type_of_expr(expr)?, const_value_of(sema, expr)),
+        Either::Right(pat) => (sema.type_of_pat(pat)?, None),
     };
-    type_info(sema, _config, ty_info)
+    type_info(sema, _config, ty_info, value)
+}
+
+/// Returns the value of `expr` if it is a constant expression whose value isn't spelled out in
+/// the source already.
+fn const_value_of(sema: &Semantics<'_, RootDatabase>, expr: &ast::Expr) -> Option<String> {
+    let is_computed = match expr {
+        ast::Expr::PrefixExpr(it) => !matches!(it.expr()?, ast::Expr::Literal(_)),
+        ast::Expr::BlockExpr(it) => matches!(it.modifier(), Some(ast::BlockModifier::Const(_))),
+        ast::Expr::BinExpr(_)
+        | ast::Expr::CallExpr(_)
+        | ast::Expr::CastExpr(_)
+        | ast::Expr::FieldExpr(_)
+        | ast::Expr::IndexExpr(_)
+        | ast::Expr::MethodCallExpr(_)
+        | ast::Expr::ParenExpr(_)
+        | ast::Expr::PathExpr(_) => true,
+        _ => false,
+    };
+    if !is_computed {
+        return None;
+    }
+    if sema.type_of_expr(expr)?.original.is_unit() {
+        return None;
+    }
+    sema.render_const_eval(expr)
+}
+
+pub(super) fn const_expr(
+    sema: &Semantics<'_, RootDatabase>,
+    config: &HoverConfig,
+    expr: ast::Expr,
+) -> Option<HoverResult> {
+    let value = const_value_of(sema, &expr)?;
+    type_info(sema, config, sema.type_of_expr(&expr)?, Some(value))
 }
 
 pub(super) fn closure_expr(
@@ -499,6 +533,7 @@ fn type_info(
     sema: &Semantics<'_, RootDatabase>,
     config: &HoverConfig,
     ty: TypeInfo,
+    value: Option<String>,
 ) -> Option<HoverResult> {
     if let Some(res) = closure_ty(sema, config, &ty) {
         return Some(res);
@@ -526,6 +561,8 @@ fn type_info(
             opad = original.len(),
         )
         .into()
+    } else if let Some(value) = value {
+        Markup::fenced_block(format_args!("{} = {value}", original.display(sema.db)))
     } else {
         Markup::fenced_block(&original.display(sema.db))
     };
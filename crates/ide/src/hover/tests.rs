@@ -4673,6 +4673,104 @@ fn test() {
     );
 }
 
+#[test]
+fn hover_const_eval_expr() {
+    check(
+        r#"
+const SHIFT: u32 = 12;
+fn f() {
+    let mask = 1 <$0< SHIFT;
+}
+"#,
+        expect![[r#"
+            *<<*
+            ```rust
+            i32 = 4096 (0x1000)
+            ```
+        "#]],
+    );
+    check(
+        r#"
+//- minicore: size_of
+fn f() {
+    let size = core::mem::size_of::<u64>($0);
+}
+"#,
+        expect![[r#"
+            *)*
+            ```rust
+            usize = 8
+            ```
+        "#]],
+    );
+    check(
+        r#"
+fn f() {
+    let x = con$0st { 2 * 8 };
+}
+"#,
+        expect![[r#"
+            *const*
+            ```rust
+            i32 = 16 (0x10)
+            ```
+        "#]],
+    );
+    check_hover_range(
+        r#"
+const N: usize = 4;
+fn f() {
+    let array = [0u8; $0N * 2$0];
+}
+"#,
+        expect![[r#"
+            ```rust
+            usize = 8
+            ```"#]],
+    );
+}
+
+#[test]
+fn hover_const_eval_expr_not_const() {
+    check_hover_range(
+        r#"
+fn not_const() -> u32 { 5 }
+fn f(x: u32) {
+    let y = $0x + 1$0;
+    let z = not_const() + 1;
+}
+"#,
+        expect![[r#"
+            ```rust
+            u32
+            ```"#]],
+    );
+    check_hover_range(
+        r#"
+fn not_const() -> u32 { 5 }
+fn f(x: u32) {
+    let z = $0not_const() + 1$0;
+}
+"#,
+        expect![[r#"
+            ```rust
+            u32
+            ```"#]],
+    );
+    check_hover_range(
+        r#"
+static S: u32 = 5;
+fn f() {
+    let z = $0S + 1$0;
+}
+"#,
+        expect![[r#"
+            ```rust
+            u32
+            ```"#]],
+    );
+}
+
 #[test]
 fn hover_const_pat() {
     check(
@@ -5393,7 +5491,7 @@ fn f() { let expr = $01 + 2 * 3$0 }
 "#,
         expect![[r#"
             ```rust
-            i32
+            i32 = 7
             ```"#]],
     );
 
@@ -5403,7 +5501,7 @@ fn f() { let expr = 1 $0+ 2 * $03 }
 "#,
         expect![[r#"
             ```rust
-            i32
+            i32 = 7
             ```"#]],
     );
 
@@ -5413,7 +5511,7 @@ fn f() { let expr = 1 + $02 * 3$0 }
 "#,
         expect![[r#"
             ```rust
-            i32
+            i32 = 6
             ```"#]],
     );
 }
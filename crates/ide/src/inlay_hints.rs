@@ -27,10 +27,14 @@ mod chaining;
 mod closing_brace;
 mod closure_ret;
 mod closure_captures;
+mod const_value;
 mod discriminant;
 mod fn_lifetime_fn;
+mod generic_param;
+mod implicit_drop;
 mod implicit_static;
 mod param_name;
+mod turbofish;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InlayHintsConfig {
@@ -44,6 +48,10 @@ pub struct InlayHintsConfig {
     pub adjustment_hints_hide_outside_unsafe: bool,
     pub closure_return_type_hints: ClosureReturnTypeHints,
     pub closure_capture_hints: bool,
+    pub turbofish_hints: bool,
+    pub generic_parameter_hints: bool,
+    pub implicit_drop_hints: bool,
+    pub const_value_hints: bool,
     pub binding_mode_hints: bool,
     pub lifetime_elision_hints: LifetimeElisionHints,
     pub param_names_for_lifetime_elision_hints: bool,
@@ -97,10 +105,14 @@ pub enum InlayKind {
     Chaining,
     ClosingBrace,
     ClosureCapture,
+    ConstValue,
     Discriminant,
+    Drop,
+    GenericParameter,
     GenericParamList,
     Lifetime,
     Parameter,
+    Turbofish,
     Type,
 }
 
@@ -405,6 +417,10 @@ fn ty_to_text_edit(
 // * return types of closure expressions
 // * elided lifetimes
 // * compiler inserted reborrows
+// * points where locals are implicitly dropped
+// * inferred generic arguments of calls
+// * names of generic parameters
+// * values of constant expressions
 //
 // image::https://user-images.githubusercontent.com/48062697/113020660-b5f98b80-917a-11eb-8d70-3be3fd558cdd.png[]
 pub(crate) fn inlay_hints(
@@ -452,6 +468,8 @@ fn hints(
             ast::Expr(expr) => {
                 chaining::hints(hints, famous_defs, config, file_id, &expr);
                 adjustment::hints(hints, sema, config, &expr);
+                turbofish::hints(hints, famous_defs, config, &expr);
+                const_value::hints(hints, sema, config, &expr);
                 match expr {
                     ast::Expr::CallExpr(it) => param_name::hints(hints, sema, config, ast::Expr::from(it)),
                     ast::Expr::MethodCallExpr(it) => {
@@ -474,13 +492,17 @@ fn hints(
             ast::Item(it) => match it {
                 // FIXME: record impl lifetimes so they aren't being reused in assoc item lifetime inlay hints
                 ast::Item::Impl(_) => None,
-                ast::Item::Fn(it) => fn_lifetime_fn::hints(hints, config, it),
+                ast::Item::Fn(it) => {
+                    implicit_drop::hints(hints, sema, config, &it);
+                    fn_lifetime_fn::hints(hints, config, it)
+                },
                 // static type elisions
                 ast::Item::Static(it) => implicit_static::hints(hints, config, Either::Left(it)),
                 ast::Item::Const(it) => implicit_static::hints(hints, config, Either::Right(it)),
                 ast::Item::Enum(it) => discriminant::enum_hints(hints, famous_defs, config, file_id, it),
                 _ => None,
             },
+            ast::GenericArgList(it) => generic_param::hints(hints, sema, config, it),
             // FIXME: fn-ptr type, dyn fn type, and trait object type elisions
             ast::Type(_) => None,
             _ => None,
@@ -549,6 +571,10 @@ mod tests {
         lifetime_elision_hints: LifetimeElisionHints::Never,
         closure_return_type_hints: ClosureReturnTypeHints::Never,
         closure_capture_hints: false,
+        turbofish_hints: false,
+        generic_parameter_hints: false,
+        implicit_drop_hints: false,
+        const_value_hints: false,
         adjustment_hints: AdjustmentHints::Never,
         adjustment_hints_mode: AdjustmentHintsMode::Prefix,
         adjustment_hints_hide_outside_unsafe: false,
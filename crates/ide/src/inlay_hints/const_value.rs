@@ -0,0 +1,145 @@
+//! Implementation of "constant value" inlay hints:
+//! ```no_run
+//! let mask = 1 << 12 /* = 4096 (0x1000) */;
+//! ```
+use hir::{CallableKind, Semantics};
+use ide_db::RootDatabase;
+use syntax::ast::{self, AstNode};
+
+use crate::{InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig, InlayKind};
+
+pub(super) fn hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<'_, RootDatabase>,
+    config: &InlayHintsConfig,
+    expr: &ast::Expr,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+
+    match expr {
+        ast::Expr::BinExpr(it) => {
+            if matches!(it.op_kind()?, ast::BinaryOp::Assignment { .. }) {
+                return None;
+            }
+        }
+        // `-1` is its own value already.
+        ast::Expr::PrefixExpr(it) => {
+            if matches!(it.expr()?, ast::Expr::Literal(_)) {
+                return None;
+            }
+        }
+        // Struct and variant constructors just spell out their value.
+        ast::Expr::CallExpr(it) => {
+            let callable = sema.type_of_expr(&it.expr()?)?.original.as_callable(sema.db)?;
+            if !matches!(callable.kind(), CallableKind::Function(_)) {
+                return None;
+            }
+        }
+        ast::Expr::MethodCallExpr(_) => (),
+        ast::Expr::BlockExpr(it) => {
+            if !matches!(it.modifier()?, ast::BlockModifier::Const(_)) {
+                return None;
+            }
+        }
+        _ => return None,
+    }
+
+    // Only the outermost constant operation gets a hint.
+    let parent = expr
+        .syntax()
+        .ancestors()
+        .skip(1)
+        .find(|it| !ast::ParenExpr::can_cast(it.kind()) && !ast::StmtList::can_cast(it.kind()));
+    let has_const_parent = match parent.and_then(ast::Expr::cast) {
+        Some(it @ (ast::Expr::BinExpr(_) | ast::Expr::PrefixExpr(_))) => {
+            sema.render_const_eval(&it).is_some()
+        }
+        Some(ast::Expr::BlockExpr(it)) => {
+            matches!(it.modifier(), Some(ast::BlockModifier::Const(_)))
+        }
+        _ => false,
+    };
+    if has_const_parent {
+        return None;
+    }
+
+    if sema.type_of_expr(expr)?.original.is_unit() {
+        return None;
+    }
+    let value = sema.render_const_eval(expr)?;
+    acc.push(InlayHint {
+        range: expr.syntax().text_range(),
+        kind: InlayKind::ConstValue,
+        label: InlayHintLabel::simple(format!("= {value}"), None, None),
+        text_edit: None,
+        position: InlayHintPosition::After,
+        pad_left: true,
+        pad_right: false,
+    });
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inlay_hints::tests::{check_with_config, DISABLED_CONFIG},
+        InlayHintsConfig,
+    };
+
+    const ONLY_CONST_VALUE_CONFIG: InlayHintsConfig =
+        InlayHintsConfig { const_value_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn operators_and_const_blocks() {
+        check_with_config(
+            ONLY_CONST_VALUE_CONFIG,
+            r#"
+const SHIFT: u32 = 12;
+
+fn main() {
+    let a = 1 << SHIFT;
+          //^^^^^^^^^^ = 4096 (0x1000)
+    let b = (2 + 3) * 4;
+          //^^^^^^^^^^^ = 20 (0x14)
+    let c = -(5 - 8);
+          //^^^^^^^^ = 3
+    let d = const { 3 > 2 };
+          //^^^^^^^^^^^^^^^ = true
+    let e = -1;
+    let f = a + 1;
+    let g = f + (2 + 2);
+               //^^^^^ = 4
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn const_fn_calls() {
+        check_with_config(
+            ONLY_CONST_VALUE_CONFIG,
+            r#"
+//- minicore: option
+const fn square(x: u64) -> u64 { x * x }
+fn not_const(x: u64) -> u64 { x }
+struct S(u8);
+impl S {
+    const fn get(&self) -> u8 { self.0 }
+}
+
+fn main() {
+    let a = square(16);
+          //^^^^^^^^^^ = 256 (0x100)
+    let b = not_const(16);
+    let c = S(5).get();
+          //^^^^^^^^^^ = 5
+    let d = S(5);
+    let e = Some(5);
+    let f = square(b);
+}
+"#,
+        );
+    }
+}
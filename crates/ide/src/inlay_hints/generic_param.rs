@@ -0,0 +1,131 @@
+//! Implementation of "generic parameter name" inlay hints:
+//! ```no_run
+//! let map: HashMap</* K = */String, /* V = */u32> = HashMap::new();
+//! ```
+use either::Either;
+use hir::{GenericDef, ModuleDef, PathResolution, Semantics};
+use ide_db::{base_db::FileRange, RootDatabase};
+use syntax::{
+    ast::{self, AstNode},
+    match_ast,
+};
+
+use crate::{
+    navigation_target::TryToNav, InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig,
+    InlayKind,
+};
+
+pub(super) fn hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<'_, RootDatabase>,
+    config: &InlayHintsConfig,
+    generic_arg_list: ast::GenericArgList,
+) -> Option<()> {
+    if !config.generic_parameter_hints {
+        return None;
+    }
+
+    let def: GenericDef = match_ast! {
+        match (generic_arg_list.syntax().parent()?) {
+            ast::PathSegment(segment) => match sema.resolve_path(&segment.parent_path())? {
+                PathResolution::Def(ModuleDef::Adt(it)) => it.into(),
+                PathResolution::Def(ModuleDef::Function(it)) => it.into(),
+                PathResolution::Def(ModuleDef::Trait(it)) => it.into(),
+                PathResolution::Def(ModuleDef::TypeAlias(it)) => it.into(),
+                // Variants take the generic arguments of their enum.
+                PathResolution::Def(ModuleDef::Variant(it)) => it.parent_enum(sema.db).into(),
+                _ => return None,
+            },
+            ast::MethodCallExpr(call) => sema.resolve_method_call(&call)?.into(),
+            _ => return None,
+        }
+    };
+
+    // `Self` of traits and `impl Trait` arguments can't be given explicitly.
+    let params = def.type_params(sema.db).into_iter().filter(|it| match it.split(sema.db) {
+        Either::Left(_) => true,
+        Either::Right(it) => !it.is_implicit(sema.db),
+    });
+    let args = generic_arg_list.generic_args().filter_map(|arg| match arg {
+        ast::GenericArg::TypeArg(it) => Some(it.syntax().clone()),
+        ast::GenericArg::ConstArg(it) => Some(it.syntax().clone()),
+        ast::GenericArg::AssocTypeArg(_) | ast::GenericArg::LifetimeArg(_) => None,
+    });
+
+    for (param, arg) in params.zip(args) {
+        let name = param.name(sema.db);
+        let name = name.display(sema.db).to_string();
+        // `Foo<T>` in a generic context is clear enough.
+        if arg.text() == name.as_str() {
+            continue;
+        }
+        let linked_location = param
+            .try_to_nav(sema.db)
+            .map(|it| FileRange { file_id: it.file_id, range: it.focus_or_full_range() });
+        acc.push(InlayHint {
+            range: arg.text_range(),
+            kind: InlayKind::GenericParameter,
+            label: InlayHintLabel::simple(format!("{name} ="), None, linked_location),
+            text_edit: None,
+            position: InlayHintPosition::Before,
+            pad_left: false,
+            pad_right: true,
+        });
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inlay_hints::tests::{check_with_config, DISABLED_CONFIG},
+        InlayHintsConfig,
+    };
+
+    const ONLY_GENERIC_PARAMETER_CONFIG: InlayHintsConfig =
+        InlayHintsConfig { generic_parameter_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn type_and_const_args() {
+        check_with_config(
+            ONLY_GENERIC_PARAMETER_CONFIG,
+            r#"
+struct Map<K, V>(K, V);
+struct Array<'a, T, const N: usize>(&'a [T; N]);
+
+fn f<T>(map: Map<String, u32>, array: Array<'static, T, 3>) {}
+               //^^^^^^ K =
+                       //^^^ V =
+                                                      //^ N =
+"#,
+        );
+    }
+
+    #[test]
+    fn paths_and_method_calls() {
+        check_with_config(
+            ONLY_GENERIC_PARAMETER_CONFIG,
+            r#"
+//- minicore: option
+struct S;
+impl S {
+    fn convert<From, To>(&self) {}
+}
+trait Tr<Rhs> {}
+impl Tr<u8> for S {}
+      //^^ Rhs =
+fn id<T>(t: T) -> T { t }
+
+fn main() {
+    S.convert::<u8, u16>();
+              //^^ From =
+                  //^^^ To =
+    id::<u8>(5);
+       //^^ T =
+    Option::<u8>::None;
+           //^^ T =
+}
+"#,
+        );
+    }
+}
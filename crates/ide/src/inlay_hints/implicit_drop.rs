@@ -0,0 +1,184 @@
+//! Implementation of "implicit drop" inlay hints:
+//! ```ignore
+//! fn main() {
+//!     let guard = lock();
+//!     /* drop(guard) */ }
+//! ```
+use hir::{DefWithBody, Semantics};
+use ide_db::RootDatabase;
+use syntax::ast::{self, AstNode};
+
+use crate::{InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig, InlayKind};
+
+pub(super) fn hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<'_, RootDatabase>,
+    config: &InlayHintsConfig,
+    def: &ast::Fn,
+) -> Option<()> {
+    if !config.implicit_drop_hints {
+        return None;
+    }
+
+    let def: DefWithBody = sema.to_def(def)?.into();
+    for drop in def.implicit_drops(sema.db) {
+        // Drops inside of macro expansions can't be pointed at.
+        if drop.expr.file_id.is_macro() {
+            continue;
+        }
+        let root = sema.parse_or_expand(drop.expr.file_id);
+        let expr = drop.expr.value.to_node(&root);
+        let (range, position) = match &expr {
+            // The local goes out of scope at the end of the block.
+            ast::Expr::BlockExpr(block) => {
+                (block.stmt_list()?.r_curly_token()?.text_range(), InlayHintPosition::Before)
+            }
+            // The expression leaves the scope of the local, or overwrites it, before it runs.
+            ast::Expr::BinExpr(_)
+            | ast::Expr::BreakExpr(_)
+            | ast::Expr::ContinueExpr(_)
+            | ast::Expr::ReturnExpr(_) => (expr.syntax().text_range(), InlayHintPosition::Before),
+            // The local is bound by the expression, and dropped once it is done.
+            _ => (expr.syntax().text_range(), InlayHintPosition::After),
+        };
+        let source = drop.local.primary_source(sema.db);
+        let name = drop.local.name(sema.db);
+        let (pad_left, pad_right) = match position {
+            InlayHintPosition::Before => (false, true),
+            InlayHintPosition::After => (true, false),
+        };
+        acc.push(InlayHint {
+            range,
+            kind: InlayKind::Drop,
+            label: InlayHintLabel::simple(
+                format!("drop({})", name.display(sema.db)),
+                None,
+                source.name().and_then(|name| name.syntax().original_file_range_opt(sema.db)),
+            ),
+            text_edit: None,
+            position,
+            pad_left,
+            pad_right,
+        });
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inlay_hints::tests::{check_with_config, DISABLED_CONFIG},
+        InlayHintsConfig,
+    };
+
+    const ONLY_DROP_CONFIG: InlayHintsConfig =
+        InlayHintsConfig { implicit_drop_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn scope_end() {
+        check_with_config(
+            ONLY_DROP_CONFIG,
+            r#"
+//- minicore: drop
+struct Guard;
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+struct NoDrop;
+
+fn main() {
+    let a = Guard;
+    let b = NoDrop;
+    let c = 2;
+    {
+        let d = (Guard, 5);
+    }
+  //^ drop(d)
+  }
+//^ drop(a)
+"#,
+        );
+    }
+
+    #[test]
+    fn moved_out() {
+        check_with_config(
+            ONLY_DROP_CONFIG,
+            r#"
+//- minicore: drop
+struct Guard;
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+fn consume(_: Guard) {}
+
+fn main() {
+    let a = Guard;
+    consume(a);
+    let b = Guard;
+    let c = b;
+    let d = Guard;
+    if true {
+        consume(d);
+    }
+  }
+//^ drop(d)
+//^ drop(c)
+"#,
+        );
+    }
+
+    #[test]
+    fn reassignment_and_early_exits() {
+        check_with_config(
+            ONLY_DROP_CONFIG,
+            r#"
+//- minicore: drop
+struct Guard;
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+fn main() {
+    let mut a = Guard;
+    a = Guard;
+  //^^^^^^^^^ drop(a)
+    loop {
+        let b = Guard;
+        if true {
+            break;
+          //^^^^^ drop(b)
+        }
+    }
+  //^ drop(b)
+    return;
+  //^^^^^^ drop(a)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn params_and_closures() {
+        check_with_config(
+            ONLY_DROP_CONFIG,
+            r#"
+//- minicore: drop
+struct Guard;
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+fn f(a: Guard, b: &Guard) {
+    let c = |d: Guard| {
+        let e = Guard;
+    };
+  //^ drop(e)
+  //^ drop(d)
+  }
+//^ drop(a)
+"#,
+        );
+    }
+}
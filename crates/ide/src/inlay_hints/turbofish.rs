@@ -0,0 +1,150 @@
+//! Implementation of "inferred turbofish" inlay hints:
+//! ```no_run
+//! let v: Vec<String> = iter.collect/* ::<Vec<String>> */();
+//! ```
+use ide_db::famous_defs::FamousDefs;
+use syntax::ast::{self, AstNode};
+
+use crate::{
+    inlay_hints::label_of_ty, InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig,
+    InlayKind,
+};
+
+pub(super) fn hints(
+    acc: &mut Vec<InlayHint>,
+    famous_defs @ FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig,
+    expr: &ast::Expr,
+) -> Option<()> {
+    if !config.turbofish_hints {
+        return None;
+    }
+
+    let (callable, name_ref) = match expr {
+        ast::Expr::MethodCallExpr(call) => {
+            if call.generic_arg_list().is_some() {
+                return None;
+            }
+            (sema.resolve_method_call_as_callable(call)?, call.name_ref()?)
+        }
+        ast::Expr::CallExpr(call) => {
+            let callee = call.expr()?;
+            let ast::Expr::PathExpr(path) = &callee else {
+                return None;
+            };
+            let segment = path.path()?.segment()?;
+            if segment.generic_arg_list().is_some() {
+                return None;
+            }
+            (sema.type_of_expr(&callee)?.original.as_callable(sema.db)?, segment.name_ref()?)
+        }
+        _ => return None,
+    };
+
+    let args = callable.inferred_type_args(sema.db)?;
+    if args.is_empty() || args.iter().any(|it| it.contains_unknown()) {
+        return None;
+    }
+
+    let mut label = InlayHintLabel::from("::<");
+    for (idx, arg) in args.iter().enumerate() {
+        if idx != 0 {
+            label.append_str(", ");
+        }
+        // Closures can't be named, but they can be left to inference.
+        if arg.is_closure() {
+            label.append_str("_");
+            continue;
+        }
+        label.parts.extend(label_of_ty(famous_defs, config, arg)?.parts);
+    }
+    label.append_str(">");
+
+    acc.push(InlayHint {
+        range: name_ref.syntax().text_range(),
+        kind: InlayKind::Turbofish,
+        label,
+        text_edit: None,
+        position: InlayHintPosition::After,
+        pad_left: false,
+        pad_right: false,
+    });
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inlay_hints::tests::{check_with_config, DISABLED_CONFIG},
+        InlayHintsConfig,
+    };
+
+    const ONLY_TURBOFISH_CONFIG: InlayHintsConfig =
+        InlayHintsConfig { turbofish_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn function_and_method_calls() {
+        check_with_config(
+            ONLY_TURBOFISH_CONFIG,
+            r#"
+struct S;
+impl S {
+    fn convert<T, U>(&self, t: T) -> U { loop {} }
+    fn not_generic(&self) {}
+}
+fn id<T>(t: T) -> T { t }
+
+fn main() {
+    let a = id(5u8);
+          //^^::<u8>
+    let b: u16 = S.convert(a);
+                 //^^^^^^^::<u8, u16>
+    S.not_generic();
+    let c = id::<u32>(5);
+    let d = S.convert::<u8, u32>(a);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_methods_only_show_own_params() {
+        check_with_config(
+            ONLY_TURBOFISH_CONFIG,
+            r#"
+trait Iter<T>: Sized {
+    fn collect<B: FromIter<T>>(self) -> B { loop {} }
+}
+trait FromIter<T> {}
+struct Wrapper<T>(T);
+impl<T> FromIter<T> for Wrapper<T> {}
+struct It;
+impl Iter<u8> for It {}
+
+fn main() {
+    let w: Wrapper<u8> = It.collect();
+                          //^^^^^^^::<Wrapper<u8>>
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn closures_and_unknown_types() {
+        check_with_config(
+            ONLY_TURBOFISH_CONFIG,
+            r#"
+fn call<F, R>(f: F) -> R { loop {} }
+fn impl_trait_arg(it: impl Sized) {}
+fn unknown<T>() -> T { loop {} }
+
+fn main() {
+    let x: u8 = call(|| 5u8);
+              //^^^^::<_, u8>
+    impl_trait_arg(5);
+    let y = unknown();
+}
+"#,
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use hir::Semantics;
+use hir::{MirEvalSandbox, Semantics};
 use ide_db::base_db::SourceDatabaseExt;
 use ide_db::RootDatabase;
 use ide_db::{base_db::FilePosition, LineIndexDatabase};
@@ -13,9 +13,13 @@ use syntax::{algo::find_node_at_offset, ast, AstNode};
 //
 // | VS Code | **rust-analyzer: Interpret Function**
 // |===
-pub(crate) fn interpret_function(db: &RootDatabase, position: FilePosition) -> String {
+pub(crate) fn interpret_function(
+    db: &RootDatabase,
+    position: FilePosition,
+    sandbox: MirEvalSandbox,
+) -> String {
     let start_time = Instant::now();
-    let mut result = find_and_interpret(db, position)
+    let mut result = find_and_interpret(db, position, sandbox)
         .unwrap_or_else(|| "Not inside a function body".to_string());
     let duration = Instant::now() - start_time;
     writeln!(result, "").unwrap();
@@ -24,7 +28,11 @@ pub(crate) fn interpret_function(db: &RootDatabase, position: FilePosition) -> S
     result
 }
 
-fn find_and_interpret(db: &RootDatabase, position: FilePosition) -> Option<String> {
+fn find_and_interpret(
+    db: &RootDatabase,
+    position: FilePosition,
+    sandbox: MirEvalSandbox,
+) -> Option<String> {
     let sema = Semantics::new(db);
     let source_file = sema.parse(position.file_id);
 
@@ -42,5 +50,5 @@ fn find_and_interpret(db: &RootDatabase, position: FilePosition) -> Option<Strin
         let path = path.as_deref().unwrap_or("<unknown file>");
         format!("file://{path}#{}:{}", line_col.line + 1, line_col.col)
     };
-    Some(def.eval(db, span_formatter))
+    Some(def.eval(db, sandbox, None, span_formatter))
 }
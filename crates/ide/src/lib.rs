@@ -52,6 +52,8 @@ mod static_index;
 mod status;
 mod syntax_highlighting;
 mod syntax_tree;
+mod test_explorer;
+mod type_hierarchy;
 mod typing;
 mod view_crate_graph;
 mod view_hir;
@@ -110,8 +112,9 @@ pub use crate::{
         tags::{Highlight, HlMod, HlMods, HlOperator, HlPunct, HlTag},
         HighlightConfig, HlRange,
     },
+    test_explorer::{TestItem, TestItemKind},
 };
-pub use hir::{Documentation, Semantics};
+pub use hir::{Documentation, ItemTreeCache, MirEvalSandbox, Semantics};
 pub use ide_assists::{
     Assist, AssistConfig, AssistId, AssistKind, AssistResolveStrategy, SingleResolve,
 };
@@ -128,7 +131,7 @@ pub use ide_db::{
     line_index::{LineCol, LineIndex},
     search::{ReferenceCategory, SearchScope},
     source_change::{FileSystemEdit, SourceChange},
-    symbol_index::Query,
+    symbol_index::{Query, SymbolIndexCache},
     RootDatabase, SymbolKind,
 };
 pub use ide_diagnostics::{Diagnostic, DiagnosticsConfig, ExprFillDefaultMode, Severity};
@@ -271,6 +274,15 @@ impl Analysis {
         self.with_db(move |db| prime_caches::parallel_prime_caches(db, num_worker_threads, &cb))
     }
 
+    /// Writes the `ItemTree`s and library symbol indices computed so far to the on-disk caches,
+    /// if there are any.
+    pub fn persist_caches(&self) -> Cancellable<()> {
+        self.with_db(|db| {
+            db.persist_item_tree_cache();
+            db.persist_symbol_index_cache();
+        })
+    }
+
     /// Gets the text of the source file.
     pub fn file_text(&self, file_id: FileId) -> Cancellable<Arc<str>> {
         self.with_db(|db| db.file_text(file_id))
@@ -326,8 +338,12 @@ impl Analysis {
         self.with_db(|db| view_mir::view_mir(db, position))
     }
 
-    pub fn interpret_function(&self, position: FilePosition) -> Cancellable<String> {
-        self.with_db(|db| interpret_function::interpret_function(db, position))
+    pub fn interpret_function(
+        &self,
+        position: FilePosition,
+        sandbox: MirEvalSandbox,
+    ) -> Cancellable<String> {
+        self.with_db(|db| interpret_function::interpret_function(db, position, sandbox))
     }
 
     pub fn view_item_tree(&self, file_id: FileId) -> Cancellable<String> {
@@ -511,6 +527,24 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
     }
 
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the supertypes of the type at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the subtypes of the type at the given file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancellable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -551,6 +585,21 @@ impl Analysis {
         self.with_db(|db| runnables::runnables(db, file_id))
     }
 
+    /// Returns the test explorer items of the local crates, without their tests.
+    pub fn discover_test_roots(&self) -> Cancellable<Vec<TestItem>> {
+        self.with_db(test_explorer::discover_test_roots)
+    }
+
+    /// Returns the test explorer items below the crate with the given test item id.
+    pub fn discover_tests_in_crate_by_test_id(&self, crate_id: &str) -> Cancellable<Vec<TestItem>> {
+        self.with_db(|db| test_explorer::discover_tests_in_crate_by_test_id(db, crate_id))
+    }
+
+    /// Returns the test explorer items of the tests defined in the given file.
+    pub fn discover_tests_in_file(&self, file_id: FileId) -> Cancellable<Vec<TestItem>> {
+        self.with_db(|db| test_explorer::discover_tests_in_file(db, file_id))
+    }
+
     /// Returns the set of tests for the given file position.
     pub fn related_tests(
         &self,
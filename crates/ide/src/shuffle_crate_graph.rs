@@ -14,7 +14,7 @@ use triomphe::Arc;
 // | VS Code | **rust-analyzer: Shuffle Crate Graph**
 // |===
 pub(crate) fn shuffle_crate_graph(db: &mut RootDatabase) {
-    let crate_graph = db.crate_graph();
+    let crate_graph = db.workspace_crate_graph();
     let proc_macros = db.proc_macros();
 
     let mut shuffled_ids = crate_graph.iter().collect::<Vec<_>>();
@@ -41,6 +41,7 @@ pub(crate) fn shuffle_crate_graph(db: &mut RootDatabase) {
             data.target_layout.clone(),
             data.channel,
         );
+        new_graph.set_target(new_id, data.target.clone());
         new_proc_macros.insert(new_id, proc_macros[&old_id].clone());
         map.insert(old_id, new_id);
     }
@@ -54,6 +55,18 @@ pub(crate) fn shuffle_crate_graph(db: &mut RootDatabase) {
         }
     }
 
-    db.set_crate_graph_with_durability(Arc::new(new_graph), Durability::HIGH);
+    // Ad-hoc crates keep their place after the workspace crates, only their dependencies move.
+    let ad_hoc_crates = db
+        .ad_hoc_crates()
+        .iter()
+        .cloned()
+        .map(|mut data| {
+            data.dependencies.iter_mut().for_each(|dep| dep.crate_id = map[&dep.crate_id]);
+            data
+        })
+        .collect::<Vec<_>>();
+
+    db.set_workspace_crate_graph_with_durability(Arc::new(new_graph), Durability::HIGH);
+    db.set_ad_hoc_crates_with_durability(Arc::from(ad_hoc_crates), Durability::LOW);
     db.set_proc_macros_with_durability(Arc::new(new_proc_macros), Durability::HIGH);
 }
@@ -124,6 +124,10 @@ impl StaticIndex<'_> {
                     binding_mode_hints: false,
                     max_length: Some(25),
                     closure_capture_hints: false,
+                    turbofish_hints: false,
+                    generic_parameter_hints: false,
+                    implicit_drop_hints: false,
+                    const_value_hints: false,
                     closing_brace_hints_min_lines: Some(25),
                 },
                 file_id,
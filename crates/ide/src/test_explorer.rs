@@ -0,0 +1,302 @@
+//! Discovers the tests of the workspace for the test explorer, grouped by crate and module.
+//!
+//! A test item id is the display name of its crate, followed by the path of the item in that
+//! crate, e.g. `foo::tests::it_works`. This makes the ids of test functions line up with the test
+//! names libtest reports. Local crates sharing a display name, like the library and the binary of a
+//! package, are told apart by a `#n` suffix, e.g. `foo#2`.
+
+use hir::{Crate, Module, Semantics};
+use ide_db::{
+    base_db::{CrateData, CrateId, FileId, SourceDatabase},
+    RootDatabase,
+};
+use syntax::TextRange;
+
+use crate::{
+    navigation_target::ToNav,
+    runnables::{runnable_fn, runnable_mod},
+    Runnable, RunnableKind,
+};
+
+#[derive(Debug)]
+pub struct TestItem {
+    pub id: String,
+    pub kind: TestItemKind,
+    pub label: String,
+    pub parent: Option<String>,
+    pub file: Option<FileId>,
+    pub text_range: Option<TextRange>,
+    pub runnable: Option<Runnable>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestItemKind {
+    Crate(CrateId),
+    Module,
+    Function,
+}
+
+/// Returns an item for each local crate, without looking for the tests inside of them.
+pub(crate) fn discover_test_roots(db: &RootDatabase) -> Vec<TestItem> {
+    let crate_graph = db.crate_graph();
+    crate_graph
+        .iter()
+        .filter(|&krate| crate_graph[krate].origin.is_local())
+        .map(|krate| crate_item(db, krate.into()))
+        .collect()
+}
+
+/// Returns all test modules and functions of the crate with the given test item id.
+pub(crate) fn discover_tests_in_crate_by_test_id(
+    db: &RootDatabase,
+    crate_test_id: &str,
+) -> Vec<TestItem> {
+    let crate_graph = db.crate_graph();
+    let krate = crate_graph.iter().find(|&krate| {
+        crate_graph[krate].origin.is_local() && self::crate_test_id(db, krate) == crate_test_id
+    });
+    match krate {
+        Some(krate) => discover_tests_in_crate(db, krate),
+        None => Vec::new(),
+    }
+}
+
+fn discover_tests_in_crate(db: &RootDatabase, crate_id: CrateId) -> Vec<TestItem> {
+    let sema = Semantics::new(db);
+    let krate = Crate::from(crate_id);
+    let mut res = Vec::new();
+    visit_module(&sema, krate.root_module(db), &crate_test_id(db, crate_id), &mut res);
+    res
+}
+
+/// Returns the test items defined in the given file, together with the items of the modules and
+/// crates containing them.
+pub(crate) fn discover_tests_in_file(db: &RootDatabase, file_id: FileId) -> Vec<TestItem> {
+    let sema = Semantics::new(db);
+    let mut res = Vec::new();
+    for module in sema.to_module_defs(file_id) {
+        let krate = module.krate();
+        if !krate.origin(db).is_local() {
+            continue;
+        }
+        let mut items = vec![crate_item(db, krate)];
+        let mut parent = crate_test_id(db, krate.into());
+        for ancestor in module.path_to_root(db).into_iter().rev().skip(1) {
+            let item = module_item(&sema, ancestor, &parent);
+            parent = item.id.clone();
+            items.push(item);
+        }
+        if visit_module(&sema, module, &parent, &mut items) {
+            res.extend(items);
+        }
+    }
+    res
+}
+
+fn crate_test_id(db: &RootDatabase, krate: CrateId) -> String {
+    let crate_graph = db.crate_graph();
+    let name = crate_display_name(&crate_graph[krate]);
+    let same_name_before = crate_graph
+        .iter()
+        .take_while(|&it| it != krate)
+        .filter(|&it| crate_graph[it].origin.is_local())
+        .filter(|&it| crate_display_name(&crate_graph[it]) == name)
+        .count();
+    match same_name_before {
+        0 => name,
+        n => format!("{name}#{}", n + 1),
+    }
+}
+
+fn crate_display_name(krate: &CrateData) -> String {
+    krate.display_name.as_ref().map_or_else(|| "unnamed".to_owned(), |it| it.to_string())
+}
+
+fn crate_item(db: &RootDatabase, krate: Crate) -> TestItem {
+    let nav = krate.root_module(db).to_nav(db);
+    TestItem {
+        id: crate_test_id(db, krate.into()),
+        kind: TestItemKind::Crate(krate.into()),
+        label: krate.display_name(db).map_or_else(|| "unnamed".to_owned(), |it| it.to_string()),
+        parent: None,
+        file: Some(nav.file_id),
+        text_range: Some(nav.full_range),
+        runnable: None,
+    }
+}
+
+fn module_item(sema: &Semantics<'_, RootDatabase>, module: Module, parent: &str) -> TestItem {
+    let db = sema.db;
+    let name = module.name(db).map(|it| it.display(db).to_string()).unwrap_or_default();
+    let nav = module.to_nav(db);
+    TestItem {
+        id: format!("{parent}::{name}"),
+        kind: TestItemKind::Module,
+        label: name,
+        parent: Some(parent.to_owned()),
+        file: Some(nav.file_id),
+        text_range: Some(nav.full_range),
+        runnable: runnable_mod(sema, module),
+    }
+}
+
+/// Pushes the items of the tests and test modules below `module` onto `acc`, returns whether there
+/// were any.
+fn visit_module(
+    sema: &Semantics<'_, RootDatabase>,
+    module: Module,
+    id: &str,
+    acc: &mut Vec<TestItem>,
+) -> bool {
+    let db = sema.db;
+    let len = acc.len();
+    for def in module.declarations(db) {
+        let hir::ModuleDef::Function(func) = def else { continue };
+        let Some(runnable) = runnable_fn(sema, func) else { continue };
+        if !matches!(runnable.kind, RunnableKind::Test { .. }) {
+            continue;
+        }
+        let label = func.name(db).display(db).to_string();
+        acc.push(TestItem {
+            id: format!("{id}::{label}"),
+            kind: TestItemKind::Function,
+            parent: Some(id.to_owned()),
+            file: Some(runnable.nav.file_id),
+            text_range: Some(runnable.nav.full_range),
+            label,
+            runnable: Some(runnable),
+        });
+    }
+    acc[len..].sort_by_key(|it| it.text_range.map(|range| range.start()));
+    for child in module.children(db) {
+        let item = module_item(sema, child, id);
+        let child_id = item.id.clone();
+        let idx = acc.len();
+        acc.push(item);
+        if !visit_module(sema, child, &child_id, acc) {
+            acc.truncate(idx);
+        }
+    }
+    acc.len() != len
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::{fixture, FileId};
+
+    use super::TestItem;
+
+    fn render(items: Vec<TestItem>) -> String {
+        items
+            .into_iter()
+            .map(|it| {
+                format!(
+                    "{} {:?} {:?} parent={:?} {:?}\n",
+                    it.id,
+                    it.kind,
+                    it.label,
+                    it.parent,
+                    it.file.zip(it.text_range)
+                )
+            })
+            .collect()
+    }
+
+    fn check_crate(ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let krate = analysis.crates_for(file_id).unwrap()[0];
+        let test_id = analysis
+            .discover_test_roots()
+            .unwrap()
+            .into_iter()
+            .find(|it| it.kind == super::TestItemKind::Crate(krate))
+            .unwrap()
+            .id;
+        expect.assert_eq(&render(analysis.discover_tests_in_crate_by_test_id(&test_id).unwrap()));
+    }
+
+    #[test]
+    fn discovers_roots() {
+        let (analysis, _) = fixture::file(
+            r#"
+//- /main.rs crate:main deps:lib
+fn main() {}
+//- /lib.rs crate:lib
+#[test]
+fn foo() {}
+"#,
+        );
+        expect![[r#"
+            main Crate(Idx::<CrateData>(0)) "main" parent=None Some((FileId(0), 0..13))
+            lib Crate(Idx::<CrateData>(1)) "lib" parent=None Some((FileId(1), 0..20))
+        "#]]
+        .assert_eq(&render(analysis.discover_test_roots().unwrap()));
+    }
+
+    #[test]
+    fn discovers_tests_in_crate() {
+        check_crate(
+            r#"
+//- /lib.rs crate:lib
+mod no_tests;
+mod nested {
+    mod tests {
+        #[test]
+        fn in_nested() {}
+    }
+}
+mod tests;
+
+#[test]
+fn at_root() {}
+
+fn not_a_test() {}
+//- /no_tests.rs
+fn foo() {}
+//- /tests.rs
+#[test]
+fn first() {}
+#[test]
+fn second() {}
+#[bench]
+fn bench() {}
+"#,
+            expect![[r#"
+                lib::at_root Function "at_root" parent=Some("lib") Some((FileId(0), 105..128))
+                lib::nested Module "nested" parent=Some("lib") Some((FileId(0), 14..92))
+                lib::nested::tests Module "tests" parent=Some("lib::nested") Some((FileId(0), 31..90))
+                lib::nested::tests::in_nested Function "in_nested" parent=Some("lib::nested::tests") Some((FileId(0), 51..84))
+                lib::tests Module "tests" parent=Some("lib") Some((FileId(2), 0..68))
+                lib::tests::first Function "first" parent=Some("lib::tests") Some((FileId(2), 0..21))
+                lib::tests::second Function "second" parent=Some("lib::tests") Some((FileId(2), 22..44))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn discovers_tests_in_file() {
+        let (analysis, _) = fixture::file(
+            r#"
+//- /lib.rs crate:lib
+mod tests;
+//- /tests.rs
+mod inner {
+    #[test]
+    fn foo() {}
+}
+#[test]
+fn bar() {}
+"#,
+        );
+        expect![[r#"
+            lib Crate(Idx::<CrateData>(0)) "lib" parent=None Some((FileId(0), 0..11))
+            lib::tests Module "tests" parent=Some("lib") Some((FileId(1), 0..62))
+            lib::tests::bar Function "bar" parent=Some("lib::tests") Some((FileId(1), 42..61))
+            lib::tests::inner Module "inner" parent=Some("lib::tests") Some((FileId(1), 0..41))
+            lib::tests::inner::foo Function "foo" parent=Some("lib::tests::inner") Some((FileId(1), 16..39))
+        "#]]
+        .assert_eq(&render(analysis.discover_tests_in_file(FileId(1)).unwrap()));
+    }
+}
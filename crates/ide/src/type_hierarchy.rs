@@ -0,0 +1,251 @@
+//! Entry point for type-hierarchy
+
+use hir::{HirDisplay, Impl, ModuleDef, Semantics};
+use ide_db::{
+    defs::{Definition, IdentClass},
+    helpers::pick_best_token,
+    FxIndexSet, RootDatabase,
+};
+use itertools::Itertools;
+use syntax::{AstNode, SyntaxKind::*, T};
+
+use crate::{FilePosition, NavigationTarget, RangeInfo, TryToNav};
+
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantics::new(db);
+    let RangeInfo { range, info: defs } = hierarchy_defs(&sema, position)?;
+    let navs = defs.into_iter().filter_map(|def| def.try_to_nav(db)).collect();
+    Some(RangeInfo { range, info: navs })
+}
+
+/// For a trait, returns its direct supertraits. For an ADT, returns the traits it implements.
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let defs = hierarchy_defs(&sema, position)?.info;
+
+    let mut res = FxIndexSet::default();
+    for def in defs {
+        match def {
+            Definition::Trait(trait_) => res.extend(
+                trait_.direct_supertraits(db).into_iter().filter_map(|it| it.try_to_nav(db)),
+            ),
+            Definition::Adt(adt) => res.extend(
+                Impl::all_for_type(db, adt.ty(db))
+                    .into_iter()
+                    .filter_map(|imp| imp.trait_(db))
+                    .filter_map(|it| it.try_to_nav(db)),
+            ),
+            _ => (),
+        }
+    }
+    Some(res.into_iter().collect())
+}
+
+/// For a trait, returns the traits which have it as a direct supertrait as well as all types
+/// implementing it. ADTs have no subtypes.
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let defs = hierarchy_defs(&sema, position)?.info;
+
+    let mut res = FxIndexSet::default();
+    for def in defs {
+        let Definition::Trait(trait_) = def else { continue };
+
+        // Subtraits can only be declared in crates which depend on the trait's crate.
+        let krate = trait_.module(db).krate();
+        for krate in krate.transitive_reverse_dependencies(db) {
+            for module in krate.modules(db) {
+                let subtraits = module
+                    .declarations(db)
+                    .into_iter()
+                    .filter_map(|def| match def {
+                        ModuleDef::Trait(it) => Some(it),
+                        _ => None,
+                    })
+                    .filter(|it| it.direct_supertraits(db).contains(&trait_))
+                    .filter_map(|it| it.try_to_nav(db));
+                res.extend(subtraits);
+            }
+        }
+
+        for imp in Impl::all_for_trait(db, trait_) {
+            let self_ty = imp.self_ty(db);
+            let nav = match self_ty.as_adt() {
+                Some(adt) => adt.try_to_nav(db),
+                // Blanket impls and impls for non-ADT types are represented by the impl itself.
+                None => imp.try_to_nav(db).map(|mut nav| {
+                    nav.name = self_ty.display(db).to_string().into();
+                    nav
+                }),
+            };
+            res.extend(nav);
+        }
+    }
+    Some(res.into_iter().collect())
+}
+
+fn hierarchy_defs(
+    sema: &Semantics<'_, RootDatabase>,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<Definition>>> {
+    let file = sema.parse(position.file_id);
+    let token =
+        pick_best_token(file.syntax().token_at_offset(position.offset), |kind| match kind {
+            IDENT | T![Self] => 1,
+            _ => 0,
+        })?;
+    let range = token.text_range();
+    let defs = sema
+        .descend_into_macros(token)
+        .into_iter()
+        .filter_map(|token| IdentClass::classify_token(sema, &token))
+        .flat_map(IdentClass::definitions_no_ops)
+        .filter(|def| matches!(def, Definition::Trait(_) | Definition::Adt(_)))
+        .unique()
+        .collect::<Vec<_>>();
+    if defs.is_empty() {
+        return None;
+    }
+    Some(RangeInfo { range, info: defs })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::base_db::FilePosition;
+    use itertools::Itertools;
+
+    use crate::fixture;
+
+    fn check_hierarchy(
+        ra_fixture: &str,
+        expected: Expect,
+        expected_supertypes: Expect,
+        expected_subtypes: Expect,
+    ) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+
+        let mut navs = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        expected.assert_eq(&nav.debug_render());
+
+        let item_pos =
+            FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+        let supertypes = analysis.supertypes(item_pos).unwrap().unwrap();
+        expected_supertypes
+            .assert_eq(&supertypes.into_iter().map(|nav| nav.debug_render()).join("\n"));
+
+        let subtypes = analysis.subtypes(item_pos).unwrap().unwrap();
+        expected_subtypes.assert_eq(&subtypes.into_iter().map(|nav| nav.debug_render()).join("\n"));
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_trait() {
+        check_hierarchy(
+            r#"
+trait Super {}
+trait Other {}
+trait Tr$0ait: Super + Other {}
+trait Sub: Trait {}
+struct S;
+impl Super for S {}
+impl Other for S {}
+impl Trait for S {}
+"#,
+            expect![["Trait Trait FileId(0) 30..59 36..41"]],
+            expect![[r#"
+                Super Trait FileId(0) 0..14 6..11
+                Other Trait FileId(0) 15..29 21..26"#]],
+            expect![[r#"
+                Sub Trait FileId(0) 60..79 66..69
+                S Struct FileId(0) 80..89 87..88"#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_trait_ref() {
+        check_hierarchy(
+            r#"
+trait Super {}
+trait Trait: Super {}
+struct S;
+impl Super for S {}
+impl Tr$0ait for S {}
+"#,
+            expect![["Trait Trait FileId(0) 15..36 21..26"]],
+            expect![["Super Trait FileId(0) 0..14 6..11"]],
+            expect![["S Struct FileId(0) 37..46 44..45"]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_adt() {
+        check_hierarchy(
+            r#"
+trait Trait {}
+trait Other {}
+enum E$0 {}
+impl Trait for E {}
+impl Other for E {}
+impl E {}
+"#,
+            expect![["E Enum FileId(0) 30..39 35..36"]],
+            expect![[r#"
+                Trait Trait FileId(0) 0..14 6..11
+                Other Trait FileId(0) 15..29 21..26"#]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_blanket_and_builtin_impls() {
+        check_hierarchy(
+            r#"
+trait Tr$0ait {}
+impl Trait for i32 {}
+impl<T: Copy> Trait for [T; 2] {}
+"#,
+            expect![["Trait Trait FileId(0) 0..14 6..11"]],
+            expect![[]],
+            expect![[r#"
+                i32 Impl FileId(0) 15..36 30..33
+                [T; 2] Impl FileId(0) 37..70 61..67"#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_across_crates() {
+        check_hierarchy(
+            r#"
+//- /lib.rs crate:lib
+pub trait Trait$0 {}
+//- /main.rs crate:main deps:lib
+struct S;
+impl lib::Trait for S {}
+trait Sub: lib::Trait {}
+"#,
+            expect![["Trait Trait FileId(0) 0..18 10..15"]],
+            expect![[]],
+            expect![[r#"
+                Sub Trait FileId(1) 35..59 41..44
+                S Struct FileId(1) 0..9 7..8"#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_not_a_type() {
+        let (analysis, pos) = fixture::position(
+            r#"
+fn foo$0() {}
+"#,
+        );
+        assert!(analysis.type_hierarchy(pos).unwrap().is_none());
+    }
+}
@@ -12,6 +12,7 @@ use dashmap::{DashMap, SharedValue};
 use hashbrown::{hash_map::RawEntryMut, HashMap};
 use once_cell::sync::OnceCell;
 use rustc_hash::FxHasher;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use triomphe::Arc;
 
 type InternMap<T> = DashMap<Arc<T>, (), BuildHasherDefault<FxHasher>>;
@@ -176,6 +177,26 @@ impl<T: Display + Internable + ?Sized> Display for Interned<T> {
     }
 }
 
+/// Serializes the interned value itself; deserializing interns it again.
+impl<T: Serialize + Internable + ?Sized> Serialize for Interned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (*self.arc).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Internable> Deserialize<'de> for Interned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Interned::new)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned<str> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Interned::new_str(&s))
+    }
+}
+
 pub struct InternStorage<T: ?Sized> {
     map: OnceCell<InternMap<T>>,
 }
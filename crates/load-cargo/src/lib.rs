@@ -6,23 +6,28 @@ use std::{collections::hash_map::Entry, mem, path::Path, sync};
 
 use ::tt::token_id as tt;
 use crossbeam_channel::{unbounded, Receiver};
-use ide::{AnalysisHost, Change, SourceRoot};
+use ide::{AnalysisHost, Change, ItemTreeCache, SourceRoot, SymbolIndexCache};
 use ide_db::{
     base_db::{
-        CrateGraph, Env, ProcMacro, ProcMacroExpander, ProcMacroExpansionError, ProcMacroKind,
-        ProcMacroLoadResult, ProcMacros,
+        CrateGraph, Env, ProcMacro, ProcMacroDiagnosticLevel, ProcMacroExpander,
+        ProcMacroExpansion, ProcMacroExpansionDiagnostic, ProcMacroExpansionError, ProcMacroKind,
+        ProcMacroLoadResult, ProcMacroSpanData, ProcMacros, SourceDatabaseExt,
     },
-    FxHashMap,
+    FxHashMap, RootDatabase,
 };
 use itertools::Itertools;
 use proc_macro_api::{MacroDylib, ProcMacroServer};
 use project_model::{CargoConfig, PackageRoot, ProjectManifest, ProjectWorkspace};
-use vfs::{file_set::FileSetConfig, loader::Handle, AbsPath, AbsPathBuf, VfsPath};
+use vfs::{file_set::FileSetConfig, loader::Handle, AbsPath, AbsPathBuf, FileId, VfsPath};
 
 pub struct LoadCargoConfig {
     pub load_out_dirs_from_check: bool,
     pub with_proc_macro_server: ProcMacroServerChoice,
     pub prefill_caches: bool,
+    /// Persists the `ItemTree`s and symbol indices of libraries in the workspace's target
+    /// directory, and reuses them on the next load. The string identifies the rust-analyzer version
+    /// writing the caches.
+    pub persist_caches: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -66,9 +71,9 @@ pub fn load_workspace(
     let proc_macro_server = match &load_config.with_proc_macro_server {
         ProcMacroServerChoice::Sysroot => ws
             .find_sysroot_proc_macro_srv()
-            .and_then(|it| ProcMacroServer::spawn(it).map_err(Into::into)),
+            .and_then(|it| ProcMacroServer::spawn(it, true).map_err(Into::into)),
         ProcMacroServerChoice::Explicit(path) => {
-            ProcMacroServer::spawn(path.clone()).map_err(Into::into)
+            ProcMacroServer::spawn(path.clone(), true).map_err(Into::into)
         }
         ProcMacroServerChoice::None => Err(anyhow::format_err!("proc macro server disabled")),
     };
@@ -105,6 +110,7 @@ pub fn load_workspace(
             .collect()
     };
 
+    let cache_dir = analysis_cache_dir(&ws);
     let project_folders = ProjectFolders::new(&[ws], &[]);
     loader.set_config(vfs::loader::Config {
         load: project_folders.load,
@@ -112,20 +118,58 @@ pub fn load_workspace(
         version: 0,
     });
 
-    let host = load_crate_graph(
+    let mut host = load_crate_graph(
         crate_graph,
         proc_macros,
         project_folders.source_root_config,
         &mut vfs,
         &receiver,
     );
+    if let Some((version, dir)) = load_config.persist_caches.as_deref().zip(cache_dir) {
+        let files = item_tree_cache_files(host.raw_database(), &vfs);
+        let item_tree_cache = ItemTreeCache::load(dir.join("item-trees").as_ref(), version, files);
+        let files = symbol_index_cache_files(&vfs);
+        let symbol_index_cache =
+            SymbolIndexCache::load(dir.join("symbols").as_ref(), version, files);
+        let db = host.raw_database_mut();
+        db.set_item_tree_cache(Some(item_tree_cache));
+        db.set_symbol_index_cache(Some(symbol_index_cache));
+    }
 
     if load_config.prefill_caches {
         host.analysis().parallel_prime_caches(1, |_| {})?;
+        host.analysis().persist_caches()?;
     }
     Ok((host, vfs, proc_macro_server.ok()))
 }
 
+/// Returns the directory of the on-disk caches of `ws`, in its Cargo target directory. The
+/// `ItemTree`s are stored in its `item-trees` subdirectory, the symbol indices in `symbols`.
+pub fn analysis_cache_dir(ws: &ProjectWorkspace) -> Option<AbsPathBuf> {
+    match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => {
+            Some(cargo.target_directory().join("rust-analyzer"))
+        }
+        ProjectWorkspace::Json { .. } | ProjectWorkspace::DetachedFiles { .. } => None,
+    }
+}
+
+/// Returns the files whose `ItemTree`s get persisted, along with their paths. These are the Rust
+/// files of library source roots, as workspace members change too often.
+pub fn item_tree_cache_files(db: &RootDatabase, vfs: &vfs::Vfs) -> Vec<(FileId, String)> {
+    vfs.iter()
+        .filter(|(_, path)| matches!(path.name_and_extension(), Some((_, Some("rs")))))
+        .filter(|&(file_id, _)| db.source_root(db.file_source_root(file_id)).is_library)
+        .map(|(file_id, path)| (file_id, path.to_string()))
+        .collect()
+}
+
+/// Returns the files persisted symbol indices may refer to, along with their paths. These are all
+/// files, as the symbols of libraries may come from any of their dependencies.
+pub fn symbol_index_cache_files(vfs: &vfs::Vfs) -> Vec<(FileId, String)> {
+    vfs.iter().map(|(file_id, path)| (file_id, path.to_string())).collect()
+}
+
 #[derive(Default)]
 pub struct ProjectFolders {
     pub load: Vec<vfs::loader::Entry>,
@@ -376,16 +420,52 @@ impl ProcMacroExpander for Expander {
         subtree: &tt::Subtree,
         attrs: Option<&tt::Subtree>,
         env: &Env,
-    ) -> Result<tt::Subtree, ProcMacroExpansionError> {
+        spans: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
         let env = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
-        match self.0.expand(subtree, attrs, env) {
-            Ok(Ok(subtree)) => Ok(subtree),
+        let span_data = proc_macro_api::msg::SpanData {
+            text: spans.text.clone(),
+            spans: spans
+                .spans
+                .iter()
+                .map(|(id, range)| (id.0, range.start().into(), range.end().into()))
+                .collect(),
+            location: spans.location.as_ref().map(|it| proc_macro_api::msg::SpanLocation {
+                file_path: it.file_path.clone(),
+                offset: it.offset.into(),
+                line: it.line,
+                column: it.column,
+            }),
+        };
+        match self.0.expand(subtree, attrs, env, span_data) {
+            Ok(Ok(expansion)) => Ok(ProcMacroExpansion {
+                subtree: expansion.subtree,
+                diagnostics: expansion.diagnostics.into_iter().map(convert_diagnostic).collect(),
+                tracked_env_vars: expansion.tracked_env_vars,
+                tracked_paths: expansion.tracked_paths,
+                needs_location: expansion.needs_location,
+            }),
             Ok(Err(err)) => Err(ProcMacroExpansionError::Panic(err.0)),
+            Err(err) if err.crashed => Err(ProcMacroExpansionError::ServerCrashed(err.message)),
             Err(err) => Err(ProcMacroExpansionError::System(err.to_string())),
         }
     }
 }
 
+fn convert_diagnostic(diagnostic: proc_macro_api::msg::Diagnostic) -> ProcMacroExpansionDiagnostic {
+    ProcMacroExpansionDiagnostic {
+        level: match diagnostic.level {
+            proc_macro_api::msg::DiagnosticLevel::Error => ProcMacroDiagnosticLevel::Error,
+            proc_macro_api::msg::DiagnosticLevel::Warning => ProcMacroDiagnosticLevel::Warning,
+            proc_macro_api::msg::DiagnosticLevel::Note => ProcMacroDiagnosticLevel::Note,
+            proc_macro_api::msg::DiagnosticLevel::Help => ProcMacroDiagnosticLevel::Help,
+        },
+        message: diagnostic.message,
+        spans: diagnostic.spans.into_iter().map(tt::TokenId).collect(),
+        children: diagnostic.children.into_iter().map(convert_diagnostic).collect(),
+    }
+}
+
 /// Dummy identity expander, used for attribute proc-macros that are deliberately ignored by the user.
 #[derive(Debug)]
 struct IdentityExpander;
@@ -396,8 +476,9 @@ impl ProcMacroExpander for IdentityExpander {
         subtree: &tt::Subtree,
         _: Option<&tt::Subtree>,
         _: &Env,
-    ) -> Result<tt::Subtree, ProcMacroExpansionError> {
-        Ok(subtree.clone())
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        Ok(subtree.clone().into())
     }
 }
 
@@ -411,8 +492,9 @@ impl ProcMacroExpander for EmptyExpander {
         _: &tt::Subtree,
         _: Option<&tt::Subtree>,
         _: &Env,
-    ) -> Result<tt::Subtree, ProcMacroExpansionError> {
-        Ok(tt::Subtree::empty())
+        _: &ProcMacroSpanData,
+    ) -> Result<ProcMacroExpansion, ProcMacroExpansionError> {
+        Ok(tt::Subtree::empty().into())
     }
 }
 
@@ -430,6 +512,7 @@ mod tests {
             load_out_dirs_from_check: false,
             with_proc_macro_server: ProcMacroServerChoice::None,
             prefill_caches: false,
+            persist_caches: None,
         };
         let (host, _vfs, _proc_macro) =
             load_workspace_at(path, &cargo_config, &load_cargo_config, &|_| {}).unwrap();
@@ -1,6 +1,7 @@
 //! Conversions between [`SyntaxNode`] and [`tt::TokenTree`].
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 use stdx::{always, non_empty_vec::NonEmptyVec};
 use syntax::{
     ast::{self, make::tokens::doc_comment},
@@ -84,7 +85,7 @@ pub fn syntax_node_to_token_map_with_modifications(
     (c.id_alloc.map, c.id_alloc.next_id)
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SyntheticTokenId(pub u32);
 
 #[derive(Debug, Clone)]
@@ -3,11 +3,12 @@
 use std::hash::Hash;
 
 use parser::{SyntaxKind, T};
+use serde::{Deserialize, Serialize};
 use syntax::{TextRange, TextSize};
 
 use crate::syntax_bridge::SyntheticTokenId;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 enum TokenTextRange {
     Token(TextRange),
     Delimiter(TextRange),
@@ -29,7 +30,7 @@ impl TokenTextRange {
 }
 
 /// Maps `tt::TokenId` to the relative range of the original token.
-#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct TokenMap {
     /// Maps `tt::TokenId` to the *relative* source range.
     entries: Vec<(tt::TokenId, TokenTextRange)>,
@@ -60,6 +61,19 @@ impl TokenMap {
             .filter_map(move |(_, range)| range.by_kind(kind))
     }
 
+    /// Returns the range of the token with the given id. For delimiters, this is the range of the
+    /// whole delimited token tree.
+    pub fn range_by_token_id(&self, token_id: tt::TokenId) -> Option<TextRange> {
+        self.entries().find(|&(tid, _)| tid == token_id).map(|(_, range)| range)
+    }
+
+    /// Returns all tokens with their ranges, see [`TokenMap::range_by_token_id`].
+    pub fn entries(&self) -> impl Iterator<Item = (tt::TokenId, TextRange)> + '_ {
+        self.entries.iter().map(|&(tid, range)| match range {
+            TokenTextRange::Token(it) | TokenTextRange::Delimiter(it) => (tid, it),
+        })
+    }
+
     pub fn synthetic_token_id(&self, token_id: tt::TokenId) -> Option<SyntheticTokenId> {
         self.synthetic_entries.iter().find(|(tid, _)| *tid == token_id).map(|(_, id)| *id)
     }
@@ -20,7 +20,10 @@ use serde::{Deserialize, Serialize};
 use ::tt::token_id as tt;
 
 use crate::{
-    msg::{ExpandMacro, FlatTree, PanicMessage},
+    msg::{
+        Diagnostic, ExpandMacro, FlatTree, PanicMessage, SpanData, EXPANSION_SIDE_EFFECTS_VERSION,
+        SPAN_DATA_VERSION,
+    },
     process::ProcMacroProcessSrv,
 };
 
@@ -37,13 +40,26 @@ pub enum ProcMacroKind {
 /// and runs actual macro expansion functions.
 #[derive(Debug)]
 pub struct ProcMacroServer {
+    processes: ServerProcesses,
+    /// Whether the server processes cache the expansions of the macros.
+    cache_expansions: bool,
+    /// The files the macros of this server declared to read since the last call to
+    /// [`ProcMacroServer::take_tracked_paths`].
+    tracked_paths: Arc<Mutex<Vec<AbsPathBuf>>>,
+}
+
+#[derive(Debug)]
+enum ServerProcesses {
     /// Currently, the proc macro process expands all procedural macros sequentially.
     ///
     /// That means that concurrent salsa requests may block each other when expanding proc macros,
     /// which is unfortunate, but simple and good enough for the time being.
     ///
     /// Therefore, we just wrap the `ProcMacroProcessSrv` in a mutex here.
-    process: Arc<Mutex<ProcMacroProcessSrv>>,
+    Shared(Arc<Mutex<ProcMacroProcessSrv>>),
+    /// In isolation mode, every dylib is loaded into a server process of its own, spawned from
+    /// this executable, so that a crashing proc-macro only takes down the macros of its own crate.
+    Isolated(AbsPathBuf),
 }
 
 pub struct MacroDylib {
@@ -58,11 +74,15 @@ impl MacroDylib {
 
 /// A handle to a specific macro (a `#[proc_macro]` annotated function).
 ///
-/// It exists within a context of a specific [`ProcMacroProcess`] -- currently
-/// we share a single expander process for all macros.
+/// It exists within a context of a specific [`ProcMacroProcess`] -- we share a single expander
+/// process for all macros, unless the server runs in isolation mode.
 #[derive(Debug, Clone)]
 pub struct ProcMacro {
     process: Arc<Mutex<ProcMacroProcessSrv>>,
+    /// Whether `process` is private to this macro's dylib, and thus gets restarted if it crashes.
+    isolated: bool,
+    /// Shared with the [`ProcMacroServer`] the macro was loaded by.
+    tracked_paths: Arc<Mutex<Vec<AbsPathBuf>>>,
     dylib_path: AbsPathBuf,
     name: String,
     kind: ProcMacroKind,
@@ -81,6 +101,8 @@ impl PartialEq for ProcMacro {
 pub struct ServerError {
     pub message: String,
     pub io: Option<io::Error>,
+    /// Whether the server process crashed, and was restarted for the next request.
+    pub crashed: bool,
 }
 
 impl fmt::Display for ServerError {
@@ -98,31 +120,95 @@ pub struct MacroPanic {
     pub message: String,
 }
 
+/// The output of a successful expansion.
+///
+/// Servers older than [`EXPANSION_SIDE_EFFECTS_VERSION`] only report the expanded tree, in which
+/// case the other fields are empty.
+#[derive(Debug)]
+pub struct MacroExpansion {
+    pub subtree: tt::Subtree,
+    pub diagnostics: Vec<Diagnostic>,
+    pub tracked_env_vars: Vec<(String, Option<String>)>,
+    pub tracked_paths: Vec<AbsPathBuf>,
+    pub needs_location: bool,
+}
+
+impl From<tt::Subtree> for MacroExpansion {
+    fn from(subtree: tt::Subtree) -> Self {
+        MacroExpansion {
+            subtree,
+            diagnostics: Vec::new(),
+            tracked_env_vars: Vec::new(),
+            tracked_paths: Vec::new(),
+            needs_location: false,
+        }
+    }
+}
+
 impl ProcMacroServer {
     /// Spawns an external process as the proc macro server and returns a client connected to it.
-    pub fn spawn(process_path: AbsPathBuf) -> io::Result<ProcMacroServer> {
-        let process = ProcMacroProcessSrv::run(process_path)?;
-        Ok(ProcMacroServer { process: Arc::new(Mutex::new(process)) })
+    ///
+    /// Unless `cache_expansions` is set, the server runs a macro again when it is asked to expand
+    /// the same input again.
+    pub fn spawn(process_path: AbsPathBuf, cache_expansions: bool) -> io::Result<ProcMacroServer> {
+        let process = ProcMacroProcessSrv::run(process_path, cache_expansions)?;
+        Ok(ProcMacroServer {
+            processes: ServerProcesses::Shared(Arc::new(Mutex::new(process))),
+            cache_expansions,
+            tracked_paths: Default::default(),
+        })
+    }
+
+    /// Like [`ProcMacroServer::spawn`], but runs every loaded dylib in a server process of its
+    /// own, which is restarted if one of the dylib's macros crashes it.
+    ///
+    /// The processes are spawned when their dylibs get loaded.
+    pub fn isolated(process_path: AbsPathBuf, cache_expansions: bool) -> ProcMacroServer {
+        ProcMacroServer {
+            processes: ServerProcesses::Isolated(process_path),
+            cache_expansions,
+            tracked_paths: Default::default(),
+        }
     }
 
     pub fn load_dylib(&self, dylib: MacroDylib) -> Result<Vec<ProcMacro>, ServerError> {
         let _p = profile::span("ProcMacroClient::load_dylib");
+        let process = match &self.processes {
+            ServerProcesses::Isolated(process_path) => {
+                let process = ProcMacroProcessSrv::run(process_path.clone(), self.cache_expansions)
+                    .map_err(|err| ServerError {
+                        message: "failed to spawn proc-macro server".into(),
+                        io: Some(err),
+                        crashed: false,
+                    })?;
+                Arc::new(Mutex::new(process))
+            }
+            ServerProcesses::Shared(process) => process.clone(),
+        };
         let macros =
-            self.process.lock().unwrap_or_else(|e| e.into_inner()).find_proc_macros(&dylib.path)?;
+            process.lock().unwrap_or_else(|e| e.into_inner()).find_proc_macros(&dylib.path)?;
 
         match macros {
             Ok(macros) => Ok(macros
                 .into_iter()
                 .map(|(name, kind)| ProcMacro {
-                    process: self.process.clone(),
+                    process: process.clone(),
+                    isolated: matches!(self.processes, ServerProcesses::Isolated(_)),
+                    tracked_paths: self.tracked_paths.clone(),
                     name,
                     kind,
                     dylib_path: dylib.path.clone(),
                 })
                 .collect()),
-            Err(message) => Err(ServerError { message, io: None }),
+            Err(message) => Err(ServerError { message, io: None, crashed: false }),
         }
     }
+
+    /// Returns the files the macros of this server declared to read via
+    /// `proc_macro::tracked_path::path` since the last call.
+    pub fn take_tracked_paths(&self) -> Vec<AbsPathBuf> {
+        std::mem::take(&mut *self.tracked_paths.lock().unwrap_or_else(|e| e.into_inner()))
+    }
 }
 
 impl ProcMacro {
@@ -139,8 +225,10 @@ impl ProcMacro {
         subtree: &tt::Subtree,
         attr: Option<&tt::Subtree>,
         env: Vec<(String, String)>,
-    ) -> Result<Result<tt::Subtree, PanicMessage>, ServerError> {
-        let version = self.process.lock().unwrap_or_else(|e| e.into_inner()).version();
+        span_data: SpanData,
+    ) -> Result<Result<MacroExpansion, PanicMessage>, ServerError> {
+        let mut process = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        let version = process.version();
         let current_dir = env
             .iter()
             .find(|(name, _)| name == "CARGO_MANIFEST_DIR")
@@ -153,17 +241,120 @@ impl ProcMacro {
             lib: self.dylib_path.to_path_buf().into(),
             env,
             current_dir,
+            span_data: (version >= SPAN_DATA_VERSION).then_some(span_data),
         };
 
-        let request = msg::Request::ExpandMacro(task);
-        let response = self.process.lock().unwrap_or_else(|e| e.into_inner()).send_task(request)?;
+        let request = if version >= EXPANSION_SIDE_EFFECTS_VERSION {
+            msg::Request::ExpandMacroExtended(task)
+        } else {
+            msg::Request::ExpandMacro(task)
+        };
+        let response = match process.send_task(request) {
+            Ok(response) => response,
+            Err(err) if self.isolated && process.has_exited() => {
+                let message = format!(
+                    "proc-macro server crashed while expanding `{}` and has been restarted",
+                    self.name
+                );
+                tracing::error!(%err, "{message}");
+                if let Err(err) = process.restart() {
+                    tracing::error!(%err, "failed to restart proc-macro server");
+                }
+                return Err(ServerError { message, io: None, crashed: true });
+            }
+            Err(err) => return Err(err),
+        };
+        drop(process);
         match response {
             msg::Response::ExpandMacro(it) => {
-                Ok(it.map(|tree| FlatTree::to_subtree(tree, version)))
+                Ok(it.map(|tree| FlatTree::to_subtree(tree, version).into()))
             }
+            msg::Response::ExpandMacroExtended(it) => Ok(it.map(|it| {
+                let tracked_paths: Vec<_> = it
+                    .tracked_paths
+                    .into_iter()
+                    .filter_map(|path| AbsPathBuf::try_from(path).ok())
+                    .collect();
+                self.tracked_paths
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend(tracked_paths.iter().cloned());
+                MacroExpansion {
+                    subtree: FlatTree::to_subtree(it.tree, version),
+                    diagnostics: it.diagnostics,
+                    tracked_env_vars: it.tracked_env_vars,
+                    tracked_paths,
+                    needs_location: it.needs_location,
+                }
+            })),
             msg::Response::ListMacros(..) | msg::Response::ApiVersionCheck(..) => {
-                Err(ServerError { message: "unexpected response".to_string(), io: None })
+                Err(ServerError {
+                    message: "unexpected response".to_string(),
+                    io: None,
+                    crashed: false,
+                })
             }
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+
+    /// Writes a server that speaks just enough of the protocol to expand a single macro, and
+    /// crashes the first time it is asked to.
+    fn crash_once_server(dir: &AbsPathBuf) -> AbsPathBuf {
+        let expansion = serde_json::to_string(&FlatTree::new(
+            &tt::Subtree::empty(),
+            msg::VERSION_CHECK_VERSION,
+        ))
+        .unwrap();
+        let crashed = dir.join("crashed");
+        let script = format!(
+            r#"#!/bin/sh
+while read -r request; do
+    case "$request" in
+        *ApiVersionCheck*) echo '{{"ApiVersionCheck":{version}}}' ;;
+        *ListMacros*) echo '{{"ListMacros":{{"Ok":[["crash_once","FuncLike"]]}}}}' ;;
+        *ExpandMacro*)
+            if [ -e "{crashed}" ]; then
+                echo '{{"ExpandMacro":{{"Ok":{expansion}}}}}'
+            else
+                touch "{crashed}"
+                exit 1
+            fi ;;
+    esac
+done
+"#,
+            version = msg::VERSION_CHECK_VERSION,
+            crashed = crashed,
+        );
+        let path = dir.join("server.sh");
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn isolated_server_restarts_after_crash() {
+        let dir = std::env::temp_dir().join(format!("ra-proc-macro-crash-{}", std::process::id()));
+        let dir = AbsPathBuf::assert(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let server = ProcMacroServer::isolated(crash_once_server(&dir), true);
+        let macros = server.load_dylib(MacroDylib::new(dir.join("macros.so"))).ok().unwrap();
+        let expand =
+            || macros[0].expand(&tt::Subtree::empty(), None, Vec::new(), SpanData::default());
+
+        let err = expand().err().expect("the first expansion crashes the server");
+        assert!(err.crashed, "{err}");
+        let expansion = expand().ok().expect("the server has been restarted").ok().unwrap();
+        assert_eq!(expansion.subtree, tt::Subtree::empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
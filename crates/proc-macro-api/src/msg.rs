@@ -16,14 +16,22 @@ pub use crate::msg::flat::FlatTree;
 pub const NO_VERSION_CHECK_VERSION: u32 = 0;
 pub const VERSION_CHECK_VERSION: u32 = 1;
 pub const ENCODE_CLOSE_SPAN_VERSION: u32 = 2;
+pub const EXPANSION_SIDE_EFFECTS_VERSION: u32 = 3;
+pub const SPAN_DATA_VERSION: u32 = 4;
 
-pub const CURRENT_API_VERSION: u32 = ENCODE_CLOSE_SPAN_VERSION;
+pub const CURRENT_API_VERSION: u32 = SPAN_DATA_VERSION;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
-    ListMacros { dylib_path: PathBuf },
+    ListMacros {
+        dylib_path: PathBuf,
+    },
     ExpandMacro(ExpandMacro),
     ApiVersionCheck {},
+    /// Like [`Request::ExpandMacro`], but answered with [`Response::ExpandMacroExtended`].
+    ///
+    /// Only sent to servers that speak [`EXPANSION_SIDE_EFFECTS_VERSION`].
+    ExpandMacroExtended(ExpandMacro),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,11 +39,47 @@ pub enum Response {
     ListMacros(Result<Vec<(String, ProcMacroKind)>, String>),
     ExpandMacro(Result<FlatTree, PanicMessage>),
     ApiVersionCheck(u32),
+    ExpandMacroExtended(Result<ExpandMacroExtended, PanicMessage>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PanicMessage(pub String);
 
+/// The result of an expansion, together with everything the macro did besides producing tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandMacroExtended {
+    pub tree: FlatTree,
+    /// Diagnostics emitted via `proc_macro::Diagnostic::emit`.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Environment variables the macro read via `proc_macro::tracked_env::var`, with the value it
+    /// observed.
+    #[serde(default)]
+    pub tracked_env_vars: Vec<(String, Option<String>)>,
+    /// Files the macro declared a dependency on via `proc_macro::tracked_path::path`.
+    pub tracked_paths: Vec<PathBuf>,
+    /// Whether the macro asked for the location of a span while [`SpanData::location`] was not
+    /// sent.
+    #[serde(default)]
+    pub needs_location: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub message: String,
+    /// The spans the diagnostic points at, as token ids of the macro input.
+    pub spans: Vec<u32>,
+    pub children: Vec<Diagnostic>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExpandMacro {
     /// Argument of macro call.
@@ -59,6 +103,33 @@ pub struct ExpandMacro {
     pub env: Vec<(String, String)>,
 
     pub current_dir: Option<String>,
+
+    /// Source information for the spans of `macro_body` and `attributes`, only sent to servers
+    /// that speak [`SPAN_DATA_VERSION`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span_data: Option<SpanData>,
+}
+
+#[derive(Debug, Default, Hash, Serialize, Deserialize)]
+pub struct SpanData {
+    /// The source text of the macro input.
+    pub text: String,
+    /// The byte ranges of the input tokens in `text`, as `(token id, start, end)`.
+    pub spans: Vec<(u32, u32, u32)>,
+    pub location: Option<SpanLocation>,
+}
+
+/// Where [`SpanData::text`] is located.
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+pub struct SpanLocation {
+    /// `None` if the text is part of another macro expansion.
+    pub file_path: Option<String>,
+    /// The byte offset of the text in its file.
+    pub offset: u32,
+    /// The 1-based line the text starts at.
+    pub line: u32,
+    /// The 0-based column, in characters, the text starts at.
+    pub column: u32,
 }
 
 pub trait Message: Serialize + DeserializeOwned {
@@ -156,6 +227,7 @@ mod tests {
             lib: std::env::current_dir().unwrap(),
             env: Default::default(),
             current_dir: Default::default(),
+            span_data: None,
         };
 
         let json = serde_json::to_string(&task).unwrap();
@@ -44,7 +44,7 @@ use crate::{
     tt::{self, TokenId},
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash)]
 pub struct FlatTree {
     subtree: Vec<u32>,
     literal: Vec<u32>,
@@ -3,6 +3,8 @@
 use std::{
     io::{self, BufRead, BufReader, Write},
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    thread,
+    time::Duration,
 };
 
 use paths::{AbsPath, AbsPathBuf};
@@ -15,19 +17,31 @@ use crate::{
 
 #[derive(Debug)]
 pub(crate) struct ProcMacroProcessSrv {
-    _process: Process,
+    process: Process,
+    process_path: AbsPathBuf,
+    cache_expansions: bool,
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
     version: u32,
 }
 
 impl ProcMacroProcessSrv {
-    pub(crate) fn run(process_path: AbsPathBuf) -> io::Result<ProcMacroProcessSrv> {
+    pub(crate) fn run(
+        process_path: AbsPathBuf,
+        cache_expansions: bool,
+    ) -> io::Result<ProcMacroProcessSrv> {
         let create_srv = |null_stderr| {
-            let mut process = Process::run(process_path.clone(), null_stderr)?;
+            let mut process = Process::run(process_path.clone(), null_stderr, cache_expansions)?;
             let (stdin, stdout) = process.stdio().expect("couldn't access child stdio");
 
-            io::Result::Ok(ProcMacroProcessSrv { _process: process, stdin, stdout, version: 0 })
+            io::Result::Ok(ProcMacroProcessSrv {
+                process,
+                process_path: process_path.clone(),
+                cache_expansions,
+                stdin,
+                stdout,
+                version: 0,
+            })
         };
         let mut srv = create_srv(true)?;
         tracing::info!("sending version check");
@@ -56,15 +70,40 @@ impl ProcMacroProcessSrv {
         self.version
     }
 
+    /// Whether the server process is no longer running, e.g. because a proc-macro aborted it.
+    pub(crate) fn has_exited(&mut self) -> bool {
+        // A dying process closes its pipes before it is done exiting, so give it a moment.
+        for _ in 0..10 {
+            if !matches!(self.process.child.try_wait(), Ok(None)) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    /// Replaces the server process with a freshly spawned one.
+    ///
+    /// The new process loads dylibs lazily when asked to expand one of their macros, so the
+    /// handles of already loaded macros stay valid.
+    pub(crate) fn restart(&mut self) -> io::Result<()> {
+        *self = ProcMacroProcessSrv::run(self.process_path.clone(), self.cache_expansions)?;
+        Ok(())
+    }
+
     pub(crate) fn version_check(&mut self) -> Result<u32, ServerError> {
         let request = Request::ApiVersionCheck {};
         let response = self.send_task(request)?;
 
         match response {
             Response::ApiVersionCheck(version) => Ok(version),
-            Response::ExpandMacro { .. } | Response::ListMacros { .. } => {
-                Err(ServerError { message: "unexpected response".to_string(), io: None })
-            }
+            Response::ExpandMacro { .. }
+            | Response::ExpandMacroExtended { .. }
+            | Response::ListMacros { .. } => Err(ServerError {
+                message: "unexpected response".to_string(),
+                io: None,
+                crashed: false,
+            }),
         }
     }
 
@@ -78,9 +117,13 @@ impl ProcMacroProcessSrv {
 
         match response {
             Response::ListMacros(it) => Ok(it),
-            Response::ExpandMacro { .. } | Response::ApiVersionCheck { .. } => {
-                Err(ServerError { message: "unexpected response".to_string(), io: None })
-            }
+            Response::ExpandMacro { .. }
+            | Response::ExpandMacroExtended { .. }
+            | Response::ApiVersionCheck { .. } => Err(ServerError {
+                message: "unexpected response".to_string(),
+                io: None,
+                crashed: false,
+            }),
         }
     }
 
@@ -96,8 +139,8 @@ struct Process {
 }
 
 impl Process {
-    fn run(path: AbsPathBuf, null_stderr: bool) -> io::Result<Process> {
-        let child = JodChild(mk_child(&path, null_stderr)?);
+    fn run(path: AbsPathBuf, null_stderr: bool, cache_expansions: bool) -> io::Result<Process> {
+        let child = JodChild(mk_child(&path, null_stderr, cache_expansions)?);
         Ok(Process { child })
     }
 
@@ -110,13 +153,16 @@ impl Process {
     }
 }
 
-fn mk_child(path: &AbsPath, null_stderr: bool) -> io::Result<Child> {
-    Command::new(path.as_os_str())
-        .env("RUST_ANALYZER_INTERNALS_DO_NOT_USE", "this is unstable")
+fn mk_child(path: &AbsPath, null_stderr: bool, cache_expansions: bool) -> io::Result<Child> {
+    let mut cmd = Command::new(path.as_os_str());
+    cmd.env("RUST_ANALYZER_INTERNALS_DO_NOT_USE", "this is unstable")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(if null_stderr { Stdio::null() } else { Stdio::inherit() })
-        .spawn()
+        .stderr(if null_stderr { Stdio::null() } else { Stdio::inherit() });
+    if !cache_expansions {
+        cmd.env("RA_DONT_CACHE_PROC_MACRO_EXPANSIONS", "1");
+    }
+    cmd.spawn()
 }
 
 fn send_request(
@@ -125,9 +171,15 @@ fn send_request(
     req: Request,
     buf: &mut String,
 ) -> Result<Response, ServerError> {
-    req.write(&mut writer)
-        .map_err(|err| ServerError { message: "failed to write request".into(), io: Some(err) })?;
-    let res = Response::read(&mut reader, buf)
-        .map_err(|err| ServerError { message: "failed to read response".into(), io: Some(err) })?;
-    res.ok_or_else(|| ServerError { message: "server exited".into(), io: None })
+    req.write(&mut writer).map_err(|err| ServerError {
+        message: "failed to write request".into(),
+        io: Some(err),
+        crashed: false,
+    })?;
+    let res = Response::read(&mut reader, buf).map_err(|err| ServerError {
+        message: "failed to read response".into(),
+        io: Some(err),
+        crashed: false,
+    })?;
+    res.ok_or_else(|| ServerError { message: "server exited".into(), io: None, crashed: false })
 }
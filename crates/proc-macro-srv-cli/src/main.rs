@@ -39,7 +39,12 @@ fn run() -> io::Result<()> {
             msg::Request::ListMacros { dylib_path } => {
                 msg::Response::ListMacros(srv.list_macros(&dylib_path))
             }
-            msg::Request::ExpandMacro(task) => msg::Response::ExpandMacro(srv.expand(task)),
+            msg::Request::ExpandMacro(task) => {
+                msg::Response::ExpandMacro(srv.expand(task).map(|it| it.tree))
+            }
+            msg::Request::ExpandMacroExtended(task) => {
+                msg::Response::ExpandMacroExtended(srv.expand(task))
+            }
             msg::Request::ApiVersionCheck {} => {
                 msg::Response::ApiVersionCheck(proc_macro_api::msg::CURRENT_API_VERSION)
             }
@@ -0,0 +1,71 @@
+//! A cache of expansion results, so that expanding the same macro with the same input again, e.g.
+//! after rust-analyzer evicted the expansion from its own caches, does not run the macro again.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+};
+
+use proc_macro_api::msg;
+
+/// The maximum number of expansions to keep around.
+const CAPACITY: usize = 1024;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ExpansionKey {
+    /// Hash of the contents of the dylib defining the macro.
+    dylib: u64,
+    macro_name: String,
+    /// Hash of the macro input, including the attribute arguments and span information.
+    input: u64,
+    /// Hash of the environment the macro is expanded in.
+    env: u64,
+}
+
+impl ExpansionKey {
+    pub(crate) fn new(dylib: u64, task: &msg::ExpandMacro) -> ExpansionKey {
+        ExpansionKey {
+            dylib,
+            macro_name: task.macro_name.clone(),
+            input: hash(&(&task.macro_body, &task.attributes, &task.span_data)),
+            env: hash(&(&task.env, &task.current_dir)),
+        }
+    }
+}
+
+fn hash(it: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    it.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Default)]
+pub(crate) struct ExpansionCache {
+    entries: HashMap<ExpansionKey, msg::ExpandMacroExtended>,
+    /// The keys of `entries` in insertion order, so that the oldest entries are evicted first.
+    order: VecDeque<ExpansionKey>,
+}
+
+impl ExpansionCache {
+    pub(crate) fn get(&self, key: &ExpansionKey) -> Option<msg::ExpandMacroExtended> {
+        self.entries.get(key).cloned()
+    }
+
+    pub(crate) fn insert(&mut self, key: ExpansionKey, expansion: &msg::ExpandMacroExtended) {
+        // The contents of the files a macro reads are not part of the key, so its next expansion
+        // may differ even for the same input.
+        if !expansion.tracked_paths.is_empty() {
+            return;
+        }
+        if self.entries.contains_key(&key) {
+            return;
+        }
+        if self.entries.len() >= CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key.clone(), expansion.clone());
+        self.order.push_back(key);
+    }
+}
@@ -1,8 +1,10 @@
 //! Handles dynamic library loading for proc macro
 
 use std::{
+    collections::hash_map::DefaultHasher,
     fmt,
-    fs::File,
+    fs::{self, File},
+    hash::{Hash, Hasher},
     io,
     path::{Path, PathBuf},
 };
@@ -13,6 +15,8 @@ use object::Object;
 use paths::AbsPath;
 use proc_macro_api::{read_dylib_info, ProcMacroKind};
 
+use crate::server::{SideEffects, SpanInfo};
+
 const NEW_REGISTRAR_SYMBOL: &str = "_rustc_proc_macro_decls_";
 
 fn invalid_data_err(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
@@ -132,6 +136,8 @@ impl ProcMacroLibraryLibloading {
 
 pub struct Expander {
     inner: ProcMacroLibraryLibloading,
+    /// Hash of the dylib's contents.
+    hash: u64,
 }
 
 impl Expander {
@@ -142,9 +148,17 @@ impl Expander {
 
         let lib = ensure_file_with_lock_free_access(&lib)?;
 
+        let mut hasher = DefaultHasher::new();
+        fs::read(&lib)?.hash(&mut hasher);
+        let hash = hasher.finish();
+
         let library = ProcMacroLibraryLibloading::open(lib.as_ref())?;
 
-        Ok(Expander { inner: library })
+        Ok(Expander { inner: library, hash })
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
     pub fn expand(
@@ -152,8 +166,9 @@ impl Expander {
         macro_name: &str,
         macro_body: &crate::tt::Subtree,
         attributes: Option<&crate::tt::Subtree>,
-    ) -> Result<crate::tt::Subtree, String> {
-        let result = self.inner.proc_macros.expand(macro_name, macro_body, attributes);
+        spans: SpanInfo,
+    ) -> Result<(crate::tt::Subtree, SideEffects), String> {
+        let result = self.inner.proc_macros.expand(macro_name, macro_body, attributes, spans);
         result.map_err(|e| e.as_str().unwrap_or_else(|| "<unknown error>".to_string()))
     }
 
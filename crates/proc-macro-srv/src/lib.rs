@@ -17,6 +17,7 @@
 
 extern crate proc_macro;
 
+mod cache;
 mod dylib;
 mod server;
 mod proc_macros;
@@ -41,20 +42,38 @@ use ::tt::token_id as tt;
 // see `build.rs`
 include!(concat!(env!("OUT_DIR"), "/rustc_version.rs"));
 
-#[derive(Default)]
 pub struct ProcMacroSrv {
     expanders: HashMap<(PathBuf, SystemTime), dylib::Expander>,
+    /// `None` if caching has been disabled by setting `RA_DONT_CACHE_PROC_MACRO_EXPANSIONS`.
+    cache: Option<cache::ExpansionCache>,
+}
+
+impl Default for ProcMacroSrv {
+    fn default() -> Self {
+        let cache = env::var("RA_DONT_CACHE_PROC_MACRO_EXPANSIONS")
+            .is_err()
+            .then(cache::ExpansionCache::default);
+        ProcMacroSrv { expanders: HashMap::default(), cache }
+    }
 }
 
 const EXPANDER_STACK_SIZE: usize = 8 * 1024 * 1024;
 
 impl ProcMacroSrv {
-    pub fn expand(&mut self, task: msg::ExpandMacro) -> Result<msg::FlatTree, msg::PanicMessage> {
-        let expander = self.expander(task.lib.as_ref()).map_err(|err| {
+    pub fn expand(
+        &mut self,
+        mut task: msg::ExpandMacro,
+    ) -> Result<msg::ExpandMacroExtended, msg::PanicMessage> {
+        let expander = Self::expander(&mut self.expanders, task.lib.as_ref()).map_err(|err| {
             debug_assert!(false, "should list macros before asking to expand");
             msg::PanicMessage(format!("failed to load macro: {err}"))
         })?;
 
+        let cache_key = cache::ExpansionKey::new(expander.hash(), &task);
+        if let Some(expansion) = self.cache.as_ref().and_then(|cache| cache.get(&cache_key)) {
+            return Ok(expansion);
+        }
+
         let prev_env = EnvSnapshot::new();
         for (k, v) in &task.env {
             env::set_var(k, v);
@@ -72,14 +91,21 @@ impl ProcMacroSrv {
 
         let macro_body = task.macro_body.to_subtree(CURRENT_API_VERSION);
         let attributes = task.attributes.map(|it| it.to_subtree(CURRENT_API_VERSION));
+        let spans = server::SpanInfo::new(task.span_data.take());
         let result = thread::scope(|s| {
             let thread = thread::Builder::new()
                 .stack_size(EXPANDER_STACK_SIZE)
                 .name(task.macro_name.clone())
                 .spawn_scoped(s, || {
-                    expander
-                        .expand(&task.macro_name, &macro_body, attributes.as_ref())
-                        .map(|it| msg::FlatTree::new(&it, CURRENT_API_VERSION))
+                    expander.expand(&task.macro_name, &macro_body, attributes.as_ref(), spans).map(
+                        |(it, side_effects)| msg::ExpandMacroExtended {
+                            tree: msg::FlatTree::new(&it, CURRENT_API_VERSION),
+                            diagnostics: side_effects.diagnostics,
+                            tracked_env_vars: side_effects.tracked_env_vars,
+                            tracked_paths: side_effects.tracked_paths,
+                            needs_location: side_effects.needs_location,
+                        },
+                    )
                 });
             let res = match thread {
                 Ok(handle) => handle.join(),
@@ -104,23 +130,30 @@ impl ProcMacroSrv {
             }
         }
 
-        result.map_err(msg::PanicMessage)
+        let expansion = result.map_err(msg::PanicMessage)?;
+        if let Some(cache) = &mut self.cache {
+            cache.insert(cache_key, &expansion);
+        }
+        Ok(expansion)
     }
 
     pub fn list_macros(
         &mut self,
         dylib_path: &Path,
     ) -> Result<Vec<(String, ProcMacroKind)>, String> {
-        let expander = self.expander(dylib_path)?;
+        let expander = Self::expander(&mut self.expanders, dylib_path)?;
         Ok(expander.list_macros())
     }
 
-    fn expander(&mut self, path: &Path) -> Result<&dylib::Expander, String> {
+    fn expander<'a>(
+        expanders: &'a mut HashMap<(PathBuf, SystemTime), dylib::Expander>,
+        path: &Path,
+    ) -> Result<&'a dylib::Expander, String> {
         let time = fs::metadata(path)
             .and_then(|it| it.modified())
             .map_err(|err| format!("Failed to get file metadata for {}: {err}", path.display()))?;
 
-        Ok(match self.expanders.entry((path.to_path_buf(), time)) {
+        Ok(match expanders.entry((path.to_path_buf(), time)) {
             Entry::Vacant(v) => {
                 v.insert(dylib::Expander::new(path).map_err(|err| {
                     format!("Cannot create expander for {}: {err}", path.display())
@@ -1,9 +1,15 @@
 //! Proc macro ABI
 
+use std::{cell::RefCell, rc::Rc};
+
 use libloading::Library;
 use proc_macro_api::{ProcMacroKind, RustCInfo};
 
-use crate::{dylib::LoadProcMacroDylibError, server::SYMBOL_INTERNER, tt};
+use crate::{
+    dylib::LoadProcMacroDylibError,
+    server::{RustAnalyzer, SideEffects, SpanInfo, SYMBOL_INTERNER},
+    tt,
+};
 
 pub(crate) struct ProcMacros {
     exported_macros: Vec<proc_macro::bridge::client::ProcMacro>,
@@ -45,12 +51,24 @@ impl ProcMacros {
         macro_name: &str,
         macro_body: &tt::Subtree,
         attributes: Option<&tt::Subtree>,
-    ) -> Result<tt::Subtree, crate::PanicMessage> {
+        spans: SpanInfo,
+    ) -> Result<(tt::Subtree, SideEffects), crate::PanicMessage> {
         let parsed_body = crate::server::TokenStream::with_subtree(macro_body.clone());
 
         let parsed_attributes = attributes.map_or(crate::server::TokenStream::new(), |attr| {
             crate::server::TokenStream::with_subtree(attr.clone())
         });
+        let spans = Rc::new(spans);
+        let side_effects = Rc::<RefCell<SideEffects>>::default();
+        let server = || RustAnalyzer {
+            interner: &SYMBOL_INTERNER,
+            spans: spans.clone(),
+            side_effects: side_effects.clone(),
+        };
+        let finish = |res: Result<crate::server::TokenStream, proc_macro::bridge::PanicMessage>| {
+            let side_effects = side_effects.take();
+            res.map(|it| (it.into_subtree(), side_effects)).map_err(crate::PanicMessage::from)
+        };
 
         for proc_macro in &self.exported_macros {
             match proc_macro {
@@ -59,34 +77,34 @@ impl ProcMacros {
                 } if *trait_name == macro_name => {
                     let res = client.run(
                         &proc_macro::bridge::server::SameThread,
-                        crate::server::RustAnalyzer { interner: &SYMBOL_INTERNER },
+                        server(),
                         parsed_body,
                         true,
                     );
-                    return res.map(|it| it.into_subtree()).map_err(crate::PanicMessage::from);
+                    return finish(res);
                 }
                 proc_macro::bridge::client::ProcMacro::Bang { name, client }
                     if *name == macro_name =>
                 {
                     let res = client.run(
                         &proc_macro::bridge::server::SameThread,
-                        crate::server::RustAnalyzer { interner: &SYMBOL_INTERNER },
+                        server(),
                         parsed_body,
                         true,
                     );
-                    return res.map(|it| it.into_subtree()).map_err(crate::PanicMessage::from);
+                    return finish(res);
                 }
                 proc_macro::bridge::client::ProcMacro::Attr { name, client }
                     if *name == macro_name =>
                 {
                     let res = client.run(
                         &proc_macro::bridge::server::SameThread,
-                        crate::server::RustAnalyzer { interner: &SYMBOL_INTERNER },
+                        server(),
                         parsed_attributes,
                         parsed_body,
                         true,
                     );
-                    return res.map(|it| it.into_subtree()).map_err(crate::PanicMessage::from);
+                    return finish(res);
                 }
                 _ => continue,
             }
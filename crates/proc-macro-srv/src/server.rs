@@ -5,8 +5,6 @@
 //! we could provide any TokenStream implementation.
 //! The original idea from fedochet is using proc-macro2 as backend,
 //! we use tt instead for better integration with RA.
-//!
-//! FIXME: No span and source file information is implemented yet
 
 use proc_macro::{
     bridge::{self, server},
@@ -20,7 +18,15 @@ use token_stream::TokenStreamBuilder;
 mod symbol;
 pub use symbol::*;
 
-use std::ops::{Bound, Range};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::{Bound, Range},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use proc_macro_api::msg;
 
 use crate::tt;
 
@@ -35,14 +41,66 @@ type Span = tt::TokenId;
 
 #[derive(Clone)]
 pub struct SourceFile {
-    // FIXME stub
+    path: Option<String>,
 }
 
 pub struct FreeFunctions;
 
 pub struct RustAnalyzer {
-    // FIXME: store span information here.
     pub(crate) interner: SymbolInternerRef,
+    pub(crate) spans: Rc<SpanInfo>,
+    /// Shared with the caller, as the server itself is consumed by the expansion.
+    pub(crate) side_effects: Rc<RefCell<SideEffects>>,
+}
+
+/// Source information about the tokens of the macro input, as sent by the client.
+#[derive(Default, Debug)]
+pub struct SpanInfo {
+    /// The source text of the macro input.
+    text: String,
+    /// Ranges of the input tokens, relative to `text`.
+    ranges: HashMap<Span, Range<usize>>,
+    location: Option<msg::SpanLocation>,
+}
+
+impl SpanInfo {
+    pub fn new(data: Option<msg::SpanData>) -> SpanInfo {
+        let Some(data) = data else { return SpanInfo::default() };
+        let ranges = data
+            .spans
+            .into_iter()
+            .map(|(id, start, end)| (tt::TokenId(id), start as usize..end as usize))
+            .collect();
+        SpanInfo { text: data.text, ranges, location: data.location }
+    }
+
+    fn range(&self, span: Span) -> Option<Range<usize>> {
+        self.ranges.get(&span).cloned().filter(|range| self.text.get(range.clone()).is_some())
+    }
+
+    /// Line and column of the given offset into the macro input, provided the location of the
+    /// input is known.
+    fn line_column(&self, offset: usize) -> Option<LineColumn> {
+        let location = self.location.as_ref()?;
+        let before = &self.text[..offset];
+        let line = location.line as usize + before.matches('\n').count();
+        let column = match before.rfind('\n') {
+            Some(idx) => before[idx + 1..].chars().count(),
+            None => location.column as usize + before.chars().count(),
+        };
+        Some(LineColumn { line, column })
+    }
+}
+
+/// Everything a macro did during its expansion besides producing output tokens.
+#[derive(Default, Debug)]
+pub struct SideEffects {
+    pub diagnostics: Vec<msg::Diagnostic>,
+    pub tracked_env_vars: Vec<(String, Option<String>)>,
+    pub tracked_paths: Vec<PathBuf>,
+    /// Whether the macro asked for information that depends on the location of the macro call
+    /// which was not sent along.
+    pub needs_location: bool,
 }
 
 impl server::Types for RustAnalyzer {
@@ -54,11 +112,21 @@ impl server::Types for RustAnalyzer {
 }
 
 impl server::FreeFunctions for RustAnalyzer {
-    fn track_env_var(&mut self, _var: &str, _value: Option<&str>) {
-        // FIXME: track env var accesses
-        // https://github.com/rust-lang/rust/pull/71858
+    fn track_env_var(&mut self, var: &str, value: Option<&str>) {
+        self.side_effects
+            .borrow_mut()
+            .tracked_env_vars
+            .push((var.to_owned(), value.map(ToOwned::to_owned)));
+    }
+    fn track_path(&mut self, path: &str) {
+        // Relative paths are resolved against the working directory, which we set to the manifest
+        // directory of the crate being expanded.
+        let path = match std::env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => PathBuf::from(path),
+        };
+        self.side_effects.borrow_mut().tracked_paths.push(path);
     }
-    fn track_path(&mut self, _path: &str) {}
 
     fn literal_from_str(
         &mut self,
@@ -73,8 +141,8 @@ impl server::FreeFunctions for RustAnalyzer {
         })
     }
 
-    fn emit_diagnostic(&mut self, _: bridge::Diagnostic<Self::Span>) {
-        // FIXME handle diagnostic
+    fn emit_diagnostic(&mut self, diagnostic: bridge::Diagnostic<Self::Span>) {
+        self.side_effects.borrow_mut().diagnostics.push(diagnostic_to_msg(diagnostic));
     }
 }
 
@@ -221,6 +289,20 @@ impl server::TokenStream for RustAnalyzer {
     }
 }
 
+fn diagnostic_to_msg(diagnostic: bridge::Diagnostic<Span>) -> msg::Diagnostic {
+    msg::Diagnostic {
+        level: match diagnostic.level {
+            proc_macro::Level::Warning => msg::DiagnosticLevel::Warning,
+            proc_macro::Level::Note => msg::DiagnosticLevel::Note,
+            proc_macro::Level::Help => msg::DiagnosticLevel::Help,
+            _ => msg::DiagnosticLevel::Error,
+        },
+        message: diagnostic.message,
+        spans: diagnostic.spans.into_iter().map(|span| span.0).collect(),
+        children: diagnostic.children.into_iter().map(diagnostic_to_msg).collect(),
+    }
+}
+
 fn delim_to_internal(d: proc_macro::Delimiter, span: bridge::DelimSpan<Span>) -> tt::Delimiter {
     let kind = match d {
         proc_macro::Delimiter::Parenthesis => tt::DelimiterKind::Parenthesis,
@@ -256,16 +338,24 @@ fn spacing_to_external(spacing: Spacing) -> proc_macro::Spacing {
     }
 }
 
+impl RustAnalyzer {
+    fn location(&mut self) -> Option<&msg::SpanLocation> {
+        if self.spans.location.is_none() {
+            self.side_effects.borrow_mut().needs_location = true;
+        }
+        self.spans.location.as_ref()
+    }
+}
+
 impl server::SourceFile for RustAnalyzer {
-    // FIXME these are all stubs
-    fn eq(&mut self, _file1: &Self::SourceFile, _file2: &Self::SourceFile) -> bool {
-        true
+    fn eq(&mut self, file1: &Self::SourceFile, file2: &Self::SourceFile) -> bool {
+        file1.path == file2.path
     }
-    fn path(&mut self, _file: &Self::SourceFile) -> String {
-        String::new()
+    fn path(&mut self, file: &Self::SourceFile) -> String {
+        file.path.clone().unwrap_or_default()
     }
-    fn is_real(&mut self, _file: &Self::SourceFile) -> bool {
-        true
+    fn is_real(&mut self, file: &Self::SourceFile) -> bool {
+        file.path.is_some()
     }
 }
 
@@ -274,7 +364,8 @@ impl server::Span for RustAnalyzer {
         format!("{:?}", span.0)
     }
     fn source_file(&mut self, _span: Self::Span) -> Self::SourceFile {
-        SourceFile {}
+        // All spans we know about come from the macro input, which lives in a single file.
+        SourceFile { path: self.location().and_then(|it| it.file_path.clone()) }
     }
     fn save_span(&mut self, _span: Self::Span) -> usize {
         // FIXME stub
@@ -284,33 +375,40 @@ impl server::Span for RustAnalyzer {
         // FIXME stub
         tt::TokenId::unspecified()
     }
-    /// Recent feature, not yet in the proc_macro
-    ///
-    /// See PR:
-    /// https://github.com/rust-lang/rust/pull/55780
-    fn source_text(&mut self, _span: Self::Span) -> Option<String> {
-        None
+    fn source_text(&mut self, span: Self::Span) -> Option<String> {
+        let range = self.spans.range(span)?;
+        Some(self.spans.text[range].to_owned())
     }
 
     fn parent(&mut self, _span: Self::Span) -> Option<Self::Span> {
-        // FIXME handle span
+        // The input spans of a macro are never the output of another expansion from the macro's
+        // point of view, as we don't forward spans of outer expansions.
         None
     }
     fn source(&mut self, span: Self::Span) -> Self::Span {
         // FIXME handle span
         span
     }
-    fn byte_range(&mut self, _span: Self::Span) -> Range<usize> {
-        // FIXME handle span
-        Range { start: 0, end: 0 }
-    }
-    fn start(&mut self, _span: Self::Span) -> LineColumn {
-        // FIXME handle span
-        LineColumn { line: 0, column: 0 }
+    fn byte_range(&mut self, span: Self::Span) -> Range<usize> {
+        let Some(offset) = self.location().map(|it| it.offset as usize) else { return 0..0 };
+        match self.spans.range(span) {
+            Some(range) => range.start + offset..range.end + offset,
+            None => 0..0,
+        }
     }
-    fn end(&mut self, _span: Self::Span) -> LineColumn {
-        // FIXME handle span
-        LineColumn { line: 0, column: 0 }
+    fn start(&mut self, span: Self::Span) -> LineColumn {
+        self.location();
+        self.spans
+            .range(span)
+            .and_then(|range| self.spans.line_column(range.start))
+            .unwrap_or(LineColumn { line: 0, column: 0 })
+    }
+    fn end(&mut self, span: Self::Span) -> LineColumn {
+        self.location();
+        self.spans
+            .range(span)
+            .and_then(|range| self.spans.line_column(range.end))
+            .unwrap_or(LineColumn { line: 0, column: 0 })
     }
     fn join(&mut self, first: Self::Span, _second: Self::Span) -> Option<Self::Span> {
         // Just return the first span again, because some macros will unwrap the result.
@@ -29,7 +29,9 @@ fn assert_expand_impl(macro_name: &str, input: &str, attr: Option<&str>, expect:
     let fixture = parse_string(input).unwrap();
     let attr = attr.map(|attr| parse_string(attr).unwrap().into_subtree());
 
-    let res = expander.expand(macro_name, &fixture.into_subtree(), attr.as_ref()).unwrap();
+    let (res, _) = expander
+        .expand(macro_name, &fixture.into_subtree(), attr.as_ref(), Default::default())
+        .unwrap();
     expect.assert_eq(&format!("{res:?}"));
 }
 
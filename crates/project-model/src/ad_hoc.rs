@@ -0,0 +1,171 @@
+//! Ad-hoc crates for files that do not belong to any crate of the loaded workspaces, like
+//! scripts, code snippets in `docs/` or `cargo -Zscript` files the user opened.
+//!
+//! Unlike [`ProjectWorkspace::DetachedFiles`](crate::ProjectWorkspace::DetachedFiles), which are
+//! known when the workspace is loaded, these crates are added while the server is running. They
+//! are kept apart from the crate graph of the workspace, see [`SourceDatabase::ad_hoc_crates`].
+//!
+//! [`SourceDatabase::ad_hoc_crates`]: base_db::SourceDatabase::ad_hoc_crates
+use base_db::{
+    CrateData, CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, Dependency, Edition,
+    Env, FileId, LangCrateOrigin,
+};
+use cfg::CfgOptions;
+use paths::AbsPath;
+
+/// Creates a single-file crate rooted at `file_id`, which depends on the sysroot crates of
+/// `crate_graph` and on the dependencies declared in the file's embedded cargo manifest, if any.
+///
+/// Dependencies are only resolved against crates that already are in the graph, no new crates
+/// get loaded for them.
+pub fn ad_hoc_crate(
+    crate_graph: &CrateGraph,
+    path: &AbsPath,
+    file_id: FileId,
+    text: &str,
+) -> CrateData {
+    // Ad-hoc crates are built for the same target as the sysroot of the workspace.
+    let core = find_lang_crate(crate_graph, LangCrateOrigin::Core).map(|it| &crate_graph[it]);
+    let cfg_options = core.map_or_else(CfgOptions::default, |it| it.cfg_options.clone());
+    let target_layout = core.map_or_else(
+        || Err("ad-hoc crates without a sysroot have no target layout".into()),
+        |it| it.target_layout.clone(),
+    );
+    let channel = core.and_then(|it| it.channel);
+
+    let mut dependencies = Vec::new();
+    // Same order as the sysroot's public dependencies.
+    for (name, origin, prelude) in [
+        ("core", LangCrateOrigin::Core, true),
+        ("alloc", LangCrateOrigin::Alloc, true),
+        ("std", LangCrateOrigin::Std, true),
+        ("test", LangCrateOrigin::Test, false),
+    ] {
+        if let Some(to) = find_lang_crate(crate_graph, origin) {
+            dependencies.push(Dependency::with_prelude(CrateName::new(name).unwrap(), to, prelude));
+        }
+    }
+    for dep in embedded_manifest(text).map(|it| script_dependencies(&it)).unwrap_or_default() {
+        match find_library(crate_graph, &dep.package) {
+            Some(to) => {
+                dependencies.push(Dependency::new(CrateName::normalize_dashes(&dep.name), to))
+            }
+            None => tracing::info!(
+                "dependency `{}` of ad-hoc crate {path} is not part of the workspace",
+                dep.name
+            ),
+        }
+    }
+
+    let display_name = path
+        .file_stem()
+        .and_then(|it| it.to_str())
+        .map(|it| CrateDisplayName::from_canonical_name(it.to_string()));
+    CrateData {
+        root_file_id: file_id,
+        edition: Edition::CURRENT,
+        version: None,
+        display_name: display_name.clone(),
+        cfg_options,
+        potential_cfg_options: None,
+        env: Env::default(),
+        dependencies,
+        origin: CrateOrigin::Local {
+            repo: None,
+            name: display_name.map(|it| it.canonical_name().to_string()),
+        },
+        is_proc_macro: false,
+        target_layout,
+        channel,
+        target: None,
+    }
+}
+
+fn find_lang_crate(crate_graph: &CrateGraph, origin: LangCrateOrigin) -> Option<CrateId> {
+    crate_graph.iter().find(|&it| crate_graph[it].origin == CrateOrigin::Lang(origin))
+}
+
+/// Finds the library target of the package `package`, which is the crate other crates of the
+/// graph depend on.
+fn find_library(crate_graph: &CrateGraph, package: &str) -> Option<CrateId> {
+    let package = package.replace('-', "_");
+    crate_graph
+        .iter()
+        .flat_map(|krate| crate_graph[krate].dependencies.iter())
+        .map(|dep| dep.crate_id)
+        .find(|&dep| {
+            let name = match &crate_graph[dep].origin {
+                CrateOrigin::Local { name: Some(name), .. }
+                | CrateOrigin::Library { name, .. }
+                | CrateOrigin::Rustc { name } => name,
+                CrateOrigin::Local { name: None, .. } | CrateOrigin::Lang(_) => return false,
+            };
+            name.replace('-', "_") == package
+        })
+}
+
+/// Extracts the cargo manifest embedded in a `cargo -Zscript` file, either as a `---` delimited
+/// frontmatter or as a ```` ```cargo ```` code block in the module's doc comment.
+fn embedded_manifest(text: &str) -> Option<String> {
+    let mut lines = text.lines().peekable();
+    // Skip the shebang, but not an inner attribute.
+    if lines.peek().map_or(false, |it| it.starts_with("#!") && !it.starts_with("#![")) {
+        lines.next();
+    }
+    while lines.peek().map_or(false, |it| it.trim().is_empty()) {
+        lines.next();
+    }
+
+    let first = lines.peek()?.trim_end();
+    if first.starts_with("---") {
+        let fence = &first[..first.len() - first.trim_start_matches('-').len()];
+        let info = first[fence.len()..].trim();
+        if !info.is_empty() && info != "cargo" {
+            return None;
+        }
+        lines.next();
+        let body: Vec<_> = lines.take_while(|it| it.trim_end() != fence).collect();
+        return Some(body.join("\n"));
+    }
+
+    let doc = lines.map_while(|it| it.trim_start().strip_prefix("//!"));
+    let mut body = None::<Vec<&str>>;
+    for line in doc {
+        let line = line.strip_prefix(' ').unwrap_or(line);
+        match &mut body {
+            None if line.trim() == "```cargo" => body = Some(Vec::new()),
+            None => (),
+            Some(_) if line.trim() == "```" => break,
+            Some(body) => body.push(line),
+        }
+    }
+    body.map(|it| it.join("\n"))
+}
+
+#[derive(Debug)]
+struct ScriptDependency {
+    /// The name the dependency is imported as.
+    name: String,
+    /// The package the dependency refers to.
+    package: String,
+}
+
+/// Collects the `[dependencies]` of a manifest.
+fn script_dependencies(manifest: &str) -> Vec<ScriptDependency> {
+    let manifest = match manifest.parse::<toml::Table>() {
+        Ok(it) => it,
+        Err(err) => {
+            tracing::info!("failed to parse embedded manifest: {err}");
+            return Vec::new();
+        }
+    };
+    let Some(deps) = manifest.get("dependencies").and_then(toml::Value::as_table) else {
+        return Vec::new();
+    };
+    deps.iter()
+        .map(|(name, dep)| {
+            let package = dep.get("package").and_then(toml::Value::as_str).unwrap_or(name);
+            ScriptDependency { name: name.clone(), package: package.to_owned() }
+        })
+        .collect()
+}
@@ -80,6 +80,8 @@ pub struct CargoConfig {
     pub features: CargoFeatures,
     /// rustc target
     pub target: Option<String>,
+    /// Additional rustc targets to analyze the workspace for.
+    pub extra_targets: Vec<String>,
     /// Sysroot loading behavior
     pub sysroot: Option<RustLibSource>,
     pub sysroot_src: Option<AbsPathBuf>,
@@ -237,7 +239,11 @@ impl CargoWorkspace {
         config: &CargoConfig,
         progress: &dyn Fn(String),
     ) -> anyhow::Result<cargo_metadata::Metadata> {
-        let targets = find_list_of_build_targets(config, cargo_toml);
+        let mut targets = find_list_of_build_targets(config, cargo_toml);
+        // Without any build target, the metadata isn't filtered to begin with.
+        if !targets.is_empty() {
+            targets.extend(config.extra_targets.iter().cloned());
+        }
 
         let mut meta = MetadataCommand::new();
         meta.cargo_path(toolchain::cargo());
@@ -11,8 +11,8 @@ use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
 
 use crate::{
-    CargoWorkspace, CfgOverrides, ProjectJson, ProjectJsonData, ProjectWorkspace, Sysroot,
-    WorkspaceBuildScripts,
+    ad_hoc_crate, cfg_flag::CfgFlag, CargoWorkspace, CfgOverrides, ExtraTarget, ProjectJson,
+    ProjectJsonData, ProjectWorkspace, Sysroot, WorkspaceBuildScripts,
 };
 
 fn load_cargo(file: &str) -> (CrateGraph, ProcMacroPaths) {
@@ -34,6 +34,7 @@ fn load_cargo_with_overrides(
         cfg_overrides,
         toolchain: None,
         target_layout: Err("target_data_layout not loaded".into()),
+        extra_targets: Vec::new(),
     };
     to_crate_graph(project_workspace)
 }
@@ -41,6 +42,14 @@ fn load_cargo_with_overrides(
 fn load_cargo_with_sysroot(
     file_map: &mut FxHashMap<AbsPathBuf, FileId>,
     file: &str,
+) -> (CrateGraph, ProcMacroPaths) {
+    load_cargo_with_extra_targets(file_map, file, Vec::new())
+}
+
+fn load_cargo_with_extra_targets(
+    file_map: &mut FxHashMap<AbsPathBuf, FileId>,
+    file: &str,
+    extra_targets: Vec<ExtraTarget>,
 ) -> (CrateGraph, ProcMacroPaths) {
     let meta = get_test_json_file(file);
     let cargo_workspace = CargoWorkspace::new(meta);
@@ -53,6 +62,7 @@ fn load_cargo_with_sysroot(
         cfg_overrides: Default::default(),
         toolchain: None,
         target_layout: Err("target_data_layout not loaded".into()),
+        extra_targets,
     };
     project_workspace.to_crate_graph(
         &mut {
@@ -249,3 +259,106 @@ fn crate_graph_dedup() {
     crate_graph.extend(regex_crate_graph, &mut regex_proc_macros);
     assert_eq!(crate_graph.iter().count(), 118);
 }
+
+#[test]
+fn cargo_extra_targets_get_their_own_crates() {
+    let windows = CfgFlag::KeyValue { key: "target_os".into(), value: "windows".into() };
+    let extra_target = ExtraTarget {
+        triple: "x86_64-pc-windows-msvc".into(),
+        rustc_cfg: vec![windows],
+        target_layout: Err("target_data_layout not loaded".into()),
+    };
+    let (crate_graph, _proc_macros) = load_cargo_with_extra_targets(
+        &mut Default::default(),
+        "hello-world-metadata.json",
+        vec![extra_target],
+    );
+    let (primary, extra): (Vec<_>, Vec<_>) =
+        crate_graph.iter().map(|it| &crate_graph[it]).partition(|it| it.target.is_none());
+    assert_eq!(primary.len(), extra.len());
+
+    let windows = CfgAtom::KeyValue { key: "target_os".into(), value: "windows".into() };
+    for primary in &primary {
+        assert_eq!(primary.cfg_options.check(&windows.clone().into()), Some(false));
+    }
+    for extra in &extra {
+        assert!(primary.iter().any(|it| it.root_file_id == extra.root_file_id));
+        assert_eq!(extra.target.as_deref(), Some("x86_64-pc-windows-msvc"));
+        assert_eq!(extra.cfg_options.check(&windows.clone().into()), Some(true));
+        // Crates only depend on crates of the same target.
+        for dep in &extra.dependencies {
+            assert_eq!(crate_graph[dep.crate_id].target, extra.target);
+        }
+    }
+}
+
+fn check_ad_hoc_crate_deps(text: &str, expect: &[&str]) {
+    let (crate_graph, _proc_macros) =
+        load_cargo_with_sysroot(&mut Default::default(), "regex-metadata.json");
+    let mut path = "$ROOT$script.rs".to_string();
+    replace_root(&mut path, true);
+    let krate = ad_hoc_crate(&crate_graph, AbsPath::assert(Path::new(&path)), FileId(!0), text);
+    let deps: Vec<_> = krate.dependencies.iter().map(|it| it.name.to_string()).collect();
+    assert_eq!(deps, expect);
+}
+
+#[test]
+fn ad_hoc_crate_depends_on_sysroot() {
+    check_ad_hoc_crate_deps("fn main() {}\n", &["core", "alloc", "std", "test"]);
+}
+
+#[test]
+fn ad_hoc_crate_with_frontmatter_dependencies() {
+    check_ad_hoc_crate_deps(
+        r#"#!/usr/bin/env -S cargo +nightly -Zscript
+---
+[package]
+edition = "2021"
+
+[dependencies]
+memchr = { version = "2", features = ["std"] } # comment
+syntax = { version = "0.7", package = "regex-syntax" }
+aho-corasick.workspace = true
+unknown = "1"
+---
+
+fn main() {}
+"#,
+        &["core", "alloc", "std", "test", "memchr", "syntax", "aho_corasick"],
+    );
+}
+
+#[test]
+fn ad_hoc_crate_with_doc_comment_manifest() {
+    check_ad_hoc_crate_deps(
+        r#"//! ```cargo
+//! [dependencies.syntax]
+//! version = "0.7"
+//! package = "regex-syntax"
+//!
+//! [dev-dependencies]
+//! memchr = "2"
+//! ```
+
+fn main() {}
+"#,
+        &["core", "alloc", "std", "test", "syntax"],
+    );
+}
+
+#[test]
+fn ad_hoc_crate_with_multiline_dependency() {
+    check_ad_hoc_crate_deps(
+        r#"---
+[dependencies]
+syntax = { version = "0.7", features = [
+    "unicode",
+], package = "regex-syntax" }
+"memchr" = "2"
+---
+
+fn main() {}
+"#,
+        &["core", "alloc", "std", "test", "syntax", "memchr"],
+    );
+}
@@ -61,7 +61,7 @@ pub enum ProjectWorkspace {
         build_scripts: WorkspaceBuildScripts,
         sysroot: Result<Sysroot, Option<String>>,
         rustc: Result<(CargoWorkspace, WorkspaceBuildScripts), Option<String>>,
-        /// Holds cfg flags for the current target. We get those by running
+        /// Holds cfg flags for the primary target. We get those by running
         /// `rustc --print cfg`.
         ///
         /// FIXME: make this a per-crate map, as, eg, build.rs might have a
@@ -70,6 +70,8 @@ pub enum ProjectWorkspace {
         cfg_overrides: CfgOverrides,
         toolchain: Option<Version>,
         target_layout: Result<String, String>,
+        /// The additional targets the crates are analyzed for, each as a separate set of crates.
+        extra_targets: Vec<ExtraTarget>,
     },
     /// Project workspace was manually specified using a `rust-project.json` file.
     Json {
@@ -80,14 +82,8 @@ pub enum ProjectWorkspace {
         rustc_cfg: Vec<CfgFlag>,
         toolchain: Option<Version>,
     },
-    // FIXME: The primary limitation of this approach is that the set of detached files needs to be fixed at the beginning.
-    // That's not the end user experience we should strive for.
-    // Ideally, you should be able to just open a random detached file in existing cargo projects, and get the basic features working.
-    // That needs some changes on the salsa-level though.
-    // In particular, we should split the unified CrateGraph (which currently has maximal durability) into proper crate graph, and a set of ad hoc roots (with minimal durability).
-    // Then, we need to hide the graph behind the queries such that most queries look only at the proper crate graph, and fall back to ad hoc roots only if there's no results.
-    // After this, we should be able to tweak the logic in reload.rs to add newly opened files, which don't belong to any existing crates, to the set of the detached files.
-    // //
+    // Files opened later on that don't belong to any crate get an ad-hoc crate instead, which is
+    // kept apart from the crate graph, see `ad_hoc_crate`.
     /// Project with a set of disjoint files, not belonging to any particular workspace.
     /// Backed by basic sysroot crates for basic completion and highlighting.
     DetachedFiles {
@@ -99,6 +95,34 @@ pub enum ProjectWorkspace {
     },
 }
 
+/// A target a Cargo workspace is analyzed for besides its primary one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraTarget {
+    pub triple: String,
+    /// Holds cfg flags for the target, from `rustc --print cfg --target`.
+    pub rustc_cfg: Vec<CfgFlag>,
+    pub target_layout: Result<String, String>,
+}
+
+impl ExtraTarget {
+    fn load(
+        cargo_toml: &ManifestPath,
+        triple: &str,
+        extra_env: &FxHashMap<String, String>,
+    ) -> Self {
+        let rustc_cfg = rustc_cfg::get(Some(cargo_toml), Some(triple), extra_env);
+        let target_layout = target_data_layout::get(Some(cargo_toml), Some(triple), extra_env);
+        if let Err(e) = &target_layout {
+            tracing::error!(%e, "failed fetching data layout of target {triple}");
+        }
+        ExtraTarget {
+            triple: triple.to_owned(),
+            rustc_cfg,
+            target_layout: target_layout.map_err(|it| it.to_string()),
+        }
+    }
+}
+
 impl fmt::Debug for ProjectWorkspace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Make sure this isn't too verbose.
@@ -112,6 +136,7 @@ impl fmt::Debug for ProjectWorkspace {
                 cfg_overrides,
                 toolchain,
                 target_layout: data_layout,
+                extra_targets,
             } => f
                 .debug_struct("Cargo")
                 .field("root", &cargo.workspace_root().file_name())
@@ -125,6 +150,7 @@ impl fmt::Debug for ProjectWorkspace {
                 .field("n_cfg_overrides", &cfg_overrides.len())
                 .field("toolchain", &toolchain)
                 .field("data_layout", &data_layout)
+                .field("n_extra_targets", &extra_targets.len())
                 .finish(),
             ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain } => {
                 let mut debug_struct = f.debug_struct("Json");
@@ -290,6 +316,12 @@ impl ProjectWorkspace {
                 if let Err(e) = &data_layout {
                     tracing::error!(%e, "failed fetching data layout for {cargo_toml:?} workspace");
                 }
+                let extra_targets = config
+                    .extra_targets
+                    .iter()
+                    .filter(|&it| Some(it) != config.target.as_ref())
+                    .map(|it| ExtraTarget::load(cargo_toml, it, &config.extra_env))
+                    .collect();
                 ProjectWorkspace::Cargo {
                     cargo,
                     build_scripts: WorkspaceBuildScripts::default(),
@@ -299,6 +331,7 @@ impl ProjectWorkspace {
                     cfg_overrides,
                     toolchain,
                     target_layout: data_layout.map_err(|it| it.to_string()),
+                    extra_targets,
                 }
             }
         };
@@ -505,6 +538,7 @@ impl ProjectWorkspace {
                 build_scripts,
                 toolchain: _,
                 target_layout: _,
+                extra_targets: _,
             } => {
                 cargo
                     .packages()
@@ -612,21 +646,42 @@ impl ProjectWorkspace {
                 build_scripts,
                 toolchain,
                 target_layout,
-            } => cargo_to_crate_graph(
-                load,
-                rustc.as_ref().ok(),
-                cargo,
-                sysroot.as_ref().ok(),
-                rustc_cfg.clone(),
-                cfg_overrides,
-                None,
-                build_scripts,
-                match target_layout.as_ref() {
-                    Ok(it) => Ok(Arc::from(it.as_str())),
-                    Err(it) => Err(Arc::from(it.as_str())),
-                },
-                toolchain.as_ref().and_then(|it| ReleaseChannel::from_str(it.pre.as_str())),
-            ),
+                extra_targets,
+            } => {
+                let mut to_crate_graph =
+                    |rustc_cfg: &[CfgFlag], target_layout: &Result<String, String>| {
+                        cargo_to_crate_graph(
+                            load,
+                            rustc.as_ref().ok(),
+                            cargo,
+                            sysroot.as_ref().ok(),
+                            rustc_cfg.to_vec(),
+                            cfg_overrides,
+                            None,
+                            build_scripts,
+                            match target_layout.as_ref() {
+                                Ok(it) => Ok(Arc::from(it.as_str())),
+                                Err(it) => Err(Arc::from(it.as_str())),
+                            },
+                            toolchain
+                                .as_ref()
+                                .and_then(|it| ReleaseChannel::from_str(it.pre.as_str())),
+                        )
+                    };
+                let (mut crate_graph, mut proc_macros) = to_crate_graph(rustc_cfg, target_layout);
+                // Every extra target gets its own copy of all crates, sysroot included.
+                // FIXME: The build scripts only ran for the primary target.
+                for target in extra_targets {
+                    let (mut other, mut other_proc_macros) =
+                        to_crate_graph(&target.rustc_cfg, &target.target_layout);
+                    for (_, data) in other.iter_mut() {
+                        data.target = Some(target.triple.clone());
+                    }
+                    crate_graph.extend(other, &mut other_proc_macros);
+                    proc_macros.extend(other_proc_macros);
+                }
+                (crate_graph, proc_macros)
+            }
             ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg } => {
                 detached_files_to_crate_graph(
                     rustc_cfg.clone(),
@@ -657,6 +712,7 @@ impl ProjectWorkspace {
                     toolchain,
                     build_scripts: _,
                     target_layout: _,
+                    extra_targets,
                 },
                 Self::Cargo {
                     cargo: o_cargo,
@@ -667,9 +723,11 @@ impl ProjectWorkspace {
                     toolchain: o_toolchain,
                     build_scripts: _,
                     target_layout: _,
+                    extra_targets: o_extra_targets,
                 },
             ) => {
                 cargo == o_cargo
+                    && extra_targets == o_extra_targets
                     && rustc == o_rustc
                     && rustc_cfg == o_rustc_cfg
                     && cfg_overrides == o_cfg_overrides
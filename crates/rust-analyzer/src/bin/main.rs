@@ -83,6 +83,7 @@ fn main() -> anyhow::Result<()> {
         flags::RustAnalyzerCmd::Lsif(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Scip(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::RunTests(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Dap(cmd) => cmd.run()?,
     }
     Ok(())
 }
@@ -227,7 +228,8 @@ fn run_server() -> anyhow::Result<()> {
         offset_encoding: None,
     };
 
-    let initialize_result = serde_json::to_value(initialize_result).unwrap();
+    let mut initialize_result = serde_json::to_value(initialize_result).unwrap();
+    rust_analyzer::add_untyped_server_capabilities(&mut initialize_result["capabilities"]);
 
     connection.initialize_finish(initialize_id, initialize_result)?;
 
@@ -3,16 +3,16 @@ use ide_db::line_index::WideEncoding;
 use lsp_types::{
     CallHierarchyServerCapability, ClientCapabilities, CodeActionKind, CodeActionOptions,
     CodeActionProviderCapability, CodeLensOptions, CompletionOptions,
-    CompletionOptionsCompletionItem, DeclarationCapability, DocumentOnTypeFormattingOptions,
-    FileOperationFilter, FileOperationPattern, FileOperationPatternKind,
-    FileOperationRegistrationOptions, FoldingRangeProviderCapability, HoverProviderCapability,
-    ImplementationProviderCapability, InlayHintOptions, InlayHintServerCapabilities, OneOf,
-    PositionEncodingKind, RenameOptions, SaveOptions, SelectionRangeProviderCapability,
-    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities,
-    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
-    TextDocumentSyncOptions, TypeDefinitionProviderCapability, WorkDoneProgressOptions,
-    WorkspaceFileOperationsServerCapabilities, WorkspaceFoldersServerCapabilities,
-    WorkspaceServerCapabilities,
+    CompletionOptionsCompletionItem, DeclarationCapability, DiagnosticOptions,
+    DiagnosticServerCapabilities, DocumentOnTypeFormattingOptions, FileOperationFilter,
+    FileOperationPattern, FileOperationPatternKind, FileOperationRegistrationOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InlayHintOptions, InlayHintServerCapabilities, OneOf, PositionEncodingKind, RenameOptions,
+    SaveOptions, SelectionRangeProviderCapability, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProviderCapability,
+    WorkDoneProgressOptions, WorkspaceFileOperationsServerCapabilities,
+    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
 use serde_json::json;
 
@@ -140,6 +140,7 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             },
         ))),
         inline_value_provider: None,
+        diagnostic_provider: diagnostic_provider(config),
         experimental: Some(json!({
             "externalDocs": true,
             "hoverRange": true,
@@ -153,11 +154,31 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
                 "kinds": [ "cargo" ],
             },
             "ssr": true,
+            "testExplorer": config.test_explorer(),
             "workspaceSymbolScopeKindFiltering": true,
         })),
     }
 }
 
+/// Adds the capabilities which `lsp_types::ServerCapabilities` has no fields for to the
+/// serialized [`server_capabilities`].
+pub fn add_untyped_server_capabilities(capabilities: &mut serde_json::Value) {
+    capabilities["typeHierarchyProvider"] = json!(true);
+}
+
+fn diagnostic_provider(config: &Config) -> Option<DiagnosticServerCapabilities> {
+    // Clients which don't support pulling diagnostics get them published instead.
+    if !config.pull_diagnostics() {
+        return None;
+    }
+    Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+        identifier: Some("rust-analyzer".to_string()),
+        inter_file_dependencies: true,
+        workspace_diagnostics: config.workspace_diagnostics(),
+        work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+    }))
+}
+
 fn completions_resolve_provider(client_caps: &ClientCapabilities) -> Option<bool> {
     if completion_item_edit_resolve(client_caps) {
         Some(true)
@@ -10,16 +10,22 @@ mod ssr;
 mod lsif;
 mod scip;
 mod run_tests;
+mod dap;
 
 mod progress_report;
 
 use std::io::Read;
 
 use anyhow::Result;
-use hir::{Module, Name};
+use hir::{Crate, Module, Name};
 use hir_ty::db::HirDatabase;
 use ide::AnalysisHost;
+use ide_db::{
+    base_db::{FileId, SourceDatabaseExt},
+    LineIndexDatabase, RootDatabase,
+};
 use itertools::Itertools;
+use syntax::TextRange;
 use vfs::Vfs;
 
 #[derive(Clone, Copy)]
@@ -85,3 +91,33 @@ fn full_name_of_item(db: &dyn HirDatabase, module: Module, name: Name) -> String
         .map(|it| it.display(db.upcast()).to_string())
         .join("::")
 }
+
+fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
+    let mut worklist: Vec<_> = Crate::all(db)
+        .into_iter()
+        .filter(|x| x.origin(db).is_local())
+        .map(|krate| krate.root_module(db))
+        .collect();
+    let mut modules = Vec::new();
+
+    while let Some(module) = worklist.pop() {
+        modules.push(module);
+        worklist.extend(module.children(db));
+    }
+
+    modules
+}
+
+/// Formats spans of mir interpreter errors as `file://path#line:col`.
+fn format_span(db: &RootDatabase, file_id: FileId, text_range: TextRange) -> String {
+    let line_col = match db.line_index(file_id).try_line_col(text_range.start()) {
+        None => " (unknown line col)".to_string(),
+        Some(x) => format!("#{}:{}", x.line + 1, x.col),
+    };
+    let path = &db
+        .source_root(db.file_source_root(file_id))
+        .path_for_file(&file_id)
+        .map(|x| x.to_string());
+    let path = path.as_deref().unwrap_or("<unknown file>");
+    format!("file://{path}{line_col}")
+}
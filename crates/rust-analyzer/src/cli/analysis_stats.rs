@@ -73,6 +73,7 @@ impl flags::AnalysisStats {
             load_out_dirs_from_check: !self.disable_build_scripts,
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
+            persist_caches: None,
         };
 
         let build_scripts_time = if self.disable_build_scripts {
@@ -776,6 +777,10 @@ impl flags::AnalysisStats {
                     adjustment_hints_hide_outside_unsafe: false,
                     closure_return_type_hints: ide::ClosureReturnTypeHints::Always,
                     closure_capture_hints: true,
+                    turbofish_hints: true,
+                    generic_parameter_hints: true,
+                    implicit_drop_hints: true,
+                    const_value_hints: true,
                     binding_mode_hints: true,
                     lifetime_elision_hints: ide::LifetimeElisionHints::Always,
                     param_names_for_lifetime_elision_hints: true,
@@ -0,0 +1,760 @@
+//! A Debug Adapter Protocol server, that runs a function with the mir interpreter and lets the
+//! editor stop it at breakpoints, step through it, and inspect its call stack and locals.
+//!
+//! Requests are read on the main thread. Once the editor is done with the configuration, the
+//! function runs on its own thread, and the requests that need a paused evaluation are forwarded
+//! to it.
+
+use std::{
+    io::{self, BufRead, Write},
+    ops::ControlFlow,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::format_err;
+use crossbeam_channel::{Receiver, Sender};
+use hir::{db::HirDatabase, EvalFrame, EvalObserver, Function, MirEvalSandbox, ModuleDef};
+use ide::{AnalysisHost, LineCol};
+use ide_db::{
+    base_db::{
+        salsa::{ParallelDatabase, Snapshot},
+        FileId, SourceDatabaseExt,
+    },
+    symbol_index::SymbolsDatabase,
+    FxHashMap, FxHashSet, LineIndexDatabase, RootDatabase,
+};
+use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
+use project_model::{CargoConfig, RustLibSource};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use stdx::thread::ThreadIntent;
+use vfs::{AbsPathBuf, VfsPath};
+
+use crate::cli::{all_modules, flags, format_span, full_name_of_item, Result};
+
+/// The interpreter recurses on the native stack for nested evaluations.
+const EVALUATION_STACK_SIZE: usize = 1024 * 1024 * 8;
+
+/// The interpreter emulates the threads of the program on a single thread, so that's the only
+/// thread the editor sees.
+const THREAD_ID: i64 = 1;
+
+/// Zero based lines with a breakpoint, per file.
+type Breakpoints = Arc<Mutex<FxHashMap<FileId, FxHashSet<u32>>>>;
+
+impl flags::Dap {
+    pub fn run(self) -> Result<()> {
+        let mut server = Server::new(Client::new(io::stdout()));
+        let mut stdin = io::stdin().lock();
+        while let Some(request) = read_message(&mut stdin)? {
+            if server.handle(request)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The state of a debugging session, which is driven by the requests of the editor.
+struct Server {
+    client: Client,
+    breakpoints: Breakpoints,
+    /// The files of the launched workspace by path, to map breakpoints to.
+    files: Option<FxHashMap<String, FileId>>,
+    /// The launched function, until the configuration is done and it starts running.
+    launched: Option<Launched>,
+    evaluation: Option<Sender<Request>>,
+}
+
+impl Server {
+    fn new(client: Client) -> Server {
+        Server {
+            client,
+            breakpoints: Breakpoints::default(),
+            files: None,
+            launched: None,
+            evaluation: None,
+        }
+    }
+
+    /// Handles a request of the editor, breaks once the editor disconnects.
+    fn handle(&mut self, request: Request) -> Result<ControlFlow<()>> {
+        let result = match request.command.as_str() {
+            "initialize" => Ok(json!({ "supportsConfigurationDoneRequest": true })),
+            "launch" => {
+                let result = launch(&request.arguments).map(|launched| {
+                    self.files = Some(workspace_files(launched.host.raw_database()));
+                    self.launched = Some(launched);
+                    json!({})
+                });
+                let is_ok = result.is_ok();
+                self.client.respond(&request, result)?;
+                if is_ok {
+                    // Breakpoints can only be mapped to files once the workspace is loaded.
+                    self.client.event("initialized", json!({}))?;
+                }
+                return Ok(ControlFlow::Continue(()));
+            }
+            // The breakpoints are shared with the evaluation, so they can also be changed while
+            // it runs or is stopped.
+            "setBreakpoints" => self.set_breakpoints(&request.arguments),
+            "configurationDone" => match self.launched.take() {
+                Some(launched) => {
+                    let (sender, receiver) = crossbeam_channel::unbounded();
+                    self.evaluation = Some(sender);
+                    launched
+                        .start(self.client.clone(), self.breakpoints.clone(), receiver)
+                        .map(|()| json!({}))
+                }
+                None => Err(format_err!("`launch` must come before `configurationDone`")),
+            },
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "disconnect" | "terminate" => {
+                // A paused evaluation never resumes, it's torn down with the process.
+                self.client.respond(&request, Ok(json!({})))?;
+                return Ok(ControlFlow::Break(()));
+            }
+            "stackTrace" | "scopes" | "variables" | "continue" | "next" | "stepIn" | "stepOut"
+            | "pause" => match &self.evaluation {
+                Some(sender) => {
+                    if let Err(it) = sender.send(request) {
+                        self.client
+                            .respond(&it.0, Err(format_err!("the evaluation has finished")))?;
+                    }
+                    return Ok(ControlFlow::Continue(()));
+                }
+                None => Err(format_err!("the evaluation has not started")),
+            },
+            command => Err(format_err!("unsupported request `{command}`")),
+        };
+        self.client.respond(&request, result)?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn set_breakpoints(&self, arguments: &Value) -> Result<Value> {
+        #[derive(Deserialize)]
+        struct Source {
+            path: PathBuf,
+        }
+        #[derive(Deserialize)]
+        struct SourceBreakpoint {
+            line: u32,
+        }
+        #[derive(Deserialize)]
+        struct SetBreakpointsArguments {
+            source: Source,
+            #[serde(default)]
+            breakpoints: Vec<SourceBreakpoint>,
+        }
+
+        let args = SetBreakpointsArguments::deserialize(arguments)?;
+        let files = self
+            .files
+            .as_ref()
+            .ok_or_else(|| format_err!("`launch` must come before breakpoints"))?;
+        // Paths of the workspace are normalized by the VFS.
+        let path = match AbsPathBuf::try_from(args.source.path) {
+            Ok(path) => VfsPath::from(path).to_string(),
+            Err(path) => path.display().to_string(),
+        };
+        let file_id = files.get(&path).copied();
+        let lines: Vec<u32> = args.breakpoints.iter().map(|it| it.line).collect();
+        if let Some(file_id) = file_id {
+            let lines = lines.iter().map(|it| it.saturating_sub(1)).collect();
+            self.breakpoints.lock().unwrap().insert(file_id, lines);
+        }
+        let verified = file_id.is_some();
+        let breakpoints: Vec<_> =
+            lines.iter().map(|line| json!({ "verified": verified, "line": line })).collect();
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    seq: i64,
+    command: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchArguments {
+    /// Directory with Cargo.toml.
+    path: PathBuf,
+    /// Path of the function to run, relative to the crate root, like `module::function`.
+    function: String,
+    #[serde(default)]
+    stop_on_entry: bool,
+    /// Environment variables visible to the function.
+    #[serde(default)]
+    env: FxHashMap<String, String>,
+    /// Let the function read, but not write, the files of the directory with Cargo.toml.
+    #[serde(default)]
+    read_workspace: bool,
+}
+
+/// A loaded workspace and the function to run in it.
+struct Launched {
+    host: AnalysisHost,
+    function: Function,
+    sandbox: MirEvalSandbox,
+    stop_on_entry: bool,
+}
+
+fn launch(arguments: &Value) -> Result<Launched> {
+    let args = LaunchArguments::deserialize(arguments)?;
+    let mut cargo_config = CargoConfig::default();
+    cargo_config.sysroot = Some(RustLibSource::Discover);
+    let load_cargo_config = LoadCargoConfig {
+        load_out_dirs_from_check: true,
+        with_proc_macro_server: ProcMacroServerChoice::Sysroot,
+        prefill_caches: false,
+        persist_caches: None,
+    };
+    let (host, _vfs, _proc_macro) =
+        load_workspace_at(&args.path, &cargo_config, &load_cargo_config, &|_| {})?;
+    let db = host.raw_database();
+    let function = all_modules(db)
+        .into_iter()
+        .flat_map(|it| it.declarations(db))
+        .filter_map(|it| match it {
+            ModuleDef::Function(f) => Some(f),
+            _ => None,
+        })
+        .find(|f| full_name_of_item(db, f.module(db), f.name(db)) == args.function)
+        .ok_or_else(|| format_err!("function `{}` not found", args.function))?;
+    let mut sandbox = MirEvalSandbox { env: args.env, ..MirEvalSandbox::default() };
+    if args.read_workspace {
+        sandbox.read_only_root = Some(std::fs::canonicalize(&args.path)?);
+    }
+    Ok(Launched { host, function, sandbox, stop_on_entry: args.stop_on_entry })
+}
+
+/// The files of the workspace, by the paths the editor refers to them with.
+fn workspace_files(db: &RootDatabase) -> FxHashMap<String, FileId> {
+    let mut files = FxHashMap::default();
+    for &root in db.local_roots().iter() {
+        let root = db.source_root(root);
+        files.extend(
+            root.iter()
+                .filter_map(|file_id| Some((root.path_for_file(&file_id)?.to_string(), file_id))),
+        );
+    }
+    files
+}
+
+impl Launched {
+    /// Runs the function on its own thread.
+    fn start(
+        self,
+        client: Client,
+        breakpoints: Breakpoints,
+        requests: Receiver<Request>,
+    ) -> Result<()> {
+        stdx::thread::Builder::new(ThreadIntent::Worker)
+            .name("DapEvaluation".to_owned())
+            .stack_size(EVALUATION_STACK_SIZE)
+            // Disconnecting doesn't wait for a paused evaluation.
+            .allow_leak(true)
+            .spawn(move || {
+                let db = self.host.raw_database();
+                let debugger = Debugger {
+                    db: db.snapshot(),
+                    client: client.clone(),
+                    breakpoints,
+                    requests: requests.clone(),
+                    mode: if self.stop_on_entry { Mode::Entry } else { Mode::Continue },
+                    last: None,
+                };
+                let output = self.function.eval(
+                    db,
+                    self.sandbox,
+                    Some(Box::new(debugger)),
+                    |file_id, text_range| format_span(db, file_id, text_range),
+                );
+                let exit_code = if output.trim() == "pass" { 0 } else { 1 };
+                _ = client
+                    .event("output", json!({ "category": "console", "output": output + "\n" }));
+                _ = client.event("exited", json!({ "exitCode": exit_code }));
+                _ = client.event("terminated", json!({}));
+                // The editor may have sent requests before it saw the events, and keeps sending
+                // them until it disconnects.
+                for request in requests {
+                    _ = client.respond(&request, Err(format_err!("the evaluation has finished")));
+                }
+            })?;
+        Ok(())
+    }
+}
+
+/// Where the evaluation should stop next.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Entry,
+    Continue,
+    Pause,
+    StepIn(Location),
+    StepOver(Location),
+    StepOut(Location),
+}
+
+/// The depth of the call stack and the source line of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    depth: usize,
+    file_id: FileId,
+    line: u32,
+}
+
+struct Debugger {
+    db: Snapshot<RootDatabase>,
+    client: Client,
+    breakpoints: Breakpoints,
+    requests: Receiver<Request>,
+    mode: Mode,
+    /// The location of the previous step. Breakpoints only stop the evaluation when it reaches
+    /// their line, not on every step of it.
+    last: Option<Location>,
+}
+
+impl EvalObserver for Debugger {
+    fn step(&mut self, stack: &[EvalFrame<'_, '_>]) {
+        let Some(frame) = stack.last() else {
+            return;
+        };
+        // Blocks are the spans of their scope ends, stopping there would jump back to the line
+        // where the block starts.
+        if frame.is_at_block_end(&*self.db) {
+            return;
+        }
+        let Some((file_id, line_col)) = self.line_col(frame) else {
+            return;
+        };
+        let location = Location { depth: stack.len(), file_id, line: line_col.line };
+        let is_new_line = self.last != Some(location);
+        self.last = Some(location);
+        while let Ok(request) = self.requests.try_recv() {
+            let result = match request.command.as_str() {
+                "pause" => {
+                    self.mode = Mode::Pause;
+                    Ok(json!({}))
+                }
+                command => Err(format_err!("`{command}` needs a paused evaluation")),
+            };
+            _ = self.client.respond(&request, result);
+        }
+        let reason = match self.mode {
+            Mode::Entry => Some("entry"),
+            Mode::Pause => Some("pause"),
+            Mode::StepIn(from) if location != from => Some("step"),
+            Mode::StepOver(from)
+                if location.depth < from.depth
+                    || (location.depth == from.depth && location != from) =>
+            {
+                Some("step")
+            }
+            Mode::StepOut(from) if location.depth < from.depth => Some("step"),
+            _ => None,
+        };
+        let reason = reason.or_else(|| {
+            let breakpoints = self.breakpoints.lock().unwrap();
+            let is_breakpoint =
+                breakpoints.get(&file_id).map_or(false, |it| it.contains(&location.line));
+            (is_new_line && is_breakpoint).then_some("breakpoint")
+        });
+        if let Some(reason) = reason {
+            self.pause(stack, location, reason);
+        }
+    }
+}
+
+impl Debugger {
+    /// Serves requests until the editor resumes the evaluation.
+    fn pause(&mut self, stack: &[EvalFrame<'_, '_>], location: Location, reason: &str) {
+        _ = self.client.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        while let Ok(request) = self.requests.recv() {
+            let (mode, result) = match request.command.as_str() {
+                "stackTrace" => (None, Ok(self.stack_trace(stack))),
+                "scopes" => (None, scopes(&request.arguments)),
+                "variables" => (None, self.variables(stack, &request.arguments)),
+                "pause" => (None, Ok(json!({}))),
+                "continue" => (Some(Mode::Continue), Ok(json!({ "allThreadsContinued": true }))),
+                "next" => (Some(Mode::StepOver(location)), Ok(json!({}))),
+                "stepIn" => (Some(Mode::StepIn(location)), Ok(json!({}))),
+                "stepOut" => (Some(Mode::StepOut(location)), Ok(json!({}))),
+                command => (None, Err(format_err!("unsupported request `{command}`"))),
+            };
+            _ = self.client.respond(&request, result);
+            if let Some(mode) = mode {
+                self.mode = mode;
+                return;
+            }
+        }
+    }
+
+    fn stack_trace(&self, stack: &[EvalFrame<'_, '_>]) -> Value {
+        let db: &dyn HirDatabase = &*self.db;
+        // Frames are identified by their index in `stack`, the editor wants the innermost first.
+        let frames: Vec<_> = stack
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(id, frame)| {
+                let name = frame
+                    .owner()
+                    .name(db)
+                    .map(|it| it.display(db.upcast()).to_string())
+                    .unwrap_or_else(|| "<unnamed>".to_owned());
+                let name = match frame.is_closure() {
+                    true => format!("{name}::{{closure}}"),
+                    false => name,
+                };
+                let mut result = json!({ "id": id, "name": name, "line": 0, "column": 0 });
+                if let Some((file_id, line_col)) = self.line_col(frame) {
+                    result["line"] = (line_col.line + 1).into();
+                    result["column"] = (line_col.col + 1).into();
+                    let source_root = self.db.source_root(self.db.file_source_root(file_id));
+                    if let Some(path) = source_root.path_for_file(&file_id) {
+                        result["source"] = json!({ "path": path.to_string() });
+                    }
+                }
+                result
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": stack.len() })
+    }
+
+    fn variables(&self, stack: &[EvalFrame<'_, '_>], arguments: &Value) -> Result<Value> {
+        let db: &dyn HirDatabase = &*self.db;
+        let frame = variables_reference(arguments)
+            .and_then(|it| it.checked_sub(1))
+            .and_then(|it| stack.get(it))
+            .ok_or_else(|| format_err!("invalid variables reference"))?;
+        let variables: Vec<_> = frame
+            .locals()
+            .into_iter()
+            .map(|it| {
+                json!({
+                    "name": it.name.display(db.upcast()).to_string(),
+                    "value": it.value.as_deref().unwrap_or("<unavailable>"),
+                    "type": it.ty,
+                    "variablesReference": 0,
+                })
+            })
+            .collect();
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn line_col(&self, frame: &EvalFrame<'_, '_>) -> Option<(FileId, LineCol)> {
+        let range = frame.source_range()?;
+        Some((range.file_id, self.db.line_index(range.file_id).line_col(range.range.start())))
+    }
+}
+
+/// Each frame has a single scope with its locals, referenced by the id of the frame plus one, as
+/// zero means no variables.
+fn scopes(arguments: &Value) -> Result<Value> {
+    let frame_id = arguments
+        .get("frameId")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| format_err!("missing frame id"))?;
+    Ok(json!({
+        "scopes": [{ "name": "Locals", "variablesReference": frame_id + 1, "expensive": false }]
+    }))
+}
+
+fn variables_reference(arguments: &Value) -> Option<usize> {
+    arguments.get("variablesReference")?.as_u64()?.try_into().ok()
+}
+
+/// Sends messages to the editor, from the main thread and from the evaluation.
+#[derive(Clone)]
+struct Client {
+    seq: Arc<AtomicI64>,
+    output: Arc<Mutex<dyn Write + Send>>,
+}
+
+impl Client {
+    fn new(output: impl Write + Send + 'static) -> Client {
+        Client { seq: Arc::default(), output: Arc::new(Mutex::new(output)) }
+    }
+
+    fn respond(&self, request: &Request, result: Result<Value>) -> Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(e) => response["message"] = e.to_string().into(),
+        }
+        self.send(response)
+    }
+
+    fn event(&self, event: &str, body: Value) -> Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&self, mut message: Value) -> Result<()> {
+        message["seq"] = (self.seq.fetch_add(1, Ordering::Relaxed) + 1).into();
+        let message = serde_json::to_string(&message)?;
+        let mut output = self.output.lock().unwrap();
+        output
+            .write_all(format!("Content-Length: {}\r\n\r\n{message}", message.len()).as_bytes())?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads a message with the same framing as LSP, returns `None` at the end of the input.
+fn read_message<T: DeserializeOwned>(input: &mut impl BufRead) -> Result<Option<T>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(len) = header.strip_prefix("Content-Length:") {
+            content_length = Some(len.trim().parse::<usize>()?);
+        }
+    }
+    let len = content_length.ok_or_else(|| format_err!("missing Content-Length header"))?;
+    let mut content = vec![0; len];
+    input.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ide_db::base_db::fixture::ChangeFixture;
+
+    use crate::cli::all_modules;
+
+    use super::*;
+
+    const FIXTURE: &str = r#"
+//- /main.rs
+fn add(a: i32, b: i32) -> i32 {
+    let sum = a + b;
+    sum
+}
+
+fn main() {
+    let x = 1;
+    let y = add(x, 2);
+    let z = y;
+}
+"#;
+
+    /// Collects what the server sends to the editor.
+    struct Output(Sender<Vec<u8>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(buf.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A session which launched `main` of a fixture, acting as the editor.
+    struct Session {
+        server: Server,
+        output: Receiver<Vec<u8>>,
+        /// Messages that have been received, but not been waited for yet.
+        received: Vec<Value>,
+        seq: i64,
+    }
+
+    impl Session {
+        fn launch(ra_fixture: &str, stop_on_entry: bool) -> Session {
+            let mut host = AnalysisHost::default();
+            host.raw_database_mut().apply_change(ChangeFixture::parse(ra_fixture).change);
+            let db = host.raw_database();
+            let function = all_modules(db)
+                .into_iter()
+                .flat_map(|it| it.declarations(db))
+                .find_map(|it| match it {
+                    ModuleDef::Function(f) if f.name(db).display(db).to_string() == "main" => {
+                        Some(f)
+                    }
+                    _ => None,
+                })
+                .unwrap();
+            let (sender, output) = crossbeam_channel::unbounded();
+            let mut server = Server::new(Client::new(Output(sender)));
+            server.files = Some(workspace_files(db));
+            server.launched = Some(Launched {
+                host,
+                function,
+                sandbox: MirEvalSandbox::default(),
+                stop_on_entry,
+            });
+            Session { server, output, received: Vec::new(), seq: 0 }
+        }
+
+        fn request(&mut self, command: &str, arguments: Value) -> Value {
+            self.seq += 1;
+            let seq = self.seq;
+            let request = Request { seq, command: command.to_owned(), arguments };
+            assert!(self.server.handle(request).unwrap().is_continue());
+            self.wait_for(|it| it["type"] == "response" && it["request_seq"] == seq)
+        }
+
+        fn wait_for(&mut self, filter: impl Fn(&Value) -> bool) -> Value {
+            loop {
+                if let Some(idx) = self.received.iter().position(&filter) {
+                    return self.received.remove(idx);
+                }
+                let message = self.output.recv_timeout(Duration::from_secs(30)).unwrap();
+                self.received.push(read_message(&mut message.as_slice()).unwrap().unwrap());
+            }
+        }
+
+        fn event(&mut self, event: &str) -> Value {
+            self.wait_for(|it| it["type"] == "event" && it["event"] == event)
+        }
+
+        /// Waits for the evaluation to stop, and describes where it stopped.
+        fn stopped(&mut self) -> String {
+            let reason = self.event("stopped")["body"]["reason"].as_str().unwrap().to_owned();
+            let stack = self.request("stackTrace", json!({ "threadId": THREAD_ID }));
+            let frames = stack["body"]["stackFrames"].as_array().unwrap();
+            let frames: Vec<_> = frames
+                .iter()
+                .map(|it| format!("{}:{}", it["name"].as_str().unwrap(), it["line"]))
+                .collect();
+            format!("{reason} at {}", frames.join(" < "))
+        }
+
+        fn set_breakpoints(&mut self, path: &str, lines: &[u32]) -> Value {
+            let breakpoints: Vec<_> = lines.iter().map(|line| json!({ "line": line })).collect();
+            let response = self.request(
+                "setBreakpoints",
+                json!({ "source": { "path": path }, "breakpoints": breakpoints }),
+            );
+            response["body"]["breakpoints"].clone()
+        }
+
+        /// Waits for the evaluation to finish, and returns its exit code.
+        fn exited(&mut self) -> Value {
+            let exit_code = self.event("exited")["body"]["exitCode"].clone();
+            self.event("terminated");
+            exit_code
+        }
+    }
+
+    fn frame(message: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{message}", message.len())
+    }
+
+    #[test]
+    fn read_requests() {
+        let input = format!(
+            "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n{}{}",
+            frame(r#"{ "seq": 1, "type": "request", "command": "initialize" }"#),
+            frame(r#"{ "seq": 2, "command": "next", "arguments": { "threadId": 1 } }"#),
+        );
+        let mut input = input.as_bytes();
+        let request: Request = read_message(&mut input).unwrap().unwrap();
+        assert_eq!((request.seq, request.command.as_str()), (1, "initialize"));
+        assert_eq!(request.arguments, Value::Null);
+        let request: Request = read_message(&mut input).unwrap().unwrap();
+        assert_eq!((request.seq, request.command.as_str()), (2, "next"));
+        assert_eq!(request.arguments, json!({ "threadId": 1 }));
+        assert!(read_message::<Request>(&mut input).unwrap().is_none());
+
+        let mut input = "Content-Type: text/plain\r\n\r\n{}".as_bytes();
+        assert!(read_message::<Request>(&mut input).is_err());
+    }
+
+    #[test]
+    fn requests_need_a_launched_evaluation() {
+        let (sender, _output) = crossbeam_channel::unbounded();
+        let mut server = Server::new(Client::new(Output(sender)));
+        for command in ["configurationDone", "setBreakpoints", "stackTrace", "continue", "foo"] {
+            let request = Request { seq: 1, command: command.to_owned(), arguments: json!({}) };
+            assert!(server.handle(request).unwrap().is_continue());
+        }
+        let request = Request { seq: 1, command: "disconnect".to_owned(), arguments: json!({}) };
+        assert!(server.handle(request).unwrap().is_break());
+    }
+
+    #[test]
+    fn step() {
+        let mut session = Session::launch(FIXTURE, true);
+        assert_eq!(session.request("configurationDone", json!({}))["success"], true);
+        assert_eq!(session.stopped(), "entry at main:7");
+        session.request("next", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "step at main:8");
+        session.request("stepIn", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "step at add:2 < main:8");
+        session.request("next", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "step at add:3 < main:8");
+        // Stepping out stops at the rest of the line of the call.
+        session.request("stepOut", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "step at main:8");
+        session.request("next", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "step at main:9");
+        session.request("continue", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.exited(), 0);
+
+        // Requests that were sent before the editor learned that the evaluation finished.
+        let response = session.request("stackTrace", json!({ "threadId": THREAD_ID }));
+        assert_eq!(response["success"], false);
+        assert_eq!(response["message"], "the evaluation has finished");
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut session = Session::launch(FIXTURE, false);
+        let breakpoints = session.set_breakpoints("/main.rs", &[3]);
+        assert_eq!(breakpoints, json!([{ "verified": true, "line": 3 }]));
+        let breakpoints = session.set_breakpoints("/other.rs", &[1]);
+        assert_eq!(breakpoints, json!([{ "verified": false, "line": 1 }]));
+        session.request("configurationDone", json!({}));
+        assert_eq!(session.stopped(), "breakpoint at add:3 < main:8");
+
+        let scopes = session.request("scopes", json!({ "frameId": 1 }));
+        let reference = scopes["body"]["scopes"][0]["variablesReference"].clone();
+        let variables = session.request("variables", json!({ "variablesReference": reference }));
+        let mut variables: Vec<_> = variables["body"]["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|it| format!("{}: {} = {}", it["name"], it["type"], it["value"]))
+            .collect();
+        variables.sort();
+        assert_eq!(
+            variables,
+            [r#""a": "i32" = "1""#, r#""b": "i32" = "2""#, r#""sum": "i32" = "3""#]
+        );
+
+        // Breakpoints can also be changed while the evaluation is stopped.
+        session.set_breakpoints("/main.rs", &[9]);
+        session.request("continue", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.stopped(), "breakpoint at main:9");
+        session.request("continue", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.exited(), 0);
+    }
+}
@@ -98,8 +98,16 @@ xflags::xflags! {
         cmd run-tests {
             /// Directory with Cargo.toml.
             required path: PathBuf
+
+            /// Let the tests read, but not write, the files of the directory with Cargo.toml.
+            optional --read-workspace
+            /// An environment variable visible to the tests, in the `KEY=VALUE` form.
+            repeated --env env: String
         }
 
+        /// Debug a function with the mir interpreter, over the Debug Adapter Protocol on stdio.
+        cmd dap {}
+
         cmd diagnostics {
             /// Directory with Cargo.toml.
             required path: PathBuf
@@ -115,6 +123,13 @@ xflags::xflags! {
         cmd ssr {
             /// A structured search replace rule (`$a.foo($b) ==> bar($a, $b)`)
             repeated rule: SsrRule
+            /// Also apply the named rules of this file, written as `name: rule`, one per line.
+            /// Indented lines continue a rule and lines starting with `#` are comments.
+            optional --rules-file path: PathBuf
+            /// Print the changes as a unified diff instead of writing them.
+            optional --dry-run
+            /// Print every match instead of writing the changes, and fail if there are any.
+            optional --check
         }
 
         cmd search {
@@ -158,6 +173,7 @@ pub enum RustAnalyzerCmd {
     Highlight(Highlight),
     AnalysisStats(AnalysisStats),
     RunTests(RunTests),
+    Dap(Dap),
     Diagnostics(Diagnostics),
     Ssr(Ssr),
     Search(Search),
@@ -209,8 +225,14 @@ pub struct AnalysisStats {
 #[derive(Debug)]
 pub struct RunTests {
     pub path: PathBuf,
+
+    pub read_workspace: bool,
+    pub env: Vec<String>,
 }
 
+#[derive(Debug)]
+pub struct Dap;
+
 #[derive(Debug)]
 pub struct Diagnostics {
     pub path: PathBuf,
@@ -223,6 +245,10 @@ pub struct Diagnostics {
 #[derive(Debug)]
 pub struct Ssr {
     pub rule: Vec<SsrRule>,
+
+    pub rules_file: Option<PathBuf>,
+    pub dry_run: bool,
+    pub check: bool,
 }
 
 #[derive(Debug)]
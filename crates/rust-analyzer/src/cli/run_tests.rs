@@ -1,15 +1,12 @@
 //! Run all tests in a project, similar to `cargo test`, but using the mir interpreter.
 
-use hir::{Crate, Module};
-use hir_ty::db::HirDatabase;
-use ide_db::{base_db::SourceDatabaseExt, LineIndexDatabase};
+use hir::MirEvalSandbox;
 use profile::StopWatch;
 use project_model::{CargoConfig, RustLibSource};
-use syntax::TextRange;
 
 use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
 
-use crate::cli::{flags, full_name_of_item, Result};
+use crate::cli::{all_modules, flags, format_span, full_name_of_item, Result};
 
 impl flags::RunTests {
     pub fn run(self) -> Result<()> {
@@ -19,10 +16,12 @@ impl flags::RunTests {
             load_out_dirs_from_check: true,
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
+            persist_caches: None,
         };
         let (host, _vfs, _proc_macro) =
             load_workspace_at(&self.path, &cargo_config, &load_cargo_config, &|_| {})?;
         let db = host.raw_database();
+        let sandbox = self.sandbox()?;
 
         let tests = all_modules(db)
             .into_iter()
@@ -32,18 +31,7 @@ impl flags::RunTests {
                 _ => None,
             })
             .filter(|x| x.is_test(db));
-        let span_formatter = |file_id, text_range: TextRange| {
-            let line_col = match db.line_index(file_id).try_line_col(text_range.start()) {
-                None => " (unknown line col)".to_string(),
-                Some(x) => format!("#{}:{}", x.line + 1, x.col),
-            };
-            let path = &db
-                .source_root(db.file_source_root(file_id))
-                .path_for_file(&file_id)
-                .map(|x| x.to_string());
-            let path = path.as_deref().unwrap_or("<unknown file>");
-            format!("file://{path}{line_col}")
-        };
+        let span_formatter = |file_id, text_range| format_span(db, file_id, text_range);
         let mut pass_count = 0;
         let mut ignore_count = 0;
         let mut fail_count = 0;
@@ -57,7 +45,7 @@ impl flags::RunTests {
                 continue;
             }
             let mut sw_one = StopWatch::start();
-            let result = test.eval(db, span_formatter);
+            let result = test.eval(db, sandbox.clone(), None, span_formatter);
             if result.trim() == "pass" {
                 pass_count += 1;
             } else {
@@ -70,20 +58,19 @@ impl flags::RunTests {
         eprintln!("{:<20} {}", "All tests", sw_all.elapsed());
         Ok(())
     }
-}
-
-fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
-    let mut worklist: Vec<_> = Crate::all(db)
-        .into_iter()
-        .filter(|x| x.origin(db).is_local())
-        .map(|krate| krate.root_module(db))
-        .collect();
-    let mut modules = Vec::new();
 
-    while let Some(module) = worklist.pop() {
-        modules.push(module);
-        worklist.extend(module.children(db));
+    fn sandbox(&self) -> Result<MirEvalSandbox> {
+        let mut sandbox = MirEvalSandbox::default();
+        for env in &self.env {
+            let Some((key, value)) = env.split_once('=') else {
+                anyhow::bail!("invalid environment variable `{env}`, expected `KEY=VALUE`");
+            };
+            sandbox.env.insert(key.to_owned(), value.to_owned());
+        }
+        if self.read_workspace {
+            // Like `cargo test`, tests see the directory of the package as the working directory.
+            sandbox.read_only_root = Some(std::fs::canonicalize(&self.path)?);
+        }
+        Ok(sandbox)
     }
-
-    modules
 }
@@ -33,6 +33,7 @@ impl flags::Scip {
             load_out_dirs_from_check: true,
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: true,
+            persist_caches: None,
         };
         let path = vfs::AbsPathBuf::assert(env::current_dir()?.join(&self.path));
         let rootpath = path.normalize();
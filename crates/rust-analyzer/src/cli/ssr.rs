@@ -1,9 +1,14 @@
 //! Applies structured search replace rules from the command line.
 
+use std::ops::Range;
+
 use anyhow::Context;
-use ide_ssr::MatchFinder;
+use ide::TextEdit;
+use ide_db::{base_db::FileId, LineIndexDatabase};
+use ide_ssr::{parse_rule_file, MatchFinder, NamedSsrRule};
 use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
 use project_model::{CargoConfig, RustLibSource};
+use vfs::{AbsPathBuf, Vfs};
 
 use crate::cli::flags;
 
@@ -16,31 +21,195 @@ impl flags::Ssr {
             load_out_dirs_from_check: true,
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
+            persist_caches: None,
         };
-        let (host, vfs, _proc_macro) = load_workspace_at(
-            &std::env::current_dir()?,
-            &cargo_config,
-            &load_cargo_config,
-            &|_| {},
-        )?;
+        let current_dir = std::env::current_dir()?;
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(&current_dir, &cargo_config, &load_cargo_config, &|_| {})?;
         let db = host.raw_database();
-        let mut match_finder = MatchFinder::at_first_file(db)?;
-        for rule in self.rule {
-            match_finder.add_rule(rule)?;
+        let current_dir = AbsPathBuf::assert(current_dir);
+        let mut rules: Vec<_> = self
+            .rule
+            .into_iter()
+            .enumerate()
+            .map(|(idx, rule)| NamedSsrRule { name: format!("rule {}", idx + 1), rule })
+            .collect();
+        if let Some(path) = &self.rules_file {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            rules.extend(
+                parse_rule_file(&text)
+                    .with_context(|| format!("invalid rules file {}", path.display()))?,
+            );
         }
-        let edits = match_finder.edits();
-        for (file_id, edit) in edits {
-            if let Some(path) = vfs.file_path(file_id).as_path() {
-                let mut contents = db.file_text(file_id).to_string();
-                edit.apply(&mut contents);
-                std::fs::write(path, contents)
-                    .with_context(|| format!("failed to write {path}"))?;
+
+        let mut match_count = 0;
+        if self.check {
+            // Rules are matched on their own, so that every match can be attributed to a rule.
+            for rule in &rules {
+                let mut match_finder = MatchFinder::at_first_file(db)?;
+                match_finder.add_rule(rule.rule.clone())?;
+                for m in match_finder.matches().flattened().matches {
+                    let range = m.file_range();
+                    let line_col = db.line_index(range.file_id).line_col(range.range.start());
+                    println!(
+                        "{}:{}:{}: {}: {}",
+                        display_path(&vfs, &current_dir, range.file_id),
+                        line_col.line + 1,
+                        line_col.col + 1,
+                        rule.name,
+                        m.matched_text()
+                    );
+                    match_count += 1;
+                }
+            }
+        }
+
+        if !self.check || self.dry_run {
+            let mut match_finder = MatchFinder::at_first_file(db)?;
+            for rule in rules {
+                match_finder.add_rule(rule.rule)?;
+            }
+            let mut edits: Vec<_> = match_finder.edits().into_iter().collect();
+            edits.sort_by_cached_key(|(file_id, _)| display_path(&vfs, &current_dir, *file_id));
+            for (file_id, edit) in edits {
+                let Some(path) = vfs.file_path(file_id).as_path().map(|it| it.to_owned()) else {
+                    continue;
+                };
+                let before = db.file_text(file_id);
+                if self.dry_run {
+                    let path = display_path(&vfs, &current_dir, file_id);
+                    print!("{}", unified_diff(&path, &before, &edit));
+                } else {
+                    let mut contents = before.to_string();
+                    edit.apply(&mut contents);
+                    std::fs::write(&path, contents)
+                        .with_context(|| format!("failed to write {path}"))?;
+                }
             }
         }
+
+        if match_count > 0 {
+            anyhow::bail!("{match_count} SSR matches found");
+        }
         Ok(())
     }
 }
 
+/// Returns the path of the file relative to the current directory, if it's inside of it.
+fn display_path(vfs: &Vfs, current_dir: &AbsPathBuf, file_id: FileId) -> String {
+    let path = vfs.file_path(file_id);
+    match path.as_path().and_then(|it| it.strip_prefix(current_dir)) {
+        Some(relative) => relative.as_ref().display().to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Renders the changes that `edit` makes to `before` as a unified diff, like `diff -u` does.
+fn unified_diff(path: &str, before: &str, edit: &TextEdit) -> String {
+    const CONTEXT: usize = 3;
+    let old_lines: Vec<&str> = before.split_inclusive('\n').collect();
+    let mut line_starts = vec![0];
+    line_starts.extend(before.match_indices('\n').map(|(idx, _)| idx + 1));
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+    // The ranges of changed lines of `before`, with the lines that replace them.
+    let mut changes: Vec<(Range<usize>, Vec<&str>)> = Vec::new();
+    let mut afters = Vec::new();
+    let mut indels = edit.iter().peekable();
+    while let Some(indel) = indels.next() {
+        let first = line_of(indel.delete.start().into());
+        let mut last = line_of(indel.delete.end().into());
+        let mut group = vec![indel];
+        // Indels that touch the same lines are rendered as a single change.
+        while let Some(next) = indels.next_if(|it| line_of(it.delete.start().into()) <= last) {
+            last = line_of(next.delete.end().into());
+            group.push(next);
+        }
+        let block_end = line_starts.get(last + 1).copied().unwrap_or(before.len());
+        let mut pos = line_starts[first];
+        let mut after = String::new();
+        for indel in group {
+            after.push_str(&before[pos..indel.delete.start().into()]);
+            after.push_str(&indel.insert);
+            pos = indel.delete.end().into();
+        }
+        after.push_str(&before[pos..block_end]);
+        afters.push((first..(last + 1).min(old_lines.len()), after));
+    }
+    for (old, after) in &afters {
+        let mut old = old.clone();
+        let mut new: Vec<&str> = after.split_inclusive('\n').collect();
+        // Lines at the edges of the change that stay the same are context, not changes.
+        while old.start < old.end && new.first() == Some(&old_lines[old.start]) {
+            old.start += 1;
+            new.remove(0);
+        }
+        while old.start < old.end && new.last() == Some(&old_lines[old.end - 1]) {
+            old.end -= 1;
+            new.pop();
+        }
+        if !old.is_empty() || !new.is_empty() {
+            changes.push((old, new));
+        }
+    }
+
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    // How many lines the changes before the current hunk added, or removed if negative.
+    let mut added_lines = 0isize;
+    let mut hunk_start = 0;
+    while hunk_start < changes.len() {
+        let mut hunk_end = hunk_start + 1;
+        while hunk_end < changes.len()
+            && changes[hunk_end].0.start - changes[hunk_end - 1].0.end <= 2 * CONTEXT
+        {
+            hunk_end += 1;
+        }
+        let hunk = &changes[hunk_start..hunk_end];
+        let start = hunk[0].0.start.saturating_sub(CONTEXT);
+        let end = (hunk[hunk.len() - 1].0.end + CONTEXT).min(old_lines.len());
+        let mut body = String::new();
+        let (mut old_len, mut new_len) = (0, 0);
+        let mut line = start;
+        for (old, new) in hunk {
+            for it in &old_lines[line..old.start] {
+                push_diff_line(&mut body, ' ', it);
+            }
+            for it in &old_lines[old.clone()] {
+                push_diff_line(&mut body, '-', it);
+            }
+            for it in new {
+                push_diff_line(&mut body, '+', it);
+            }
+            old_len += old.start - line + old.len();
+            new_len += old.start - line + new.len();
+            line = old.end;
+        }
+        for it in &old_lines[line..end] {
+            push_diff_line(&mut body, ' ', it);
+        }
+        old_len += end - line;
+        new_len += end - line;
+        let new_start = (start as isize + added_lines) as usize;
+        // Empty ranges start at the line before them.
+        let old_start = if old_len == 0 { start } else { start + 1 };
+        let new_start = if new_len == 0 { new_start } else { new_start + 1 };
+        out.push_str(&format!("@@ -{old_start},{old_len} +{new_start},{new_len} @@\n"));
+        out.push_str(&body);
+        added_lines += new_len as isize - old_len as isize;
+        hunk_start = hunk_end;
+    }
+    out
+}
+
+fn push_diff_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
 impl flags::Search {
     /// Searches for `patterns`, printing debug information for any nodes whose text exactly matches
     /// `debug_snippet`. This is intended for debugging and probably isn't in it's current form useful
@@ -53,6 +222,7 @@ impl flags::Search {
             load_out_dirs_from_check: true,
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
+            persist_caches: None,
         };
         let (host, _vfs, _proc_macro) = load_workspace_at(
             &std::env::current_dir()?,
@@ -84,3 +254,85 @@ impl flags::Search {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide::{TextRange, TextSize};
+
+    use super::*;
+
+    fn check_diff(before: &str, edits: &[(u32, u32, &str)], expect: Expect) {
+        let mut builder = TextEdit::builder();
+        for &(start, end, insert) in edits {
+            builder
+                .replace(TextRange::new(TextSize::from(start), TextSize::from(end)), insert.into());
+        }
+        expect.assert_eq(&unified_diff("src/lib.rs", before, &builder.finish()));
+    }
+
+    #[test]
+    fn diff_single_change() {
+        check_diff(
+            "a\nb\nc\nd\ne\n",
+            &[(4, 5, "x")],
+            expect![[r#"
+                --- a/src/lib.rs
+                +++ b/src/lib.rs
+                @@ -1,5 +1,5 @@
+                 a
+                 b
+                -c
+                +x
+                 d
+                 e
+            "#]],
+        );
+    }
+
+    #[test]
+    fn diff_separate_hunks() {
+        let before: String = (1..=20).map(|it| format!("{it}\n")).collect();
+        check_diff(
+            &before,
+            // Replaces `2` with two lines, and removes the line `18`.
+            &[(2, 3, "two\nzwei"), (42, 45, "")],
+            expect![[r#"
+                --- a/src/lib.rs
+                +++ b/src/lib.rs
+                @@ -1,5 +1,6 @@
+                 1
+                -2
+                +two
+                +zwei
+                 3
+                 4
+                 5
+                @@ -15,6 +16,5 @@
+                 15
+                 16
+                 17
+                -18
+                 19
+                 20
+            "#]],
+        );
+    }
+
+    #[test]
+    fn diff_without_trailing_newline() {
+        check_diff(
+            "fn f() {}",
+            &[(3, 4, "g")],
+            expect![[r#"
+                --- a/src/lib.rs
+                +++ b/src/lib.rs
+                @@ -1,1 +1,1 @@
+                -fn f() {}
+                \ No newline at end of file
+                +fn g() {}
+                \ No newline at end of file
+            "#]],
+        );
+    }
+}
@@ -14,7 +14,8 @@ use flycheck::FlycheckConfig;
 use ide::{
     AssistConfig, CallableSnippets, CompletionConfig, DiagnosticsConfig, ExprFillDefaultMode,
     HighlightConfig, HighlightRelatedConfig, HoverConfig, HoverDocFormat, InlayHintsConfig,
-    JoinLinesConfig, MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind, Snippet, SnippetScope,
+    JoinLinesConfig, MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind, MirEvalSandbox, Snippet,
+    SnippetScope,
 };
 use ide_db::{
     imports::insert_use::{ImportGranularity, InsertUseConfig, PrefixKind},
@@ -66,6 +67,10 @@ config_data! {
         cachePriming_enable: bool = "true",
         /// How many worker threads to handle priming caches. The default `0` means to pick automatically.
         cachePriming_numThreads: ParallelCachePrimingNumThreads = "0",
+        /// Whether to store the item trees and symbol indices of library crates in the
+        /// `rust-analyzer` directory of the Cargo target directory, so that later sessions can
+        /// reuse them.
+        cachePriming_persist: bool = "false",
 
         /// Automatically refresh project info via `cargo metadata` on
         /// `Cargo.toml` or `.cargo/config.toml` changes.
@@ -108,6 +113,10 @@ config_data! {
         /// Extra environment variables that will be set when running cargo, rustc
         /// or other commands within the workspace. Useful for setting RUSTFLAGS.
         cargo_extraEnv: FxHashMap<String, String> = "{}",
+        /// Additional target triples to analyze the crates of Cargo workspaces for, next to
+        /// `#rust-analyzer.cargo.target#`. Each crate is analyzed once per target, and the target
+        /// a file is shown for can be switched with the `rust-analyzer.setActiveTarget` command.
+        cargo_extraTargets: Vec<String> = "[]",
         /// List of features to activate.
         ///
         /// Set this to `"all"` to pass `--all-features` to cargo.
@@ -274,6 +283,12 @@ config_data! {
         /// The warnings will be indicated by a blue squiggly underline in code
         /// and a blue icon in the `Problems Panel`.
         diagnostics_warningsAsInfo: Vec<String> = "[]",
+        /// Whether to compute native diagnostics for all files in local source roots instead of
+        /// only for the open ones.
+        ///
+        /// If the client supports pull diagnostics, these are reported through `workspace/diagnostic`,
+        /// otherwise they are published as they get computed.
+        diagnostics_workspace_enable: bool = "false",
         /// These directories will be ignored by rust-analyzer. They are
         /// relative to the workspace root, and globs are not supported. You may
         /// also need to add the folders to Code's `files.watcherExclude`.
@@ -356,6 +371,8 @@ config_data! {
         inlayHints_closureReturnTypeHints_enable: ClosureReturnTypeHintsDef  = "\"never\"",
         /// Closure notation in type and chaining inlay hints.
         inlayHints_closureStyle: ClosureStyle                                = "\"impl_fn\"",
+        /// Whether to show inlay hints for the values of constant expressions.
+        inlayHints_constValueHints_enable: bool                              = "false",
         /// Whether to show enum variant discriminant hints.
         inlayHints_discriminantHints_enable: DiscriminantHintsDef            = "\"never\"",
         /// Whether to show inlay hints for type adjustments.
@@ -364,6 +381,11 @@ config_data! {
         inlayHints_expressionAdjustmentHints_hideOutsideUnsafe: bool = "false",
         /// Whether to show inlay hints as postfix ops (`.*` instead of `*`, etc).
         inlayHints_expressionAdjustmentHints_mode: AdjustmentHintsModeDef = "\"prefix\"",
+        /// Whether to show inlay hints for the names of generic parameters of explicit generic
+        /// arguments.
+        inlayHints_genericParameterHints_enable: bool              = "false",
+        /// Whether to show inlay hints for the points where locals are implicitly dropped.
+        inlayHints_implicitDrops_enable: bool                      = "false",
         /// Whether to show inlay type hints for elided lifetimes in function signatures.
         inlayHints_lifetimeElisionHints_enable: LifetimeElisionDef = "\"never\"",
         /// Whether to prefer using parameter names as the name for elided lifetime hints if possible.
@@ -378,6 +400,9 @@ config_data! {
         inlayHints_reborrowHints_enable: ReborrowHintsDef          = "\"never\"",
         /// Whether to render leading colons for type hints, and trailing colons for parameter hints.
         inlayHints_renderColons: bool                              = "true",
+        /// Whether to show inlay hints for the inferred generic arguments of function and method
+        /// calls without a turbofish.
+        inlayHints_turbofishHints_enable: bool                     = "false",
         /// Whether to show inlay type hints for variables.
         inlayHints_typeHints_enable: bool                          = "true",
         /// Whether to hide inlay type hints for `let` statements that initialize to a closure.
@@ -385,6 +410,12 @@ config_data! {
         inlayHints_typeHints_hideClosureInitialization: bool       = "false",
         /// Whether to hide inlay type hints for constructors.
         inlayHints_typeHints_hideNamedConstructor: bool            = "false",
+        /// Environment variables visible to the functions and tests run by the interpreter.
+        interpret_env: FxHashMap<String, String>                   = "{}",
+        /// Whether the functions and tests run by the interpreter can read the files of their
+        /// package. They can never write to the real file system, and without this they only see
+        /// an empty in-memory one.
+        interpret_readWorkspace: bool                              = "false",
         /// Enables the experimental support for interpreting tests.
         interpret_tests: bool                                      = "false",
 
@@ -447,12 +478,18 @@ config_data! {
 
         /// Expand attribute macros. Requires `#rust-analyzer.procMacro.enable#` to be set.
         procMacro_attributes_enable: bool = "true",
+        /// Cache the expansions of proc-macros in the proc-macro server, so that expanding a macro
+        /// with the same input again does not run it again. Disable this for proc-macros which
+        /// read external state without declaring it.
+        procMacro_cache_enable: bool = "true",
         /// Enable support for procedural macros, implies `#rust-analyzer.cargo.buildScripts.enable#`.
         procMacro_enable: bool                     = "true",
         /// These proc-macros will be ignored when trying to expand them.
         ///
         /// This config takes a map of crate names with the exported proc-macro names to ignore as values.
         procMacro_ignored: FxHashMap<Box<str>, Box<[Box<str>]>>          = "{}",
+        /// Run a separate proc-macro server for every proc-macro crate, restarting it if it crashes.
+        procMacro_isolate: bool                    = "false",
         /// Internal config, path to proc-macro server executable.
         procMacro_server: Option<PathBuf>          = "null",
 
@@ -530,9 +567,19 @@ config_data! {
         /// Show documentation.
         signatureInfo_documentation_enable: bool                       = "true",
 
+        /// Whether to provide the test explorer protocol, which lets the client discover the tests
+        /// of the workspace and run them, receiving a result for each test.
+        testExplorer_enable: bool = "false",
+
         /// Whether to insert closing angle brackets when typing an opening angle bracket of a generic argument list.
         typing_autoClosingAngleBrackets_enable: bool = "false",
 
+        /// Command run to discover the `rust-project.json` describing an opened file which doesn't
+        /// belong to any known crate, for build systems other than cargo. The path of the file is
+        /// appended as the last argument, and the command is expected to print the contents of a
+        /// `rust-project.json` to stdout. Relative paths in it are resolved against the workspace
+        /// root, which is also the working directory of the command.
+        workspace_discoverCommand: Option<Vec<String>> = "null",
         /// Workspace symbol search kind.
         workspace_symbol_search_kind: WorkspaceSymbolSearchKindDef = "\"only_types\"",
         /// Limits the number of items returned from a workspace symbol search (Defaults to 128).
@@ -944,6 +991,10 @@ impl Config {
         self.data.cachePriming_enable
     }
 
+    pub fn persist_caches(&self) -> bool {
+        self.data.cachePriming_persist
+    }
+
     pub fn location_link(&self) -> bool {
         try_or_def!(self.caps.text_document.as_ref()?.definition?.link_support?)
     }
@@ -1077,6 +1128,10 @@ impl Config {
         self.data.diagnostics_enable
     }
 
+    pub fn workspace_diagnostics(&self) -> bool {
+        self.data.diagnostics_workspace_enable
+    }
+
     pub fn diagnostics(&self) -> DiagnosticsConfig {
         DiagnosticsConfig {
             enabled: self.data.diagnostics_enable,
@@ -1105,6 +1160,10 @@ impl Config {
         &self.data.cargo_extraArgs
     }
 
+    pub fn discover_command(&self) -> Option<&[String]> {
+        self.data.workspace_discoverCommand.as_deref()
+    }
+
     pub fn extra_env(&self) -> &FxHashMap<String, String> {
         &self.data.cargo_extraEnv
     }
@@ -1134,6 +1193,14 @@ impl Config {
         Some(AbsPathBuf::try_from(path).unwrap_or_else(|path| self.root_path.join(&path)))
     }
 
+    pub fn proc_macro_srv_cache(&self) -> bool {
+        self.data.procMacro_cache_enable
+    }
+
+    pub fn proc_macro_srv_isolated(&self) -> bool {
+        self.data.procMacro_isolate
+    }
+
     pub fn dummy_replacements(&self) -> &FxHashMap<Box<str>, Box<[Box<str>]>> {
         &self.data.procMacro_ignored
     }
@@ -1197,6 +1264,7 @@ impl Config {
                 },
             },
             target: self.data.cargo_target.clone(),
+            extra_targets: self.data.cargo_extraTargets.clone(),
             sysroot,
             sysroot_src,
             rustc_source,
@@ -1361,6 +1429,10 @@ impl Config {
                 ClosureStyle::Hide => hir::ClosureStyle::Hide,
             },
             closure_capture_hints: self.data.inlayHints_closureCaptureHints_enable,
+            turbofish_hints: self.data.inlayHints_turbofishHints_enable,
+            generic_parameter_hints: self.data.inlayHints_genericParameterHints_enable,
+            implicit_drop_hints: self.data.inlayHints_implicitDrops_enable,
+            const_value_hints: self.data.inlayHints_constValueHints_enable,
             adjustment_hints: match self.data.inlayHints_expressionAdjustmentHints_enable {
                 AdjustmentHintsDef::Always => ide::AdjustmentHints::Always,
                 AdjustmentHintsDef::Never => match self.data.inlayHints_reborrowHints_enable {
@@ -1475,6 +1547,19 @@ impl Config {
         }
     }
 
+    /// The sandbox of the interpreter, `package_root` is the directory it can read if that's
+    /// enabled.
+    pub fn interpret_sandbox(&self, package_root: &AbsPath) -> MirEvalSandbox {
+        MirEvalSandbox {
+            files: Default::default(),
+            env: self.data.interpret_env.clone(),
+            read_only_root: self
+                .data
+                .interpret_readWorkspace
+                .then(|| package_root.as_ref().to_path_buf()),
+        }
+    }
+
     pub fn lens(&self) -> LensConfig {
         LensConfig {
             run: self.data.lens_enable && self.data.lens_run_enable,
@@ -1578,6 +1663,14 @@ impl Config {
         try_or_def!(self.caps.workspace.as_ref()?.semantic_tokens.as_ref()?.refresh_support?)
     }
 
+    pub fn pull_diagnostics(&self) -> bool {
+        try_!(self.caps.text_document.as_ref()?.diagnostic.as_ref()?).is_some()
+    }
+
+    pub fn diagnostics_refresh(&self) -> bool {
+        try_or_def!(self.caps.workspace.as_ref()?.diagnostic.as_ref()?.refresh_support?)
+    }
+
     pub fn code_lens_refresh(&self) -> bool {
         try_or_def!(self.caps.workspace.as_ref()?.code_lens.as_ref()?.refresh_support?)
     }
@@ -1639,6 +1732,10 @@ impl Config {
         self.data.numThreads.unwrap_or(num_cpus::get_physical().try_into().unwrap_or(1))
     }
 
+    pub fn test_explorer(&self) -> bool {
+        self.data.testExplorer_enable
+    }
+
     pub fn typing_autoclose_angle(&self) -> bool {
         self.data.typing_autoClosingAngleBrackets_enable
     }
@@ -3,14 +3,20 @@ pub(crate) mod to_proto;
 
 use std::mem;
 
-use ide::FileId;
+use ide::{Cancellable, FileId};
 use ide_db::FxHashMap;
 use nohash_hasher::{IntMap, IntSet};
 use triomphe::Arc;
 
-use crate::lsp_ext;
+use crate::{global_state::GlobalStateSnapshot, lsp_ext};
 
 pub(crate) type CheckFixes = Arc<IntMap<usize, IntMap<FileId, Vec<Fix>>>>;
+pub(crate) type NativeDiagnostics =
+    Arc<IntMap<FileId, (DiagnosticsGeneration, Vec<lsp_types::Diagnostic>)>>;
+
+/// Identifies a computation of native diagnostics, increasing with every change of them. The
+/// generation of a file's diagnostics is used as the result id for pulled diagnostics.
+pub(crate) type DiagnosticsGeneration = usize;
 
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticsMapConfig {
@@ -22,11 +28,13 @@ pub struct DiagnosticsMapConfig {
 #[derive(Debug, Default, Clone)]
 pub(crate) struct DiagnosticCollection {
     // FIXME: should be IntMap<FileId, Vec<ra_id::Diagnostic>>
-    pub(crate) native: IntMap<FileId, Vec<lsp_types::Diagnostic>>,
+    pub(crate) native: NativeDiagnostics,
     // FIXME: should be Vec<flycheck::Diagnostic>
     pub(crate) check: IntMap<usize, IntMap<FileId, Vec<lsp_types::Diagnostic>>>,
     pub(crate) check_fixes: CheckFixes,
-    changes: IntSet<FileId>,
+    native_changes: IntSet<FileId>,
+    check_changes: IntSet<FileId>,
+    generation: DiagnosticsGeneration,
 }
 
 #[derive(Debug, Clone)]
@@ -42,19 +50,19 @@ impl DiagnosticCollection {
             it.clear();
         }
         if let Some(it) = self.check.get_mut(&flycheck_id) {
-            self.changes.extend(it.drain().map(|(key, _value)| key));
+            self.check_changes.extend(it.drain().map(|(key, _value)| key));
         }
     }
 
     pub(crate) fn clear_check_all(&mut self) {
         Arc::make_mut(&mut self.check_fixes).clear();
-        self.changes
+        self.check_changes
             .extend(self.check.values_mut().flat_map(|it| it.drain().map(|(key, _value)| key)))
     }
 
     pub(crate) fn clear_native_for(&mut self, file_id: FileId) {
-        self.native.remove(&file_id);
-        self.changes.insert(file_id);
+        Arc::make_mut(&mut self.native).remove(&file_id);
+        self.native_changes.insert(file_id);
     }
 
     pub(crate) fn add_check_diagnostic(
@@ -74,7 +82,7 @@ impl DiagnosticCollection {
         let check_fixes = Arc::make_mut(&mut self.check_fixes);
         check_fixes.entry(flycheck_id).or_default().entry(file_id).or_default().extend(fix);
         diagnostics.push(diagnostic);
-        self.changes.insert(file_id);
+        self.check_changes.insert(file_id);
     }
 
     pub(crate) fn set_native_diagnostics(
@@ -82,7 +90,7 @@ impl DiagnosticCollection {
         file_id: FileId,
         diagnostics: Vec<lsp_types::Diagnostic>,
     ) {
-        if let Some(existing_diagnostics) = self.native.get(&file_id) {
+        if let Some((_, existing_diagnostics)) = self.native.get(&file_id) {
             if existing_diagnostics.len() == diagnostics.len()
                 && diagnostics
                     .iter()
@@ -93,24 +101,102 @@ impl DiagnosticCollection {
             }
         }
 
-        self.native.insert(file_id, diagnostics);
-        self.changes.insert(file_id);
+        self.generation += 1;
+        Arc::make_mut(&mut self.native).insert(file_id, (self.generation, diagnostics));
+        self.native_changes.insert(file_id);
     }
 
     pub(crate) fn diagnostics_for(
         &self,
         file_id: FileId,
     ) -> impl Iterator<Item = &lsp_types::Diagnostic> {
-        let native = self.native.get(&file_id).into_iter().flatten();
-        let check = self.check.values().filter_map(move |it| it.get(&file_id)).flatten();
-        native.chain(check)
+        let native = self.native.get(&file_id).into_iter().flat_map(|(_, it)| it);
+        native.chain(self.check_diagnostics_for(file_id))
+    }
+
+    pub(crate) fn check_diagnostics_for(
+        &self,
+        file_id: FileId,
+    ) -> impl Iterator<Item = &lsp_types::Diagnostic> {
+        self.check.values().filter_map(move |it| it.get(&file_id)).flatten()
     }
 
-    pub(crate) fn take_changes(&mut self) -> Option<IntSet<FileId>> {
-        if self.changes.is_empty() {
+    /// Returns the files whose native and whose `cargo check` diagnostics changed, respectively.
+    pub(crate) fn take_changes(&mut self) -> Option<(IntSet<FileId>, IntSet<FileId>)> {
+        if self.native_changes.is_empty() && self.check_changes.is_empty() {
             return None;
         }
-        Some(mem::take(&mut self.changes))
+        Some((mem::take(&mut self.native_changes), mem::take(&mut self.check_changes)))
+    }
+}
+
+/// Computes the native rust-analyzer diagnostics of a file.
+pub(crate) fn fetch_native_diagnostics(
+    snapshot: &GlobalStateSnapshot,
+    file_id: FileId,
+) -> Cancellable<Vec<lsp_types::Diagnostic>> {
+    let line_index = snapshot.file_line_index(file_id)?;
+    let diagnostics = snapshot.analysis.diagnostics(
+        &snapshot.config.diagnostics(),
+        ide::AssistResolveStrategy::None,
+        file_id,
+    )?;
+    let res = diagnostics
+        .into_iter()
+        .map(move |d| lsp_types::Diagnostic {
+            range: crate::to_proto::range(&line_index, d.range),
+            severity: Some(crate::to_proto::diagnostic_severity(d.severity)),
+            code: Some(lsp_types::NumberOrString::String(d.code.as_str().to_string())),
+            code_description: Some(lsp_types::CodeDescription {
+                href: lsp_types::Url::parse(&d.code.url()).unwrap(),
+            }),
+            source: Some("rust-analyzer".to_string()),
+            message: d.message,
+            related_information: if d.related.is_empty() {
+                None
+            } else {
+                Some(
+                    d.related
+                        .into_iter()
+                        .filter_map(|(frange, message)| {
+                            Some(lsp_types::DiagnosticRelatedInformation {
+                                location: crate::to_proto::location(snapshot, frange).ok()?,
+                                message,
+                            })
+                        })
+                        .collect(),
+                )
+            },
+            tags: if d.unused { Some(vec![lsp_types::DiagnosticTag::UNNECESSARY]) } else { None },
+            data: None,
+        })
+        .collect();
+    Ok(res)
+}
+
+pub(crate) fn patch_empty_diagnostic_messages(diagnostics: &mut [lsp_types::Diagnostic]) {
+    // VSCode assumes diagnostic messages to be non-empty strings, so we need to patch
+    // empty diagnostics. Neither the docs of VSCode nor the LSP spec say whether
+    // diagnostic messages are actually allowed to be empty or not and patching this
+    // in the VSCode client does not work as the assertion happens in the protocol
+    // conversion. So this hack is here to stay, and will be considered a hack
+    // until the LSP decides to state that empty messages are allowed.
+
+    // See https://github.com/rust-lang/rust-analyzer/issues/11404
+    // See https://github.com/rust-lang/rust-analyzer/issues/13130
+    let patch_empty = |message: &mut String| {
+        if message.is_empty() {
+            *message = " ".to_string();
+        }
+    };
+
+    for d in diagnostics {
+        patch_empty(&mut d.message);
+        if let Some(dri) = &mut d.related_information {
+            for dri in dri {
+                patch_empty(&mut dri.message);
+            }
+        }
     }
 }
 
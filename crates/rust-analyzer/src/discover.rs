@@ -0,0 +1,42 @@
+//! Runs the user-configured `workspace.discoverCommand`, which lets build systems other than cargo
+//! describe the project a file belongs to as a `rust-project.json`.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, format_err, Context};
+use ide_db::FxHashMap;
+use project_model::{ProjectJson, ProjectJsonData};
+use vfs::AbsPath;
+
+/// Runs `command` with the path of `file` appended as its last argument, and parses its stdout as a
+/// `rust-project.json`. The command runs in `root`, relative paths of the project are resolved
+/// against it as well.
+pub(crate) fn discover_workspace(
+    command: &[String],
+    file: &AbsPath,
+    root: &AbsPath,
+    extra_env: &FxHashMap<String, String>,
+) -> anyhow::Result<ProjectJson> {
+    let (program, args) =
+        command.split_first().ok_or_else(|| format_err!("the discover command is empty"))?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .arg(file.as_os_str())
+        .current_dir(root)
+        .envs(extra_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let output = cmd.output().with_context(|| format!("failed to run {cmd:?}"))?;
+    if !output.status.success() {
+        bail!(
+            "{cmd:?} failed, {}\nstderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let data: ProjectJsonData = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("{cmd:?} did not print a valid rust-project.json"))?;
+    Ok(ProjectJson::new(root, data))
+}
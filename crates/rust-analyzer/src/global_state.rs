@@ -3,10 +3,10 @@
 //!
 //! Each tick provides an immutable snapshot of the state as `WorldSnapshot`.
 
-use std::time::Instant;
+use std::{collections::VecDeque, process::Command, time::Instant};
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use flycheck::FlycheckHandle;
+use flycheck::{CargoTestHandle, CargoTestMessage, FlycheckHandle};
 use ide::{Analysis, AnalysisHost, Cancellable, Change, FileId};
 use ide_db::base_db::{CrateId, FileLoader, ProcMacroPaths, SourceDatabase};
 use load_cargo::SourceRootConfig;
@@ -14,14 +14,14 @@ use lsp_types::{SemanticTokens, Url};
 use nohash_hasher::IntMap;
 use parking_lot::{Mutex, RwLock};
 use proc_macro_api::ProcMacroServer;
-use project_model::{CargoWorkspace, ProjectWorkspace, Target, WorkspaceBuildScripts};
+use project_model::{CargoWorkspace, ProjectJson, ProjectWorkspace, Target, WorkspaceBuildScripts};
 use rustc_hash::{FxHashMap, FxHashSet};
 use triomphe::Arc;
-use vfs::AnchoredPathBuf;
+use vfs::{AbsPathBuf, AnchoredPathBuf};
 
 use crate::{
     config::{Config, ConfigError},
-    diagnostics::{CheckFixes, DiagnosticCollection},
+    diagnostics::{CheckFixes, DiagnosticCollection, NativeDiagnostics},
     from_proto,
     line_index::{LineEndings, LineIndex},
     lsp_ext,
@@ -78,6 +78,10 @@ pub(crate) struct GlobalState {
     pub(crate) flycheck_receiver: Receiver<flycheck::Message>,
     pub(crate) last_flycheck_error: Option<String>,
 
+    // Test explorer
+    pub(crate) test_run_session: Option<TestRunSession>,
+    pub(crate) test_run_count: usize,
+
     // VFS
     pub(crate) loader: Handle<Box<dyn vfs::loader::Handle>, Receiver<vfs::loader::Message>>,
     pub(crate) vfs: Arc<RwLock<(vfs::Vfs, IntMap<FileId, LineEndings>)>>,
@@ -112,7 +116,24 @@ pub(crate) struct GlobalState {
     /// the user just adds comments or whitespace to Cargo.toml, we do not want
     /// to invalidate any salsa caches.
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
+    /// Projects found by running the `workspace.discoverCommand`, which are loaded in addition to
+    /// the linked projects.
+    pub(crate) discovered_workspaces: Vec<ProjectJson>,
+    /// The files the `workspace.discoverCommand` has been started for, so that it runs only once
+    /// per file, however often the file is opened and whether or not the run has finished.
+    pub(crate) workspace_discovery_files: FxHashSet<AbsPathBuf>,
     pub(crate) crate_graph_file_dependencies: FxHashSet<vfs::VfsPath>,
+    /// Opened files which still need to be checked for whether they belong to any crate.
+    pub(crate) ad_hoc_candidates: Vec<AbsPathBuf>,
+    /// Files which belong to no crate of the workspaces, and thus got an ad-hoc crate of their
+    /// own, see [`project_model::ad_hoc_crate`].
+    pub(crate) ad_hoc_files: Vec<AbsPathBuf>,
+    /// Files outside of the workspaces which proc-macros declared to read, and which are thus
+    /// loaded and watched in addition to them.
+    pub(crate) proc_macro_tracked_paths: FxHashSet<AbsPathBuf>,
+    /// Whether the analysis was changed outside of [`GlobalState::process_changes`], which the
+    /// clients need to be refreshed for.
+    pub(crate) analysis_changed: bool,
 
     // op queues
     pub(crate) fetch_workspaces_queue:
@@ -123,11 +144,57 @@ pub(crate) struct GlobalState {
     pub(crate) prime_caches_queue: OpQueue,
 }
 
+/// The `cargo test` invocations of a test explorer run, which are executed one after another.
+pub(crate) struct TestRunSession {
+    /// Tells the sessions of subsequent runs apart, as the invocations of a session are determined
+    /// in the background.
+    pub(crate) id: usize,
+    pub(crate) current: Option<CurrentTestRun>,
+    pub(crate) pending: VecDeque<TestRun>,
+}
+
+#[derive(Debug)]
+pub(crate) struct TestRun {
+    pub(crate) command: Command,
+    /// The id of the test item of the crate being tested, the names reported by libtest are
+    /// relative to it.
+    pub(crate) crate_test_id: String,
+    pub(crate) workspace_root: AbsPathBuf,
+    /// The ids of the test items this invocation runs.
+    pub(crate) enqueued: Vec<String>,
+}
+
+pub(crate) struct CurrentTestRun {
+    pub(crate) crate_test_id: String,
+    pub(crate) workspace_root: AbsPathBuf,
+    pub(crate) enqueued: Vec<String>,
+    /// The ids of the tests libtest reported a result for.
+    pub(crate) finished: FxHashSet<String>,
+    /// Whether libtest has started, i.e. whether the tests have been built successfully.
+    pub(crate) suite_started: bool,
+    pub(crate) receiver: Receiver<CargoTestMessage>,
+    pub(crate) _handle: CargoTestHandle,
+}
+
+impl CurrentTestRun {
+    /// Returns the enqueued test items for which neither they nor any of their tests got a result.
+    pub(crate) fn unfinished(&self) -> impl Iterator<Item = &String> {
+        self.enqueued.iter().filter(|enqueued| {
+            !self.finished.iter().any(|test_id| is_same_or_child_test(test_id, enqueued))
+        })
+    }
+}
+
+pub(crate) fn is_same_or_child_test(test_id: &str, ancestor: &str) -> bool {
+    test_id.strip_prefix(ancestor).map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+}
+
 /// An immutable snapshot of the world's state at a point in time.
 pub(crate) struct GlobalStateSnapshot {
     pub(crate) config: Arc<Config>,
     pub(crate) analysis: Analysis,
     pub(crate) check_fixes: CheckFixes,
+    pub(crate) native_diagnostics: NativeDiagnostics,
     mem_docs: MemDocs,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
     vfs: Arc<RwLock<(vfs::Vfs, IntMap<FileId, LineEndings>)>>,
@@ -191,6 +258,9 @@ impl GlobalState {
             flycheck_receiver,
             last_flycheck_error: None,
 
+            test_run_session: None,
+            test_run_count: 0,
+
             vfs: Arc::new(RwLock::new((vfs::Vfs::default(), IntMap::default()))),
             vfs_config_version: 0,
             vfs_progress_config_version: 0,
@@ -198,7 +268,13 @@ impl GlobalState {
             vfs_progress_n_done: 0,
 
             workspaces: Arc::new(Vec::new()),
+            discovered_workspaces: Vec::new(),
+            workspace_discovery_files: FxHashSet::default(),
             crate_graph_file_dependencies: FxHashSet::default(),
+            ad_hoc_candidates: Vec::new(),
+            ad_hoc_files: Vec::new(),
+            proc_macro_tracked_paths: FxHashSet::default(),
+            analysis_changed: false,
             fetch_workspaces_queue: OpQueue::default(),
             fetch_build_data_queue: OpQueue::default(),
             fetch_proc_macros_queue: OpQueue::default(),
@@ -342,6 +418,7 @@ impl GlobalState {
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
+            native_diagnostics: Arc::clone(&self.diagnostics.native),
             mem_docs: self.mem_docs.clone(),
             semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
             proc_macros_loaded: !self.config.expand_proc_macros()
@@ -470,6 +547,25 @@ impl GlobalStateSnapshot {
         })
     }
 
+    /// Returns the Rust files which belong to local, i.e. non-library, source roots.
+    pub(crate) fn local_rust_files(&self) -> Cancellable<Vec<FileId>> {
+        let files: Vec<_> = self
+            .vfs
+            .read()
+            .0
+            .iter()
+            .filter(|(_, path)| matches!(path.name_and_extension(), Some((_, Some("rs")))))
+            .map(|(file_id, _)| file_id)
+            .collect();
+        let mut res = Vec::with_capacity(files.len());
+        for file_id in files {
+            if !self.analysis.is_library_file(file_id)? {
+                res.push(file_id);
+            }
+        }
+        Ok(res)
+    }
+
     pub(crate) fn vfs_memory_usage(&self) -> usize {
         self.vfs.read().0.memory_usage()
     }
@@ -7,14 +7,21 @@ use itertools::Itertools;
 use lsp_types::{
     CancelParams, DidChangeConfigurationParams, DidChangeTextDocumentParams,
     DidChangeWatchedFilesParams, DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, DidSaveTextDocumentParams, WorkDoneProgressCancelParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, TextDocumentIdentifier,
+    WorkDoneProgressCancelParams,
 };
 use triomphe::Arc;
 use vfs::{AbsPathBuf, ChangeKind, VfsPath};
 
 use crate::{
-    config::Config, from_proto, global_state::GlobalState, lsp_ext::RunFlycheckParams,
-    lsp_utils::apply_document_changes, mem_docs::DocumentData, reload,
+    config::Config,
+    from_proto,
+    global_state::GlobalState,
+    lsp_ext::{self, RunFlycheckParams},
+    lsp_utils::apply_document_changes,
+    main_loop::Task,
+    mem_docs::DocumentData,
+    reload, to_proto,
 };
 
 pub(crate) fn handle_cancel(state: &mut GlobalState, params: CancelParams) -> anyhow::Result<()> {
@@ -60,6 +67,10 @@ pub(crate) fn handle_did_open_text_document(
         if already_exists {
             tracing::error!("duplicate DidOpenTextDocument: {}", path);
         }
+        if let Some(abs_path) = path.as_path() {
+            state.discover_workspace(abs_path);
+            state.ad_hoc_candidates.push(abs_path.to_path_buf());
+        }
         state.vfs.write().0.set_file_contents(path, Some(params.text_document.text.into_bytes()));
     }
     Ok(())
@@ -121,6 +132,10 @@ pub(crate) fn handle_did_save_text_document(
     state: &mut GlobalState,
     params: DidSaveTextDocumentParams,
 ) -> anyhow::Result<()> {
+    if state.config.test_explorer() {
+        discover_tests_in_file(state, params.text_document.clone());
+    }
+
     if let Ok(vfs_path) = from_proto::vfs_path(&params.text_document.uri) {
         // Re-fetch workspaces if a workspace related file has changed
         if let Some(abs_path) = vfs_path.as_path() {
@@ -302,6 +317,25 @@ fn run_flycheck(state: &mut GlobalState, vfs_path: VfsPath) -> bool {
     }
 }
 
+fn discover_tests_in_file(state: &mut GlobalState, text_document: TextDocumentIdentifier) {
+    let snap = state.snapshot();
+    state.task_pool.handle.spawn_with_sender(stdx::thread::ThreadIntent::Worker, move |sender| {
+        let Ok(file_id) = from_proto::file_id(&snap, &text_document.uri) else { return };
+        let tests = snap
+            .analysis
+            .discover_tests_in_file(file_id)
+            .and_then(|tests| tests.into_iter().map(|it| to_proto::test_item(&snap, it)).collect());
+        // The tests are discovered again on the next save if this got cancelled.
+        let Ok(tests) = tests else { return };
+        let results = lsp_ext::DiscoverTestResults {
+            tests,
+            scope: None,
+            scope_file: Some(vec![text_document]),
+        };
+        sender.send(Task::DiscoverTest(results)).unwrap();
+    });
+}
+
 pub(crate) fn handle_cancel_flycheck(state: &mut GlobalState, _: ()) -> anyhow::Result<()> {
     let _p = profile::span("handle_stop_flycheck");
     state.flycheck.iter().for_each(|flycheck| flycheck.cancel());
@@ -332,3 +366,9 @@ pub(crate) fn handle_run_flycheck(
     }
     Ok(())
 }
+
+pub(crate) fn handle_abort_run_test(state: &mut GlobalState, _: ()) -> anyhow::Result<()> {
+    let _p = profile::span("handle_abort_run_test");
+    state.abort_test_run();
+    Ok(())
+}
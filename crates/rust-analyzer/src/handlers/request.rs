@@ -10,11 +10,12 @@ use std::{
 use anyhow::Context;
 
 use ide::{
-    AnnotationConfig, AssistKind, AssistResolveStrategy, Cancellable, FilePosition, FileRange,
-    HoverAction, HoverGotoTypeData, Query, RangeInfo, ReferenceCategory, Runnable, RunnableKind,
-    SingleResolve, SourceChange, TextEdit,
+    AnnotationConfig, AssistKind, AssistResolveStrategy, Cancellable, Change, FileId, FilePosition,
+    FileRange, HoverAction, HoverGotoTypeData, Query, RangeInfo, ReferenceCategory, Runnable,
+    RunnableKind, SingleResolve, SourceChange, TestItemKind, TextEdit,
 };
-use ide_db::SymbolKind;
+use ide_db::{base_db::SourceDatabase, FxHashMap, SymbolKind};
+use itertools::Itertools;
 use lsp_server::ErrorCode;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
@@ -23,11 +24,13 @@ use lsp_types::{
     InlayHintParams, Location, LocationLink, Position, PrepareRenameResponse, Range, RenameParams,
     ResourceOp, ResourceOperationKind, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
     SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult,
-    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, Url, WorkspaceEdit,
+    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, Url,
+    WorkspaceEdit,
 };
 use project_model::{ManifestPath, ProjectWorkspace, TargetKind};
 use serde_json::json;
-use stdx::{format_to, never};
+use stdx::{format_to, never, thread::ThreadIntent};
 use syntax::{algo, ast, AstNode, TextRange, TextSize};
 use triomphe::Arc;
 use vfs::{AbsPath, AbsPathBuf, VfsPath};
@@ -35,15 +38,17 @@ use vfs::{AbsPath, AbsPathBuf, VfsPath};
 use crate::{
     cargo_target_spec::CargoTargetSpec,
     config::{Config, RustfmtConfig, WorkspaceSymbolConfig},
+    diagnostics::{fetch_native_diagnostics, patch_empty_diagnostic_messages},
     diff::diff,
     from_proto,
-    global_state::{GlobalState, GlobalStateSnapshot},
+    global_state::{is_same_or_child_test, GlobalState, GlobalStateSnapshot, TestRun},
     line_index::LineEndings,
     lsp_ext::{
         self, CrateInfoResult, ExternalDocsPair, ExternalDocsResponse, FetchDependencyListParams,
         FetchDependencyListResult, PositionOrRange, ViewCrateGraphParams, WorkspaceSymbolParams,
     },
     lsp_utils::{all_edits_are_disjoint, invalid_params_error},
+    main_loop::Task,
     to_proto, LspError,
 };
 
@@ -131,6 +136,39 @@ pub(crate) fn handle_shuffle_crate_graph(state: &mut GlobalState, _: ()) -> anyh
     Ok(())
 }
 
+pub(crate) fn handle_set_active_target(
+    state: &mut GlobalState,
+    params: lsp_ext::SetActiveTargetParams,
+) -> anyhow::Result<Vec<String>> {
+    let _p = profile::span("handle_set_active_target");
+    let snap = state.snapshot();
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let crates = snap.analysis.crates_for(file_id)?;
+    drop(snap);
+
+    let db = state.analysis_host.raw_database();
+    let crate_graph = db.crate_graph();
+    let targets: Vec<String> = crates
+        .into_iter()
+        .filter_map(|krate| crate_graph[krate].target.clone())
+        .sorted()
+        .dedup()
+        .collect();
+    let mut active_targets = FxHashMap::clone(&db.active_targets());
+    match params.target {
+        Some(target) if !targets.contains(&target) => {
+            return Err(invalid_params_error(format!("file is not analyzed for `{target}`")).into())
+        }
+        Some(target) => active_targets.insert(file_id, target),
+        None => active_targets.remove(&file_id),
+    };
+    let mut change = Change::new();
+    change.set_active_targets(active_targets);
+    state.analysis_host.apply_change(change);
+    state.analysis_changed = true;
+    Ok(targets)
+}
+
 pub(crate) fn handle_syntax_tree(
     snap: GlobalStateSnapshot,
     params: lsp_ext::SyntaxTreeParams,
@@ -169,7 +207,13 @@ pub(crate) fn handle_interpret_function(
 ) -> anyhow::Result<String> {
     let _p = profile::span("handle_interpret_function");
     let position = from_proto::file_position(&snap, params)?;
-    let res = snap.analysis.interpret_function(position)?;
+    // Like `cargo test`, run in the directory of the package.
+    let package_root = match CargoTargetSpec::for_file(&snap, position.file_id)? {
+        Some(spec) => spec.cargo_toml.parent().to_path_buf(),
+        None => snap.config.root_path().clone(),
+    };
+    let sandbox = snap.config.interpret_sandbox(&package_root);
+    let res = snap.analysis.interpret_function(position, sandbox)?;
     Ok(res)
 }
 
@@ -845,6 +889,122 @@ pub(crate) fn handle_related_tests(
     Ok(res)
 }
 
+pub(crate) fn handle_discover_test(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DiscoverTestParams,
+) -> anyhow::Result<lsp_ext::DiscoverTestResults> {
+    let _p = profile::span("handle_discover_test");
+    let (tests, scope) = match params.test_id {
+        Some(test_id) => {
+            let tests = snap.analysis.discover_tests_in_crate_by_test_id(&test_id)?;
+            (tests, Some(vec![test_id]))
+        }
+        None => (snap.analysis.discover_test_roots()?, None),
+    };
+    let tests =
+        tests.into_iter().map(|it| to_proto::test_item(&snap, it)).collect::<Cancellable<_>>()?;
+    Ok(lsp_ext::DiscoverTestResults { tests, scope, scope_file: None })
+}
+
+pub(crate) fn handle_run_test(
+    state: &mut GlobalState,
+    params: lsp_ext::RunTestParams,
+) -> anyhow::Result<()> {
+    let _p = profile::span("handle_run_test");
+    let id = state.prepare_test_run();
+    // Looking for the tests to run requires analysis, so it happens on a worker thread.
+    let snap = state.snapshot();
+    state
+        .task_pool
+        .handle
+        .spawn(ThreadIntent::Worker, move || Task::TestRuns { id, runs: test_runs(&snap, params) });
+    Ok(())
+}
+
+/// Returns the `cargo test` invocations running the tests selected by `params`.
+fn test_runs(
+    snap: &GlobalStateSnapshot,
+    params: lsp_ext::RunTestParams,
+) -> anyhow::Result<Vec<TestRun>> {
+    let roots = snap.analysis.discover_test_roots()?;
+    let include = params.include.unwrap_or_else(|| roots.iter().map(|it| it.id.clone()).collect());
+    let exclude = params.exclude.unwrap_or_default();
+    let is_excluded = |test_id: &str| exclude.iter().any(|it| is_same_or_child_test(test_id, it));
+
+    let mut runs = Vec::new();
+    for root in roots {
+        let TestItemKind::Crate(crate_id) = root.kind else { continue };
+        let included: Vec<&String> =
+            include.iter().filter(|it| is_same_or_child_test(it, &root.id)).collect();
+        if included.is_empty() {
+            continue;
+        }
+        let whole_crate = included.contains(&&root.id)
+            && !exclude.iter().any(|it| is_same_or_child_test(it, &root.id));
+        // libtest reports the names of tests relative to their crate, the filters select the
+        // tests to run by these names.
+        let filters = if whole_crate {
+            Vec::new()
+        } else {
+            let filters: Vec<String> = snap
+                .analysis
+                .discover_tests_in_crate_by_test_id(&root.id)?
+                .into_iter()
+                .filter(|it| it.kind == TestItemKind::Function && !is_excluded(&it.id))
+                .filter(|it| included.iter().any(|inc| is_same_or_child_test(&it.id, inc)))
+                .map(|it| it.id[root.id.len() + "::".len()..].to_owned())
+                .collect();
+            if filters.is_empty() {
+                continue;
+            }
+            filters
+        };
+        let Some(spec) = CargoTargetSpec::for_file(snap, snap.analysis.crate_root(crate_id)?)?
+        else {
+            continue;
+        };
+        let enqueued = included.into_iter().cloned().collect();
+        runs.push(cargo_test_run(snap, spec, root.id, filters, enqueued));
+    }
+    Ok(runs)
+}
+
+fn cargo_test_run(
+    snap: &GlobalStateSnapshot,
+    spec: CargoTargetSpec,
+    crate_test_id: String,
+    filters: Vec<String>,
+    enqueued: Vec<String>,
+) -> TestRun {
+    let workspace_root = spec.workspace_root.clone();
+    let config = snap.config.runnables();
+    let (cargo_args, _) = CargoTargetSpec::runnable_args(
+        snap,
+        Some(spec),
+        &RunnableKind::TestMod { path: String::new() },
+        &None,
+    );
+
+    let mut command = match config.override_cargo {
+        Some(cargo) => process::Command::new(cargo),
+        None => process::Command::new(toolchain::cargo()),
+    };
+    command
+        .current_dir(&workspace_root)
+        .envs(snap.config.extra_env())
+        // libtest's JSON output is unstable.
+        .env("RUSTC_BOOTSTRAP", "1")
+        .args(cargo_args)
+        .args(config.cargo_extra_args)
+        .arg("--")
+        .args(&filters);
+    if !filters.is_empty() {
+        command.arg("--exact");
+    }
+    command.args(["-Z", "unstable-options", "--format=json"]);
+    TestRun { command, crate_test_id, workspace_root, enqueued }
+}
+
 pub(crate) fn handle_completion(
     snap: GlobalStateSnapshot,
     params: lsp_types::CompletionParams,
@@ -1514,6 +1674,189 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_document_diagnostic(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentDiagnosticParams,
+) -> anyhow::Result<lsp_types::DocumentDiagnosticReportResult> {
+    let _p = profile::span("handle_document_diagnostic");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let report = match native_diagnostics_report(&snap, file_id, params.previous_result_id)? {
+        lsp_types::DocumentDiagnosticReportKind::Full(full) => {
+            lsp_types::DocumentDiagnosticReport::Full(
+                lsp_types::RelatedFullDocumentDiagnosticReport {
+                    related_documents: None,
+                    full_document_diagnostic_report: full,
+                },
+            )
+        }
+        lsp_types::DocumentDiagnosticReportKind::Unchanged(unchanged) => {
+            lsp_types::DocumentDiagnosticReport::Unchanged(
+                lsp_types::RelatedUnchangedDocumentDiagnosticReport {
+                    related_documents: None,
+                    unchanged_document_diagnostic_report: unchanged,
+                },
+            )
+        }
+    };
+    Ok(report.into())
+}
+
+pub(crate) fn handle_workspace_diagnostic(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::WorkspaceDiagnosticParams,
+) -> anyhow::Result<lsp_types::WorkspaceDiagnosticReportResult> {
+    let _p = profile::span("handle_workspace_diagnostic");
+    if !snap.config.workspace_diagnostics() {
+        return Ok(lsp_types::WorkspaceDiagnosticReport::default().into());
+    }
+
+    // The diagnostics of the workspace get computed in the background by the main loop, which asks
+    // the client to pull them again whenever they change. So we only report the ones known so far.
+    let mut previous_result_ids: FxHashMap<_, _> =
+        params.previous_result_ids.into_iter().map(|it| (it.uri, it.value)).collect();
+    let mut items = Vec::new();
+    for (&file_id, (generation, diagnostics)) in snap.native_diagnostics.iter() {
+        if snap.analysis.is_library_file(file_id)? {
+            continue;
+        }
+        let uri = snap.file_id_to_url(file_id);
+        let version = snap.url_file_version(&uri).map(i64::from);
+        let result_id = generation.to_string();
+        let item = if previous_result_ids.remove(&uri).as_ref() == Some(&result_id) {
+            lsp_types::WorkspaceDocumentDiagnosticReport::Unchanged(
+                lsp_types::WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri,
+                    version,
+                    unchanged_document_diagnostic_report:
+                        lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+                },
+            )
+        } else {
+            let mut items = diagnostics.clone();
+            patch_empty_diagnostic_messages(&mut items);
+            lsp_types::WorkspaceDocumentDiagnosticReport::Full(
+                lsp_types::WorkspaceFullDocumentDiagnosticReport {
+                    uri,
+                    version,
+                    full_document_diagnostic_report: lsp_types::FullDocumentDiagnosticReport {
+                        result_id: Some(result_id),
+                        items,
+                    },
+                },
+            )
+        };
+        items.push(item);
+    }
+    // Clear the diagnostics of files which have been reported before but are gone by now.
+    items.extend(previous_result_ids.into_keys().map(|uri| {
+        lsp_types::WorkspaceDocumentDiagnosticReport::Full(
+            lsp_types::WorkspaceFullDocumentDiagnosticReport {
+                uri,
+                version: None,
+                full_document_diagnostic_report: lsp_types::FullDocumentDiagnosticReport::default(),
+            },
+        )
+    }));
+    Ok(lsp_types::WorkspaceDiagnosticReport { items }.into())
+}
+
+/// Returns the native diagnostics of a file, as an "unchanged" report if they are the same as in
+/// the report identified by `previous_result_id`.
+fn native_diagnostics_report(
+    snap: &GlobalStateSnapshot,
+    file_id: FileId,
+    previous_result_id: Option<String>,
+) -> Cancellable<lsp_types::DocumentDiagnosticReportKind> {
+    // Like for published diagnostics, we don't report anything for libraries.
+    if !snap.config.publish_diagnostics() || snap.analysis.is_library_file(file_id)? {
+        return Ok(lsp_types::FullDocumentDiagnosticReport::default().into());
+    }
+    let Some((generation, diagnostics)) = snap.native_diagnostics.get(&file_id) else {
+        // The main loop hasn't computed the diagnostics of the file yet, so there is no result id
+        // to attach to them. The client gets asked to pull them again once they are ready.
+        let mut items = fetch_native_diagnostics(snap, file_id)?;
+        patch_empty_diagnostic_messages(&mut items);
+        return Ok(lsp_types::FullDocumentDiagnosticReport { result_id: None, items }.into());
+    };
+
+    let result_id = generation.to_string();
+    if previous_result_id.as_ref() == Some(&result_id) {
+        return Ok(lsp_types::UnchangedDocumentDiagnosticReport { result_id }.into());
+    }
+    let mut items = diagnostics.clone();
+    patch_empty_diagnostic_messages(&mut items);
+    Ok(lsp_types::FullDocumentDiagnosticReport { result_id: Some(result_id), items }.into())
+}
+
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchyPrepareParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_prepare");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+
+    let nav_info = match snap.analysis.type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let RangeInfo { range: _, info: navs } = nav_info;
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySupertypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_supertypes");
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(&snap, doc, item.selection_range)?;
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.supertypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySubtypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_subtypes");
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(&snap, doc, item.selection_range)?;
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.subtypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_semantic_tokens_full(
     snap: GlobalStateSnapshot,
     params: SemanticTokensParams,
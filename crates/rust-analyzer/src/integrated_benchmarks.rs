@@ -37,6 +37,7 @@ fn integrated_highlighting_benchmark() {
         load_out_dirs_from_check: true,
         with_proc_macro_server: ProcMacroServerChoice::None,
         prefill_caches: false,
+        persist_caches: None,
     };
 
     let (mut host, vfs, _proc_macro) = {
@@ -91,6 +92,7 @@ fn integrated_completion_benchmark() {
         load_out_dirs_from_check: true,
         with_proc_macro_server: ProcMacroServerChoice::None,
         prefill_caches: true,
+        persist_caches: None,
     };
 
     let (mut host, vfs, _proc_macro) = {
@@ -22,6 +22,7 @@ mod caps;
 mod cargo_target_spec;
 mod diagnostics;
 mod diff;
+mod discover;
 mod dispatch;
 mod from_proto;
 mod global_state;
@@ -52,7 +53,11 @@ use std::fmt;
 
 use serde::de::DeserializeOwned;
 
-pub use crate::{caps::server_capabilities, main_loop::main_loop, version::version};
+pub use crate::{
+    caps::{add_untyped_server_capabilities, server_capabilities},
+    main_loop::main_loop,
+    version::version,
+};
 
 pub fn from_json<T: DeserializeOwned>(
     what: &'static str,
@@ -1,30 +1,42 @@
 //! The main loop of `rust-analyzer` responsible for dispatching LSP
 //! requests/replies and notifications back to the client.
 use std::{
-    fmt,
+    collections::VecDeque,
+    fmt, mem,
     time::{Duration, Instant},
 };
 
 use always_assert::always;
-use crossbeam_channel::{select, Receiver};
-use flycheck::FlycheckHandle;
+use crossbeam_channel::{never, select, unbounded, Receiver};
+use flycheck::{CargoTestHandle, CargoTestMessage, FlycheckHandle};
 use ide_db::base_db::{SourceDatabaseExt, VfsPath};
+use itertools::Itertools;
 use lsp_server::{Connection, Notification, Request};
 use lsp_types::notification::Notification as _;
+use nohash_hasher::IntSet;
+use project_model::ProjectJson;
+use rustc_hash::FxHashSet;
 use stdx::thread::ThreadIntent;
 use triomphe::Arc;
-use vfs::FileId;
+use vfs::{AbsPathBuf, FileId};
 
 use crate::{
     config::Config,
+    diagnostics::{fetch_native_diagnostics, patch_empty_diagnostic_messages},
     dispatch::{NotificationDispatcher, RequestDispatcher},
     from_proto,
-    global_state::{file_id_to_url, url_to_file_id, GlobalState},
+    global_state::{
+        file_id_to_url, url_to_file_id, CurrentTestRun, GlobalState, TestRun, TestRunSession,
+    },
     lsp_ext,
     lsp_utils::{notification_is, Progress},
     reload::{BuildDataProgress, ProcMacroProgress, ProjectWorkspaceProgress},
+    to_proto,
 };
 
+/// The number of files whose workspace diagnostics are sent to the client together.
+const WORKSPACE_DIAGNOSTICS_CHUNK_SIZE: usize = 16;
+
 pub fn main_loop(config: Config, connection: Connection) -> anyhow::Result<()> {
     tracing::info!("initial config: {:#?}", config);
 
@@ -55,6 +67,7 @@ enum Event {
     Task(Task),
     Vfs(vfs::loader::Message),
     Flycheck(flycheck::Message),
+    TestResult(CargoTestMessage),
 }
 
 #[derive(Debug)]
@@ -66,6 +79,23 @@ pub(crate) enum Task {
     FetchWorkspace(ProjectWorkspaceProgress),
     FetchBuildData(BuildDataProgress),
     LoadProcMacros(ProcMacroProgress),
+    DiscoverTest(lsp_ext::DiscoverTestResults),
+    DiscoverWorkspace(anyhow::Result<ProjectJson>),
+    /// Files which belong to no crate, and the files which need to be checked again because the
+    /// check got cancelled.
+    AdHocFiles {
+        files: Vec<AbsPathBuf>,
+        retry: Vec<AbsPathBuf>,
+    },
+    PersistentCaches {
+        item_trees: ide::ItemTreeCache,
+        symbols: ide::SymbolIndexCache,
+    },
+    /// The `cargo test` invocations of the test run with the given id.
+    TestRuns {
+        id: usize,
+        runs: anyhow::Result<Vec<TestRun>>,
+    },
 }
 
 #[derive(Debug)]
@@ -103,6 +133,7 @@ impl fmt::Debug for Event {
             Event::Task(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
             Event::Flycheck(it) => fmt::Debug::fmt(it, f),
+            Event::TestResult(it) => fmt::Debug::fmt(it, f),
         }
     }
 }
@@ -172,6 +203,11 @@ impl GlobalState {
     }
 
     fn next_event(&self, inbox: &Receiver<lsp_server::Message>) -> Option<Event> {
+        let no_test_run = never();
+        let test_run_receiver = match &self.test_run_session {
+            Some(session) => session.current.as_ref().map_or(&no_test_run, |it| &it.receiver),
+            None => &no_test_run,
+        };
         select! {
             recv(inbox) -> msg =>
                 msg.ok().map(Event::Lsp),
@@ -187,6 +223,9 @@ impl GlobalState {
 
             recv(self.flycheck_receiver) -> task =>
                 Some(Event::Flycheck(task.unwrap())),
+
+            recv(test_run_receiver) -> message =>
+                Some(Event::TestResult(message.unwrap_or(CargoTestMessage::Finished))),
         }
     }
 
@@ -258,6 +297,8 @@ impl GlobalState {
                             if cancelled {
                                 self.prime_caches_queue
                                     .request_op("restart after cancellation".to_string(), ());
+                            } else {
+                                self.persist_caches();
                             }
                         }
                     };
@@ -281,10 +322,15 @@ impl GlobalState {
                     self.handle_flycheck_msg(message);
                 }
             }
+            Event::TestResult(message) => {
+                let _p = profile::span("GlobalState::handle_event/test_result");
+                self.handle_cargo_test_msg(message);
+            }
         }
         let event_handling_duration = loop_start.elapsed();
 
-        let state_changed = self.process_changes();
+        self.track_proc_macro_paths();
+        let state_changed = self.process_changes() | mem::take(&mut self.analysis_changed);
         let memdocs_added_or_removed = self.mem_docs.take_changes();
 
         if self.is_quiescent() {
@@ -301,8 +347,15 @@ impl GlobalState {
                 if self.config.prefill_caches() {
                     self.prime_caches_queue.request_op("became quiescent".to_string(), ());
                 }
+                if self.config.persist_caches() {
+                    // Only now all library files are known.
+                    self.update_persistent_caches();
+                }
             }
 
+            // Only now the crate graph of the workspaces is complete.
+            self.check_ad_hoc_files();
+
             let client_refresh = !was_quiescent || state_changed;
             if client_refresh {
                 // Refresh semantic tokens if the client supports it.
@@ -329,35 +382,24 @@ impl GlobalState {
             }
         }
 
-        if let Some(diagnostic_changes) = self.diagnostics.take_changes() {
+        if let Some((native_changes, check_changes)) = self.diagnostics.take_changes() {
+            // Clients supporting pull diagnostics ask for native diagnostics themselves, so only
+            // the ones of `cargo check` get published to them.
+            let pull_diagnostics = self.config.pull_diagnostics();
+            let diagnostic_changes = if pull_diagnostics {
+                check_changes
+            } else {
+                native_changes.union(&check_changes).copied().collect()
+            };
             for file_id in diagnostic_changes {
                 let uri = file_id_to_url(&self.vfs.read().0, file_id);
-                let mut diagnostics =
-                    self.diagnostics.diagnostics_for(file_id).cloned().collect::<Vec<_>>();
-
-                // VSCode assumes diagnostic messages to be non-empty strings, so we need to patch
-                // empty diagnostics. Neither the docs of VSCode nor the LSP spec say whether
-                // diagnostic messages are actually allowed to be empty or not and patching this
-                // in the VSCode client does not work as the assertion happens in the protocol
-                // conversion. So this hack is here to stay, and will be considered a hack
-                // until the LSP decides to state that empty messages are allowed.
-
-                // See https://github.com/rust-lang/rust-analyzer/issues/11404
-                // See https://github.com/rust-lang/rust-analyzer/issues/13130
-                let patch_empty = |message: &mut String| {
-                    if message.is_empty() {
-                        *message = " ".to_string();
-                    }
+                let mut diagnostics = if pull_diagnostics {
+                    self.diagnostics.check_diagnostics_for(file_id).cloned().collect::<Vec<_>>()
+                } else {
+                    self.diagnostics.diagnostics_for(file_id).cloned().collect::<Vec<_>>()
                 };
 
-                for d in &mut diagnostics {
-                    patch_empty(&mut d.message);
-                    if let Some(dri) = &mut d.related_information {
-                        for dri in dri {
-                            patch_empty(&mut dri.message);
-                        }
-                    }
-                }
+                patch_empty_diagnostic_messages(&mut diagnostics);
 
                 let version = from_proto::vfs_path(&uri)
                     .map(|path| self.mem_docs.get(&path).map(|it| it.version))
@@ -367,6 +409,11 @@ impl GlobalState {
                     lsp_types::PublishDiagnosticsParams { uri, diagnostics, version },
                 );
             }
+
+            // Refresh pulled diagnostics if the client supports it.
+            if pull_diagnostics && !native_changes.is_empty() && self.config.diagnostics_refresh() {
+                self.send_request::<lsp_types::request::WorkspaceDiagnosticRefresh>((), |_, _| ());
+            }
         }
 
         if self.config.cargo_autoreload() {
@@ -534,6 +581,35 @@ impl GlobalState {
                     self.report_progress("Loading", state, msg, None, None);
                 }
             }
+            Task::DiscoverTest(results) => {
+                self.send_notification::<lsp_ext::DiscoveredTests>(results);
+            }
+            Task::DiscoverWorkspace(Ok(project)) => {
+                // Every file of a project discovers the same project, which must be loaded once.
+                if !self.discovered_workspaces.contains(&project) {
+                    let cause = format!("discovered workspace at {}", project.path());
+                    self.discovered_workspaces.push(project);
+                    self.fetch_workspaces_queue.request_op(cause, false);
+                }
+            }
+            Task::DiscoverWorkspace(Err(e)) => {
+                self.show_and_log_error(
+                    "Failed to discover workspace".to_owned(),
+                    Some(e.to_string()),
+                );
+            }
+            Task::AdHocFiles { files, retry } => {
+                self.ad_hoc_candidates.extend(retry);
+                self.add_ad_hoc_crates(files);
+            }
+            // Persisting might have been disabled while the cache was loading.
+            Task::PersistentCaches { item_trees, symbols } if self.config.persist_caches() => {
+                let db = self.analysis_host.raw_database_mut();
+                db.set_item_tree_cache(Some(item_trees));
+                db.set_symbol_index_cache(Some(symbols));
+            }
+            Task::PersistentCaches { .. } => (),
+            Task::TestRuns { id, runs } => self.start_test_run(id, runs),
         }
     }
 
@@ -643,6 +719,172 @@ impl GlobalState {
         }
     }
 
+    /// Replaces the current test run, if any, by a new one whose `cargo test` invocations still
+    /// have to be determined. Returns the id of the new run's session.
+    pub(crate) fn prepare_test_run(&mut self) -> usize {
+        self.abort_test_run();
+        self.test_run_count += 1;
+        let id = self.test_run_count;
+        self.test_run_session =
+            Some(TestRunSession { id, current: None, pending: VecDeque::new() });
+        id
+    }
+
+    /// Starts executing the `cargo test` invocations of the session with the given id.
+    fn start_test_run(&mut self, id: usize, runs: anyhow::Result<Vec<TestRun>>) {
+        // The run might have been aborted or replaced while looking for the tests to run.
+        if self.test_run_session.as_ref().map_or(true, |it| it.id != id) {
+            return;
+        }
+        let runs = runs.unwrap_or_else(|e| {
+            self.send_notification::<lsp_ext::AppendOutputToRunTest>(format!(
+                "failed to find the tests to run: {e}\n"
+            ));
+            Vec::new()
+        });
+        for test_id in runs.iter().flat_map(|it| &it.enqueued) {
+            self.send_notification::<lsp_ext::ChangeTestState>(lsp_ext::ChangeTestStateParams {
+                test_id: test_id.clone(),
+                state: lsp_ext::TestState::Enqueued,
+            });
+        }
+        if let Some(session) = &mut self.test_run_session {
+            session.pending = runs.into();
+        }
+        self.start_next_test_run();
+    }
+
+    /// Ends the current test run, killing its `cargo` process if it is still running. The tests
+    /// which haven't been run are marked as skipped.
+    pub(crate) fn abort_test_run(&mut self) {
+        let Some(session) = self.test_run_session.take() else { return };
+        let current = session.current.iter().flat_map(|it| it.unfinished());
+        let pending = session.pending.iter().flat_map(|it| &it.enqueued);
+        for test_id in current.chain(pending) {
+            self.send_notification::<lsp_ext::ChangeTestState>(lsp_ext::ChangeTestStateParams {
+                test_id: test_id.clone(),
+                state: lsp_ext::TestState::Skipped,
+            });
+        }
+        self.send_notification::<lsp_ext::EndRunTest>(());
+    }
+
+    fn start_next_test_run(&mut self) {
+        loop {
+            let Some(session) = &mut self.test_run_session else { return };
+            if let Some(current) = session.current.take() {
+                // If the tests couldn't be built, libtest never started and no test reported a
+                // result.
+                let suite_started = current.suite_started;
+                for test_id in current.unfinished() {
+                    let state = if suite_started {
+                        lsp_ext::TestState::Skipped
+                    } else {
+                        lsp_ext::TestState::Failed {
+                            message: "`cargo test` failed before running the tests, see its output"
+                                .to_owned(),
+                            location: None,
+                        }
+                    };
+                    self.send_notification::<lsp_ext::ChangeTestState>(
+                        lsp_ext::ChangeTestStateParams { test_id: test_id.clone(), state },
+                    );
+                }
+                continue;
+            }
+            let Some(TestRun { command, crate_test_id, workspace_root, enqueued }) =
+                session.pending.pop_front()
+            else {
+                break;
+            };
+            let (sender, receiver) = unbounded();
+            match CargoTestHandle::spawn(command, sender) {
+                Ok(handle) => {
+                    session.current = Some(CurrentTestRun {
+                        crate_test_id,
+                        workspace_root,
+                        enqueued,
+                        finished: FxHashSet::default(),
+                        suite_started: false,
+                        receiver,
+                        _handle: handle,
+                    });
+                    return;
+                }
+                Err(e) => {
+                    self.send_notification::<lsp_ext::AppendOutputToRunTest>(format!(
+                        "failed to run `cargo test`: {e}\n"
+                    ));
+                    for test_id in enqueued {
+                        self.send_notification::<lsp_ext::ChangeTestState>(
+                            lsp_ext::ChangeTestStateParams {
+                                test_id,
+                                state: lsp_ext::TestState::Failed {
+                                    message: format!("failed to run `cargo test`: {e}"),
+                                    location: None,
+                                },
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        self.abort_test_run();
+    }
+
+    fn current_test_run_mut(&mut self) -> Option<&mut CurrentTestRun> {
+        self.test_run_session.as_mut()?.current.as_mut()
+    }
+
+    fn handle_cargo_test_msg(&mut self, message: CargoTestMessage) {
+        let Some(current) = self.test_run_session.as_ref().and_then(|it| it.current.as_ref())
+        else {
+            return;
+        };
+        match message {
+            CargoTestMessage::Test { name, state } => {
+                let state = match state {
+                    flycheck::TestState::Started => lsp_ext::TestState::Started,
+                    flycheck::TestState::Ok => lsp_ext::TestState::Passed,
+                    flycheck::TestState::Ignored => lsp_ext::TestState::Skipped,
+                    flycheck::TestState::Failed { stdout } => {
+                        let location =
+                            flycheck::panic_location(&stdout).map(|(path, line, column)| {
+                                let position = lsp_types::Position::new(
+                                    line.saturating_sub(1),
+                                    column.saturating_sub(1),
+                                );
+                                lsp_types::Location::new(
+                                    to_proto::url_from_abs_path(&current.workspace_root.join(path)),
+                                    lsp_types::Range::new(position, position),
+                                )
+                            });
+                        self.send_notification::<lsp_ext::AppendOutputToRunTest>(stdout.clone());
+                        lsp_ext::TestState::Failed { message: stdout, location }
+                    }
+                };
+                let test_id = format!("{}::{name}", current.crate_test_id);
+                if !matches!(state, lsp_ext::TestState::Started) {
+                    if let Some(current) = self.current_test_run_mut() {
+                        current.finished.insert(test_id.clone());
+                    }
+                }
+                self.send_notification::<lsp_ext::ChangeTestState>(
+                    lsp_ext::ChangeTestStateParams { test_id, state },
+                );
+            }
+            CargoTestMessage::Suite => {
+                if let Some(current) = self.current_test_run_mut() {
+                    current.suite_started = true;
+                }
+            }
+            CargoTestMessage::Custom { text } => {
+                self.send_notification::<lsp_ext::AppendOutputToRunTest>(text + "\n");
+            }
+            CargoTestMessage::Finished => self.start_next_test_run(),
+        }
+    }
+
     /// Registers and handles a request. This should only be called once per incoming request.
     fn on_new_request(&mut self, request_received: Instant, req: Request) {
         self.register_request(&req, request_received);
@@ -678,6 +920,8 @@ impl GlobalState {
             .on_sync_mut::<lsp_ext::RebuildProcMacros>(handlers::handle_proc_macros_rebuild)
             .on_sync_mut::<lsp_ext::MemoryUsage>(handlers::handle_memory_usage)
             .on_sync_mut::<lsp_ext::ShuffleCrateGraph>(handlers::handle_shuffle_crate_graph)
+            .on_sync_mut::<lsp_ext::SetActiveTarget>(handlers::handle_set_active_target)
+            .on_sync_mut::<lsp_ext::RunTest>(handlers::handle_run_test)
             // Request handlers which are related to the user typing
             // are run on the main thread to reduce latency:
             .on_sync::<lsp_ext::JoinLines>(handlers::handle_join_lines)
@@ -722,6 +966,7 @@ impl GlobalState {
             .on::<lsp_ext::ParentModule>(handlers::handle_parent_module)
             .on::<lsp_ext::Runnables>(handlers::handle_runnables)
             .on::<lsp_ext::RelatedTests>(handlers::handle_related_tests)
+            .on::<lsp_ext::DiscoverTest>(handlers::handle_discover_test)
             .on::<lsp_ext::CodeActionRequest>(handlers::handle_code_action)
             .on::<lsp_ext::CodeActionResolveRequest>(handlers::handle_code_action_resolve)
             .on::<lsp_ext::HoverRequest>(handlers::handle_hover)
@@ -751,6 +996,19 @@ impl GlobalState {
             .on::<lsp_types::request::CallHierarchyOutgoingCalls>(
                 handlers::handle_call_hierarchy_outgoing,
             )
+            .on::<lsp_types::request::DocumentDiagnosticRequest>(
+                handlers::handle_document_diagnostic,
+            )
+            .on::<lsp_types::request::WorkspaceDiagnosticRequest>(
+                handlers::handle_workspace_diagnostic,
+            )
+            .on::<lsp_types::request::TypeHierarchyPrepare>(handlers::handle_type_hierarchy_prepare)
+            .on::<lsp_types::request::TypeHierarchySupertypes>(
+                handlers::handle_type_hierarchy_supertypes,
+            )
+            .on::<lsp_types::request::TypeHierarchySubtypes>(
+                handlers::handle_type_hierarchy_subtypes,
+            )
             .on::<lsp_types::request::WillRenameFiles>(handlers::handle_will_rename_files)
             .on::<lsp_ext::Ssr>(handlers::handle_ssr)
             .on::<lsp_ext::ViewRecursiveMemoryLayout>(handlers::handle_view_recursive_memory_layout)
@@ -785,6 +1043,7 @@ impl GlobalState {
             .on_sync_mut::<lsp_ext::CancelFlycheck>(handlers::handle_cancel_flycheck)?
             .on_sync_mut::<lsp_ext::ClearFlycheck>(handlers::handle_clear_flycheck)?
             .on_sync_mut::<lsp_ext::RunFlycheck>(handlers::handle_run_flycheck)?
+            .on_sync_mut::<lsp_ext::AbortRunTest>(handlers::handle_abort_run_test)?
             .finish();
         Ok(())
     }
@@ -810,54 +1069,36 @@ impl GlobalState {
 
         let snapshot = self.snapshot();
 
+        if self.config.workspace_diagnostics() {
+            // The remaining local files are less important than the open ones, so we compute
+            // their diagnostics separately and stream them to the client as they get ready.
+            let open_files = subscriptions.iter().copied().collect::<IntSet<_>>();
+            let snapshot = self.snapshot();
+            self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |sender| {
+                let _p = profile::span("publish_workspace_diagnostics");
+                let _ctx = stdx::panic_context::enter("publish_workspace_diagnostics".to_owned());
+                let Ok(files) = snapshot.local_rust_files() else { return };
+                let files = files.into_iter().filter(|file_id| !open_files.contains(file_id));
+                for chunk in &files.chunks(WORKSPACE_DIAGNOSTICS_CHUNK_SIZE) {
+                    let mut diagnostics = Vec::new();
+                    for file_id in chunk {
+                        // On cancellation the state changed, so diagnostics get recomputed anyway.
+                        let Ok(it) = fetch_native_diagnostics(&snapshot, file_id) else { return };
+                        diagnostics.push((file_id, it));
+                    }
+                    sender.send(Task::Diagnostics(diagnostics)).unwrap();
+                }
+            });
+        }
+
         // Diagnostics are triggered by the user typing
         // so we run them on a latency sensitive thread.
         self.task_pool.handle.spawn(ThreadIntent::LatencySensitive, move || {
             let _p = profile::span("publish_diagnostics");
             let _ctx = stdx::panic_context::enter("publish_diagnostics".to_owned());
-            let diagnostics = subscriptions
-                .into_iter()
-                .filter_map(|file_id| {
-                    let line_index = snapshot.file_line_index(file_id).ok()?;
-                    Some((
-                        file_id,
-                        line_index,
-                        snapshot
-                            .analysis
-                            .diagnostics(
-                                &snapshot.config.diagnostics(),
-                                ide::AssistResolveStrategy::None,
-                                file_id,
-                            )
-                            .ok()?,
-                    ))
-                })
-                .map(|(file_id, line_index, it)| {
-                    (
-                        file_id,
-                        it.into_iter()
-                            .map(move |d| lsp_types::Diagnostic {
-                                range: crate::to_proto::range(&line_index, d.range),
-                                severity: Some(crate::to_proto::diagnostic_severity(d.severity)),
-                                code: Some(lsp_types::NumberOrString::String(
-                                    d.code.as_str().to_string(),
-                                )),
-                                code_description: Some(lsp_types::CodeDescription {
-                                    href: lsp_types::Url::parse(&d.code.url()).unwrap(),
-                                }),
-                                source: Some("rust-analyzer".to_string()),
-                                message: d.message,
-                                related_information: None,
-                                tags: if d.unused {
-                                    Some(vec![lsp_types::DiagnosticTag::UNNECESSARY])
-                                } else {
-                                    None
-                                },
-                                data: None,
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                });
+            let diagnostics = subscriptions.into_iter().filter_map(|file_id| {
+                Some((file_id, fetch_native_diagnostics(&snapshot, file_id).ok()?))
+            });
             Task::Diagnostics(diagnostics.collect())
         });
     }
@@ -16,25 +16,33 @@ use std::{iter, mem};
 
 use flycheck::{FlycheckConfig, FlycheckHandle};
 use hir::db::DefDatabase;
-use ide::Change;
+use ide::{Change, ItemTreeCache, SymbolIndexCache};
 use ide_db::{
-    base_db::{salsa::Durability, CrateGraph, ProcMacroPaths, ProcMacros},
+    base_db::{
+        salsa::Durability, CrateData, CrateGraph, ProcMacroPaths, ProcMacros, SourceDatabase,
+    },
+    symbol_index::SymbolsDatabase,
     FxHashMap,
 };
-use load_cargo::{load_proc_macro, ProjectFolders};
+use load_cargo::{
+    analysis_cache_dir, item_tree_cache_files, load_proc_macro, symbol_index_cache_files,
+    ProjectFolders,
+};
 use proc_macro_api::ProcMacroServer;
 use project_model::{ProjectWorkspace, WorkspaceBuildScripts};
 use rustc_hash::FxHashSet;
 use stdx::{format_to, thread::ThreadIntent};
 use triomphe::Arc;
-use vfs::{AbsPath, ChangeKind};
+use vfs::{AbsPath, AbsPathBuf, ChangeKind};
 
 use crate::{
     config::{Config, FilesWatcher, LinkedProject},
+    discover,
     global_state::GlobalState,
     lsp_ext,
     main_loop::Task,
     op_queue::Cause,
+    to_proto,
 };
 
 #[derive(Debug)]
@@ -93,6 +101,13 @@ impl GlobalState {
                 Durability::HIGH,
             );
         }
+        if self.config.discover_command() != old_config.discover_command() {
+            // Let the new command discover the workspaces of the files opened from now on.
+            self.workspace_discovery_files.clear();
+        }
+        if self.config.persist_caches() != old_config.persist_caches() {
+            self.update_persistent_caches();
+        }
     }
 
     pub(crate) fn current_status(&self) -> lsp_ext::ServerStatusParams {
@@ -123,6 +138,7 @@ impl GlobalState {
             message.push_str("Auto-reloading is disabled and the workspace has changed, a manual workspace reload is required.\n\n");
         }
         if self.config.linked_projects().is_empty()
+            && self.discovered_workspaces.is_empty()
             && self.config.detached_files().is_empty()
             && self.config.notifications().cargo_toml_not_found
         {
@@ -181,7 +197,10 @@ impl GlobalState {
         tracing::info!(%cause, "will fetch workspaces");
 
         self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, {
-            let linked_projects = self.config.linked_projects();
+            let mut linked_projects = self.config.linked_projects();
+            linked_projects.extend(
+                self.discovered_workspaces.iter().cloned().map(LinkedProject::InlineJsonProject),
+            );
             let detached_files = self.config.detached_files().to_vec();
             let cargo_config = self.config.cargo();
 
@@ -254,6 +273,97 @@ impl GlobalState {
         });
     }
 
+    /// Runs the `workspace.discoverCommand` for a file which isn't part of any loaded workspace.
+    pub(crate) fn discover_workspace(&mut self, path: &AbsPath) {
+        let Some(command) = self.config.discover_command() else { return };
+        let in_workspace = self.workspaces.iter().flat_map(|ws| ws.to_roots()).any(|root| {
+            root.include.iter().any(|dir| path.starts_with(dir))
+                && !root.exclude.iter().any(|dir| path.starts_with(dir))
+        });
+        if in_workspace || !self.workspace_discovery_files.insert(path.to_path_buf()) {
+            return;
+        }
+        tracing::info!("discovering workspace of {path}");
+
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, {
+            let command = command.to_vec();
+            let path = path.to_path_buf();
+            let root = self.config.root_path().clone();
+            let extra_env = self.config.extra_env().clone();
+            move |sender| {
+                let project = discover::discover_workspace(&command, &path, &root, &extra_env);
+                sender.send(Task::DiscoverWorkspace(project)).unwrap();
+            }
+        });
+    }
+
+    /// Checks the files opened since the last call for whether they belong to any crate, giving
+    /// the ones that don't an ad-hoc crate of their own.
+    pub(crate) fn check_ad_hoc_files(&mut self) {
+        if self.ad_hoc_candidates.is_empty() {
+            return;
+        }
+        let crate_graph = self.analysis_host.raw_database().workspace_crate_graph();
+        let candidates = {
+            let vfs = &self.vfs.read().0;
+            let crate_dirs = crate_root_dirs(&crate_graph, vfs);
+            mem::take(&mut self.ad_hoc_candidates)
+                .into_iter()
+                .filter(|path| path.extension().map_or(false, |it| it == "rs"))
+                .filter(|path| !self.ad_hoc_files.contains(path))
+                .filter(|path| !crate_dirs.iter().any(|dir| path.starts_with(dir)))
+                .filter_map(|path| {
+                    let file_id = vfs.file_id(&vfs::VfsPath::from(path.clone()))?;
+                    Some((path, file_id))
+                })
+                .collect::<Vec<_>>()
+        };
+        if candidates.is_empty() {
+            return;
+        }
+
+        let snap = self.snapshot();
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |sender| {
+            let mut files = Vec::new();
+            let mut retry = Vec::new();
+            for (path, file_id) in candidates {
+                match snap.analysis.crates_for(file_id) {
+                    Ok(crates) if crates.is_empty() => files.push(path),
+                    Ok(_) => (),
+                    Err(_) => retry.push(path),
+                }
+            }
+            sender.send(Task::AdHocFiles { files, retry }).unwrap();
+        });
+    }
+
+    /// Adds an ad-hoc crate for each of `files`. They are set apart from the workspace's crate
+    /// graph, which stays untouched.
+    pub(crate) fn add_ad_hoc_crates(&mut self, files: Vec<AbsPathBuf>) {
+        let files: Vec<_> =
+            files.into_iter().filter(|it| !self.ad_hoc_files.contains(it)).collect();
+        if files.is_empty() {
+            return;
+        }
+        tracing::info!("adding ad-hoc crates for {files:?}");
+
+        let crate_graph = self.analysis_host.raw_database().workspace_crate_graph();
+        let ad_hoc_crates = {
+            let vfs = &self.vfs.read().0;
+            self.ad_hoc_files.extend(
+                files
+                    .into_iter()
+                    .filter(|it| vfs.file_id(&vfs::VfsPath::from(it.clone())).is_some()),
+            );
+            ad_hoc_crates(&crate_graph, vfs, &self.ad_hoc_files)
+        };
+
+        let mut change = Change::new();
+        change.set_ad_hoc_crates(ad_hoc_crates);
+        self.analysis_host.apply_change(change);
+        self.analysis_changed = true;
+    }
+
     pub(crate) fn fetch_build_data(&mut self, cause: Cause) {
         tracing::info!(%cause, "will fetch build data");
         let workspaces = Arc::clone(&self.workspaces);
@@ -446,7 +556,11 @@ impl GlobalState {
                             };
 
                             tracing::info!("Using proc-macro server at {path}");
-                            ProcMacroServer::spawn(path.clone()).map_err(|err| {
+                            let cache = self.config.proc_macro_srv_cache();
+                            if self.config.proc_macro_srv_isolated() {
+                                return Ok(ProcMacroServer::isolated(path, cache));
+                            }
+                            ProcMacroServer::spawn(path.clone(), cache).map_err(|err| {
                                 tracing::error!(
                                     "Failed to run proc-macro server from path {path}, error: {err:?}",
                                 );
@@ -460,26 +574,85 @@ impl GlobalState {
             };
         }
 
-        let watch = match files_config.watcher {
-            FilesWatcher::Client => vec![],
-            FilesWatcher::Server => project_folders.watch,
-        };
+        self.set_vfs_config(project_folders.load, project_folders.watch);
+        self.source_root_config = project_folders.source_root_config;
+
+        self.recreate_crate_graph(cause);
+
+        tracing::info!("did switch workspaces");
+    }
+
+    /// Points the VFS loader at the files of the workspaces, and at the files proc-macros declared
+    /// to read.
+    fn set_vfs_config(&mut self, mut load: Vec<vfs::loader::Entry>, mut watch: Vec<usize>) {
+        if !self.proc_macro_tracked_paths.is_empty() {
+            watch.push(load.len());
+            load.push(vfs::loader::Entry::Files(
+                self.proc_macro_tracked_paths.iter().cloned().collect(),
+            ));
+        }
+        if let FilesWatcher::Client = self.config.files().watcher {
+            watch.clear();
+        }
         self.vfs_config_version += 1;
         self.loader.handle.set_config(vfs::loader::Config {
-            load: project_folders.load,
+            load,
             watch,
             version: self.vfs_config_version,
         });
-        self.source_root_config = project_folders.source_root_config;
+    }
 
-        self.recreate_crate_graph(cause);
+    /// Loads and watches the files proc-macros declared to read which aren't part of the VFS yet,
+    /// so that changing them invalidates the expansions that read them.
+    pub(crate) fn track_proc_macro_paths(&mut self) {
+        let new_paths: Vec<_> = {
+            let vfs = &self.vfs.read().0;
+            self.proc_macro_clients
+                .iter()
+                .flatten()
+                .flat_map(ProcMacroServer::take_tracked_paths)
+                .filter(|path| vfs.file_id(&vfs::VfsPath::from(path.clone())).is_none())
+                .filter(|path| self.proc_macro_tracked_paths.insert(path.clone()))
+                .collect()
+        };
+        if new_paths.is_empty() {
+            return;
+        }
+        tracing::info!("tracking files read by proc-macros: {new_paths:?}");
 
-        tracing::info!("did switch workspaces");
+        if let FilesWatcher::Client = self.config.files().watcher {
+            let registration_options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
+                watchers: new_paths
+                    .iter()
+                    .map(|path| lsp_types::FileSystemWatcher {
+                        glob_pattern: lsp_types::GlobPattern::String(path.to_string()),
+                        kind: None,
+                    })
+                    .collect(),
+            };
+            // Each batch of paths gets a registration of its own, keeping the earlier ones alive.
+            let id = format!(
+                "workspace/didChangeWatchedFiles/proc-macros/{}",
+                self.proc_macro_tracked_paths.len()
+            );
+            let registration = lsp_types::Registration {
+                id,
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: Some(serde_json::to_value(registration_options).unwrap()),
+            };
+            self.send_request::<lsp_types::request::RegisterCapability>(
+                lsp_types::RegistrationParams { registrations: vec![registration] },
+                |_, _| (),
+            );
+        }
+
+        let project_folders = ProjectFolders::new(&self.workspaces, &self.config.files().exclude);
+        self.set_vfs_config(project_folders.load, project_folders.watch);
     }
 
     fn recreate_crate_graph(&mut self, cause: String) {
         // Create crate graph from all the workspaces
-        let (crate_graph, proc_macro_paths, crate_graph_file_dependencies) = {
+        let (crate_graph, ad_hoc_crates, proc_macro_paths, crate_graph_file_dependencies) = {
             let vfs = &mut self.vfs.write().0;
             let loader = &mut self.loader;
             // crate graph construction relies on these paths, record them so when one of them gets
@@ -510,7 +683,12 @@ impl GlobalState {
                 crate_graph.extend(other, &mut crate_proc_macros);
                 proc_macros.push(crate_proc_macros);
             }
-            (crate_graph, proc_macros, crate_graph_file_dependencies)
+
+            // Ad-hoc files which are now part of a workspace get their crate from it instead.
+            let crate_dirs = crate_root_dirs(&crate_graph, vfs);
+            self.ad_hoc_files.retain(|path| !crate_dirs.iter().any(|dir| path.starts_with(dir)));
+            let ad_hoc_crates = ad_hoc_crates(&crate_graph, vfs, &self.ad_hoc_files);
+            (crate_graph, ad_hoc_crates, proc_macros, crate_graph_file_dependencies)
         };
 
         if self.config.expand_proc_macros() {
@@ -518,11 +696,80 @@ impl GlobalState {
         }
         let mut change = Change::new();
         change.set_crate_graph(crate_graph);
+        change.set_ad_hoc_crates(ad_hoc_crates);
         self.analysis_host.apply_change(change);
         self.crate_graph_file_dependencies = crate_graph_file_dependencies;
         self.process_changes();
 
         self.reload_flycheck();
+        self.discover_test_roots();
+    }
+
+    /// Sends the crates of the new crate graph to the test explorer.
+    fn discover_test_roots(&mut self) {
+        if !self.config.test_explorer() {
+            return;
+        }
+        let snap = self.snapshot();
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |sender| {
+            let tests = snap.analysis.discover_test_roots().and_then(|tests| {
+                tests.into_iter().map(|it| to_proto::test_item(&snap, it)).collect()
+            });
+            let Ok(tests) = tests else { return };
+            let results = lsp_ext::DiscoverTestResults { tests, scope: None, scope_file: None };
+            sender.send(Task::DiscoverTest(results)).unwrap();
+        });
+    }
+
+    /// Loads the on-disk `ItemTree` and symbol index caches of the workspaces in the background, or
+    /// drops the current ones if persisting caches has been disabled.
+    pub(crate) fn update_persistent_caches(&mut self) {
+        let dir = self
+            .config
+            .persist_caches()
+            .then(|| self.workspaces.iter().find_map(analysis_cache_dir))
+            .flatten();
+        let Some(dir) = dir else {
+            let db = self.analysis_host.raw_database();
+            if db.item_tree_cache().is_some() || db.symbol_index_cache().is_some() {
+                let db = self.analysis_host.raw_database_mut();
+                db.set_item_tree_cache(None);
+                db.set_symbol_index_cache(None);
+            }
+            return;
+        };
+        let vfs = &self.vfs.read().0;
+        let item_tree_files = item_tree_cache_files(self.analysis_host.raw_database(), vfs);
+        let symbol_index_files = symbol_index_cache_files(vfs);
+        self.task_pool.handle.spawn(ThreadIntent::Worker, move || {
+            let version = crate::version().to_string();
+            Task::PersistentCaches {
+                item_trees: ItemTreeCache::load(
+                    dir.join("item-trees").as_ref(),
+                    &version,
+                    item_tree_files,
+                ),
+                symbols: SymbolIndexCache::load(
+                    dir.join("symbols").as_ref(),
+                    &version,
+                    symbol_index_files,
+                ),
+            }
+        });
+    }
+
+    /// Writes the `ItemTree`s and library symbol indices computed so far to the on-disk caches in
+    /// the background.
+    pub(crate) fn persist_caches(&mut self) {
+        let db = self.analysis_host.raw_database();
+        if db.item_tree_cache().is_none() && db.symbol_index_cache().is_none() {
+            return;
+        }
+        let analysis = self.analysis_host.analysis();
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |_| {
+            // A cancelled run leaves the remaining entries to the next one.
+            let _ = analysis.persist_caches();
+        });
     }
 
     pub(super) fn fetch_workspace_error(&self) -> Result<(), String> {
@@ -531,7 +778,8 @@ impl GlobalState {
         let Some((last_op_result, _)) = self.fetch_workspaces_queue.last_op_result() else {
             return Ok(());
         };
-        if last_op_result.is_empty() {
+        // With a discover command, workspaces are only found once a file gets opened.
+        if last_op_result.is_empty() && self.config.discover_command().is_none() {
             stdx::format_to!(buf, "rust-analyzer failed to discover workspace");
         } else {
             for ws in last_op_result {
@@ -617,6 +865,36 @@ impl GlobalState {
     }
 }
 
+/// Creates the ad-hoc crates of `files`, see [`project_model::ad_hoc_crate`].
+fn ad_hoc_crates(crate_graph: &CrateGraph, vfs: &vfs::Vfs, files: &[AbsPathBuf]) -> Vec<CrateData> {
+    files
+        .iter()
+        .filter_map(|path| {
+            let file_id = vfs.file_id(&vfs::VfsPath::from(path.clone()))?;
+            let text = String::from_utf8_lossy(vfs.file_contents(file_id));
+            Some(project_model::ad_hoc_crate(crate_graph, path, file_id, &text))
+        })
+        .collect()
+}
+
+/// The directories of the crate roots in `crate_graph`.
+///
+/// Files next to a crate root most likely are modules that aren't declared yet, rather than
+/// standalone files. Build scripts live in the package root, so they don't count.
+fn crate_root_dirs(crate_graph: &CrateGraph, vfs: &vfs::Vfs) -> Vec<AbsPathBuf> {
+    crate_graph
+        .iter()
+        .filter_map(|krate| {
+            let root = vfs.file_path(crate_graph[krate].root_file_id);
+            let root = root.as_path()?;
+            if root.name_and_extension() == Some(("build", Some("rs"))) {
+                return None;
+            }
+            root.parent().map(AbsPath::to_path_buf)
+        })
+        .collect()
+}
+
 pub(crate) fn should_refresh_for_change(path: &AbsPath, change_kind: ChangeKind) -> bool {
     const IMPLICIT_TARGET_FILES: &[&str] = &["build.rs", "src/main.rs", "src/lib.rs"];
     const IMPLICIT_TARGET_DIRS: &[&str] = &["src/bin", "examples", "tests", "benches"];
@@ -447,8 +447,12 @@ pub(crate) fn inlay_hint(
         padding_left: Some(inlay_hint.pad_left),
         padding_right: Some(inlay_hint.pad_right),
         kind: match inlay_hint.kind {
-            InlayKind::Parameter => Some(lsp_types::InlayHintKind::PARAMETER),
-            InlayKind::Type | InlayKind::Chaining => Some(lsp_types::InlayHintKind::TYPE),
+            InlayKind::Parameter | InlayKind::GenericParameter => {
+                Some(lsp_types::InlayHintKind::PARAMETER)
+            }
+            InlayKind::Type | InlayKind::Chaining | InlayKind::Turbofish => {
+                Some(lsp_types::InlayHintKind::TYPE)
+            }
             _ => None,
         },
         text_edits: inlay_hint.text_edit.map(|it| text_edit_vec(line_index, it)),
@@ -1073,6 +1077,26 @@ pub(crate) fn call_hierarchy_item(
     })
 }
 
+pub(crate) fn type_hierarchy_item(
+    snap: &GlobalStateSnapshot,
+    target: NavigationTarget,
+) -> Cancellable<lsp_types::TypeHierarchyItem> {
+    let name = target.name.to_string();
+    let detail = target.description.clone();
+    let kind = target.kind.map(symbol_kind).unwrap_or(lsp_types::SymbolKind::STRUCT);
+    let (uri, range, selection_range) = location_info(snap, target)?;
+    Ok(lsp_types::TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail,
+        uri,
+        range,
+        selection_range,
+        data: None,
+    })
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::None | AssistKind::Generate => lsp_types::CodeActionKind::EMPTY,
@@ -1146,6 +1170,37 @@ pub(crate) fn runnable(
     })
 }
 
+pub(crate) fn test_item(
+    snap: &GlobalStateSnapshot,
+    test_item: ide::TestItem,
+) -> Cancellable<lsp_ext::TestItem> {
+    let (text_document, range) = match test_item.file.zip(test_item.text_range) {
+        Some((file_id, text_range)) => {
+            let line_index = snap.file_line_index(file_id)?;
+            (
+                Some(lsp_types::TextDocumentIdentifier { uri: url(snap, file_id) }),
+                Some(range(&line_index, text_range)),
+            )
+        }
+        None => (None, None),
+    };
+    Ok(lsp_ext::TestItem {
+        id: test_item.id,
+        label: test_item.label,
+        kind: match test_item.kind {
+            ide::TestItemKind::Crate(_) => lsp_ext::TestItemKind::Package,
+            ide::TestItemKind::Module => lsp_ext::TestItemKind::Module,
+            ide::TestItemKind::Function => lsp_ext::TestItemKind::Test,
+        },
+        // The tests of a crate are discovered all at once.
+        can_resolve_children: matches!(test_item.kind, ide::TestItemKind::Crate(_)),
+        parent: test_item.parent,
+        text_document,
+        range,
+        runnable: test_item.runnable.map(|it| runnable(snap, it)).transpose()?,
+    })
+}
+
 pub(crate) fn code_lens(
     acc: &mut Vec<lsp_types::CodeLens>,
     snap: &GlobalStateSnapshot,
@@ -19,17 +19,24 @@ mod tidy;
 use std::{collections::HashMap, path::PathBuf, time::Instant};
 
 use lsp_types::{
-    notification::DidOpenTextDocument,
+    notification::{DidCloseTextDocument, DidOpenTextDocument, ShowMessage},
     request::{
-        CodeActionRequest, Completion, Formatting, GotoTypeDefinition, HoverRequest,
-        WillRenameFiles, WorkspaceSymbolRequest,
+        CodeActionRequest, Completion, DocumentDiagnosticRequest, Formatting, GotoTypeDefinition,
+        HoverRequest, TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+        WillRenameFiles, WorkspaceDiagnosticRequest, WorkspaceSymbolRequest,
     },
-    CodeActionContext, CodeActionParams, CompletionParams, DidOpenTextDocumentParams,
-    DocumentFormattingParams, FileRename, FormattingOptions, GotoDefinitionParams, HoverParams,
-    PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
-    TextDocumentPositionParams, WorkDoneProgressParams,
+    CodeActionContext, CodeActionParams, CompletionParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentDiagnosticParams, DocumentFormattingParams, FileRename,
+    FormattingOptions, GotoDefinitionParams, HoverParams, PartialResultParams, Position,
+    PreviousResultId, Range, RenameFilesParams, SymbolKind, TextDocumentItem,
+    TextDocumentPositionParams, TypeHierarchyItem, TypeHierarchyPrepareParams,
+    TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, WorkDoneProgressParams,
+    WorkspaceDiagnosticParams,
+};
+use rust_analyzer::lsp_ext::{
+    ChangeTestState, DiscoverTest, DiscoverTestParams, DiscoverTestResults, EndRunTest, OnEnter,
+    RunTest, RunTestParams, Runnables, RunnablesParams, TestState,
 };
-use rust_analyzer::lsp_ext::{OnEnter, Runnables, RunnablesParams};
 use serde_json::json;
 use test_utils::skip_slow_tests;
 
@@ -204,6 +211,197 @@ fn main() {}
     );
 }
 
+#[test]
+fn test_discover_tests() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+pub fn foo() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {}
+
+    fn helper() {}
+}
+
+//- /foo/tests/spam.rs
+#[test]
+fn test_eggs() {}
+"#,
+    )
+    .root("foo")
+    .with_config(json!({ "testExplorer": { "enable": true } }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let discover = |test_id: Option<String>| -> DiscoverTestResults {
+        let res = server.send_request::<DiscoverTest>(DiscoverTestParams { test_id });
+        serde_json::from_value(res).unwrap()
+    };
+
+    let roots = discover(None);
+    assert_eq!(roots.scope, None);
+    let mut labels: Vec<_> = roots.tests.iter().map(|it| &*it.label).collect();
+    labels.sort();
+    assert_eq!(labels, ["foo", "spam"]);
+
+    let lib = roots.tests.iter().find(|it| it.label == "foo").unwrap();
+    assert!(lib.can_resolve_children);
+    let tests = discover(Some(lib.id.clone()));
+    assert_eq!(tests.scope, Some(vec![lib.id.clone()]));
+    let items: Vec<_> =
+        tests.tests.iter().map(|it| (it.id.clone(), it.parent.clone().unwrap())).collect();
+    assert_eq!(
+        items,
+        [
+            (format!("{}::tests", lib.id), lib.id.clone()),
+            (format!("{}::tests::it_works", lib.id), format!("{}::tests", lib.id)),
+        ]
+    );
+    let test = &tests.tests[1];
+    assert_eq!(test.text_document.as_ref().unwrap().uri, server.doc_id("foo/src/lib.rs").uri);
+    assert_eq!(test.runnable.as_ref().unwrap().label, "test tests::it_works");
+}
+
+#[test]
+fn test_run_tests() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn passes() {}
+
+    #[test]
+    fn fails() {
+        assert_eq!(1, 2);
+    }
+
+    #[test]
+    fn excluded() {}
+}
+"#,
+    )
+    .root("foo")
+    .with_config(json!({ "testExplorer": { "enable": true } }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let res = server.send_request::<DiscoverTest>(DiscoverTestParams { test_id: None });
+    let roots: DiscoverTestResults = serde_json::from_value(res).unwrap();
+    let lib = &roots.tests[0].id;
+
+    server.request::<RunTest>(
+        RunTestParams {
+            include: Some(vec![format!("{lib}::tests")]),
+            exclude: Some(vec![format!("{lib}::tests::excluded")]),
+        },
+        json!(null),
+    );
+    server.wait_for_notification::<EndRunTest>();
+
+    let mut states: Vec<_> = server
+        .notifications::<ChangeTestState>()
+        .into_iter()
+        .map(|it| {
+            let state = match it.state {
+                TestState::Failed { location, .. } => {
+                    let location = location.unwrap();
+                    assert!(location.uri.path().ends_with("foo/src/lib.rs"));
+                    format!("failed at {}", location.range.start.line)
+                }
+                state => format!("{state:?}"),
+            };
+            (it.test_id.strip_prefix(&format!("{lib}::")).unwrap().to_owned(), state)
+        })
+        .collect();
+    states.sort();
+    assert_eq!(
+        states,
+        [
+            ("tests".to_owned(), "Enqueued".to_owned()),
+            ("tests::fails".to_owned(), "Started".to_owned()),
+            ("tests::fails".to_owned(), "failed at 7".to_owned()),
+            ("tests::passes".to_owned(), "Passed".to_owned()),
+            ("tests::passes".to_owned(), "Started".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn test_run_tests_compile_error() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+#[test]
+fn broken() {
+    let _: u32 = "";
+}
+"#,
+    )
+    .root("foo")
+    .with_config(json!({ "testExplorer": { "enable": true } }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let res = server.send_request::<DiscoverTest>(DiscoverTestParams { test_id: None });
+    let roots: DiscoverTestResults = serde_json::from_value(res).unwrap();
+    let lib = &roots.tests[0].id;
+    assert_eq!(lib, "foo");
+
+    server.request::<RunTest>(RunTestParams { include: None, exclude: None }, json!(null));
+    server.wait_for_notification::<EndRunTest>();
+
+    let states: Vec<_> = server
+        .notifications::<ChangeTestState>()
+        .into_iter()
+        .map(|it| {
+            let state = match it.state {
+                TestState::Failed { location, .. } => format!("Failed at {location:?}"),
+                state => format!("{state:?}"),
+            };
+            (it.test_id, state)
+        })
+        .collect();
+    assert_eq!(
+        states,
+        [
+            ("foo".to_owned(), "Enqueued".to_owned()),
+            ("foo".to_owned(), "Failed at None".to_owned()),
+        ]
+    );
+}
+
 // Each package in these workspaces should be run from its own root
 #[test]
 fn test_path_dependency_runnables() {
@@ -588,6 +786,256 @@ fn main() {{}}
     );
 }
 
+#[test]
+fn test_type_hierarchy() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let tmp_dir = TestDir::new();
+    let path = tmp_dir.path();
+    let project = json!({
+        "roots": [path],
+        "crates": [ {
+            "root_module": path.join("src/lib.rs"),
+            "deps": [],
+            "edition": "2021",
+            "cfg": [],
+        } ]
+    });
+    let code = format!(
+        r#"
+//- /rust-project.json
+{project}
+
+//- /src/lib.rs
+trait Super {{}}
+trait Trait: Super {{}}
+trait Sub: Trait {{}}
+struct S;
+impl Super for S {{}}
+impl Trait for S {{}}
+"#,
+    );
+    let server =
+        Project::with_fixture(&code).tmp_dir(tmp_dir).server().wait_until_workspace_is_loaded();
+
+    let items = server.send_request::<TypeHierarchyPrepare>(TypeHierarchyPrepareParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            server.doc_id("src/lib.rs"),
+            Position::new(1, 7),
+        ),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+    });
+    let item: TypeHierarchyItem = serde_json::from_value(items[0].clone()).unwrap();
+    assert_eq!(item.name, "Trait");
+
+    let names = |items: serde_json::Value| {
+        let items: Vec<TypeHierarchyItem> = serde_json::from_value(items).unwrap();
+        items.into_iter().map(|it| (it.name, it.kind)).collect::<Vec<_>>()
+    };
+
+    let supertypes =
+        server.send_request::<TypeHierarchySupertypes>(TypeHierarchySupertypesParams {
+            item: item.clone(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        });
+    assert_eq!(names(supertypes), vec![("Super".to_owned(), SymbolKind::INTERFACE)]);
+
+    let subtypes = server.send_request::<TypeHierarchySubtypes>(TypeHierarchySubtypesParams {
+        item,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    assert_eq!(
+        names(subtypes),
+        vec![("Sub".to_owned(), SymbolKind::INTERFACE), ("S".to_owned(), SymbolKind::STRUCT)]
+    );
+}
+
+#[test]
+fn test_discover_command() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /discover.sh
+test -f "$1" && cat build/project.json
+
+//- /build/project.json
+{
+    "crates": [{ "root_module": "src/lib.rs", "deps": [], "edition": "2021" }]
+}
+
+//- /src/lib.rs
+pub struct Foo;
+pub fn foo() -> Foo { Foo }
+"#,
+    )
+    .with_config(json!({
+        "notifications": { "cargoTomlNotFound": false },
+        "workspace": { "discoverCommand": ["sh", "discover.sh"] },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: server.doc_id("src/lib.rs").uri,
+            language_id: "rust".to_string(),
+            version: 0,
+            text: std::fs::read_to_string(server.path().join("src/lib.rs")).unwrap(),
+        },
+    });
+    server.wait_until_workspace_is_reloaded();
+
+    let res = server.send_request::<GotoTypeDefinition>(GotoDefinitionParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            server.doc_id("src/lib.rs"),
+            Position::new(1, 22),
+        ),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    assert_eq!(
+        res[0]["targetRange"],
+        json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 15 },
+        })
+    );
+}
+
+#[test]
+fn test_discover_command_multiple_projects() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /discover.sh
+case "$1" in
+    */a/*) cat build/a.json;;
+    */b/*) cat build/b.json;;
+esac
+
+//- /build/a.json
+{
+    "crates": [{ "root_module": "a/lib.rs", "deps": [], "edition": "2021" }]
+}
+
+//- /build/b.json
+{
+    "crates": [{ "root_module": "b/lib.rs", "deps": [], "edition": "2021" }]
+}
+
+//- /a/lib.rs
+pub struct A;
+pub fn a() -> A { A }
+
+//- /b/lib.rs
+pub struct B;
+pub fn b() -> B { B }
+"#,
+    )
+    .with_config(json!({
+        "notifications": { "cargoTomlNotFound": false },
+        "workspace": { "discoverCommand": ["sh", "discover.sh"] },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    for path in ["a/lib.rs", "b/lib.rs"] {
+        server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: server.doc_id(path).uri,
+                language_id: "rust".to_string(),
+                version: 0,
+                text: std::fs::read_to_string(server.path().join(path)).unwrap(),
+            },
+        });
+        server.wait_until_workspace_is_reloaded();
+    }
+
+    // Discovering the project of `b` must not unload the one of `a`.
+    for path in ["a/lib.rs", "b/lib.rs"] {
+        let res = server.send_request::<GotoTypeDefinition>(GotoDefinitionParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                server.doc_id(path),
+                Position::new(1, 20),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        assert_eq!(
+            res[0]["targetRange"],
+            json!({
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 13 },
+            }),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn test_discover_command_runs_once_per_file() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /discover.sh
+echo "$1" >> runs.log
+case "$1" in *b.rs) sleep 1;; esac
+exit 1
+
+//- /src/a.rs
+pub struct A;
+
+//- /src/b.rs
+pub struct B;
+"#,
+    )
+    .with_config(json!({
+        "notifications": { "cargoTomlNotFound": false },
+        "workspace": { "discoverCommand": ["sh", "discover.sh"] },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let open = |path: &str| {
+        server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: server.doc_id(path).uri,
+                language_id: "rust".to_string(),
+                version: 0,
+                text: std::fs::read_to_string(server.path().join(path)).unwrap(),
+            },
+        });
+    };
+    open("src/a.rs");
+    server.wait_for_notification::<ShowMessage>();
+    server.notification::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+        text_document: server.doc_id("src/a.rs"),
+    });
+    open("src/a.rs");
+    // The command takes a while for `b.rs`, a second run for `a.rs` would have failed by the time
+    // it's done.
+    open("src/b.rs");
+    let messages = server.wait_for_notifications::<ShowMessage>(2);
+
+    assert_eq!(messages.len(), 2);
+    let runs = std::fs::read_to_string(server.path().join("runs.log")).unwrap();
+    let runs: Vec<_> = runs.lines().map(|it| it.rsplit('/').next().unwrap()).collect();
+    assert_eq!(runs, ["a.rs", "b.rs"]);
+}
+
 #[test]
 fn diagnostics_dont_block_typing() {
     if skip_slow_tests() {
@@ -646,6 +1094,81 @@ fn main() {{}}
     assert!(elapsed.as_millis() < 2000, "typing enter took {elapsed:?}");
 }
 
+#[test]
+fn pull_diagnostics() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod bar;
+mod missing;
+
+//- /src/bar.rs
+mod also_missing;
+"#,
+    )
+    .with_config(serde_json::json!({
+        "diagnostics": { "workspace": { "enable": true } },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let workspace_diagnostics = |previous_result_ids: Vec<PreviousResultId>| {
+        server.send_request::<WorkspaceDiagnosticRequest>(WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        })
+    };
+    let item_for = |report: &serde_json::Value, path: &str| {
+        let uri = server.doc_id(path).uri.to_string();
+        report["items"].as_array().unwrap().iter().find(|it| it["uri"] == uri.as_str()).cloned()
+    };
+
+    // Workspace diagnostics are computed in the background, so only report them once ready.
+    let mut report = workspace_diagnostics(Vec::new());
+    for _ in 0..100 {
+        if report["items"].as_array().unwrap().len() == 2 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        report = workspace_diagnostics(Vec::new());
+    }
+    let lib = item_for(&report, "src/lib.rs").unwrap();
+    assert_eq!(lib["kind"], "full");
+    assert_eq!(lib["items"].as_array().unwrap().len(), 1);
+    assert_eq!(lib["items"][0]["code"], "E0583");
+    let result_id = lib["resultId"].as_str().unwrap().to_owned();
+
+    let report = server.send_request::<DocumentDiagnosticRequest>(DocumentDiagnosticParams {
+        text_document: server.doc_id("src/lib.rs"),
+        identifier: None,
+        previous_result_id: Some(result_id.clone()),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    assert_eq!(report, json!({ "kind": "unchanged", "resultId": result_id }));
+
+    let report = workspace_diagnostics(vec![PreviousResultId {
+        uri: server.doc_id("src/lib.rs").uri,
+        value: result_id,
+    }]);
+    assert_eq!(report["items"].as_array().unwrap().len(), 2);
+    assert_eq!(item_for(&report, "src/lib.rs").unwrap()["kind"], "unchanged");
+    let bar = item_for(&report, "src/bar.rs").unwrap();
+    assert_eq!(bar["kind"], "full");
+    assert_eq!(bar["items"][0]["code"], "E0583");
+}
+
 #[test]
 fn preserves_dos_line_endings() {
     if skip_slow_tests() {
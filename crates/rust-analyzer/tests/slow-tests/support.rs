@@ -10,7 +10,7 @@ use crossbeam_channel::{after, select, Receiver};
 use lsp_server::{Connection, Message, Notification, Request};
 use lsp_types::{notification::Exit, request::Shutdown, TextDocumentIdentifier, Url};
 use rust_analyzer::{config::Config, lsp_ext, main_loop};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{json, to_string_pretty, Value};
 use test_utils::FixtureWithProjectMeta;
 use tracing_subscriber::{prelude::*, Layer};
@@ -138,6 +138,7 @@ impl Project<'_> {
                         content_format: Some(vec![lsp_types::MarkupKind::Markdown]),
                         ..Default::default()
                     }),
+                    diagnostic: Some(lsp_types::DiagnosticClientCapabilities::default()),
                     ..Default::default()
                 }),
                 window: Some(lsp_types::WindowClientCapabilities {
@@ -256,22 +257,58 @@ impl Server {
         panic!("no response for {r:?}");
     }
     pub(crate) fn wait_until_workspace_is_loaded(self) -> Server {
-        self.wait_for_message_cond(1, &|msg: &Message| match msg {
-            Message::Notification(n) if n.method == "experimental/serverStatus" => {
-                let status = n
-                    .clone()
-                    .extract::<lsp_ext::ServerStatusParams>("experimental/serverStatus")
-                    .unwrap();
-                if status.health != lsp_ext::Health::Ok {
-                    panic!("server errored/warned while loading workspace: {:?}", status.message);
-                }
-                status.quiescent
-            }
-            _ => false,
-        })
-        .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
+        self.wait_for_message_cond(1, &is_quiescent_status)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
         self
     }
+    /// Waits for the server to become quiescent again, after something made it reload the
+    /// workspace.
+    pub(crate) fn wait_until_workspace_is_reloaded(&self) {
+        let loaded = self.messages.borrow().iter().filter(|msg| is_quiescent_status(msg)).count();
+        self.wait_for_message_cond(loaded + 1, &is_quiescent_status)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to reload"));
+    }
+    /// Waits for a notification of type `N`, returning the params of all notifications of
+    /// that type received so far.
+    pub(crate) fn wait_for_notification<N>(&self) -> Vec<N::Params>
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: DeserializeOwned,
+    {
+        self.wait_for_notifications::<N>(1)
+    }
+    /// Waits until `n` notifications of type `N` have been received, returning their params.
+    pub(crate) fn wait_for_notifications<N>(&self, n: usize) -> Vec<N::Params>
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: DeserializeOwned,
+    {
+        self.wait_for_message_cond(
+            n,
+            &|msg: &Message| matches!(msg, Message::Notification(n) if n.method == N::METHOD),
+        )
+        .unwrap_or_else(|Timeout| panic!("timeout while waiting for {}", N::METHOD));
+        self.notifications::<N>()
+    }
+
+    /// Returns the params of all notifications of type `N` received so far.
+    pub(crate) fn notifications<N>(&self) -> Vec<N::Params>
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: DeserializeOwned,
+    {
+        self.messages
+            .borrow()
+            .iter()
+            .filter_map(|msg| match msg {
+                Message::Notification(n) if n.method == N::METHOD => {
+                    Some(n.clone().extract::<N::Params>(N::METHOD).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     fn wait_for_message_cond(
         &self,
         n: usize,
@@ -317,6 +354,22 @@ impl Drop for Server {
 
 struct Timeout;
 
+fn is_quiescent_status(msg: &Message) -> bool {
+    match msg {
+        Message::Notification(n) if n.method == "experimental/serverStatus" => {
+            let status = n
+                .clone()
+                .extract::<lsp_ext::ServerStatusParams>("experimental/serverStatus")
+                .unwrap();
+            if status.health != lsp_ext::Health::Ok {
+                panic!("server errored/warned while loading workspace: {:?}", status.message);
+            }
+            status.quiescent
+        }
+        _ => false,
+    }
+}
+
 fn recv_timeout(receiver: &Receiver<Message>) -> Result<Option<Message>, Timeout> {
     let timeout =
         if cfg!(target_os = "macos") { Duration::from_secs(300) } else { Duration::from_secs(120) };
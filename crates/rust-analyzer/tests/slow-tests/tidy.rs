@@ -223,6 +223,8 @@ fn check_todo(path: &Path, text: &str) {
         "ide-assists/src/tests/generated.rs",
         // The tests for missing fields
         "ide-diagnostics/src/handlers/missing_fields.rs",
+        // The tests for missing trait items
+        "ide-diagnostics/src/handlers/trait_impl_missing_assoc_item.rs",
     ];
     if need_todo.iter().any(|p| path.ends_with(p)) {
         return;
@@ -14,13 +14,16 @@
 //!     builtin_impls:
 //!     cell: copy, drop
 //!     clone: sized
+//!     coerce_pointee: derive, coerce_unsized, dispatch_from_dyn
 //!     coerce_unsized: unsize
+//!     const_param_ty:
 //!     copy: clone
 //!     default: sized
 //!     deref_mut: deref
 //!     deref: sized
 //!     derive:
 //!     discriminant:
+//!     dispatch_from_dyn: unsize
 //!     drop:
 //!     eq: sized
 //!     error: fmt
@@ -89,6 +92,20 @@ pub mod marker {
     pub trait Unsize<T: ?Sized> {}
     // endregion:unsize
 
+    // region:coerce_pointee
+    #[rustc_builtin_macro(CoercePointee, attributes(pointee))]
+    pub macro CoercePointee($item:item) {}
+    // endregion:coerce_pointee
+
+    // region:const_param_ty
+    #[lang = "const_param_ty"]
+    pub trait ConstParamTy {}
+    // region:derive
+    #[rustc_builtin_macro]
+    pub macro ConstParamTy($item:item) {}
+    // endregion:derive
+    // endregion:const_param_ty
+
     // region:copy
     #[lang = "copy"]
     pub trait Copy: Clone {}
@@ -269,7 +286,7 @@ pub mod clone {
     }
 
     impl Clone for ! {
-        fn clone(&self) {
+        fn clone(&self) -> Self {
             *self
         }
     }
@@ -425,6 +442,21 @@ pub mod ops {
     pub use self::unsize::CoerceUnsized;
     // endregion:coerce_unsized
 
+    // region:dispatch_from_dyn
+    mod dispatch_from_dyn {
+        use crate::marker::Unsize;
+
+        #[lang = "dispatch_from_dyn"]
+        pub trait DispatchFromDyn<T> {}
+
+        impl<'a, T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<&'a U> for &'a T {}
+        impl<'a, T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<&'a mut U> for &'a mut T {}
+        impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<*const U> for *const T {}
+        impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<*mut U> for *mut T {}
+    }
+    pub use self::dispatch_from_dyn::DispatchFromDyn;
+    // endregion:dispatch_from_dyn
+
     // region:deref
     mod deref {
         #[lang = "deref"]
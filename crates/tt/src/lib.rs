@@ -6,6 +6,7 @@
 
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
 use stdx::impl_from;
 
 pub use smol_str::SmolStr;
@@ -16,7 +17,7 @@ pub use smol_str::SmolStr;
 /// which source tokens. We do it by assigning an distinct identity to each
 /// source token and making sure that identities are preserved during macro
 /// expansion.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TokenId(pub u32);
 
 impl fmt::Debug for TokenId {
@@ -80,14 +81,14 @@ pub struct SyntaxContext(pub u32);
 // }
 // pub type Span = (TokenId, SyntaxContext);
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TokenTree<Span> {
     Leaf(Leaf<Span>),
     Subtree(Subtree<Span>),
 }
 impl_from!(Leaf<Span>, Subtree<Span> for TokenTree);
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Leaf<Span> {
     Literal(Literal<Span>),
     Punct(Punct<Span>),
@@ -105,21 +106,21 @@ impl<Span> Leaf<Span> {
 }
 impl_from!(Literal<Span>, Punct<Span>, Ident<Span> for Leaf);
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Subtree<Span> {
     // FIXME, this should not be Option
     pub delimiter: Delimiter<Span>,
     pub token_trees: Vec<TokenTree<Span>>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Delimiter<Span> {
     pub open: Span,
     pub close: Span,
     pub kind: DelimiterKind,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DelimiterKind {
     Parenthesis,
     Brace,
@@ -127,26 +128,26 @@ pub enum DelimiterKind {
     Invisible,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Literal<Span> {
     pub text: SmolStr,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Punct<Span> {
     pub char: char,
     pub spacing: Spacing,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Spacing {
     Alone,
     Joint,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Identifier or keyword. Unlike rustc, we keep "r#" prefix when it represents a raw identifier.
 pub struct Ident<Span> {
     pub text: SmolStr,
@@ -360,6 +360,8 @@ impl VirtualPath {
     /// Append the given *relative* path `path` to `self`.
     ///
     /// This will resolve any leading `"../"` in `path` before appending it.
+    /// Like [`Path::join`](std::path::Path::join), an absolute `path` replaces
+    /// `self`.
     ///
     /// Returns [`None`] if `path` has more leading `"../"` than the number of
     /// components in `self`.
@@ -368,6 +370,9 @@ impl VirtualPath {
     ///
     /// In practice, appending here means `self/path` as strings.
     fn join(&self, mut path: &str) -> Option<VirtualPath> {
+        if path.starts_with('/') {
+            return Some(VirtualPath(path.to_owned()));
+        }
         let mut res = self.clone();
         while path.starts_with("../") {
             if !res.pop() {
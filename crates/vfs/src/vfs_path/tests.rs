@@ -28,3 +28,12 @@ fn virtual_path_extensions() {
         Some(("file", Some("rs")))
     );
 }
+
+#[test]
+fn virtual_path_join() {
+    let dir = VirtualPath("/directory/nested".to_string());
+    assert_eq!(dir.join("file.rs"), Some(VirtualPath("/directory/nested/file.rs".to_string())));
+    assert_eq!(dir.join("../file.rs"), Some(VirtualPath("/directory/file.rs".to_string())));
+    assert_eq!(dir.join("../../../file.rs"), None);
+    assert_eq!(dir.join("/file.rs"), Some(VirtualPath("/file.rs".to_string())));
+}
@@ -869,6 +869,36 @@ export function rebuildProcMacros(ctx: CtxInit): Cmd {
     return async () => ctx.client.sendRequest(ra.rebuildProcMacros);
 }
 
+export function setActiveTarget(ctx: CtxInit): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        if (!editor) return;
+
+        const client = ctx.client;
+        const textDocument = client.code2ProtocolConverter.asTextDocumentIdentifier(
+            editor.document
+        );
+        // Switching to the primary target also returns the targets the file can be switched to.
+        const targets = await client.sendRequest(ra.setActiveTarget, {
+            textDocument,
+            target: null,
+        });
+        if (targets.length === 0) {
+            await vscode.window.showInformationMessage(
+                "This file is only analyzed for the primary target."
+            );
+            return;
+        }
+
+        const primary = "Primary target";
+        const target = await vscode.window.showQuickPick([primary, ...targets], {
+            placeHolder: "Target to analyze this file for",
+        });
+        if (!target || target === primary) return;
+        await client.sendRequest(ra.setActiveTarget, { textDocument, target });
+    };
+}
+
 export function addProject(ctx: CtxInit): Cmd {
     return async () => {
         const discoverProjectCommand = ctx.config.discoverProjectCommand;